@@ -0,0 +1,18 @@
+# Source-based code coverage of the by2 / ccore sources.
+#
+# With BY2_COVERAGE=ON every target of this project is compiled with clang's
+# -fprofile-instr-generate -fcoverage-mapping. The libraries end up in Rust
+# test binaries built with -C instrument-coverage (cargo llvm-cov), and the
+# profile runtime linked by rustc writes the counters of both languages into
+# the same .profraw files. See docs/coverage.md.
+option(BY2_COVERAGE "Instrument the C++ sources for llvm source-based coverage" OFF)
+
+if(BY2_COVERAGE)
+    message(STATUS "Coverage instrumentation enabled")
+
+    target_compile_options(
+        by2_default_compiler_flags
+        INTERFACE
+        $<${ULTRA_COMPILER_IS_GCC_LIKE}:-fprofile-instr-generate;-fcoverage-mapping>
+    )
+endif()
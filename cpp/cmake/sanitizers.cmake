@@ -0,0 +1,32 @@
+# Instrumentation of the by2 / ccore sources with clang sanitizers.
+#
+# BY2_SANITIZERS is a list of -fsanitize= values, e.g. "fuzzer-no-link;address".
+# It is normally set by a configure preset (see CMakePresets.json) rather than
+# by hand. The flags go on by2_default_compiler_flags so every target of this
+# project is instrumented; vcpkg dependencies are left untouched.
+#
+# Only the compile side is handled here. The libraries are linked into a Rust
+# binary, so the sanitizer runtimes are provided on the Rust side (see
+# rust/src/bridge/build.rs).
+set(BY2_SANITIZERS "" CACHE STRING "List of clang sanitizers to build with (-fsanitize=...)")
+
+if(BY2_SANITIZERS)
+    list(JOIN BY2_SANITIZERS "," _by2_sanitizers)
+    message(STATUS "Sanitizers: ${_by2_sanitizers}")
+
+    target_compile_options(
+        by2_default_compiler_flags
+        INTERFACE
+        $<${ULTRA_COMPILER_IS_GCC_LIKE}:-fsanitize=${_by2_sanitizers};-fno-omit-frame-pointer>
+    )
+
+    # UBSan only prints a diagnostic and carries on by default. Make it abort
+    # so `cargo test` fails on undefined behaviour instead of passing.
+    if("undefined" IN_LIST BY2_SANITIZERS)
+        target_compile_options(
+            by2_default_compiler_flags
+            INTERFACE
+            $<${ULTRA_COMPILER_IS_GCC_LIKE}:-fno-sanitize-recover=undefined>
+        )
+    endif()
+endif()
@@ -1,9 +1,45 @@
-
 #include "by2/by2.h"
+#include "status.hpp"
 #include <ccore/ccore.hpp>
 
+#include <string>
+#include <utility>
+
+static_assert(BY2_LOG_LEVEL_TRACE == static_cast<int32_t>(by2::log_level::trace));
+static_assert(BY2_LOG_LEVEL_OFF == static_cast<int32_t>(by2::log_level::off));
+static_assert(BY2_STATUS_INVALID_ARGUMENT == static_cast<int32_t>(by2::error_code::invalid_argument));
+static_assert(BY2_STATUS_SINGULAR == static_cast<int32_t>(by2::error_code::singular));
+static_assert(BY2_STATUS_NOT_CONVERGED == static_cast<int32_t>(by2::error_code::not_converged));
+static_assert(BY2_STATUS_INTERNAL == static_cast<int32_t>(by2::error_code::internal));
+static_assert(BY2_STATUS_CALLBACK_FAILED == static_cast<int32_t>(by2::error_code::callback_failed));
+static_assert(BY2_STATUS_IO == static_cast<int32_t>(by2::error_code::io));
+
+namespace
+{
+    thread_local std::string last_error_message;
+}
+
+void by2::detail::set_last_error(std::string message)
+{
+    last_error_message = std::move(message);
+}
+
+const char* by2_last_error_message(void)
+{
+    return last_error_message.c_str();
+}
+
 int32_t by2_add(int32_t a, int32_t b)
 {
     return by2::ccore_add(a, b);
 }
 
+void by2_add_batch(const int32_t* a, const int32_t* b, int32_t* out, size_t count)
+{
+    by2::ccore_add_batch({a, count}, {b, count}, {out, count});
+}
+
+int32_t by2_set_log_level(int32_t level)
+{
+    return by2::detail::call_with_status([&] { by2::ccore_set_log_level(static_cast<by2::log_level>(level)); });
+}
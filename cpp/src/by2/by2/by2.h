@@ -1,4 +1,5 @@
 #pragma once
+#include <stddef.h>
 #include <stdint.h>
 
 // Export / import mechanics
@@ -28,8 +29,1234 @@ extern "C"
 {
 #endif
 
+    // Logging verbosity of the native core. Values match spdlog's levels.
+    enum by2_log_level
+    {
+        BY2_LOG_LEVEL_TRACE = 0,
+        BY2_LOG_LEVEL_DEBUG = 1,
+        BY2_LOG_LEVEL_INFO = 2,
+        BY2_LOG_LEVEL_WARN = 3,
+        BY2_LOG_LEVEL_ERROR = 4,
+        BY2_LOG_LEVEL_CRITICAL = 5,
+        BY2_LOG_LEVEL_OFF = 6,
+    };
+
     BY2_API int32_t by2_add(int32_t a, int32_t b);
 
+    // Element-wise add: out[i] = a[i] + b[i] for i in [0, count).
+    BY2_API void by2_add_batch(const int32_t* a, const int32_t* b, int32_t* out, size_t count);
+
+    // Sets the global log level. `level` is one of `by2_log_level`;
+    // anything else is BY2_STATUS_INVALID_ARGUMENT and leaves the level as
+    // it was. The status codes are declared below.
+    BY2_API int32_t by2_set_log_level(int32_t level);
+
+    // ---------------------------------------------------------------------
+    // Errors
+    // ---------------------------------------------------------------------
+
+    // Returned by every fallible by2 function. On anything but
+    // BY2_STATUS_OK, by2_last_error_message() describes the failure.
+    enum by2_status
+    {
+        BY2_STATUS_OK = 0,
+        BY2_STATUS_INVALID_ARGUMENT = 1,
+        BY2_STATUS_SINGULAR = 2,
+        BY2_STATUS_NOT_CONVERGED = 3,
+        BY2_STATUS_INTERNAL = 4,
+        // A user callback (e.g. a least-squares residual) reported failure.
+        BY2_STATUS_CALLBACK_FAILED = 5,
+        // A file couldn't be opened, read or written, or its contents are
+        // damaged.
+        BY2_STATUS_IO = 6,
+    };
+
+    // Message of the last error reported on the calling thread. The pointer
+    // stays valid until the next failing by2 call on the same thread.
+    BY2_API const char* by2_last_error_message(void);
+
+    // ---------------------------------------------------------------------
+    // Dense linear algebra (Eigen)
+    // ---------------------------------------------------------------------
+
+    enum by2_scalar_type
+    {
+        BY2_SCALAR_F32 = 0,
+        BY2_SCALAR_F64 = 1,
+    };
+
+    enum by2_solve_method
+    {
+        BY2_SOLVE_LU = 0,
+        BY2_SOLVE_QR = 1,
+        BY2_SOLVE_CHOLESKY = 2,
+    };
+
+    // Non-owning description of a dense matrix buffer. Element (r, c) lives at
+    // data[r * row_stride + c * col_stride], strides counted in elements:
+    // row-major is {row_stride = cols, col_stride = 1}, column-major is
+    // {row_stride = 1, col_stride = rows}. Strides must be positive.
+    // `data` points to float or double depending on `scalar_type`. Inputs
+    // are only read; outputs must be preallocated with the documented shape.
+    // All matrices passed to one call must share the same scalar type.
+    typedef struct by2_matrix
+    {
+        void* data;
+        int32_t scalar_type;
+        size_t rows;
+        size_t cols;
+        ptrdiff_t row_stride;
+        ptrdiff_t col_stride;
+    } by2_matrix;
+
+    // out (m x n) = a (m x k) * b (k x n)
+    BY2_API int32_t by2_matrix_multiply(const by2_matrix* a, const by2_matrix* b, const by2_matrix* out);
+
+    // out (n x n) = a^-1. BY2_STATUS_SINGULAR if `a` is not invertible.
+    BY2_API int32_t by2_matrix_inverse(const by2_matrix* a, const by2_matrix* out);
+
+    // Solves a (m x n) * x (n x k) = b (m x k) with one of `by2_solve_method`.
+    // LU and Cholesky need a square `a` (Cholesky: symmetric positive
+    // definite, otherwise BY2_STATUS_SINGULAR). QR also accepts m > n and
+    // returns the least-squares solution.
+    BY2_API int32_t by2_matrix_solve(int32_t method, const by2_matrix* a, const by2_matrix* b, const by2_matrix* x);
+
+    // Thin SVD with p = min(m, n): a (m x n) = u (m x p) * diag(s) * v^T,
+    // where s is p x 1 and v is n x p. Singular values decrease.
+    BY2_API int32_t by2_matrix_svd(const by2_matrix* a, const by2_matrix* u, const by2_matrix* s, const by2_matrix* v);
+
+    // Eigendecomposition of a symmetric a (n x n); only its lower triangle is
+    // read. `values` (n x 1) are increasing, the columns of `vectors` (n x n)
+    // are the matching unit eigenvectors.
+    BY2_API int32_t by2_matrix_eigen_symmetric(const by2_matrix* a, const by2_matrix* values, const by2_matrix* vectors);
+
+    // ---------------------------------------------------------------------
+    // Sparse linear algebra (Eigen Sparse)
+    // ---------------------------------------------------------------------
+
+    enum by2_sparse_format
+    {
+        BY2_SPARSE_CSR = 0,
+        BY2_SPARSE_CSC = 1,
+    };
+
+    enum by2_sparse_solver
+    {
+        // Direct LDL^T; symmetric with nonzero pivots (positive definite or
+        // indefinite), lower triangle read.
+        BY2_SPARSE_SIMPLICIAL_LDLT = 0,
+        // Direct QR; rows >= cols, least-squares solution.
+        BY2_SPARSE_QR = 1,
+        // Iterative; symmetric positive definite.
+        BY2_SPARSE_CONJUGATE_GRADIENT = 2,
+    };
+
+    // Non-owning compressed sparse matrix. With `outer` = rows (CSR) or cols
+    // (CSC), `outer_offsets` holds outer + 1 entries starting at 0, and the
+    // entries of outer slice k are inner_indices[outer_offsets[k] ..
+    // outer_offsets[k + 1]] (column indices for CSR, row indices for CSC)
+    // with the matching `values`. Inner indices must be strictly increasing
+    // within a slice. `values` points to float or double depending on
+    // `scalar_type`, which must match the dense arguments of the call.
+    typedef struct by2_sparse_matrix
+    {
+        int32_t format;
+        int32_t scalar_type;
+        size_t rows;
+        size_t cols;
+        const size_t* outer_offsets;
+        const size_t* inner_indices;
+        const void* values;
+    } by2_sparse_matrix;
+
+    typedef struct by2_sparse_solve_options
+    {
+        int32_t solver;
+        // BY2_SPARSE_CONJUGATE_GRADIENT only; 0 keeps Eigen's defaults
+        // (2 * cols iterations, relative residual of machine epsilon).
+        size_t max_iterations;
+        double tolerance;
+    } by2_sparse_solve_options;
+
+    // out (m x n) = a (m x k, sparse) * b (k x n)
+    BY2_API int32_t by2_sparse_multiply(const by2_sparse_matrix* a, const by2_matrix* b, const by2_matrix* out);
+
+    // Solves a (m x n, sparse) * x (n x k) = b (m x k). BY2_STATUS_SINGULAR
+    // when a direct factorization fails, BY2_STATUS_NOT_CONVERGED when the
+    // conjugate gradient runs out of iterations.
+    BY2_API int32_t by2_sparse_solve(
+        const by2_sparse_solve_options* options,
+        const by2_sparse_matrix* a,
+        const by2_matrix* b,
+        const by2_matrix* x);
+
+    // ---------------------------------------------------------------------
+    // Rigid-body geometry (Eigen Geometry)
+    // ---------------------------------------------------------------------
+
+    // Conventions: Hamilton quaternions (w + xi + yj + zk), row-major rotation
+    // matrices, euler angles applied as R = Rz(yaw) * Ry(pitch) * Rx(roll),
+    // and poses mapping p to rotation * p + translation.
+    //
+    // Input quaternions are normalized; zero or non-finite ones fail with
+    // BY2_STATUS_INVALID_ARGUMENT. Output quaternions are unit length with
+    // w >= 0. Outputs may alias inputs.
+
+    typedef struct by2_vec3
+    {
+        double x;
+        double y;
+        double z;
+    } by2_vec3;
+
+    typedef struct by2_quat
+    {
+        double w;
+        double x;
+        double y;
+        double z;
+    } by2_quat;
+
+    typedef struct by2_mat3
+    {
+        double m[9];
+    } by2_mat3;
+
+    typedef struct by2_euler
+    {
+        double roll;
+        double pitch;
+        double yaw;
+    } by2_euler;
+
+    typedef struct by2_axis_angle
+    {
+        by2_vec3 axis;
+        double angle;
+    } by2_axis_angle;
+
+    typedef struct by2_pose
+    {
+        by2_quat rotation;
+        by2_vec3 translation;
+    } by2_pose;
+
+    // Fails unless `m` is orthonormal with determinant +1 (tolerance 1e-6).
+    BY2_API int32_t by2_quat_from_matrix(const by2_mat3* m, by2_quat* out);
+    BY2_API int32_t by2_quat_to_matrix(const by2_quat* q, by2_mat3* out);
+
+    // to_euler reports pitch in [-pi/2, pi/2], and roll = 0 at gimbal lock.
+    BY2_API int32_t by2_quat_from_euler(const by2_euler* e, by2_quat* out);
+    BY2_API int32_t by2_quat_to_euler(const by2_quat* q, by2_euler* out);
+
+    // The axis must be non-zero but not necessarily unit length. to_axis_angle
+    // reports an angle in [0, pi] and axis (1, 0, 0) for the identity.
+    BY2_API int32_t by2_quat_from_axis_angle(const by2_axis_angle* a, by2_quat* out);
+    BY2_API int32_t by2_quat_to_axis_angle(const by2_quat* q, by2_axis_angle* out);
+
+    // out = a * b (rotate by b, then by a).
+    BY2_API int32_t by2_quat_multiply(const by2_quat* a, const by2_quat* b, by2_quat* out);
+    BY2_API int32_t by2_quat_inverse(const by2_quat* q, by2_quat* out);
+
+    // out = a * b (apply b, then a).
+    BY2_API int32_t by2_pose_compose(const by2_pose* a, const by2_pose* b, by2_pose* out);
+    BY2_API int32_t by2_pose_inverse(const by2_pose* p, by2_pose* out);
+
+    // Transforms `count` packed xyz triples. `out` may equal `points`.
+    BY2_API int32_t by2_pose_transform_points(const by2_pose* p, const double* points, double* out, size_t count);
+
+    // ---------------------------------------------------------------------
+    // Nonlinear least squares (Eigen NonLinearOptimization)
+    // ---------------------------------------------------------------------
+
+    enum by2_lsq_method
+    {
+        BY2_LSQ_LEVENBERG_MARQUARDT = 0,
+        // Undamped steps; needs a good initial guess.
+        BY2_LSQ_GAUSS_NEWTON = 1,
+    };
+
+    // The first three count as converged. TOLERANCE_TOO_SMALL: no further
+    // progress is possible at the requested tolerances.
+    enum by2_lsq_termination
+    {
+        BY2_LSQ_FUNCTION_TOLERANCE = 0,
+        BY2_LSQ_PARAMETER_TOLERANCE = 1,
+        BY2_LSQ_GRADIENT_TOLERANCE = 2,
+        BY2_LSQ_MAX_ITERATIONS = 3,
+        BY2_LSQ_TOLERANCE_TOO_SMALL = 4,
+    };
+
+    // Fill with by2_lsq_default_options() and override what you need.
+    typedef struct by2_lsq_options
+    {
+        int32_t method;
+        size_t max_iterations;
+        // Relative reduction of the cost.
+        double function_tolerance;
+        // Relative change of the parameters.
+        double parameter_tolerance;
+        // Gradient threshold (Levenberg-Marquardt: cosine between the
+        // residuals and the jacobian columns).
+        double gradient_tolerance;
+    } by2_lsq_options;
+
+    typedef struct by2_lsq_summary
+    {
+        int32_t converged;
+        int32_t termination;
+        // 0.5 * ||residuals||^2 at the initial and final parameters.
+        double initial_cost;
+        double final_cost;
+        size_t iterations;
+        // Including the evaluations spent on finite differences.
+        size_t residual_evaluations;
+        size_t jacobian_evaluations;
+    } by2_lsq_summary;
+
+    // Writes `residual_count` residuals for `param_count` parameters. Returns
+    // 0 on success; anything else aborts the solve with
+    // BY2_STATUS_CALLBACK_FAILED.
+    typedef int32_t (*by2_lsq_residual_fn)(
+        void* user_data,
+        const double* params,
+        size_t param_count,
+        double* residuals,
+        size_t residual_count);
+
+    // Writes the row-major residual_count x param_count jacobian:
+    // jacobian[i * param_count + j] = d residual_i / d param_j. Same return
+    // convention as by2_lsq_residual_fn.
+    typedef int32_t (*by2_lsq_jacobian_fn)(
+        void* user_data,
+        const double* params,
+        size_t param_count,
+        double* jacobian,
+        size_t residual_count);
+
+    BY2_API void by2_lsq_default_options(by2_lsq_options* options);
+
+    // Minimizes 0.5 * ||residual(params)||^2. `params` holds the initial
+    // guess and receives the solution; it is left unchanged on error.
+    // `jacobian` may be NULL, then forward differences are used. Needs
+    // residual_count >= param_count >= 1. Running out of iterations is not
+    // an error: check `summary->converged`. The callbacks run on the calling
+    // thread, and must not unwind through this function.
+    BY2_API int32_t by2_lsq_solve(
+        by2_lsq_residual_fn residual,
+        by2_lsq_jacobian_fn jacobian,
+        void* user_data,
+        double* params,
+        size_t param_count,
+        size_t residual_count,
+        const by2_lsq_options* options,
+        by2_lsq_summary* summary);
+
+    // ---------------------------------------------------------------------
+    // Point clouds
+    // ---------------------------------------------------------------------
+
+    // Non-owning structure-of-arrays point cloud. Every non-null column has
+    // `count` entries. x, y and z are required (unless count is 0); normals
+    // and colours are either all set or all NULL, intensity is optional.
+    typedef struct by2_point_cloud
+    {
+        size_t count;
+        const float* x;
+        const float* y;
+        const float* z;
+        const float* normal_x;
+        const float* normal_y;
+        const float* normal_z;
+        const uint8_t* red;
+        const uint8_t* green;
+        const uint8_t* blue;
+        const float* intensity;
+    } by2_point_cloud;
+
+    typedef struct by2_aabb
+    {
+        by2_vec3 min;
+        by2_vec3 max;
+    } by2_aabb;
+
+    // Axis-aligned bounds of the points, skipping NaN coordinates. Fails
+    // with BY2_STATUS_INVALID_ARGUMENT for an empty cloud.
+    BY2_API int32_t by2_point_cloud_bounds(const by2_point_cloud* cloud, by2_aabb* out);
+
+    // Output columns for kernels that produce a new cloud. NULL columns are
+    // not written; every other column has room for `capacity` entries.
+    typedef struct by2_point_cloud_buffers
+    {
+        size_t capacity;
+        float* x;
+        float* y;
+        float* z;
+        float* normal_x;
+        float* normal_y;
+        float* normal_z;
+        uint8_t* red;
+        uint8_t* green;
+        uint8_t* blue;
+        float* intensity;
+    } by2_point_cloud_buffers;
+
+    // The kernels below skip points with a NaN or infinite coordinate: they
+    // are never part of an output and never count as neighbours. Neighbour
+    // searches use a kd-tree and break distance ties by point index, so the
+    // results are deterministic. Kernels returning indices write them in
+    // ascending order to `inliers`, which needs room for cloud->count
+    // entries, and store how many there are in `inlier_count`.
+
+    // Replaces the points of each occupied voxel (the cube of side
+    // `voxel_size` at floor(p / voxel_size)) by their centroid. Normals,
+    // colours and intensity are averaged as well: normals are not
+    // renormalized, colours are rounded. Points are ordered by voxel (x
+    // index, then y, then z). Writes the columns of `out` that are non-NULL
+    // and present in `cloud`; x, y and z are required and
+    // out->capacity >= cloud->count.
+    BY2_API int32_t by2_point_cloud_voxel_downsample(
+        const by2_point_cloud* cloud,
+        double voxel_size,
+        const by2_point_cloud_buffers* out,
+        size_t* out_count);
+
+    // Keeps the points whose mean distance to their `k` nearest neighbours is
+    // at most mean + std_ratio * stddev over the whole cloud (stddev with
+    // n - 1). Needs more than `k` finite points.
+    BY2_API int32_t by2_point_cloud_statistical_inliers(
+        const by2_point_cloud* cloud,
+        size_t k,
+        double std_ratio,
+        size_t* inliers,
+        size_t* inlier_count);
+
+    // Keeps the points with at least `min_neighbors` other points within
+    // `radius` (inclusive).
+    BY2_API int32_t by2_point_cloud_radius_inliers(
+        const by2_point_cloud* cloud,
+        double radius,
+        size_t min_neighbors,
+        size_t* inliers,
+        size_t* inlier_count);
+
+    // Keeps the points inside `box`, bounds included. Fails unless
+    // box->min <= box->max on every axis.
+    BY2_API int32_t by2_point_cloud_crop(
+        const by2_point_cloud* cloud,
+        const by2_aabb* box,
+        size_t* inliers,
+        size_t* inlier_count);
+
+    // Estimates a unit normal per point from the covariance of its `k`
+    // nearest neighbours (the point itself included) and flips it to face
+    // `viewpoint`. Needs k >= 3. Points that are not finite, or have fewer
+    // than 3 finite neighbours, get NaN. Each output holds cloud->count
+    // entries.
+    BY2_API int32_t by2_point_cloud_estimate_normals(
+        const by2_point_cloud* cloud,
+        size_t k,
+        const by2_vec3* viewpoint,
+        float* normal_x,
+        float* normal_y,
+        float* normal_z);
+
+    // ---------------------------------------------------------------------
+    // k-d tree
+    // ---------------------------------------------------------------------
+
+    // Spatial index over the finite points of a cloud. The coordinates are
+    // copied, so the cloud may be freed once the tree is built. Queries don't
+    // modify the tree and may run concurrently from several threads.
+    typedef struct by2_kdtree by2_kdtree;
+
+    // A query result: the index of the point in the cloud the tree was built
+    // from and its squared distance to the query (computed in double
+    // precision). Results are sorted by (squared_distance, index).
+    typedef struct by2_neighbor
+    {
+        size_t index;
+        double squared_distance;
+    } by2_neighbor;
+
+    // Builds a tree and stores it in `*out`; free it with by2_kdtree_destroy.
+    BY2_API int32_t by2_kdtree_create(const by2_point_cloud* cloud, by2_kdtree** out);
+    // Accepts NULL.
+    BY2_API void by2_kdtree_destroy(by2_kdtree* tree);
+    // Number of indexed points.
+    BY2_API size_t by2_kdtree_size(const by2_kdtree* tree);
+
+    // Query points must be finite.
+
+    // The `k` nearest points, or all of them when the tree has fewer. `out`
+    // needs room for min(k, size) entries, which is what `*out_count`
+    // receives.
+    BY2_API int32_t by2_kdtree_knn(
+        const by2_kdtree* tree,
+        const by2_vec3* query,
+        size_t k,
+        by2_neighbor* out,
+        size_t* out_count);
+
+    // The points within `radius` (inclusive, finite and non-negative).
+    // `*out_count` always receives the number of matches; they are written
+    // to `out` only if they all fit in `capacity`, so a caller can retry
+    // with a bigger buffer.
+    BY2_API int32_t by2_kdtree_radius(
+        const by2_kdtree* tree,
+        const by2_vec3* query,
+        double radius,
+        by2_neighbor* out,
+        size_t capacity,
+        size_t* out_count);
+
+    // by2_kdtree_knn for `query_count` queries. With m = min(k, size), `out`
+    // needs room for query_count * m entries; the results of query i are at
+    // out[i * m], and counts[i] of them are written (m, as for a single
+    // query).
+    BY2_API int32_t by2_kdtree_knn_batch(
+        const by2_kdtree* tree,
+        const by2_vec3* queries,
+        size_t query_count,
+        size_t k,
+        by2_neighbor* out,
+        size_t* counts);
+
+    // by2_kdtree_radius for `query_count` queries. `offsets` (query_count + 1
+    // entries) always receives the start of each query's results, and
+    // offsets[query_count] the total; the results are written to `out` only
+    // if the total fits in `capacity`.
+    BY2_API int32_t by2_kdtree_radius_batch(
+        const by2_kdtree* tree,
+        const by2_vec3* queries,
+        size_t query_count,
+        double radius,
+        by2_neighbor* out,
+        size_t capacity,
+        size_t* offsets);
+
+    // ---------------------------------------------------------------------
+    // Registration (iterative closest point)
+    // ---------------------------------------------------------------------
+
+    enum by2_icp_method
+    {
+        BY2_ICP_POINT_TO_POINT = 0,
+        // Needs target normals.
+        BY2_ICP_POINT_TO_PLANE = 1,
+    };
+
+    // Only CONVERGED counts as converged. TOO_FEW_CORRESPONDENCES: fewer
+    // pairs than the method needs to determine a pose (3 point-to-point, 6
+    // point-to-plane).
+    enum by2_icp_termination
+    {
+        BY2_ICP_CONVERGED = 0,
+        BY2_ICP_MAX_ITERATIONS = 1,
+        BY2_ICP_TOO_FEW_CORRESPONDENCES = 2,
+    };
+
+    // Fill with by2_icp_default_options() and override what you need.
+    typedef struct by2_icp_options
+    {
+        int32_t method;
+        size_t max_iterations;
+        // Positive; source points farther than this from their nearest target
+        // point have no correspondence. The default (infinity) pairs all.
+        double max_correspondence_distance;
+        // Converged once an iteration moves the pose by less than both
+        // (rotation in radians).
+        double translation_tolerance;
+        double rotation_tolerance;
+        // Maps the source into the target frame.
+        by2_pose initial_pose;
+    } by2_icp_options;
+
+    // How well a pose aligns the clouds: the fraction of finite source points
+    // with a correspondence and the RMS distance over those (0 without any).
+    typedef struct by2_icp_iteration
+    {
+        double fitness;
+        double rmse;
+        size_t correspondences;
+    } by2_icp_iteration;
+
+    typedef struct by2_icp_result
+    {
+        // Maps the source into the target frame.
+        by2_pose transform;
+        // Evaluated at `transform`.
+        by2_icp_iteration alignment;
+        int32_t converged;
+        int32_t termination;
+        // Number of pose updates.
+        size_t iterations;
+    } by2_icp_result;
+
+    BY2_API void by2_icp_default_options(by2_icp_options* options);
+
+    // Refines options->initial_pose so that it maps `source` onto `target`.
+    // Points that are not finite are ignored, and so are target points
+    // without a finite normal for point-to-plane. `history` has room for
+    // `history_capacity` entries (it may be NULL if that is 0) and receives
+    // the first min(result->iterations, history_capacity) of them, each
+    // evaluated at the pose before that update. Running out of iterations or
+    // correspondences is not an error: check `result->converged`.
+    BY2_API int32_t by2_icp(
+        const by2_point_cloud* source,
+        const by2_point_cloud* target,
+        const by2_icp_options* options,
+        by2_icp_result* result,
+        by2_icp_iteration* history,
+        size_t history_capacity);
+
+    // ---------------------------------------------------------------------
+    // Occupancy maps (octomap)
+    // ---------------------------------------------------------------------
+
+    // Only defined when by2 is built with BY2_WITH_MAPPING=ON (the `mapping`
+    // feature of vcpkg.json).
+    //
+    // A probabilistic voxel grid. Every voxel starts unknown; inserting a
+    // scan makes the voxels along each sensor ray more likely free and the
+    // voxel at its end point more likely occupied. Coordinates are limited to
+    // [-32768, 32768) voxels along each axis. Queries don't modify the map
+    // and may run concurrently, but not alongside an insertion.
+    typedef struct by2_octree by2_octree;
+
+    // Creates an empty map with voxels of edge length `resolution` (positive,
+    // and at most DBL_MAX / 65536 so the mappable volume stays finite); free
+    // it with by2_octree_destroy.
+    BY2_API int32_t by2_octree_create(double resolution, by2_octree** out);
+    // Accepts NULL.
+    BY2_API void by2_octree_destroy(by2_octree* tree);
+    BY2_API double by2_octree_resolution(const by2_octree* tree);
+    // Number of leaves; children that all agree are merged into one leaf.
+    BY2_API size_t by2_octree_leaf_count(const by2_octree* tree);
+
+    // Casts a ray from `origin` to every finite point of `cloud`. Rays longer
+    // than `max_range` (positive, may be INFINITY) are cut at that length and
+    // their end points are not marked occupied. Fails without changing the
+    // map if `origin` or a (cut) end point is outside the mappable volume.
+    BY2_API int32_t by2_octree_insert_point_cloud(
+        by2_octree* tree,
+        const by2_point_cloud* cloud,
+        const by2_vec3* origin,
+        double max_range);
+
+    // Occupancy probability of the voxel containing `point` (finite). `*known`
+    // receives 0 while the voxel is unknown, and then `*probability` is left
+    // unchanged.
+    BY2_API int32_t by2_octree_occupancy(
+        const by2_octree* tree,
+        const by2_vec3* point,
+        double* probability,
+        int32_t* known);
+
+    // Rays and segments are walked voxel by voxel at the map's resolution.
+
+    enum by2_octree_ray_result
+    {
+        // Stopped at an occupied voxel.
+        BY2_OCTREE_RAY_OCCUPIED = 0,
+        // Stopped at an unknown voxel, unless unknown voxels were ignored.
+        BY2_OCTREE_RAY_UNKNOWN = 1,
+        // Reached `max_range` or the edge of the mappable volume, having only
+        // crossed free (or ignored unknown) voxels.
+        BY2_OCTREE_RAY_CLEAR = 2,
+    };
+
+    typedef struct by2_octree_ray_hit
+    {
+        int32_t result;
+        // Center of the voxel the ray stopped at; for BY2_OCTREE_RAY_CLEAR,
+        // the last voxel it crossed.
+        by2_vec3 voxel;
+    } by2_octree_ray_hit;
+
+    // Casts a ray from `origin` (finite, inside the mappable volume) in
+    // `direction` (finite, non-zero) up to `max_range` (positive, may be
+    // INFINITY). The voxel holding `origin` is checked too. Unknown voxels
+    // stop the ray unless `ignore_unknown` is non-zero.
+    BY2_API int32_t by2_octree_cast_ray(
+        const by2_octree* tree,
+        const by2_vec3* origin,
+        const by2_vec3* direction,
+        double max_range,
+        int32_t ignore_unknown,
+        by2_octree_ray_hit* out);
+
+    // `*visible` receives 1 if every voxel the segment between `from` and `to`
+    // (finite, inside the mappable volume) passes through is free, the
+    // voxels holding its end points included, and 0 otherwise. Unknown
+    // voxels count as free if `ignore_unknown` is non-zero.
+    BY2_API int32_t by2_octree_line_of_sight(
+        const by2_octree* tree,
+        const by2_vec3* from,
+        const by2_vec3* to,
+        int32_t ignore_unknown,
+        int32_t* visible);
+
+    // A leaf: a cube of edge length `size` (the resolution times a power of
+    // two) around `center`.
+    typedef struct by2_octree_leaf
+    {
+        by2_vec3 center;
+        double size;
+        double probability;
+    } by2_octree_leaf;
+
+    // The occupied leaf closest to `point` (finite) within `max_distance`
+    // (non-negative, may be INFINITY), measured to the leaf's cube. `*found`
+    // receives 0 if there is none, and then `*leaf` and `*distance` are left
+    // unchanged.
+    BY2_API int32_t by2_octree_nearest_occupied(
+        const by2_octree* tree,
+        const by2_vec3* point,
+        double max_distance,
+        by2_octree_leaf* leaf,
+        double* distance,
+        int32_t* found);
+
+    enum by2_octree_leaf_filter
+    {
+        BY2_OCTREE_LEAVES_OCCUPIED = 0,
+        BY2_OCTREE_LEAVES_FREE = 1,
+        // Occupied and free.
+        BY2_OCTREE_LEAVES_KNOWN = 2,
+    };
+
+    // A cursor over the leaves intersecting a box, in octree order. It reads
+    // the map it was created from, which must outlive it and must not be
+    // modified while it is in use.
+    typedef struct by2_octree_leaves by2_octree_leaves;
+
+    // Creates a cursor over the leaves matching `filter` that intersect the
+    // box [min, max]. Bounds may be infinite; the box is clipped to the
+    // mappable volume. Free it with by2_octree_leaves_destroy.
+    BY2_API int32_t by2_octree_leaves_create(
+        const by2_octree* tree,
+        const by2_vec3* min,
+        const by2_vec3* max,
+        int32_t filter,
+        by2_octree_leaves** out);
+    // Accepts NULL.
+    BY2_API void by2_octree_leaves_destroy(by2_octree_leaves* leaves);
+    // Writes up to `capacity` more leaves to `out` and their number to
+    // `*out_count`; fewer than `capacity` once the cursor is exhausted.
+    BY2_API int32_t by2_octree_leaves_next(
+        by2_octree_leaves* leaves,
+        by2_octree_leaf* out,
+        size_t capacity,
+        size_t* out_count);
+
+    // Serialized maps, in octomap's file formats.
+    enum by2_octree_format
+    {
+        // `.bt`: compact, keeps only whether each known voxel is free or
+        // occupied.
+        BY2_OCTREE_BINARY = 0,
+        // `.ot`: every node with its occupancy.
+        BY2_OCTREE_FULL = 1,
+    };
+
+    // Writes the map in `format`. `*out_size` always receives the size in
+    // bytes; they are written to `out` only if they fit in `capacity`, so a
+    // caller can query the size with capacity 0 first.
+    BY2_API int32_t by2_octree_serialize(
+        const by2_octree* tree,
+        int32_t format,
+        uint8_t* out,
+        size_t capacity,
+        size_t* out_size);
+
+    // Reads a map written in `format` by by2_octree_serialize or octomap, and
+    // stores it in `*out`. Data in the other format, truncated or otherwise
+    // malformed is rejected with BY2_STATUS_INVALID_ARGUMENT.
+    BY2_API int32_t by2_octree_deserialize(const uint8_t* data, size_t size, int32_t format, by2_octree** out);
+
+    // ---------------------------------------------------------------------
+    // Media (FFmpeg)
+    // ---------------------------------------------------------------------
+
+    // Only defined when by2 is built with BY2_WITH_MEDIA=ON (the `media`
+    // feature of vcpkg.json). Paths are UTF-8.
+
+    // Layouts of frames. Frames are tightly packed: rows follow each other
+    // without padding, and planes follow each other in the listed order.
+    enum by2_pixel_format
+    {
+        // R, G, B bytes per pixel.
+        BY2_PIXEL_RGB24 = 0,
+        // R, G, B, A bytes per pixel.
+        BY2_PIXEL_RGBA = 1,
+        // Y plane, then U and V planes of ceil(width / 2) x ceil(height / 2)
+        // samples.
+        BY2_PIXEL_YUV420P = 2,
+        // One luma byte per pixel.
+        BY2_PIXEL_GRAY8 = 3,
+        // B, G, R bytes per pixel.
+        BY2_PIXEL_BGR24 = 4,
+        // B, G, R, A bytes per pixel.
+        BY2_PIXEL_BGRA = 5,
+        // Y plane, then a plane of ceil(width / 2) x ceil(height / 2)
+        // interleaved U, V pairs.
+        BY2_PIXEL_NV12 = 6,
+        // Y plane, then U and V planes of ceil(width / 2) x height samples.
+        BY2_PIXEL_YUV422P = 7,
+        // Y, U and V planes of width x height samples.
+        BY2_PIXEL_YUV444P = 8,
+    };
+
+    enum by2_media_type
+    {
+        BY2_MEDIA_VIDEO = 0,
+        BY2_MEDIA_AUDIO = 1,
+        BY2_MEDIA_SUBTITLE = 2,
+        BY2_MEDIA_DATA = 3,
+        BY2_MEDIA_UNKNOWN = 4,
+    };
+
+    typedef struct by2_rational
+    {
+        int32_t num;
+        int32_t den;
+    } by2_rational;
+
+    // A stream of a media file, as its container describes it.
+    typedef struct by2_stream_info
+    {
+        size_t index;
+        int32_t type;
+        // FFmpeg's name for the codec, e.g. "h264", NUL-terminated.
+        char codec[32];
+        // Video only, 0 otherwise.
+        int32_t width;
+        int32_t height;
+        // Average frames per second; 0 if unknown or not video.
+        double frame_rate;
+        // FFmpeg's name for the layout of decoded frames, e.g. "yuv420p".
+        // Empty if unknown or not video.
+        char pixel_format[32];
+        // Audio only, 0 otherwise.
+        int32_t sample_rate;
+        int32_t channels;
+        // FFmpeg's description of the channels, e.g. "stereo" or "5.1".
+        // Empty if unknown or not audio.
+        char channel_layout[64];
+        // Bits per second, 0 if unknown.
+        int64_t bit_rate;
+        // Unit of the stream's timestamps, in seconds.
+        by2_rational time_base;
+        // In seconds, NAN if unknown.
+        double duration;
+        // As stated by the container, 0 if it doesn't say.
+        int64_t frame_count;
+    } by2_stream_info;
+
+    // The container of a probed file.
+    typedef struct by2_media_format
+    {
+        // FFmpeg's short name, e.g. "matroska,webm", NUL-terminated.
+        char name[64];
+        // A human readable name, e.g. "Matroska / WebM".
+        char long_name[128];
+        // In seconds, NAN if unknown.
+        double duration;
+        // Bits per second of the whole file, 0 if unknown.
+        int64_t bit_rate;
+    } by2_media_format;
+
+    // What a media file holds: its container, streams and metadata tags.
+    typedef struct by2_media_info by2_media_info;
+
+    // Describes `path` without decoding it. Fails with BY2_STATUS_IO if the
+    // file can't be opened or read. Free the result with
+    // by2_media_info_destroy.
+    BY2_API int32_t by2_media_probe(const char* path, by2_media_info** out);
+    // Accepts NULL.
+    BY2_API void by2_media_info_destroy(by2_media_info* info);
+    BY2_API int32_t by2_media_info_format(const by2_media_info* info, by2_media_format* out);
+    BY2_API size_t by2_media_info_stream_count(const by2_media_info* info);
+    BY2_API int32_t by2_media_info_stream(const by2_media_info* info, size_t index, by2_stream_info* out);
+    // Metadata tags of stream `stream`, or of the file for -1.
+    BY2_API size_t by2_media_info_tag_count(const by2_media_info* info, int64_t stream);
+    // Points `*key` and `*value` at tag `index` of `stream` (see
+    // by2_media_info_tag_count). The strings live as long as `info`.
+    BY2_API int32_t by2_media_info_tag(
+        const by2_media_info* info,
+        int64_t stream,
+        size_t index,
+        const char** key,
+        const char** value);
+
+    // How by2_scaler computes output pixels from input pixels.
+    enum by2_interpolation
+    {
+        BY2_INTERPOLATION_BILINEAR = 0,
+        // Sharper than bilinear, a little slower.
+        BY2_INTERPOLATION_BICUBIC = 1,
+        // Averages the input pixels each output pixel covers; best for
+        // shrinking.
+        BY2_INTERPOLATION_AREA = 2,
+    };
+
+    // Converts frames of one size and `by2_pixel_format` to another with
+    // swscale. Create one per conversion and reuse it for every frame; it is
+    // not thread-safe.
+    typedef struct by2_scaler by2_scaler;
+
+    // Fails with BY2_STATUS_INVALID_ARGUMENT for a size that isn't positive
+    // or an unknown format or interpolation.
+    BY2_API int32_t by2_scaler_create(
+        int32_t source_width,
+        int32_t source_height,
+        int32_t source_format,
+        int32_t destination_width,
+        int32_t destination_height,
+        int32_t destination_format,
+        int32_t interpolation,
+        by2_scaler** out);
+    // Accepts NULL.
+    BY2_API void by2_scaler_destroy(by2_scaler* scaler);
+    // Bytes of one input frame.
+    BY2_API size_t by2_scaler_source_size(const by2_scaler* scaler);
+    // Bytes of one output frame.
+    BY2_API size_t by2_scaler_destination_size(const by2_scaler* scaler);
+    // Converts `source` (`source_size` bytes) into `destination`
+    // (`destination_size` bytes), both owned by the caller. Fails with
+    // BY2_STATUS_INVALID_ARGUMENT unless the sizes are
+    // by2_scaler_source_size and by2_scaler_destination_size.
+    BY2_API int32_t by2_scaler_scale(
+        by2_scaler* scaler,
+        const uint8_t* source,
+        size_t source_size,
+        uint8_t* destination,
+        size_t destination_size);
+
+    // Decodes the frames of a file's video stream one after the other, in
+    // presentation order, converted to one of `by2_pixel_format` at the size
+    // the stream declares.
+    typedef struct by2_video_reader by2_video_reader;
+
+    typedef struct by2_video_frame_info
+    {
+        // Presentation timestamp in the stream's time base, INT64_MIN if the
+        // frame has none.
+        int64_t pts;
+        // The timestamp in seconds, NAN without one.
+        double time;
+        // Position of the frame in the stream, from 0.
+        int64_t index;
+        int32_t key_frame;
+    } by2_video_frame_info;
+
+    // Opens `path` and picks its best video stream. Fails with
+    // BY2_STATUS_IO if the file can't be opened or read or has no decodable
+    // video stream. Free the reader with by2_video_reader_destroy.
+    BY2_API int32_t by2_video_reader_open(const char* path, int32_t pixel_format, by2_video_reader** out);
+    // Accepts NULL.
+    BY2_API void by2_video_reader_destroy(by2_video_reader* reader);
+    // Number of streams in the file, video or not.
+    BY2_API size_t by2_video_reader_stream_count(const by2_video_reader* reader);
+    BY2_API int32_t by2_video_reader_stream(const by2_video_reader* reader, size_t index, by2_stream_info* out);
+    // Index of the stream frames are decoded from.
+    BY2_API size_t by2_video_reader_video_stream(const by2_video_reader* reader);
+    // Bytes of one converted frame.
+    BY2_API size_t by2_video_reader_frame_size(const by2_video_reader* reader);
+
+    // Decodes the next frame into `out` (`capacity` bytes, at least
+    // by2_video_reader_frame_size) and describes it in `*info`. `*decoded`
+    // receives 0 at the end of the stream, and then `out` and `*info` are
+    // left unchanged. Fails with BY2_STATUS_IO if reading or decoding fails.
+    BY2_API int32_t by2_video_reader_next(
+        by2_video_reader* reader,
+        uint8_t* out,
+        size_t capacity,
+        by2_video_frame_info* info,
+        int32_t* decoded);
+
+    // Seeks go back to the keyframe before the target and decode forward to
+    // it, so the next frame by2_video_reader_next returns is exactly the one
+    // asked for, or none past the end. Without an index, frame numbers after
+    // a seek come from the timestamps and the average frame rate, which is
+    // exact for constant frame rate video; with one they are counted from
+    // the keyframes.
+    typedef struct by2_video_keyframe
+    {
+        // Position of the keyframe in the stream, from 0.
+        int64_t index;
+        // Presentation timestamp in the stream's time base.
+        int64_t pts;
+    } by2_video_keyframe;
+
+    // To the first frame at or after `seconds` (finite). Fails with
+    // BY2_STATUS_IO if seeking fails.
+    BY2_API int32_t by2_video_reader_seek_time(by2_video_reader* reader, double seconds);
+    // To frame `index` (non-negative).
+    BY2_API int32_t by2_video_reader_seek_frame(by2_video_reader* reader, int64_t index);
+
+    // Reads the packets of the video stream, without decoding them, to find
+    // its keyframes, then rewinds to the first frame. Streams without a
+    // frame rate get an index on their first seek. Fails with BY2_STATUS_IO
+    // if reading fails or packets have no timestamps.
+    BY2_API int32_t by2_video_reader_build_index(by2_video_reader* reader);
+    // The index in use. `*out_count` always receives the number of
+    // keyframes and `*frame_count` the number of frames, -1 without an
+    // index; the keyframes are written to `out` only if they all fit in
+    // `capacity`.
+    BY2_API int32_t by2_video_reader_index(
+        const by2_video_reader* reader,
+        by2_video_keyframe* out,
+        size_t capacity,
+        size_t* out_count,
+        int64_t* frame_count);
+    // Uses an index built earlier for the same file, e.g. loaded from a
+    // cache. Fails with BY2_STATUS_INVALID_ARGUMENT if the keyframes (by
+    // increasing index and pts) don't start at frame 0 or lie past
+    // `frame_count`.
+    BY2_API int32_t by2_video_reader_set_index(
+        by2_video_reader* reader,
+        const by2_video_keyframe* keyframes,
+        size_t count,
+        int64_t frame_count);
+
+    // Encoders by2_video_writer can use. Both are part of FFmpeg itself.
+    typedef enum by2_video_codec
+    {
+        // MPEG-4 part 2: lossy, plays almost everywhere.
+        BY2_VIDEO_CODEC_MPEG4 = 0,
+        // FFV1: lossless, keeps RGB frames as RGB. Not in MP4.
+        BY2_VIDEO_CODEC_FFV1 = 1,
+    } by2_video_codec;
+
+    // Fill with by2_video_writer_default_options() and override what you
+    // need.
+    typedef struct by2_video_writer_options
+    {
+        int32_t codec;
+        // Frames per second, positive.
+        by2_rational frame_rate;
+        // Bits per second, or 0 for the encoder's default. Lossless codecs
+        // ignore it.
+        int64_t bit_rate;
+        // Frames from one keyframe to the next; 0 makes every frame a
+        // keyframe.
+        int32_t gop_size;
+    } by2_video_writer_options;
+
+    // Encodes frames and muxes them into a file whose container FFmpeg picks
+    // by extension: .mp4, .mkv or .avi, among others. Frame i is shown at
+    // i / frame_rate seconds.
+    typedef struct by2_video_writer by2_video_writer;
+
+    BY2_API void by2_video_writer_default_options(by2_video_writer_options* options);
+
+    // Creates `path` for `width` x `height` frames in `pixel_format`. Fails
+    // with BY2_STATUS_INVALID_ARGUMENT for bad options, an extension FFmpeg
+    // doesn't know or a container that can't hold the codec, and with
+    // BY2_STATUS_IO if the file can't be created or the encoder fails.
+    BY2_API int32_t by2_video_writer_create(
+        const char* path,
+        int32_t width,
+        int32_t height,
+        int32_t pixel_format,
+        const by2_video_writer_options* options,
+        by2_video_writer** out);
+    // Finishes the file first if by2_video_writer_finish wasn't called,
+    // ignoring errors. Accepts NULL.
+    BY2_API void by2_video_writer_destroy(by2_video_writer* writer);
+    // Bytes of one input frame.
+    BY2_API size_t by2_video_writer_frame_size(const by2_video_writer* writer);
+    // Frames written so far.
+    BY2_API int64_t by2_video_writer_frame_count(const by2_video_writer* writer);
+    // Encodes the next frame, `size` bytes (by2_video_writer_frame_size).
+    // Fails with BY2_STATUS_INVALID_ARGUMENT for a frame of another size or
+    // after by2_video_writer_finish, and with BY2_STATUS_IO if encoding or
+    // writing fails.
+    BY2_API int32_t by2_video_writer_write(by2_video_writer* writer, const uint8_t* frame, size_t size);
+    // Encodes the frames the encoder still holds and completes the file.
+    // Later calls do nothing.
+    BY2_API int32_t by2_video_writer_finish(by2_video_writer* writer);
+
+    // Layouts of the samples by2_audio_reader puts out: 32-bit floats in
+    // [-1, 1].
+    typedef enum by2_sample_format
+    {
+        // The channels of each sample follow each other.
+        BY2_SAMPLE_F32 = 0,
+        // One plane per channel, each holding all of the chunk's samples.
+        BY2_SAMPLE_F32_PLANAR = 1,
+    } by2_sample_format;
+
+    // Fill with by2_audio_reader_default_options() and override what you
+    // need.
+    typedef struct by2_audio_reader_options
+    {
+        // Samples per second per channel; 0 keeps the stream's rate.
+        int32_t sample_rate;
+        // FFmpeg's name for the channel layout, NUL-terminated, e.g. "mono",
+        // "stereo" or "5.1"; empty keeps the stream's.
+        char channel_layout[64];
+        int32_t sample_format;
+    } by2_audio_reader_options;
+
+    // What by2_audio_reader puts out.
+    typedef struct by2_audio_format
+    {
+        int32_t sample_rate;
+        int32_t channels;
+        // FFmpeg's description of the channels, e.g. "stereo".
+        char channel_layout[64];
+        int32_t sample_format;
+    } by2_audio_format;
+
+    typedef struct by2_audio_chunk
+    {
+        // channels * samples floats in the reader's sample format, owned by
+        // the reader and valid until the next by2_audio_reader_next call.
+        const float* data;
+        // Samples per channel.
+        int64_t samples;
+        // Samples per channel before this chunk, counted from the first
+        // decoded one.
+        int64_t position;
+        // Time of the first sample in seconds: the stream's first timestamp
+        // plus position / sample_rate.
+        double time;
+    } by2_audio_chunk;
+
+    // Decodes a file's audio stream and resamples it with libswresample, in
+    // chunks of about one decoded frame each.
+    typedef struct by2_audio_reader by2_audio_reader;
+
+    BY2_API void by2_audio_reader_default_options(by2_audio_reader_options* options);
+
+    // Opens `path` and picks its best audio stream. Fails with
+    // BY2_STATUS_INVALID_ARGUMENT for bad options, and with BY2_STATUS_IO if
+    // the file can't be opened or read or has no decodable audio stream.
+    // Free the reader with by2_audio_reader_destroy.
+    BY2_API int32_t by2_audio_reader_open(
+        const char* path,
+        const by2_audio_reader_options* options,
+        by2_audio_reader** out);
+    // Accepts NULL.
+    BY2_API void by2_audio_reader_destroy(by2_audio_reader* reader);
+    // Number of streams in the file, audio or not.
+    BY2_API size_t by2_audio_reader_stream_count(const by2_audio_reader* reader);
+    BY2_API int32_t by2_audio_reader_stream(const by2_audio_reader* reader, size_t index, by2_stream_info* out);
+    // Index of the stream samples are decoded from.
+    BY2_API size_t by2_audio_reader_audio_stream(const by2_audio_reader* reader);
+    BY2_API int32_t by2_audio_reader_format(const by2_audio_reader* reader, by2_audio_format* out);
+    // Decodes and resamples the next chunk into `*chunk`. `*decoded`
+    // receives 0 once the stream and the resampler are drained, and then
+    // `*chunk` is left unchanged. Fails with BY2_STATUS_IO if reading,
+    // decoding or resampling fails.
+    BY2_API int32_t by2_audio_reader_next(by2_audio_reader* reader, by2_audio_chunk* chunk, int32_t* decoded);
+
+    // Which frames by2_extract_frames picks.
+    typedef enum by2_frame_selection
+    {
+        // The first frame at or after each multiple of `interval` seconds,
+        // counted from the first frame.
+        BY2_SELECT_INTERVAL = 0,
+        // Frames 0, step, 2 * step, ...
+        BY2_SELECT_EVERY_NTH = 1,
+        // The first frame, then every frame whose scene score reaches
+        // `scene_threshold`.
+        BY2_SELECT_SCENE_CHANGE = 2,
+    } by2_frame_selection;
+
+    typedef enum by2_image_format
+    {
+        BY2_IMAGE_PNG = 0,
+        BY2_IMAGE_JPEG = 1,
+    } by2_image_format;
+
+    // Fill with by2_extract_default_options() and override what you need.
+    typedef struct by2_extract_options
+    {
+        int32_t selection;
+        // Seconds between frames, for BY2_SELECT_INTERVAL.
+        double interval;
+        // Frames between frames, for BY2_SELECT_EVERY_NTH.
+        int64_t step;
+        // In (0, 1], for BY2_SELECT_SCENE_CHANGE.
+        double scene_threshold;
+        // Size of the extracted frames. 0 for both keeps the video's, 0 for
+        // one of them keeps its aspect ratio.
+        int32_t width;
+        int32_t height;
+        // Parts of the file decoded in parallel, from 0 to 1024; 0 picks one
+        // per hardware thread, but none shorter than 10 s. Never more than
+        // there are hardware threads or frames to pick.
+        int32_t segments;
+        // UTF-8, may be NULL. If not empty, frames are encoded as
+        // `image_format` and written there as frame_<index>.png or .jpg
+        // instead of being kept in memory.
+        const char* directory;
+        int32_t image_format;
+        // JPEG quality from 1 to 100; PNG ignores it.
+        int32_t quality;
+    } by2_extract_options;
+
+    typedef struct by2_extracted_frame
+    {
+        // Position of the frame in the stream, from 0.
+        int64_t index;
+        // Presentation timestamp in the stream's time base, INT64_MIN if the
+        // frame has none.
+        int64_t pts;
+        // In seconds, NaN if unknown.
+        double time;
+        int32_t key_frame;
+        // Mean absolute luma difference in [0, 1] to the frame before. NaN
+        // for the first frame and outside BY2_SELECT_SCENE_CHANGE.
+        double scene_score;
+        // Packed RGB24 of the set's size, owned by the set; NULL with
+        // size 0 if the frame was written to a file.
+        const uint8_t* data;
+        size_t size;
+        // The file the frame was written to, owned by the set; NULL if it
+        // is in `data`.
+        const char* path;
+    } by2_extracted_frame;
+
+    // The frames by2_extract_frames picked, in stream order.
+    typedef struct by2_frame_set by2_frame_set;
+
+    BY2_API void by2_extract_default_options(by2_extract_options* options);
+
+    // Picks frames from the best video stream of `path`, decoding segments
+    // of the file in parallel. Fails with BY2_STATUS_INVALID_ARGUMENT for
+    // bad options, and with BY2_STATUS_IO if the file can't be opened or
+    // decoded or an image can't be written. Free the set with
+    // by2_frame_set_destroy.
+    BY2_API int32_t by2_extract_frames(const char* path, const by2_extract_options* options, by2_frame_set** out);
+    // Accepts NULL.
+    BY2_API void by2_frame_set_destroy(by2_frame_set* set);
+    BY2_API size_t by2_frame_set_count(const by2_frame_set* set);
+    // Size of every frame in the set.
+    BY2_API int32_t by2_frame_set_size(const by2_frame_set* set, int32_t* width, int32_t* height);
+    BY2_API int32_t by2_frame_set_frame(const by2_frame_set* set, size_t index, by2_extracted_frame* out);
+
+    // Encodes a packed `pixel_format` frame (`size` bytes) as a PNG or JPEG
+    // image. `*out_size` always receives the size of the image; it is
+    // written to `out` only if it fits in `capacity`, so a caller can retry
+    // with a bigger buffer. Fails with BY2_STATUS_INVALID_ARGUMENT for a
+    // frame of the wrong size or a bad format or quality.
+    BY2_API int32_t by2_encode_image(
+        const uint8_t* frame,
+        size_t size,
+        int32_t width,
+        int32_t height,
+        int32_t pixel_format,
+        int32_t image_format,
+        int32_t quality,
+        uint8_t* out,
+        size_t capacity,
+        size_t* out_size);
+
 #ifdef __cplusplus
 } // extern "C"
 #endif
\ No newline at end of file
@@ -0,0 +1,125 @@
+#include "by2/by2.h"
+#include "status.hpp"
+#include <ccore/geometry.hpp>
+
+#include <spdlog/fmt/fmt.h>
+
+#include <algorithm>
+#include <limits>
+#include <span>
+
+namespace
+{
+    using by2::detail::require;
+    using by2::detail::to_core;
+
+    by2_vec3 to_c(const by2::vec3& v)
+    {
+        return {v.x, v.y, v.z};
+    }
+
+    by2_quat to_c(const by2::quat& q)
+    {
+        return {q.w, q.x, q.y, q.z};
+    }
+
+    by2_pose to_c(const by2::pose& p)
+    {
+        return {to_c(p.rotation), to_c(p.translation)};
+    }
+}
+
+int32_t by2_quat_from_matrix(const by2_mat3* m, by2_quat* out)
+{
+    return by2::detail::call_with_status([&] {
+        by2::mat3 core;
+        std::ranges::copy(require(m, "m").m, core.begin());
+        require(out, "out") = to_c(by2::quat_from_matrix(core));
+    });
+}
+
+int32_t by2_quat_to_matrix(const by2_quat* q, by2_mat3* out)
+{
+    return by2::detail::call_with_status([&] {
+        const by2::mat3 core = by2::quat_to_matrix(to_core(require(q, "q")));
+        std::ranges::copy(core, require(out, "out").m);
+    });
+}
+
+int32_t by2_quat_from_euler(const by2_euler* e, by2_quat* out)
+{
+    return by2::detail::call_with_status([&] {
+        const by2_euler& angles = require(e, "e");
+        require(out, "out") = to_c(by2::quat_from_euler({angles.roll, angles.pitch, angles.yaw}));
+    });
+}
+
+int32_t by2_quat_to_euler(const by2_quat* q, by2_euler* out)
+{
+    return by2::detail::call_with_status([&] {
+        const by2::euler_angles angles = by2::quat_to_euler(to_core(require(q, "q")));
+        require(out, "out") = {angles.roll, angles.pitch, angles.yaw};
+    });
+}
+
+int32_t by2_quat_from_axis_angle(const by2_axis_angle* a, by2_quat* out)
+{
+    return by2::detail::call_with_status([&] {
+        const by2_axis_angle& axis_angle = require(a, "a");
+        require(out, "out") = to_c(by2::quat_from_axis_angle({to_core(axis_angle.axis), axis_angle.angle}));
+    });
+}
+
+int32_t by2_quat_to_axis_angle(const by2_quat* q, by2_axis_angle* out)
+{
+    return by2::detail::call_with_status([&] {
+        const by2::axis_angle axis_angle = by2::quat_to_axis_angle(to_core(require(q, "q")));
+        require(out, "out") = {to_c(axis_angle.axis), axis_angle.angle};
+    });
+}
+
+int32_t by2_quat_multiply(const by2_quat* a, const by2_quat* b, by2_quat* out)
+{
+    return by2::detail::call_with_status([&] {
+        require(out, "out") = to_c(by2::quat_multiply(to_core(require(a, "a")), to_core(require(b, "b"))));
+    });
+}
+
+int32_t by2_quat_inverse(const by2_quat* q, by2_quat* out)
+{
+    return by2::detail::call_with_status([&] {
+        require(out, "out") = to_c(by2::quat_inverse(to_core(require(q, "q"))));
+    });
+}
+
+int32_t by2_pose_compose(const by2_pose* a, const by2_pose* b, by2_pose* out)
+{
+    return by2::detail::call_with_status([&] {
+        require(out, "out") = to_c(by2::pose_compose(to_core(require(a, "a")), to_core(require(b, "b"))));
+    });
+}
+
+int32_t by2_pose_inverse(const by2_pose* p, by2_pose* out)
+{
+    return by2::detail::call_with_status([&] {
+        require(out, "out") = to_c(by2::pose_inverse(to_core(require(p, "p"))));
+    });
+}
+
+int32_t by2_pose_transform_points(const by2_pose* p, const double* points, double* out, size_t count)
+{
+    return by2::detail::call_with_status([&] {
+        if (count > std::numeric_limits<size_t>::max() / 3)
+        {
+            throw by2::error(by2::error_code::invalid_argument, fmt::format("{} points overflow size_t", count));
+        }
+        if (count > 0 && (points == nullptr || out == nullptr))
+        {
+            throw by2::error(by2::error_code::invalid_argument, "points or out is null");
+        }
+        by2::pose_transform_points(
+            to_core(require(p, "p")),
+            std::span<const double>(points, count * 3),
+            std::span<double>(out, count * 3));
+    });
+}
@@ -0,0 +1,153 @@
+#include "by2/by2.h"
+#include "status.hpp"
+#include <ccore/kdtree.hpp>
+
+#include <algorithm>
+#include <array>
+#include <limits>
+#include <vector>
+
+struct by2_kdtree
+{
+    by2::kdtree tree;
+};
+
+namespace
+{
+    using by2::detail::require;
+    using by2::detail::to_core;
+
+    std::array<double, 3> to_query(const by2_vec3& v)
+    {
+        return {v.x, v.y, v.z};
+    }
+
+    by2_neighbor* write(const std::vector<by2::neighbor>& found, by2_neighbor* out)
+    {
+        return std::transform(found.begin(), found.end(), out, [](const by2::neighbor& n) {
+            return by2_neighbor{n.second, n.first};
+        });
+    }
+
+    // The knn batch output holds query_count * stride entries.
+    void expect_query_count(std::size_t query_count, std::size_t stride)
+    {
+        if (stride > 0 && query_count > std::numeric_limits<std::size_t>::max() / stride)
+        {
+            throw by2::error(by2::error_code::invalid_argument, "kdtree: output size overflows size_t");
+        }
+    }
+}
+
+int32_t by2_kdtree_create(const by2_point_cloud* cloud, by2_kdtree** out)
+{
+    return by2::detail::call_with_status([&] {
+        const by2_point_cloud& c = require(cloud, "cloud");
+        by2_kdtree*& result = require(out, "out");
+        result = new by2_kdtree{by2::kdtree(to_core(c))};
+    });
+}
+
+void by2_kdtree_destroy(by2_kdtree* tree)
+{
+    delete tree;
+}
+
+size_t by2_kdtree_size(const by2_kdtree* tree)
+{
+    return tree == nullptr ? 0 : tree->tree.size();
+}
+
+int32_t by2_kdtree_knn(const by2_kdtree* tree, const by2_vec3* query, size_t k, by2_neighbor* out, size_t* out_count)
+{
+    return by2::detail::call_with_status([&] {
+        const by2::kdtree& t = require(tree, "tree").tree;
+        const std::array<double, 3> q = to_query(require(query, "query"));
+        size_t& count = require(out_count, "out_count");
+        std::vector<by2::neighbor> found;
+        t.knn(q, k, found);
+        write(found, require(out, found.size(), "out"));
+        count = found.size();
+    });
+}
+
+int32_t by2_kdtree_radius(
+    const by2_kdtree* tree,
+    const by2_vec3* query,
+    double radius,
+    by2_neighbor* out,
+    size_t capacity,
+    size_t* out_count)
+{
+    return by2::detail::call_with_status([&] {
+        const by2::kdtree& t = require(tree, "tree").tree;
+        const std::array<double, 3> q = to_query(require(query, "query"));
+        size_t& count = require(out_count, "out_count");
+        std::vector<by2::neighbor> found;
+        t.radius(q, radius, found);
+        if (found.size() <= capacity)
+        {
+            write(found, require(out, found.size(), "out"));
+        }
+        count = found.size();
+    });
+}
+
+int32_t by2_kdtree_knn_batch(
+    const by2_kdtree* tree,
+    const by2_vec3* queries,
+    size_t query_count,
+    size_t k,
+    by2_neighbor* out,
+    size_t* counts)
+{
+    return by2::detail::call_with_status([&] {
+        const by2::kdtree& t = require(tree, "tree").tree;
+        const size_t stride = std::min(k, t.size());
+        expect_query_count(query_count, stride);
+        require(queries, query_count, "queries");
+        require(counts, query_count, "counts");
+        require(out, stride * query_count, "out");
+        std::vector<by2::neighbor> found;
+        for (size_t i = 0; i < query_count; ++i)
+        {
+            t.knn(to_query(queries[i]), k, found);
+            write(found, out + i * stride);
+            counts[i] = found.size();
+        }
+    });
+}
+
+int32_t by2_kdtree_radius_batch(
+    const by2_kdtree* tree,
+    const by2_vec3* queries,
+    size_t query_count,
+    double radius,
+    by2_neighbor* out,
+    size_t capacity,
+    size_t* offsets)
+{
+    return by2::detail::call_with_status([&] {
+        const by2::kdtree& t = require(tree, "tree").tree;
+        require(queries, query_count, "queries");
+        require(offsets, 1, "offsets");
+        std::vector<std::vector<by2::neighbor>> found(query_count);
+        size_t total = 0;
+        for (size_t i = 0; i < query_count; ++i)
+        {
+            t.radius(to_query(queries[i]), radius, found[i]);
+            total += found[i].size();
+        }
+        require(out, total <= capacity ? total : 0, "out");
+
+        offsets[0] = 0;
+        for (size_t i = 0; i < query_count; ++i)
+        {
+            offsets[i + 1] = offsets[i] + found[i].size();
+            if (total <= capacity)
+            {
+                write(found[i], out + offsets[i]);
+            }
+        }
+    });
+}
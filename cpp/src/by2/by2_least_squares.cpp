@@ -0,0 +1,77 @@
+#include "by2/by2.h"
+#include "status.hpp"
+#include <ccore/least_squares.hpp>
+
+#include <span>
+
+static_assert(BY2_LSQ_LEVENBERG_MARQUARDT == static_cast<int32_t>(by2::lsq_method::levenberg_marquardt));
+static_assert(BY2_LSQ_GAUSS_NEWTON == static_cast<int32_t>(by2::lsq_method::gauss_newton));
+static_assert(BY2_LSQ_FUNCTION_TOLERANCE == static_cast<int32_t>(by2::lsq_termination::function_tolerance));
+static_assert(BY2_LSQ_PARAMETER_TOLERANCE == static_cast<int32_t>(by2::lsq_termination::parameter_tolerance));
+static_assert(BY2_LSQ_GRADIENT_TOLERANCE == static_cast<int32_t>(by2::lsq_termination::gradient_tolerance));
+static_assert(BY2_LSQ_MAX_ITERATIONS == static_cast<int32_t>(by2::lsq_termination::max_iterations));
+static_assert(BY2_LSQ_TOLERANCE_TOO_SMALL == static_cast<int32_t>(by2::lsq_termination::tolerance_too_small));
+
+void by2_lsq_default_options(by2_lsq_options* options)
+{
+    if (options == nullptr)
+    {
+        return;
+    }
+    const by2::lsq_options defaults;
+    options->method = static_cast<int32_t>(defaults.method);
+    options->max_iterations = defaults.max_iterations;
+    options->function_tolerance = defaults.function_tolerance;
+    options->parameter_tolerance = defaults.parameter_tolerance;
+    options->gradient_tolerance = defaults.gradient_tolerance;
+}
+
+int32_t by2_lsq_solve(
+    by2_lsq_residual_fn residual,
+    by2_lsq_jacobian_fn jacobian,
+    void* user_data,
+    double* params,
+    size_t param_count,
+    size_t residual_count,
+    const by2_lsq_options* options,
+    by2_lsq_summary* summary)
+{
+    return by2::detail::call_with_status([&] {
+        if (residual == nullptr || options == nullptr || summary == nullptr || (param_count > 0 && params == nullptr))
+        {
+            throw by2::error(by2::error_code::invalid_argument, "residual, params, options and summary must not be null");
+        }
+
+        const by2::lsq_residual_function residual_fn = [&](std::span<const double> p, std::span<double> r) {
+            return residual(user_data, p.data(), p.size(), r.data(), r.size()) == 0;
+        };
+        by2::lsq_jacobian_function jacobian_fn;
+        if (jacobian != nullptr)
+        {
+            jacobian_fn = [&](std::span<const double> p, std::span<double> j) {
+                return jacobian(user_data, p.data(), p.size(), j.data(), residual_count) == 0;
+            };
+        }
+
+        const by2::lsq_summary result = by2::least_squares_solve(
+            residual_fn,
+            jacobian_fn,
+            std::span<double>(params, param_count),
+            residual_count,
+            {
+                static_cast<by2::lsq_method>(options->method),
+                options->max_iterations,
+                options->function_tolerance,
+                options->parameter_tolerance,
+                options->gradient_tolerance,
+            });
+
+        summary->converged = result.converged ? 1 : 0;
+        summary->termination = static_cast<int32_t>(result.termination);
+        summary->initial_cost = result.initial_cost;
+        summary->final_cost = result.final_cost;
+        summary->iterations = result.iterations;
+        summary->residual_evaluations = result.residual_evaluations;
+        summary->jacobian_evaluations = result.jacobian_evaluations;
+    });
+}
@@ -0,0 +1,180 @@
+#include "by2/by2.h"
+#include "status.hpp"
+#include <ccore/linalg.hpp>
+#include <ccore/sparse.hpp>
+
+#include <spdlog/fmt/fmt.h>
+
+#include <initializer_list>
+
+static_assert(BY2_SPARSE_CSR == static_cast<int32_t>(by2::sparse_format::csr));
+static_assert(BY2_SPARSE_CSC == static_cast<int32_t>(by2::sparse_format::csc));
+static_assert(BY2_SPARSE_SIMPLICIAL_LDLT == static_cast<int32_t>(by2::sparse_solver::simplicial_ldlt));
+static_assert(BY2_SPARSE_QR == static_cast<int32_t>(by2::sparse_solver::qr));
+static_assert(BY2_SPARSE_CONJUGATE_GRADIENT == static_cast<int32_t>(by2::sparse_solver::conjugate_gradient));
+
+namespace
+{
+    void require_matrix(const by2_matrix* m, const char* name)
+    {
+        if (m == nullptr)
+        {
+            throw by2::error(by2::error_code::invalid_argument, fmt::format("{} is null", name));
+        }
+    }
+
+    // The scalar type shared by all `matrices`; throws if they disagree.
+    int32_t common_scalar_type(std::initializer_list<const by2_matrix*> matrices, int32_t scalar_type)
+    {
+        for (const by2_matrix* m : matrices)
+        {
+            if (m->scalar_type != scalar_type)
+            {
+                throw by2::error(by2::error_code::invalid_argument, "matrices have different scalar types");
+            }
+        }
+        return scalar_type;
+    }
+
+    int32_t common_scalar_type(std::initializer_list<const by2_matrix*> matrices)
+    {
+        return common_scalar_type(matrices, (*matrices.begin())->scalar_type);
+    }
+
+    template <typename T>
+    by2::matrix_view<T> view(const by2_matrix* m)
+    {
+        return {static_cast<T*>(m->data), m->rows, m->cols, m->row_stride, m->col_stride};
+    }
+
+    template <typename T>
+    by2::sparse_view<T> sparse_view(const by2_sparse_matrix* m)
+    {
+        return {
+            static_cast<by2::sparse_format>(m->format),
+            m->rows,
+            m->cols,
+            m->outer_offsets,
+            m->inner_indices,
+            static_cast<const T*>(m->values),
+        };
+    }
+
+    // Calls `f.template operator()<T>()` with T matching `scalar_type`.
+    template <typename F>
+    void dispatch(int32_t scalar_type, F&& f)
+    {
+        switch (scalar_type)
+        {
+        case BY2_SCALAR_F32:
+            f.template operator()<float>();
+            break;
+        case BY2_SCALAR_F64:
+            f.template operator()<double>();
+            break;
+        default:
+            throw by2::error(
+                by2::error_code::invalid_argument,
+                fmt::format("unknown scalar type {}", scalar_type));
+        }
+    }
+}
+
+int32_t by2_matrix_multiply(const by2_matrix* a, const by2_matrix* b, const by2_matrix* out)
+{
+    return by2::detail::call_with_status([&] {
+        require_matrix(a, "a");
+        require_matrix(b, "b");
+        require_matrix(out, "out");
+        dispatch(common_scalar_type({a, b, out}), [&]<typename T>() {
+            by2::matrix_multiply<T>(view<const T>(a), view<const T>(b), view<T>(out));
+        });
+    });
+}
+
+int32_t by2_matrix_inverse(const by2_matrix* a, const by2_matrix* out)
+{
+    return by2::detail::call_with_status([&] {
+        require_matrix(a, "a");
+        require_matrix(out, "out");
+        dispatch(common_scalar_type({a, out}), [&]<typename T>() {
+            by2::matrix_inverse<T>(view<const T>(a), view<T>(out));
+        });
+    });
+}
+
+int32_t by2_matrix_solve(int32_t method, const by2_matrix* a, const by2_matrix* b, const by2_matrix* x)
+{
+    return by2::detail::call_with_status([&] {
+        require_matrix(a, "a");
+        require_matrix(b, "b");
+        require_matrix(x, "x");
+        dispatch(common_scalar_type({a, b, x}), [&]<typename T>() {
+            by2::matrix_solve<T>(static_cast<by2::solve_method>(method), view<const T>(a), view<const T>(b), view<T>(x));
+        });
+    });
+}
+
+int32_t by2_matrix_svd(const by2_matrix* a, const by2_matrix* u, const by2_matrix* s, const by2_matrix* v)
+{
+    return by2::detail::call_with_status([&] {
+        require_matrix(a, "a");
+        require_matrix(u, "u");
+        require_matrix(s, "s");
+        require_matrix(v, "v");
+        dispatch(common_scalar_type({a, u, s, v}), [&]<typename T>() {
+            by2::matrix_svd<T>(view<const T>(a), view<T>(u), view<T>(s), view<T>(v));
+        });
+    });
+}
+
+int32_t by2_matrix_eigen_symmetric(const by2_matrix* a, const by2_matrix* values, const by2_matrix* vectors)
+{
+    return by2::detail::call_with_status([&] {
+        require_matrix(a, "a");
+        require_matrix(values, "values");
+        require_matrix(vectors, "vectors");
+        dispatch(common_scalar_type({a, values, vectors}), [&]<typename T>() {
+            by2::matrix_eigen_symmetric<T>(view<const T>(a), view<T>(values), view<T>(vectors));
+        });
+    });
+}
+
+int32_t by2_sparse_multiply(const by2_sparse_matrix* a, const by2_matrix* b, const by2_matrix* out)
+{
+    return by2::detail::call_with_status([&] {
+        if (a == nullptr)
+        {
+            throw by2::error(by2::error_code::invalid_argument, "a is null");
+        }
+        require_matrix(b, "b");
+        require_matrix(out, "out");
+        dispatch(common_scalar_type({b, out}, a->scalar_type), [&]<typename T>() {
+            by2::sparse_multiply<T>(sparse_view<T>(a), view<const T>(b), view<T>(out));
+        });
+    });
+}
+
+int32_t by2_sparse_solve(
+    const by2_sparse_solve_options* options,
+    const by2_sparse_matrix* a,
+    const by2_matrix* b,
+    const by2_matrix* x)
+{
+    return by2::detail::call_with_status([&] {
+        if (options == nullptr || a == nullptr)
+        {
+            throw by2::error(by2::error_code::invalid_argument, "options and a must not be null");
+        }
+        require_matrix(b, "b");
+        require_matrix(x, "x");
+        const by2::sparse_solve_options core_options{
+            static_cast<by2::sparse_solver>(options->solver),
+            options->max_iterations,
+            options->tolerance,
+        };
+        dispatch(common_scalar_type({b, x}, a->scalar_type), [&]<typename T>() {
+            by2::sparse_solve<T>(core_options, sparse_view<T>(a), view<const T>(b), view<T>(x));
+        });
+    });
+}
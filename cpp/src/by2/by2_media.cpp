@@ -0,0 +1,663 @@
+#include "by2/by2.h"
+#include "status.hpp"
+#include <ccore/audio_reader.hpp>
+#include <ccore/frame_extractor.hpp>
+#include <ccore/media.hpp>
+#include <ccore/media_probe.hpp>
+#include <ccore/scaler.hpp>
+#include <ccore/video_reader.hpp>
+#include <ccore/video_writer.hpp>
+
+#include <spdlog/fmt/fmt.h>
+
+#include <algorithm>
+#include <iterator>
+#include <optional>
+#include <span>
+#include <string>
+#include <utility>
+#include <vector>
+
+static_assert(BY2_PIXEL_RGB24 == static_cast<int32_t>(by2::pixel_format::rgb24));
+static_assert(BY2_PIXEL_RGBA == static_cast<int32_t>(by2::pixel_format::rgba));
+static_assert(BY2_PIXEL_YUV420P == static_cast<int32_t>(by2::pixel_format::yuv420p));
+static_assert(BY2_PIXEL_GRAY8 == static_cast<int32_t>(by2::pixel_format::gray8));
+static_assert(BY2_PIXEL_BGR24 == static_cast<int32_t>(by2::pixel_format::bgr24));
+static_assert(BY2_PIXEL_BGRA == static_cast<int32_t>(by2::pixel_format::bgra));
+static_assert(BY2_PIXEL_NV12 == static_cast<int32_t>(by2::pixel_format::nv12));
+static_assert(BY2_PIXEL_YUV422P == static_cast<int32_t>(by2::pixel_format::yuv422p));
+static_assert(BY2_PIXEL_YUV444P == static_cast<int32_t>(by2::pixel_format::yuv444p));
+
+static_assert(BY2_MEDIA_VIDEO == static_cast<int32_t>(by2::media_type::video));
+static_assert(BY2_MEDIA_AUDIO == static_cast<int32_t>(by2::media_type::audio));
+static_assert(BY2_MEDIA_SUBTITLE == static_cast<int32_t>(by2::media_type::subtitle));
+static_assert(BY2_MEDIA_DATA == static_cast<int32_t>(by2::media_type::data));
+static_assert(BY2_MEDIA_UNKNOWN == static_cast<int32_t>(by2::media_type::unknown));
+
+static_assert(BY2_INTERPOLATION_BILINEAR == static_cast<int32_t>(by2::interpolation::bilinear));
+static_assert(BY2_INTERPOLATION_BICUBIC == static_cast<int32_t>(by2::interpolation::bicubic));
+static_assert(BY2_INTERPOLATION_AREA == static_cast<int32_t>(by2::interpolation::area));
+
+static_assert(BY2_VIDEO_CODEC_MPEG4 == static_cast<int32_t>(by2::video_codec::mpeg4));
+static_assert(BY2_VIDEO_CODEC_FFV1 == static_cast<int32_t>(by2::video_codec::ffv1));
+
+static_assert(BY2_SAMPLE_F32 == static_cast<int32_t>(by2::sample_format::f32));
+static_assert(BY2_SAMPLE_F32_PLANAR == static_cast<int32_t>(by2::sample_format::f32_planar));
+
+static_assert(BY2_SELECT_INTERVAL == static_cast<int32_t>(by2::frame_selection::interval));
+static_assert(BY2_SELECT_EVERY_NTH == static_cast<int32_t>(by2::frame_selection::every_nth));
+static_assert(BY2_SELECT_SCENE_CHANGE == static_cast<int32_t>(by2::frame_selection::scene_change));
+
+static_assert(BY2_IMAGE_PNG == static_cast<int32_t>(by2::image_format::png));
+static_assert(BY2_IMAGE_JPEG == static_cast<int32_t>(by2::image_format::jpeg));
+
+struct by2_media_info
+{
+    by2::media_info info;
+};
+
+struct by2_scaler
+{
+    by2::scaler scaler;
+};
+
+struct by2_video_reader
+{
+    by2::video_reader reader;
+};
+
+struct by2_video_writer
+{
+    by2::video_writer writer;
+};
+
+struct by2_audio_reader
+{
+    by2::audio_reader reader;
+};
+
+struct by2_frame_set
+{
+    by2::extracted_frames frames;
+};
+
+namespace
+{
+    using by2::detail::require;
+
+    // Truncated to fit, always NUL-terminated.
+    template <std::size_t N>
+    void copy_name(const std::string& name, char (&out)[N])
+    {
+        const std::size_t length = std::min(name.size(), N - 1);
+        std::copy_n(name.data(), length, out);
+        out[length] = '\0';
+    }
+
+    by2_stream_info to_c(const by2::stream_info& s)
+    {
+        by2_stream_info out{};
+        out.index = s.index;
+        out.type = static_cast<int32_t>(s.type);
+        copy_name(s.codec, out.codec);
+        out.width = s.width;
+        out.height = s.height;
+        out.frame_rate = s.frame_rate;
+        copy_name(s.pixel_format, out.pixel_format);
+        out.sample_rate = s.sample_rate;
+        out.channels = s.channels;
+        copy_name(s.channel_layout, out.channel_layout);
+        out.bit_rate = s.bit_rate;
+        out.time_base = {s.time_base.num, s.time_base.den};
+        out.duration = s.duration;
+        out.frame_count = s.frame_count;
+        return out;
+    }
+
+    // Tags of the file for -1, else of stream `stream`; null if out of range.
+    const std::vector<by2::media_tag>* tags_of(const by2::media_info& info, int64_t stream)
+    {
+        if (stream == -1)
+        {
+            return &info.tags;
+        }
+        if (stream < 0 || static_cast<uint64_t>(stream) >= info.streams.size())
+        {
+            return nullptr;
+        }
+        return &info.streams[static_cast<std::size_t>(stream)].tags;
+    }
+
+    by2_video_frame_info to_c(const by2::video_frame_info& f)
+    {
+        return {f.pts, f.time, f.index, f.key_frame ? 1 : 0};
+    }
+}
+
+int32_t by2_media_probe(const char* path, by2_media_info** out)
+{
+    return by2::detail::call_with_status([&] {
+        const char& p = require(path, "path");
+        by2_media_info*& result = require(out, "out");
+        result = new by2_media_info{by2::probe_media(&p)};
+    });
+}
+
+void by2_media_info_destroy(by2_media_info* info)
+{
+    delete info;
+}
+
+int32_t by2_media_info_format(const by2_media_info* info, by2_media_format* out)
+{
+    return by2::detail::call_with_status([&] {
+        const by2::media_info& i = require(info, "info").info;
+        by2_media_format& result = require(out, "out");
+        result = {};
+        copy_name(i.format, result.name);
+        copy_name(i.format_long_name, result.long_name);
+        result.duration = i.duration;
+        result.bit_rate = i.bit_rate;
+    });
+}
+
+size_t by2_media_info_stream_count(const by2_media_info* info)
+{
+    return info == nullptr ? 0 : info->info.streams.size();
+}
+
+int32_t by2_media_info_stream(const by2_media_info* info, size_t index, by2_stream_info* out)
+{
+    return by2::detail::call_with_status([&] {
+        const std::vector<by2::stream_info>& streams = require(info, "info").info.streams;
+        by2_stream_info& result = require(out, "out");
+        if (index >= streams.size())
+        {
+            throw by2::error(
+                by2::error_code::invalid_argument,
+                fmt::format("stream {} is out of range for {} streams", index, streams.size()));
+        }
+        result = to_c(streams[index]);
+    });
+}
+
+size_t by2_media_info_tag_count(const by2_media_info* info, int64_t stream)
+{
+    if (info == nullptr)
+    {
+        return 0;
+    }
+    const std::vector<by2::media_tag>* tags = tags_of(info->info, stream);
+    return tags == nullptr ? 0 : tags->size();
+}
+
+int32_t by2_media_info_tag(
+    const by2_media_info* info,
+    int64_t stream,
+    size_t index,
+    const char** key,
+    const char** value)
+{
+    return by2::detail::call_with_status([&] {
+        const by2::media_info& i = require(info, "info").info;
+        const char*& k = require(key, "key");
+        const char*& v = require(value, "value");
+        const std::vector<by2::media_tag>* tags = tags_of(i, stream);
+        if (tags == nullptr)
+        {
+            throw by2::error(
+                by2::error_code::invalid_argument,
+                fmt::format("stream {} is out of range for {} streams", stream, i.streams.size()));
+        }
+        if (index >= tags->size())
+        {
+            throw by2::error(
+                by2::error_code::invalid_argument,
+                fmt::format("tag {} is out of range for {} tags", index, tags->size()));
+        }
+        k = (*tags)[index].key.c_str();
+        v = (*tags)[index].value.c_str();
+    });
+}
+
+int32_t by2_scaler_create(
+    int32_t source_width,
+    int32_t source_height,
+    int32_t source_format,
+    int32_t destination_width,
+    int32_t destination_height,
+    int32_t destination_format,
+    int32_t interpolation,
+    by2_scaler** out)
+{
+    return by2::detail::call_with_status([&] {
+        by2_scaler*& result = require(out, "out");
+        result = new by2_scaler{by2::scaler(
+            source_width,
+            source_height,
+            static_cast<by2::pixel_format>(source_format),
+            destination_width,
+            destination_height,
+            static_cast<by2::pixel_format>(destination_format),
+            static_cast<by2::interpolation>(interpolation))};
+    });
+}
+
+void by2_scaler_destroy(by2_scaler* scaler)
+{
+    delete scaler;
+}
+
+size_t by2_scaler_source_size(const by2_scaler* scaler)
+{
+    return scaler == nullptr ? 0 : scaler->scaler.source_size();
+}
+
+size_t by2_scaler_destination_size(const by2_scaler* scaler)
+{
+    return scaler == nullptr ? 0 : scaler->scaler.destination_size();
+}
+
+int32_t by2_scaler_scale(
+    by2_scaler* scaler,
+    const uint8_t* source,
+    size_t source_size,
+    uint8_t* destination,
+    size_t destination_size)
+{
+    return by2::detail::call_with_status([&] {
+        by2::scaler& s = require(scaler, "scaler").scaler;
+        s.scale(
+            std::span<const std::uint8_t>(require(source, source_size, "source"), source_size),
+            std::span<std::uint8_t>(require(destination, destination_size, "destination"), destination_size));
+    });
+}
+
+int32_t by2_video_reader_open(const char* path, int32_t pixel_format, by2_video_reader** out)
+{
+    return by2::detail::call_with_status([&] {
+        const char& p = require(path, "path");
+        by2_video_reader*& result = require(out, "out");
+        result = new by2_video_reader{by2::video_reader(&p, static_cast<by2::pixel_format>(pixel_format))};
+    });
+}
+
+void by2_video_reader_destroy(by2_video_reader* reader)
+{
+    delete reader;
+}
+
+size_t by2_video_reader_stream_count(const by2_video_reader* reader)
+{
+    return reader == nullptr ? 0 : reader->reader.streams().size();
+}
+
+int32_t by2_video_reader_stream(const by2_video_reader* reader, size_t index, by2_stream_info* out)
+{
+    return by2::detail::call_with_status([&] {
+        const std::span<const by2::stream_info> streams = require(reader, "reader").reader.streams();
+        by2_stream_info& result = require(out, "out");
+        if (index >= streams.size())
+        {
+            throw by2::error(
+                by2::error_code::invalid_argument,
+                fmt::format("stream {} is out of range for {} streams", index, streams.size()));
+        }
+        result = to_c(streams[index]);
+    });
+}
+
+size_t by2_video_reader_video_stream(const by2_video_reader* reader)
+{
+    return reader == nullptr ? 0 : reader->reader.video_stream();
+}
+
+size_t by2_video_reader_frame_size(const by2_video_reader* reader)
+{
+    return reader == nullptr ? 0 : reader->reader.frame_size();
+}
+
+int32_t by2_video_reader_next(
+    by2_video_reader* reader,
+    uint8_t* out,
+    size_t capacity,
+    by2_video_frame_info* info,
+    int32_t* decoded)
+{
+    return by2::detail::call_with_status([&] {
+        by2::video_reader& r = require(reader, "reader").reader;
+        std::span<std::uint8_t> frame(require(out, capacity, "out"), capacity);
+        by2_video_frame_info& frame_info = require(info, "info");
+        int32_t& got = require(decoded, "decoded");
+        const std::optional<by2::video_frame_info> next = r.next(frame);
+        got = next.has_value() ? 1 : 0;
+        if (next)
+        {
+            frame_info = to_c(*next);
+        }
+    });
+}
+
+int32_t by2_video_reader_seek_time(by2_video_reader* reader, double seconds)
+{
+    return by2::detail::call_with_status([&] { require(reader, "reader").reader.seek_to_time(seconds); });
+}
+
+int32_t by2_video_reader_seek_frame(by2_video_reader* reader, int64_t index)
+{
+    return by2::detail::call_with_status([&] { require(reader, "reader").reader.seek_to_frame(index); });
+}
+
+int32_t by2_video_reader_build_index(by2_video_reader* reader)
+{
+    return by2::detail::call_with_status([&] { require(reader, "reader").reader.build_index(); });
+}
+
+int32_t by2_video_reader_index(
+    const by2_video_reader* reader,
+    by2_video_keyframe* out,
+    size_t capacity,
+    size_t* out_count,
+    int64_t* frame_count)
+{
+    return by2::detail::call_with_status([&] {
+        const by2::video_index* index = require(reader, "reader").reader.index();
+        by2_video_keyframe* keyframes = require(out, capacity, "out");
+        size_t& count = require(out_count, "out_count");
+        int64_t& frames = require(frame_count, "frame_count");
+        count = index == nullptr ? 0 : index->keyframes.size();
+        frames = index == nullptr ? -1 : index->frame_count;
+        if (index != nullptr && count <= capacity)
+        {
+            std::transform(
+                index->keyframes.begin(),
+                index->keyframes.end(),
+                keyframes,
+                [](const by2::video_keyframe& k) { return by2_video_keyframe{k.index, k.pts}; });
+        }
+    });
+}
+
+int32_t by2_video_reader_set_index(
+    by2_video_reader* reader,
+    const by2_video_keyframe* keyframes,
+    size_t count,
+    int64_t frame_count)
+{
+    return by2::detail::call_with_status([&] {
+        by2::video_reader& r = require(reader, "reader").reader;
+        const std::span<const by2_video_keyframe> in(require(keyframes, count, "keyframes"), count);
+        by2::video_index index;
+        index.frame_count = frame_count;
+        index.keyframes.reserve(count);
+        for (const by2_video_keyframe& k : in)
+        {
+            index.keyframes.push_back({k.index, k.pts});
+        }
+        r.set_index(std::move(index));
+    });
+}
+
+void by2_video_writer_default_options(by2_video_writer_options* options)
+{
+    if (options == nullptr)
+    {
+        return;
+    }
+    const by2::video_writer_options defaults;
+    options->codec = static_cast<int32_t>(defaults.codec);
+    options->frame_rate = {defaults.frame_rate.num, defaults.frame_rate.den};
+    options->bit_rate = defaults.bit_rate;
+    options->gop_size = defaults.gop_size;
+}
+
+int32_t by2_video_writer_create(
+    const char* path,
+    int32_t width,
+    int32_t height,
+    int32_t pixel_format,
+    const by2_video_writer_options* options,
+    by2_video_writer** out)
+{
+    return by2::detail::call_with_status([&] {
+        const char& p = require(path, "path");
+        const by2_video_writer_options& o = require(options, "options");
+        by2_video_writer*& result = require(out, "out");
+        result = new by2_video_writer{by2::video_writer(
+            &p,
+            width,
+            height,
+            static_cast<by2::pixel_format>(pixel_format),
+            {
+                static_cast<by2::video_codec>(o.codec),
+                {o.frame_rate.num, o.frame_rate.den},
+                o.bit_rate,
+                o.gop_size,
+            })};
+    });
+}
+
+void by2_video_writer_destroy(by2_video_writer* writer)
+{
+    delete writer;
+}
+
+size_t by2_video_writer_frame_size(const by2_video_writer* writer)
+{
+    return writer == nullptr ? 0 : writer->writer.frame_size();
+}
+
+int64_t by2_video_writer_frame_count(const by2_video_writer* writer)
+{
+    return writer == nullptr ? 0 : writer->writer.frame_count();
+}
+
+int32_t by2_video_writer_write(by2_video_writer* writer, const uint8_t* frame, size_t size)
+{
+    return by2::detail::call_with_status([&] {
+        by2::video_writer& w = require(writer, "writer").writer;
+        w.write(std::span<const std::uint8_t>(require(frame, size, "frame"), size));
+    });
+}
+
+int32_t by2_video_writer_finish(by2_video_writer* writer)
+{
+    return by2::detail::call_with_status([&] { require(writer, "writer").writer.finish(); });
+}
+
+void by2_audio_reader_default_options(by2_audio_reader_options* options)
+{
+    if (options == nullptr)
+    {
+        return;
+    }
+    const by2::audio_reader_options defaults;
+    options->sample_rate = defaults.sample_rate;
+    copy_name(defaults.channel_layout, options->channel_layout);
+    options->sample_format = static_cast<int32_t>(defaults.format);
+}
+
+int32_t by2_audio_reader_open(const char* path, const by2_audio_reader_options* options, by2_audio_reader** out)
+{
+    return by2::detail::call_with_status([&] {
+        const char& p = require(path, "path");
+        const by2_audio_reader_options& o = require(options, "options");
+        by2_audio_reader*& result = require(out, "out");
+        by2::audio_reader_options core;
+        core.sample_rate = o.sample_rate;
+        // Up to the NUL, or the whole array without one.
+        core.channel_layout.assign(o.channel_layout, std::find(std::begin(o.channel_layout), std::end(o.channel_layout), '\0'));
+        core.format = static_cast<by2::sample_format>(o.sample_format);
+        result = new by2_audio_reader{by2::audio_reader(&p, core)};
+    });
+}
+
+void by2_audio_reader_destroy(by2_audio_reader* reader)
+{
+    delete reader;
+}
+
+size_t by2_audio_reader_stream_count(const by2_audio_reader* reader)
+{
+    return reader == nullptr ? 0 : reader->reader.streams().size();
+}
+
+int32_t by2_audio_reader_stream(const by2_audio_reader* reader, size_t index, by2_stream_info* out)
+{
+    return by2::detail::call_with_status([&] {
+        const std::span<const by2::stream_info> streams = require(reader, "reader").reader.streams();
+        by2_stream_info& result = require(out, "out");
+        if (index >= streams.size())
+        {
+            throw by2::error(
+                by2::error_code::invalid_argument,
+                fmt::format("stream {} is out of range for {} streams", index, streams.size()));
+        }
+        result = to_c(streams[index]);
+    });
+}
+
+size_t by2_audio_reader_audio_stream(const by2_audio_reader* reader)
+{
+    return reader == nullptr ? 0 : reader->reader.audio_stream();
+}
+
+int32_t by2_audio_reader_format(const by2_audio_reader* reader, by2_audio_format* out)
+{
+    return by2::detail::call_with_status([&] {
+        const by2::audio_reader& r = require(reader, "reader").reader;
+        by2_audio_format& result = require(out, "out");
+        result.sample_rate = r.sample_rate();
+        result.channels = r.channels();
+        copy_name(r.channel_layout(), result.channel_layout);
+        result.sample_format = static_cast<int32_t>(r.format());
+    });
+}
+
+int32_t by2_audio_reader_next(by2_audio_reader* reader, by2_audio_chunk* chunk, int32_t* decoded)
+{
+    return by2::detail::call_with_status([&] {
+        by2::audio_reader& r = require(reader, "reader").reader;
+        by2_audio_chunk& result = require(chunk, "chunk");
+        int32_t& got = require(decoded, "decoded");
+        const std::optional<by2::audio_chunk> next = r.next();
+        got = next.has_value() ? 1 : 0;
+        if (next)
+        {
+            result = {next->data.data(), next->samples, next->position, next->time};
+        }
+    });
+}
+
+void by2_extract_default_options(by2_extract_options* options)
+{
+    if (options == nullptr)
+    {
+        return;
+    }
+    const by2::frame_extractor_options defaults;
+    options->selection = static_cast<int32_t>(defaults.selection);
+    options->interval = defaults.interval;
+    options->step = defaults.step;
+    options->scene_threshold = defaults.scene_threshold;
+    options->width = defaults.width;
+    options->height = defaults.height;
+    options->segments = defaults.segments;
+    options->directory = nullptr;
+    options->image_format = static_cast<int32_t>(defaults.format);
+    options->quality = defaults.quality;
+}
+
+int32_t by2_extract_frames(const char* path, const by2_extract_options* options, by2_frame_set** out)
+{
+    return by2::detail::call_with_status([&] {
+        const char& p = require(path, "path");
+        const by2_extract_options& o = require(options, "options");
+        by2_frame_set*& result = require(out, "out");
+        by2::frame_extractor_options core;
+        core.selection = static_cast<by2::frame_selection>(o.selection);
+        core.interval = o.interval;
+        core.step = o.step;
+        core.scene_threshold = o.scene_threshold;
+        core.width = o.width;
+        core.height = o.height;
+        core.segments = o.segments;
+        core.directory = o.directory == nullptr ? "" : o.directory;
+        core.format = static_cast<by2::image_format>(o.image_format);
+        core.quality = o.quality;
+        result = new by2_frame_set{by2::extract_frames(&p, core)};
+    });
+}
+
+void by2_frame_set_destroy(by2_frame_set* set)
+{
+    delete set;
+}
+
+size_t by2_frame_set_count(const by2_frame_set* set)
+{
+    return set == nullptr ? 0 : set->frames.frames.size();
+}
+
+int32_t by2_frame_set_size(const by2_frame_set* set, int32_t* width, int32_t* height)
+{
+    return by2::detail::call_with_status([&] {
+        const by2::extracted_frames& f = require(set, "set").frames;
+        require(width, "width") = f.width;
+        require(height, "height") = f.height;
+    });
+}
+
+int32_t by2_frame_set_frame(const by2_frame_set* set, size_t index, by2_extracted_frame* out)
+{
+    return by2::detail::call_with_status([&] {
+        const std::vector<by2::extracted_frame>& frames = require(set, "set").frames.frames;
+        by2_extracted_frame& result = require(out, "out");
+        if (index >= frames.size())
+        {
+            throw by2::error(
+                by2::error_code::invalid_argument,
+                fmt::format("frame {} is out of range for {} frames", index, frames.size()));
+        }
+        const by2::extracted_frame& f = frames[index];
+        result.index = f.index;
+        result.pts = f.pts;
+        result.time = f.time;
+        result.key_frame = f.key_frame ? 1 : 0;
+        result.scene_score = f.scene_score;
+        result.data = f.pixels.empty() ? nullptr : f.pixels.data();
+        result.size = f.pixels.size();
+        result.path = f.path.empty() ? nullptr : f.path.c_str();
+    });
+}
+
+int32_t by2_encode_image(
+    const uint8_t* frame,
+    size_t size,
+    int32_t width,
+    int32_t height,
+    int32_t pixel_format,
+    int32_t image_format,
+    int32_t quality,
+    uint8_t* out,
+    size_t capacity,
+    size_t* out_size)
+{
+    return by2::detail::call_with_status([&] {
+        const std::span<const std::uint8_t> in(require(frame, size, "frame"), size);
+        std::uint8_t* bytes = require(out, capacity, "out");
+        size_t& written = require(out_size, "out_size");
+        const std::vector<std::uint8_t> image = by2::encode_image(
+            in,
+            width,
+            height,
+            static_cast<by2::pixel_format>(pixel_format),
+            static_cast<by2::image_format>(image_format),
+            quality);
+        written = image.size();
+        if (image.size() <= capacity)
+        {
+            std::copy(image.begin(), image.end(), bytes);
+        }
+    });
+}
@@ -0,0 +1,226 @@
+#include "by2/by2.h"
+#include "status.hpp"
+#include <ccore/occupancy_map.hpp>
+
+#include <algorithm>
+#include <array>
+#include <optional>
+#include <span>
+#include <vector>
+
+static_assert(BY2_OCTREE_BINARY == static_cast<int32_t>(by2::octree_format::binary));
+static_assert(BY2_OCTREE_FULL == static_cast<int32_t>(by2::octree_format::full));
+
+static_assert(BY2_OCTREE_RAY_OCCUPIED == static_cast<int32_t>(by2::ray_result::occupied));
+static_assert(BY2_OCTREE_RAY_UNKNOWN == static_cast<int32_t>(by2::ray_result::unknown));
+static_assert(BY2_OCTREE_RAY_CLEAR == static_cast<int32_t>(by2::ray_result::clear));
+static_assert(BY2_OCTREE_LEAVES_OCCUPIED == static_cast<int32_t>(by2::leaf_filter::occupied));
+static_assert(BY2_OCTREE_LEAVES_FREE == static_cast<int32_t>(by2::leaf_filter::free));
+static_assert(BY2_OCTREE_LEAVES_KNOWN == static_cast<int32_t>(by2::leaf_filter::known));
+
+struct by2_octree
+{
+    by2::occupancy_map map;
+};
+
+struct by2_octree_leaves
+{
+    by2::leaf_cursor cursor;
+};
+
+namespace
+{
+    using by2::detail::require;
+    using by2::detail::to_core;
+
+    by2_vec3 to_c(const by2::vec3& v)
+    {
+        return {v.x, v.y, v.z};
+    }
+
+    by2_octree_leaf to_c(const by2::octree_leaf& leaf)
+    {
+        return {to_c(leaf.center), leaf.size, leaf.probability};
+    }
+}
+
+int32_t by2_octree_create(double resolution, by2_octree** out)
+{
+    return by2::detail::call_with_status([&] {
+        by2_octree*& result = require(out, "out");
+        result = new by2_octree{by2::occupancy_map(resolution)};
+    });
+}
+
+void by2_octree_destroy(by2_octree* tree)
+{
+    delete tree;
+}
+
+double by2_octree_resolution(const by2_octree* tree)
+{
+    return tree == nullptr ? 0.0 : tree->map.resolution();
+}
+
+size_t by2_octree_leaf_count(const by2_octree* tree)
+{
+    return tree == nullptr ? 0 : tree->map.leaf_count();
+}
+
+int32_t by2_octree_insert_point_cloud(
+    by2_octree* tree,
+    const by2_point_cloud* cloud,
+    const by2_vec3* origin,
+    double max_range)
+{
+    return by2::detail::call_with_status([&] {
+        by2::occupancy_map& map = require(tree, "tree").map;
+        const by2_point_cloud& c = require(cloud, "cloud");
+        map.insert_point_cloud(to_core(c), to_core(require(origin, "origin")), max_range);
+    });
+}
+
+int32_t by2_octree_occupancy(const by2_octree* tree, const by2_vec3* point, double* probability, int32_t* known)
+{
+    return by2::detail::call_with_status([&] {
+        const by2::occupancy_map& map = require(tree, "tree").map;
+        const by2::vec3 p = to_core(require(point, "point"));
+        double& out = require(probability, "probability");
+        int32_t& is_known = require(known, "known");
+        const std::optional<double> found = map.occupancy(p);
+        is_known = found.has_value() ? 1 : 0;
+        if (found)
+        {
+            out = *found;
+        }
+    });
+}
+
+int32_t by2_octree_cast_ray(
+    const by2_octree* tree,
+    const by2_vec3* origin,
+    const by2_vec3* direction,
+    double max_range,
+    int32_t ignore_unknown,
+    by2_octree_ray_hit* out)
+{
+    return by2::detail::call_with_status([&] {
+        const by2::occupancy_map& map = require(tree, "tree").map;
+        const by2::vec3 o = to_core(require(origin, "origin"));
+        const by2::vec3 d = to_core(require(direction, "direction"));
+        by2_octree_ray_hit& result = require(out, "out");
+        const by2::ray_hit hit = map.cast_ray(o, d, max_range, ignore_unknown != 0);
+        result = {static_cast<int32_t>(hit.result), to_c(hit.voxel)};
+    });
+}
+
+int32_t by2_octree_line_of_sight(
+    const by2_octree* tree,
+    const by2_vec3* from,
+    const by2_vec3* to,
+    int32_t ignore_unknown,
+    int32_t* visible)
+{
+    return by2::detail::call_with_status([&] {
+        const by2::occupancy_map& map = require(tree, "tree").map;
+        const by2::vec3 a = to_core(require(from, "from"));
+        const by2::vec3 b = to_core(require(to, "to"));
+        int32_t& result = require(visible, "visible");
+        result = map.line_of_sight(a, b, ignore_unknown != 0) ? 1 : 0;
+    });
+}
+
+int32_t by2_octree_nearest_occupied(
+    const by2_octree* tree,
+    const by2_vec3* point,
+    double max_distance,
+    by2_octree_leaf* leaf,
+    double* distance,
+    int32_t* found)
+{
+    return by2::detail::call_with_status([&] {
+        const by2::occupancy_map& map = require(tree, "tree").map;
+        const by2::vec3 p = to_core(require(point, "point"));
+        by2_octree_leaf& out_leaf = require(leaf, "leaf");
+        double& out_distance = require(distance, "distance");
+        int32_t& is_found = require(found, "found");
+        const std::optional<by2::nearest_leaf> nearest = map.nearest_occupied(p, max_distance);
+        is_found = nearest.has_value() ? 1 : 0;
+        if (nearest)
+        {
+            out_leaf = to_c(nearest->leaf);
+            out_distance = nearest->distance;
+        }
+    });
+}
+
+int32_t by2_octree_leaves_create(
+    const by2_octree* tree,
+    const by2_vec3* min,
+    const by2_vec3* max,
+    int32_t filter,
+    by2_octree_leaves** out)
+{
+    return by2::detail::call_with_status([&] {
+        const by2::occupancy_map& map = require(tree, "tree").map;
+        const by2::vec3 lo = to_core(require(min, "min"));
+        const by2::vec3 hi = to_core(require(max, "max"));
+        by2_octree_leaves*& result = require(out, "out");
+        result = new by2_octree_leaves{map.leaves(lo, hi, static_cast<by2::leaf_filter>(filter))};
+    });
+}
+
+void by2_octree_leaves_destroy(by2_octree_leaves* leaves)
+{
+    delete leaves;
+}
+
+int32_t by2_octree_leaves_next(by2_octree_leaves* leaves, by2_octree_leaf* out, size_t capacity, size_t* out_count)
+{
+    return by2::detail::call_with_status([&] {
+        by2::leaf_cursor& cursor = require(leaves, "leaves").cursor;
+        by2_octree_leaf* dest = require(out, capacity, "out");
+        size_t& count = require(out_count, "out_count");
+        // Convert through a small buffer; by2_octree_leaf and octree_leaf
+        // need not share a layout.
+        std::array<by2::octree_leaf, 64> batch;
+        std::size_t written = 0;
+        while (written < capacity)
+        {
+            const std::size_t want = std::min(batch.size(), capacity - written);
+            const std::size_t got = cursor.next(std::span(batch.data(), want));
+            std::transform(batch.begin(), batch.begin() + got, dest + written, [](const by2::octree_leaf& leaf) {
+                return to_c(leaf);
+            });
+            written += got;
+            if (got < want)
+            {
+                break;
+            }
+        }
+        count = written;
+    });
+}
+
+int32_t by2_octree_serialize(const by2_octree* tree, int32_t format, uint8_t* out, size_t capacity, size_t* out_size)
+{
+    return by2::detail::call_with_status([&] {
+        const by2::occupancy_map& map = require(tree, "tree").map;
+        size_t& size = require(out_size, "out_size");
+        const std::vector<std::uint8_t> bytes = map.serialize(static_cast<by2::octree_format>(format));
+        if (bytes.size() <= capacity)
+        {
+            std::copy(bytes.begin(), bytes.end(), require(out, bytes.size(), "out"));
+        }
+        size = bytes.size();
+    });
+}
+
+int32_t by2_octree_deserialize(const uint8_t* data, size_t size, int32_t format, by2_octree** out)
+{
+    return by2::detail::call_with_status([&] {
+        by2_octree*& result = require(out, "out");
+        const std::span<const std::uint8_t> bytes(require(data, size, "data"), size);
+        result = new by2_octree{by2::occupancy_map::deserialize(bytes, static_cast<by2::octree_format>(format))};
+    });
+}
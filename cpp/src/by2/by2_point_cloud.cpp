@@ -0,0 +1,120 @@
+#include "by2/by2.h"
+#include "status.hpp"
+#include <ccore/point_cloud.hpp>
+
+#include <span>
+
+namespace
+{
+    using by2::detail::require;
+    using by2::detail::to_core;
+
+    by2_vec3 to_c(const by2::vec3& v)
+    {
+        return {v.x, v.y, v.z};
+    }
+
+    // A `count`-entry output column; null is only allowed when count is 0.
+    template <typename T>
+    std::span<T> column(T* data, std::size_t count, const char* name)
+    {
+        return {require(data, count, name), count};
+    }
+
+    // Runs an index-returning kernel on `cloud` and stores the count.
+    template <typename F>
+    int32_t select(const by2_point_cloud* cloud, size_t* inliers, size_t* inlier_count, F&& kernel)
+    {
+        return by2::detail::call_with_status([&] {
+            const by2::point_cloud_view view = to_core(require(cloud, "cloud"));
+            size_t& count = require(inlier_count, "inlier_count");
+            count = kernel(view, column(inliers, view.count, "inliers"));
+        });
+    }
+}
+
+int32_t by2_point_cloud_bounds(const by2_point_cloud* cloud, by2_aabb* out)
+{
+    return by2::detail::call_with_status([&] {
+        const by2::aabb box = by2::point_cloud_bounds(to_core(require(cloud, "cloud")));
+        require(out, "out") = {to_c(box.min), to_c(box.max)};
+    });
+}
+
+int32_t by2_point_cloud_voxel_downsample(
+    const by2_point_cloud* cloud,
+    double voxel_size,
+    const by2_point_cloud_buffers* out,
+    size_t* out_count)
+{
+    return by2::detail::call_with_status([&] {
+        const by2_point_cloud_buffers& b = require(out, "out");
+        const by2::point_cloud_buffers buffers{
+            b.capacity,
+            b.x,
+            b.y,
+            b.z,
+            b.normal_x,
+            b.normal_y,
+            b.normal_z,
+            b.red,
+            b.green,
+            b.blue,
+            b.intensity,
+        };
+        size_t& count = require(out_count, "out_count");
+        count = by2::voxel_downsample(to_core(require(cloud, "cloud")), voxel_size, buffers);
+    });
+}
+
+int32_t by2_point_cloud_statistical_inliers(
+    const by2_point_cloud* cloud,
+    size_t k,
+    double std_ratio,
+    size_t* inliers,
+    size_t* inlier_count)
+{
+    return select(cloud, inliers, inlier_count, [&](const by2::point_cloud_view& view, std::span<size_t> out) {
+        return by2::statistical_inliers(view, k, std_ratio, out);
+    });
+}
+
+int32_t by2_point_cloud_radius_inliers(
+    const by2_point_cloud* cloud,
+    double radius,
+    size_t min_neighbors,
+    size_t* inliers,
+    size_t* inlier_count)
+{
+    return select(cloud, inliers, inlier_count, [&](const by2::point_cloud_view& view, std::span<size_t> out) {
+        return by2::radius_inliers(view, radius, min_neighbors, out);
+    });
+}
+
+int32_t by2_point_cloud_crop(const by2_point_cloud* cloud, const by2_aabb* box, size_t* inliers, size_t* inlier_count)
+{
+    return select(cloud, inliers, inlier_count, [&](const by2::point_cloud_view& view, std::span<size_t> out) {
+        const by2_aabb& b = require(box, "box");
+        return by2::crop(view, {to_core(b.min), to_core(b.max)}, out);
+    });
+}
+
+int32_t by2_point_cloud_estimate_normals(
+    const by2_point_cloud* cloud,
+    size_t k,
+    const by2_vec3* viewpoint,
+    float* normal_x,
+    float* normal_y,
+    float* normal_z)
+{
+    return by2::detail::call_with_status([&] {
+        const by2::point_cloud_view view = to_core(require(cloud, "cloud"));
+        by2::estimate_normals(
+            view,
+            k,
+            to_core(require(viewpoint, "viewpoint")),
+            column(normal_x, view.count, "normal_x"),
+            column(normal_y, view.count, "normal_y"),
+            column(normal_z, view.count, "normal_z"));
+    });
+}
@@ -0,0 +1,86 @@
+#include "by2/by2.h"
+#include "status.hpp"
+#include <ccore/registration.hpp>
+
+#include <algorithm>
+
+static_assert(BY2_ICP_POINT_TO_POINT == static_cast<int32_t>(by2::icp_method::point_to_point));
+static_assert(BY2_ICP_POINT_TO_PLANE == static_cast<int32_t>(by2::icp_method::point_to_plane));
+static_assert(BY2_ICP_CONVERGED == static_cast<int32_t>(by2::icp_termination::converged));
+static_assert(BY2_ICP_MAX_ITERATIONS == static_cast<int32_t>(by2::icp_termination::max_iterations));
+static_assert(BY2_ICP_TOO_FEW_CORRESPONDENCES == static_cast<int32_t>(by2::icp_termination::too_few_correspondences));
+
+namespace
+{
+    using by2::detail::require;
+    using by2::detail::to_core;
+
+    by2_pose to_c(const by2::pose& p)
+    {
+        return {
+            {p.rotation.w, p.rotation.x, p.rotation.y, p.rotation.z},
+            {p.translation.x, p.translation.y, p.translation.z},
+        };
+    }
+
+    by2_icp_iteration to_c(const by2::icp_iteration& i)
+    {
+        return {i.fitness, i.rmse, i.correspondences};
+    }
+}
+
+void by2_icp_default_options(by2_icp_options* options)
+{
+    if (options == nullptr)
+    {
+        return;
+    }
+    const by2::icp_options defaults;
+    options->method = static_cast<int32_t>(defaults.method);
+    options->max_iterations = defaults.max_iterations;
+    options->max_correspondence_distance = defaults.max_correspondence_distance;
+    options->translation_tolerance = defaults.translation_tolerance;
+    options->rotation_tolerance = defaults.rotation_tolerance;
+    options->initial_pose = to_c(defaults.initial_pose);
+}
+
+int32_t by2_icp(
+    const by2_point_cloud* source,
+    const by2_point_cloud* target,
+    const by2_icp_options* options,
+    by2_icp_result* result,
+    by2_icp_iteration* history,
+    size_t history_capacity)
+{
+    return by2::detail::call_with_status([&] {
+        const by2_icp_options& o = require(options, "options");
+        by2_icp_result& out = require(result, "result");
+        if (history == nullptr && history_capacity > 0)
+        {
+            throw by2::error(by2::error_code::invalid_argument, "history is null");
+        }
+        const by2::icp_result core = by2::icp(
+            to_core(require(source, "source")),
+            to_core(require(target, "target")),
+            {
+                static_cast<by2::icp_method>(o.method),
+                o.max_iterations,
+                o.max_correspondence_distance,
+                o.translation_tolerance,
+                o.rotation_tolerance,
+                to_core(o.initial_pose),
+            });
+
+        const std::size_t kept = std::min(core.history.size(), history_capacity);
+        std::ranges::transform(
+            core.history.begin(),
+            core.history.begin() + static_cast<std::ptrdiff_t>(kept),
+            history,
+            [](const by2::icp_iteration& i) { return to_c(i); });
+        out.transform = to_c(core.transform);
+        out.alignment = to_c(core.alignment);
+        out.converged = core.converged ? 1 : 0;
+        out.termination = static_cast<int32_t>(core.termination);
+        out.iterations = core.history.size();
+    });
+}
@@ -0,0 +1,102 @@
+#pragma once
+
+// Private helpers that turn ccore exceptions into by2_status codes, check
+// pointer arguments and convert the C structs to ccore types. Not installed.
+
+#include "by2/by2.h"
+#include <ccore/error.hpp>
+#include <ccore/geometry.hpp>
+#include <ccore/point_cloud.hpp>
+
+#include <spdlog/fmt/fmt.h>
+
+#include <cstddef>
+#include <exception>
+#include <string>
+
+namespace by2::detail
+{
+    // Stores the message returned by by2_last_error_message() for this thread.
+    void set_last_error(std::string message);
+
+    // Runs `f` and maps whatever it throws to a by2_status. Every C entry
+    // point that can fail goes through here so no exception crosses the ABI.
+    template <typename F>
+    int32_t call_with_status(F&& f) noexcept
+    {
+        try
+        {
+            f();
+            return BY2_STATUS_OK;
+        }
+        catch (const by2::error& e)
+        {
+            set_last_error(e.what());
+            return static_cast<int32_t>(e.code());
+        }
+        catch (const std::exception& e)
+        {
+            set_last_error(e.what());
+            return BY2_STATUS_INTERNAL;
+        }
+        catch (...)
+        {
+            set_last_error("unknown error");
+            return BY2_STATUS_INTERNAL;
+        }
+    }
+
+    // `*p`, or invalid_argument naming the argument if it is null.
+    template <typename T>
+    T& require(T* p, const char* name)
+    {
+        if (p == nullptr)
+        {
+            throw error(error_code::invalid_argument, fmt::format("{} is null", name));
+        }
+        return *p;
+    }
+
+    // Like require, for arrays that may be null when empty.
+    template <typename T>
+    T* require(T* p, std::size_t count, const char* name)
+    {
+        if (p == nullptr && count > 0)
+        {
+            throw error(error_code::invalid_argument, fmt::format("{} is null", name));
+        }
+        return p;
+    }
+
+    inline point_cloud_view to_core(const by2_point_cloud& c)
+    {
+        return {
+            c.count,
+            c.x,
+            c.y,
+            c.z,
+            c.normal_x,
+            c.normal_y,
+            c.normal_z,
+            c.red,
+            c.green,
+            c.blue,
+            c.intensity,
+        };
+    }
+
+    inline vec3 to_core(const by2_vec3& v)
+    {
+        return {v.x, v.y, v.z};
+    }
+
+    inline quat to_core(const by2_quat& q)
+    {
+        return {q.w, q.x, q.y, q.z};
+    }
+
+    inline pose to_core(const by2_pose& p)
+    {
+        return {to_core(p.rotation), to_core(p.translation)};
+    }
+}
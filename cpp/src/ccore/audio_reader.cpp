@@ -0,0 +1,244 @@
+#include "ccore/audio_reader.hpp"
+#include "ccore/error.hpp"
+#include "ffmpeg.hpp"
+
+#include <spdlog/fmt/fmt.h>
+
+#include <algorithm>
+#include <array>
+#include <cmath>
+#include <limits>
+#include <new>
+#include <vector>
+
+namespace by2
+{
+    namespace
+    {
+        AVSampleFormat to_av(sample_format format)
+        {
+            switch (format)
+            {
+            case sample_format::f32:
+                return AV_SAMPLE_FMT_FLT;
+            case sample_format::f32_planar:
+                return AV_SAMPLE_FMT_FLTP;
+            }
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("audio_reader: unknown sample format {}", static_cast<int32_t>(format)));
+        }
+    }
+
+    struct audio_reader::impl
+    {
+        detail::av_ptr<AVFormatContext> input;
+        detail::av_ptr<AVCodecContext> decoder;
+        detail::av_ptr<AVPacket> packet;
+        detail::av_ptr<AVFrame> frame;
+        detail::av_ptr<SwrContext> resampler;
+        std::vector<stream_info> streams;
+        int stream = -1;
+        sample_format format = sample_format::f32;
+        AVChannelLayout layout{};
+        std::string layout_name;
+        int32_t sample_rate = 0;
+        bool draining = false;
+        // Set once the resampler has put out what it held back.
+        bool done = false;
+        // Time of the first decoded sample, NaN before it.
+        double start = std::numeric_limits<double>::quiet_NaN();
+        int64_t position = 0;
+        std::vector<float> samples;
+
+        impl() = default;
+        impl(const impl&) = delete;
+        impl& operator=(const impl&) = delete;
+
+        ~impl()
+        {
+            av_channel_layout_uninit(&layout);
+        }
+
+        // Resamples `count` samples per channel of `in`, or drains the
+        // resampler for a null `in`, into `samples`. Returns the samples per
+        // channel put out.
+        int64_t resample(const std::uint8_t** in, int count)
+        {
+            const int capacity = detail::check_av(
+                swr_get_out_samples(resampler.get(), count), "audio_reader: resampling failed");
+            const std::size_t channels = static_cast<std::size_t>(layout.nb_channels);
+            const std::size_t plane = static_cast<std::size_t>(capacity);
+            samples.resize(std::max<std::size_t>(plane * channels, 1));
+
+            // One plane per channel, `capacity` samples apart, or a single
+            // interleaved one.
+            const std::size_t plane_count = format == sample_format::f32_planar ? channels : 1;
+            std::vector<std::uint8_t*> out(plane_count);
+            for (std::size_t c = 0; c < plane_count; ++c)
+            {
+                out[c] = reinterpret_cast<std::uint8_t*>(samples.data() + c * plane);
+            }
+            const int converted = detail::check_av(
+                swr_convert(resampler.get(), out.data(), capacity, in, count), "audio_reader: resampling failed");
+
+            // Close the gaps between planes.
+            const std::size_t n = static_cast<std::size_t>(converted);
+            for (std::size_t c = 1; c < plane_count && n < plane; ++c)
+            {
+                const float* from = samples.data() + c * plane;
+                std::copy(from, from + n, samples.data() + c * n);
+            }
+            return converted;
+        }
+    };
+
+    audio_reader::audio_reader(const std::string& path, const audio_reader_options& options)
+        : m_impl(std::make_unique<impl>())
+    {
+        impl& s = *m_impl;
+        const AVSampleFormat output_format = to_av(options.format);
+        s.format = options.format;
+        if (options.sample_rate < 0)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("audio_reader: bad sample rate {}", options.sample_rate));
+        }
+        if (!options.channel_layout.empty()
+            && av_channel_layout_from_string(&s.layout, options.channel_layout.c_str()) < 0)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("audio_reader: unknown channel layout {}", options.channel_layout));
+        }
+
+        s.input = detail::open_input(path);
+        s.streams = detail::describe_streams(*s.input);
+        const AVCodec* decoder = nullptr;
+        s.stream = av_find_best_stream(s.input.get(), AVMEDIA_TYPE_AUDIO, -1, -1, &decoder, 0);
+        if (s.stream < 0 || decoder == nullptr)
+        {
+            throw error(error_code::io, fmt::format("audio_reader: {} has no decodable audio stream", path));
+        }
+        s.decoder = detail::open_decoder(*s.input->streams[s.stream], *decoder);
+        AVCodecContext& d = *s.decoder;
+        if (d.sample_rate <= 0 || d.ch_layout.nb_channels <= 0)
+        {
+            throw error(
+                error_code::io,
+                fmt::format("audio_reader: the audio stream of {} has no sample rate or channels", path));
+        }
+        // Some containers only know the channel count.
+        if (d.ch_layout.order == AV_CHANNEL_ORDER_UNSPEC)
+        {
+            const int channels = d.ch_layout.nb_channels;
+            av_channel_layout_uninit(&d.ch_layout);
+            av_channel_layout_default(&d.ch_layout, channels);
+        }
+        if (options.channel_layout.empty())
+        {
+            detail::check_av(av_channel_layout_copy(&s.layout, &d.ch_layout), "audio_reader: bad channel layout");
+        }
+        s.sample_rate = options.sample_rate == 0 ? d.sample_rate : options.sample_rate;
+        std::array<char, 64> name{};
+        if (av_channel_layout_describe(&s.layout, name.data(), name.size()) > 0)
+        {
+            s.layout_name = name.data();
+        }
+
+        SwrContext* resampler = nullptr;
+        detail::check_av(
+            swr_alloc_set_opts2(
+                &resampler,
+                &s.layout,
+                output_format,
+                s.sample_rate,
+                &d.ch_layout,
+                d.sample_fmt,
+                d.sample_rate,
+                0,
+                nullptr),
+            "audio_reader: can't set up resampling");
+        s.resampler.reset(resampler);
+        detail::check_av(swr_init(resampler), "audio_reader: can't set up resampling");
+
+        s.packet.reset(av_packet_alloc());
+        s.frame.reset(av_frame_alloc());
+        if (!s.packet || !s.frame)
+        {
+            throw std::bad_alloc();
+        }
+    }
+
+    audio_reader::~audio_reader() = default;
+    audio_reader::audio_reader(audio_reader&&) noexcept = default;
+    audio_reader& audio_reader::operator=(audio_reader&&) noexcept = default;
+
+    std::span<const stream_info> audio_reader::streams() const noexcept
+    {
+        return m_impl->streams;
+    }
+
+    std::size_t audio_reader::audio_stream() const noexcept
+    {
+        return static_cast<std::size_t>(m_impl->stream);
+    }
+
+    int32_t audio_reader::sample_rate() const noexcept
+    {
+        return m_impl->sample_rate;
+    }
+
+    int32_t audio_reader::channels() const noexcept
+    {
+        return m_impl->layout.nb_channels;
+    }
+
+    const std::string& audio_reader::channel_layout() const noexcept
+    {
+        return m_impl->layout_name;
+    }
+
+    sample_format audio_reader::format() const noexcept
+    {
+        return m_impl->format;
+    }
+
+    std::optional<audio_chunk> audio_reader::next()
+    {
+        impl& s = *m_impl;
+        while (!s.done)
+        {
+            int64_t converted = 0;
+            if (detail::decode_next(*s.input, s.stream, *s.decoder, *s.packet, *s.frame, s.draining))
+            {
+                if (std::isnan(s.start))
+                {
+                    const int64_t pts = s.frame->best_effort_timestamp;
+                    const AVRational time_base = s.input->streams[s.stream]->time_base;
+                    s.start = pts == AV_NOPTS_VALUE ? 0.0 : static_cast<double>(pts) * av_q2d(time_base);
+                }
+                // FFmpeg 6 takes the input as non-const pointers to const.
+                converted = s.resample(const_cast<const std::uint8_t**>(s.frame->extended_data), s.frame->nb_samples);
+                av_frame_unref(s.frame.get());
+            }
+            else
+            {
+                converted = s.resample(nullptr, 0);
+                s.done = true;
+            }
+            if (converted > 0)
+            {
+                audio_chunk chunk{};
+                chunk.data = std::span<const float>(s.samples.data(), static_cast<std::size_t>(converted * channels()));
+                chunk.samples = converted;
+                chunk.position = s.position;
+                chunk.time = (std::isnan(s.start) ? 0.0 : s.start) + static_cast<double>(s.position) / s.sample_rate;
+                s.position += converted;
+                return chunk;
+            }
+        }
+        return std::nullopt;
+    }
+}
@@ -1,4 +1,7 @@
 #include "ccore/ccore.hpp"
+#include "ccore/error.hpp"
+
+#include <spdlog/fmt/fmt.h>
 #include <spdlog/spdlog.h>
 
 namespace by2
@@ -9,4 +12,26 @@ namespace by2
         spdlog::info("adding {} + {} = {}", a, b, c);
         return c;
     }
+
+    void ccore_add_batch(std::span<const int32_t> a, std::span<const int32_t> b, std::span<int32_t> out)
+    {
+        spdlog::info("adding batch of {} elements", out.size());
+        for (std::size_t i = 0; i < out.size(); ++i)
+        {
+            out[i] = a[i] + b[i];
+        }
+    }
+
+    void ccore_set_log_level(log_level level)
+    {
+        // spdlog indexes its level names with the value, so anything outside
+        // the enum would read past them.
+        if (level < log_level::trace || level > log_level::off)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("set_log_level: unknown level {}", static_cast<int32_t>(level)));
+        }
+        spdlog::set_level(static_cast<spdlog::level::level_enum>(level));
+    }
 }
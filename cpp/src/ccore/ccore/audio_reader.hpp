@@ -0,0 +1,89 @@
+#pragma once
+
+#include "ccore/media.hpp"
+
+#include <cstddef>
+#include <cstdint>
+#include <memory>
+#include <optional>
+#include <span>
+#include <string>
+
+namespace by2
+{
+    // Layouts of decoded audio. Samples are 32-bit floats in [-1, 1].
+    enum class sample_format : int32_t
+    {
+        // The channels of each sample follow each other.
+        f32 = 0,
+        // One plane per channel, each holding all of the chunk's samples.
+        f32_planar = 1,
+    };
+
+    struct audio_reader_options
+    {
+        // Samples per second per channel; 0 keeps the stream's rate.
+        int32_t sample_rate = 0;
+        // FFmpeg's name for the channel layout, e.g. "mono", "stereo" or
+        // "5.1"; empty keeps the stream's.
+        std::string channel_layout;
+        sample_format format = sample_format::f32;
+    };
+
+    struct audio_chunk
+    {
+        // channels() * samples floats in the reader's format.
+        std::span<const float> data;
+        // Samples per channel.
+        int64_t samples;
+        // Samples per channel before this chunk, counted from the first
+        // decoded one.
+        int64_t position;
+        // Time of the first sample in seconds: the stream's first timestamp
+        // plus position / sample_rate().
+        double time;
+    };
+
+    // Decodes a file's audio stream and resamples it with libswresample to
+    // a given rate, channel layout and float format, in chunks of about one
+    // decoded frame each.
+    //
+    // Only available when built with BY2_WITH_MEDIA.
+    class audio_reader
+    {
+    public:
+        // Opens `path` and picks its best audio stream (FFmpeg's choice).
+        // Throws error_code::invalid_argument for bad options, and
+        // error_code::io if the file can't be opened or read or has no
+        // decodable audio stream.
+        audio_reader(const std::string& path, const audio_reader_options& options);
+        ~audio_reader();
+
+        audio_reader(audio_reader&&) noexcept;
+        audio_reader& operator=(audio_reader&&) noexcept;
+
+        // Every stream of the file, audio or not.
+        std::span<const stream_info> streams() const noexcept;
+
+        // Index in streams() of the stream samples are decoded from.
+        std::size_t audio_stream() const noexcept;
+
+        // Of the output.
+        int32_t sample_rate() const noexcept;
+        int32_t channels() const noexcept;
+        // FFmpeg's description of the output channels, e.g. "stereo".
+        const std::string& channel_layout() const noexcept;
+        sample_format format() const noexcept;
+
+        // Decodes and resamples the next chunk, or returns nullopt once the
+        // stream and the resampler are drained. The chunk's data stays valid
+        // until the next call. Throws error_code::io if reading, decoding or
+        // resampling fails.
+        std::optional<audio_chunk> next();
+
+    private:
+        struct impl;
+
+        std::unique_ptr<impl> m_impl;
+    };
+}
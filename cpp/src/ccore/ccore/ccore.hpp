@@ -1,8 +1,28 @@
 #pragma once
 
 #include <cstdint>
+#include <span>
 
 namespace by2
 {
+    enum class log_level : int32_t
+    {
+        trace = 0,
+        debug = 1,
+        info = 2,
+        warn = 3,
+        error = 4,
+        critical = 5,
+        off = 6,
+    };
+
     int32_t ccore_add(int32_t a, int32_t b);
+
+    // Element-wise add of two equally sized buffers into `out`.
+    // All three spans are expected to have the same size.
+    void ccore_add_batch(std::span<const int32_t> a, std::span<const int32_t> b, std::span<int32_t> out);
+
+    // Throws error_code::invalid_argument if `level` is not one of
+    // log_level's values.
+    void ccore_set_log_level(log_level level);
 }
@@ -0,0 +1,40 @@
+#pragma once
+
+#include <cstdint>
+#include <stdexcept>
+#include <string>
+
+namespace by2
+{
+    // Error categories reported by ccore. The numeric values are part of the
+    // C ABI: by2.h exposes the same values as `by2_status`.
+    enum class error_code : int32_t
+    {
+        invalid_argument = 1,
+        singular = 2,
+        not_converged = 3,
+        internal = 4,
+        callback_failed = 5,
+        io = 6,
+    };
+
+    // Exception thrown by ccore functions. The by2 C layer catches it and
+    // turns it into a status code plus a per-thread error message.
+    class error : public std::runtime_error
+    {
+    public:
+        error(error_code code, const std::string& message)
+            : std::runtime_error(message)
+            , m_code(code)
+        {
+        }
+
+        error_code code() const noexcept
+        {
+            return m_code;
+        }
+
+    private:
+        error_code m_code;
+    };
+}
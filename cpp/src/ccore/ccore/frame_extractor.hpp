@@ -0,0 +1,113 @@
+#pragma once
+
+#include "ccore/media.hpp"
+
+#include <cstddef>
+#include <cstdint>
+#include <span>
+#include <string>
+#include <vector>
+
+namespace by2
+{
+    // Which frames extract_frames picks.
+    enum class frame_selection : int32_t
+    {
+        // The first frame at or after each multiple of `interval` seconds,
+        // counted from the first frame.
+        interval = 0,
+        // Frames 0, step, 2 * step, ...
+        every_nth = 1,
+        // The first frame, then every frame whose scene_score reaches
+        // `scene_threshold`.
+        scene_change = 2,
+    };
+
+    enum class image_format : int32_t
+    {
+        png = 0,
+        jpeg = 1,
+    };
+
+    struct frame_extractor_options
+    {
+        frame_selection selection = frame_selection::interval;
+        // Seconds between frames, for frame_selection::interval.
+        double interval = 1.0;
+        // Frames between frames, for frame_selection::every_nth.
+        int64_t step = 1;
+        // In (0, 1], for frame_selection::scene_change.
+        double scene_threshold = 0.3;
+        // Size of the extracted frames. 0 for both keeps the video's, 0 for
+        // one of them keeps its aspect ratio.
+        int32_t width = 0;
+        int32_t height = 0;
+        // Parts of the file decoded in parallel, each by its own reader, from
+        // 0 to 1024. 0 picks one per hardware thread, but none shorter than
+        // 10 s. Either way there are no more than hardware threads or frames
+        // to pick, and the readers split the threads between them.
+        int32_t segments = 0;
+        // If not empty, each frame is encoded as `image_format` and written
+        // there, as frame_<index>.png or .jpg, instead of being kept in
+        // memory. The directory is created if needed.
+        std::string directory;
+        image_format format = image_format::png;
+        // JPEG quality from 1 to 100; PNG is lossless and ignores it.
+        int32_t quality = 90;
+    };
+
+    struct extracted_frame
+    {
+        // Position of the frame in the stream, from 0.
+        int64_t index;
+        // Presentation timestamp in the stream's time base, INT64_MIN if the
+        // frame has none.
+        int64_t pts;
+        // In seconds, NaN without a timestamp or frame rate to derive it
+        // from.
+        double time;
+        bool key_frame;
+        // Mean absolute difference in [0, 1] between the luma of this frame
+        // and the one before it. NaN for the first frame and outside
+        // frame_selection::scene_change.
+        double scene_score;
+        // Packed RGB24, empty if the frame was written to `path`.
+        std::vector<std::uint8_t> pixels;
+        std::string path;
+    };
+
+    struct extracted_frames
+    {
+        int32_t width;
+        int32_t height;
+        // In stream order.
+        std::vector<extracted_frame> frames;
+    };
+
+    // Decodes the best video stream of `path` and picks frames as `options`
+    // say, scaled to the requested size. With more than one segment, each
+    // covers a time range of the file and seeks to it, so frames are picked
+    // as if the file were decoded in one go; every_nth then builds an index
+    // first to number frames exactly.
+    //
+    // Frames without a timestamp are never picked by interval. Throws
+    // error_code::invalid_argument for bad options, and error_code::io if
+    // the file can't be opened or decoded or an image can't be written.
+    //
+    // Only available when built with BY2_WITH_MEDIA.
+    extracted_frames extract_frames(const std::string& path, const frame_extractor_options& options);
+
+    // Encodes a packed `format` frame as a PNG or JPEG image with FFmpeg's
+    // image encoders. Throws error_code::invalid_argument for a frame of the
+    // wrong size, an unknown format or a quality outside 1 to 100, and
+    // error_code::io if encoding fails.
+    //
+    // Only available when built with BY2_WITH_MEDIA.
+    std::vector<std::uint8_t> encode_image(
+        std::span<const std::uint8_t> frame,
+        int32_t width,
+        int32_t height,
+        pixel_format format,
+        image_format image,
+        int32_t quality);
+}
@@ -0,0 +1,78 @@
+#pragma once
+
+#include <array>
+#include <span>
+
+namespace by2
+{
+    // Rigid-body geometry in double precision. Conventions:
+    //  - quaternions are Hamilton (w + xi + yj + zk) and rotate active,
+    //  - rotation matrices are row-major,
+    //  - euler angles are intrinsic z-y'-x'' (yaw, then pitch, then roll), so
+    //    R = Rz(yaw) * Ry(pitch) * Rx(roll),
+    //  - a pose maps p to rotation * p + translation.
+    //
+    // Quaternion arguments are normalized on entry; functions throw
+    // error_code::invalid_argument for zero or non-finite input.
+
+    struct vec3
+    {
+        double x;
+        double y;
+        double z;
+    };
+
+    struct quat
+    {
+        double w;
+        double x;
+        double y;
+        double z;
+    };
+
+    using mat3 = std::array<double, 9>;
+
+    struct euler_angles
+    {
+        double roll;
+        double pitch;
+        double yaw;
+    };
+
+    struct axis_angle
+    {
+        vec3 axis;
+        double angle;
+    };
+
+    struct pose
+    {
+        quat rotation;
+        vec3 translation;
+    };
+
+    // Throws unless `m` is orthonormal with determinant +1 (tolerance 1e-6).
+    quat quat_from_matrix(const mat3& m);
+    mat3 quat_to_matrix(const quat& q);
+
+    quat quat_from_euler(const euler_angles& e);
+    // pitch is in [-pi/2, pi/2]. At gimbal lock roll is reported as 0.
+    euler_angles quat_to_euler(const quat& q);
+
+    // `axis` does not need to be unit length but must not be zero.
+    quat quat_from_axis_angle(const axis_angle& a);
+    // angle is in [0, pi]; the identity gives axis (1, 0, 0).
+    axis_angle quat_to_axis_angle(const quat& q);
+
+    // a * b: rotates by b first, then by a.
+    quat quat_multiply(const quat& a, const quat& b);
+    quat quat_inverse(const quat& q);
+
+    // a * b: applies b first, then a.
+    pose pose_compose(const pose& a, const pose& b);
+    pose pose_inverse(const pose& p);
+
+    // Transforms packed xyz triples. `points` and `out` must have the same
+    // length, a multiple of 3, and may be the same buffer.
+    void pose_transform_points(const pose& p, std::span<const double> points, std::span<double> out);
+}
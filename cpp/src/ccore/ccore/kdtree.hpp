@@ -0,0 +1,63 @@
+#pragma once
+
+#include "ccore/point_cloud.hpp"
+
+#include <array>
+#include <cstddef>
+#include <utility>
+#include <vector>
+
+namespace by2
+{
+    // A neighbour found by a kdtree query: squared euclidean distance and the
+    // index of the point in the cloud the tree was built from.
+    using neighbor = std::pair<double, std::size_t>;
+
+    // Balanced 3-d tree over the finite points of a cloud. The coordinates
+    // are copied, so the tree doesn't borrow the cloud it was built from.
+    // Queries don't modify the tree and may run concurrently.
+    //
+    // Distances are computed in double precision from the float
+    // coordinates. Results are sorted by (distance, index), which also breaks
+    // ties between equidistant points deterministically.
+    class kdtree
+    {
+    public:
+        explicit kdtree(const point_cloud_view& cloud);
+
+        // Number of indexed (finite) points.
+        std::size_t size() const noexcept
+        {
+            return m_points.size();
+        }
+
+        // The `k` nearest points to `query`, or all of them when the tree has
+        // fewer. `out` is cleared first. Throws error_code::invalid_argument
+        // for a non-finite query.
+        void knn(const std::array<double, 3>& query, std::size_t k, std::vector<neighbor>& out) const;
+
+        // All points within `radius` of `query` (inclusive). `out` is
+        // cleared first. Throws error_code::invalid_argument for a non-finite
+        // query or a radius that is negative or not finite.
+        void radius(const std::array<double, 3>& query, double radius, std::vector<neighbor>& out) const;
+
+    private:
+        struct entry
+        {
+            std::array<float, 3> point;
+            std::size_t index;
+        };
+
+        // Subtree [lo, hi) is split at its middle entry along m_axes[mid].
+        void build(std::size_t lo, std::size_t hi);
+        void knn(std::size_t lo, std::size_t hi, const std::array<double, 3>& query, std::size_t k, std::vector<neighbor>& heap) const;
+        void radius(std::size_t lo, std::size_t hi, const std::array<double, 3>& query, double radius_sq, std::vector<neighbor>& out) const;
+
+        std::vector<entry> m_points;
+        std::vector<unsigned char> m_axes;
+    };
+
+    // Squared distance between a float point and a query, summed as
+    // (dx * dx + dy * dy) + dz * dz.
+    double squared_distance(const std::array<float, 3>& p, const std::array<double, 3>& q) noexcept;
+}
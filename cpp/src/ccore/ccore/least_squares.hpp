@@ -0,0 +1,78 @@
+#pragma once
+
+#include <cstddef>
+#include <cstdint>
+#include <functional>
+#include <span>
+
+namespace by2
+{
+    enum class lsq_method : int32_t
+    {
+        // Eigen's MINPACK port (unsupported/NonLinearOptimization).
+        levenberg_marquardt = 0,
+        // Undamped Gauss-Newton steps solved by column-pivoting QR. Cheaper
+        // per iteration but needs a good initial guess.
+        gauss_newton = 1,
+    };
+
+    // Why a solve stopped. The first three count as converged.
+    enum class lsq_termination : int32_t
+    {
+        function_tolerance = 0,
+        parameter_tolerance = 1,
+        gradient_tolerance = 2,
+        max_iterations = 3,
+        // The tolerances are too small for any further progress; the
+        // parameters are the best the solver found.
+        tolerance_too_small = 4,
+    };
+
+    // The defaults match Eigen's LevenbergMarquardt parameters.
+    struct lsq_options
+    {
+        lsq_method method = lsq_method::levenberg_marquardt;
+        std::size_t max_iterations = 100;
+        // Relative reduction of the cost.
+        double function_tolerance = 1.49012e-08;
+        // Relative change of the parameters.
+        double parameter_tolerance = 1.49012e-08;
+        // Max norm of the gradient (levenberg_marquardt: cosine between the
+        // residuals and the jacobian columns).
+        double gradient_tolerance = 0.0;
+    };
+
+    struct lsq_summary
+    {
+        bool converged;
+        lsq_termination termination;
+        // 0.5 * ||residuals||^2 at the initial and final parameters.
+        double initial_cost;
+        double final_cost;
+        std::size_t iterations;
+        std::size_t residual_evaluations;
+        std::size_t jacobian_evaluations;
+    };
+
+    // Writes the residuals for `params`. Returns false to abort the solve.
+    using lsq_residual_function = std::function<bool(std::span<const double> params, std::span<double> residuals)>;
+
+    // Writes the row-major (residual_count x param_count) jacobian, i.e.
+    // jacobian[i * param_count + j] = d residual_i / d param_j. Returns false
+    // to abort the solve.
+    using lsq_jacobian_function = std::function<bool(std::span<const double> params, std::span<double> jacobian)>;
+
+    // Minimizes 0.5 * ||residual(params)||^2 starting from `params`, which
+    // holds the solution on return. Without a jacobian it is approximated by
+    // forward differences. Needs residual_count >= params.size() >= 1.
+    //
+    // Throws error_code::callback_failed when a callback returns false,
+    // invalid_argument for bad options or non-finite residuals, and
+    // singular when gauss_newton meets a rank deficient jacobian.
+    lsq_summary least_squares_solve(
+        const lsq_residual_function& residual,
+        const lsq_jacobian_function& jacobian,
+        std::span<double> params,
+        std::size_t residual_count,
+        const lsq_options& options);
+}
@@ -0,0 +1,56 @@
+#pragma once
+
+#include <cstddef>
+#include <cstdint>
+
+namespace by2
+{
+    // Non-owning view of a dense matrix. Element (r, c) lives at
+    // data[r * row_stride + c * col_stride], strides counted in elements, so
+    // row-major, column-major and sub-block layouts can all be described.
+    template <typename T>
+    struct matrix_view
+    {
+        T* data;
+        std::size_t rows;
+        std::size_t cols;
+        std::ptrdiff_t row_stride;
+        std::ptrdiff_t col_stride;
+    };
+
+    enum class solve_method : int32_t
+    {
+        lu = 0,
+        qr = 1,
+        cholesky = 2,
+    };
+
+    // All functions below validate the shapes of their arguments and throw
+    // by2::error on failure. Outputs must be preallocated with the documented
+    // shape. Instantiated for float and double.
+
+    // out (m x n) = a (m x k) * b (k x n)
+    template <typename T>
+    void matrix_multiply(matrix_view<const T> a, matrix_view<const T> b, matrix_view<T> out);
+
+    // out (n x n) = a^-1. Throws error_code::singular if `a` is not invertible.
+    template <typename T>
+    void matrix_inverse(matrix_view<const T> a, matrix_view<T> out);
+
+    // Solves a (m x n) * x (n x k) = b (m x k).
+    // lu and cholesky need a square `a` (cholesky: symmetric positive definite).
+    // qr also accepts m > n and returns the least-squares solution.
+    template <typename T>
+    void matrix_solve(solve_method method, matrix_view<const T> a, matrix_view<const T> b, matrix_view<T> x);
+
+    // Thin SVD of a (m x n), p = min(m, n): a = u (m x p) * diag(s) (p x 1) * v^T (n x p).
+    // Singular values are sorted in decreasing order.
+    template <typename T>
+    void matrix_svd(matrix_view<const T> a, matrix_view<T> u, matrix_view<T> s, matrix_view<T> v);
+
+    // Eigendecomposition of a symmetric a (n x n) = vectors * diag(values) * vectors^T.
+    // Only the lower triangle of `a` is read. Eigenvalues (n x 1) are sorted
+    // in increasing order; the columns of `vectors` (n x n) are the eigenvectors.
+    template <typename T>
+    void matrix_eigen_symmetric(matrix_view<const T> a, matrix_view<T> values, matrix_view<T> vectors);
+}
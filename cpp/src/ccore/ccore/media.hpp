@@ -0,0 +1,96 @@
+#pragma once
+
+#include <cstddef>
+#include <cstdint>
+#include <string>
+#include <vector>
+
+namespace by2
+{
+    // Layouts of frames handed to and from the media module. Frames are
+    // tightly packed: rows follow each other without padding, and planes
+    // follow each other in the listed order.
+    enum class pixel_format : int32_t
+    {
+        // R, G, B bytes per pixel.
+        rgb24 = 0,
+        // R, G, B, A bytes per pixel.
+        rgba = 1,
+        // Y plane, then U and V planes of ceil(width / 2) x ceil(height / 2)
+        // samples.
+        yuv420p = 2,
+        // One luma byte per pixel.
+        gray8 = 3,
+        // B, G, R bytes per pixel.
+        bgr24 = 4,
+        // B, G, R, A bytes per pixel.
+        bgra = 5,
+        // Y plane, then a plane of ceil(width / 2) x ceil(height / 2)
+        // interleaved U, V pairs.
+        nv12 = 6,
+        // Y plane, then U and V planes of ceil(width / 2) x height samples.
+        yuv422p = 7,
+        // Y, U and V planes of width x height samples.
+        yuv444p = 8,
+    };
+
+    // Bytes of a packed `format` frame. Throws error_code::invalid_argument
+    // for an unknown format or a negative size.
+    std::size_t frame_size(pixel_format format, int32_t width, int32_t height);
+
+    enum class media_type : int32_t
+    {
+        video = 0,
+        audio = 1,
+        subtitle = 2,
+        data = 3,
+        unknown = 4,
+    };
+
+    struct rational
+    {
+        int32_t num;
+        int32_t den;
+    };
+
+    // A metadata entry of a file or stream, e.g. "title" or "language".
+    struct media_tag
+    {
+        std::string key;
+        std::string value;
+    };
+
+    // A stream of a media file, as its container describes it.
+    struct stream_info
+    {
+        // Position among the file's streams.
+        std::size_t index;
+        media_type type;
+        // FFmpeg's name for the codec, e.g. "h264".
+        std::string codec;
+        // Video only, 0 otherwise.
+        int32_t width;
+        int32_t height;
+        // Average frames per second; 0 if unknown or not video.
+        double frame_rate;
+        // FFmpeg's name for the layout of decoded frames, e.g. "yuv420p".
+        // Empty if unknown or not video.
+        std::string pixel_format;
+        // Audio only, 0 otherwise.
+        int32_t sample_rate;
+        int32_t channels;
+        // FFmpeg's description of the channels, e.g. "stereo" or "5.1".
+        // Empty if unknown or not audio.
+        std::string channel_layout;
+        // Bits per second, 0 if unknown.
+        int64_t bit_rate;
+        // Unit of the stream's timestamps, in seconds.
+        rational time_base;
+        // In seconds, NaN if unknown.
+        double duration;
+        // As stated by the container, 0 if it doesn't say.
+        int64_t frame_count;
+        // In the order the file lists them.
+        std::vector<media_tag> tags;
+    };
+}
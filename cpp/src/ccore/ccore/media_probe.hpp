@@ -0,0 +1,32 @@
+#pragma once
+
+#include "ccore/media.hpp"
+
+#include <cstdint>
+#include <string>
+#include <vector>
+
+namespace by2
+{
+    // What a media file holds, as its container describes it.
+    struct media_info
+    {
+        // FFmpeg's short name for the container, e.g. "matroska,webm".
+        std::string format;
+        // A human readable name, e.g. "Matroska / WebM".
+        std::string format_long_name;
+        // In seconds, NaN if unknown.
+        double duration;
+        // Bits per second of the whole file, 0 if unknown.
+        int64_t bit_rate;
+        // Metadata of the file; each stream has its own.
+        std::vector<media_tag> tags;
+        std::vector<stream_info> streams;
+    };
+
+    // Opens `path` and describes its container and streams without decoding
+    // them. Throws error_code::io if FFmpeg can't open or read it.
+    //
+    // Only available when built with BY2_WITH_MEDIA.
+    media_info probe_media(const std::string& path);
+}
@@ -0,0 +1,177 @@
+#pragma once
+
+#include "ccore/geometry.hpp"
+#include "ccore/point_cloud.hpp"
+
+#include <cstddef>
+#include <cstdint>
+#include <memory>
+#include <optional>
+#include <span>
+#include <vector>
+
+namespace by2
+{
+    // Serialized map formats, as octomap writes them.
+    enum class octree_format : int32_t
+    {
+        // `.bt`: compact, keeps only whether each known voxel is free or
+        // occupied. Reading it back sets voxels to the clamping bounds.
+        binary = 0,
+        // `.ot`: every node with its log-odds.
+        full = 1,
+    };
+
+    // Which leaves an occupancy_map::leaves cursor yields.
+    enum class leaf_filter : int32_t
+    {
+        occupied = 0,
+        free = 1,
+        // Occupied and free.
+        known = 2,
+    };
+
+    // A leaf of the map: a cube of edge length `size` (the resolution times
+    // a power of two) around `center`.
+    struct octree_leaf
+    {
+        vec3 center;
+        double size;
+        double probability;
+    };
+
+    // Where occupancy_map::cast_ray stopped.
+    enum class ray_result : int32_t
+    {
+        // At an occupied voxel.
+        occupied = 0,
+        // At an unknown voxel, unless unknown voxels were ignored.
+        unknown = 1,
+        // Past `max_range` or the edge of the mappable volume, having only
+        // crossed free (or ignored unknown) voxels.
+        clear = 2,
+    };
+
+    struct ray_hit
+    {
+        ray_result result;
+        // Center of the voxel the ray stopped at; for ray_result::clear, the
+        // last voxel it crossed.
+        vec3 voxel;
+    };
+
+    struct nearest_leaf
+    {
+        octree_leaf leaf;
+        // From the query point to the leaf's cube, 0 inside it.
+        double distance;
+    };
+
+    // Streams the leaves of a map that intersect a bounding box, in octree
+    // order. It reads the map it was created from, which must outlive it and
+    // stay unchanged while it is used.
+    class leaf_cursor
+    {
+    public:
+        ~leaf_cursor();
+
+        leaf_cursor(leaf_cursor&&) noexcept;
+        leaf_cursor& operator=(leaf_cursor&&) noexcept;
+
+        // Writes the next leaves to `out` and returns how many; fewer than
+        // out.size() once the cursor is exhausted.
+        std::size_t next(std::span<octree_leaf> out);
+
+    private:
+        friend class occupancy_map;
+        struct impl;
+
+        explicit leaf_cursor(std::unique_ptr<impl> state);
+
+        std::unique_ptr<impl> m_impl;
+    };
+
+    // Probabilistic 3-d occupancy grid on an octomap::OcTree. Every voxel
+    // starts unknown; inserting a scan marks the voxels along each sensor ray
+    // as more likely free and the voxel at its end point as more likely
+    // occupied, using octomap's default sensor model and clamping.
+    //
+    // Coordinates are limited to the volume an OcTree can address at the
+    // map's resolution: [-32768, 32768) voxels along each axis.
+    //
+    // Only available when built with BY2_WITH_MAPPING.
+    class occupancy_map
+    {
+    public:
+        // Throws error_code::invalid_argument unless `resolution` (the voxel
+        // edge length) is positive and the mappable volume it gives has a
+        // finite size.
+        explicit occupancy_map(double resolution);
+        ~occupancy_map();
+
+        occupancy_map(occupancy_map&&) noexcept;
+        occupancy_map& operator=(occupancy_map&&) noexcept;
+
+        double resolution() const noexcept;
+
+        // Number of leaves. Leaves cover the known voxels; children that all
+        // agree are merged into one bigger leaf.
+        std::size_t leaf_count() const noexcept;
+
+        // Casts a ray from `origin` to every finite point of `cloud`. With a
+        // finite `max_range`, rays are cut at that length and their end
+        // points are not marked occupied. Throws error_code::invalid_argument
+        // if `max_range` is not positive, or if `origin` or a (cut) end point
+        // lies outside the mappable volume.
+        void insert_point_cloud(const point_cloud_view& cloud, const vec3& origin, double max_range);
+
+        // Occupancy probability of the voxel containing `point`, or nullopt
+        // while it is unknown (including outside the mappable volume). Throws
+        // error_code::invalid_argument for a non-finite point.
+        std::optional<double> occupancy(const vec3& point) const;
+
+        // Walks the voxels along the ray from `origin` in `direction` up to
+        // `max_range` (positive, may be infinite) and stops at the first
+        // occupied voxel, or at the first unknown one unless
+        // `ignore_unknown`. The voxel holding `origin` is checked too. Throws
+        // error_code::invalid_argument if `origin` is not finite or outside
+        // the mappable volume, or `direction` is zero or not finite.
+        ray_hit cast_ray(const vec3& origin, const vec3& direction, double max_range, bool ignore_unknown) const;
+
+        // Whether every voxel the segment from `from` to `to` passes
+        // through, the voxels holding its end points included, is free (or
+        // unknown, if `ignore_unknown`). Throws error_code::invalid_argument
+        // if either point is not finite or outside the mappable volume.
+        bool line_of_sight(const vec3& from, const vec3& to, bool ignore_unknown) const;
+
+        // The occupied leaf closest to `point` within `max_distance`
+        // (non-negative, may be infinite), or nullopt. Ties go to the first
+        // leaf in octree order. Throws error_code::invalid_argument for a
+        // non-finite point or a bad `max_distance`.
+        std::optional<nearest_leaf> nearest_occupied(const vec3& point, double max_distance) const;
+
+        // A cursor over the leaves matching `filter` that intersect the box
+        // [min, max]. Bounds may be infinite; the box is clipped to the
+        // mappable volume. Throws error_code::invalid_argument for a NaN
+        // bound, min > max on some axis or an unknown filter.
+        leaf_cursor leaves(const vec3& min, const vec3& max, leaf_filter filter) const;
+
+        // The map as a `.bt` or `.ot` file would hold it. Throws
+        // error_code::invalid_argument for an unknown format.
+        std::vector<std::uint8_t> serialize(octree_format format) const;
+
+        // Reads a map written by serialize() or by octomap. octomap trusts
+        // its input, so the header and the node structure are checked first:
+        // throws error_code::invalid_argument for the other format, a
+        // malformed header, truncated or malformed node data, or a node count
+        // that differs from the header's.
+        static occupancy_map deserialize(std::span<const std::uint8_t> data, octree_format format);
+
+    private:
+        struct impl;
+
+        explicit occupancy_map(std::unique_ptr<impl> state);
+
+        std::unique_ptr<impl> m_impl;
+    };
+}
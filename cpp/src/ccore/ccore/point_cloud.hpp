@@ -0,0 +1,103 @@
+#pragma once
+
+#include "ccore/geometry.hpp"
+
+#include <cstddef>
+#include <cstdint>
+#include <span>
+
+namespace by2
+{
+    // Non-owning structure-of-arrays point cloud: every non-null column has
+    // `count` entries. x, y and z are required; the other columns are
+    // optional and null when absent.
+    struct point_cloud_view
+    {
+        std::size_t count;
+        const float* x;
+        const float* y;
+        const float* z;
+        const float* normal_x;
+        const float* normal_y;
+        const float* normal_z;
+        const std::uint8_t* red;
+        const std::uint8_t* green;
+        const std::uint8_t* blue;
+        const float* intensity;
+    };
+
+    // Output columns for kernels that produce a new cloud. Null columns are
+    // not written; every other column has room for `capacity` entries.
+    struct point_cloud_buffers
+    {
+        std::size_t capacity;
+        float* x;
+        float* y;
+        float* z;
+        float* normal_x;
+        float* normal_y;
+        float* normal_z;
+        std::uint8_t* red;
+        std::uint8_t* green;
+        std::uint8_t* blue;
+        float* intensity;
+    };
+
+    struct aabb
+    {
+        vec3 min;
+        vec3 max;
+    };
+
+    // Throws error_code::invalid_argument when a required column is null or
+    // only some of the normal or colour columns are set.
+    void validate(const point_cloud_view& cloud);
+
+    // Axis-aligned bounds of the points. NaN coordinates are skipped, so an
+    // axis with no finite or infinite value keeps min = +inf, max = -inf.
+    // Throws error_code::invalid_argument for an empty cloud.
+    aabb point_cloud_bounds(const point_cloud_view& cloud);
+
+    // The kernels below skip points with a non-finite coordinate: such points
+    // are never part of an output and never count as neighbours. They throw
+    // error_code::invalid_argument for invalid parameters.
+
+    // Replaces the points in each occupied voxel (the cube of side
+    // `voxel_size` at floor(p / voxel_size)) by their centroid. Normals,
+    // colours and intensity are averaged too; normals are not renormalized
+    // and colours are rounded. The output is ordered by voxel (x index, then
+    // y, then z) and written to the columns of `out` that are non-null and
+    // present in `cloud`. Needs out.capacity >= cloud.count. Returns the
+    // number of points written.
+    std::size_t voxel_downsample(const point_cloud_view& cloud, double voxel_size, const point_cloud_buffers& out);
+
+    // Statistical outlier removal: a point is an inlier when the mean
+    // distance to its `k` nearest neighbours is at most
+    // mean + std_ratio * stddev, both taken over all points (stddev with
+    // n - 1). Needs more than `k` finite points. Writes the inlier indices in
+    // ascending order and returns how many there are. `inliers` needs room
+    // for cloud.count entries.
+    std::size_t statistical_inliers(const point_cloud_view& cloud, std::size_t k, double std_ratio, std::span<std::size_t> inliers);
+
+    // Radius outlier removal: a point is an inlier when at least
+    // `min_neighbors` other points lie within `radius` of it. Output as for
+    // statistical_inliers.
+    std::size_t radius_inliers(const point_cloud_view& cloud, double radius, std::size_t min_neighbors, std::span<std::size_t> inliers);
+
+    // Indices of the points inside `box`, bounds included. Output as for
+    // statistical_inliers.
+    std::size_t crop(const point_cloud_view& cloud, const aabb& box, std::span<std::size_t> inliers);
+
+    // Unit normal of every point: the eigenvector of the smallest eigenvalue
+    // of the covariance of its `k` nearest neighbours (the point included),
+    // flipped to face `viewpoint`. Needs k >= 3. Points with a non-finite
+    // coordinate, or with fewer than 3 finite neighbours, get a NaN normal.
+    // Each output span needs cloud.count entries.
+    void estimate_normals(
+        const point_cloud_view& cloud,
+        std::size_t k,
+        const vec3& viewpoint,
+        std::span<float> normal_x,
+        std::span<float> normal_y,
+        std::span<float> normal_z);
+}
@@ -0,0 +1,78 @@
+#pragma once
+
+#include "ccore/geometry.hpp"
+#include "ccore/point_cloud.hpp"
+
+#include <cstddef>
+#include <cstdint>
+#include <limits>
+#include <vector>
+
+namespace by2
+{
+    enum class icp_method : int32_t
+    {
+        // Minimizes the distances between corresponding points (closed form,
+        // Umeyama without scaling).
+        point_to_point = 0,
+        // Minimizes the distances from source points to the tangent planes
+        // of their target points. Needs target normals; usually converges in
+        // fewer iterations on smooth surfaces.
+        point_to_plane = 1,
+    };
+
+    // Why the iterations stopped. Only `converged` counts as converged.
+    enum class icp_termination : int32_t
+    {
+        converged = 0,
+        max_iterations = 1,
+        // Fewer correspondences than the method needs to determine a pose
+        // (3 point-to-point, 6 point-to-plane).
+        too_few_correspondences = 2,
+    };
+
+    struct icp_options
+    {
+        icp_method method = icp_method::point_to_point;
+        std::size_t max_iterations = 30;
+        // Source points farther than this from their nearest target point
+        // have no correspondence. Infinite pairs every point.
+        double max_correspondence_distance = std::numeric_limits<double>::infinity();
+        // Converged once an iteration moves the pose by less than both.
+        double translation_tolerance = 1e-6;
+        // Radians.
+        double rotation_tolerance = 1e-6;
+        // Maps the source into the target frame.
+        pose initial_pose = {{1.0, 0.0, 0.0, 0.0}, {0.0, 0.0, 0.0}};
+    };
+
+    // How well a pose aligns the clouds.
+    struct icp_iteration
+    {
+        // Fraction of the finite source points with a correspondence.
+        double fitness;
+        // Root mean square distance over the correspondences (0 without any).
+        double rmse;
+        std::size_t correspondences;
+    };
+
+    struct icp_result
+    {
+        // Maps the source into the target frame.
+        pose transform;
+        // Evaluated at `transform`.
+        icp_iteration alignment;
+        bool converged;
+        icp_termination termination;
+        // One entry per pose update, evaluated at the pose before it.
+        std::vector<icp_iteration> history;
+    };
+
+    // Iterative closest point: refines `options.initial_pose` so that it maps
+    // `source` onto `target`. Points that are not finite are ignored, and so
+    // are target points without a finite normal for point_to_plane. Running
+    // out of iterations or correspondences is not an error, check
+    // `converged`. Throws error_code::invalid_argument for bad options, and
+    // for point_to_plane without target normals.
+    icp_result icp(const point_cloud_view& source, const point_cloud_view& target, const icp_options& options);
+}
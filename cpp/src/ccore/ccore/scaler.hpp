@@ -0,0 +1,61 @@
+#pragma once
+
+#include "ccore/media.hpp"
+
+#include <cstddef>
+#include <cstdint>
+#include <memory>
+#include <span>
+
+namespace by2
+{
+    // How scaler computes output pixels from input pixels.
+    enum class interpolation : int32_t
+    {
+        bilinear = 0,
+        // Sharper than bilinear, a little slower.
+        bicubic = 1,
+        // Averages the input pixels each output pixel covers; best for
+        // shrinking.
+        area = 2,
+    };
+
+    // Converts packed frames of one size and pixel format to another with
+    // swscale. Set up once and reused for every frame; not thread-safe.
+    //
+    // Only available when built with BY2_WITH_MEDIA.
+    class scaler
+    {
+    public:
+        // Throws error_code::invalid_argument for a size that isn't positive
+        // or an unknown format or interpolation.
+        scaler(
+            int32_t source_width,
+            int32_t source_height,
+            pixel_format source_format,
+            int32_t destination_width,
+            int32_t destination_height,
+            pixel_format destination_format,
+            interpolation method);
+        ~scaler();
+
+        scaler(scaler&&) noexcept;
+        scaler& operator=(scaler&&) noexcept;
+
+        // Bytes of one input frame.
+        std::size_t source_size() const noexcept;
+
+        // Bytes of one output frame.
+        std::size_t destination_size() const noexcept;
+
+        // Converts `source` into `destination`, which must hold exactly
+        // source_size() and destination_size() bytes; throws
+        // error_code::invalid_argument otherwise.
+        void scale(std::span<const std::uint8_t> source, std::span<std::uint8_t> destination);
+
+    private:
+        struct impl;
+
+        std::unique_ptr<impl> m_impl;
+    };
+}
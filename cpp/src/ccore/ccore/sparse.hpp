@@ -0,0 +1,71 @@
+#pragma once
+
+#include "ccore/linalg.hpp"
+
+#include <cstddef>
+#include <cstdint>
+
+namespace by2
+{
+    enum class sparse_format : int32_t
+    {
+        // Compressed rows: one offset per row, column indices.
+        csr = 0,
+        // Compressed columns: one offset per column, row indices.
+        csc = 1,
+    };
+
+    // Non-owning view of a compressed sparse matrix. With `outer` the number
+    // of rows (csr) or columns (csc), `outer_offsets` has outer + 1 entries
+    // starting at 0, and the entries of outer slice k are
+    // inner_indices[outer_offsets[k] .. outer_offsets[k + 1]] with the
+    // matching `values`. Inner indices must be strictly increasing within a
+    // slice, so there are no duplicates.
+    template <typename T>
+    struct sparse_view
+    {
+        sparse_format format;
+        std::size_t rows;
+        std::size_t cols;
+        const std::size_t* outer_offsets;
+        const std::size_t* inner_indices;
+        const T* values;
+    };
+
+    enum class sparse_solver : int32_t
+    {
+        // Direct LDL^T for symmetric matrices, positive definite or
+        // indefinite, as long as no pivot is zero. Only the lower triangle is
+        // read.
+        simplicial_ldlt = 0,
+        // Direct QR with COLAMD ordering. Accepts rows >= cols and returns
+        // the least-squares solution.
+        qr = 1,
+        // Iterative, for symmetric positive definite matrices.
+        conjugate_gradient = 2,
+    };
+
+    struct sparse_solve_options
+    {
+        sparse_solver solver = sparse_solver::simplicial_ldlt;
+        // conjugate_gradient only. 0 keeps Eigen's defaults: 2 * cols
+        // iterations and a relative residual of machine epsilon.
+        std::size_t max_iterations = 0;
+        double tolerance = 0.0;
+    };
+
+    // Both functions validate the structure of `a` and the shapes of the
+    // dense arguments and throw by2::error on failure. Instantiated for float
+    // and double.
+
+    // out (m x n) = a (m x k, sparse) * b (k x n)
+    template <typename T>
+    void sparse_multiply(sparse_view<T> a, matrix_view<const T> b, matrix_view<T> out);
+
+    // Solves a (m x n, sparse) * x (n x k) = b (m x k). simplicial_ldlt and
+    // conjugate_gradient need a square `a`. Throws error_code::singular when
+    // a direct factorization fails and not_converged when conjugate_gradient
+    // runs out of iterations.
+    template <typename T>
+    void sparse_solve(const sparse_solve_options& options, sparse_view<T> a, matrix_view<const T> b, matrix_view<T> x);
+}
@@ -0,0 +1,117 @@
+#pragma once
+
+#include "ccore/media.hpp"
+
+#include <cstddef>
+#include <cstdint>
+#include <memory>
+#include <optional>
+#include <span>
+#include <string>
+#include <vector>
+
+namespace by2
+{
+    struct video_frame_info
+    {
+        // Presentation timestamp in the stream's time base, INT64_MIN if the
+        // frame has none.
+        int64_t pts;
+        // The timestamp in seconds, NaN without one.
+        double time;
+        // Position of the frame in the stream, from 0.
+        int64_t index;
+        bool key_frame;
+    };
+
+    struct video_keyframe
+    {
+        // Position of the keyframe in the stream, from 0.
+        int64_t index;
+        // Presentation timestamp in the stream's time base.
+        int64_t pts;
+    };
+
+    // Where the keyframes of a video stream are, so seeks land on the right
+    // frame whatever the frame rate does.
+    struct video_index
+    {
+        // By increasing index and pts; the first one is frame 0.
+        std::vector<video_keyframe> keyframes;
+        int64_t frame_count = 0;
+    };
+
+    // Decodes the frames of a file's video stream one after the other, in
+    // presentation order, and converts them to a packed pixel format.
+    //
+    // Seeks go back to the keyframe before the target and decode forward to
+    // it, so the next frame is exactly the one asked for. Without an index,
+    // frame numbers after a seek come from the timestamps and the average
+    // frame rate, which is exact for constant frame rate video; with one
+    // (see build_index and set_index) they are counted from the keyframes.
+    //
+    // Only available when built with BY2_WITH_MEDIA.
+    class video_reader
+    {
+    public:
+        // Opens `path` and picks its best video stream (FFmpeg's choice).
+        // Throws error_code::io if the file can't be opened or read or has
+        // no decodable video stream, and error_code::invalid_argument for an
+        // unknown format. The decoder runs on `threads` threads, or on as
+        // many as there are cores for 0.
+        video_reader(const std::string& path, pixel_format format, int32_t threads = 0);
+        ~video_reader();
+
+        video_reader(video_reader&&) noexcept;
+        video_reader& operator=(video_reader&&) noexcept;
+
+        // Every stream of the file, video or not.
+        std::span<const stream_info> streams() const noexcept;
+
+        // Index in streams() of the stream frames are decoded from.
+        std::size_t video_stream() const noexcept;
+
+        pixel_format format() const noexcept;
+
+        // Bytes of one converted frame, which has the size the video stream
+        // declares.
+        std::size_t frame_size() const noexcept;
+
+        // Decodes the next frame into `out`, which must hold frame_size()
+        // bytes, or returns nullopt at the end of the stream. Frames whose
+        // size changed mid-stream are scaled to the declared size. Throws
+        // error_code::invalid_argument for a short `out` and error_code::io
+        // if reading or decoding fails.
+        std::optional<video_frame_info> next(std::span<std::uint8_t> out);
+
+        // Makes next() return the first frame at or after `seconds`, or
+        // nothing past the end.
+        // Throws error_code::invalid_argument if `seconds` isn't finite and
+        // error_code::io if seeking fails.
+        void seek_to_time(double seconds);
+
+        // Makes next() return frame `index`, or nothing past the end.
+        // Throws error_code::invalid_argument for a negative index and
+        // error_code::io if seeking fails.
+        void seek_to_frame(int64_t index);
+
+        // Reads the packets of the video stream, without decoding them, to
+        // find its keyframes, then rewinds to the first frame. Streams
+        // without a frame rate get one on their first seek. Throws
+        // error_code::io if reading fails or packets have no timestamps.
+        const video_index& build_index();
+
+        // Uses an index built earlier for the same file, e.g. loaded from a
+        // cache. Throws error_code::invalid_argument if the keyframes aren't
+        // increasing, don't start at frame 0 or lie past frame_count.
+        void set_index(video_index index);
+
+        // The index in use, if any.
+        const video_index* index() const noexcept;
+
+    private:
+        struct impl;
+
+        std::unique_ptr<impl> m_impl;
+    };
+}
@@ -0,0 +1,81 @@
+#pragma once
+
+#include "ccore/media.hpp"
+
+#include <cstddef>
+#include <cstdint>
+#include <memory>
+#include <span>
+#include <string>
+
+namespace by2
+{
+    // Encoders video_writer can use. Both are part of FFmpeg itself, so
+    // every build of the media module has them.
+    enum class video_codec : int32_t
+    {
+        // MPEG-4 part 2: lossy, plays almost everywhere.
+        mpeg4 = 0,
+        // FFV1: lossless, keeps RGB frames as RGB. Not in MP4.
+        ffv1 = 1,
+    };
+
+    struct video_writer_options
+    {
+        video_codec codec = video_codec::mpeg4;
+        // Frames per second, positive.
+        rational frame_rate = {25, 1};
+        // Bits per second, or 0 for the encoder's default. Lossless codecs
+        // ignore it.
+        int64_t bit_rate = 0;
+        // Frames from one keyframe to the next; 0 makes every frame a
+        // keyframe.
+        int32_t gop_size = 12;
+    };
+
+    // Encodes frames and muxes them into a file whose container FFmpeg
+    // picks by extension: .mp4, .mkv or .avi, among others. Frame i is
+    // shown at i / frame_rate seconds.
+    //
+    // Only available when built with BY2_WITH_MEDIA.
+    class video_writer
+    {
+    public:
+        // Creates `path` for `width` x `height` frames given in `format`.
+        // Throws error_code::invalid_argument for bad options, an extension
+        // FFmpeg doesn't know or a container that can't hold the codec, and
+        // error_code::io if the file can't be created or the encoder fails.
+        video_writer(
+            const std::string& path,
+            int32_t width,
+            int32_t height,
+            pixel_format format,
+            const video_writer_options& options);
+        // Calls finish(), ignoring errors.
+        ~video_writer();
+
+        video_writer(video_writer&&) noexcept;
+        video_writer& operator=(video_writer&&) noexcept;
+
+        // Bytes of one input frame.
+        std::size_t frame_size() const noexcept;
+
+        // Frames written so far.
+        int64_t frame_count() const noexcept;
+
+        // Encodes the next frame, which must hold exactly frame_size()
+        // bytes. Throws error_code::invalid_argument for a frame of another
+        // size or after finish(), and error_code::io if encoding or writing
+        // fails.
+        void write(std::span<const std::uint8_t> frame);
+
+        // Encodes the frames the encoder still holds and completes the file.
+        // Later calls do nothing. Throws error_code::io on failure.
+        void finish();
+
+    private:
+        struct impl;
+
+        std::unique_ptr<impl> m_impl;
+    };
+}
@@ -0,0 +1,63 @@
+#pragma once
+
+// Private helpers to look at ccore matrix views through Eigen. Not installed.
+
+#include "ccore/error.hpp"
+#include "ccore/linalg.hpp"
+
+#include <Eigen/Core>
+#include <spdlog/fmt/fmt.h>
+
+#include <type_traits>
+
+namespace by2::detail
+{
+    template <typename T>
+    using eigen_matrix = Eigen::Matrix<std::remove_const_t<T>, Eigen::Dynamic, Eigen::Dynamic>;
+
+    using eigen_stride = Eigen::Stride<Eigen::Dynamic, Eigen::Dynamic>;
+
+    // Map<const Matrix> for views of const T, Map<Matrix> otherwise.
+    template <typename T>
+    using eigen_map = Eigen::Map<
+        std::conditional_t<std::is_const_v<T>, const eigen_matrix<T>, eigen_matrix<T>>,
+        Eigen::Unaligned,
+        eigen_stride>;
+
+    template <typename T>
+    void validate_view(const matrix_view<T>& m, const char* name)
+    {
+        if (m.row_stride <= 0 || m.col_stride <= 0)
+        {
+            throw error(error_code::invalid_argument, fmt::format("{}: strides must be positive", name));
+        }
+        if (m.data == nullptr && m.rows * m.cols != 0)
+        {
+            throw error(error_code::invalid_argument, fmt::format("{}: data is null", name));
+        }
+    }
+
+    template <typename T>
+    void expect_shape(const matrix_view<T>& m, std::size_t rows, std::size_t cols, const char* name)
+    {
+        if (m.rows != rows || m.cols != cols)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("{}: expected a {}x{} matrix, got {}x{}", name, rows, cols, m.rows, m.cols));
+        }
+    }
+
+    template <typename T>
+    eigen_map<T> as_eigen(const matrix_view<T>& m, const char* name)
+    {
+        validate_view(m, name);
+        // For Eigen's (column-major) Map the inner stride is the step between
+        // two rows of a column and the outer stride the step between columns.
+        return eigen_map<T>(
+            m.data,
+            static_cast<Eigen::Index>(m.rows),
+            static_cast<Eigen::Index>(m.cols),
+            eigen_stride(m.col_stride, m.row_stride));
+    }
+}
@@ -0,0 +1,241 @@
+#include "ffmpeg.hpp"
+#include "ccore/error.hpp"
+
+#include <spdlog/fmt/fmt.h>
+
+#include <array>
+#include <limits>
+#include <new>
+
+namespace by2
+{
+    std::size_t frame_size(pixel_format format, int32_t width, int32_t height)
+    {
+        if (width < 0 || height < 0)
+        {
+            throw error(error_code::invalid_argument, fmt::format("frame_size: negative size {}x{}", width, height));
+        }
+        const std::size_t w = static_cast<std::size_t>(width);
+        const std::size_t h = static_cast<std::size_t>(height);
+        switch (format)
+        {
+        case pixel_format::rgb24:
+            return w * h * 3;
+        case pixel_format::rgba:
+            return w * h * 4;
+        case pixel_format::yuv420p:
+        case pixel_format::nv12:
+            return w * h + 2 * ((w + 1) / 2) * ((h + 1) / 2);
+        case pixel_format::gray8:
+            return w * h;
+        case pixel_format::bgr24:
+            return w * h * 3;
+        case pixel_format::bgra:
+            return w * h * 4;
+        case pixel_format::yuv422p:
+            return w * h + 2 * ((w + 1) / 2) * h;
+        case pixel_format::yuv444p:
+            return w * h * 3;
+        }
+        throw error(
+            error_code::invalid_argument,
+            fmt::format("frame_size: unknown pixel format {}", static_cast<int32_t>(format)));
+    }
+}
+
+namespace by2::detail
+{
+    namespace
+    {
+        media_type to_core(AVMediaType type)
+        {
+            switch (type)
+            {
+            case AVMEDIA_TYPE_VIDEO:
+                return media_type::video;
+            case AVMEDIA_TYPE_AUDIO:
+                return media_type::audio;
+            case AVMEDIA_TYPE_SUBTITLE:
+                return media_type::subtitle;
+            case AVMEDIA_TYPE_DATA:
+                return media_type::data;
+            default:
+                return media_type::unknown;
+            }
+        }
+
+        // 0 for the 0/0 and 1/0 FFmpeg uses for unknown rates.
+        double positive(AVRational rate)
+        {
+            return rate.num > 0 && rate.den > 0 ? av_q2d(rate) : 0.0;
+        }
+    }
+
+    int check_av(int result, std::string_view what)
+    {
+        if (result >= 0)
+        {
+            return result;
+        }
+        std::array<char, AV_ERROR_MAX_STRING_SIZE> text{};
+        av_strerror(result, text.data(), text.size());
+        throw error(error_code::io, fmt::format("{}: {}", what, text.data()));
+    }
+
+    AVPixelFormat to_av(pixel_format format)
+    {
+        switch (format)
+        {
+        case pixel_format::rgb24:
+            return AV_PIX_FMT_RGB24;
+        case pixel_format::rgba:
+            return AV_PIX_FMT_RGBA;
+        case pixel_format::yuv420p:
+            return AV_PIX_FMT_YUV420P;
+        case pixel_format::gray8:
+            return AV_PIX_FMT_GRAY8;
+        case pixel_format::bgr24:
+            return AV_PIX_FMT_BGR24;
+        case pixel_format::bgra:
+            return AV_PIX_FMT_BGRA;
+        case pixel_format::nv12:
+            return AV_PIX_FMT_NV12;
+        case pixel_format::yuv422p:
+            return AV_PIX_FMT_YUV422P;
+        case pixel_format::yuv444p:
+            return AV_PIX_FMT_YUV444P;
+        }
+        throw error(error_code::invalid_argument, fmt::format("unknown pixel format {}", static_cast<int32_t>(format)));
+    }
+
+    av_ptr<AVFormatContext> open_input(const std::string& path)
+    {
+        AVFormatContext* raw = nullptr;
+        // Frees the context itself on failure.
+        check_av(avformat_open_input(&raw, path.c_str(), nullptr, nullptr), fmt::format("can't open {}", path));
+        av_ptr<AVFormatContext> input(raw);
+        check_av(avformat_find_stream_info(input.get(), nullptr), fmt::format("can't read the streams of {}", path));
+        return input;
+    }
+
+    std::vector<media_tag> read_tags(const AVDictionary* tags)
+    {
+        std::vector<media_tag> result;
+        const AVDictionaryEntry* entry = nullptr;
+        // An empty key with AV_DICT_IGNORE_SUFFIX matches every entry.
+        while ((entry = av_dict_get(tags, "", entry, AV_DICT_IGNORE_SUFFIX)) != nullptr)
+        {
+            result.push_back({entry->key, entry->value});
+        }
+        return result;
+    }
+
+    std::vector<stream_info> describe_streams(const AVFormatContext& input)
+    {
+        std::vector<stream_info> streams;
+        streams.reserve(input.nb_streams);
+        for (unsigned i = 0; i < input.nb_streams; ++i)
+        {
+            const AVStream& stream = *input.streams[i];
+            const AVCodecParameters& parameters = *stream.codecpar;
+
+            stream_info info{};
+            info.index = i;
+            info.type = to_core(parameters.codec_type);
+            info.codec = avcodec_get_name(parameters.codec_id);
+            if (parameters.codec_type == AVMEDIA_TYPE_VIDEO)
+            {
+                info.width = parameters.width;
+                info.height = parameters.height;
+                info.frame_rate = positive(stream.avg_frame_rate);
+                if (const char* name = av_get_pix_fmt_name(static_cast<AVPixelFormat>(parameters.format)))
+                {
+                    info.pixel_format = name;
+                }
+            }
+            else if (parameters.codec_type == AVMEDIA_TYPE_AUDIO)
+            {
+                info.sample_rate = parameters.sample_rate;
+                info.channels = parameters.ch_layout.nb_channels;
+                std::array<char, 64> layout{};
+                if (info.channels > 0 && av_channel_layout_describe(&parameters.ch_layout, layout.data(), layout.size()) > 0)
+                {
+                    info.channel_layout = layout.data();
+                }
+            }
+            info.bit_rate = parameters.bit_rate;
+            info.time_base = {stream.time_base.num, stream.time_base.den};
+            if (stream.duration != AV_NOPTS_VALUE)
+            {
+                info.duration = static_cast<double>(stream.duration) * av_q2d(stream.time_base);
+            }
+            else if (input.duration != AV_NOPTS_VALUE)
+            {
+                info.duration = static_cast<double>(input.duration) / AV_TIME_BASE;
+            }
+            else
+            {
+                info.duration = std::numeric_limits<double>::quiet_NaN();
+            }
+            info.frame_count = stream.nb_frames;
+            info.tags = read_tags(stream.metadata);
+            streams.push_back(std::move(info));
+        }
+        return streams;
+    }
+
+    av_ptr<AVCodecContext> open_decoder(const AVStream& stream, const AVCodec& decoder, int32_t threads)
+    {
+        av_ptr<AVCodecContext> context(avcodec_alloc_context3(&decoder));
+        if (!context)
+        {
+            throw std::bad_alloc();
+        }
+        check_av(
+            avcodec_parameters_to_context(context.get(), stream.codecpar),
+            fmt::format("can't set up the {} decoder", decoder.name));
+        context->pkt_timebase = stream.time_base;
+        context->thread_count = threads;
+        check_av(avcodec_open2(context.get(), &decoder, nullptr), fmt::format("can't open the {} decoder", decoder.name));
+        return context;
+    }
+
+    bool decode_next(
+        AVFormatContext& input,
+        int stream,
+        AVCodecContext& decoder,
+        AVPacket& packet,
+        AVFrame& frame,
+        bool& draining)
+    {
+        while (true)
+        {
+            int result = avcodec_receive_frame(&decoder, &frame);
+            if (result == 0)
+            {
+                return true;
+            }
+            if (result == AVERROR_EOF || (result == AVERROR(EAGAIN) && draining))
+            {
+                return false;
+            }
+            if (result != AVERROR(EAGAIN))
+            {
+                check_av(result, "decoding failed");
+            }
+
+            result = av_read_frame(&input, &packet);
+            if (result == AVERROR_EOF)
+            {
+                // A null packet makes the decoder put out what it holds back.
+                draining = true;
+                check_av(avcodec_send_packet(&decoder, nullptr), "decoding failed");
+                continue;
+            }
+            check_av(result, "reading failed");
+            result = packet.stream_index == stream ? avcodec_send_packet(&decoder, &packet) : 0;
+            av_packet_unref(&packet);
+            check_av(result, "decoding failed");
+        }
+    }
+}
@@ -0,0 +1,103 @@
+#pragma once
+
+// Private helpers around the FFmpeg C API. Not installed.
+
+#include "ccore/media.hpp"
+
+// libavutil wants UINT64_C and friends defined before it is included.
+#include <cstdint>
+
+extern "C"
+{
+#include <libavcodec/avcodec.h>
+#include <libavformat/avformat.h>
+#include <libavutil/imgutils.h>
+#include <libavutil/pixdesc.h>
+#include <libswresample/swresample.h>
+#include <libswscale/swscale.h>
+}
+
+#include <memory>
+#include <string>
+#include <string_view>
+#include <vector>
+
+namespace by2::detail
+{
+    // Frees FFmpeg objects with the matching FFmpeg function.
+    struct av_deleter
+    {
+        void operator()(AVFormatContext* p) const noexcept
+        {
+            avformat_close_input(&p);
+        }
+
+        void operator()(AVCodecContext* p) const noexcept
+        {
+            avcodec_free_context(&p);
+        }
+
+        void operator()(AVPacket* p) const noexcept
+        {
+            av_packet_free(&p);
+        }
+
+        void operator()(AVFrame* p) const noexcept
+        {
+            av_frame_free(&p);
+        }
+
+        void operator()(SwsContext* p) const noexcept
+        {
+            sws_freeContext(p);
+        }
+
+        void operator()(SwrContext* p) const noexcept
+        {
+            swr_free(&p);
+        }
+    };
+
+    template <typename T>
+    using av_ptr = std::unique_ptr<T, av_deleter>;
+
+    // Throws error_code::io, "<what>: <FFmpeg's text for result>", if
+    // `result` is an AVERROR code. Returns it otherwise.
+    int check_av(int result, std::string_view what);
+
+    // Throws error_code::invalid_argument for an unknown format.
+    AVPixelFormat to_av(pixel_format format);
+
+    // Opens `path` for demuxing and reads enough of it to know its streams.
+    av_ptr<AVFormatContext> open_input(const std::string& path);
+
+    // The entries of an FFmpeg metadata dictionary, which may be null.
+    std::vector<media_tag> read_tags(const AVDictionary* tags);
+
+    std::vector<stream_info> describe_streams(const AVFormatContext& input);
+
+    // A decoder for `stream`, opened with the stream's parameters, that
+    // decodes on `threads` threads, or as many as there are cores for 0.
+    av_ptr<AVCodecContext> open_decoder(const AVStream& stream, const AVCodec& decoder, int32_t threads = 0);
+
+    // Sends the packets of stream `stream` to `decoder` until it puts out a
+    // frame, and returns false once the decoder is drained at the end of the
+    // input. `draining` tracks whether the end of the input was reached and
+    // starts out false.
+    bool decode_next(
+        AVFormatContext& input,
+        int stream,
+        AVCodecContext& decoder,
+        AVPacket& packet,
+        AVFrame& frame,
+        bool& draining);
+
+    inline bool is_key_frame(const AVFrame& frame)
+    {
+#ifdef AV_FRAME_FLAG_KEY
+        return (frame.flags & AV_FRAME_FLAG_KEY) != 0;
+#else
+        return frame.key_frame != 0;
+#endif
+    }
+}
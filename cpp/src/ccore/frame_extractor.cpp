@@ -0,0 +1,562 @@
+#include "ccore/frame_extractor.hpp"
+#include "ccore/error.hpp"
+#include "ccore/scaler.hpp"
+#include "ccore/video_reader.hpp"
+#include "ffmpeg.hpp"
+
+#include <spdlog/fmt/fmt.h>
+
+#include <algorithm>
+#include <array>
+#include <cmath>
+#include <exception>
+#include <filesystem>
+#include <fstream>
+#include <iterator>
+#include <limits>
+#include <new>
+#include <optional>
+#include <system_error>
+#include <thread>
+#include <tuple>
+#include <utility>
+
+namespace by2
+{
+    namespace
+    {
+        // Automatic segments are at least this long.
+        constexpr double min_segment_seconds = 10.0;
+        // More segments than this are rejected rather than clamped.
+        constexpr int32_t max_segments = 1024;
+        // Scene scores compare luma thumbnails of at most this width.
+        constexpr int32_t thumbnail_width = 64;
+
+        AVCodecID to_av(image_format format)
+        {
+            switch (format)
+            {
+            case image_format::png:
+                return AV_CODEC_ID_PNG;
+            case image_format::jpeg:
+                return AV_CODEC_ID_MJPEG;
+            }
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("unknown image format {}", static_cast<int32_t>(format)));
+        }
+
+        const char* extension(image_format format)
+        {
+            return format == image_format::jpeg ? "jpg" : "png";
+        }
+
+        void validate(const frame_extractor_options& o)
+        {
+            switch (o.selection)
+            {
+            case frame_selection::interval:
+                if (!std::isfinite(o.interval) || o.interval <= 0.0)
+                {
+                    throw error(
+                        error_code::invalid_argument,
+                        fmt::format("extract_frames: bad interval {} s", o.interval));
+                }
+                break;
+            case frame_selection::every_nth:
+                if (o.step < 1)
+                {
+                    throw error(error_code::invalid_argument, fmt::format("extract_frames: bad step {}", o.step));
+                }
+                break;
+            case frame_selection::scene_change:
+                if (!(o.scene_threshold > 0.0 && o.scene_threshold <= 1.0))
+                {
+                    throw error(
+                        error_code::invalid_argument,
+                        fmt::format("extract_frames: scene threshold {} is not in (0, 1]", o.scene_threshold));
+                }
+                break;
+            default:
+                throw error(
+                    error_code::invalid_argument,
+                    fmt::format("extract_frames: unknown selection {}", static_cast<int32_t>(o.selection)));
+            }
+            if (o.width < 0 || o.height < 0)
+            {
+                throw error(
+                    error_code::invalid_argument,
+                    fmt::format("extract_frames: negative size {}x{}", o.width, o.height));
+            }
+            if (o.segments < 0 || o.segments > max_segments)
+            {
+                throw error(
+                    error_code::invalid_argument,
+                    fmt::format("extract_frames: segment count {} is not in 0 to {}", o.segments, max_segments));
+            }
+            if (!o.directory.empty())
+            {
+                to_av(o.format);
+                if (o.quality < 1 || o.quality > 100)
+                {
+                    throw error(
+                        error_code::invalid_argument,
+                        fmt::format("extract_frames: quality {} is not in 1 to 100", o.quality));
+                }
+            }
+        }
+
+        // `width` x `height` scaled to fit the requested size, keeping the
+        // aspect ratio for a missing side.
+        std::pair<int32_t, int32_t> output_size(const frame_extractor_options& o, int32_t width, int32_t height)
+        {
+            const auto scaled = [](int32_t side, int32_t to, int32_t from) {
+                return std::max<int32_t>(1, static_cast<int32_t>(std::lround(double(side) * to / from)));
+            };
+            if (o.width == 0 && o.height == 0)
+            {
+                return {width, height};
+            }
+            if (o.width == 0)
+            {
+                return {scaled(width, o.height, height), o.height};
+            }
+            if (o.height == 0)
+            {
+                return {o.width, scaled(height, o.width, width)};
+            }
+            return {o.width, o.height};
+        }
+
+        // Time of a frame, from its timestamp or else its number.
+        double time_of(const video_frame_info& info, double frame_rate)
+        {
+            if (!std::isnan(info.time))
+            {
+                return info.time;
+            }
+            return frame_rate > 0.0 ? static_cast<double>(info.index) / frame_rate
+                                    : std::numeric_limits<double>::quiet_NaN();
+        }
+
+        // How many frames `o` picks at most from `duration` seconds, or
+        // SIZE_MAX if that isn't known.
+        std::size_t expected_frames(const frame_extractor_options& o, double duration, double frame_rate)
+        {
+            double frames = std::numeric_limits<double>::infinity();
+            if (frame_rate > 0.0)
+            {
+                frames = std::ceil(duration * frame_rate);
+            }
+            switch (o.selection)
+            {
+            case frame_selection::interval:
+                frames = std::min(frames, std::floor(duration / o.interval) + 1.0);
+                break;
+            case frame_selection::every_nth:
+                frames = std::ceil(frames / static_cast<double>(o.step));
+                break;
+            case frame_selection::scene_change:
+                break;
+            }
+            if (!(frames < static_cast<double>(std::numeric_limits<std::size_t>::max())))
+            {
+                return std::numeric_limits<std::size_t>::max();
+            }
+            return std::max<std::size_t>(1, static_cast<std::size_t>(frames));
+        }
+
+        // What the segments share.
+        struct plan
+        {
+            std::string path;
+            frame_extractor_options options;
+            int32_t width = 0;
+            int32_t height = 0;
+            int32_t output_width = 0;
+            int32_t output_height = 0;
+            double frame_rate = 0.0;
+            // Time of the first frame, which interval counts from.
+            double start = 0.0;
+            // How far before its start a segment starts decoding, to know the
+            // frame before its first one.
+            double lookback = 1.0;
+            // Decoder threads of each segment's reader, 0 for one per core.
+            int32_t threads = 0;
+            std::optional<video_index> index;
+        };
+
+        void write_file(const std::string& path, std::span<const std::uint8_t> bytes)
+        {
+            std::ofstream file(path, std::ios::binary | std::ios::trunc);
+            file.write(reinterpret_cast<const char*>(bytes.data()), static_cast<std::streamsize>(bytes.size()));
+            file.close();
+            if (!file)
+            {
+                throw error(error_code::io, fmt::format("extract_frames: can't write {}", path));
+            }
+        }
+
+        // The frames picked from [begin, end) seconds of the stream; the
+        // first segment starts at -inf and also takes frames without a time.
+        std::vector<extracted_frame> extract_segment(const plan& p, double begin, double end)
+        {
+            const frame_extractor_options& o = p.options;
+            const bool first = !std::isfinite(begin);
+            video_reader reader(p.path, pixel_format::rgb24, p.threads);
+            if (p.index)
+            {
+                reader.set_index(*p.index);
+            }
+            if (!first)
+            {
+                reader.seek_to_time(begin - p.lookback);
+            }
+
+            std::vector<std::uint8_t> frame(reader.frame_size());
+            std::optional<scaler> resize;
+            if (p.output_width != p.width || p.output_height != p.height)
+            {
+                resize.emplace(
+                    p.width,
+                    p.height,
+                    pixel_format::rgb24,
+                    p.output_width,
+                    p.output_height,
+                    pixel_format::rgb24,
+                    interpolation::bicubic);
+            }
+            std::optional<scaler> thumbnail;
+            std::vector<std::uint8_t> luma;
+            std::vector<std::uint8_t> previous_luma;
+            if (o.selection == frame_selection::scene_change)
+            {
+                const int32_t w = std::min(p.width, thumbnail_width);
+                const int32_t h = std::max<int32_t>(1, static_cast<int32_t>(std::lround(double(p.height) * w / p.width)));
+                thumbnail.emplace(p.width, p.height, pixel_format::rgb24, w, h, pixel_format::gray8, interpolation::area);
+                luma.resize(thumbnail->destination_size());
+            }
+
+            std::vector<extracted_frame> picked;
+            bool has_previous = false;
+            double previous_time = std::numeric_limits<double>::quiet_NaN();
+            // Which interval a time falls in; a little slack keeps frames that
+            // land right on a multiple in it despite rounding.
+            const auto slot = [&](double time) { return std::floor((time - p.start) / o.interval + 1e-9); };
+            while (const std::optional<video_frame_info> info = reader.next(frame))
+            {
+                const double time = time_of(*info, p.frame_rate);
+                if (time >= end)
+                {
+                    break;
+                }
+                double score = std::numeric_limits<double>::quiet_NaN();
+                if (thumbnail)
+                {
+                    thumbnail->scale(frame, luma);
+                    if (has_previous)
+                    {
+                        double sum = 0.0;
+                        for (std::size_t i = 0; i < luma.size(); ++i)
+                        {
+                            sum += std::abs(int(luma[i]) - int(previous_luma[i]));
+                        }
+                        score = sum / (255.0 * static_cast<double>(luma.size()));
+                    }
+                    std::swap(luma, previous_luma);
+                    luma.resize(previous_luma.size());
+                }
+
+                bool pick = false;
+                switch (o.selection)
+                {
+                case frame_selection::interval:
+                    pick = !std::isnan(time) && (std::isnan(previous_time) || slot(time) > slot(previous_time));
+                    break;
+                case frame_selection::every_nth:
+                    pick = info->index % o.step == 0;
+                    break;
+                case frame_selection::scene_change:
+                    pick = !has_previous || score >= o.scene_threshold;
+                    break;
+                }
+                has_previous = true;
+                if (!std::isnan(time))
+                {
+                    previous_time = time;
+                }
+                // Seen only to know the frame before the segment, or without
+                // a time outside the first segment.
+                const bool inside = std::isnan(time) ? first : time >= begin;
+                if (!pick || !inside)
+                {
+                    continue;
+                }
+
+                extracted_frame out{info->index, info->pts, time, info->key_frame, score, {}, {}};
+                if (resize)
+                {
+                    out.pixels.resize(resize->destination_size());
+                    resize->scale(frame, out.pixels);
+                }
+                else
+                {
+                    out.pixels = frame;
+                }
+                if (!o.directory.empty())
+                {
+                    const std::vector<std::uint8_t> image = encode_image(
+                        out.pixels,
+                        p.output_width,
+                        p.output_height,
+                        pixel_format::rgb24,
+                        o.format,
+                        o.quality);
+                    out.path = (std::filesystem::path(o.directory)
+                                / fmt::format("frame_{:08}.{}", out.index, extension(o.format)))
+                                   .string();
+                    write_file(out.path, image);
+                    out.pixels = {};
+                }
+                picked.push_back(std::move(out));
+            }
+            return picked;
+        }
+    }
+
+    extracted_frames extract_frames(const std::string& path, const frame_extractor_options& options)
+    {
+        validate(options);
+        plan p;
+        p.path = path;
+        p.options = options;
+
+        video_reader probe(path, pixel_format::rgb24);
+        const stream_info& video = probe.streams()[probe.video_stream()];
+        p.width = video.width;
+        p.height = video.height;
+        std::tie(p.output_width, p.output_height) = output_size(options, p.width, p.height);
+        p.frame_rate = video.frame_rate;
+        if (p.frame_rate > 0.0)
+        {
+            p.lookback = 2.0 / p.frame_rate;
+        }
+
+        std::vector<std::uint8_t> frame(probe.frame_size());
+        const std::optional<video_frame_info> first = probe.next(frame);
+        extracted_frames result{p.output_width, p.output_height, {}};
+        if (!first)
+        {
+            return result;
+        }
+        const double first_time = time_of(*first, p.frame_rate);
+        p.start = std::isnan(first_time) ? 0.0 : first_time;
+
+        double duration = video.duration;
+        if (std::isnan(duration) && video.frame_count > 0 && p.frame_rate > 0.0)
+        {
+            duration = static_cast<double>(video.frame_count) / p.frame_rate;
+        }
+        const std::size_t cores = std::max(1u, std::thread::hardware_concurrency());
+        std::size_t segments = 1;
+        if (duration > 0.0)
+        {
+            if (options.segments > 0)
+            {
+                segments = static_cast<std::size_t>(options.segments);
+            }
+            else
+            {
+                segments = static_cast<std::size_t>(std::min(duration / min_segment_seconds, double(max_segments)));
+            }
+            // A segment per core at most, and none without a frame to pick.
+            const std::size_t frames = expected_frames(options, duration, p.frame_rate);
+            segments = std::clamp<std::size_t>(segments, 1, std::min(cores, frames));
+        }
+        if (segments > 1)
+        {
+            // The segments share the cores rather than each decoder taking
+            // all of them.
+            p.threads = static_cast<int32_t>(std::max<std::size_t>(1, cores / segments));
+        }
+        if (segments > 1 && options.selection == frame_selection::every_nth)
+        {
+            // Frame numbers after a seek are only exact with an index.
+            p.index = probe.build_index();
+        }
+        if (!options.directory.empty())
+        {
+            std::error_code failed;
+            std::filesystem::create_directories(options.directory, failed);
+            if (failed)
+            {
+                throw error(
+                    error_code::io,
+                    fmt::format("extract_frames: can't create {}: {}", options.directory, failed.message()));
+            }
+        }
+
+        // Segment k covers [start + k * duration / n, start + (k + 1) *
+        // duration / n); the first and last are open-ended.
+        const auto bound = [&](std::size_t k) {
+            if (k == 0)
+            {
+                return -std::numeric_limits<double>::infinity();
+            }
+            if (k == segments)
+            {
+                return std::numeric_limits<double>::infinity();
+            }
+            return p.start + duration * static_cast<double>(k) / static_cast<double>(segments);
+        };
+        std::vector<std::vector<extracted_frame>> parts(segments);
+        std::vector<std::exception_ptr> errors(segments);
+        const auto run = [&](std::size_t k) {
+            try
+            {
+                parts[k] = extract_segment(p, bound(k), bound(k + 1));
+            }
+            catch (...)
+            {
+                errors[k] = std::current_exception();
+            }
+        };
+        {
+            std::vector<std::jthread> workers;
+            workers.reserve(segments - 1);
+            for (std::size_t k = 1; k < segments; ++k)
+            {
+                workers.emplace_back(run, k);
+            }
+            run(0);
+        }
+        for (const std::exception_ptr& e : errors)
+        {
+            if (e)
+            {
+                std::rethrow_exception(e);
+            }
+        }
+        for (std::vector<extracted_frame>& part : parts)
+        {
+            std::move(part.begin(), part.end(), std::back_inserter(result.frames));
+        }
+        return result;
+    }
+
+    std::vector<std::uint8_t> encode_image(
+        std::span<const std::uint8_t> frame,
+        int32_t width,
+        int32_t height,
+        pixel_format format,
+        image_format image,
+        int32_t quality)
+    {
+        const AVPixelFormat input_format = detail::to_av(format);
+        if (width <= 0 || height <= 0)
+        {
+            throw error(error_code::invalid_argument, fmt::format("encode_image: bad size {}x{}", width, height));
+        }
+        const std::size_t size = frame_size(format, width, height);
+        if (frame.size() != size)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("encode_image: a frame has {} bytes, got {}", size, frame.size()));
+        }
+        if (quality < 1 || quality > 100)
+        {
+            throw error(error_code::invalid_argument, fmt::format("encode_image: quality {} is not in 1 to 100", quality));
+        }
+        const AVCodecID codec = to_av(image);
+        const AVCodec* encoder = avcodec_find_encoder(codec);
+        if (encoder == nullptr)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("encode_image: FFmpeg was built without the {} encoder", avcodec_get_name(codec)));
+        }
+
+        detail::av_ptr<AVCodecContext> context(avcodec_alloc_context3(encoder));
+        detail::av_ptr<AVFrame> picture(av_frame_alloc());
+        detail::av_ptr<AVPacket> packet(av_packet_alloc());
+        if (!context || !picture || !packet)
+        {
+            throw std::bad_alloc();
+        }
+        AVCodecContext& e = *context;
+        e.width = width;
+        e.height = height;
+        e.time_base = {1, 1};
+        if (image == image_format::jpeg)
+        {
+            e.pix_fmt = AV_PIX_FMT_YUVJ420P;
+            e.color_range = AVCOL_RANGE_JPEG;
+            // Quality 100 is quantizer 2, the best, and 1 is 31, the worst.
+            const int qscale = 2 + (100 - quality) * 29 / 99;
+            e.flags |= AV_CODEC_FLAG_QSCALE;
+            e.global_quality = FF_QP2LAMBDA * qscale;
+        }
+        else
+        {
+            e.pix_fmt = avcodec_find_best_pix_fmt_of_list(
+                encoder->pix_fmts,
+                input_format,
+                format == pixel_format::rgba || format == pixel_format::bgra ? 1 : 0,
+                nullptr);
+        }
+        detail::check_av(avcodec_open2(&e, encoder, nullptr), "encode_image: can't open the encoder");
+
+        picture->format = e.pix_fmt;
+        picture->width = width;
+        picture->height = height;
+        picture->quality = e.global_quality;
+        detail::check_av(av_frame_get_buffer(picture.get(), 0), "encode_image: can't allocate a frame");
+        const detail::av_ptr<SwsContext> convert(sws_getContext(
+            width,
+            height,
+            input_format,
+            width,
+            height,
+            e.pix_fmt,
+            SWS_BICUBIC | SWS_ACCURATE_RND,
+            nullptr,
+            nullptr,
+            nullptr));
+        if (!convert)
+        {
+            throw error(
+                error_code::io,
+                fmt::format("encode_image: can't convert {} to {}", av_get_pix_fmt_name(input_format), av_get_pix_fmt_name(e.pix_fmt)));
+        }
+        std::array<std::uint8_t*, 4> planes{};
+        std::array<int, 4> strides{};
+        // swscale only reads the source planes.
+        detail::check_av(
+            av_image_fill_arrays(
+                planes.data(),
+                strides.data(),
+                const_cast<std::uint8_t*>(frame.data()),
+                input_format,
+                width,
+                height,
+                1),
+            "encode_image: bad frame");
+        sws_scale(convert.get(), planes.data(), strides.data(), 0, height, picture->data, picture->linesize);
+
+        detail::check_av(avcodec_send_frame(&e, picture.get()), "encode_image: encoding failed");
+        detail::check_av(avcodec_send_frame(&e, nullptr), "encode_image: encoding failed");
+        std::vector<std::uint8_t> bytes;
+        while (true)
+        {
+            const int result = avcodec_receive_packet(&e, packet.get());
+            if (result == AVERROR_EOF)
+            {
+                return bytes;
+            }
+            detail::check_av(result, "encode_image: encoding failed");
+            bytes.insert(bytes.end(), packet->data, packet->data + packet->size);
+            av_packet_unref(packet.get());
+        }
+    }
+}
@@ -0,0 +1,175 @@
+#include "ccore/geometry.hpp"
+#include "ccore/error.hpp"
+
+#include <Eigen/Geometry>
+#include <spdlog/fmt/fmt.h>
+
+#include <algorithm>
+#include <cmath>
+#include <initializer_list>
+
+namespace by2
+{
+    namespace
+    {
+        using row_major_matrix3 = Eigen::Matrix<double, 3, 3, Eigen::RowMajor>;
+
+        Eigen::Vector3d to_eigen(const vec3& v)
+        {
+            return {v.x, v.y, v.z};
+        }
+
+        vec3 from_eigen(const Eigen::Vector3d& v)
+        {
+            return {v.x(), v.y(), v.z()};
+        }
+
+        // Normalized copy of `q`; throws for quaternions that are not a rotation.
+        Eigen::Quaterniond to_eigen(const quat& q, const char* name)
+        {
+            Eigen::Quaterniond e(q.w, q.x, q.y, q.z);
+            const double norm = e.norm();
+            if (!std::isfinite(norm) || norm < 1e-12)
+            {
+                throw error(
+                    error_code::invalid_argument,
+                    fmt::format("{} ({}, {}, {}, {}) is not a rotation", name, q.w, q.x, q.y, q.z));
+            }
+            e.coeffs() /= norm;
+            return e;
+        }
+
+        // Unit quaternion with w >= 0, so each rotation has one representation.
+        quat from_eigen(const Eigen::Quaterniond& q)
+        {
+            Eigen::Quaterniond n = q.normalized();
+            if (n.w() < 0.0)
+            {
+                n.coeffs() = -n.coeffs();
+            }
+            return {n.w(), n.x(), n.y(), n.z()};
+        }
+
+        void require_finite(std::initializer_list<double> values, const char* name)
+        {
+            if (!std::ranges::all_of(values, [](double v) { return std::isfinite(v); }))
+            {
+                throw error(error_code::invalid_argument, fmt::format("{} is not finite", name));
+            }
+        }
+    }
+
+    quat quat_from_matrix(const mat3& m)
+    {
+        const Eigen::Map<const row_major_matrix3> r(m.data());
+        if (!r.allFinite())
+        {
+            throw error(error_code::invalid_argument, "rotation matrix is not finite");
+        }
+        constexpr double tolerance = 1e-6;
+        if (!(r * r.transpose()).isIdentity(tolerance) || std::abs(r.determinant() - 1.0) > tolerance)
+        {
+            throw error(error_code::invalid_argument, "matrix is not a rotation (orthonormal, determinant 1)");
+        }
+        return from_eigen(Eigen::Quaterniond(row_major_matrix3(r)));
+    }
+
+    mat3 quat_to_matrix(const quat& q)
+    {
+        mat3 m;
+        Eigen::Map<row_major_matrix3>(m.data()) = to_eigen(q, "q").toRotationMatrix();
+        return m;
+    }
+
+    quat quat_from_euler(const euler_angles& e)
+    {
+        require_finite({e.roll, e.pitch, e.yaw}, "euler angles");
+        return from_eigen(
+            Eigen::AngleAxisd(e.yaw, Eigen::Vector3d::UnitZ()) * Eigen::AngleAxisd(e.pitch, Eigen::Vector3d::UnitY()) *
+            Eigen::AngleAxisd(e.roll, Eigen::Vector3d::UnitX()));
+    }
+
+    euler_angles quat_to_euler(const quat& q)
+    {
+        const Eigen::Matrix3d r = to_eigen(q, "q").toRotationMatrix();
+
+        // Eigen's eulerAngles() returns the first angle in [0, pi], which is
+        // not the usual yaw range, so read the angles off the matrix instead.
+        const double sin_pitch = std::clamp(-r(2, 0), -1.0, 1.0);
+        const double pitch = std::asin(sin_pitch);
+        if (std::abs(sin_pitch) > 1.0 - 1e-12)
+        {
+            // Gimbal lock: only yaw - roll (or yaw + roll) is defined.
+            return {0.0, pitch, std::atan2(-r(0, 1), r(1, 1))};
+        }
+        return {std::atan2(r(2, 1), r(2, 2)), pitch, std::atan2(r(1, 0), r(0, 0))};
+    }
+
+    quat quat_from_axis_angle(const axis_angle& a)
+    {
+        require_finite({a.axis.x, a.axis.y, a.axis.z, a.angle}, "axis-angle");
+        const Eigen::Vector3d axis = to_eigen(a.axis);
+        const double norm = axis.norm();
+        if (norm < 1e-12)
+        {
+            throw error(error_code::invalid_argument, "rotation axis is zero");
+        }
+        return from_eigen(Eigen::Quaterniond(Eigen::AngleAxisd(a.angle, axis / norm)));
+    }
+
+    axis_angle quat_to_axis_angle(const quat& q)
+    {
+        // Eigen takes |w|, which keeps the angle in [0, pi].
+        const Eigen::AngleAxisd a(to_eigen(q, "q"));
+        return {from_eigen(a.axis()), a.angle()};
+    }
+
+    quat quat_multiply(const quat& a, const quat& b)
+    {
+        return from_eigen(to_eigen(a, "a") * to_eigen(b, "b"));
+    }
+
+    quat quat_inverse(const quat& q)
+    {
+        return from_eigen(to_eigen(q, "q").conjugate());
+    }
+
+    pose pose_compose(const pose& a, const pose& b)
+    {
+        const Eigen::Quaterniond ra = to_eigen(a.rotation, "a.rotation");
+        const Eigen::Quaterniond rb = to_eigen(b.rotation, "b.rotation");
+        return {from_eigen(ra * rb), from_eigen(ra * to_eigen(b.translation) + to_eigen(a.translation))};
+    }
+
+    pose pose_inverse(const pose& p)
+    {
+        const Eigen::Quaterniond inverse = to_eigen(p.rotation, "rotation").conjugate();
+        return {from_eigen(inverse), from_eigen(-(inverse * to_eigen(p.translation)))};
+    }
+
+    void pose_transform_points(const pose& p, std::span<const double> points, std::span<double> out)
+    {
+        if (points.size() % 3 != 0)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("point buffer length {} is not a multiple of 3", points.size()));
+        }
+        if (out.size() != points.size())
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("output length {} differs from input length {}", out.size(), points.size()));
+        }
+
+        const Eigen::Matrix3d r = to_eigen(p.rotation, "rotation").toRotationMatrix();
+        const Eigen::Index count = static_cast<Eigen::Index>(points.size() / 3);
+        const Eigen::Map<const Eigen::Matrix3Xd> in_map(points.data(), 3, count);
+        Eigen::Map<Eigen::Matrix3Xd> out_map(out.data(), 3, count);
+
+        // Without noalias() Eigen evaluates the product into a temporary, so
+        // `points` and `out` may share storage.
+        out_map = r * in_map;
+        out_map.colwise() += to_eigen(p.translation);
+    }
+}
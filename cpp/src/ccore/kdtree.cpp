@@ -0,0 +1,181 @@
+#include "ccore/kdtree.hpp"
+#include "ccore/error.hpp"
+
+#include <spdlog/fmt/fmt.h>
+
+#include <algorithm>
+#include <cmath>
+
+namespace by2
+{
+    namespace
+    {
+        void expect_finite(const std::array<double, 3>& query)
+        {
+            if (!std::isfinite(query[0]) || !std::isfinite(query[1]) || !std::isfinite(query[2]))
+            {
+                throw error(
+                    error_code::invalid_argument,
+                    fmt::format("kdtree: query ({}, {}, {}) is not finite", query[0], query[1], query[2]));
+            }
+        }
+    }
+
+    double squared_distance(const std::array<float, 3>& p, const std::array<double, 3>& q) noexcept
+    {
+        const double dx = static_cast<double>(p[0]) - q[0];
+        const double dy = static_cast<double>(p[1]) - q[1];
+        const double dz = static_cast<double>(p[2]) - q[2];
+        return dx * dx + dy * dy + dz * dz;
+    }
+
+    kdtree::kdtree(const point_cloud_view& cloud)
+    {
+        validate(cloud);
+        m_points.reserve(cloud.count);
+        for (std::size_t i = 0; i < cloud.count; ++i)
+        {
+            const std::array<float, 3> p{cloud.x[i], cloud.y[i], cloud.z[i]};
+            if (std::isfinite(p[0]) && std::isfinite(p[1]) && std::isfinite(p[2]))
+            {
+                m_points.push_back({p, i});
+            }
+        }
+        m_axes.resize(m_points.size());
+        build(0, m_points.size());
+    }
+
+    void kdtree::build(std::size_t lo, std::size_t hi)
+    {
+        if (hi - lo <= 1)
+        {
+            return;
+        }
+
+        // Split along the axis with the largest spread.
+        std::array<float, 3> min = m_points[lo].point;
+        std::array<float, 3> max = min;
+        for (std::size_t i = lo + 1; i < hi; ++i)
+        {
+            for (std::size_t axis = 0; axis < 3; ++axis)
+            {
+                min[axis] = std::min(min[axis], m_points[i].point[axis]);
+                max[axis] = std::max(max[axis], m_points[i].point[axis]);
+            }
+        }
+        unsigned char axis = 0;
+        for (unsigned char a = 1; a < 3; ++a)
+        {
+            if (max[a] - min[a] > max[axis] - min[axis])
+            {
+                axis = a;
+            }
+        }
+
+        const std::size_t mid = lo + (hi - lo) / 2;
+        std::nth_element(
+            m_points.begin() + static_cast<std::ptrdiff_t>(lo),
+            m_points.begin() + static_cast<std::ptrdiff_t>(mid),
+            m_points.begin() + static_cast<std::ptrdiff_t>(hi),
+            [axis](const entry& a, const entry& b) { return a.point[axis] < b.point[axis]; });
+        m_axes[mid] = axis;
+        build(lo, mid);
+        build(mid + 1, hi);
+    }
+
+    void kdtree::knn(const std::array<double, 3>& query, std::size_t k, std::vector<neighbor>& out) const
+    {
+        expect_finite(query);
+        out.clear();
+        if (k == 0)
+        {
+            return;
+        }
+        // `out` is used as a max-heap on (distance, index) while searching.
+        knn(0, m_points.size(), query, k, out);
+        std::sort_heap(out.begin(), out.end());
+    }
+
+    void kdtree::knn(
+        std::size_t lo,
+        std::size_t hi,
+        const std::array<double, 3>& query,
+        std::size_t k,
+        std::vector<neighbor>& heap) const
+    {
+        if (lo >= hi)
+        {
+            return;
+        }
+        const std::size_t mid = lo + (hi - lo) / 2;
+        const entry& e = m_points[mid];
+        const neighbor candidate{squared_distance(e.point, query), e.index};
+        if (heap.size() < k)
+        {
+            heap.push_back(candidate);
+            std::push_heap(heap.begin(), heap.end());
+        }
+        else if (candidate < heap.front())
+        {
+            std::pop_heap(heap.begin(), heap.end());
+            heap.back() = candidate;
+            std::push_heap(heap.begin(), heap.end());
+        }
+
+        const unsigned char axis = m_axes[mid];
+        const double diff = query[axis] - static_cast<double>(e.point[axis]);
+        const bool left_first = diff < 0.0;
+        knn(left_first ? lo : mid + 1, left_first ? mid : hi, query, k, heap);
+        // Points on the far side are at least |diff| away. Ties are visited
+        // too, since a smaller index wins them.
+        if (heap.size() < k || diff * diff <= heap.front().first)
+        {
+            knn(left_first ? mid + 1 : lo, left_first ? hi : mid, query, k, heap);
+        }
+    }
+
+    void kdtree::radius(const std::array<double, 3>& query, double radius, std::vector<neighbor>& out) const
+    {
+        expect_finite(query);
+        if (!std::isfinite(radius) || radius < 0.0)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("kdtree: radius must be finite and non-negative, got {}", radius));
+        }
+        out.clear();
+        this->radius(0, m_points.size(), query, radius * radius, out);
+        std::sort(out.begin(), out.end());
+    }
+
+    void kdtree::radius(
+        std::size_t lo,
+        std::size_t hi,
+        const std::array<double, 3>& query,
+        double radius_sq,
+        std::vector<neighbor>& out) const
+    {
+        if (lo >= hi)
+        {
+            return;
+        }
+        const std::size_t mid = lo + (hi - lo) / 2;
+        const entry& e = m_points[mid];
+        const double d = squared_distance(e.point, query);
+        if (d <= radius_sq)
+        {
+            out.emplace_back(d, e.index);
+        }
+
+        const unsigned char axis = m_axes[mid];
+        const double diff = query[axis] - static_cast<double>(e.point[axis]);
+        if (diff <= 0.0 || diff * diff <= radius_sq)
+        {
+            radius(lo, mid, query, radius_sq, out);
+        }
+        if (diff >= 0.0 || diff * diff <= radius_sq)
+        {
+            radius(mid + 1, hi, query, radius_sq, out);
+        }
+    }
+}
@@ -0,0 +1,282 @@
+#include "ccore/least_squares.hpp"
+#include "ccore/error.hpp"
+
+#include <Eigen/Core>
+#include <Eigen/QR>
+#include <spdlog/fmt/fmt.h>
+#include <unsupported/Eigen/NonLinearOptimization>
+#include <unsupported/Eigen/NumericalDiff>
+
+#include <cmath>
+#include <initializer_list>
+#include <limits>
+
+namespace by2
+{
+    namespace
+    {
+        using row_major_matrix = Eigen::Matrix<double, Eigen::Dynamic, Eigen::Dynamic, Eigen::RowMajor>;
+
+        std::span<const double> as_span(const Eigen::VectorXd& v)
+        {
+            return {v.data(), static_cast<std::size_t>(v.size())};
+        }
+
+        std::span<double> as_span(Eigen::VectorXd& v)
+        {
+            return {v.data(), static_cast<std::size_t>(v.size())};
+        }
+
+        // Adapts the callbacks to the functor interface of Eigen's unsupported
+        // solvers. Callback failures are thrown straight through Eigen.
+        struct residual_functor
+        {
+            using Scalar = double;
+            enum
+            {
+                InputsAtCompileTime = Eigen::Dynamic,
+                ValuesAtCompileTime = Eigen::Dynamic
+            };
+            using InputType = Eigen::VectorXd;
+            using ValueType = Eigen::VectorXd;
+            using JacobianType = Eigen::MatrixXd;
+
+            const lsq_residual_function* residual;
+            const lsq_jacobian_function* jacobian;
+            Eigen::Index param_count;
+            Eigen::Index residual_count;
+            // Shared with the NumericalDiff copy of this functor.
+            lsq_summary* summary;
+
+            Eigen::Index inputs() const
+            {
+                return param_count;
+            }
+
+            Eigen::Index values() const
+            {
+                return residual_count;
+            }
+
+            int operator()(const Eigen::VectorXd& x, Eigen::VectorXd& fvec) const
+            {
+                ++summary->residual_evaluations;
+                fvec.resize(residual_count);
+                if (!(*residual)(as_span(x), as_span(fvec)))
+                {
+                    throw error(error_code::callback_failed, "residual callback failed");
+                }
+                if (!fvec.allFinite())
+                {
+                    throw error(error_code::invalid_argument, "residual callback returned non-finite values");
+                }
+                return 0;
+            }
+
+            int df(const Eigen::VectorXd& x, Eigen::MatrixXd& fjac) const
+            {
+                ++summary->jacobian_evaluations;
+                row_major_matrix j(residual_count, param_count);
+                if (!(*jacobian)(as_span(x), {j.data(), static_cast<std::size_t>(j.size())}))
+                {
+                    throw error(error_code::callback_failed, "jacobian callback failed");
+                }
+                if (!j.allFinite())
+                {
+                    throw error(error_code::invalid_argument, "jacobian callback returned non-finite values");
+                }
+                fjac = j;
+                return 0;
+            }
+        };
+
+        template <typename Functor>
+        void run_levenberg_marquardt(Functor& functor, Eigen::VectorXd& x, const lsq_options& options, lsq_summary& summary)
+        {
+            namespace lm_status = Eigen::LevenbergMarquardtSpace;
+
+            Eigen::LevenbergMarquardt<Functor> lm(functor);
+            lm.parameters.ftol = options.function_tolerance;
+            lm.parameters.xtol = options.parameter_tolerance;
+            lm.parameters.gtol = options.gradient_tolerance;
+            // Iterations are capped below instead of evaluations.
+            lm.parameters.maxfev = std::numeric_limits<decltype(lm.parameters.maxfev)>::max();
+
+            lm_status::Status status = lm.minimizeInit(x);
+            if (status == lm_status::ImproperInputParameters)
+            {
+                throw error(error_code::invalid_argument, "levenberg-marquardt rejected the problem");
+            }
+            summary.initial_cost = 0.5 * lm.fnorm * lm.fnorm;
+
+            status = lm_status::Running;
+            while (status == lm_status::Running && summary.iterations < options.max_iterations)
+            {
+                status = lm.minimizeOneStep(x);
+                ++summary.iterations;
+            }
+            summary.final_cost = 0.5 * lm.fnorm * lm.fnorm;
+
+            switch (status)
+            {
+            case lm_status::RelativeReductionTooSmall:
+            case lm_status::RelativeErrorAndReductionTooSmall:
+                summary.termination = lsq_termination::function_tolerance;
+                break;
+            case lm_status::RelativeErrorTooSmall:
+                summary.termination = lsq_termination::parameter_tolerance;
+                break;
+            case lm_status::CosinusTooSmall:
+                summary.termination = lsq_termination::gradient_tolerance;
+                break;
+            case lm_status::FtolTooSmall:
+            case lm_status::XtolTooSmall:
+            case lm_status::GtolTooSmall:
+                summary.termination = lsq_termination::tolerance_too_small;
+                break;
+            case lm_status::Running:
+            case lm_status::TooManyFunctionEvaluation:
+                summary.termination = lsq_termination::max_iterations;
+                break;
+            default:
+                throw error(
+                    error_code::internal,
+                    fmt::format("levenberg-marquardt stopped with status {}", static_cast<int>(status)));
+            }
+        }
+
+        template <typename Functor>
+        void run_gauss_newton(const Functor& functor, Eigen::VectorXd& x, const lsq_options& options, lsq_summary& summary)
+        {
+            Eigen::VectorXd r(functor.values());
+            functor(x, r);
+            double cost = 0.5 * r.squaredNorm();
+            summary.initial_cost = cost;
+            summary.termination = lsq_termination::max_iterations;
+
+            Eigen::MatrixXd j(functor.values(), x.size());
+            while (summary.iterations < options.max_iterations)
+            {
+                functor.df(x, j);
+                if ((j.transpose() * r).template lpNorm<Eigen::Infinity>() <= options.gradient_tolerance)
+                {
+                    summary.termination = lsq_termination::gradient_tolerance;
+                    break;
+                }
+
+                const Eigen::ColPivHouseholderQR<Eigen::MatrixXd> qr(j);
+                if (qr.rank() < x.size())
+                {
+                    throw error(error_code::singular, "gauss-newton: jacobian is rank deficient");
+                }
+                const Eigen::VectorXd step = qr.solve(-r);
+                x += step;
+                functor(x, r);
+                ++summary.iterations;
+
+                const double previous_cost = cost;
+                cost = 0.5 * r.squaredNorm();
+                if (step.norm() <= options.parameter_tolerance * (x.norm() + options.parameter_tolerance))
+                {
+                    summary.termination = lsq_termination::parameter_tolerance;
+                    break;
+                }
+                if (std::abs(previous_cost - cost) <= options.function_tolerance * previous_cost)
+                {
+                    summary.termination = lsq_termination::function_tolerance;
+                    break;
+                }
+            }
+            summary.final_cost = cost;
+        }
+
+        void validate(std::span<const double> params, std::size_t residual_count, const lsq_options& options)
+        {
+            if (params.empty() || residual_count < params.size())
+            {
+                throw error(
+                    error_code::invalid_argument,
+                    fmt::format(
+                        "least squares needs residual_count >= param_count >= 1, got {} residuals and {} parameters",
+                        residual_count,
+                        params.size()));
+            }
+            if (options.max_iterations == 0)
+            {
+                throw error(error_code::invalid_argument, "max_iterations must be positive");
+            }
+            for (const double tolerance :
+                 {options.function_tolerance, options.parameter_tolerance, options.gradient_tolerance})
+            {
+                if (!std::isfinite(tolerance) || tolerance < 0.0)
+                {
+                    throw error(error_code::invalid_argument, "tolerances must be finite and non-negative");
+                }
+            }
+            for (const double p : params)
+            {
+                if (!std::isfinite(p))
+                {
+                    throw error(error_code::invalid_argument, "initial parameters must be finite");
+                }
+            }
+        }
+    }
+
+    lsq_summary least_squares_solve(
+        const lsq_residual_function& residual,
+        const lsq_jacobian_function& jacobian,
+        std::span<double> params,
+        std::size_t residual_count,
+        const lsq_options& options)
+    {
+        validate(params, residual_count, options);
+        if (!residual)
+        {
+            throw error(error_code::invalid_argument, "residual function is empty");
+        }
+        if (options.method != lsq_method::levenberg_marquardt && options.method != lsq_method::gauss_newton)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("unknown least squares method {}", static_cast<int32_t>(options.method)));
+        }
+
+        lsq_summary summary{};
+        residual_functor functor{
+            &residual,
+            &jacobian,
+            static_cast<Eigen::Index>(params.size()),
+            static_cast<Eigen::Index>(residual_count),
+            &summary,
+        };
+
+        // Work on a copy so `params` stays untouched when the solve throws.
+        Eigen::VectorXd x = Eigen::Map<const Eigen::VectorXd>(params.data(), functor.param_count);
+        const auto run = [&](auto& f) {
+            if (options.method == lsq_method::levenberg_marquardt)
+            {
+                run_levenberg_marquardt(f, x, options, summary);
+            }
+            else
+            {
+                run_gauss_newton(f, x, options, summary);
+            }
+        };
+        if (jacobian)
+        {
+            run(functor);
+        }
+        else
+        {
+            Eigen::NumericalDiff<residual_functor> numeric(functor);
+            run(numeric);
+        }
+
+        summary.converged = summary.termination == lsq_termination::function_tolerance ||
+                            summary.termination == lsq_termination::parameter_tolerance ||
+                            summary.termination == lsq_termination::gradient_tolerance;
+        Eigen::Map<Eigen::VectorXd>(params.data(), functor.param_count) = x;
+        return summary;
+    }
+}
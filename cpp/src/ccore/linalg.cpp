@@ -0,0 +1,172 @@
+#include "ccore/linalg.hpp"
+#include "eigen_map.hpp"
+
+#include <Eigen/Cholesky>
+#include <Eigen/Eigenvalues>
+#include <Eigen/LU>
+#include <Eigen/QR>
+#include <Eigen/SVD>
+
+#include <algorithm>
+
+namespace by2
+{
+    template <typename T>
+    void matrix_multiply(matrix_view<const T> a, matrix_view<const T> b, matrix_view<T> out)
+    {
+        if (a.cols != b.rows)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("multiply: inner dimensions differ ({}x{} * {}x{})", a.rows, a.cols, b.rows, b.cols));
+        }
+        detail::expect_shape(out, a.rows, b.cols, "out");
+
+        auto out_map = detail::as_eigen(out, "out");
+        out_map = detail::as_eigen(a, "a") * detail::as_eigen(b, "b");
+    }
+
+    template <typename T>
+    void matrix_inverse(matrix_view<const T> a, matrix_view<T> out)
+    {
+        if (a.rows != a.cols)
+        {
+            throw error(error_code::invalid_argument, fmt::format("inverse: {}x{} matrix is not square", a.rows, a.cols));
+        }
+        detail::expect_shape(out, a.rows, a.cols, "out");
+
+        Eigen::FullPivLU<detail::eigen_matrix<T>> lu(detail::as_eigen(a, "a"));
+        if (!lu.isInvertible())
+        {
+            throw error(error_code::singular, "inverse: matrix is singular");
+        }
+
+        auto out_map = detail::as_eigen(out, "out");
+        out_map = lu.inverse();
+    }
+
+    template <typename T>
+    void matrix_solve(solve_method method, matrix_view<const T> a, matrix_view<const T> b, matrix_view<T> x)
+    {
+        if (b.rows != a.rows)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("solve: a has {} rows but b has {}", a.rows, b.rows));
+        }
+        detail::expect_shape(x, a.cols, b.cols, "x");
+
+        const auto a_map = detail::as_eigen(a, "a");
+        const auto b_map = detail::as_eigen(b, "b");
+        auto x_map = detail::as_eigen(x, "x");
+
+        if (method != solve_method::qr && a.rows != a.cols)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("solve: {}x{} matrix is not square", a.rows, a.cols));
+        }
+
+        switch (method)
+        {
+        case solve_method::lu:
+        {
+            Eigen::FullPivLU<detail::eigen_matrix<T>> lu(a_map);
+            if (!lu.isInvertible())
+            {
+                throw error(error_code::singular, "solve: matrix is singular");
+            }
+            x_map = lu.solve(b_map);
+            break;
+        }
+        case solve_method::qr:
+        {
+            if (a.rows < a.cols)
+            {
+                throw error(
+                    error_code::invalid_argument,
+                    fmt::format("solve: qr needs at least as many rows as columns, got {}x{}", a.rows, a.cols));
+            }
+            Eigen::ColPivHouseholderQR<detail::eigen_matrix<T>> qr(a_map);
+            if (qr.rank() < static_cast<Eigen::Index>(a.cols))
+            {
+                throw error(error_code::singular, "solve: matrix is rank deficient");
+            }
+            x_map = qr.solve(b_map);
+            break;
+        }
+        case solve_method::cholesky:
+        {
+            Eigen::LLT<detail::eigen_matrix<T>> llt(a_map);
+            if (llt.info() != Eigen::Success)
+            {
+                throw error(error_code::singular, "solve: matrix is not positive definite");
+            }
+            x_map = llt.solve(b_map);
+            break;
+        }
+        default:
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("solve: unknown method {}", static_cast<int32_t>(method)));
+        }
+    }
+
+    template <typename T>
+    void matrix_svd(matrix_view<const T> a, matrix_view<T> u, matrix_view<T> s, matrix_view<T> v)
+    {
+        const auto p = std::min(a.rows, a.cols);
+        detail::expect_shape(u, a.rows, p, "u");
+        detail::expect_shape(s, p, 1, "s");
+        detail::expect_shape(v, a.cols, p, "v");
+
+        Eigen::BDCSVD<detail::eigen_matrix<T>> svd(detail::as_eigen(a, "a"), Eigen::ComputeThinU | Eigen::ComputeThinV);
+        if (svd.info() != Eigen::Success)
+        {
+            throw error(error_code::not_converged, "svd: decomposition did not converge");
+        }
+
+        auto u_map = detail::as_eigen(u, "u");
+        auto s_map = detail::as_eigen(s, "s");
+        auto v_map = detail::as_eigen(v, "v");
+        u_map = svd.matrixU();
+        s_map = svd.singularValues();
+        v_map = svd.matrixV();
+    }
+
+    template <typename T>
+    void matrix_eigen_symmetric(matrix_view<const T> a, matrix_view<T> values, matrix_view<T> vectors)
+    {
+        if (a.rows != a.cols)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("eigen: {}x{} matrix is not square", a.rows, a.cols));
+        }
+        detail::expect_shape(values, a.rows, 1, "values");
+        detail::expect_shape(vectors, a.rows, a.rows, "vectors");
+
+        Eigen::SelfAdjointEigenSolver<detail::eigen_matrix<T>> solver(detail::as_eigen(a, "a"));
+        if (solver.info() != Eigen::Success)
+        {
+            throw error(error_code::not_converged, "eigen: decomposition did not converge");
+        }
+
+        auto values_map = detail::as_eigen(values, "values");
+        auto vectors_map = detail::as_eigen(vectors, "vectors");
+        values_map = solver.eigenvalues();
+        vectors_map = solver.eigenvectors();
+    }
+
+#define BY2_INSTANTIATE_LINALG(T)                                                                               \
+    template void matrix_multiply<T>(matrix_view<const T>, matrix_view<const T>, matrix_view<T>);                \
+    template void matrix_inverse<T>(matrix_view<const T>, matrix_view<T>);                                       \
+    template void matrix_solve<T>(solve_method, matrix_view<const T>, matrix_view<const T>, matrix_view<T>);     \
+    template void matrix_svd<T>(matrix_view<const T>, matrix_view<T>, matrix_view<T>, matrix_view<T>);           \
+    template void matrix_eigen_symmetric<T>(matrix_view<const T>, matrix_view<T>, matrix_view<T>);
+
+    BY2_INSTANTIATE_LINALG(float)
+    BY2_INSTANTIATE_LINALG(double)
+
+#undef BY2_INSTANTIATE_LINALG
+}
@@ -0,0 +1,25 @@
+#include "ccore/media_probe.hpp"
+#include "ffmpeg.hpp"
+
+#include <limits>
+
+namespace by2
+{
+    media_info probe_media(const std::string& path)
+    {
+        const detail::av_ptr<AVFormatContext> input = detail::open_input(path);
+
+        media_info info{};
+        info.format = input->iformat->name;
+        if (input->iformat->long_name != nullptr)
+        {
+            info.format_long_name = input->iformat->long_name;
+        }
+        info.duration = input->duration != AV_NOPTS_VALUE ? static_cast<double>(input->duration) / AV_TIME_BASE
+                                                          : std::numeric_limits<double>::quiet_NaN();
+        info.bit_rate = input->bit_rate;
+        info.tags = detail::read_tags(input->metadata);
+        info.streams = detail::describe_streams(*input);
+        return info;
+    }
+}
@@ -0,0 +1,665 @@
+#include "ccore/occupancy_map.hpp"
+#include "ccore/error.hpp"
+
+#include <octomap/OcTree.h>
+#include <spdlog/fmt/fmt.h>
+
+#include <algorithm>
+#include <charconv>
+#include <cmath>
+#include <cstring>
+#include <limits>
+#include <sstream>
+#include <string>
+#include <string_view>
+
+namespace by2
+{
+    namespace
+    {
+        // octomap stores a node's children at most this many levels below
+        // the root; its readers recurse as deep as the data says.
+        constexpr unsigned tree_depth = 16;
+
+        // Keys run over [0, 2 * key_offset), the coordinate 0 falling between
+        // key_offset - 1 and key_offset.
+        constexpr double key_offset = 1 << (tree_depth - 1);
+
+        // Positive, and small enough that the mappable volume, and so every
+        // voxel center, stays finite.
+        bool valid_resolution(double resolution)
+        {
+            return resolution > 0.0 && std::isfinite(resolution * 2 * key_offset);
+        }
+
+        std::string_view first_line(octree_format format)
+        {
+            switch (format)
+            {
+            case octree_format::binary:
+                return "# Octomap OcTree binary file";
+            case octree_format::full:
+                return "# Octomap OcTree file";
+            }
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("occupancy_map: unknown format {}", static_cast<int32_t>(format)));
+        }
+
+        struct header
+        {
+            double resolution;
+            std::size_t size;
+            // Where the node data starts.
+            std::size_t data;
+        };
+
+        [[noreturn]] void malformed(std::string_view what)
+        {
+            throw error(error_code::invalid_argument, fmt::format("occupancy_map: {}", what));
+        }
+
+        template <typename T>
+        T parse_number(std::string_view token, std::string_view key)
+        {
+            T value{};
+            const auto [end, ec] = std::from_chars(token.data(), token.data() + token.size(), value);
+            if (ec != std::errc() || end != token.data() + token.size())
+            {
+                malformed(fmt::format("header has a bad {} '{}'", key, token));
+            }
+            return value;
+        }
+
+        // Follows octomap's AbstractOcTree::readHeader: whitespace separated
+        // keywords, '#' comments and unknown keywords to the end of the line,
+        // and the node data right after the line holding `data`.
+        header parse_header(std::string_view text, octree_format format)
+        {
+            const std::string_view magic = first_line(format);
+            if (!text.starts_with(magic))
+            {
+                malformed(fmt::format("expected a stream starting with '{}'", magic));
+            }
+            std::size_t pos = text.find('\n');
+            const auto skip_line = [&] {
+                pos = text.find('\n', pos);
+                pos = pos == std::string_view::npos ? text.size() : pos + 1;
+            };
+            const auto next_token = [&] {
+                const std::size_t begin = text.find_first_not_of(" \t\r\n\v\f", pos);
+                if (begin == std::string_view::npos)
+                {
+                    pos = text.size();
+                    return std::string_view();
+                }
+                const std::size_t end = std::min(text.find_first_of(" \t\r\n\v\f", begin), text.size());
+                pos = end;
+                return text.substr(begin, end - begin);
+            };
+
+            std::string_view id;
+            std::optional<double> resolution;
+            std::optional<std::size_t> size;
+            while (pos < text.size())
+            {
+                const std::string_view token = next_token();
+                if (token == "data")
+                {
+                    skip_line();
+                    if (id != "OcTree")
+                    {
+                        malformed(fmt::format("expected an OcTree, got '{}'", id));
+                    }
+                    if (!resolution || !valid_resolution(*resolution))
+                    {
+                        malformed("header has no valid res");
+                    }
+                    if (!size)
+                    {
+                        malformed("header has no size");
+                    }
+                    return {*resolution, *size, pos};
+                }
+                if (token.starts_with('#'))
+                {
+                    skip_line();
+                }
+                else if (token == "id")
+                {
+                    id = next_token();
+                }
+                else if (token == "res")
+                {
+                    resolution = parse_number<double>(next_token(), "res");
+                }
+                else if (token == "size")
+                {
+                    size = parse_number<std::size_t>(next_token(), "size");
+                }
+                else if (!token.empty())
+                {
+                    skip_line();
+                }
+            }
+            malformed("header has no data line");
+        }
+
+        class node_reader
+        {
+        public:
+            explicit node_reader(std::string_view data)
+                : m_data(data)
+            {
+            }
+
+            const unsigned char* take(std::size_t n)
+            {
+                if (m_data.size() - m_pos < n)
+                {
+                    malformed("node data is truncated");
+                }
+                const auto* p = reinterpret_cast<const unsigned char*>(m_data.data() + m_pos);
+                m_pos += n;
+                return p;
+            }
+
+        private:
+            std::string_view m_data;
+            std::size_t m_pos = 0;
+        };
+
+        // Nodes in the subtree of a `.bt` node at `depth`, itself included.
+        // Two bytes hold a 2-bit code per child: 0 none, 1 free leaf, 2
+        // occupied leaf, 3 inner node whose own bytes follow, in child order.
+        std::size_t walk_binary(node_reader& reader, unsigned depth)
+        {
+            const unsigned char* bits = reader.take(2);
+            std::size_t count = 1;
+            unsigned inner = 0;
+            for (unsigned i = 0; i < 8; ++i)
+            {
+                const unsigned pair = (bits[i / 4] >> (i % 4 * 2)) & 3u;
+                count += pair != 0 ? 1 : 0;
+                inner |= pair == 3 ? 1u << i : 0u;
+            }
+            if (inner != 0 && depth + 1 >= tree_depth)
+            {
+                malformed("node data is deeper than the tree");
+            }
+            if (count == 1 && depth > 0)
+            {
+                malformed("node data has an inner node without children");
+            }
+            for (unsigned i = 0; i < 8; ++i)
+            {
+                if ((inner >> i) & 1u)
+                {
+                    count += walk_binary(reader, depth + 1) - 1;
+                }
+            }
+            return count;
+        }
+
+        // Same for a `.ot` node: its float log-odds, a byte with one bit per
+        // existing child, then the children in order.
+        std::size_t walk_full(node_reader& reader, unsigned depth)
+        {
+            float log_odds;
+            std::memcpy(&log_odds, reader.take(sizeof(log_odds)), sizeof(log_odds));
+            if (!std::isfinite(log_odds))
+            {
+                malformed("node data holds a log-odds that is not finite");
+            }
+            const unsigned children = *reader.take(1);
+            if (children != 0 && depth >= tree_depth)
+            {
+                malformed("node data is deeper than the tree");
+            }
+            std::size_t count = 1;
+            for (unsigned i = 0; i < 8; ++i)
+            {
+                if ((children >> i) & 1u)
+                {
+                    count += walk_full(reader, depth + 1);
+                }
+            }
+            return count;
+        }
+
+        octree_leaf to_leaf(const octomap::OcTree& tree, const octomap::OcTree::leaf_bbx_iterator& it)
+        {
+            const octomap::OcTreeKey& key = it.getKey();
+            const unsigned depth = it.getDepth();
+            return {
+                {tree.keyToCoord(key[0], depth), tree.keyToCoord(key[1], depth), tree.keyToCoord(key[2], depth)},
+                tree.getNodeSize(depth),
+                it->getOccupancy(),
+            };
+        }
+
+        bool matches(const octomap::OcTree& tree, const octomap::OcTreeNode& node, leaf_filter filter)
+        {
+            switch (filter)
+            {
+            case leaf_filter::occupied:
+                return tree.isNodeOccupied(node);
+            case leaf_filter::free:
+                return !tree.isNodeOccupied(node);
+            case leaf_filter::known:
+                return true;
+            }
+            return false;
+        }
+
+        double distance_to_leaf(const vec3& p, const octree_leaf& leaf)
+        {
+            const double half = leaf.size / 2;
+            const double dx = std::max(std::abs(p.x - leaf.center.x) - half, 0.0);
+            const double dy = std::max(std::abs(p.y - leaf.center.y) - half, 0.0);
+            const double dz = std::max(std::abs(p.z - leaf.center.z) - half, 0.0);
+            return std::sqrt(dx * dx + dy * dy + dz * dz);
+        }
+
+        void expect_finite(const vec3& p, const char* name)
+        {
+            if (!std::isfinite(p.x) || !std::isfinite(p.y) || !std::isfinite(p.z))
+            {
+                throw error(
+                    error_code::invalid_argument,
+                    fmt::format("occupancy_map: {} ({}, {}, {}) is not finite", name, p.x, p.y, p.z));
+            }
+        }
+    }
+
+    struct occupancy_map::impl
+    {
+        explicit impl(double resolution)
+            : tree(resolution)
+        {
+        }
+
+        void expect_mappable(const vec3& p, const char* name) const
+        {
+            octomap::OcTreeKey key;
+            if (!tree.coordToKeyChecked(p.x, p.y, p.z, key))
+            {
+                throw error(
+                    error_code::invalid_argument,
+                    fmt::format("occupancy_map: {} ({}, {}, {}) is outside the mappable volume", name, p.x, p.y, p.z));
+            }
+        }
+
+        vec3 center(const octomap::OcTreeKey& key) const
+        {
+            return {tree.keyToCoord(key[0]), tree.keyToCoord(key[1]), tree.keyToCoord(key[2])};
+        }
+
+        // Where a ray reaching the voxel at `key` stops, if it does.
+        std::optional<ray_result> stop_at(const octomap::OcTreeKey& key, bool ignore_unknown) const
+        {
+            const octomap::OcTreeNode* node = tree.search(key);
+            if (node == nullptr)
+            {
+                return ignore_unknown ? std::nullopt : std::optional(ray_result::unknown);
+            }
+            return tree.isNodeOccupied(node) ? std::optional(ray_result::occupied) : std::nullopt;
+        }
+
+        // Amanatides and Woo's voxel traversal along a unit `direction`.
+        // octomap's castRay does the same, but wraps around at the edge of
+        // the volume instead of stopping there.
+        ray_hit walk(const vec3& origin, const vec3& direction, double max_range, bool ignore_unknown) const
+        {
+            octomap::OcTreeKey key;
+            tree.coordToKeyChecked(origin.x, origin.y, origin.z, key);
+            const double o[3] = {origin.x, origin.y, origin.z};
+            const double d[3] = {direction.x, direction.y, direction.z};
+            const double resolution = tree.getResolution();
+            int step[3];
+            double t_next[3];
+            double t_delta[3];
+            for (unsigned i = 0; i < 3; ++i)
+            {
+                step[i] = d[i] > 0.0 ? 1 : d[i] < 0.0 ? -1 : 0;
+                if (step[i] == 0)
+                {
+                    t_next[i] = std::numeric_limits<double>::infinity();
+                    t_delta[i] = t_next[i];
+                    continue;
+                }
+                const double border = tree.keyToCoord(key[i]) + step[i] * resolution / 2;
+                t_next[i] = (border - o[i]) / d[i];
+                t_delta[i] = resolution / std::abs(d[i]);
+            }
+
+            while (true)
+            {
+                if (const std::optional<ray_result> result = stop_at(key, ignore_unknown))
+                {
+                    return {*result, center(key)};
+                }
+                const unsigned axis = t_next[0] < t_next[1] ? (t_next[0] < t_next[2] ? 0 : 2)
+                                                            : (t_next[1] < t_next[2] ? 1 : 2);
+                const int next = key[axis] + step[axis];
+                if (t_next[axis] > max_range || next < 0 || next >= 2 * key_offset)
+                {
+                    return {ray_result::clear, center(key)};
+                }
+                key[axis] = static_cast<octomap::key_type>(next);
+                t_next[axis] += t_delta[axis];
+            }
+        }
+
+        // Keys of the voxels holding `min` and `max`, clipped to the mappable
+        // volume; false if the box misses it.
+        bool key_box(const vec3& min, const vec3& max, octomap::OcTreeKey& lo, octomap::OcTreeKey& hi) const
+        {
+            const double factor = 1.0 / tree.getResolution();
+            const double a[3] = {min.x, min.y, min.z};
+            const double b[3] = {max.x, max.y, max.z};
+            for (unsigned i = 0; i < 3; ++i)
+            {
+                const double first = std::floor(a[i] * factor);
+                const double last = std::floor(b[i] * factor);
+                if (last < -key_offset || first >= key_offset)
+                {
+                    return false;
+                }
+                lo[i] = static_cast<octomap::key_type>(std::max(first, -key_offset) + key_offset);
+                hi[i] = static_cast<octomap::key_type>(std::min(last, key_offset - 1) + key_offset);
+            }
+            return true;
+        }
+
+        octomap::OcTree tree;
+    };
+
+    struct leaf_cursor::impl
+    {
+        const octomap::OcTree* tree;
+        octomap::OcTree::leaf_bbx_iterator it;
+        octomap::OcTree::leaf_bbx_iterator end;
+        leaf_filter filter;
+    };
+
+    leaf_cursor::leaf_cursor(std::unique_ptr<impl> state)
+        : m_impl(std::move(state))
+    {
+    }
+
+    leaf_cursor::~leaf_cursor() = default;
+    leaf_cursor::leaf_cursor(leaf_cursor&&) noexcept = default;
+    leaf_cursor& leaf_cursor::operator=(leaf_cursor&&) noexcept = default;
+
+    std::size_t leaf_cursor::next(std::span<octree_leaf> out)
+    {
+        impl& s = *m_impl;
+        std::size_t count = 0;
+        for (; count < out.size() && s.it != s.end; ++s.it)
+        {
+            if (matches(*s.tree, *s.it, s.filter))
+            {
+                out[count++] = to_leaf(*s.tree, s.it);
+            }
+        }
+        return count;
+    }
+
+    occupancy_map::occupancy_map(std::unique_ptr<impl> state)
+        : m_impl(std::move(state))
+    {
+    }
+
+    occupancy_map::occupancy_map(double resolution)
+    {
+        if (!valid_resolution(resolution))
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format(
+                    "occupancy_map: resolution must be positive and at most {}, got {}",
+                    std::numeric_limits<double>::max() / (2 * key_offset),
+                    resolution));
+        }
+        m_impl = std::make_unique<impl>(resolution);
+    }
+
+    occupancy_map::~occupancy_map() = default;
+    occupancy_map::occupancy_map(occupancy_map&&) noexcept = default;
+    occupancy_map& occupancy_map::operator=(occupancy_map&&) noexcept = default;
+
+    double occupancy_map::resolution() const noexcept
+    {
+        return m_impl->tree.getResolution();
+    }
+
+    std::size_t occupancy_map::leaf_count() const noexcept
+    {
+        return m_impl->tree.getNumLeafNodes();
+    }
+
+    void occupancy_map::insert_point_cloud(const point_cloud_view& cloud, const vec3& origin, double max_range)
+    {
+        validate(cloud);
+        expect_finite(origin, "origin");
+        m_impl->expect_mappable(origin, "origin");
+        if (!(max_range > 0.0))
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("occupancy_map: max_range must be positive, got {}", max_range));
+        }
+
+        // Validate every ray before touching the tree, so a bad scan leaves
+        // the map unchanged. octomap would only log and skip such rays.
+        octomap::Pointcloud scan;
+        scan.reserve(cloud.count);
+        for (std::size_t i = 0; i < cloud.count; ++i)
+        {
+            const vec3 p{cloud.x[i], cloud.y[i], cloud.z[i]};
+            if (!std::isfinite(p.x) || !std::isfinite(p.y) || !std::isfinite(p.z))
+            {
+                continue;
+            }
+            vec3 end = p;
+            const double dx = p.x - origin.x;
+            const double dy = p.y - origin.y;
+            const double dz = p.z - origin.z;
+            const double length = std::sqrt(dx * dx + dy * dy + dz * dz);
+            if (length > max_range)
+            {
+                const double scale = max_range / length;
+                end = {origin.x + dx * scale, origin.y + dy * scale, origin.z + dz * scale};
+            }
+            m_impl->expect_mappable(end, "end point");
+            scan.push_back(cloud.x[i], cloud.y[i], cloud.z[i]);
+        }
+
+        // octomap takes a negative range for "unlimited".
+        const double range = std::isinf(max_range) ? -1.0 : max_range;
+        m_impl->tree.insertPointCloud(
+            scan,
+            octomap::point3d(static_cast<float>(origin.x), static_cast<float>(origin.y), static_cast<float>(origin.z)),
+            range);
+    }
+
+    std::optional<double> occupancy_map::occupancy(const vec3& point) const
+    {
+        expect_finite(point, "point");
+        octomap::OcTreeKey key;
+        if (!m_impl->tree.coordToKeyChecked(point.x, point.y, point.z, key))
+        {
+            return std::nullopt;
+        }
+        const octomap::OcTreeNode* node = m_impl->tree.search(key);
+        if (node == nullptr)
+        {
+            return std::nullopt;
+        }
+        return node->getOccupancy();
+    }
+
+    ray_hit occupancy_map::cast_ray(const vec3& origin, const vec3& direction, double max_range, bool ignore_unknown) const
+    {
+        expect_finite(origin, "origin");
+        m_impl->expect_mappable(origin, "origin");
+        expect_finite(direction, "direction");
+        // Scale before normalizing, so huge components don't overflow.
+        const double scale = std::max({std::abs(direction.x), std::abs(direction.y), std::abs(direction.z)});
+        if (scale == 0.0)
+        {
+            throw error(error_code::invalid_argument, "occupancy_map: direction is zero");
+        }
+        if (!(max_range > 0.0))
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("occupancy_map: max_range must be positive, got {}", max_range));
+        }
+        const vec3 d{direction.x / scale, direction.y / scale, direction.z / scale};
+        const double length = std::sqrt(d.x * d.x + d.y * d.y + d.z * d.z);
+        return m_impl->walk(origin, {d.x / length, d.y / length, d.z / length}, max_range, ignore_unknown);
+    }
+
+    bool occupancy_map::line_of_sight(const vec3& from, const vec3& to, bool ignore_unknown) const
+    {
+        expect_finite(from, "from");
+        m_impl->expect_mappable(from, "from");
+        expect_finite(to, "to");
+        m_impl->expect_mappable(to, "to");
+        const vec3 d{to.x - from.x, to.y - from.y, to.z - from.z};
+        const double length = std::sqrt(d.x * d.x + d.y * d.y + d.z * d.z);
+        if (length == 0.0)
+        {
+            octomap::OcTreeKey key;
+            m_impl->tree.coordToKeyChecked(from.x, from.y, from.z, key);
+            return !m_impl->stop_at(key, ignore_unknown);
+        }
+        const vec3 direction{d.x / length, d.y / length, d.z / length};
+        return m_impl->walk(from, direction, length, ignore_unknown).result == ray_result::clear;
+    }
+
+    std::optional<nearest_leaf> occupancy_map::nearest_occupied(const vec3& point, double max_distance) const
+    {
+        expect_finite(point, "point");
+        if (!(max_distance >= 0.0))
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("occupancy_map: max_distance must be non-negative, got {}", max_distance));
+        }
+        const octomap::OcTree& tree = m_impl->tree;
+        octomap::OcTreeKey lo;
+        octomap::OcTreeKey hi;
+        const vec3 min{point.x - max_distance, point.y - max_distance, point.z - max_distance};
+        const vec3 max{point.x + max_distance, point.y + max_distance, point.z + max_distance};
+        if (!m_impl->key_box(min, max, lo, hi))
+        {
+            return std::nullopt;
+        }
+        std::optional<nearest_leaf> best;
+        for (auto it = tree.begin_leafs_bbx(lo, hi), end = tree.end_leafs_bbx(); it != end; ++it)
+        {
+            if (!tree.isNodeOccupied(*it))
+            {
+                continue;
+            }
+            const octree_leaf leaf = to_leaf(tree, it);
+            const double distance = distance_to_leaf(point, leaf);
+            if (distance <= max_distance && (!best || distance < best->distance))
+            {
+                best = nearest_leaf{leaf, distance};
+            }
+        }
+        return best;
+    }
+
+    leaf_cursor occupancy_map::leaves(const vec3& min, const vec3& max, leaf_filter filter) const
+    {
+        const double a[3] = {min.x, min.y, min.z};
+        const double b[3] = {max.x, max.y, max.z};
+        for (unsigned i = 0; i < 3; ++i)
+        {
+            if (std::isnan(a[i]) || std::isnan(b[i]) || a[i] > b[i])
+            {
+                throw error(
+                    error_code::invalid_argument,
+                    fmt::format(
+                        "occupancy_map: bad box ({}, {}, {}) to ({}, {}, {})", min.x, min.y, min.z, max.x, max.y, max.z));
+            }
+        }
+        if (filter != leaf_filter::occupied && filter != leaf_filter::free && filter != leaf_filter::known)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("occupancy_map: unknown leaf filter {}", static_cast<int32_t>(filter)));
+        }
+        const octomap::OcTree& tree = m_impl->tree;
+        auto state = std::make_unique<leaf_cursor::impl>(&tree, tree.end_leafs_bbx(), tree.end_leafs_bbx(), filter);
+        octomap::OcTreeKey lo;
+        octomap::OcTreeKey hi;
+        if (m_impl->key_box(min, max, lo, hi))
+        {
+            state->it = tree.begin_leafs_bbx(lo, hi);
+        }
+        return leaf_cursor(std::move(state));
+    }
+
+    std::vector<std::uint8_t> occupancy_map::serialize(octree_format format) const
+    {
+        first_line(format);
+        std::ostringstream stream(std::ios::binary);
+        // octomap prints the resolution with the stream's precision, 6 digits
+        // by default; keep every digit so it reads back unchanged.
+        stream.precision(std::numeric_limits<double>::max_digits10);
+        if (format == octree_format::binary)
+        {
+            m_impl->tree.writeBinaryConst(stream);
+        }
+        else
+        {
+            m_impl->tree.write(stream);
+        }
+        if (!stream)
+        {
+            throw error(error_code::internal, "occupancy_map: octomap failed to write the map");
+        }
+        const std::string bytes = std::move(stream).str();
+        return {bytes.begin(), bytes.end()};
+    }
+
+    occupancy_map occupancy_map::deserialize(std::span<const std::uint8_t> data, octree_format format)
+    {
+        const std::string_view text(reinterpret_cast<const char*>(data.data()), data.size());
+        const header h = parse_header(text, format);
+        auto state = std::make_unique<impl>(h.resolution);
+        if (h.size == 0)
+        {
+            return occupancy_map(std::move(state));
+        }
+
+        const std::string_view nodes = text.substr(h.data);
+        node_reader reader(nodes);
+        const std::size_t count = format == octree_format::binary ? walk_binary(reader, 0) : walk_full(reader, 0);
+        if (count != h.size)
+        {
+            malformed(fmt::format("header says {} nodes, the data holds {}", h.size, count));
+        }
+
+        std::istringstream stream(std::string(nodes), std::ios::binary);
+        if (format == octree_format::binary)
+        {
+            state->tree.readBinaryData(stream);
+        }
+        else
+        {
+            state->tree.readData(stream);
+        }
+        if (!stream || state->tree.size() != count)
+        {
+            throw error(error_code::internal, "occupancy_map: octomap failed to read checked node data");
+        }
+        return occupancy_map(std::move(state));
+    }
+}
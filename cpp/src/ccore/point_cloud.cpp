@@ -0,0 +1,389 @@
+#include "ccore/point_cloud.hpp"
+#include "ccore/error.hpp"
+#include "ccore/kdtree.hpp"
+
+#include <Eigen/Eigenvalues>
+#include <spdlog/fmt/fmt.h>
+
+#include <algorithm>
+#include <array>
+#include <cmath>
+#include <limits>
+#include <utility>
+#include <vector>
+
+namespace by2
+{
+    namespace
+    {
+        bool is_finite_point(const point_cloud_view& cloud, std::size_t i)
+        {
+            return std::isfinite(cloud.x[i]) && std::isfinite(cloud.y[i]) && std::isfinite(cloud.z[i]);
+        }
+
+        std::array<double, 3> point_at(const point_cloud_view& cloud, std::size_t i)
+        {
+            return {cloud.x[i], cloud.y[i], cloud.z[i]};
+        }
+
+        void expect_capacity(std::size_t capacity, std::size_t count, const char* what)
+        {
+            if (capacity < count)
+            {
+                throw error(
+                    error_code::invalid_argument,
+                    fmt::format("{} has room for {} entries but the cloud has {} points", what, capacity, count));
+            }
+        }
+
+        // Writes the indices of the points that pass `keep`, in order.
+        template <typename F>
+        std::size_t select(const point_cloud_view& cloud, std::span<std::size_t> out, F&& keep)
+        {
+            std::size_t n = 0;
+            for (std::size_t i = 0; i < cloud.count; ++i)
+            {
+                if (is_finite_point(cloud, i) && keep(i))
+                {
+                    out[n++] = i;
+                }
+            }
+            return n;
+        }
+    }
+
+    void validate(const point_cloud_view& cloud)
+    {
+        if (cloud.count == 0)
+        {
+            return;
+        }
+        if (cloud.x == nullptr || cloud.y == nullptr || cloud.z == nullptr)
+        {
+            throw error(error_code::invalid_argument, "point cloud: x, y and z must not be null");
+        }
+        const auto all_or_none = [](const void* a, const void* b, const void* c) {
+            return (a == nullptr) == (b == nullptr) && (b == nullptr) == (c == nullptr);
+        };
+        if (!all_or_none(cloud.normal_x, cloud.normal_y, cloud.normal_z))
+        {
+            throw error(error_code::invalid_argument, "point cloud: normals must be all set or all null");
+        }
+        if (!all_or_none(cloud.red, cloud.green, cloud.blue))
+        {
+            throw error(error_code::invalid_argument, "point cloud: colours must be all set or all null");
+        }
+    }
+
+    aabb point_cloud_bounds(const point_cloud_view& cloud)
+    {
+        validate(cloud);
+        if (cloud.count == 0)
+        {
+            throw error(error_code::invalid_argument, "point cloud bounds: the cloud is empty");
+        }
+
+        constexpr double inf = std::numeric_limits<double>::infinity();
+        aabb box{{inf, inf, inf}, {-inf, -inf, -inf}};
+        const auto extend = [](double& lo, double& hi, float value) {
+            // Comparisons with NaN are false, so NaN never moves a bound.
+            if (value < lo)
+            {
+                lo = value;
+            }
+            if (value > hi)
+            {
+                hi = value;
+            }
+        };
+        for (std::size_t i = 0; i < cloud.count; ++i)
+        {
+            extend(box.min.x, box.max.x, cloud.x[i]);
+            extend(box.min.y, box.max.y, cloud.y[i]);
+            extend(box.min.z, box.max.z, cloud.z[i]);
+        }
+        return box;
+    }
+
+    std::size_t voxel_downsample(const point_cloud_view& cloud, double voxel_size, const point_cloud_buffers& out)
+    {
+        validate(cloud);
+        if (!std::isfinite(voxel_size) || voxel_size <= 0.0)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("voxel downsample: voxel_size must be positive and finite, got {}", voxel_size));
+        }
+        expect_capacity(out.capacity, cloud.count, "voxel downsample: out");
+        if (out.x == nullptr || out.y == nullptr || out.z == nullptr)
+        {
+            throw error(error_code::invalid_argument, "voxel downsample: out x, y and z must not be null");
+        }
+
+        // Sorting (voxel, index) pairs groups the points of a voxel and
+        // keeps them in input order, so the sums below are deterministic.
+        using voxel = std::array<std::int64_t, 3>;
+        std::vector<std::pair<voxel, std::size_t>> cells;
+        cells.reserve(cloud.count);
+        for (std::size_t i = 0; i < cloud.count; ++i)
+        {
+            if (!is_finite_point(cloud, i))
+            {
+                continue;
+            }
+            voxel key{};
+            const auto p = point_at(cloud, i);
+            for (std::size_t axis = 0; axis < 3; ++axis)
+            {
+                const double cell = std::floor(p[axis] / voxel_size);
+                if (!(std::abs(cell) < 0x1p62))
+                {
+                    throw error(
+                        error_code::invalid_argument,
+                        fmt::format(
+                            "voxel downsample: voxel_size {} is too small for the point ({}, {}, {})",
+                            voxel_size,
+                            p[0],
+                            p[1],
+                            p[2]));
+                }
+                key[axis] = static_cast<std::int64_t>(cell);
+            }
+            cells.emplace_back(key, i);
+        }
+        std::sort(cells.begin(), cells.end());
+
+        const bool normals = cloud.normal_x != nullptr;
+        const bool colors = cloud.red != nullptr;
+        const bool intensity = cloud.intensity != nullptr && out.intensity != nullptr;
+        std::size_t n = 0;
+        for (auto begin = cells.begin(); begin != cells.end(); ++n)
+        {
+            const auto end = std::find_if(begin, cells.end(), [&](const auto& c) { return c.first != begin->first; });
+            std::array<double, 10> sum{};
+            for (auto it = begin; it != end; ++it)
+            {
+                const std::size_t i = it->second;
+                sum[0] += cloud.x[i];
+                sum[1] += cloud.y[i];
+                sum[2] += cloud.z[i];
+                if (normals)
+                {
+                    sum[3] += cloud.normal_x[i];
+                    sum[4] += cloud.normal_y[i];
+                    sum[5] += cloud.normal_z[i];
+                }
+                if (colors)
+                {
+                    sum[6] += cloud.red[i];
+                    sum[7] += cloud.green[i];
+                    sum[8] += cloud.blue[i];
+                }
+                if (intensity)
+                {
+                    sum[9] += cloud.intensity[i];
+                }
+            }
+
+            const auto count = static_cast<double>(end - begin);
+            const auto mean = [&](std::size_t k) { return static_cast<float>(sum[k] / count); };
+            const auto color = [&](std::size_t k) { return static_cast<std::uint8_t>(std::round(sum[k] / count)); };
+            const auto write = [n](auto* column, auto value) {
+                if (column != nullptr)
+                {
+                    column[n] = value;
+                }
+            };
+            out.x[n] = mean(0);
+            out.y[n] = mean(1);
+            out.z[n] = mean(2);
+            if (normals)
+            {
+                write(out.normal_x, mean(3));
+                write(out.normal_y, mean(4));
+                write(out.normal_z, mean(5));
+            }
+            if (colors)
+            {
+                write(out.red, color(6));
+                write(out.green, color(7));
+                write(out.blue, color(8));
+            }
+            if (intensity)
+            {
+                out.intensity[n] = mean(9);
+            }
+            begin = end;
+        }
+        return n;
+    }
+
+    std::size_t statistical_inliers(const point_cloud_view& cloud, std::size_t k, double std_ratio, std::span<std::size_t> inliers)
+    {
+        validate(cloud);
+        if (k == 0 || !std::isfinite(std_ratio))
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("statistical outlier removal: needs k > 0 and a finite std_ratio, got {} and {}", k, std_ratio));
+        }
+        expect_capacity(inliers.size(), cloud.count, "statistical outlier removal: inliers");
+        const kdtree tree(cloud);
+        if (tree.size() <= k)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("statistical outlier removal: needs more than k = {} finite points, got {}", k, tree.size()));
+        }
+
+        std::vector<double> mean_distance(cloud.count, std::numeric_limits<double>::quiet_NaN());
+        std::vector<neighbor> found;
+        double total = 0.0;
+        for (std::size_t i = 0; i < cloud.count; ++i)
+        {
+            if (!is_finite_point(cloud, i))
+            {
+                continue;
+            }
+            // The point itself is normally the first hit. It can be missing
+            // when more than k points share its position and have smaller
+            // indices, and then the last hit is the extra one.
+            tree.knn(point_at(cloud, i), k + 1, found);
+            const auto self = std::find_if(found.begin(), found.end(), [i](const neighbor& n) { return n.second == i; });
+            found.erase(self != found.end() ? self : found.end() - 1);
+
+            double sum = 0.0;
+            for (const auto& [distance_sq, j] : found)
+            {
+                sum += std::sqrt(distance_sq);
+            }
+            mean_distance[i] = sum / static_cast<double>(k);
+            total += mean_distance[i];
+        }
+
+        const auto n = static_cast<double>(tree.size());
+        const double mean = total / n;
+        double deviations = 0.0;
+        for (const double d : mean_distance)
+        {
+            if (!std::isnan(d))
+            {
+                deviations += (d - mean) * (d - mean);
+            }
+        }
+        const double threshold = mean + std_ratio * std::sqrt(deviations / (n - 1.0));
+        return select(cloud, inliers, [&](std::size_t i) { return mean_distance[i] <= threshold; });
+    }
+
+    std::size_t radius_inliers(const point_cloud_view& cloud, double radius, std::size_t min_neighbors, std::span<std::size_t> inliers)
+    {
+        validate(cloud);
+        if (!std::isfinite(radius) || radius < 0.0)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("radius outlier removal: radius must be finite and non-negative, got {}", radius));
+        }
+        expect_capacity(inliers.size(), cloud.count, "radius outlier removal: inliers");
+        const kdtree tree(cloud);
+        std::vector<neighbor> found;
+        return select(cloud, inliers, [&](std::size_t i) {
+            // The point itself is always within the radius.
+            tree.radius(point_at(cloud, i), radius, found);
+            return found.size() - 1 >= min_neighbors;
+        });
+    }
+
+    std::size_t crop(const point_cloud_view& cloud, const aabb& box, std::span<std::size_t> inliers)
+    {
+        validate(cloud);
+        const std::array<double, 3> min{box.min.x, box.min.y, box.min.z};
+        const std::array<double, 3> max{box.max.x, box.max.y, box.max.z};
+        for (std::size_t axis = 0; axis < 3; ++axis)
+        {
+            if (!(min[axis] <= max[axis]))
+            {
+                throw error(
+                    error_code::invalid_argument,
+                    fmt::format("crop: box min {} is not <= max {} on axis {}", min[axis], max[axis], axis));
+            }
+        }
+        expect_capacity(inliers.size(), cloud.count, "crop: inliers");
+        return select(cloud, inliers, [&](std::size_t i) {
+            const auto p = point_at(cloud, i);
+            for (std::size_t axis = 0; axis < 3; ++axis)
+            {
+                if (p[axis] < min[axis] || p[axis] > max[axis])
+                {
+                    return false;
+                }
+            }
+            return true;
+        });
+    }
+
+    void estimate_normals(
+        const point_cloud_view& cloud,
+        std::size_t k,
+        const vec3& viewpoint,
+        std::span<float> normal_x,
+        std::span<float> normal_y,
+        std::span<float> normal_z)
+    {
+        validate(cloud);
+        if (k < 3)
+        {
+            throw error(error_code::invalid_argument, fmt::format("normal estimation: needs k >= 3, got {}", k));
+        }
+        expect_capacity(normal_x.size(), cloud.count, "normal estimation: normal_x");
+        expect_capacity(normal_y.size(), cloud.count, "normal estimation: normal_y");
+        expect_capacity(normal_z.size(), cloud.count, "normal estimation: normal_z");
+
+        const kdtree tree(cloud);
+        const Eigen::Vector3d eye(viewpoint.x, viewpoint.y, viewpoint.z);
+        std::vector<neighbor> found;
+        for (std::size_t i = 0; i < cloud.count; ++i)
+        {
+            constexpr float nan = std::numeric_limits<float>::quiet_NaN();
+            normal_x[i] = normal_y[i] = normal_z[i] = nan;
+            if (!is_finite_point(cloud, i))
+            {
+                continue;
+            }
+            tree.knn(point_at(cloud, i), k, found);
+            if (found.size() < 3)
+            {
+                continue;
+            }
+
+            const auto position = [&](std::size_t j) {
+                const auto p = point_at(cloud, j);
+                return Eigen::Vector3d(p[0], p[1], p[2]);
+            };
+            Eigen::Vector3d centroid = Eigen::Vector3d::Zero();
+            for (const auto& [distance_sq, j] : found)
+            {
+                centroid += position(j);
+            }
+            centroid /= static_cast<double>(found.size());
+            Eigen::Matrix3d covariance = Eigen::Matrix3d::Zero();
+            for (const auto& [distance_sq, j] : found)
+            {
+                const Eigen::Vector3d d = position(j) - centroid;
+                covariance += d * d.transpose();
+            }
+            covariance /= static_cast<double>(found.size());
+
+            // Eigenvalues come out in increasing order.
+            const Eigen::SelfAdjointEigenSolver<Eigen::Matrix3d> solver(covariance);
+            Eigen::Vector3d normal = solver.eigenvectors().col(0);
+            if (normal.dot(eye - position(i)) < 0.0)
+            {
+                normal = -normal;
+            }
+            normal_x[i] = static_cast<float>(normal.x());
+            normal_y[i] = static_cast<float>(normal.y());
+            normal_z[i] = static_cast<float>(normal.z());
+        }
+    }
+}
@@ -0,0 +1,261 @@
+#include "ccore/registration.hpp"
+#include "ccore/error.hpp"
+#include "ccore/kdtree.hpp"
+
+#include <Eigen/Cholesky>
+#include <Eigen/Geometry>
+#include <spdlog/fmt/fmt.h>
+
+#include <cmath>
+#include <limits>
+#include <vector>
+
+namespace by2
+{
+    namespace
+    {
+        // p -> rotation * p + translation.
+        struct rigid
+        {
+            Eigen::Matrix3d rotation;
+            Eigen::Vector3d translation;
+        };
+
+        // Apply b, then a.
+        rigid compose(const rigid& a, const rigid& b)
+        {
+            return {a.rotation * b.rotation, a.rotation * b.translation + a.translation};
+        }
+
+        rigid to_rigid(const pose& p)
+        {
+            const Eigen::Quaterniond q(p.rotation.w, p.rotation.x, p.rotation.y, p.rotation.z);
+            const Eigen::Vector3d t(p.translation.x, p.translation.y, p.translation.z);
+            const double norm = q.norm();
+            if (!std::isfinite(norm) || norm < 1e-12 || !t.allFinite())
+            {
+                throw error(error_code::invalid_argument, "icp: initial pose is not a finite rigid transform");
+            }
+            return {q.normalized().toRotationMatrix(), t};
+        }
+
+        // Unit quaternion with w >= 0, as geometry.hpp returns them.
+        pose to_pose(const rigid& r)
+        {
+            Eigen::Quaterniond q(r.rotation);
+            q.normalize();
+            const double sign = q.w() < 0.0 ? -1.0 : 1.0;
+            return {
+                {sign * q.w(), sign * q.x(), sign * q.y(), sign * q.z()},
+                {r.translation.x(), r.translation.y(), r.translation.z()},
+            };
+        }
+
+        double rotation_angle(const Eigen::Matrix3d& rotation)
+        {
+            const Eigen::Quaterniond q(rotation);
+            return 2.0 * std::atan2(q.vec().norm(), std::abs(q.w()));
+        }
+
+        void validate_options(const icp_options& options)
+        {
+            if (options.method != icp_method::point_to_point && options.method != icp_method::point_to_plane)
+            {
+                throw error(
+                    error_code::invalid_argument,
+                    fmt::format("icp: unknown method {}", static_cast<int32_t>(options.method)));
+            }
+            if (!(options.max_correspondence_distance > 0.0))
+            {
+                throw error(
+                    error_code::invalid_argument,
+                    fmt::format(
+                        "icp: max_correspondence_distance must be positive, got {}",
+                        options.max_correspondence_distance));
+            }
+            if (!(options.translation_tolerance >= 0.0) || !(options.rotation_tolerance >= 0.0))
+            {
+                throw error(
+                    error_code::invalid_argument,
+                    fmt::format(
+                        "icp: tolerances must be non-negative, got translation {} and rotation {}",
+                        options.translation_tolerance,
+                        options.rotation_tolerance));
+            }
+        }
+
+        struct correspondence
+        {
+            // Source point, transformed by the current estimate.
+            Eigen::Vector3d source;
+            std::size_t target;
+        };
+
+        class aligner
+        {
+        public:
+            aligner(const point_cloud_view& source, const point_cloud_view& target, const icp_options& options)
+                : m_target(target)
+                , m_tree(indexed(target, options.method, m_masked_x))
+                , m_max_distance_sq(options.max_correspondence_distance * options.max_correspondence_distance)
+            {
+                m_source.reserve(source.count);
+                for (std::size_t i = 0; i < source.count; ++i)
+                {
+                    const Eigen::Vector3d p(source.x[i], source.y[i], source.z[i]);
+                    if (p.allFinite())
+                    {
+                        m_source.push_back(p);
+                    }
+                }
+            }
+
+            // Pairs each source point, moved by `estimate`, with its nearest
+            // target point within the correspondence distance.
+            icp_iteration match(const rigid& estimate, std::vector<correspondence>& out) const
+            {
+                out.clear();
+                std::vector<neighbor> found;
+                double sum_sq = 0.0;
+                for (const Eigen::Vector3d& p : m_source)
+                {
+                    const Eigen::Vector3d moved = estimate.rotation * p + estimate.translation;
+                    m_tree.knn({moved.x(), moved.y(), moved.z()}, 1, found);
+                    if (found.empty() || !(found.front().first <= m_max_distance_sq))
+                    {
+                        continue;
+                    }
+                    out.push_back({moved, found.front().second});
+                    sum_sq += found.front().first;
+                }
+                const double n = static_cast<double>(out.size());
+                return {
+                    m_source.empty() ? 0.0 : n / static_cast<double>(m_source.size()),
+                    out.empty() ? 0.0 : std::sqrt(sum_sq / n),
+                    out.size(),
+                };
+            }
+
+            // Closed-form alignment of the pairs (Umeyama without scaling).
+            rigid point_to_point(const std::vector<correspondence>& pairs) const
+            {
+                Eigen::Matrix3Xd from(3, pairs.size());
+                Eigen::Matrix3Xd to(3, pairs.size());
+                for (std::size_t k = 0; k < pairs.size(); ++k)
+                {
+                    from.col(k) = pairs[k].source;
+                    to.col(k) = target_point(pairs[k].target);
+                }
+                const Eigen::Matrix4d m = Eigen::umeyama(from, to, false);
+                return {m.topLeftCorner<3, 3>(), m.topRightCorner<3, 1>()};
+            }
+
+            // One Gauss-Newton step on the point-to-plane distances, with the
+            // rotation linearized around the current estimate. Directions the
+            // pairs don't constrain (e.g. sliding along a plane) are left
+            // alone: LDLT zeroes them instead of failing.
+            rigid point_to_plane(const std::vector<correspondence>& pairs) const
+            {
+                Eigen::Matrix<double, 6, 6> normal_matrix = Eigen::Matrix<double, 6, 6>::Zero();
+                Eigen::Matrix<double, 6, 1> rhs = Eigen::Matrix<double, 6, 1>::Zero();
+                for (const correspondence& c : pairs)
+                {
+                    const Eigen::Vector3d n = target_normal(c.target);
+                    const double residual = n.dot(c.source - target_point(c.target));
+                    Eigen::Matrix<double, 6, 1> jacobian;
+                    jacobian << c.source.cross(n), n;
+                    normal_matrix += jacobian * jacobian.transpose();
+                    rhs -= jacobian * residual;
+                }
+                const Eigen::Matrix<double, 6, 1> step = normal_matrix.ldlt().solve(rhs);
+
+                const Eigen::Vector3d omega = step.head<3>();
+                const double angle = omega.norm();
+                const Eigen::Matrix3d rotation = angle > 0.0
+                                                     ? Eigen::AngleAxisd(angle, omega / angle).toRotationMatrix()
+                                                     : Eigen::Matrix3d::Identity();
+                return {rotation, step.tail<3>()};
+            }
+
+        private:
+            // For point-to-plane, hides the target points without a finite
+            // normal from the tree by giving them a NaN coordinate.
+            static point_cloud_view indexed(const point_cloud_view& target, icp_method method, std::vector<float>& masked_x)
+            {
+                validate(target);
+                if (method != icp_method::point_to_plane)
+                {
+                    return target;
+                }
+                if (target.count > 0 && target.normal_x == nullptr)
+                {
+                    throw error(error_code::invalid_argument, "icp: point-to-plane needs target normals");
+                }
+                masked_x.assign(target.x, target.x + target.count);
+                for (std::size_t i = 0; i < target.count; ++i)
+                {
+                    if (!std::isfinite(target.normal_x[i]) || !std::isfinite(target.normal_y[i]) || !std::isfinite(target.normal_z[i]))
+                    {
+                        masked_x[i] = std::numeric_limits<float>::quiet_NaN();
+                    }
+                }
+                point_cloud_view view = target;
+                view.x = masked_x.data();
+                return view;
+            }
+
+            Eigen::Vector3d target_point(std::size_t i) const
+            {
+                return {m_target.x[i], m_target.y[i], m_target.z[i]};
+            }
+
+            Eigen::Vector3d target_normal(std::size_t i) const
+            {
+                return {m_target.normal_x[i], m_target.normal_y[i], m_target.normal_z[i]};
+            }
+
+            point_cloud_view m_target;
+            std::vector<float> m_masked_x;
+            kdtree m_tree;
+            double m_max_distance_sq;
+            std::vector<Eigen::Vector3d> m_source;
+        };
+    }
+
+    icp_result icp(const point_cloud_view& source, const point_cloud_view& target, const icp_options& options)
+    {
+        validate(source);
+        validate_options(options);
+        const bool plane = options.method == icp_method::point_to_plane;
+        const std::size_t needed = plane ? 6 : 3;
+        const aligner align(source, target, options);
+
+        rigid estimate = to_rigid(options.initial_pose);
+        icp_result result{};
+        result.termination = icp_termination::max_iterations;
+        std::vector<correspondence> pairs;
+        for (std::size_t iteration = 0; iteration < options.max_iterations; ++iteration)
+        {
+            const icp_iteration before = align.match(estimate, pairs);
+            if (pairs.size() < needed)
+            {
+                result.termination = icp_termination::too_few_correspondences;
+                break;
+            }
+            result.history.push_back(before);
+
+            const rigid step = plane ? align.point_to_plane(pairs) : align.point_to_point(pairs);
+            estimate = compose(step, estimate);
+            if (step.translation.norm() < options.translation_tolerance &&
+                rotation_angle(step.rotation) < options.rotation_tolerance)
+            {
+                result.converged = true;
+                result.termination = icp_termination::converged;
+                break;
+            }
+        }
+        result.alignment = align.match(estimate, pairs);
+        result.transform = to_pose(estimate);
+        return result;
+    }
+}
@@ -0,0 +1,157 @@
+#include "ccore/scaler.hpp"
+#include "ccore/error.hpp"
+#include "ffmpeg.hpp"
+
+#include <spdlog/fmt/fmt.h>
+
+#include <array>
+
+namespace by2
+{
+    namespace
+    {
+        int to_sws(interpolation method)
+        {
+            switch (method)
+            {
+            case interpolation::bilinear:
+                return SWS_BILINEAR;
+            case interpolation::bicubic:
+                return SWS_BICUBIC;
+            case interpolation::area:
+                return SWS_AREA;
+            }
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("scaler: unknown interpolation {}", static_cast<int32_t>(method)));
+        }
+
+        // The planes of a packed frame in `data`.
+        struct planes
+        {
+            std::array<std::uint8_t*, 4> data{};
+            std::array<int, 4> strides{};
+        };
+
+        planes split(std::uint8_t* data, AVPixelFormat format, int32_t width, int32_t height)
+        {
+            planes result;
+            detail::check_av(
+                av_image_fill_arrays(result.data.data(), result.strides.data(), data, format, width, height, 1),
+                "scaler: bad frame");
+            return result;
+        }
+    }
+
+    struct scaler::impl
+    {
+        detail::av_ptr<SwsContext> context;
+        AVPixelFormat source_format = AV_PIX_FMT_NONE;
+        AVPixelFormat destination_format = AV_PIX_FMT_NONE;
+        int32_t source_width = 0;
+        int32_t source_height = 0;
+        int32_t destination_width = 0;
+        int32_t destination_height = 0;
+        std::size_t source_size = 0;
+        std::size_t destination_size = 0;
+    };
+
+    scaler::scaler(
+        int32_t source_width,
+        int32_t source_height,
+        pixel_format source_format,
+        int32_t destination_width,
+        int32_t destination_height,
+        pixel_format destination_format,
+        interpolation method)
+        : m_impl(std::make_unique<impl>())
+    {
+        if (source_width <= 0 || source_height <= 0 || destination_width <= 0 || destination_height <= 0)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format(
+                    "scaler: bad frame sizes {}x{} -> {}x{}",
+                    source_width,
+                    source_height,
+                    destination_width,
+                    destination_height));
+        }
+        impl& s = *m_impl;
+        s.source_format = detail::to_av(source_format);
+        s.destination_format = detail::to_av(destination_format);
+        s.source_width = source_width;
+        s.source_height = source_height;
+        s.destination_width = destination_width;
+        s.destination_height = destination_height;
+        s.source_size = frame_size(source_format, source_width, source_height);
+        s.destination_size = frame_size(destination_format, destination_width, destination_height);
+
+        // Exact rounding, so unscaled conversions between RGB layouts are
+        // lossless.
+        s.context.reset(sws_getContext(
+            source_width,
+            source_height,
+            s.source_format,
+            destination_width,
+            destination_height,
+            s.destination_format,
+            to_sws(method) | SWS_ACCURATE_RND,
+            nullptr,
+            nullptr,
+            nullptr));
+        if (!s.context)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format(
+                    "scaler: swscale can't convert {} to {}",
+                    av_get_pix_fmt_name(s.source_format),
+                    av_get_pix_fmt_name(s.destination_format)));
+        }
+    }
+
+    scaler::~scaler() = default;
+    scaler::scaler(scaler&&) noexcept = default;
+    scaler& scaler::operator=(scaler&&) noexcept = default;
+
+    std::size_t scaler::source_size() const noexcept
+    {
+        return m_impl->source_size;
+    }
+
+    std::size_t scaler::destination_size() const noexcept
+    {
+        return m_impl->destination_size;
+    }
+
+    void scaler::scale(std::span<const std::uint8_t> source, std::span<std::uint8_t> destination)
+    {
+        impl& s = *m_impl;
+        if (source.size() != s.source_size || destination.size() != s.destination_size)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format(
+                    "scaler: frames have {} and {} bytes, got {} and {}",
+                    s.source_size,
+                    s.destination_size,
+                    source.size(),
+                    destination.size()));
+        }
+        // Only read through `in`.
+        const planes in =
+            split(const_cast<std::uint8_t*>(source.data()), s.source_format, s.source_width, s.source_height);
+        const planes out = split(destination.data(), s.destination_format, s.destination_width, s.destination_height);
+        detail::check_av(
+            sws_scale(
+                s.context.get(),
+                in.data.data(),
+                in.strides.data(),
+                0,
+                s.source_height,
+                out.data.data(),
+                out.strides.data()),
+            "scaler: scaling failed");
+    }
+}
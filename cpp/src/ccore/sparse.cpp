@@ -0,0 +1,230 @@
+#include "ccore/sparse.hpp"
+#include "eigen_map.hpp"
+
+#include <Eigen/IterativeLinearSolvers>
+#include <Eigen/OrderingMethods>
+#include <Eigen/SparseCholesky>
+#include <Eigen/SparseCore>
+#include <Eigen/SparseQR>
+
+#include <cmath>
+#include <limits>
+#include <type_traits>
+
+namespace by2
+{
+    namespace
+    {
+        // Eigen wants a signed storage index. size_t and its signed
+        // counterpart may alias each other, so the index arrays are mapped in
+        // place once validate() has checked that every value fits.
+        using sparse_index = std::make_signed_t<std::size_t>;
+
+        template <typename T, int Order>
+        using eigen_sparse = Eigen::SparseMatrix<T, Order, sparse_index>;
+
+        template <typename T>
+        void validate(const sparse_view<T>& a)
+        {
+            if (a.format != sparse_format::csr && a.format != sparse_format::csc)
+            {
+                throw error(
+                    error_code::invalid_argument,
+                    fmt::format("unknown sparse format {}", static_cast<int32_t>(a.format)));
+            }
+            constexpr auto max_index = static_cast<std::size_t>(std::numeric_limits<sparse_index>::max());
+            if (a.rows >= max_index || a.cols >= max_index)
+            {
+                throw error(error_code::invalid_argument, "sparse matrix dimensions overflow the index type");
+            }
+            if (a.outer_offsets == nullptr)
+            {
+                throw error(error_code::invalid_argument, "sparse matrix: outer_offsets is null");
+            }
+
+            const bool csr = a.format == sparse_format::csr;
+            const std::size_t outer = csr ? a.rows : a.cols;
+            const std::size_t inner = csr ? a.cols : a.rows;
+            const std::size_t nnz = a.outer_offsets[outer];
+            if (a.outer_offsets[0] != 0)
+            {
+                throw error(error_code::invalid_argument, "sparse matrix: outer_offsets must start at 0");
+            }
+            if (nnz >= max_index)
+            {
+                throw error(error_code::invalid_argument, "sparse matrix: too many non-zeros for the index type");
+            }
+            if (nnz > 0 && (a.inner_indices == nullptr || a.values == nullptr))
+            {
+                throw error(error_code::invalid_argument, "sparse matrix: inner_indices or values is null");
+            }
+
+            for (std::size_t k = 0; k < outer; ++k)
+            {
+                const std::size_t begin = a.outer_offsets[k];
+                const std::size_t end = a.outer_offsets[k + 1];
+                if (end < begin || end > nnz)
+                {
+                    throw error(
+                        error_code::invalid_argument,
+                        fmt::format("sparse matrix: outer_offsets[{}] = {} is out of order", k + 1, end));
+                }
+                for (std::size_t i = begin; i < end; ++i)
+                {
+                    if (a.inner_indices[i] >= inner)
+                    {
+                        throw error(
+                            error_code::invalid_argument,
+                            fmt::format(
+                                "sparse matrix: index {} at position {} is out of range for a {}x{} matrix",
+                                a.inner_indices[i],
+                                i,
+                                a.rows,
+                                a.cols));
+                    }
+                    if (i > begin && a.inner_indices[i] <= a.inner_indices[i - 1])
+                    {
+                        throw error(
+                            error_code::invalid_argument,
+                            fmt::format(
+                                "sparse matrix: indices of {} {} are not strictly increasing",
+                                csr ? "row" : "column",
+                                k));
+                    }
+                }
+            }
+        }
+
+        // Calls `f` with an Eigen::Map of `a` in its own storage order.
+        template <typename T, typename F>
+        void with_eigen(const sparse_view<T>& a, F&& f)
+        {
+            validate(a);
+            const auto rows = static_cast<Eigen::Index>(a.rows);
+            const auto cols = static_cast<Eigen::Index>(a.cols);
+            const auto* outer = reinterpret_cast<const sparse_index*>(a.outer_offsets);
+            const auto* inner = reinterpret_cast<const sparse_index*>(a.inner_indices);
+            if (a.format == sparse_format::csr)
+            {
+                f(Eigen::Map<const eigen_sparse<T, Eigen::RowMajor>>(rows, cols, outer[rows], outer, inner, a.values));
+            }
+            else
+            {
+                f(Eigen::Map<const eigen_sparse<T, Eigen::ColMajor>>(rows, cols, outer[cols], outer, inner, a.values));
+            }
+        }
+    }
+
+    template <typename T>
+    void sparse_multiply(sparse_view<T> a, matrix_view<const T> b, matrix_view<T> out)
+    {
+        if (a.cols != b.rows)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("sparse multiply: inner dimensions differ ({}x{} * {}x{})", a.rows, a.cols, b.rows, b.cols));
+        }
+        detail::expect_shape(out, a.rows, b.cols, "out");
+
+        const auto b_map = detail::as_eigen(b, "b");
+        auto out_map = detail::as_eigen(out, "out");
+        with_eigen(a, [&](const auto& a_map) { out_map = a_map * b_map; });
+    }
+
+    template <typename T>
+    void sparse_solve(const sparse_solve_options& options, sparse_view<T> a, matrix_view<const T> b, matrix_view<T> x)
+    {
+        if (b.rows != a.rows)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("sparse solve: a has {} rows but b has {}", a.rows, b.rows));
+        }
+        detail::expect_shape(x, a.cols, b.cols, "x");
+        if (options.solver != sparse_solver::qr && a.rows != a.cols)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("sparse solve: {}x{} matrix is not square", a.rows, a.cols));
+        }
+
+        const auto b_map = detail::as_eigen(b, "b");
+        auto x_map = detail::as_eigen(x, "x");
+
+        // The solvers all take compressed column-major storage.
+        eigen_sparse<T, Eigen::ColMajor> a_csc;
+        with_eigen(a, [&](const auto& a_map) { a_csc = a_map; });
+        a_csc.makeCompressed();
+
+        switch (options.solver)
+        {
+        case sparse_solver::simplicial_ldlt:
+        {
+            const Eigen::SimplicialLDLT<eigen_sparse<T, Eigen::ColMajor>> ldlt(a_csc);
+            if (ldlt.info() != Eigen::Success)
+            {
+                throw error(error_code::singular, "sparse solve: matrix is singular or has a zero pivot");
+            }
+            x_map = ldlt.solve(b_map);
+            break;
+        }
+        case sparse_solver::qr:
+        {
+            if (a.rows < a.cols)
+            {
+                throw error(
+                    error_code::invalid_argument,
+                    fmt::format("sparse solve: qr needs at least as many rows as columns, got {}x{}", a.rows, a.cols));
+            }
+            Eigen::SparseQR<eigen_sparse<T, Eigen::ColMajor>, Eigen::COLAMDOrdering<sparse_index>> qr(a_csc);
+            if (qr.info() != Eigen::Success || qr.rank() < static_cast<Eigen::Index>(a.cols))
+            {
+                throw error(error_code::singular, "sparse solve: matrix is rank deficient");
+            }
+            x_map = qr.solve(b_map);
+            break;
+        }
+        case sparse_solver::conjugate_gradient:
+        {
+            if (!std::isfinite(options.tolerance) || options.tolerance < 0.0)
+            {
+                throw error(error_code::invalid_argument, "sparse solve: tolerance must be finite and non-negative");
+            }
+            Eigen::ConjugateGradient<eigen_sparse<T, Eigen::ColMajor>, Eigen::Lower | Eigen::Upper> cg;
+            if (options.max_iterations > 0)
+            {
+                cg.setMaxIterations(static_cast<Eigen::Index>(options.max_iterations));
+            }
+            if (options.tolerance > 0.0)
+            {
+                cg.setTolerance(static_cast<T>(options.tolerance));
+            }
+            cg.compute(a_csc);
+            x_map = cg.solve(b_map);
+            if (cg.info() != Eigen::Success)
+            {
+                throw error(
+                    error_code::not_converged,
+                    fmt::format(
+                        "sparse solve: conjugate gradient stopped after {} iterations at relative residual {}",
+                        cg.iterations(),
+                        static_cast<double>(cg.error())));
+            }
+            break;
+        }
+        default:
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("sparse solve: unknown solver {}", static_cast<int32_t>(options.solver)));
+        }
+    }
+
+#define BY2_INSTANTIATE_SPARSE(T)                                                                                  \
+    template void sparse_multiply<T>(sparse_view<T>, matrix_view<const T>, matrix_view<T>);                        \
+    template void sparse_solve<T>(const sparse_solve_options&, sparse_view<T>, matrix_view<const T>, matrix_view<T>);
+
+    BY2_INSTANTIATE_SPARSE(float)
+    BY2_INSTANTIATE_SPARSE(double)
+
+#undef BY2_INSTANTIATE_SPARSE
+}
@@ -0,0 +1,412 @@
+#include "ccore/video_reader.hpp"
+#include "ccore/error.hpp"
+#include "ffmpeg.hpp"
+
+#include <spdlog/fmt/fmt.h>
+
+#include <algorithm>
+#include <array>
+#include <cmath>
+#include <iterator>
+#include <limits>
+#include <new>
+#include <utility>
+#include <vector>
+
+namespace by2
+{
+    struct video_reader::impl
+    {
+        detail::av_ptr<AVFormatContext> input;
+        detail::av_ptr<AVCodecContext> decoder;
+        detail::av_ptr<AVPacket> packet;
+        detail::av_ptr<AVFrame> frame;
+        detail::av_ptr<SwsContext> scaler;
+        std::vector<stream_info> streams;
+        int stream = -1;
+        pixel_format format = pixel_format::rgb24;
+        AVPixelFormat output_format = AV_PIX_FMT_NONE;
+        int32_t width = 0;
+        int32_t height = 0;
+        bool draining = false;
+        std::optional<video_index> index;
+        // Frame number of the next decoded frame.
+        int64_t next_index = 0;
+        // Take frame numbers from the timestamps rather than next_index; set
+        // by seeks without an index.
+        bool index_from_pts = false;
+        // After a seek: frames before drop_before_pts are left out and not
+        // counted (they precede the keyframe the frame numbers count from),
+        // frames before skip_to_pts or skip_to_index are counted but
+        // skipped.
+        int64_t drop_before_pts = AV_NOPTS_VALUE;
+        int64_t skip_to_pts = AV_NOPTS_VALUE;
+        int64_t skip_to_index = -1;
+
+        const AVStream& video() const
+        {
+            return *input->streams[stream];
+        }
+
+        int64_t start_pts() const
+        {
+            return video().start_time == AV_NOPTS_VALUE ? 0 : video().start_time;
+        }
+
+        // Frames per second, or 0/1 if the stream doesn't say.
+        AVRational frame_rate() const
+        {
+            const AVStream& v = video();
+            for (const AVRational rate : {v.avg_frame_rate, v.r_frame_rate})
+            {
+                if (rate.num > 0 && rate.den > 0)
+                {
+                    return rate;
+                }
+            }
+            return {0, 1};
+        }
+
+        // Moves the demuxer to the last keyframe at or before `pts` and
+        // drops what the decoder holds.
+        void seek(int64_t pts)
+        {
+            detail::check_av(
+                avformat_seek_file(input.get(), stream, std::numeric_limits<int64_t>::min(), pts, pts, 0),
+                "video_reader: seeking failed");
+            avcodec_flush_buffers(decoder.get());
+            av_frame_unref(frame.get());
+            draining = false;
+            next_index = 0;
+            index_from_pts = false;
+            drop_before_pts = AV_NOPTS_VALUE;
+            skip_to_pts = AV_NOPTS_VALUE;
+            skip_to_index = -1;
+        }
+
+        // The last keyframe at or before frame `target`, or at or before
+        // timestamp `target`. The first keyframe if there is none.
+        const video_keyframe& keyframe_before(int64_t target, int64_t video_keyframe::* key) const
+        {
+            const std::vector<video_keyframe>& keyframes = index->keyframes;
+            const auto after = std::upper_bound(
+                keyframes.begin(),
+                keyframes.end(),
+                target,
+                [key](int64_t t, const video_keyframe& k) { return t < k.*key; });
+            return after == keyframes.begin() ? keyframes.front() : *std::prev(after);
+        }
+
+        // Seeks to `keyframe` and counts frames from it.
+        void seek_to_keyframe(const video_keyframe& keyframe)
+        {
+            seek(keyframe.pts);
+            next_index = keyframe.index;
+            drop_before_pts = keyframe.pts;
+        }
+
+        // Frame number of the decoded frame, which has timestamp `pts`.
+        int64_t frame_number(int64_t pts) const
+        {
+            const AVRational rate = frame_rate();
+            if (!index_from_pts || pts == AV_NOPTS_VALUE || rate.num == 0)
+            {
+                return next_index;
+            }
+            const double frames = static_cast<double>(pts - start_pts()) * av_q2d(video().time_base) * av_q2d(rate);
+            return std::llround(frames);
+        }
+
+        // Whether a seek is still on its way to the frame it asked for.
+        bool before_target(int64_t pts, int64_t number) const
+        {
+            const bool known = pts != AV_NOPTS_VALUE;
+            return (known && drop_before_pts != AV_NOPTS_VALUE && pts < drop_before_pts)
+                || (known && skip_to_pts != AV_NOPTS_VALUE && pts < skip_to_pts) || number < skip_to_index;
+        }
+
+        // Converts the decoded frame into `out`.
+        void convert(std::span<std::uint8_t> out)
+        {
+            const AVFrame& f = *frame;
+            scaler.reset(sws_getCachedContext(
+                scaler.release(),
+                f.width,
+                f.height,
+                static_cast<AVPixelFormat>(f.format),
+                width,
+                height,
+                output_format,
+                SWS_BICUBIC,
+                nullptr,
+                nullptr,
+                nullptr));
+            if (!scaler)
+            {
+                const char* name = av_get_pix_fmt_name(static_cast<AVPixelFormat>(f.format));
+                throw error(
+                    error_code::io,
+                    fmt::format(
+                        "video_reader: can't convert {}x{} {} frames",
+                        f.width,
+                        f.height,
+                        name == nullptr ? "unknown" : name));
+            }
+            std::array<std::uint8_t*, 4> planes{};
+            std::array<int, 4> strides{};
+            detail::check_av(
+                av_image_fill_arrays(planes.data(), strides.data(), out.data(), output_format, width, height, 1),
+                "video_reader: bad output frame");
+            sws_scale(scaler.get(), f.data, f.linesize, 0, f.height, planes.data(), strides.data());
+        }
+    };
+
+    video_reader::video_reader(const std::string& path, pixel_format format, int32_t threads)
+        : m_impl(std::make_unique<impl>())
+    {
+        impl& s = *m_impl;
+        s.format = format;
+        s.output_format = detail::to_av(format);
+        s.input = detail::open_input(path);
+        s.streams = detail::describe_streams(*s.input);
+
+        const AVCodec* decoder = nullptr;
+        s.stream = av_find_best_stream(s.input.get(), AVMEDIA_TYPE_VIDEO, -1, -1, &decoder, 0);
+        if (s.stream < 0 || decoder == nullptr)
+        {
+            throw error(error_code::io, fmt::format("video_reader: {} has no decodable video stream", path));
+        }
+        s.decoder = detail::open_decoder(*s.input->streams[s.stream], *decoder, threads);
+        s.width = s.decoder->width;
+        s.height = s.decoder->height;
+        if (s.width <= 0 || s.height <= 0)
+        {
+            throw error(error_code::io, fmt::format("video_reader: the video stream of {} has no frame size", path));
+        }
+
+        s.packet.reset(av_packet_alloc());
+        s.frame.reset(av_frame_alloc());
+        if (!s.packet || !s.frame)
+        {
+            throw std::bad_alloc();
+        }
+    }
+
+    video_reader::~video_reader() = default;
+    video_reader::video_reader(video_reader&&) noexcept = default;
+    video_reader& video_reader::operator=(video_reader&&) noexcept = default;
+
+    std::span<const stream_info> video_reader::streams() const noexcept
+    {
+        return m_impl->streams;
+    }
+
+    std::size_t video_reader::video_stream() const noexcept
+    {
+        return static_cast<std::size_t>(m_impl->stream);
+    }
+
+    pixel_format video_reader::format() const noexcept
+    {
+        return m_impl->format;
+    }
+
+    std::size_t video_reader::frame_size() const noexcept
+    {
+        return by2::frame_size(m_impl->format, m_impl->width, m_impl->height);
+    }
+
+    std::optional<video_frame_info> video_reader::next(std::span<std::uint8_t> out)
+    {
+        impl& s = *m_impl;
+        if (out.size() < frame_size())
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("video_reader: a frame needs {} bytes, got {}", frame_size(), out.size()));
+        }
+        int64_t pts = AV_NOPTS_VALUE;
+        int64_t number = 0;
+        while (true)
+        {
+            if (!detail::decode_next(*s.input, s.stream, *s.decoder, *s.packet, *s.frame, s.draining))
+            {
+                return std::nullopt;
+            }
+            pts = s.frame->best_effort_timestamp;
+            const bool dropped = pts != AV_NOPTS_VALUE && s.drop_before_pts != AV_NOPTS_VALUE && pts < s.drop_before_pts;
+            if (!dropped)
+            {
+                number = s.frame_number(pts);
+                s.next_index = number + 1;
+                if (!s.before_target(pts, number))
+                {
+                    break;
+                }
+            }
+            av_frame_unref(s.frame.get());
+        }
+        // Past the target, so later frames follow it.
+        s.drop_before_pts = AV_NOPTS_VALUE;
+        s.skip_to_pts = AV_NOPTS_VALUE;
+        s.skip_to_index = -1;
+
+        s.convert(out);
+        video_frame_info info{};
+        info.pts = pts;
+        info.time = pts == AV_NOPTS_VALUE ? std::numeric_limits<double>::quiet_NaN()
+                                          : static_cast<double>(pts) * av_q2d(s.video().time_base);
+        info.index = number;
+        info.key_frame = detail::is_key_frame(*s.frame);
+        av_frame_unref(s.frame.get());
+        return info;
+    }
+
+    void video_reader::seek_to_time(double seconds)
+    {
+        impl& s = *m_impl;
+        if (!std::isfinite(seconds))
+        {
+            throw error(error_code::invalid_argument, fmt::format("video_reader: can't seek to {} s", seconds));
+        }
+        if (!s.index && s.frame_rate().num == 0)
+        {
+            build_index();
+        }
+        // Less a millionth of a tick, so rounding errors in `seconds` don't
+        // skip the frame it names.
+        const double ticks = std::ceil(seconds / av_q2d(s.video().time_base) - 1e-6);
+        const double limit = static_cast<double>(std::numeric_limits<int64_t>::max() / 2);
+        const int64_t target = std::max(static_cast<int64_t>(std::clamp(ticks, -limit, limit)), s.start_pts());
+        if (s.index && !s.index->keyframes.empty())
+        {
+            s.seek_to_keyframe(s.keyframe_before(target, &video_keyframe::pts));
+        }
+        else
+        {
+            s.seek(target);
+            s.index_from_pts = true;
+        }
+        s.skip_to_pts = target;
+    }
+
+    void video_reader::seek_to_frame(int64_t index)
+    {
+        impl& s = *m_impl;
+        if (index < 0)
+        {
+            throw error(error_code::invalid_argument, fmt::format("video_reader: can't seek to frame {}", index));
+        }
+        if (!s.index && s.frame_rate().num == 0)
+        {
+            build_index();
+        }
+        if (s.index && !s.index->keyframes.empty())
+        {
+            s.seek_to_keyframe(s.keyframe_before(index, &video_keyframe::index));
+            s.skip_to_index = index;
+            return;
+        }
+        const AVRational rate = s.frame_rate();
+        if (rate.num == 0)
+        {
+            // Only for an empty index: count from the start.
+            s.seek(s.start_pts());
+            s.skip_to_index = index;
+            return;
+        }
+        const int64_t target = s.start_pts() + av_rescale_q(index, av_inv_q(rate), s.video().time_base);
+        s.seek(target);
+        s.index_from_pts = true;
+        s.skip_to_pts = target;
+    }
+
+    const video_index& video_reader::build_index()
+    {
+        impl& s = *m_impl;
+        s.seek(s.start_pts());
+
+        // (timestamp, keyframe) of every packet, in decoding order.
+        std::vector<std::pair<int64_t, bool>> packets;
+        while (true)
+        {
+            const int result = av_read_frame(s.input.get(), s.packet.get());
+            if (result == AVERROR_EOF)
+            {
+                break;
+            }
+            detail::check_av(result, "video_reader: reading failed");
+            const bool video = s.packet->stream_index == s.stream;
+            const int64_t pts = s.packet->pts != AV_NOPTS_VALUE ? s.packet->pts : s.packet->dts;
+            const bool key = (s.packet->flags & AV_PKT_FLAG_KEY) != 0;
+            av_packet_unref(s.packet.get());
+            if (!video)
+            {
+                continue;
+            }
+            if (pts == AV_NOPTS_VALUE)
+            {
+                throw error(error_code::io, "video_reader: can't index video packets without timestamps");
+            }
+            packets.emplace_back(pts, key);
+        }
+
+        std::vector<int64_t> order(packets.size());
+        std::transform(packets.begin(), packets.end(), order.begin(), [](const auto& p) { return p.first; });
+        std::sort(order.begin(), order.end());
+        video_index index;
+        index.frame_count = static_cast<int64_t>(packets.size());
+        for (const auto& [pts, key] : packets)
+        {
+            if (key)
+            {
+                const auto at = std::lower_bound(order.begin(), order.end(), pts);
+                index.keyframes.push_back({at - order.begin(), pts});
+            }
+        }
+        std::sort(
+            index.keyframes.begin(),
+            index.keyframes.end(),
+            [](const video_keyframe& a, const video_keyframe& b) { return a.index < b.index; });
+        try
+        {
+            set_index(std::move(index));
+        }
+        catch (const error& e)
+        {
+            throw error(error_code::io, fmt::format("video_reader: can't index the video stream: {}", e.what()));
+        }
+        seek_to_frame(0);
+        return *s.index;
+    }
+
+    void video_reader::set_index(video_index index)
+    {
+        const std::vector<video_keyframe>& keyframes = index.keyframes;
+        if (index.frame_count > 0 && (keyframes.empty() || keyframes.front().index != 0))
+        {
+            throw error(error_code::invalid_argument, "video_index: frame 0 isn't a keyframe");
+        }
+        for (std::size_t i = 0; i < keyframes.size(); ++i)
+        {
+            const video_keyframe& k = keyframes[i];
+            if (k.index >= index.frame_count || (i > 0 && (k.index <= keyframes[i - 1].index || k.pts <= keyframes[i - 1].pts)))
+            {
+                throw error(
+                    error_code::invalid_argument,
+                    fmt::format(
+                        "video_index: keyframe {} (frame {}, pts {}) is out of order or past frame {}",
+                        i,
+                        k.index,
+                        k.pts,
+                        index.frame_count));
+            }
+        }
+        m_impl->index = std::move(index);
+    }
+
+    const video_index* video_reader::index() const noexcept
+    {
+        return m_impl->index ? &*m_impl->index : nullptr;
+    }
+}
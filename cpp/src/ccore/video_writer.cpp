@@ -0,0 +1,286 @@
+#include "ccore/video_writer.hpp"
+#include "ccore/error.hpp"
+#include "ffmpeg.hpp"
+
+#include <spdlog/fmt/fmt.h>
+
+#include <array>
+#include <new>
+
+namespace by2
+{
+    namespace
+    {
+        // Closes the file, if open, and frees the muxer.
+        struct output_deleter
+        {
+            void operator()(AVFormatContext* p) const noexcept
+            {
+                if ((p->oformat->flags & AVFMT_NOFILE) == 0)
+                {
+                    avio_closep(&p->pb);
+                }
+                avformat_free_context(p);
+            }
+        };
+
+        AVCodecID to_av(video_codec codec)
+        {
+            switch (codec)
+            {
+            case video_codec::mpeg4:
+                return AV_CODEC_ID_MPEG4;
+            case video_codec::ffv1:
+                return AV_CODEC_ID_FFV1;
+            }
+            throw error(error_code::invalid_argument, fmt::format("unknown video codec {}", static_cast<int32_t>(codec)));
+        }
+    }
+
+    struct video_writer::impl
+    {
+        std::unique_ptr<AVFormatContext, output_deleter> output;
+        detail::av_ptr<AVCodecContext> encoder;
+        detail::av_ptr<AVFrame> frame;
+        detail::av_ptr<AVPacket> packet;
+        detail::av_ptr<SwsContext> scaler;
+        AVStream* stream = nullptr;
+        pixel_format format = pixel_format::rgb24;
+        AVPixelFormat input_format = AV_PIX_FMT_NONE;
+        int32_t width = 0;
+        int32_t height = 0;
+        int64_t count = 0;
+        bool finished = false;
+
+        // Sends `f` to the encoder, or flushes it if null, and writes the
+        // packets it puts out.
+        void encode(const AVFrame* f)
+        {
+            detail::check_av(avcodec_send_frame(encoder.get(), f), "video_writer: encoding failed");
+            while (true)
+            {
+                const int result = avcodec_receive_packet(encoder.get(), packet.get());
+                if (result == AVERROR(EAGAIN) || result == AVERROR_EOF)
+                {
+                    return;
+                }
+                detail::check_av(result, "video_writer: encoding failed");
+                av_packet_rescale_ts(packet.get(), encoder->time_base, stream->time_base);
+                packet->stream_index = stream->index;
+                // Takes the packet's data and leaves it blank.
+                detail::check_av(av_interleaved_write_frame(output.get(), packet.get()), "video_writer: writing failed");
+            }
+        }
+    };
+
+    video_writer::video_writer(
+        const std::string& path,
+        int32_t width,
+        int32_t height,
+        pixel_format format,
+        const video_writer_options& options)
+        : m_impl(std::make_unique<impl>())
+    {
+        if (width <= 0 || height <= 0)
+        {
+            throw error(error_code::invalid_argument, fmt::format("video_writer: bad frame size {}x{}", width, height));
+        }
+        const rational rate = options.frame_rate;
+        if (rate.num <= 0 || rate.den <= 0)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("video_writer: bad frame rate {}/{}", rate.num, rate.den));
+        }
+        if (options.bit_rate < 0 || options.gop_size < 0)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("video_writer: negative bit rate {} or GOP size {}", options.bit_rate, options.gop_size));
+        }
+        impl& s = *m_impl;
+        s.format = format;
+        s.input_format = detail::to_av(format);
+        s.width = width;
+        s.height = height;
+
+        const AVCodecID codec = to_av(options.codec);
+        const AVCodec* encoder = avcodec_find_encoder(codec);
+        if (encoder == nullptr)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("video_writer: FFmpeg was built without the {} encoder", avcodec_get_name(codec)));
+        }
+
+        AVFormatContext* raw = nullptr;
+        if (avformat_alloc_output_context2(&raw, nullptr, nullptr, path.c_str()) < 0 || raw == nullptr)
+        {
+            throw error(error_code::invalid_argument, fmt::format("video_writer: no known container for {}", path));
+        }
+        s.output.reset(raw);
+        if (avformat_query_codec(raw->oformat, codec, FF_COMPLIANCE_NORMAL) != 1)
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("video_writer: {} files can't hold {} video", raw->oformat->name, encoder->name));
+        }
+
+        s.encoder.reset(avcodec_alloc_context3(encoder));
+        if (!s.encoder)
+        {
+            throw std::bad_alloc();
+        }
+        AVCodecContext& e = *s.encoder;
+        e.width = width;
+        e.height = height;
+        // The closest the encoder takes, e.g. an RGB layout for lossless
+        // FFV1 of RGB frames.
+        e.pix_fmt = avcodec_find_best_pix_fmt_of_list(
+            encoder->pix_fmts,
+            s.input_format,
+            format == pixel_format::rgba || format == pixel_format::bgra ? 1 : 0,
+            nullptr);
+        e.framerate = {rate.num, rate.den};
+        e.time_base = av_inv_q(e.framerate);
+        if (options.bit_rate > 0)
+        {
+            e.bit_rate = options.bit_rate;
+        }
+        e.gop_size = options.gop_size;
+        // As many threads as there are cores.
+        e.thread_count = 0;
+        if ((raw->oformat->flags & AVFMT_GLOBALHEADER) != 0)
+        {
+            e.flags |= AV_CODEC_FLAG_GLOBAL_HEADER;
+        }
+        detail::check_av(
+            avcodec_open2(&e, encoder, nullptr),
+            fmt::format("video_writer: can't open the {} encoder", encoder->name));
+
+        s.stream = avformat_new_stream(raw, nullptr);
+        if (s.stream == nullptr)
+        {
+            throw std::bad_alloc();
+        }
+        detail::check_av(
+            avcodec_parameters_from_context(s.stream->codecpar, &e),
+            "video_writer: can't set up the video stream");
+        s.stream->time_base = e.time_base;
+        s.stream->avg_frame_rate = e.framerate;
+
+        if ((raw->oformat->flags & AVFMT_NOFILE) == 0)
+        {
+            detail::check_av(avio_open(&raw->pb, path.c_str(), AVIO_FLAG_WRITE), fmt::format("can't create {}", path));
+        }
+        detail::check_av(avformat_write_header(raw, nullptr), fmt::format("can't write the header of {}", path));
+
+        s.frame.reset(av_frame_alloc());
+        s.packet.reset(av_packet_alloc());
+        if (!s.frame || !s.packet)
+        {
+            throw std::bad_alloc();
+        }
+        s.frame->format = e.pix_fmt;
+        s.frame->width = width;
+        s.frame->height = height;
+        detail::check_av(av_frame_get_buffer(s.frame.get(), 0), "video_writer: can't allocate a frame");
+    }
+
+    video_writer::~video_writer()
+    {
+        // Moved-from writers have nothing to finish.
+        if (m_impl)
+        {
+            try
+            {
+                finish();
+            }
+            catch (...)
+            {
+            }
+        }
+    }
+
+    video_writer::video_writer(video_writer&&) noexcept = default;
+    video_writer& video_writer::operator=(video_writer&&) noexcept = default;
+
+    std::size_t video_writer::frame_size() const noexcept
+    {
+        return by2::frame_size(m_impl->format, m_impl->width, m_impl->height);
+    }
+
+    int64_t video_writer::frame_count() const noexcept
+    {
+        return m_impl->count;
+    }
+
+    void video_writer::write(std::span<const std::uint8_t> frame)
+    {
+        impl& s = *m_impl;
+        if (s.finished)
+        {
+            throw error(error_code::invalid_argument, "video_writer: the file is already finished");
+        }
+        if (frame.size() != frame_size())
+        {
+            throw error(
+                error_code::invalid_argument,
+                fmt::format("video_writer: a frame has {} bytes, got {}", frame_size(), frame.size()));
+        }
+
+        // The encoder may still hold on to the previous frame's buffer.
+        detail::check_av(av_frame_make_writable(s.frame.get()), "video_writer: can't allocate a frame");
+        s.scaler.reset(sws_getCachedContext(
+            s.scaler.release(),
+            s.width,
+            s.height,
+            s.input_format,
+            s.width,
+            s.height,
+            static_cast<AVPixelFormat>(s.frame->format),
+            SWS_BICUBIC,
+            nullptr,
+            nullptr,
+            nullptr));
+        if (!s.scaler)
+        {
+            throw error(error_code::io, "video_writer: can't convert frames for the encoder");
+        }
+        std::array<std::uint8_t*, 4> planes{};
+        std::array<int, 4> strides{};
+        // Only read through `planes`.
+        detail::check_av(
+            av_image_fill_arrays(
+                planes.data(),
+                strides.data(),
+                const_cast<std::uint8_t*>(frame.data()),
+                s.input_format,
+                s.width,
+                s.height,
+                1),
+            "video_writer: bad input frame");
+        sws_scale(s.scaler.get(), planes.data(), strides.data(), 0, s.height, s.frame->data, s.frame->linesize);
+
+        s.frame->pts = s.count;
+        s.encode(s.frame.get());
+        ++s.count;
+    }
+
+    void video_writer::finish()
+    {
+        impl& s = *m_impl;
+        if (s.finished)
+        {
+            return;
+        }
+        // Not retried from the destructor if it fails.
+        s.finished = true;
+        s.encode(nullptr);
+        detail::check_av(av_write_trailer(s.output.get()), "video_writer: can't complete the file");
+        if ((s.output->oformat->flags & AVFMT_NOFILE) == 0)
+        {
+            detail::check_av(avio_closep(&s.output->pb), "video_writer: can't close the file");
+        }
+    }
+}
@@ -0,0 +1,56 @@
+// This file is automatically generated, so please do not edit it.
+// @generated by `flutter_rust_bridge`@ 2.11.1.
+
+// ignore_for_file: invalid_use_of_internal_member, unused_import, unnecessary_import
+
+import '../frb_generated.dart';
+import 'package:flutter_rust_bridge/flutter_rust_bridge_for_generated.dart';
+
+// These functions are ignored because they are not marked as `pub`: `options`
+
+/// Exports the occupied voxels of a serialized map as PLY or OBJ bytes,
+/// e.g. for a 3D viewer.
+Future<Uint8List> exportOccupancyMap({
+  required List<int> map,
+  required MapFormat mapFormat,
+  required MapGeometry geometry,
+  required MapColoring coloring,
+  required ExportFileFormat format,
+}) => RustLib.instance.api.crateApiMappingExportOccupancyMap(
+  map: map,
+  mapFormat: mapFormat,
+  geometry: geometry,
+  coloring: coloring,
+  format: format,
+);
+
+/// Loads a `.bt` or `.ot` map and writes its occupied voxels to a `.ply` or
+/// `.obj` file.
+Future<void> exportOccupancyMapFile({
+  required String mapPath,
+  required String outPath,
+  required MapGeometry geometry,
+  required MapColoring coloring,
+}) => RustLib.instance.api.crateApiMappingExportOccupancyMapFile(
+  mapPath: mapPath,
+  outPath: outPath,
+  geometry: geometry,
+  coloring: coloring,
+);
+
+enum ExportFileFormat { ply, obj }
+
+enum MapColoring { none, height, probability }
+
+/// Octomap file formats: `.bt` (free/occupied only) or `.ot` (probabilities).
+enum MapFormat { binary, full }
+
+/// What to export from an occupancy map.
+enum MapGeometry {
+  /// One point per occupied leaf.
+  points,
+  /// The exposed faces of the occupied voxels.
+  cubes,
+  /// A smooth surface around the occupied voxels.
+  marchingCubes,
+}
@@ -6,6 +6,8 @@
 // Static analysis wrongly picks the IO variant, thus ignore this
 // ignore_for_file: argument_type_not_assignable
 
+import 'api/mapping.dart';
+import 'api/media.dart';
 import 'api/simple.dart';
 import 'dart:async';
 import 'dart:convert';
@@ -23,12 +25,27 @@ abstract class RustLibApiImplPlatform extends BaseApiImpl<RustLibWire> {
   @protected
   String dco_decode_String(dynamic raw);
 
+  @protected
+  ExportFileFormat dco_decode_export_file_format(dynamic raw);
+
   @protected
   int dco_decode_i_32(dynamic raw);
 
+  @protected
+  List<int> dco_decode_list_prim_u_8_loose(dynamic raw);
+
   @protected
   Uint8List dco_decode_list_prim_u_8_strict(dynamic raw);
 
+  @protected
+  MapColoring dco_decode_map_coloring(dynamic raw);
+
+  @protected
+  MapFormat dco_decode_map_format(dynamic raw);
+
+  @protected
+  MapGeometry dco_decode_map_geometry(dynamic raw);
+
   @protected
   int dco_decode_u_8(dynamic raw);
 
@@ -38,12 +55,27 @@ abstract class RustLibApiImplPlatform extends BaseApiImpl<RustLibWire> {
   @protected
   String sse_decode_String(SseDeserializer deserializer);
 
+  @protected
+  ExportFileFormat sse_decode_export_file_format(SseDeserializer deserializer);
+
   @protected
   int sse_decode_i_32(SseDeserializer deserializer);
 
+  @protected
+  List<int> sse_decode_list_prim_u_8_loose(SseDeserializer deserializer);
+
   @protected
   Uint8List sse_decode_list_prim_u_8_strict(SseDeserializer deserializer);
 
+  @protected
+  MapColoring sse_decode_map_coloring(SseDeserializer deserializer);
+
+  @protected
+  MapFormat sse_decode_map_format(SseDeserializer deserializer);
+
+  @protected
+  MapGeometry sse_decode_map_geometry(SseDeserializer deserializer);
+
   @protected
   int sse_decode_u_8(SseDeserializer deserializer);
 
@@ -56,15 +88,33 @@ abstract class RustLibApiImplPlatform extends BaseApiImpl<RustLibWire> {
   @protected
   void sse_encode_String(String self, SseSerializer serializer);
 
+  @protected
+  void sse_encode_export_file_format(
+    ExportFileFormat self,
+    SseSerializer serializer,
+  );
+
   @protected
   void sse_encode_i_32(int self, SseSerializer serializer);
 
+  @protected
+  void sse_encode_list_prim_u_8_loose(List<int> self, SseSerializer serializer);
+
   @protected
   void sse_encode_list_prim_u_8_strict(
     Uint8List self,
     SseSerializer serializer,
   );
 
+  @protected
+  void sse_encode_map_coloring(MapColoring self, SseSerializer serializer);
+
+  @protected
+  void sse_encode_map_format(MapFormat self, SseSerializer serializer);
+
+  @protected
+  void sse_encode_map_geometry(MapGeometry self, SseSerializer serializer);
+
   @protected
   void sse_encode_u_8(int self, SseSerializer serializer);
 
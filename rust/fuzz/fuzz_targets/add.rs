@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (i32, i32)| {
+    let (a, b) = input;
+    bridge::by2_add_safe(a, b);
+});
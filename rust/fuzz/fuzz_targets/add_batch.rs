@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Inputs are generated as pairs so `a`, `b` and `out` always have the same
+// length; mismatched lengths are a documented panic of the wrapper, not a bug.
+fuzz_target!(|pairs: Vec<(i32, i32)>| {
+    let (a, b): (Vec<i32>, Vec<i32>) = pairs.into_iter().unzip();
+    let mut out = vec![0; a.len()];
+    bridge::by2_add_batch_safe(&a, &b, &mut out);
+});
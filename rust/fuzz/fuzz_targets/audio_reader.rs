@@ -0,0 +1,42 @@
+#![no_main]
+
+use bridge::{AudioReader, AudioReaderOptions, By2Error, SampleFormat};
+use libfuzzer_sys::fuzz_target;
+
+// Arbitrary bytes, written to a file, are either rejected with an I/O error
+// or decode into chunks of whole samples that follow each other, at the
+// rate and with the channels asked for.
+fuzz_target!(|data: &[u8]| {
+    let path = std::env::temp_dir().join(format!("by2-fuzz-audio-{}", std::process::id()));
+    std::fs::write(&path, data).unwrap();
+    for options in [
+        AudioReaderOptions::default(),
+        AudioReaderOptions {
+            sample_rate: Some(16000),
+            channel_layout: Some("stereo".into()),
+            sample_format: SampleFormat::F32Planar,
+        },
+    ] {
+        let reader = match AudioReader::open(&path, &options) {
+            Ok(reader) => reader,
+            Err(By2Error::Io(_)) => continue,
+            Err(e) => panic!("{e:?}"),
+        };
+        if options.sample_rate.is_some() {
+            assert_eq!((reader.sample_rate(), reader.channels()), (16000, 2));
+        }
+        let channels = reader.channels() as usize;
+        let mut position = 0;
+        for chunk in reader {
+            match chunk {
+                Ok(chunk) => {
+                    assert_eq!(chunk.position(), position);
+                    assert_eq!(chunk.data().len(), chunk.samples() * channels);
+                    position += chunk.samples() as u64;
+                }
+                Err(By2Error::Io(_)) => break,
+                Err(e) => panic!("{e:?}"),
+            }
+        }
+    }
+});
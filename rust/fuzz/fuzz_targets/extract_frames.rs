@@ -0,0 +1,40 @@
+#![no_main]
+
+use bridge::{By2Error, ExtractOptions, ExtractedImage, FrameSelection, extract_frames};
+use libfuzzer_sys::fuzz_target;
+
+// Arbitrary bytes, written to a file, are either rejected with an I/O error
+// or give frames in stream order, of the requested size, whichever way they
+// are picked and however many segments decode them.
+fuzz_target!(|data: &[u8]| {
+    let path = std::env::temp_dir().join(format!("by2-fuzz-extract-{}", std::process::id()));
+    std::fs::write(&path, data).unwrap();
+    for (selection, segments) in [
+        (FrameSelection::Interval(0.1), 1),
+        (FrameSelection::EveryNth(2), 3),
+        (FrameSelection::SceneChange(0.3), 2),
+    ] {
+        let options = ExtractOptions {
+            selection,
+            width: Some(8),
+            segments: Some(segments),
+            ..Default::default()
+        };
+        let frames = match extract_frames(&path, &options) {
+            Ok(frames) => frames,
+            Err(By2Error::Io(_)) => continue,
+            Err(e) => panic!("{e:?}"),
+        };
+        assert!(frames.windows(2).all(|w| w[0].index < w[1].index));
+        for frame in &frames {
+            let ExtractedImage::Memory(image) = &frame.image else {
+                panic!("{frame:?} was written to a file");
+            };
+            assert_eq!(image.width(), 8);
+            assert_eq!(
+                image.data().len(),
+                image.format().frame_size(8, image.height())
+            );
+        }
+    }
+});
@@ -0,0 +1,41 @@
+#![no_main]
+
+use bridge::{AxisAngle, EulerAngles, Inverse, Pose, Quaternion};
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    a: [f64; 4],
+    b: [f64; 4],
+    translation: [f64; 3],
+    matrix: [[f64; 3]; 3],
+    angles: [f64; 3],
+    points: Vec<[f64; 3]>,
+}
+
+// Arbitrary floats (NaN and infinities included) either get rejected by the
+// constructors or flow through every geometry entry point.
+fuzz_target!(|input: Input| {
+    let _ = Quaternion::from_rotation_matrix(&input.matrix);
+    let [roll, pitch, yaw] = input.angles;
+    let _ = Quaternion::from_euler(EulerAngles { roll, pitch, yaw });
+    let _ = Quaternion::from_axis_angle(AxisAngle {
+        axis: input.translation,
+        angle: roll,
+    });
+
+    let (Ok(a), Ok(b)) = (
+        Quaternion::new(input.a[0], input.a[1], input.a[2], input.a[3]),
+        Quaternion::new(input.b[0], input.b[1], input.b[2], input.b[3]),
+    ) else {
+        return;
+    };
+    let _ = (a * b, a.inverse(), a.to_euler(), a.to_axis_angle());
+    let _ = a.to_rotation_matrix();
+
+    let pose = Pose::new(a, input.translation);
+    let composed = pose * Pose::new(b, [0.0; 3]).inverse();
+    let mut points = composed.transform_points(&input.points);
+    composed.transform_points_in_place(&mut points);
+});
@@ -0,0 +1,67 @@
+#![no_main]
+
+use bridge::{IcpMethod, IcpOptions, IcpTermination, PointCloudView, Pose, Quaternion, icp};
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+use rcore::point_cloud::PointCloud;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    source: Vec<[f32; 3]>,
+    target: Vec<[f32; 3]>,
+    target_normals: Option<Vec<[f32; 3]>>,
+    point_to_plane: bool,
+    max_iterations: u8,
+    max_correspondence_distance: f64,
+    translation_tolerance: f64,
+    rotation_tolerance: f64,
+    rotation: [f64; 4],
+    translation: [f64; 3],
+}
+
+// Arbitrary clouds (NaN and infinities included), normals and options either
+// get rejected or produce a result consistent with the options.
+fuzz_target!(|input: Input| {
+    let source = PointCloud::from_points(&input.source);
+    let mut target = PointCloud::from_points(&input.target);
+    if let Some(normals) = &input.target_normals {
+        let column = |axis: usize| {
+            (0..target.len())
+                .map(|i| normals.get(i).map_or(f32::NAN, |n| n[axis]))
+                .collect()
+        };
+        target.set_normals(column(0), column(1), column(2)).unwrap();
+    }
+    let [w, x, y, z] = input.rotation;
+    let Ok(rotation) = Quaternion::new(w, x, y, z) else {
+        return;
+    };
+    let options = IcpOptions {
+        method: if input.point_to_plane {
+            IcpMethod::PointToPlane
+        } else {
+            IcpMethod::PointToPoint
+        },
+        max_iterations: usize::from(input.max_iterations),
+        max_correspondence_distance: input.max_correspondence_distance,
+        translation_tolerance: input.translation_tolerance,
+        rotation_tolerance: input.rotation_tolerance,
+        initial_pose: Pose::new(rotation, input.translation),
+    };
+
+    let Ok(result) = icp(
+        &PointCloudView::from(&source),
+        &PointCloudView::from(&target),
+        &options,
+    ) else {
+        return;
+    };
+    assert!(result.iterations <= options.max_iterations);
+    assert_eq!(result.history.len(), result.iterations);
+    assert_eq!(
+        result.converged,
+        result.termination == IcpTermination::Converged
+    );
+    assert!((0.0..=1.0).contains(&result.fitness));
+    assert!(result.correspondences <= source.len());
+});
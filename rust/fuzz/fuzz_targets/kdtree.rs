@@ -0,0 +1,42 @@
+#![no_main]
+
+use bridge::{KdTree, PointCloudView};
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+use rcore::point_cloud::PointCloud;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    points: Vec<[f32; 3]>,
+    queries: Vec<[f64; 3]>,
+    k: u8,
+    radius: f64,
+}
+
+// The tree answers every query exactly like the brute-force search in
+// `rcore`, and rejects the same queries.
+fuzz_target!(|input: Input| {
+    let cloud = PointCloud::from_points(&input.points);
+    let tree = KdTree::new(&PointCloudView::from(&cloud)).unwrap();
+    let k = usize::from(input.k);
+
+    let mut knn = Some(Vec::new());
+    let mut radius = Some(Vec::new());
+    for &query in &input.queries {
+        let expected = cloud.knn(query, k).ok();
+        assert_eq!(tree.knn(query, k).ok(), expected);
+        knn = knn.zip(expected).map(|(mut all, found)| {
+            all.push(found);
+            all
+        });
+
+        let expected = cloud.radius_neighbors(query, input.radius).ok();
+        assert_eq!(tree.radius(query, input.radius).ok(), expected);
+        radius = radius.zip(expected).map(|(mut all, found)| {
+            all.push(found);
+            all
+        });
+    }
+    assert_eq!(tree.knn_batch(&input.queries, k).ok(), knn);
+    assert_eq!(tree.radius_batch(&input.queries, input.radius).ok(), radius);
+});
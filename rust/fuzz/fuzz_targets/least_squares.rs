@@ -0,0 +1,54 @@
+#![no_main]
+
+use bridge::{LeastSquares, LeastSquaresMethod, LeastSquaresOptions};
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    gauss_newton: bool,
+    analytic_jacobian: bool,
+    max_iterations: u8,
+    tolerances: [f64; 3],
+    params: Vec<f64>,
+    samples: Vec<(f64, f64)>,
+}
+
+// Fits a polynomial with one coefficient per parameter to arbitrary samples.
+// Bad sizes, tolerances and non-finite residuals must come back as errors.
+fuzz_target!(|input: Input| {
+    let mut params = input.params;
+    params.truncate(4);
+    let samples = &input.samples[..input.samples.len().min(64)];
+    let options = LeastSquaresOptions {
+        method: if input.gauss_newton {
+            LeastSquaresMethod::GaussNewton
+        } else {
+            LeastSquaresMethod::LevenbergMarquardt
+        },
+        max_iterations: input.max_iterations.into(),
+        function_tolerance: input.tolerances[0],
+        parameter_tolerance: input.tolerances[1],
+        gradient_tolerance: input.tolerances[2],
+    };
+
+    let mut problem = LeastSquares::new(samples.len(), |p, r| {
+        for (r, &(t, y)) in r.iter_mut().zip(samples) {
+            *r = p.iter().rev().fold(0.0, |acc, c| acc * t + c) - y;
+        }
+    });
+    if input.analytic_jacobian {
+        problem = problem.with_jacobian(|p, j| {
+            for (row, &(t, _)) in j.chunks_mut(p.len()).zip(samples) {
+                let mut power = 1.0;
+                for d in row {
+                    *d = power;
+                    power *= t;
+                }
+            }
+        });
+    }
+    if let Ok(summary) = problem.solve(&mut params, &options) {
+        assert!(summary.iterations <= options.max_iterations);
+    }
+});
@@ -0,0 +1,38 @@
+#![no_main]
+
+use bridge::{MatrixView, SolveMethod};
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    op: u8,
+    rows: u8,
+    cols: u8,
+    row_stride: u8,
+    col_stride: u8,
+    data: Vec<f64>,
+}
+
+// Arbitrary shapes and strides over an arbitrary buffer. Views that don't
+// fit their buffer must be rejected in Rust; the rest reach Eigen.
+fuzz_target!(|input: Input| {
+    let Ok(a) = MatrixView::with_strides(
+        &input.data,
+        input.rows as usize,
+        input.cols as usize,
+        input.row_stride as usize,
+        input.col_stride as usize,
+    ) else {
+        return;
+    };
+
+    let _ = match input.op % 6 {
+        0 => a.multiply(&a).map(drop),
+        1 => a.inverse().map(drop),
+        2 => a.solve(&a, SolveMethod::Lu).map(drop),
+        3 => a.solve(&a, SolveMethod::Qr).map(drop),
+        4 => a.svd().map(drop),
+        _ => a.symmetric_eigen().map(drop),
+    };
+});
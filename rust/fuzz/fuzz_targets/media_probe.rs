@@ -0,0 +1,21 @@
+#![no_main]
+
+use bridge::{By2Error, MediaInfo};
+use libfuzzer_sys::fuzz_target;
+
+// Arbitrary bytes, written to a file, are either rejected with an I/O error
+// or described with consistently numbered streams.
+fuzz_target!(|data: &[u8]| {
+    let path = std::env::temp_dir().join(format!("by2-fuzz-probe-{}", std::process::id()));
+    std::fs::write(&path, data).unwrap();
+    match MediaInfo::probe(&path) {
+        Ok(info) => {
+            for (i, stream) in info.streams.iter().enumerate() {
+                assert_eq!(stream.info.index, i);
+            }
+            assert!(info.duration.is_none_or(|d| !d.is_nan()));
+        }
+        Err(By2Error::Io(_)) => {}
+        Err(e) => panic!("{e:?}"),
+    }
+});
@@ -0,0 +1,89 @@
+#![no_main]
+
+use bridge::{
+    By2Error, Coloring, LeafFilter, MeshStyle, OccupancyMap, OctreeFormat, PointCloudView, RayHit,
+};
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+use rcore::point_cloud::PointCloud;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    scans: Vec<(Vec<[f32; 3]>, [f64; 3], f64)>,
+    queries: Vec<[f64; 3]>,
+    resolution: f64,
+}
+
+// Arbitrary scans (NaN, infinities and points outside the mappable volume
+// included) either get rejected without touching the map or leave every
+// known voxel with a probability. The `.ot` bytes of the map read back into
+// the same map. Pairs of queries drive the ray, nearest and box queries,
+// whose answers must agree with the per-voxel occupancy. Exports hold one
+// point per occupied leaf and a mesh that is empty only without any.
+fuzz_target!(|input: Input| {
+    let Ok(mut map) = OccupancyMap::new(input.resolution) else {
+        return;
+    };
+    for (points, origin, max_range) in &input.scans {
+        let cloud = PointCloud::from_points(points);
+        let leaves = map.leaf_count();
+        if map
+            .insert_point_cloud(&PointCloudView::from(&cloud), *origin, *max_range)
+            .is_err()
+        {
+            assert_eq!(map.leaf_count(), leaves);
+        }
+    }
+    let bytes = map.to_bytes(OctreeFormat::Full).unwrap();
+    let loaded = OccupancyMap::from_bytes(&bytes, OctreeFormat::Full).unwrap();
+    assert_eq!(loaded.leaf_count(), map.leaf_count());
+    for &query in &input.queries {
+        let Ok(occupancy) = map.occupancy(query) else {
+            assert!(query.iter().any(|c| !c.is_finite()));
+            continue;
+        };
+        if let Some(p) = occupancy {
+            assert!(p > 0.0 && p < 1.0, "{p}");
+        }
+        assert_eq!(loaded.occupancy(query).unwrap(), occupancy);
+    }
+
+    let everywhere = ([f64::NEG_INFINITY; 3], [f64::INFINITY; 3]);
+    let known = map.leaves(everywhere.0, everywhere.1, LeafFilter::Known);
+    assert_eq!(known.unwrap().map(Result::unwrap).count(), map.leaf_count());
+    let occupied_leaves = map.leaves(everywhere.0, everywhere.1, LeafFilter::Occupied);
+    let occupied_leaves = occupied_leaves.unwrap().map(Result::unwrap).count();
+    let points = map.occupied_points(Coloring::Probability).unwrap();
+    assert_eq!(points.len(), occupied_leaves);
+    for style in [MeshStyle::Cubes, MeshStyle::MarchingCubes] {
+        match map.occupied_mesh(style, Coloring::Height) {
+            Ok(mesh) => assert_eq!(mesh.triangles().is_empty(), occupied_leaves == 0),
+            Err(e) => assert!(matches!(e, By2Error::InvalidArgument(_)), "{e}"),
+        }
+    }
+
+    let occupied = |p: [f64; 3]| map.occupancy(p).unwrap().is_some_and(|p| p >= 0.5);
+    let free = |p: [f64; 3]| map.occupancy(p).unwrap().is_some_and(|p| p < 0.5);
+    for pair in input.queries.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let direction = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+        if let Ok(RayHit::Occupied(voxel)) = map.cast_ray(a, direction, f64::INFINITY, false) {
+            assert!(occupied(voxel));
+        }
+        if let Ok(true) = map.line_of_sight(a, b, false) {
+            assert!(free(a) && free(b));
+        }
+        if let Ok(Some((leaf, distance))) = map.nearest_occupied(a, f64::INFINITY) {
+            assert!(leaf.probability >= 0.5 && occupied(leaf.center));
+            assert!(distance >= 0.0);
+        }
+        let min = [0, 1, 2].map(|i| a[i].min(b[i]));
+        let max = [0, 1, 2].map(|i| a[i].max(b[i]));
+        if let Ok(leaves) = map.leaves(min, max, LeafFilter::Free) {
+            for leaf in leaves {
+                let leaf = leaf.unwrap();
+                assert!(leaf.probability < 0.5 && free(leaf.center));
+            }
+        }
+    }
+});
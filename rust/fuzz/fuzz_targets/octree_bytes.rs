@@ -0,0 +1,18 @@
+#![no_main]
+
+use bridge::{OccupancyMap, OctreeFormat};
+use libfuzzer_sys::fuzz_target;
+
+// Arbitrary bytes are either rejected or read as a map that writes itself
+// back out in the same format and reads back again.
+fuzz_target!(|data: &[u8]| {
+    for format in [OctreeFormat::Binary, OctreeFormat::Full] {
+        let Ok(map) = OccupancyMap::from_bytes(data, format) else {
+            continue;
+        };
+        let bytes = map.to_bytes(format).unwrap();
+        let again = OccupancyMap::from_bytes(&bytes, format).unwrap();
+        assert_eq!(again.leaf_count(), map.leaf_count());
+        assert_eq!(again.resolution(), map.resolution());
+    }
+});
@@ -0,0 +1,44 @@
+#![no_main]
+
+use bridge::PointCloudView;
+use libfuzzer_sys::fuzz_target;
+use rcore::point_cloud::{PointCloud, pcd, ply};
+
+// Arbitrary bytes go through both readers. Malformed input has to come back
+// as an error; whatever parses must survive a write/read round trip and the
+// native bounds kernel.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(cloud) = ply::read(data) {
+        check(
+            &cloud,
+            |c, out| ply::write(c, out, ply::Encoding::BinaryLittleEndian),
+            |b| ply::read(b),
+        );
+    }
+    if let Ok(cloud) = pcd::read(data) {
+        check(
+            &cloud,
+            |c, out| pcd::write(c, out, pcd::Encoding::Binary),
+            |b| pcd::read(b),
+        );
+    }
+});
+
+fn check<E: std::fmt::Debug>(
+    cloud: &PointCloud,
+    write: impl Fn(&PointCloud, &mut Vec<u8>) -> Result<(), E>,
+    read: impl Fn(&[u8]) -> Result<PointCloud, E>,
+) {
+    let mut bytes = Vec::new();
+    write(cloud, &mut bytes).unwrap();
+    let again = read(&bytes).unwrap();
+    // Compare bit patterns: NaN coordinates must round trip too.
+    let bits = |c: &PointCloud| {
+        [c.x(), c.y(), c.z()].map(|col| col.iter().map(|v| v.to_bits()).collect::<Vec<_>>())
+    };
+    assert_eq!(bits(cloud), bits(&again));
+    assert_eq!(cloud.colors(), again.colors());
+
+    let view = PointCloudView::from(cloud);
+    assert_eq!(view.bounds().is_ok(), !cloud.is_empty());
+}
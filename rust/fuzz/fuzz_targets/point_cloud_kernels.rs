@@ -0,0 +1,51 @@
+#![no_main]
+
+use bridge::{Aabb, PointCloudView};
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+use rcore::point_cloud::PointCloud;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    points: Vec<[f32; 3]>,
+    intensity: bool,
+    voxel_size: f64,
+    k: u8,
+    std_ratio: f64,
+    radius: f64,
+    min_neighbors: u8,
+    bounds: [[f64; 3]; 2],
+    viewpoint: [f64; 3],
+}
+
+// Arbitrary coordinates (NaN and infinities included) and parameters either
+// get rejected or produce outputs that index into the cloud.
+fuzz_target!(|input: Input| {
+    let mut cloud = PointCloud::from_points(&input.points);
+    if input.intensity {
+        cloud.set_intensity(vec![1.0; cloud.len()]).unwrap();
+    }
+    let view = PointCloudView::from(&cloud);
+    let k = usize::from(input.k);
+
+    if let Ok(down) = view.voxel_downsample(input.voxel_size) {
+        assert!(down.len() <= cloud.len());
+        assert_eq!(down.intensity().is_some(), input.intensity);
+    }
+    let [min, max] = input.bounds;
+    for inliers in [
+        view.statistical_inliers(k, input.std_ratio),
+        view.radius_inliers(input.radius, usize::from(input.min_neighbors)),
+        view.crop(&Aabb { min, max }),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        assert!(inliers.windows(2).all(|w| w[0] < w[1]));
+        assert!(inliers.iter().all(|&i| i < cloud.len()));
+        cloud.select(&inliers);
+    }
+    if let Ok(normals) = view.estimate_normals(k, input.viewpoint) {
+        assert!(normals.iter().all(|n| n.len() == cloud.len()));
+    }
+});
@@ -0,0 +1,59 @@
+#![no_main]
+
+use bridge::{By2Error, FrameLayout, Interpolation, PixelFormat, Scaler};
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    from: (u8, u8, u8),
+    to: (u8, u8, u8),
+    interpolation: u8,
+    data: Vec<u8>,
+}
+
+const FORMATS: [PixelFormat; 9] = [
+    PixelFormat::Rgb24,
+    PixelFormat::Rgba,
+    PixelFormat::Yuv420p,
+    PixelFormat::Gray8,
+    PixelFormat::Bgr24,
+    PixelFormat::Bgra,
+    PixelFormat::Nv12,
+    PixelFormat::Yuv422p,
+    PixelFormat::Yuv444p,
+];
+
+fn layout((width, height, format): (u8, u8, u8)) -> FrameLayout {
+    FrameLayout::new(
+        u32::from(width % 40),
+        u32::from(height % 40),
+        FORMATS[usize::from(format) % FORMATS.len()],
+    )
+}
+
+// Arbitrary sizes and format pairs either fail to set up with an invalid
+// argument or convert any frame of the right size, rejecting the rest.
+fuzz_target!(|input: Input| {
+    let (from, to) = (layout(input.from), layout(input.to));
+    let interpolation = match input.interpolation % 3 {
+        0 => Interpolation::Bilinear,
+        1 => Interpolation::Bicubic,
+        _ => Interpolation::Area,
+    };
+    let mut scaler = match Scaler::new(from, to, interpolation) {
+        Ok(scaler) => scaler,
+        Err(By2Error::InvalidArgument(_)) => return,
+        Err(e) => panic!("{e:?}"),
+    };
+    let mut out = vec![0; to.frame_size()];
+    let mut frame = input.data;
+    let result = scaler.scale(&frame, &mut out);
+    assert_eq!(
+        result.is_ok(),
+        frame.len() == from.frame_size(),
+        "{result:?}"
+    );
+    frame.resize(from.frame_size(), 0x80);
+    scaler.scale(&frame, &mut out).unwrap();
+});
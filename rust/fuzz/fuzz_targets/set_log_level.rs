@@ -0,0 +1,23 @@
+#![no_main]
+
+use bridge::LogLevel;
+use libfuzzer_sys::fuzz_target;
+
+const LEVELS: [LogLevel; 7] = [
+    LogLevel::Trace,
+    LogLevel::Debug,
+    LogLevel::Info,
+    LogLevel::Warn,
+    LogLevel::Error,
+    LogLevel::Critical,
+    LogLevel::Off,
+];
+
+// Switches the log level between native calls so every spdlog level is
+// exercised against the logging in ccore.
+fuzz_target!(|input: Vec<(u8, i32, i32)>| {
+    for (level, a, b) in input {
+        bridge::set_log_level(LEVELS[level as usize % LEVELS.len()]).unwrap();
+        bridge::by2_add_safe(a, b);
+    }
+});
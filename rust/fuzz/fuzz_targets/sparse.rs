@@ -0,0 +1,65 @@
+#![no_main]
+
+use bridge::{Layout, Matrix, SparseFormat, SparseMatrix, SparseSolver, TripletBuilder};
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    rows: u8,
+    cols: u8,
+    csc: bool,
+    triplets: Vec<(u8, u8, f64)>,
+    rhs: Vec<f64>,
+    max_iterations: Option<u8>,
+    tolerance: Option<f64>,
+}
+
+// Arbitrary (possibly out of range) triplets go through the builder, and
+// whatever it accepts through the conversions, the product and every solver.
+fuzz_target!(|input: Input| {
+    let (rows, cols) = (usize::from(input.rows % 16), usize::from(input.cols % 16));
+    let format = if input.csc {
+        SparseFormat::Csc
+    } else {
+        SparseFormat::Csr
+    };
+    let mut builder = TripletBuilder::new(rows, cols);
+    for &(row, col, value) in &input.triplets {
+        builder.push(row.into(), col.into(), value);
+    }
+    let Ok(a) = builder.build(format) else {
+        return;
+    };
+    let other = a.to_format(match format {
+        SparseFormat::Csr => SparseFormat::Csc,
+        SparseFormat::Csc => SparseFormat::Csr,
+    });
+    // Bit patterns, so NaN entries compare equal to themselves.
+    let bits = |m: &SparseMatrix<f64>| {
+        m.triplets()
+            .map(|(row, col, value)| (row, col, value.to_bits()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(bits(&other.to_format(format)), bits(&a));
+
+    let mut rhs = input.rhs;
+    rhs.resize(rows.max(cols), 0.0);
+    let (Ok(x), Ok(b)) = (
+        Matrix::from_vec(cols, 1, Layout::ColMajor, rhs[..cols].to_vec()),
+        Matrix::from_vec(rows, 1, Layout::ColMajor, rhs[..rows].to_vec()),
+    ) else {
+        return;
+    };
+    let _ = a.multiply(&x.view());
+    for solver in [
+        SparseSolver::SimplicialLdlt,
+        SparseSolver::Qr,
+        SparseSolver::ConjugateGradient {
+            max_iterations: input.max_iterations.map(usize::from),
+            tolerance: input.tolerance,
+        },
+    ] {
+        let _ = other.solve(&b.view(), solver);
+    }
+});
@@ -0,0 +1,42 @@
+#![no_main]
+
+use bridge::{By2Error, PixelFormat, VideoReader};
+use libfuzzer_sys::fuzz_target;
+
+// Arbitrary bytes, written to a file, are either rejected with an I/O error
+// or decode into frames of the advertised size, numbered in order. Seeking
+// and indexing them only fail with I/O errors.
+fuzz_target!(|data: &[u8]| {
+    let path = std::env::temp_dir().join(format!("by2-fuzz-video-{}", std::process::id()));
+    std::fs::write(&path, data).unwrap();
+    for format in [PixelFormat::Rgb24, PixelFormat::Yuv420p] {
+        let mut reader = match VideoReader::open(&path, format) {
+            Ok(reader) => reader,
+            Err(By2Error::Io(_)) => continue,
+            Err(e) => panic!("{e:?}"),
+        };
+        let size = format.frame_size(reader.width(), reader.height());
+        assert_eq!(reader.frame_size(), size);
+        for (i, frame) in reader.by_ref().enumerate() {
+            match frame {
+                Ok(frame) => {
+                    assert_eq!(frame.index(), i as u64);
+                    assert_eq!(frame.data().len(), size);
+                }
+                Err(By2Error::Io(_)) => break,
+                Err(e) => panic!("{e:?}"),
+            }
+        }
+        for result in [
+            reader.seek_to_time(0.5),
+            reader.frame_at(1).map(drop),
+            reader.build_index().map(drop),
+            reader.frame_at(0).map(drop),
+        ] {
+            assert!(
+                matches!(result, Ok(()) | Err(By2Error::Io(_))),
+                "{result:?}"
+            );
+        }
+    }
+});
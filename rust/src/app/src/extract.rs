@@ -0,0 +1,74 @@
+//! `app extract [options] <file> <directory>`: frame dumps and thumbnails.
+
+use bridge::{ExtractOptions, ExtractedImage, FrameSelection, ImageFormat, ImageOutput};
+
+const USAGE: &str =
+    "usage: app extract [--every <seconds> | --every-frames <n> | --scenes <threshold>]
+                   [--size <width>x<height>] [--jpeg [--quality <1-100>]]
+                   [--segments <n>] <file> <directory>
+
+Writes one PNG (or JPEG) per picked frame, every second by default.
+Either side of --size may be left out, e.g. 320x, to keep the aspect ratio.";
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let mut options = ExtractOptions::default();
+    let mut format = ImageFormat::Png;
+    let mut quality = None;
+    let mut positional = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("{arg} needs a value\n{USAGE}"))
+        };
+        match arg.as_str() {
+            "--every" => options.selection = FrameSelection::Interval(number(value()?)?),
+            "--every-frames" => options.selection = FrameSelection::EveryNth(number(value()?)?),
+            "--scenes" => options.selection = FrameSelection::SceneChange(number(value()?)?),
+            "--size" => (options.width, options.height) = size(value()?)?,
+            "--segments" => options.segments = Some(number(value()?)?),
+            "--jpeg" => format = ImageFormat::Jpeg,
+            "--quality" => quality = Some(number(value()?)?),
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return Ok(());
+            }
+            flag if flag.starts_with("--") => {
+                return Err(format!("unknown option {flag}\n{USAGE}"));
+            }
+            _ => positional.push(arg),
+        }
+    }
+    let [path, directory] = positional[..] else {
+        return Err(USAGE.into());
+    };
+    let mut output = ImageOutput::new(directory, format);
+    if let Some(quality) = quality {
+        output.quality = quality;
+    }
+    options.output = Some(output);
+
+    let frames = bridge::extract_frames(path, &options).map_err(|e| format!("{path}: {e}"))?;
+    for frame in &frames {
+        if let ExtractedImage::File(file) = &frame.image {
+            let time = frame.time.map_or("?".into(), |t| format!("{t:.3}"));
+            println!("{} @ {time} s: {}", frame.index, file.display());
+        }
+    }
+    println!("{} frames written to {directory}", frames.len());
+    Ok(())
+}
+
+fn number<T: std::str::FromStr>(text: &str) -> Result<T, String> {
+    text.parse()
+        .map_err(|_| format!("{text:?} is not a valid number"))
+}
+
+/// `WxH`, `Wx` or `xH`.
+fn size(text: &str) -> Result<(Option<u32>, Option<u32>), String> {
+    let (width, height) = text
+        .split_once('x')
+        .ok_or_else(|| format!("{text:?} is not a size like 320x240"))?;
+    let side = |s: &str| (!s.is_empty()).then(|| number(s)).transpose();
+    Ok((side(width)?, side(height)?))
+}
@@ -1,6 +1,32 @@
 // use bridge::by2_add_safe;
 
+#[cfg(feature = "media")]
+mod extract;
+#[cfg(feature = "media")]
+mod probe;
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        #[cfg(feature = "media")]
+        Some("probe") => {
+            if let Err(e) = probe::run(&args[1..]) {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+        #[cfg(feature = "media")]
+        Some("extract") => {
+            if let Err(e) = extract::run(&args[1..]) {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+        _ => hello(),
+    }
+}
+
+fn hello() {
     println!("Hello, world!");
     let x = 5;
     let y = 10;
@@ -0,0 +1,67 @@
+//! `app probe [--json] <file>`: what a media file holds.
+
+use bridge::{MediaInfo, MediaType, Tag};
+
+pub fn run(args: &[String]) -> Result<(), String> {
+    let (json, path) = match args {
+        [flag, path] if flag == "--json" => (true, path),
+        [path] => (false, path),
+        _ => return Err("usage: app probe [--json] <file>".into()),
+    };
+    let info = MediaInfo::probe(path).map_err(|e| format!("{path}: {e}"))?;
+    if json {
+        let text = serde_json::to_string_pretty(&info).map_err(|e| format!("{path}: {e}"))?;
+        println!("{text}");
+    } else {
+        print!("{}", describe(&info));
+    }
+    Ok(())
+}
+
+fn describe(info: &MediaInfo) -> String {
+    let mut text = format!("format: {} ({})\n", info.format, info.format_long_name);
+    text += &format!("duration: {}\n", seconds(info.duration));
+    if let Some(bit_rate) = info.bit_rate {
+        text += &format!("bit rate: {} kb/s\n", bit_rate / 1000);
+    }
+    text += &tags(&info.tags, "");
+    for stream in &info.streams {
+        let s = &stream.info;
+        text += &format!("stream {}: {:?}, {}", s.index, s.media_type, s.codec);
+        match s.media_type {
+            MediaType::Video => {
+                text += &format!(", {}x{}", s.width, s.height);
+                if let Some(pixel_format) = &s.pixel_format {
+                    text += &format!(", {pixel_format}");
+                }
+                if let Some(rate) = s.frame_rate {
+                    text += &format!(", {rate:.3} fps");
+                }
+            }
+            MediaType::Audio => {
+                text += &format!(", {} Hz", s.sample_rate);
+                match &s.channel_layout {
+                    Some(layout) => text += &format!(", {layout}"),
+                    None => text += &format!(", {} channels", s.channels),
+                }
+            }
+            _ => {}
+        }
+        if let Some(bit_rate) = s.bit_rate {
+            text += &format!(", {} kb/s", bit_rate / 1000);
+        }
+        text += &format!(", {}\n", seconds(s.duration));
+        text += &tags(&stream.tags, "  ");
+    }
+    text
+}
+
+fn seconds(duration: Option<f64>) -> String {
+    duration.map_or("unknown".into(), |d| format!("{d:.3} s"))
+}
+
+fn tags(tags: &[Tag], indent: &str) -> String {
+    tags.iter()
+        .map(|t| format!("{indent}  {}: {}\n", t.key, t.value))
+        .collect()
+}
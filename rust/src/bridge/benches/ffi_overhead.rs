@@ -0,0 +1,72 @@
+//! Measures the cost of going through the C++ core (`bridge` -> `by2` ->
+//! `ccore`) compared with the pure-Rust equivalents in `rcore`.
+//!
+//! Run with `cargo bench -p bridge --bench ffi_overhead`. See
+//! `docs/benchmarks.md` for saving and comparing baselines.
+
+use std::hint::black_box;
+
+use bridge::LogLevel;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+
+/// Log levels the native calls are measured under. `Info` makes `ccore`
+/// emit a spdlog line per call, `Off` measures the bare FFI hop.
+const LOG_LEVELS: [(LogLevel, &str); 2] =
+    [(LogLevel::Off, "log-off"), (LogLevel::Info, "log-info")];
+
+/// Buffer lengths (in elements) used for the batch benchmarks.
+const BATCH_SIZES: [usize; 6] = [1, 16, 256, 4096, 65_536, 1 << 20];
+
+fn bench_scalar_add(c: &mut Criterion) {
+    let mut group = c.benchmark_group("add/scalar");
+
+    group.bench_function("rcore", |b| {
+        b.iter(|| rcore::add(black_box(2), black_box(3)))
+    });
+
+    for (level, label) in LOG_LEVELS {
+        bridge::set_log_level(level).unwrap();
+        group.bench_function(BenchmarkId::new("bridge", label), |b| {
+            b.iter(|| bridge::by2_add_safe(black_box(2), black_box(3)))
+        });
+    }
+
+    group.finish();
+    bridge::set_log_level(LogLevel::Info).unwrap();
+}
+
+fn bench_batch_add(c: &mut Criterion) {
+    let mut group = c.benchmark_group("add/batch");
+
+    for size in BATCH_SIZES {
+        group.throughput(Throughput::Elements(size as u64));
+
+        let a: Vec<u64> = (0..size as u64).collect();
+        let b: Vec<u64> = (0..size as u64).rev().collect();
+        let mut out = vec![0u64; size];
+        group.bench_with_input(BenchmarkId::new("rcore", size), &size, |bench, _| {
+            bench.iter(|| rcore::add_batch(black_box(&a), black_box(&b), &mut out))
+        });
+
+        let a: Vec<i32> = (0..size as i32).collect();
+        let b: Vec<i32> = (0..size as i32).rev().collect();
+        let mut out = vec![0i32; size];
+        for (level, label) in LOG_LEVELS {
+            bridge::set_log_level(level).unwrap();
+            group.bench_with_input(
+                BenchmarkId::new(format!("bridge/{label}"), size),
+                &size,
+                |bench, _| {
+                    bench
+                        .iter(|| bridge::by2_add_batch_safe(black_box(&a), black_box(&b), &mut out))
+                },
+            );
+        }
+    }
+
+    group.finish();
+    bridge::set_log_level(LogLevel::Info).unwrap();
+}
+
+criterion_group!(benches, bench_scalar_add, bench_batch_add);
+criterion_main!(benches);
@@ -0,0 +1,126 @@
+//! Point cloud kernels in the C++ core (kd-tree neighbour search) against the
+//! brute-force references on `rcore::point_cloud::PointCloud`.
+//!
+//! Run with `cargo bench -p bridge --bench point_cloud`. The `rcore` side is
+//! quadratic in the cloud size, so the largest clouds only run natively.
+
+use std::hint::black_box;
+
+use bridge::{Aabb, PointCloudView};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use rcore::point_cloud::PointCloud;
+
+/// Cloud sizes for the native kernels; the `rcore` ones stop at
+/// `NAIVE_LIMIT`.
+const SIZES: [usize; 4] = [1_000, 4_000, 16_000, 100_000];
+const NAIVE_LIMIT: usize = 4_000;
+
+/// A noisy unit sphere with 1% of the points scattered far out, from a fixed
+/// xorshift seed so every run measures the same cloud.
+fn synthetic_cloud(n: usize) -> PointCloud {
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut uniform = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state >> 11) as f64 / (1u64 << 53) as f64
+    };
+    let points: Vec<[f32; 3]> = (0..n)
+        .map(|i| {
+            let theta = uniform() * std::f64::consts::TAU;
+            let z = uniform() * 2.0 - 1.0;
+            let r = if i % 100 == 0 {
+                3.0 + 5.0 * uniform()
+            } else {
+                1.0 + 0.01 * uniform()
+            };
+            let s = (1.0 - z * z).sqrt();
+            [r * s * theta.cos(), r * s * theta.sin(), r * z].map(|v| v as f32)
+        })
+        .collect();
+    PointCloud::from_points(&points)
+}
+
+/// Runs `rcore` (up to `NAIVE_LIMIT` points) and `bridge` versions of one
+/// kernel over `SIZES`.
+fn compare<R, B>(
+    c: &mut Criterion,
+    name: &str,
+    rust: impl Fn(&PointCloud) -> R,
+    native: impl Fn(PointCloudView<'_>) -> B,
+) {
+    let mut group = c.benchmark_group(format!("point_cloud/{name}"));
+    group.sample_size(10);
+    for size in SIZES {
+        let cloud = synthetic_cloud(size);
+        group.throughput(Throughput::Elements(size as u64));
+        if size <= NAIVE_LIMIT {
+            group.bench_with_input(BenchmarkId::new("rcore", size), &cloud, |b, cloud| {
+                b.iter(|| rust(black_box(cloud)))
+            });
+        }
+        group.bench_with_input(BenchmarkId::new("bridge", size), &cloud, |b, cloud| {
+            b.iter(|| native(PointCloudView::from(black_box(cloud))))
+        });
+    }
+    group.finish();
+}
+
+fn bench_voxel_downsample(c: &mut Criterion) {
+    compare(
+        c,
+        "voxel_downsample",
+        |cloud| cloud.voxel_downsample(0.05).unwrap(),
+        |view| view.voxel_downsample(0.05).unwrap(),
+    );
+}
+
+fn bench_statistical_inliers(c: &mut Criterion) {
+    compare(
+        c,
+        "statistical_inliers",
+        |cloud| cloud.statistical_inliers(8, 1.0).unwrap(),
+        |view| view.statistical_inliers(8, 1.0).unwrap(),
+    );
+}
+
+fn bench_radius_inliers(c: &mut Criterion) {
+    compare(
+        c,
+        "radius_inliers",
+        |cloud| cloud.radius_inliers(0.1, 4).unwrap(),
+        |view| view.radius_inliers(0.1, 4).unwrap(),
+    );
+}
+
+fn bench_crop(c: &mut Criterion) {
+    let bounds = Aabb {
+        min: [-0.5; 3],
+        max: [0.5, 1.5, 1.5],
+    };
+    compare(
+        c,
+        "crop",
+        |cloud| cloud.crop(bounds.min, bounds.max).unwrap(),
+        |view| view.crop(&bounds).unwrap(),
+    );
+}
+
+fn bench_estimate_normals(c: &mut Criterion) {
+    compare(
+        c,
+        "estimate_normals",
+        |cloud| cloud.estimate_normals(10, [0.0; 3]).unwrap(),
+        |view| view.estimate_normals(10, [0.0; 3]).unwrap(),
+    );
+}
+
+criterion_group!(
+    benches,
+    bench_voxel_downsample,
+    bench_statistical_inliers,
+    bench_radius_inliers,
+    bench_crop,
+    bench_estimate_normals
+);
+criterion_main!(benches);
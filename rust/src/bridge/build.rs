@@ -4,12 +4,51 @@ use std::process::Command;
 
 const CMAKE_INSTALLED_DIR: &str = "installed";
 const VCPKG_INSTALLED_DIR: &str = "vcpkg_installed";
+/// Env var that selects the sanitize flavor, same as `--features sanitize`.
+const SANITIZE_ENV_VAR: &str = "BY2_SANITIZE";
+/// The clang driver matching the clang-20 CMake presets. Used to locate
+/// the sanitizer runtimes.
+const CLANG_DRIVER: &str = "clang-20";
 
 #[derive(Debug)]
 struct BuildDetails {
     /// The CMake preset to use for this build (e.g. "vs2022r-install")
     cmake_config_preset: &'static str,
     cmake_build_preset: &'static str,
+    /// Directory of the generated by2.pc, relative to the install prefix
+    package_config_dir: &'static str,
+}
+
+/// Which instrumentation the C++ side is built with.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum BuildFlavor {
+    /// Plain build for the current profile.
+    Default,
+    /// libFuzzer + ASan instrumentation. Selected when `cargo fuzz` builds
+    /// us, which passes `--cfg fuzzing` to rustc.
+    Fuzz,
+    /// ASan + UBSan instrumentation. Selected by the `sanitize` feature or
+    /// by setting `BY2_SANITIZE=1`.
+    Sanitize,
+    /// llvm source-based coverage instrumentation. Selected when
+    /// `cargo llvm-cov` builds us, which passes `--cfg coverage` to rustc.
+    Coverage,
+}
+
+fn deduce_build_flavor() -> BuildFlavor {
+    let sanitize_env = env::var(SANITIZE_ENV_VAR)
+        .map(|v| !matches!(v.as_str(), "" | "0" | "false" | "off"))
+        .unwrap_or(false);
+
+    if env::var_os("CARGO_CFG_FUZZING").is_some() {
+        BuildFlavor::Fuzz
+    } else if env::var_os("CARGO_CFG_COVERAGE").is_some() {
+        BuildFlavor::Coverage
+    } else if sanitize_env || env::var_os("CARGO_FEATURE_SANITIZE").is_some() {
+        BuildFlavor::Sanitize
+    } else {
+        BuildFlavor::Default
+    }
 }
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
@@ -32,9 +71,30 @@ fn deduce_build_details(
     target_os: TargetOS,
     _target_arch: &str,
     build_profile: &str,
+    build_flavor: BuildFlavor,
 ) -> BuildDetails {
-    match target_os {
-        TargetOS::Windows => {
+    match (target_os, build_flavor) {
+        (TargetOS::Linux, BuildFlavor::Fuzz) => BuildDetails {
+            // Instrumented RelWithDebInfo regardless of the cargo profile;
+            // cargo fuzz builds in release by default.
+            cmake_config_preset: "clang-20-fuzz",
+            cmake_build_preset: "clang-20-fuzz-install",
+            package_config_dir: "lib/pkgconfig",
+        },
+        (TargetOS::Linux, BuildFlavor::Sanitize) => BuildDetails {
+            cmake_config_preset: "clang-20-sanitize",
+            cmake_build_preset: "clang-20-sanitize-install",
+            package_config_dir: "debug/lib/pkgconfig",
+        },
+        (TargetOS::Linux, BuildFlavor::Coverage) => BuildDetails {
+            cmake_config_preset: "clang-20-coverage",
+            cmake_build_preset: "clang-20-coverage-install",
+            package_config_dir: "debug/lib/pkgconfig",
+        },
+        (_, BuildFlavor::Fuzz | BuildFlavor::Sanitize | BuildFlavor::Coverage) => {
+            panic!("{:?} builds are only supported on Linux", build_flavor);
+        }
+        (TargetOS::Windows, BuildFlavor::Default) => {
             // NOTE: The C/C++ objects produced by the cxx crate and its build
             // infrastructure are typically compiled with the release CRT
             // settings. Mixing MSVC debug CRT (MDd) and release CRT (MD) will
@@ -42,57 +102,55 @@ fn deduce_build_details(
             // `cargo test` (PROFILE=debug) we map the debug profile to the
             // CMake RelWithDebInfo install preset so the installed C++ libs
             // use release-like runtime settings and match the cxx bridge.
-            let (cmake_config_preset, cmake_build_preset, _package_config_path) =
-                match build_profile {
-                    // Build using the release install preset. The project's
-                    // installation step places artifacts under lib/Release (see
-                    // cmake/installation.cmake which maps RelWithDebInfo -> Release
-                    // for install layout), so use lib/Release here.
-                    // "debug" => {
-                    //     // https://github.com/rust-lang/rust/issues/39016#issuecomment-2391095973
-                    //     // Don't link the default CRT
-                    //     // println!("cargo::rustc-link-arg=/nodefaultlib:msvcrt");
-                    //     // Link the debug CRT instead
-                    //     // println!("cargo::rustc-link-arg=/defaultlib:msvcrtd");
-                    //     ("windows-debug-install", "lib/Debug", "debug/lib")
-                    // }
-                    "debug" => (
-                        "msvc-mt",
-                        "msvc-mt-debug-install",
-                        "debug/lib/pkgconfig/by2.pc",
-                    ),
-                    "release" => ("msvc-md", "msvc-md-release-install", "lib/pkgconfig/by2.pc"),
-                    _ => {
-                        panic!("Unsupported build profile: {}", build_profile);
-                    }
-                };
+            let (cmake_config_preset, cmake_build_preset, package_config_dir) = match build_profile
+            {
+                // Build using the release install preset. The project's
+                // installation step places artifacts under lib/Release (see
+                // cmake/installation.cmake which maps RelWithDebInfo -> Release
+                // for install layout), so use lib/Release here.
+                // "debug" => {
+                //     // https://github.com/rust-lang/rust/issues/39016#issuecomment-2391095973
+                //     // Don't link the default CRT
+                //     // println!("cargo::rustc-link-arg=/nodefaultlib:msvcrt");
+                //     // Link the debug CRT instead
+                //     // println!("cargo::rustc-link-arg=/defaultlib:msvcrtd");
+                //     ("windows-debug-install", "lib/Debug", "debug/lib")
+                // }
+                "debug" => ("msvc-mt", "msvc-mt-debug-install", "debug/lib/pkgconfig"),
+                "release" => ("msvc-md", "msvc-md-release-install", "lib/pkgconfig"),
+                _ => {
+                    panic!("Unsupported build profile: {}", build_profile);
+                }
+            };
 
             BuildDetails {
                 cmake_config_preset,
                 cmake_build_preset,
+                package_config_dir,
             }
         }
-        TargetOS::Linux => {
-            let (cmake_config_preset, cmake_build_preset, _package_config_path) =
-                match build_profile {
-                    "debug" => (
-                        "clang-20-debug",
-                        "clang-20-debug-install",
-                        "debug/lib/pkgconfig/by2.pc",
-                    ),
-                    "release" => (
-                        "clang-20-release",
-                        "clang-20-release-install",
-                        "lib/pkgconfig/by2.pc",
-                    ),
-                    _ => {
-                        panic!("Unsupported build profile: {}", build_profile);
-                    }
-                };
+        (TargetOS::Linux, BuildFlavor::Default) => {
+            let (cmake_config_preset, cmake_build_preset, package_config_dir) = match build_profile
+            {
+                "debug" => (
+                    "clang-20-debug",
+                    "clang-20-debug-install",
+                    "debug/lib/pkgconfig",
+                ),
+                "release" => (
+                    "clang-20-release",
+                    "clang-20-release-install",
+                    "lib/pkgconfig",
+                ),
+                _ => {
+                    panic!("Unsupported build profile: {}", build_profile);
+                }
+            };
 
             BuildDetails {
                 cmake_config_preset,
                 cmake_build_preset,
+                package_config_dir,
             }
         }
         _ => {
@@ -114,6 +172,56 @@ fn get_workspace_root() -> PathBuf {
         .to_path_buf()
 }
 
+/// Emits the link directives for the sanitizer runtimes the C++ side needs
+/// in the sanitize flavor.
+///
+/// ASan can't be linked in from here: its runtime has to be part of the
+/// final executable, which is what `-Zsanitizer=address` does. UBSan has no
+/// rustc equivalent, so the shared UBSan runtime of the clang that built
+/// the C++ code is linked explicitly.
+fn link_sanitizer_runtimes() {
+    let rust_sanitizers = env::var("CARGO_CFG_SANITIZE").unwrap_or_default();
+    if !rust_sanitizers.split(',').any(|s| s == "address") {
+        panic!(
+            "The sanitize flavor builds the C++ code with ASan, which needs the Rust side \
+             to provide the ASan runtime. Build with a nightly toolchain and \
+             RUSTFLAGS=\"-Zsanitizer=address\" --target <host triple> (see docs/sanitizers.md)"
+        );
+    }
+
+    let ubsan_runtime = find_clang_runtime(&[
+        "libclang_rt.ubsan_standalone.so",
+        "libclang_rt.ubsan_standalone-x86_64.so",
+    ])
+    .unwrap_or_else(|| panic!("Could not locate the UBSan runtime of {}", CLANG_DRIVER));
+
+    let runtime_dir = ubsan_runtime.parent().unwrap();
+    let lib_name = ubsan_runtime
+        .file_stem()
+        .unwrap()
+        .to_string_lossy()
+        .trim_start_matches("lib")
+        .to_string();
+
+    println!("cargo:rustc-link-search=native={}", runtime_dir.display());
+    println!("cargo:rustc-link-lib=dylib={}", lib_name);
+    println!("cargo:rustc-link-arg=-Wl,-rpath,{}", runtime_dir.display());
+}
+
+/// Asks the clang driver for the full path of one of `file_names`. Returns the
+/// first one that exists.
+fn find_clang_runtime(file_names: &[&str]) -> Option<PathBuf> {
+    file_names.iter().find_map(|file_name| {
+        let output = Command::new(CLANG_DRIVER)
+            .arg(format!("-print-file-name={}", file_name))
+            .output()
+            .ok()?;
+        // clang echoes the name back unchanged when it can't find the file.
+        let path = PathBuf::from(String::from_utf8_lossy(&output.stdout).trim());
+        (path.is_absolute() && path.exists()).then_some(path)
+    })
+}
+
 // Credit to ssrlive for this function
 // Taken from the following issue: https://github.com/rust-lang/cargo/issues/9661#issuecomment-1722358176
 fn get_cargo_target_dir() -> Result<std::path::PathBuf, Box<dyn std::error::Error>> {
@@ -138,16 +246,26 @@ fn main() {
     let target_arch = std::env::var("CARGO_CFG_TARGET_ARCH").unwrap();
     let build_profile = std::env::var("PROFILE").unwrap();
 
-    let build_details = deduce_build_details(target_os, &target_arch, &build_profile);
+    let build_flavor = deduce_build_flavor();
+    let build_details = deduce_build_details(target_os, &target_arch, &build_profile, build_flavor);
     let workspace_root = get_workspace_root();
 
     let cpp_root = workspace_root.parent().unwrap().join("cpp");
     println!(
-        "cargo:warning=Building for OS={:?}, ARCH={}, PROFILE={}, out_dir={}",
-        target_os, target_arch, build_profile, out_dir
+        "cargo:warning=Building for OS={:?}, ARCH={}, PROFILE={}, FLAVOR={:?}, out_dir={}",
+        target_os, target_arch, build_profile, build_flavor, out_dir
     );
 
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed={}", SANITIZE_ENV_VAR);
+
+    // Lets tests that only make sense on an instrumented build opt in with
+    // `#[cfg(by2_sanitize)]`.
+    println!("cargo:rustc-check-cfg=cfg(by2_sanitize)");
+    if build_flavor == BuildFlavor::Sanitize {
+        println!("cargo:rustc-cfg=by2_sanitize");
+        link_sanitizer_runtimes();
+    }
 
     // Ensure the build script is re-run whenever any file under src/by2
 
@@ -163,10 +281,23 @@ fn main() {
         cmake_install_dir
     );
 
+    // Always pass the option: the preset's binary dir is shared between
+    // builds with and without the feature, and CMake caches the last value.
+    let with_mapping = env::var_os("CARGO_FEATURE_MAPPING").is_some();
+    let with_media = env::var_os("CARGO_FEATURE_MEDIA").is_some();
+
     let status = Command::new("cmake")
         .arg(format!("--preset={}", build_details.cmake_config_preset))
         .arg(format!("-DCMAKE_INSTALL_PREFIX={}", cmake_install_dir))
         .arg(format!("-DVCPKG_INSTALLED_DIR={}", vcpkg_install_dir))
+        .arg(format!(
+            "-DBY2_WITH_MAPPING={}",
+            if with_mapping { "ON" } else { "OFF" }
+        ))
+        .arg(format!(
+            "-DBY2_WITH_MEDIA={}",
+            if with_media { "ON" } else { "OFF" }
+        ))
         .current_dir(&cpp_root)
         .status()
         .expect("failed to run cmake configure");
@@ -192,6 +323,10 @@ fn main() {
         // The input header we would like to generate
         // bindings for.
         .header(format!("{}/by2/by2.h", include_dir))
+        // Emit C enum constants as `BY2_LOG_LEVEL_INFO` rather than
+        // `by2_log_level_BY2_LOG_LEVEL_INFO`; the constants are already
+        // prefixed in the header.
+        .prepend_enum_name(false)
         // Attributes for the generated bindings are applied in the
         // surrounding module (src/bridge/src/lib.rs). Avoid emitting
         // attributes here to keep the generated file as a plain include.
@@ -218,11 +353,7 @@ fn main() {
     // PKG_CONFIG_LIBDIR to the install's pkgconfig dir so probe finds the
     // by2.pc that CMake produced. If probe fails we fall back to the
     // previous manual parsing logic.
-    let pkgconfig_dir = PathBuf::from(&cmake_install_dir).join(if build_profile == "debug" {
-        "debug/lib/pkgconfig"
-    } else {
-        "lib/pkgconfig"
-    });
+    let pkgconfig_dir = PathBuf::from(&cmake_install_dir).join(build_details.package_config_dir);
 
     // Try using pkg-config crate. Set PKG_CONFIG_LIBDIR so the probe finds
     // the .pc file that CMake generated inside our install prefix.
@@ -0,0 +1,82 @@
+use std::ffi::CStr;
+use std::fmt;
+
+use crate::bindings::{
+    BY2_STATUS_CALLBACK_FAILED, BY2_STATUS_INTERNAL, BY2_STATUS_INVALID_ARGUMENT, BY2_STATUS_IO,
+    BY2_STATUS_NOT_CONVERGED, BY2_STATUS_OK, BY2_STATUS_SINGULAR, by2_last_error_message,
+};
+
+/// Error reported by the native core, or by the safe wrappers before a
+/// call reaches it.
+///
+/// Each variant carries a human readable message. For errors coming from
+/// C++ this is the text of `by2_last_error_message`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum By2Error {
+    /// An argument was rejected, e.g. mismatched matrix dimensions.
+    InvalidArgument(String),
+    /// The matrix is singular, rank deficient or not positive definite.
+    Singular(String),
+    /// An iterative algorithm did not converge.
+    NotConverged(String),
+    /// Unexpected failure inside the native core.
+    Internal(String),
+    /// A Rust callback handed to the native core failed or panicked.
+    CallbackFailed(String),
+    /// Reading or writing a file failed.
+    Io(String),
+}
+
+impl By2Error {
+    /// The message describing this error.
+    pub fn message(&self) -> &str {
+        match self {
+            By2Error::InvalidArgument(message)
+            | By2Error::Singular(message)
+            | By2Error::NotConverged(message)
+            | By2Error::Internal(message)
+            | By2Error::CallbackFailed(message)
+            | By2Error::Io(message) => message,
+        }
+    }
+}
+
+impl fmt::Display for By2Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            By2Error::InvalidArgument(_) => "invalid argument",
+            By2Error::Singular(_) => "singular matrix",
+            By2Error::NotConverged(_) => "not converged",
+            By2Error::Internal(_) => "internal error",
+            By2Error::CallbackFailed(_) => "callback failed",
+            By2Error::Io(_) => "i/o error",
+        };
+        write!(f, "{}: {}", kind, self.message())
+    }
+}
+
+impl std::error::Error for By2Error {}
+
+/// Turns a `by2_status` returned by a native call into a `Result`, picking
+/// up the thread's last error message on failure.
+pub(crate) fn check_status(status: i32) -> Result<(), By2Error> {
+    if status == BY2_STATUS_OK as i32 {
+        return Ok(());
+    }
+
+    // The message is owned by the native side and stays valid until the
+    // next failing call on this thread; copy it out right away.
+    let message = unsafe { CStr::from_ptr(by2_last_error_message()) }
+        .to_string_lossy()
+        .into_owned();
+
+    Err(match status {
+        s if s == BY2_STATUS_INVALID_ARGUMENT as i32 => By2Error::InvalidArgument(message),
+        s if s == BY2_STATUS_SINGULAR as i32 => By2Error::Singular(message),
+        s if s == BY2_STATUS_NOT_CONVERGED as i32 => By2Error::NotConverged(message),
+        s if s == BY2_STATUS_INTERNAL as i32 => By2Error::Internal(message),
+        s if s == BY2_STATUS_CALLBACK_FAILED as i32 => By2Error::CallbackFailed(message),
+        s if s == BY2_STATUS_IO as i32 => By2Error::Io(message),
+        s => By2Error::Internal(format!("unknown status {}: {}", s, message)),
+    })
+}
@@ -0,0 +1,416 @@
+//! Rigid-body geometry backed by Eigen's `Geometry` module in the native core.
+//!
+//! Conventions follow `by2.h`: Hamilton quaternions, row-major rotation
+//! matrices, euler angles applied as `Rz(yaw) * Ry(pitch) * Rx(roll)`, and
+//! poses mapping `p` to `rotation * p + translation`.
+//!
+//! A [`Quaternion`] is always a unit quaternion with `w >= 0`; invalid input is
+//! rejected when it is constructed. That keeps the operators, which can't
+//! return a `Result`, infallible.
+
+use std::ops::Mul;
+
+use crate::bindings::{
+    by2_axis_angle, by2_euler, by2_mat3, by2_pose, by2_pose_compose, by2_pose_inverse,
+    by2_pose_transform_points, by2_quat, by2_quat_from_axis_angle, by2_quat_from_euler,
+    by2_quat_from_matrix, by2_quat_inverse, by2_quat_multiply, by2_quat_to_axis_angle,
+    by2_quat_to_euler, by2_quat_to_matrix, by2_vec3,
+};
+use crate::error::{By2Error, check_status};
+
+/// Row-major 3x3 rotation matrix.
+pub type RotationMatrix = [[f64; 3]; 3];
+
+/// The inverse of a rotation or rigid transform, in the style of the
+/// `std::ops` traits.
+pub trait Inverse {
+    type Output;
+
+    fn inverse(self) -> Self::Output;
+}
+
+/// Unit quaternion `w + xi + yj + zk` with `w >= 0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion {
+    w: f64,
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+/// Intrinsic z-y'-x'' angles in radians.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EulerAngles {
+    pub roll: f64,
+    pub pitch: f64,
+    pub yaw: f64,
+}
+
+/// Rotation by `angle` radians about `axis`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisAngle {
+    pub axis: [f64; 3],
+    pub angle: f64,
+}
+
+/// Rigid transform in SE(3).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pose {
+    pub rotation: Quaternion,
+    pub translation: [f64; 3],
+}
+
+impl Quaternion {
+    pub const IDENTITY: Self = Self {
+        w: 1.0,
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+
+    /// Normalizes `(w, x, y, z)`. Fails for a zero or non-finite quaternion.
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Result<Self, By2Error> {
+        // Same threshold as the native side.
+        let norm = (w * w + x * x + y * y + z * z).sqrt();
+        if !norm.is_finite() || norm < 1e-12 {
+            return Err(By2Error::InvalidArgument(format!(
+                "({w}, {x}, {y}, {z}) is not a rotation"
+            )));
+        }
+        let s = if w < 0.0 { -1.0 / norm } else { 1.0 / norm };
+        Ok(Self {
+            w: w * s,
+            x: x * s,
+            y: y * s,
+            z: z * s,
+        })
+    }
+
+    pub fn w(&self) -> f64 {
+        self.w
+    }
+
+    pub fn x(&self) -> f64 {
+        self.x
+    }
+
+    pub fn y(&self) -> f64 {
+        self.y
+    }
+
+    pub fn z(&self) -> f64 {
+        self.z
+    }
+
+    /// Fails unless `m` is orthonormal with determinant +1 (tolerance 1e-6).
+    pub fn from_rotation_matrix(m: &RotationMatrix) -> Result<Self, By2Error> {
+        let raw = by2_mat3 {
+            m: std::array::from_fn(|i| m[i / 3][i % 3]),
+        };
+        let mut out = Self::IDENTITY.to_raw();
+        check_status(unsafe { by2_quat_from_matrix(&raw, &mut out) })?;
+        Ok(Self::from_raw(out))
+    }
+
+    pub fn to_rotation_matrix(&self) -> RotationMatrix {
+        let mut out = by2_mat3 { m: [0.0; 9] };
+        expect_ok(unsafe { by2_quat_to_matrix(&self.to_raw(), &mut out) });
+        std::array::from_fn(|r| std::array::from_fn(|c| out.m[r * 3 + c]))
+    }
+
+    /// Fails for non-finite angles.
+    pub fn from_euler(e: EulerAngles) -> Result<Self, By2Error> {
+        let raw = by2_euler {
+            roll: e.roll,
+            pitch: e.pitch,
+            yaw: e.yaw,
+        };
+        let mut out = Self::IDENTITY.to_raw();
+        check_status(unsafe { by2_quat_from_euler(&raw, &mut out) })?;
+        Ok(Self::from_raw(out))
+    }
+
+    /// `pitch` is in `[-pi/2, pi/2]`. At gimbal lock `roll` is reported as 0.
+    pub fn to_euler(&self) -> EulerAngles {
+        let mut out = by2_euler {
+            roll: 0.0,
+            pitch: 0.0,
+            yaw: 0.0,
+        };
+        expect_ok(unsafe { by2_quat_to_euler(&self.to_raw(), &mut out) });
+        EulerAngles {
+            roll: out.roll,
+            pitch: out.pitch,
+            yaw: out.yaw,
+        }
+    }
+
+    /// Fails for a zero axis or non-finite input. The axis does not need to
+    /// be unit length.
+    pub fn from_axis_angle(a: AxisAngle) -> Result<Self, By2Error> {
+        let raw = by2_axis_angle {
+            axis: vec3_to_raw(a.axis),
+            angle: a.angle,
+        };
+        let mut out = Self::IDENTITY.to_raw();
+        check_status(unsafe { by2_quat_from_axis_angle(&raw, &mut out) })?;
+        Ok(Self::from_raw(out))
+    }
+
+    /// `angle` is in `[0, pi]`; the identity gives axis `(1, 0, 0)`.
+    pub fn to_axis_angle(&self) -> AxisAngle {
+        let mut out = by2_axis_angle {
+            axis: vec3_to_raw([0.0; 3]),
+            angle: 0.0,
+        };
+        expect_ok(unsafe { by2_quat_to_axis_angle(&self.to_raw(), &mut out) });
+        AxisAngle {
+            axis: vec3_from_raw(out.axis),
+            angle: out.angle,
+        }
+    }
+
+    fn to_raw(self) -> by2_quat {
+        by2_quat {
+            w: self.w,
+            x: self.x,
+            y: self.y,
+            z: self.z,
+        }
+    }
+
+    /// The native side only returns unit quaternions with `w >= 0`.
+    fn from_raw(q: by2_quat) -> Self {
+        Self {
+            w: q.w,
+            x: q.x,
+            y: q.y,
+            z: q.z,
+        }
+    }
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// `a * b`: rotates by `b` first, then by `a`.
+impl Mul for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, rhs: Quaternion) -> Quaternion {
+        let mut out = Self::IDENTITY.to_raw();
+        expect_ok(unsafe { by2_quat_multiply(&self.to_raw(), &rhs.to_raw(), &mut out) });
+        Self::from_raw(out)
+    }
+}
+
+/// Rotates a point.
+impl Mul<[f64; 3]> for Quaternion {
+    type Output = [f64; 3];
+
+    fn mul(self, rhs: [f64; 3]) -> [f64; 3] {
+        Pose::new(self, [0.0; 3]) * rhs
+    }
+}
+
+impl Inverse for Quaternion {
+    type Output = Quaternion;
+
+    fn inverse(self) -> Quaternion {
+        let mut out = Self::IDENTITY.to_raw();
+        expect_ok(unsafe { by2_quat_inverse(&self.to_raw(), &mut out) });
+        Self::from_raw(out)
+    }
+}
+
+impl Pose {
+    pub const IDENTITY: Self = Self {
+        rotation: Quaternion::IDENTITY,
+        translation: [0.0; 3],
+    };
+
+    pub fn new(rotation: Quaternion, translation: [f64; 3]) -> Self {
+        Self {
+            rotation,
+            translation,
+        }
+    }
+
+    /// Transforms every point into a new buffer.
+    pub fn transform_points(&self, points: &[[f64; 3]]) -> Vec<[f64; 3]> {
+        let mut out = vec![[0.0; 3]; points.len()];
+        let status = unsafe {
+            by2_pose_transform_points(
+                &self.to_raw(),
+                points.as_ptr().cast(),
+                out.as_mut_ptr().cast(),
+                points.len(),
+            )
+        };
+        expect_ok(status);
+        out
+    }
+
+    /// Transforms every point in place.
+    pub fn transform_points_in_place(&self, points: &mut [[f64; 3]]) {
+        // by2_pose_transform_points allows `out == points`.
+        let ptr = points.as_mut_ptr().cast::<f64>();
+        let status = unsafe { by2_pose_transform_points(&self.to_raw(), ptr, ptr, points.len()) };
+        expect_ok(status);
+    }
+
+    pub(crate) fn to_raw(self) -> by2_pose {
+        by2_pose {
+            rotation: self.rotation.to_raw(),
+            translation: vec3_to_raw(self.translation),
+        }
+    }
+
+    pub(crate) fn from_raw(p: by2_pose) -> Self {
+        Self::new(
+            Quaternion::from_raw(p.rotation),
+            vec3_from_raw(p.translation),
+        )
+    }
+}
+
+impl Default for Pose {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// `a * b`: applies `b` first, then `a`.
+impl Mul for Pose {
+    type Output = Pose;
+
+    fn mul(self, rhs: Pose) -> Pose {
+        let mut out = Self::IDENTITY.to_raw();
+        expect_ok(unsafe { by2_pose_compose(&self.to_raw(), &rhs.to_raw(), &mut out) });
+        Self::from_raw(out)
+    }
+}
+
+/// Transforms a point.
+impl Mul<[f64; 3]> for Pose {
+    type Output = [f64; 3];
+
+    fn mul(self, rhs: [f64; 3]) -> [f64; 3] {
+        self.transform_points(&[rhs])[0]
+    }
+}
+
+impl Inverse for Pose {
+    type Output = Pose;
+
+    fn inverse(self) -> Pose {
+        let mut out = Self::IDENTITY.to_raw();
+        expect_ok(unsafe { by2_pose_inverse(&self.to_raw(), &mut out) });
+        Self::from_raw(out)
+    }
+}
+
+fn vec3_to_raw(v: [f64; 3]) -> by2_vec3 {
+    by2_vec3 {
+        x: v[0],
+        y: v[1],
+        z: v[2],
+    }
+}
+
+fn vec3_from_raw(v: by2_vec3) -> [f64; 3] {
+    [v.x, v.y, v.z]
+}
+
+/// For calls whose arguments are valid by construction: a failure here is a
+/// bug in the wrappers or the native core.
+fn expect_ok(status: i32) {
+    if let Err(err) = check_status(status) {
+        panic!("by2 rejected a valid geometry argument: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    fn assert_close(a: [f64; 3], b: [f64; 3]) {
+        for i in 0..3 {
+            assert!((a[i] - b[i]).abs() < 1e-12, "{a:?} != {b:?}");
+        }
+    }
+
+    fn yaw(angle: f64) -> Quaternion {
+        Quaternion::from_euler(EulerAngles {
+            roll: 0.0,
+            pitch: 0.0,
+            yaw: angle,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn new_normalizes_and_rejects_zero() {
+        let q = Quaternion::new(-2.0, 0.0, 0.0, 0.0).unwrap();
+        assert_eq!(q, Quaternion::IDENTITY);
+        let err = Quaternion::new(0.0, 0.0, 0.0, 0.0).unwrap_err();
+        assert!(matches!(err, By2Error::InvalidArgument(_)), "{err}");
+        assert!(Quaternion::new(f64::NAN, 0.0, 0.0, 1.0).is_err());
+    }
+
+    #[test]
+    fn yaw_rotates_x_onto_y() {
+        assert_close(yaw(FRAC_PI_2) * [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn rotation_matrix_round_trips_and_validates() {
+        let q = Quaternion::new(0.2, -0.5, 0.4, 0.7).unwrap();
+        let back = Quaternion::from_rotation_matrix(&q.to_rotation_matrix()).unwrap();
+        assert!((back.w() - q.w()).abs() < 1e-12);
+        assert!((back.x() - q.x()).abs() < 1e-12);
+
+        let scaled = [[2.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        let err = Quaternion::from_rotation_matrix(&scaled).unwrap_err();
+        assert!(matches!(err, By2Error::InvalidArgument(_)), "{err}");
+    }
+
+    #[test]
+    fn axis_angle_rejects_zero_axis() {
+        let err = Quaternion::from_axis_angle(AxisAngle {
+            axis: [0.0; 3],
+            angle: 1.0,
+        })
+        .unwrap_err();
+        assert!(matches!(err, By2Error::InvalidArgument(_)), "{err}");
+    }
+
+    #[test]
+    fn pose_composition_and_inverse() {
+        let a = Pose::new(yaw(FRAC_PI_2), [1.0, 0.0, 0.0]);
+        let b = Pose::new(Quaternion::IDENTITY, [0.0, 0.0, 2.0]);
+        // b first, then a.
+        assert_close((a * b) * [1.0, 0.0, 0.0], [1.0, 1.0, 2.0]);
+
+        let identity = a * a.inverse();
+        assert_close(identity.translation, [0.0; 3]);
+        assert!((identity.rotation.w() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn transform_points_in_place_matches_copy() {
+        let pose = Pose::new(
+            Quaternion::new(0.9, 0.1, -0.3, 0.2).unwrap(),
+            [1.0, -2.0, 3.0],
+        );
+        let mut points = vec![[0.0, 0.0, 0.0], [1.0, 2.0, 3.0], [-4.0, 0.5, 8.0]];
+        let expected = pose.transform_points(&points);
+        pose.transform_points_in_place(&mut points);
+        assert_eq!(points, expected);
+        assert!(pose.transform_points(&[]).is_empty());
+    }
+}
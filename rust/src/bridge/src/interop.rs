@@ -0,0 +1,89 @@
+//! Conversions between [`Matrix`]/[`MatrixView`] and the matrix types of
+//! other crates, behind the `nalgebra` and `ndarray` features. The `sprs`
+//! feature converts [`SparseMatrix`](crate::SparseMatrix) to and from
+//! `sprs::CsMat`.
+//!
+//! Inputs go through [`AsMatrixView`]. It borrows the foreign storage
+//! whenever its strides fit a [`MatrixView`] and only copies otherwise, which
+//! the returned [`MatrixCow`] makes visible. Results convert back with
+//! `From<Matrix<T>>`, which moves the buffer for the column-major matrices
+//! returned by the native routines.
+
+use crate::matrix::{Matrix, MatrixView, Scalar};
+
+#[cfg(feature = "nalgebra")]
+mod nalgebra;
+#[cfg(feature = "ndarray")]
+mod ndarray;
+#[cfg(feature = "sprs")]
+mod sprs;
+
+/// A matrix prepared for the native routines: borrowed from the caller's
+/// storage, or copied because that storage can't be described by a
+/// [`MatrixView`].
+#[derive(Debug, Clone)]
+pub enum MatrixCow<'a, T> {
+    Borrowed(MatrixView<'a, T>),
+    Copied(Matrix<T>),
+}
+
+impl<T: Scalar> MatrixCow<'_, T> {
+    /// View to pass to [`MatrixView::multiply`] and friends.
+    pub fn view(&self) -> MatrixView<'_, T> {
+        match self {
+            MatrixCow::Borrowed(view) => *view,
+            MatrixCow::Copied(matrix) => matrix.view(),
+        }
+    }
+
+    /// Whether the conversion had to copy the elements.
+    pub fn is_copied(&self) -> bool {
+        matches!(self, MatrixCow::Copied(_))
+    }
+}
+
+/// Matrix types that can be handed to the native routines.
+pub trait AsMatrixView<T: Scalar> {
+    /// Borrows `self` if its layout can be described by a [`MatrixView`],
+    /// copies it otherwise.
+    fn as_matrix_view(&self) -> MatrixCow<'_, T>;
+}
+
+/// Describes `rows x cols` elements starting at `ptr` with the given strides
+/// (in elements) as a [`MatrixView`], or `None` when a stride is zero or
+/// negative or the last element's offset overflows.
+///
+/// Only the elements themselves are read; the storage between them may be
+/// mutably borrowed elsewhere (split views), so no slice spanning them is
+/// ever formed.
+///
+/// # Safety
+///
+/// Every element `ptr + r * row_stride + c * col_stride` must be readable,
+/// and not mutated, for `'a`.
+#[cfg(any(feature = "nalgebra", feature = "ndarray"))]
+unsafe fn strided_view<'a, T: Scalar>(
+    ptr: *const T,
+    rows: usize,
+    cols: usize,
+    row_stride: isize,
+    col_stride: isize,
+) -> Option<MatrixView<'a, T>> {
+    if rows == 0 || cols == 0 {
+        return MatrixView::with_strides(&[], rows, cols, 1, 1).ok();
+    }
+
+    // The stride of an axis of length one is never applied, and both crates
+    // leave it at arbitrary values (zero included).
+    let row_stride = if rows == 1 { 1 } else { row_stride };
+    let col_stride = if cols == 1 { 1 } else { col_stride };
+    let row_stride = usize::try_from(row_stride).ok().filter(|&s| s > 0)?;
+    let col_stride = usize::try_from(col_stride).ok().filter(|&s| s > 0)?;
+
+    // The native side computes offsets as `ptrdiff_t`.
+    let last = (rows - 1)
+        .checked_mul(row_stride)?
+        .checked_add((cols - 1).checked_mul(col_stride)?)?;
+    isize::try_from(last).ok()?;
+    Some(unsafe { MatrixView::from_raw_parts(ptr, rows, cols, row_stride, col_stride) })
+}
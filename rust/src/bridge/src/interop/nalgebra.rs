@@ -0,0 +1,119 @@
+use nalgebra::{DMatrix, Dim, RawStorage};
+
+use super::{AsMatrixView, MatrixCow, strided_view};
+use crate::matrix::{Layout, Matrix, Scalar};
+
+/// Borrows any nalgebra matrix or view: its storage is always described by a
+/// pointer and two non-negative strides.
+impl<T, R, C, S> AsMatrixView<T> for nalgebra::Matrix<T, R, C, S>
+where
+    T: Scalar,
+    R: Dim,
+    C: Dim,
+    S: RawStorage<T, R, C>,
+{
+    fn as_matrix_view(&self) -> MatrixCow<'_, T> {
+        let (rows, cols) = self.shape();
+        let (row_stride, col_stride) = self.strides();
+        // Every element indexed by the strides belongs to `self`, which
+        // is borrowed for the lifetime of the view.
+        let view = unsafe {
+            strided_view(
+                self.as_ptr(),
+                rows,
+                cols,
+                row_stride as isize,
+                col_stride as isize,
+            )
+        };
+        match view {
+            Some(view) => MatrixCow::Borrowed(view),
+            None => MatrixCow::Copied(
+                Matrix::from_vec(rows, cols, Layout::ColMajor, self.iter().copied().collect())
+                    .expect("nalgebra matrix has rows * cols elements"),
+            ),
+        }
+    }
+}
+
+/// Moves the buffer of a column-major matrix, copies a row-major one.
+impl<T: Scalar + nalgebra::Scalar> From<Matrix<T>> for DMatrix<T> {
+    fn from(matrix: Matrix<T>) -> Self {
+        let (rows, cols) = (matrix.rows(), matrix.cols());
+        match matrix.layout() {
+            Layout::ColMajor => DMatrix::from_vec(rows, cols, matrix.into_vec()),
+            Layout::RowMajor => DMatrix::from_row_slice(rows, cols, matrix.as_slice()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Matrix2x3;
+
+    use super::*;
+
+    #[test]
+    fn dmatrix_is_borrowed() {
+        let m = DMatrix::from_row_slice(2, 3, &[1.0f64, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let cow = m.as_matrix_view();
+        assert!(!cow.is_copied());
+        let view = cow.view();
+        assert_eq!((view.rows(), view.cols()), (2, 3));
+        assert_eq!((view.row_stride(), view.col_stride()), (1, 2));
+        assert_eq!(view.get(1, 2), Some(6.0));
+    }
+
+    #[test]
+    fn static_matrices_and_sub_views_are_borrowed() {
+        let m = Matrix2x3::new(1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0);
+        assert!(!m.as_matrix_view().is_copied());
+
+        let big = DMatrix::from_fn(4, 4, |r, c| (r * 4 + c) as f64);
+        let block = big.view((1, 2), (2, 2));
+        let cow = block.as_matrix_view();
+        assert!(!cow.is_copied());
+        assert_eq!(cow.view().get(0, 0), Some(6.0));
+        assert_eq!(cow.view().get(1, 1), Some(11.0));
+    }
+
+    #[test]
+    fn split_mutable_views_are_borrowed() {
+        let mut m = DMatrix::from_fn(4, 3, |r, c| (r * 3 + c) as f64);
+        let (top, mut bottom) = m.rows_range_pair_mut(..2, 2..);
+        // The columns of `top` straddle elements that `bottom` owns.
+        let cow = top.as_matrix_view();
+        bottom.fill(-1.0);
+        assert!(!cow.is_copied());
+        assert_eq!(cow.view().get(1, 2), Some(5.0));
+    }
+
+    #[test]
+    fn empty_matrix_is_borrowed() {
+        let m = DMatrix::<f64>::zeros(0, 3);
+        let cow = m.as_matrix_view();
+        assert!(!cow.is_copied());
+        assert_eq!((cow.view().rows(), cow.view().cols()), (0, 3));
+    }
+
+    #[test]
+    fn multiply_round_trips_through_dmatrix() {
+        let a = DMatrix::from_row_slice(2, 3, &[1.0f64, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let b = a.transpose();
+        let product = a
+            .as_matrix_view()
+            .view()
+            .multiply(&b.as_matrix_view().view())
+            .unwrap();
+        assert_eq!(DMatrix::from(product), &a * &b);
+    }
+
+    #[test]
+    fn row_major_matrix_converts_to_dmatrix() {
+        let m = Matrix::from_vec(2, 2, Layout::RowMajor, vec![1.0f64, 2.0, 3.0, 4.0]).unwrap();
+        assert_eq!(
+            DMatrix::from(m),
+            DMatrix::from_row_slice(2, 2, &[1.0, 2.0, 3.0, 4.0])
+        );
+    }
+}
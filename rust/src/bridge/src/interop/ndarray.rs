@@ -0,0 +1,117 @@
+use ndarray::{Array2, ArrayBase, Data, Ix2, ShapeBuilder};
+
+use super::{AsMatrixView, MatrixCow, strided_view};
+use crate::matrix::{Layout, Matrix, Scalar};
+
+/// Borrows arrays with positive strides (standard, Fortran order, transposed
+/// or sliced with positive steps). Reversed axes and broadcast arrays are
+/// copied into a row-major [`Matrix`].
+impl<T, S> AsMatrixView<T> for ArrayBase<S, Ix2>
+where
+    T: Scalar,
+    S: Data<Elem = T>,
+{
+    fn as_matrix_view(&self) -> MatrixCow<'_, T> {
+        let (rows, cols) = self.dim();
+        let strides = self.strides();
+        // Every element indexed by the strides belongs to `self`, which is
+        // borrowed for the lifetime of the view.
+        let view = unsafe { strided_view(self.as_ptr(), rows, cols, strides[0], strides[1]) };
+        match view {
+            Some(view) => MatrixCow::Borrowed(view),
+            None => MatrixCow::Copied(
+                // `iter` walks in logical row-major order whatever the strides.
+                Matrix::from_vec(rows, cols, Layout::RowMajor, self.iter().copied().collect())
+                    .expect("ndarray matrix has rows * cols elements"),
+            ),
+        }
+    }
+}
+
+/// Moves the buffer in either layout.
+impl<T: Scalar> From<Matrix<T>> for Array2<T> {
+    fn from(matrix: Matrix<T>) -> Self {
+        let (rows, cols) = (matrix.rows(), matrix.cols());
+        let shape = (rows, cols).set_f(matrix.layout() == Layout::ColMajor);
+        Array2::from_shape_vec(shape, matrix.into_vec()).expect("matrix has rows * cols elements")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::{array, s};
+
+    use super::*;
+
+    #[test]
+    fn standard_and_fortran_layouts_are_borrowed() {
+        let a = array![[1.0f64, 2.0, 3.0], [4.0, 5.0, 6.0]];
+        let cow = a.as_matrix_view();
+        assert!(!cow.is_copied());
+        assert_eq!((cow.view().row_stride(), cow.view().col_stride()), (3, 1));
+
+        let f = Array2::from_shape_vec((2, 3).f(), vec![1.0f64, 4.0, 2.0, 5.0, 3.0, 6.0]).unwrap();
+        let cow = f.as_matrix_view();
+        assert!(!cow.is_copied());
+        assert_eq!((cow.view().row_stride(), cow.view().col_stride()), (1, 2));
+        assert_eq!(cow.view().get(1, 2), Some(6.0));
+    }
+
+    #[test]
+    fn transposed_and_stepped_views_are_borrowed() {
+        let a = Array2::from_shape_fn((4, 4), |(r, c)| (r * 4 + c) as f64);
+        let t = a.t();
+        let cow = t.as_matrix_view();
+        assert!(!cow.is_copied());
+        assert_eq!(cow.view().get(0, 1), Some(4.0));
+
+        let stepped = a.slice(s![1.., ..;2]);
+        let cow = stepped.as_matrix_view();
+        assert!(!cow.is_copied());
+        assert_eq!(cow.view().get(2, 1), Some(14.0));
+    }
+
+    #[test]
+    fn split_mutable_views_are_borrowed() {
+        let mut a = Array2::from_shape_fn((3, 4), |(r, c)| (r * 4 + c) as f64);
+        let (left, mut right) = a.multi_slice_mut((s![.., ..2], s![.., 2..]));
+        // The rows of `left` straddle elements that `right` owns.
+        let cow = left.as_matrix_view();
+        right.fill(-1.0);
+        assert!(!cow.is_copied());
+        assert_eq!(cow.view().get(2, 1), Some(9.0));
+    }
+
+    #[test]
+    fn reversed_and_broadcast_views_are_copied() {
+        let a = array![[1.0f64, 2.0], [3.0, 4.0]];
+        let reversed = a.slice(s![..;-1, ..]);
+        let cow = reversed.as_matrix_view();
+        assert!(cow.is_copied());
+        assert_eq!(cow.view().get(0, 0), Some(3.0));
+        assert_eq!(cow.view().get(1, 1), Some(2.0));
+
+        let row = array![1.0f32, 2.0, 3.0];
+        let broadcast = row.broadcast((2, 3)).unwrap();
+        let cow = broadcast.as_matrix_view();
+        assert!(cow.is_copied());
+        assert_eq!(cow.view().get(1, 2), Some(3.0));
+    }
+
+    #[test]
+    fn multiply_round_trips_through_array2() {
+        let a = array![[1.0f64, 2.0, 3.0], [4.0, 5.0, 6.0]];
+        let product = a
+            .as_matrix_view()
+            .view()
+            .multiply(&a.t().as_matrix_view().view())
+            .unwrap();
+        assert_eq!(Array2::from(product), a.dot(&a.t()));
+    }
+
+    #[test]
+    fn row_major_matrix_converts_to_array2() {
+        let m = Matrix::from_vec(2, 2, Layout::RowMajor, vec![1.0f64, 2.0, 3.0, 4.0]).unwrap();
+        assert_eq!(Array2::from(m), array![[1.0, 2.0], [3.0, 4.0]]);
+    }
+}
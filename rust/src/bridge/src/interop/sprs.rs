@@ -0,0 +1,92 @@
+use std::ops::Deref;
+
+use sprs::{CsMat, CsMatBase};
+
+use crate::matrix::Scalar;
+use crate::sparse::{SparseFormat, SparseMatrix};
+
+/// Moves the compressed arrays; sprs accepts exactly the same structure.
+impl<T: Scalar> From<SparseMatrix<T>> for CsMat<T> {
+    fn from(matrix: SparseMatrix<T>) -> Self {
+        let shape = (matrix.rows(), matrix.cols());
+        let format = matrix.format();
+        let (offsets, indices, values) = matrix.into_compressed();
+        match format {
+            SparseFormat::Csr => CsMat::new(shape, offsets, indices, values),
+            SparseFormat::Csc => CsMat::new_csc(shape, offsets, indices, values),
+        }
+    }
+}
+
+/// Copies any sprs matrix or view with `usize` indices, keeping its storage
+/// order.
+impl<T, IptrStorage, IndStorage, DataStorage>
+    From<&CsMatBase<T, usize, IptrStorage, IndStorage, DataStorage>> for SparseMatrix<T>
+where
+    T: Scalar,
+    IptrStorage: Deref<Target = [usize]>,
+    IndStorage: Deref<Target = [usize]>,
+    DataStorage: Deref<Target = [T]>,
+{
+    fn from(matrix: &CsMatBase<T, usize, IptrStorage, IndStorage, DataStorage>) -> Self {
+        let format = if matrix.is_csr() {
+            SparseFormat::Csr
+        } else {
+            SparseFormat::Csc
+        };
+        SparseMatrix::from_compressed(
+            format,
+            matrix.rows(),
+            matrix.cols(),
+            matrix.proper_indptr().into_owned(),
+            matrix.indices().to_vec(),
+            matrix.data().to_vec(),
+        )
+        .expect("sprs matrices have sorted, in-range indices")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sprs::TriMat;
+
+    use super::*;
+    use crate::matrix::{Layout, Matrix};
+
+    fn sample(format: SparseFormat) -> SparseMatrix<f64> {
+        SparseMatrix::from_triplets(2, 3, format, &[(0, 0, 1.0), (0, 2, 2.0), (1, 1, 3.0)]).unwrap()
+    }
+
+    #[test]
+    fn round_trips_in_both_formats() {
+        for format in [SparseFormat::Csr, SparseFormat::Csc] {
+            let matrix = sample(format);
+            let converted = CsMat::from(matrix.clone());
+            assert_eq!(converted.is_csr(), format == SparseFormat::Csr);
+            assert_eq!(converted.get(0, 2), Some(&2.0));
+            assert_eq!(converted.get(1, 0), None);
+            assert_eq!(SparseMatrix::from(&converted), matrix);
+        }
+    }
+
+    #[test]
+    fn converts_sprs_built_matrices_and_views() {
+        let mut triplets = TriMat::new((2, 3));
+        triplets.add_triplet(1, 1, 3.0);
+        triplets.add_triplet(0, 2, 2.0);
+        triplets.add_triplet(0, 0, 1.0);
+        let csc: CsMat<f64> = triplets.to_csc();
+        assert_eq!(SparseMatrix::from(&csc.view()), sample(SparseFormat::Csc));
+    }
+
+    #[test]
+    fn sliced_views_keep_their_offsets() {
+        let csr: CsMat<f64> = sample(SparseFormat::Csr).into();
+        // The second row alone has an indptr that starts past zero.
+        let row = csr.slice_outer(1..2);
+        let matrix = SparseMatrix::from(&row);
+        assert_eq!(matrix.offsets(), [0, 1]);
+        let b = Matrix::from_vec(3, 1, Layout::ColMajor, vec![1.0, 1.0, 1.0]).unwrap();
+        assert_eq!(matrix.multiply(&b.view()).unwrap().as_slice(), [3.0]);
+    }
+}
@@ -0,0 +1,309 @@
+//! A native kd-tree over the points of a cloud.
+//!
+//! [`KdTree`] owns a `by2_kdtree` handle. The tree copies the coordinates it
+//! indexes, so it doesn't borrow the cloud it was built from, and its queries
+//! only read it, so a tree can be shared between threads.
+//!
+//! Results match [`PointCloud::knn`] and [`PointCloud::radius_neighbors`]:
+//! points that are not finite are never returned, squared distances are
+//! computed in `f64`, and neighbours are sorted by (distance, index).
+//!
+//! [`PointCloud::knn`]: rcore::point_cloud::PointCloud::knn
+//! [`PointCloud::radius_neighbors`]: rcore::point_cloud::PointCloud::radius_neighbors
+
+use std::ptr::{self, NonNull};
+
+use rcore::point_cloud::Neighbor;
+
+use crate::bindings::{
+    by2_kdtree, by2_kdtree_create, by2_kdtree_destroy, by2_kdtree_knn, by2_kdtree_knn_batch,
+    by2_kdtree_radius, by2_kdtree_radius_batch, by2_kdtree_size, by2_neighbor, by2_vec3,
+};
+use crate::error::{By2Error, check_status};
+use crate::point_cloud::PointCloudView;
+
+/// Room reserved per query before the first radius search; larger result
+/// sets cost one more search.
+const RADIUS_GUESS: usize = 16;
+
+/// Spatial index over the finite points of a cloud.
+#[derive(Debug)]
+pub struct KdTree {
+    raw: NonNull<by2_kdtree>,
+}
+
+// The native tree is immutable once built and its queries are documented as
+// safe to run concurrently.
+unsafe impl Send for KdTree {}
+unsafe impl Sync for KdTree {}
+
+impl KdTree {
+    /// Builds a tree over the finite points of `cloud`.
+    pub fn new(cloud: &PointCloudView<'_>) -> Result<Self, By2Error> {
+        let mut raw = ptr::null_mut();
+        check_status(unsafe { by2_kdtree_create(cloud.as_raw(), &mut raw) })?;
+        let raw = NonNull::new(raw)
+            .ok_or_else(|| By2Error::Internal("by2_kdtree_create returned null".into()))?;
+        Ok(Self { raw })
+    }
+
+    /// Number of indexed points, which excludes those that are not finite.
+    pub fn len(&self) -> usize {
+        unsafe { by2_kdtree_size(self.raw.as_ptr()) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The `k` points nearest to `query`, or all of them when the tree has
+    /// fewer.
+    pub fn knn(&self, query: [f64; 3], k: usize) -> Result<Vec<Neighbor>, By2Error> {
+        let mut out = vec![empty(); k.min(self.len())];
+        let mut count = 0;
+        // `out` holds min(k, size) entries, the most the query writes.
+        check_status(unsafe {
+            by2_kdtree_knn(
+                self.raw.as_ptr(),
+                &vec3(query),
+                k,
+                out.as_mut_ptr(),
+                &mut count,
+            )
+        })?;
+        Ok(out[..count].iter().map(from_raw).collect())
+    }
+
+    /// The points within `radius` of `query`, boundary included.
+    pub fn radius(&self, query: [f64; 3], radius: f64) -> Result<Vec<Neighbor>, By2Error> {
+        let query = vec3(query);
+        let mut out = vec![empty(); RADIUS_GUESS];
+        loop {
+            let mut count = 0;
+            check_status(unsafe {
+                by2_kdtree_radius(
+                    self.raw.as_ptr(),
+                    &query,
+                    radius,
+                    out.as_mut_ptr(),
+                    out.len(),
+                    &mut count,
+                )
+            })?;
+            // Nothing was written if the matches didn't fit; retry with room
+            // for all of them.
+            if count <= out.len() {
+                return Ok(out[..count].iter().map(from_raw).collect());
+            }
+            out.resize(count, empty());
+        }
+    }
+
+    /// [`knn`](Self::knn) for each of `queries`.
+    pub fn knn_batch(
+        &self,
+        queries: &[[f64; 3]],
+        k: usize,
+    ) -> Result<Vec<Vec<Neighbor>>, By2Error> {
+        let stride = k.min(self.len());
+        let total = stride
+            .checked_mul(queries.len())
+            .ok_or_else(|| By2Error::InvalidArgument("knn batch output overflows usize".into()))?;
+        let raw_queries: Vec<by2_vec3> = queries.iter().copied().map(vec3).collect();
+        let mut out = vec![empty(); total];
+        let mut counts = vec![0; queries.len()];
+        // `out` holds `stride` entries per query and `counts` one per query.
+        check_status(unsafe {
+            by2_kdtree_knn_batch(
+                self.raw.as_ptr(),
+                raw_queries.as_ptr(),
+                raw_queries.len(),
+                k,
+                out.as_mut_ptr(),
+                counts.as_mut_ptr(),
+            )
+        })?;
+        Ok(counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| {
+                out[i * stride..i * stride + count]
+                    .iter()
+                    .map(from_raw)
+                    .collect()
+            })
+            .collect())
+    }
+
+    /// [`radius`](Self::radius) for each of `queries`.
+    pub fn radius_batch(
+        &self,
+        queries: &[[f64; 3]],
+        radius: f64,
+    ) -> Result<Vec<Vec<Neighbor>>, By2Error> {
+        let raw_queries: Vec<by2_vec3> = queries.iter().copied().map(vec3).collect();
+        let mut out = vec![empty(); RADIUS_GUESS * queries.len()];
+        let mut offsets = vec![0; queries.len() + 1];
+        loop {
+            // `offsets` holds query_count + 1 entries.
+            check_status(unsafe {
+                by2_kdtree_radius_batch(
+                    self.raw.as_ptr(),
+                    raw_queries.as_ptr(),
+                    raw_queries.len(),
+                    radius,
+                    out.as_mut_ptr(),
+                    out.len(),
+                    offsets.as_mut_ptr(),
+                )
+            })?;
+            let total = offsets[queries.len()];
+            if total <= out.len() {
+                break;
+            }
+            out.resize(total, empty());
+        }
+        Ok(offsets
+            .windows(2)
+            .map(|w| out[w[0]..w[1]].iter().map(from_raw).collect())
+            .collect())
+    }
+}
+
+impl Drop for KdTree {
+    fn drop(&mut self) {
+        unsafe { by2_kdtree_destroy(self.raw.as_ptr()) }
+    }
+}
+
+fn vec3([x, y, z]: [f64; 3]) -> by2_vec3 {
+    by2_vec3 { x, y, z }
+}
+
+fn empty() -> by2_neighbor {
+    by2_neighbor {
+        index: 0,
+        squared_distance: 0.0,
+    }
+}
+
+fn from_raw(raw: &by2_neighbor) -> Neighbor {
+    Neighbor {
+        index: raw.index,
+        squared_distance: raw.squared_distance,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rcore::point_cloud::PointCloud;
+
+    use super::*;
+
+    /// A 4 x 4 x 4 lattice with spacing 1 plus a NaN point at index 64.
+    fn lattice() -> PointCloud {
+        let mut points = Vec::new();
+        for i in 0..4 {
+            for j in 0..4 {
+                for k in 0..4 {
+                    points.push([i as f32, j as f32, k as f32]);
+                }
+            }
+        }
+        points.push([f32::NAN, 0.0, 0.0]);
+        PointCloud::from_points(&points)
+    }
+
+    fn indices(found: &[Neighbor]) -> Vec<usize> {
+        found.iter().map(|n| n.index).collect()
+    }
+
+    #[test]
+    fn tree_skips_points_that_are_not_finite() {
+        let cloud = lattice();
+        let tree = KdTree::new(&PointCloudView::from(&cloud)).unwrap();
+        assert_eq!(tree.len(), 64);
+        assert_eq!(tree.knn([0.0; 3], 100).unwrap().len(), 64);
+
+        let empty = PointCloud::new();
+        let tree = KdTree::new(&PointCloudView::from(&empty)).unwrap();
+        assert!(tree.is_empty());
+        assert!(tree.knn([0.0; 3], 3).unwrap().is_empty());
+        assert!(tree.radius([0.0; 3], 1.0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn knn_breaks_ties_by_index() {
+        let cloud = lattice();
+        let tree = KdTree::new(&PointCloudView::from(&cloud)).unwrap();
+        // (1, 1, 1) is index 21; its six axis neighbours are equidistant.
+        let found = tree.knn([1.0, 1.0, 1.0], 5).unwrap();
+        assert_eq!(indices(&found), [21, 5, 17, 20, 22]);
+        assert_eq!(found[0].squared_distance, 0.0);
+        assert!(found[1..].iter().all(|n| n.squared_distance == 1.0));
+    }
+
+    #[test]
+    fn radius_grows_its_buffer() {
+        let cloud = lattice();
+        let tree = KdTree::new(&PointCloudView::from(&cloud)).unwrap();
+        // More matches than the first guess has room for.
+        let found = tree.radius([1.5, 1.5, 1.5], 10.0).unwrap();
+        assert_eq!(found.len(), 64);
+        assert!(
+            found
+                .windows(2)
+                .all(|w| w[0].squared_distance <= w[1].squared_distance)
+        );
+        assert_eq!(tree.radius([0.0; 3], 1.0).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn batches_match_single_queries() {
+        let cloud = lattice();
+        let tree = KdTree::new(&PointCloudView::from(&cloud)).unwrap();
+        let queries = [[0.2, 0.0, 3.1], [1.5, 1.5, 1.5], [-9.0, 2.0, 2.0]];
+        let knn = tree.knn_batch(&queries, 7).unwrap();
+        let radius = tree.radius_batch(&queries, 2.5).unwrap();
+        for (i, &query) in queries.iter().enumerate() {
+            assert_eq!(knn[i], tree.knn(query, 7).unwrap());
+            assert_eq!(radius[i], tree.radius(query, 2.5).unwrap());
+        }
+        assert!(tree.knn_batch(&[], 3).unwrap().is_empty());
+        assert!(tree.radius_batch(&[], 1.0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn queries_run_concurrently() {
+        let cloud = lattice();
+        let tree = KdTree::new(&PointCloudView::from(&cloud)).unwrap();
+        let expected = tree.knn([1.2, 2.7, 0.4], 9).unwrap();
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..100 {
+                        assert_eq!(tree.knn([1.2, 2.7, 0.4], 9).unwrap(), expected);
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn bad_queries_are_rejected() {
+        let cloud = lattice();
+        let tree = KdTree::new(&PointCloudView::from(&cloud)).unwrap();
+        assert!(matches!(
+            tree.knn([f64::NAN, 0.0, 0.0], 1),
+            Err(By2Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            tree.radius([0.0; 3], -1.0),
+            Err(By2Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            tree.radius_batch(&[[0.0; 3], [0.0, f64::INFINITY, 0.0]], 1.0),
+            Err(By2Error::InvalidArgument(_))
+        ));
+    }
+}
@@ -0,0 +1,427 @@
+//! Nonlinear least squares (Levenberg-Marquardt / Gauss-Newton) in the native
+//! core, driven by Rust closures.
+//!
+//! The closures are called back from C++ through `extern "C"` trampolines. A
+//! panic inside them is caught before it reaches the C++ frames, aborts the
+//! solve and comes back as [`By2Error::CallbackFailed`].
+
+use std::any::Any;
+use std::ffi::c_void;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+use crate::bindings::{
+    BY2_LSQ_FUNCTION_TOLERANCE, BY2_LSQ_GAUSS_NEWTON, BY2_LSQ_GRADIENT_TOLERANCE,
+    BY2_LSQ_LEVENBERG_MARQUARDT, BY2_LSQ_MAX_ITERATIONS, BY2_LSQ_PARAMETER_TOLERANCE,
+    BY2_LSQ_TOLERANCE_TOO_SMALL, by2_lsq_default_options, by2_lsq_options, by2_lsq_solve,
+    by2_lsq_summary,
+};
+use crate::error::{By2Error, check_status};
+
+type Callback<'a> = Box<dyn FnMut(&[f64], &mut [f64]) + 'a>;
+
+/// Algorithm used by [`LeastSquares::solve`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeastSquaresMethod {
+    /// Eigen's MINPACK port. Robust to poor initial guesses.
+    LevenbergMarquardt,
+    /// Undamped Gauss-Newton steps. Cheaper, but needs a good initial guess.
+    GaussNewton,
+}
+
+impl LeastSquaresMethod {
+    fn to_raw(self) -> i32 {
+        let raw = match self {
+            LeastSquaresMethod::LevenbergMarquardt => BY2_LSQ_LEVENBERG_MARQUARDT,
+            LeastSquaresMethod::GaussNewton => BY2_LSQ_GAUSS_NEWTON,
+        };
+        raw as i32
+    }
+
+    fn from_raw(raw: i32) -> Result<Self, By2Error> {
+        Ok(match raw {
+            r if r == BY2_LSQ_LEVENBERG_MARQUARDT as i32 => LeastSquaresMethod::LevenbergMarquardt,
+            r if r == BY2_LSQ_GAUSS_NEWTON as i32 => LeastSquaresMethod::GaussNewton,
+            r => {
+                return Err(By2Error::Internal(format!(
+                    "unknown least squares method {r}"
+                )));
+            }
+        })
+    }
+}
+
+/// Stopping criteria. [`Default`] gives the native defaults.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LeastSquaresOptions {
+    pub method: LeastSquaresMethod,
+    pub max_iterations: usize,
+    /// Relative reduction of the cost.
+    pub function_tolerance: f64,
+    /// Relative change of the parameters.
+    pub parameter_tolerance: f64,
+    /// Gradient threshold (Levenberg-Marquardt: cosine between the residuals
+    /// and the jacobian columns).
+    pub gradient_tolerance: f64,
+}
+
+impl Default for LeastSquaresOptions {
+    fn default() -> Self {
+        let mut raw = by2_lsq_options {
+            method: 0,
+            max_iterations: 0,
+            function_tolerance: 0.0,
+            parameter_tolerance: 0.0,
+            gradient_tolerance: 0.0,
+        };
+        unsafe { by2_lsq_default_options(&mut raw) };
+        Self {
+            method: LeastSquaresMethod::from_raw(raw.method)
+                .expect("by2_lsq_default_options returns a known method"),
+            max_iterations: raw.max_iterations,
+            function_tolerance: raw.function_tolerance,
+            parameter_tolerance: raw.parameter_tolerance,
+            gradient_tolerance: raw.gradient_tolerance,
+        }
+    }
+}
+
+/// Why a solve stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    FunctionTolerance,
+    ParameterTolerance,
+    GradientTolerance,
+    MaxIterations,
+    /// The tolerances are too small for any further progress; the parameters
+    /// are the best the solver found.
+    ToleranceTooSmall,
+}
+
+impl Termination {
+    fn from_raw(raw: i32) -> Result<Self, By2Error> {
+        Ok(match raw {
+            r if r == BY2_LSQ_FUNCTION_TOLERANCE as i32 => Termination::FunctionTolerance,
+            r if r == BY2_LSQ_PARAMETER_TOLERANCE as i32 => Termination::ParameterTolerance,
+            r if r == BY2_LSQ_GRADIENT_TOLERANCE as i32 => Termination::GradientTolerance,
+            r if r == BY2_LSQ_MAX_ITERATIONS as i32 => Termination::MaxIterations,
+            r if r == BY2_LSQ_TOLERANCE_TOO_SMALL as i32 => Termination::ToleranceTooSmall,
+            r => {
+                return Err(By2Error::Internal(format!(
+                    "unknown least squares termination {r}"
+                )));
+            }
+        })
+    }
+}
+
+/// Outcome of [`LeastSquares::solve`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LeastSquaresSummary {
+    /// Stopped on one of the tolerances rather than the iteration limit.
+    pub converged: bool,
+    pub termination: Termination,
+    /// `0.5 * |residuals|^2` at the initial guess.
+    pub initial_cost: f64,
+    /// `0.5 * |residuals|^2` at the returned parameters.
+    pub final_cost: f64,
+    pub iterations: usize,
+    /// Including the evaluations spent on finite differences.
+    pub residual_evaluations: usize,
+    pub jacobian_evaluations: usize,
+}
+
+/// A problem `min 0.5 * |residual(params)|^2`.
+///
+/// The residual closure writes `residual_count` values for the given
+/// parameters. The optional jacobian closure writes the row-major
+/// `residual_count x params.len()` matrix of partial derivatives; without it
+/// the jacobian is approximated by forward differences.
+pub struct LeastSquares<'a> {
+    residual_count: usize,
+    residual: Callback<'a>,
+    jacobian: Option<Callback<'a>>,
+}
+
+impl<'a> LeastSquares<'a> {
+    pub fn new(residual_count: usize, residual: impl FnMut(&[f64], &mut [f64]) + 'a) -> Self {
+        Self {
+            residual_count,
+            residual: Box::new(residual),
+            jacobian: None,
+        }
+    }
+
+    /// Supplies the analytic jacobian: `jacobian[i * params.len() + j]` is
+    /// `d residual_i / d param_j`.
+    pub fn with_jacobian(mut self, jacobian: impl FnMut(&[f64], &mut [f64]) + 'a) -> Self {
+        self.jacobian = Some(Box::new(jacobian));
+        self
+    }
+
+    /// Minimizes from the initial guess in `params`, which receives the
+    /// solution. `params` is left unchanged on error.
+    ///
+    /// Hitting `max_iterations` is not an error; check
+    /// [`LeastSquaresSummary::converged`].
+    pub fn solve(
+        &mut self,
+        params: &mut [f64],
+        options: &LeastSquaresOptions,
+    ) -> Result<LeastSquaresSummary, By2Error> {
+        let raw_options = by2_lsq_options {
+            method: options.method.to_raw(),
+            max_iterations: options.max_iterations,
+            function_tolerance: options.function_tolerance,
+            parameter_tolerance: options.parameter_tolerance,
+            gradient_tolerance: options.gradient_tolerance,
+        };
+        let mut summary = by2_lsq_summary {
+            converged: 0,
+            termination: 0,
+            initial_cost: 0.0,
+            final_cost: 0.0,
+            iterations: 0,
+            residual_evaluations: 0,
+            jacobian_evaluations: 0,
+        };
+        let mut callbacks = Callbacks {
+            residual: &mut self.residual,
+            jacobian: self.jacobian.as_mut(),
+            panic: None,
+        };
+        let jacobian = callbacks
+            .jacobian
+            .is_some()
+            .then_some(jacobian_trampoline as unsafe extern "C" fn(_, _, _, _, _) -> _);
+
+        let status = unsafe {
+            by2_lsq_solve(
+                Some(residual_trampoline),
+                jacobian,
+                (&mut callbacks as *mut Callbacks).cast::<c_void>(),
+                params.as_mut_ptr(),
+                params.len(),
+                self.residual_count,
+                &raw_options,
+                &mut summary,
+            )
+        };
+        if let Some(message) = callbacks.panic {
+            return Err(By2Error::CallbackFailed(message));
+        }
+        check_status(status)?;
+
+        Ok(LeastSquaresSummary {
+            converged: summary.converged != 0,
+            termination: Termination::from_raw(summary.termination)?,
+            initial_cost: summary.initial_cost,
+            final_cost: summary.final_cost,
+            iterations: summary.iterations,
+            residual_evaluations: summary.residual_evaluations,
+            jacobian_evaluations: summary.jacobian_evaluations,
+        })
+    }
+}
+
+/// `user_data` of one `by2_lsq_solve` call.
+struct Callbacks<'s, 'a> {
+    residual: &'s mut Callback<'a>,
+    jacobian: Option<&'s mut Callback<'a>>,
+    /// Message of the first panic; the native side aborts right after it.
+    panic: Option<String>,
+}
+
+unsafe extern "C" fn residual_trampoline(
+    user_data: *mut c_void,
+    params: *const f64,
+    param_count: usize,
+    residuals: *mut f64,
+    residual_count: usize,
+) -> i32 {
+    let callbacks = unsafe { &mut *user_data.cast::<Callbacks>() };
+    let params = unsafe { std::slice::from_raw_parts(params, param_count) };
+    let residuals = unsafe { std::slice::from_raw_parts_mut(residuals, residual_count) };
+    let result = catch_unwind(AssertUnwindSafe(|| (callbacks.residual)(params, residuals)));
+    callbacks.finish("residual", result)
+}
+
+unsafe extern "C" fn jacobian_trampoline(
+    user_data: *mut c_void,
+    params: *const f64,
+    param_count: usize,
+    jacobian: *mut f64,
+    residual_count: usize,
+) -> i32 {
+    let callbacks = unsafe { &mut *user_data.cast::<Callbacks>() };
+    let params = unsafe { std::slice::from_raw_parts(params, param_count) };
+    let jacobian =
+        unsafe { std::slice::from_raw_parts_mut(jacobian, residual_count * param_count) };
+    let Some(closure) = callbacks.jacobian.as_mut() else {
+        return 1;
+    };
+    let result = catch_unwind(AssertUnwindSafe(|| closure(params, jacobian)));
+    callbacks.finish("jacobian", result)
+}
+
+impl Callbacks<'_, '_> {
+    /// Status for the native side; remembers the panic message on failure.
+    fn finish(&mut self, name: &str, result: Result<(), Box<dyn Any + Send>>) -> i32 {
+        match result {
+            Ok(()) => 0,
+            Err(payload) => {
+                self.panic = Some(format!(
+                    "{name} closure panicked: {}",
+                    panic_message(&*payload)
+                ));
+                1
+            }
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "non-string panic payload"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Samples of `y = 2 * exp(-0.5 * t)`.
+    fn exponential_samples() -> Vec<(f64, f64)> {
+        (0..20)
+            .map(|i| {
+                let t = i as f64 * 0.25;
+                (t, 2.0 * (-0.5 * t).exp())
+            })
+            .collect()
+    }
+
+    fn exponential_fit(samples: &[(f64, f64)]) -> LeastSquares<'_> {
+        LeastSquares::new(samples.len(), move |p, r| {
+            for (r, &(t, y)) in r.iter_mut().zip(samples) {
+                *r = p[0] * (p[1] * t).exp() - y;
+            }
+        })
+    }
+
+    #[test]
+    fn levenberg_marquardt_fits_exponential_with_jacobian() {
+        let samples = exponential_samples();
+        let mut problem = exponential_fit(&samples).with_jacobian(|p, j| {
+            for (row, &(t, _)) in j.chunks_mut(2).zip(&samples) {
+                row[0] = (p[1] * t).exp();
+                row[1] = p[0] * t * (p[1] * t).exp();
+            }
+        });
+        let mut params = [1.0, 0.0];
+        let summary = problem
+            .solve(&mut params, &LeastSquaresOptions::default())
+            .unwrap();
+        assert!(summary.converged, "{summary:?}");
+        assert!(summary.final_cost < 1e-12 && summary.initial_cost > summary.final_cost);
+        assert!((params[0] - 2.0).abs() < 1e-6 && (params[1] + 0.5).abs() < 1e-6);
+        assert!(summary.jacobian_evaluations > 0);
+    }
+
+    #[test]
+    fn gauss_newton_fits_with_finite_differences() {
+        let samples = exponential_samples();
+        let options = LeastSquaresOptions {
+            method: LeastSquaresMethod::GaussNewton,
+            ..Default::default()
+        };
+        let mut params = [1.5, -0.3];
+        let summary = exponential_fit(&samples)
+            .solve(&mut params, &options)
+            .unwrap();
+        assert!(summary.converged, "{summary:?}");
+        assert!((params[0] - 2.0).abs() < 1e-6 && (params[1] + 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn solves_rosenbrock_from_the_classic_start() {
+        let mut problem = LeastSquares::new(2, |p, r| {
+            r[0] = 10.0 * (p[1] - p[0] * p[0]);
+            r[1] = 1.0 - p[0];
+        });
+        let mut params = [-1.2, 1.0];
+        let summary = problem
+            .solve(&mut params, &LeastSquaresOptions::default())
+            .unwrap();
+        assert!(summary.converged, "{summary:?}");
+        assert!((params[0] - 1.0).abs() < 1e-6 && (params[1] - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn iteration_limit_is_reported_not_an_error() {
+        let samples = exponential_samples();
+        let options = LeastSquaresOptions {
+            max_iterations: 1,
+            ..Default::default()
+        };
+        let mut params = [0.1, 1.0];
+        let summary = exponential_fit(&samples)
+            .solve(&mut params, &options)
+            .unwrap();
+        assert!(!summary.converged);
+        assert_eq!(summary.termination, Termination::MaxIterations);
+        assert_eq!(summary.iterations, 1);
+    }
+
+    #[test]
+    fn residual_panic_becomes_an_error() {
+        let mut calls = 0;
+        let mut problem = LeastSquares::new(2, |p, r| {
+            calls += 1;
+            if calls == 3 {
+                panic!("residual exploded");
+            }
+            r[0] = p[0] - 1.0;
+            r[1] = p[1] - 2.0;
+        });
+        let mut params = [0.0, 0.0];
+        let err = problem
+            .solve(&mut params, &LeastSquaresOptions::default())
+            .unwrap_err();
+        match err {
+            By2Error::CallbackFailed(message) => assert!(message.contains("residual exploded")),
+            other => panic!("unexpected error {other}"),
+        }
+        assert_eq!(params, [0.0, 0.0]);
+    }
+
+    #[test]
+    fn jacobian_panic_becomes_an_error() {
+        let mut problem = LeastSquares::new(1, |p, r| r[0] = p[0])
+            .with_jacobian(|_, _| panic!("no jacobian today"));
+        let err = problem
+            .solve(&mut [1.0], &LeastSquaresOptions::default())
+            .unwrap_err();
+        assert!(
+            matches!(&err, By2Error::CallbackFailed(m) if m.contains("no jacobian today")),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn rejects_bad_problems() {
+        let mut underdetermined = LeastSquares::new(1, |p, r| r[0] = p[0] + p[1]);
+        let err = underdetermined
+            .solve(&mut [0.0, 0.0], &LeastSquaresOptions::default())
+            .unwrap_err();
+        assert!(matches!(err, By2Error::InvalidArgument(_)), "{err}");
+
+        let mut not_finite = LeastSquares::new(1, |_, r| r[0] = f64::NAN);
+        let err = not_finite
+            .solve(&mut [0.0], &LeastSquaresOptions::default())
+            .unwrap_err();
+        assert!(matches!(err, By2Error::InvalidArgument(_)), "{err}");
+    }
+}
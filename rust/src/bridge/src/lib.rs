@@ -11,6 +11,49 @@ mod bindings {
 // Re-export bindings so callers can use the generated symbols directly.
 pub use bindings::*;
 
+mod error;
+mod geometry;
+mod interop;
+mod kdtree;
+mod least_squares;
+mod matrix;
+#[cfg(feature = "media")]
+mod media;
+#[cfg(feature = "mapping")]
+mod occupancy_map;
+mod point_cloud;
+mod registration;
+mod sparse;
+#[cfg(test)]
+mod testing;
+
+pub use error::By2Error;
+pub use geometry::{AxisAngle, EulerAngles, Inverse, Pose, Quaternion, RotationMatrix};
+pub use interop::{AsMatrixView, MatrixCow};
+pub use kdtree::KdTree;
+pub use least_squares::{
+    LeastSquares, LeastSquaresMethod, LeastSquaresOptions, LeastSquaresSummary, Termination,
+};
+pub use matrix::{Layout, Matrix, MatrixView, Scalar, SolveMethod, Svd, SymmetricEigen};
+#[cfg(feature = "media")]
+pub use media::{
+    AudioChunk, AudioReader, AudioReaderOptions, ExtractOptions, ExtractedFrame, ExtractedImage,
+    Frame, FrameLayout, FrameSelection, ImageFormat, ImageOutput, Interpolation, Keyframe,
+    MediaInfo, MediaStream, MediaType, PixelFormat, SampleFormat, Scaler, StreamInfo, Tag,
+    VideoCodec, VideoIndex, VideoReader, VideoWriter, VideoWriterOptions, encode_image,
+    extract_frames,
+};
+#[cfg(feature = "mapping")]
+pub use occupancy_map::{
+    Coloring, ExportFormat, ExportGeometry, ExportOptions, LeafFilter, Leaves, MeshStyle,
+    OccupancyMap, OctreeFormat, OctreeLeaf, RayHit,
+};
+pub use point_cloud::{Aabb, PointCloudView};
+pub use registration::{
+    IcpIteration, IcpMethod, IcpOptions, IcpResult, IcpTermination, MAX_ICP_HISTORY, icp,
+};
+pub use sparse::{SparseFormat, SparseMatrix, SparseSolver, TripletBuilder};
+
 /// Safe wrapper around the generated FFI function `by2_add`.
 pub fn by2_add_safe(a: i32, b: i32) -> i32 {
     // Bindings expose `by2_add` as an extern "C" function in this module.
@@ -18,6 +61,59 @@ pub fn by2_add_safe(a: i32, b: i32) -> i32 {
     unsafe { by2_add(a, b) }
 }
 
+/// Safe wrapper around the generated FFI function `by2_add_batch`.
+///
+/// Computes `out[i] = a[i] + b[i]` in the native core.
+///
+/// # Panics
+///
+/// Panics if `a`, `b` and `out` do not all have the same length.
+pub fn by2_add_batch_safe(a: &[i32], b: &[i32], out: &mut [i32]) {
+    assert_eq!(a.len(), b.len(), "input buffers differ in length");
+    assert_eq!(
+        a.len(),
+        out.len(),
+        "output buffer length differs from inputs"
+    );
+    // All three slices were checked to hold `out.len()` elements.
+    unsafe { by2_add_batch(a.as_ptr(), b.as_ptr(), out.as_mut_ptr(), out.len()) }
+}
+
+/// Log verbosity of the native core (mirrors `by2_log_level`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Critical,
+    Off,
+}
+
+impl LogLevel {
+    fn to_raw(self) -> i32 {
+        let raw = match self {
+            LogLevel::Trace => BY2_LOG_LEVEL_TRACE,
+            LogLevel::Debug => BY2_LOG_LEVEL_DEBUG,
+            LogLevel::Info => BY2_LOG_LEVEL_INFO,
+            LogLevel::Warn => BY2_LOG_LEVEL_WARN,
+            LogLevel::Error => BY2_LOG_LEVEL_ERROR,
+            LogLevel::Critical => BY2_LOG_LEVEL_CRITICAL,
+            LogLevel::Off => BY2_LOG_LEVEL_OFF,
+        };
+        raw as i32
+    }
+}
+
+/// Sets the global log level of the native core.
+///
+/// Every `LogLevel` is known to the native core, so this only fails if the
+/// two are out of sync.
+pub fn set_log_level(level: LogLevel) -> Result<(), By2Error> {
+    error::check_status(unsafe { by2_set_log_level(level.to_raw()) })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -27,4 +123,56 @@ mod tests {
         let r = by2_add_safe(2, 3);
         assert_eq!(r, 5);
     }
+
+    #[test]
+    fn test_by2_add_batch() {
+        let a = [1, 2, 3];
+        let b = [10, 20, 30];
+        let mut out = [0; 3];
+        by2_add_batch_safe(&a, &b, &mut out);
+        assert_eq!(out, [11, 22, 33]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_by2_add_batch_length_mismatch() {
+        let mut out = [0; 2];
+        by2_add_batch_safe(&[1, 2], &[1], &mut out);
+    }
+
+    #[test]
+    fn test_set_log_level() {
+        set_log_level(LogLevel::Off).unwrap();
+        set_log_level(LogLevel::Info).unwrap();
+
+        for raw in [-1, BY2_LOG_LEVEL_OFF as i32 + 1, i32::MAX] {
+            let status = unsafe { by2_set_log_level(raw) };
+            assert!(matches!(
+                error::check_status(status),
+                Err(By2Error::InvalidArgument(_))
+            ));
+        }
+    }
+
+    /// `ccore_add` overflows `int32_t` for these inputs. UBSan aborts the
+    /// process on it, so this only runs as the child of the test below.
+    #[cfg(by2_sanitize)]
+    #[test]
+    #[ignore = "aborts under UBSan; spawned by sanitizer_reports_signed_overflow"]
+    fn signed_overflow() {
+        by2_add_safe(i32::MAX, 1);
+    }
+
+    #[cfg(by2_sanitize)]
+    #[test]
+    fn sanitizer_reports_signed_overflow() {
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "tests::signed_overflow", "--ignored"])
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert!(!output.status.success());
+        assert!(stderr.contains("signed integer overflow"), "{stderr}");
+    }
 }
@@ -0,0 +1,596 @@
+//! Dense matrices backed by the Eigen routines of the native core.
+//!
+//! [`MatrixView`] borrows any strided `f32`/`f64` buffer (row-major,
+//! column-major or a sub-block) and checks up front that every element it
+//! describes lies inside the buffer. Results come back as owned,
+//! column-major [`Matrix`] values.
+
+use std::marker::PhantomData;
+use std::ops::Index;
+
+use crate::bindings::{
+    BY2_SCALAR_F32, BY2_SCALAR_F64, BY2_SOLVE_CHOLESKY, BY2_SOLVE_LU, BY2_SOLVE_QR, by2_matrix,
+    by2_matrix_eigen_symmetric, by2_matrix_inverse, by2_matrix_multiply, by2_matrix_solve,
+    by2_matrix_svd,
+};
+use crate::error::{By2Error, check_status};
+
+mod sealed {
+    pub trait Sealed {}
+    impl Sealed for f32 {}
+    impl Sealed for f64 {}
+}
+
+/// Element types the native linear algebra routines are instantiated for.
+pub trait Scalar: Copy + Default + PartialEq + std::fmt::Debug + sealed::Sealed + 'static {
+    #[doc(hidden)]
+    const SCALAR_TYPE: i32;
+}
+
+impl Scalar for f32 {
+    const SCALAR_TYPE: i32 = BY2_SCALAR_F32 as i32;
+}
+
+impl Scalar for f64 {
+    const SCALAR_TYPE: i32 = BY2_SCALAR_F64 as i32;
+}
+
+/// Memory order of a contiguous matrix buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    RowMajor,
+    ColMajor,
+}
+
+impl Layout {
+    /// `(row_stride, col_stride)` of a contiguous `rows x cols` buffer.
+    fn strides(self, rows: usize, cols: usize) -> (usize, usize) {
+        match self {
+            Layout::RowMajor => (cols.max(1), 1),
+            Layout::ColMajor => (1, rows.max(1)),
+        }
+    }
+}
+
+/// Decomposition used by [`MatrixView::solve`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolveMethod {
+    /// Full-pivoting LU. Square matrices only.
+    Lu,
+    /// Column-pivoting Householder QR. Accepts `rows >= cols` and returns the
+    /// least-squares solution.
+    Qr,
+    /// LLT. Symmetric positive definite matrices only.
+    Cholesky,
+}
+
+impl SolveMethod {
+    fn to_raw(self) -> i32 {
+        let raw = match self {
+            SolveMethod::Lu => BY2_SOLVE_LU,
+            SolveMethod::Qr => BY2_SOLVE_QR,
+            SolveMethod::Cholesky => BY2_SOLVE_CHOLESKY,
+        };
+        raw as i32
+    }
+}
+
+/// Borrowed view of a strided matrix buffer.
+///
+/// Element `(r, c)` lives at `data[r * row_stride + c * col_stride]`.
+///
+/// Only the elements themselves are borrowed, not the whole span between
+/// the first and the last: the gaps between them may belong to someone else
+/// (e.g. the other half of a split mutable ndarray or nalgebra view), so
+/// the view keeps a pointer rather than a slice covering the span.
+#[derive(Debug, Clone, Copy)]
+pub struct MatrixView<'a, T> {
+    data: *const T,
+    rows: usize,
+    cols: usize,
+    row_stride: usize,
+    col_stride: usize,
+    _marker: PhantomData<&'a T>,
+}
+
+// The view only reads `T: Scalar` (`f32`/`f64`) elements through a shared
+// borrow, like the `&'a [T]` it is created from.
+unsafe impl<T: Scalar> Send for MatrixView<'_, T> {}
+unsafe impl<T: Scalar> Sync for MatrixView<'_, T> {}
+
+impl<'a, T: Scalar> MatrixView<'a, T> {
+    /// Views a contiguous buffer of exactly `rows * cols` elements.
+    pub fn new(data: &'a [T], rows: usize, cols: usize, layout: Layout) -> Result<Self, By2Error> {
+        if rows.checked_mul(cols) != Some(data.len()) {
+            return Err(By2Error::InvalidArgument(format!(
+                "a {}x{} matrix needs {} elements, buffer has {}",
+                rows,
+                cols,
+                rows.saturating_mul(cols),
+                data.len()
+            )));
+        }
+        let (row_stride, col_stride) = layout.strides(rows, cols);
+        Self::with_strides(data, rows, cols, row_stride, col_stride)
+    }
+
+    /// Views a buffer with explicit strides (in elements).
+    ///
+    /// Fails unless both strides are positive and the last element,
+    /// `(rows - 1) * row_stride + (cols - 1) * col_stride`, is inside `data`.
+    pub fn with_strides(
+        data: &'a [T],
+        rows: usize,
+        cols: usize,
+        row_stride: usize,
+        col_stride: usize,
+    ) -> Result<Self, By2Error> {
+        if row_stride == 0 || col_stride == 0 {
+            return Err(By2Error::InvalidArgument(
+                "matrix strides must be positive".to_string(),
+            ));
+        }
+        if isize::try_from(row_stride).is_err() || isize::try_from(col_stride).is_err() {
+            return Err(By2Error::InvalidArgument(
+                "matrix strides overflow isize".to_string(),
+            ));
+        }
+
+        if rows > 0 && cols > 0 {
+            let last = (rows - 1)
+                .checked_mul(row_stride)
+                .and_then(|r| (cols - 1).checked_mul(col_stride).map(|c| (r, c)))
+                .and_then(|(r, c)| r.checked_add(c));
+            match last {
+                Some(last) if last < data.len() => {}
+                _ => {
+                    return Err(By2Error::InvalidArgument(format!(
+                        "a {}x{} matrix with strides ({}, {}) does not fit in a buffer of {} elements",
+                        rows,
+                        cols,
+                        row_stride,
+                        col_stride,
+                        data.len()
+                    )));
+                }
+            }
+        }
+
+        // Checked above: every element lies inside `data`.
+        Ok(unsafe { Self::from_raw_parts(data.as_ptr(), rows, cols, row_stride, col_stride) })
+    }
+
+    /// Views `rows x cols` elements starting at `data` without a bounds
+    /// check. The strides must be positive and fit in an `isize`.
+    ///
+    /// # Safety
+    ///
+    /// Every element `data + r * row_stride + c * col_stride` must be
+    /// readable and not mutated for `'a`. The elements in between them are
+    /// never touched.
+    pub(crate) unsafe fn from_raw_parts(
+        data: *const T,
+        rows: usize,
+        cols: usize,
+        row_stride: usize,
+        col_stride: usize,
+    ) -> Self {
+        debug_assert!(row_stride > 0 && col_stride > 0);
+        Self {
+            data,
+            rows,
+            cols,
+            row_stride,
+            col_stride,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn row_stride(&self) -> usize {
+        self.row_stride
+    }
+
+    pub fn col_stride(&self) -> usize {
+        self.col_stride
+    }
+
+    /// Element `(row, col)`, or `None` when out of bounds.
+    pub fn get(&self, row: usize, col: usize) -> Option<T> {
+        // In bounds, so one of the elements the view was created over.
+        (row < self.rows && col < self.cols)
+            .then(|| unsafe { *self.data.add(row * self.row_stride + col * self.col_stride) })
+    }
+
+    /// `self * rhs`.
+    pub fn multiply(&self, rhs: &MatrixView<'_, T>) -> Result<Matrix<T>, By2Error> {
+        let mut out = Matrix::zeros(self.rows, rhs.cols, Layout::ColMajor)?;
+        let status =
+            unsafe { by2_matrix_multiply(&self.as_raw(), &rhs.as_raw(), &out.as_raw_mut()) };
+        check_status(status)?;
+        Ok(out)
+    }
+
+    /// The inverse of this square matrix. Fails with
+    /// [`By2Error::Singular`] if it is not invertible.
+    pub fn inverse(&self) -> Result<Matrix<T>, By2Error> {
+        let mut out = Matrix::zeros(self.rows, self.cols, Layout::ColMajor)?;
+        let status = unsafe { by2_matrix_inverse(&self.as_raw(), &out.as_raw_mut()) };
+        check_status(status)?;
+        Ok(out)
+    }
+
+    /// Solves `self * x = b` for `x`.
+    pub fn solve(&self, b: &MatrixView<'_, T>, method: SolveMethod) -> Result<Matrix<T>, By2Error> {
+        let mut x = Matrix::zeros(self.cols, b.cols, Layout::ColMajor)?;
+        let status = unsafe {
+            by2_matrix_solve(
+                method.to_raw(),
+                &self.as_raw(),
+                &b.as_raw(),
+                &x.as_raw_mut(),
+            )
+        };
+        check_status(status)?;
+        Ok(x)
+    }
+
+    /// Thin singular value decomposition.
+    pub fn svd(&self) -> Result<Svd<T>, By2Error> {
+        let p = self.rows.min(self.cols);
+        let mut u = Matrix::zeros(self.rows, p, Layout::ColMajor)?;
+        let mut s = Matrix::zeros(p, 1, Layout::ColMajor)?;
+        let mut v = Matrix::zeros(self.cols, p, Layout::ColMajor)?;
+        let status = unsafe {
+            by2_matrix_svd(
+                &self.as_raw(),
+                &u.as_raw_mut(),
+                &s.as_raw_mut(),
+                &v.as_raw_mut(),
+            )
+        };
+        check_status(status)?;
+        Ok(Svd {
+            u,
+            singular_values: s.into_vec(),
+            v,
+        })
+    }
+
+    /// Eigendecomposition of this symmetric matrix. Only the lower triangle
+    /// is read.
+    pub fn symmetric_eigen(&self) -> Result<SymmetricEigen<T>, By2Error> {
+        let mut values = Matrix::zeros(self.rows, 1, Layout::ColMajor)?;
+        let mut vectors = Matrix::zeros(self.rows, self.rows, Layout::ColMajor)?;
+        let status = unsafe {
+            by2_matrix_eigen_symmetric(&self.as_raw(), &values.as_raw_mut(), &vectors.as_raw_mut())
+        };
+        check_status(status)?;
+        Ok(SymmetricEigen {
+            values: values.into_vec(),
+            vectors,
+        })
+    }
+
+    /// Descriptor for passing this view as a read-only native argument.
+    pub(crate) fn as_raw(&self) -> by2_matrix {
+        by2_matrix {
+            // The native side only reads through input descriptors.
+            data: self.data as *mut _,
+            scalar_type: T::SCALAR_TYPE,
+            rows: self.rows as _,
+            cols: self.cols as _,
+            row_stride: self.row_stride as _,
+            col_stride: self.col_stride as _,
+        }
+    }
+}
+
+/// Owned, contiguous matrix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix<T> {
+    data: Vec<T>,
+    rows: usize,
+    cols: usize,
+    layout: Layout,
+}
+
+impl<T: Scalar> Matrix<T> {
+    /// A `rows x cols` matrix of zeros. Fails with
+    /// [`By2Error::InvalidArgument`] if that many elements don't fit in
+    /// memory's address range, as zero-sized views can ask for.
+    pub fn zeros(rows: usize, cols: usize, layout: Layout) -> Result<Self, By2Error> {
+        let len = rows
+            .checked_mul(cols)
+            .filter(|len| {
+                len.checked_mul(size_of::<T>())
+                    .is_some_and(|bytes| bytes <= isize::MAX as usize)
+            })
+            .ok_or_else(|| {
+                By2Error::InvalidArgument(format!("a {rows}x{cols} matrix is too large"))
+            })?;
+        Ok(Self {
+            data: vec![T::default(); len],
+            rows,
+            cols,
+            layout,
+        })
+    }
+
+    /// Takes ownership of a buffer of exactly `rows * cols` elements.
+    pub fn from_vec(
+        rows: usize,
+        cols: usize,
+        layout: Layout,
+        data: Vec<T>,
+    ) -> Result<Self, By2Error> {
+        // Reuse the size validation of the view.
+        MatrixView::new(&data, rows, cols, layout)?;
+        Ok(Self {
+            data,
+            rows,
+            cols,
+            layout,
+        })
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.data
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    pub fn view(&self) -> MatrixView<'_, T> {
+        let (row_stride, col_stride) = self.layout.strides(self.rows, self.cols);
+        // The buffer holds exactly `rows * cols` elements in this layout.
+        unsafe {
+            MatrixView::from_raw_parts(
+                self.data.as_ptr(),
+                self.rows,
+                self.cols,
+                row_stride,
+                col_stride,
+            )
+        }
+    }
+
+    /// Descriptor for passing this matrix as a native output argument.
+    pub(crate) fn as_raw_mut(&mut self) -> by2_matrix {
+        let (row_stride, col_stride) = self.layout.strides(self.rows, self.cols);
+        by2_matrix {
+            data: self.data.as_mut_ptr() as *mut _,
+            scalar_type: T::SCALAR_TYPE,
+            rows: self.rows as _,
+            cols: self.cols as _,
+            row_stride: row_stride as _,
+            col_stride: col_stride as _,
+        }
+    }
+}
+
+impl<T: Scalar> Index<(usize, usize)> for Matrix<T> {
+    type Output = T;
+
+    fn index(&self, (row, col): (usize, usize)) -> &T {
+        assert!(
+            row < self.rows && col < self.cols,
+            "index ({}, {}) out of bounds for a {}x{} matrix",
+            row,
+            col,
+            self.rows,
+            self.cols
+        );
+        let (row_stride, col_stride) = self.layout.strides(self.rows, self.cols);
+        &self.data[row * row_stride + col * col_stride]
+    }
+}
+
+/// Result of [`MatrixView::svd`]: `a = u * diag(singular_values) * v^T`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Svd<T> {
+    /// `rows x p` with `p = min(rows, cols)`.
+    pub u: Matrix<T>,
+    /// `p` values in decreasing order.
+    pub singular_values: Vec<T>,
+    /// `cols x p`.
+    pub v: Matrix<T>,
+}
+
+/// Result of [`MatrixView::symmetric_eigen`]: `a = vectors * diag(values) * vectors^T`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymmetricEigen<T> {
+    /// Eigenvalues in increasing order.
+    pub values: Vec<T>,
+    /// Unit eigenvectors as columns, matching `values`.
+    pub vectors: Matrix<T>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row_major(rows: usize, cols: usize, data: &[f64]) -> Matrix<f64> {
+        Matrix::from_vec(rows, cols, Layout::RowMajor, data.to_vec()).unwrap()
+    }
+
+    fn assert_close(actual: &Matrix<f64>, expected: &Matrix<f64>) {
+        assert_eq!(
+            (actual.rows(), actual.cols()),
+            (expected.rows(), expected.cols())
+        );
+        for r in 0..expected.rows() {
+            for c in 0..expected.cols() {
+                assert!(
+                    (actual[(r, c)] - expected[(r, c)]).abs() < 1e-9,
+                    "({r}, {c}): {} != {}",
+                    actual[(r, c)],
+                    expected[(r, c)]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn view_rejects_buffers_that_are_too_small() {
+        let data = [0.0f64; 5];
+        assert!(MatrixView::new(&data, 2, 3, Layout::RowMajor).is_err());
+        assert!(MatrixView::with_strides(&data, 2, 3, 3, 1).is_err());
+        assert!(MatrixView::with_strides(&data, 2, 2, 0, 1).is_err());
+        assert!(MatrixView::with_strides(&data, 2, 2, 3, 1).is_ok());
+    }
+
+    #[test]
+    fn view_reads_sub_blocks() {
+        // Bottom-right 2x2 block of a row-major 3x3 matrix.
+        let data = [1.0f64, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0];
+        let block = MatrixView::with_strides(&data[4..], 2, 2, 3, 1).unwrap();
+        assert_eq!(block.get(0, 0), Some(5.0));
+        assert_eq!(block.get(1, 1), Some(9.0));
+        assert_eq!(block.get(2, 0), None);
+    }
+
+    #[test]
+    fn multiply_mixes_layouts() {
+        let a = row_major(2, 3, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let b = Matrix::from_vec(
+            3,
+            2,
+            Layout::ColMajor,
+            vec![7.0, 9.0, 11.0, 8.0, 10.0, 12.0],
+        )
+        .unwrap();
+        let product = a.view().multiply(&b.view()).unwrap();
+        assert_close(&product, &row_major(2, 2, &[58.0, 64.0, 139.0, 154.0]));
+    }
+
+    #[test]
+    fn outputs_too_large_to_allocate_are_rejected() {
+        // Empty views can claim any size along their other side.
+        let tall = MatrixView::<f64>::with_strides(&[], usize::MAX, 0, 1, 1).unwrap();
+        let wide = MatrixView::<f64>::with_strides(&[], 0, usize::MAX, 1, 1).unwrap();
+        for err in [
+            tall.multiply(&wide).unwrap_err(),
+            tall.symmetric_eigen().unwrap_err(),
+        ] {
+            assert!(matches!(err, By2Error::InvalidArgument(_)), "{err}");
+        }
+        assert!(Matrix::<f32>::zeros(1 << 62, 1, Layout::RowMajor).is_err());
+    }
+
+    #[test]
+    fn multiply_rejects_mismatched_dimensions() {
+        let a = row_major(2, 3, &[0.0; 6]);
+        let err = a.view().multiply(&a.view()).unwrap_err();
+        assert!(matches!(err, By2Error::InvalidArgument(_)), "{err}");
+    }
+
+    #[test]
+    fn inverse_of_invertible_matrix() {
+        let a = row_major(2, 2, &[4.0, 7.0, 2.0, 6.0]);
+        let inv = a.view().inverse().unwrap();
+        assert_close(&inv, &row_major(2, 2, &[0.6, -0.7, -0.2, 0.4]));
+    }
+
+    #[test]
+    fn inverse_of_singular_matrix_fails() {
+        let a = row_major(2, 2, &[1.0, 2.0, 2.0, 4.0]);
+        let err = a.view().inverse().unwrap_err();
+        assert!(matches!(err, By2Error::Singular(_)), "{err}");
+    }
+
+    #[test]
+    fn solve_with_every_method() {
+        // Symmetric positive definite so Cholesky applies too.
+        let a = row_major(2, 2, &[4.0, 1.0, 1.0, 3.0]);
+        let b = row_major(2, 1, &[1.0, 2.0]);
+        let expected = row_major(2, 1, &[1.0 / 11.0, 7.0 / 11.0]);
+        for method in [SolveMethod::Lu, SolveMethod::Qr, SolveMethod::Cholesky] {
+            let x = a.view().solve(&b.view(), method).unwrap();
+            assert_close(&x, &expected);
+        }
+    }
+
+    #[test]
+    fn qr_solves_least_squares() {
+        // Fit y = c0 + c1 * t through (0, 1), (1, 3), (2, 5).
+        let a = row_major(3, 2, &[1.0, 0.0, 1.0, 1.0, 1.0, 2.0]);
+        let b = row_major(3, 1, &[1.0, 3.0, 5.0]);
+        let x = a.view().solve(&b.view(), SolveMethod::Qr).unwrap();
+        assert_close(&x, &row_major(2, 1, &[1.0, 2.0]));
+    }
+
+    #[test]
+    fn cholesky_rejects_indefinite_matrix() {
+        let a = row_major(2, 2, &[1.0, 2.0, 2.0, 1.0]);
+        let b = row_major(2, 1, &[1.0, 1.0]);
+        let err = a
+            .view()
+            .solve(&b.view(), SolveMethod::Cholesky)
+            .unwrap_err();
+        assert!(matches!(err, By2Error::Singular(_)), "{err}");
+    }
+
+    #[test]
+    fn svd_reconstructs_input() {
+        let a = row_major(3, 2, &[3.0, 0.0, 0.0, -2.0, 0.0, 0.0]);
+        let svd = a.view().svd().unwrap();
+        assert_eq!(svd.u.rows(), 3);
+        assert_eq!(svd.v.rows(), 2);
+        assert!((svd.singular_values[0] - 3.0).abs() < 1e-12);
+        assert!((svd.singular_values[1] - 2.0).abs() < 1e-12);
+
+        let mut us = svd.u.clone();
+        for c in 0..2 {
+            for r in 0..3 {
+                let i = r + c * 3;
+                us.data[i] *= svd.singular_values[c];
+            }
+        }
+        let vt = Matrix::from_vec(2, 2, Layout::RowMajor, svd.v.as_slice().to_vec()).unwrap();
+        assert_close(&us.view().multiply(&vt.view()).unwrap(), &a);
+    }
+
+    #[test]
+    fn symmetric_eigen_of_diagonalizable_matrix() {
+        let a = row_major(2, 2, &[2.0, 1.0, 1.0, 2.0]);
+        let eigen = a.view().symmetric_eigen().unwrap();
+        assert!((eigen.values[0] - 1.0).abs() < 1e-12);
+        assert!((eigen.values[1] - 3.0).abs() < 1e-12);
+        // A v = lambda v for each column.
+        let av = a.view().multiply(&eigen.vectors.view()).unwrap();
+        for c in 0..2 {
+            for r in 0..2 {
+                assert!((av[(r, c)] - eigen.values[c] * eigen.vectors[(r, c)]).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn single_precision_is_supported() {
+        let a = Matrix::from_vec(2, 2, Layout::RowMajor, vec![2.0f32, 0.0, 0.0, 4.0]).unwrap();
+        let inv = a.view().inverse().unwrap();
+        assert_eq!(inv[(0, 0)], 0.5);
+        assert_eq!(inv[(1, 1)], 0.25);
+    }
+}
@@ -0,0 +1,316 @@
+//! Video decoding and encoding with FFmpeg.
+//!
+//! [`VideoReader`] owns a `by2_video_reader` handle and decodes the frames of
+//! a file's video stream in order, converted to a packed [`PixelFormat`].
+//! It also seeks to frames by time or number; a [`VideoIndex`] of the
+//! keyframes, built once and cached to disk, makes frame numbers exact for
+//! any stream. [`VideoWriter`] goes the other way, encoding packed frames
+//! into an MP4, Matroska or AVI file, and [`MediaInfo::probe`] describes a
+//! file's container, streams and metadata without decoding anything. A
+//! [`Scaler`] resizes frames and converts them between formats, and an
+//! [`AudioReader`] decodes audio and resamples it to float PCM.
+//! [`extract_frames`] picks frames at intervals or scene changes, decoding
+//! long files in parallel, and keeps them or writes them as images. Needs the
+//! `media` feature, which builds the C++ side with FFmpeg; the `serde`
+//! feature makes the descriptions serializable.
+
+use std::ffi::{CStr, CString, c_char};
+use std::path::Path;
+
+use crate::bindings::{
+    BY2_MEDIA_AUDIO, BY2_MEDIA_DATA, BY2_MEDIA_SUBTITLE, BY2_MEDIA_VIDEO, BY2_PIXEL_BGR24,
+    BY2_PIXEL_BGRA, BY2_PIXEL_GRAY8, BY2_PIXEL_NV12, BY2_PIXEL_RGB24, BY2_PIXEL_RGBA,
+    BY2_PIXEL_YUV420P, BY2_PIXEL_YUV422P, BY2_PIXEL_YUV444P, by2_stream_info,
+};
+use crate::error::By2Error;
+
+mod audio_reader;
+mod frame_extractor;
+mod probe;
+mod scaler;
+mod video_index;
+mod video_reader;
+mod video_writer;
+
+pub use audio_reader::{AudioChunk, AudioReader, AudioReaderOptions, SampleFormat};
+pub use frame_extractor::{
+    ExtractOptions, ExtractedFrame, ExtractedImage, FrameSelection, ImageFormat, ImageOutput,
+    encode_image, extract_frames,
+};
+pub use probe::{MediaInfo, MediaStream, Tag};
+pub use scaler::{FrameLayout, Interpolation, Scaler};
+pub use video_index::{Keyframe, VideoIndex};
+pub use video_reader::{Frame, VideoReader};
+pub use video_writer::{VideoCodec, VideoWriter, VideoWriterOptions};
+
+/// Layouts of frames. Frames are tightly packed: rows follow each other
+/// without padding, and planes follow each other in the listed order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PixelFormat {
+    /// R, G, B bytes per pixel.
+    Rgb24,
+    /// R, G, B, A bytes per pixel.
+    Rgba,
+    /// Y plane, then U and V planes of `ceil(width / 2) x ceil(height / 2)`
+    /// samples.
+    Yuv420p,
+    /// One luma byte per pixel.
+    Gray8,
+    /// B, G, R bytes per pixel.
+    Bgr24,
+    /// B, G, R, A bytes per pixel.
+    Bgra,
+    /// Y plane, then one plane of interleaved U, V pairs of
+    /// `ceil(width / 2) x ceil(height / 2)` samples.
+    Nv12,
+    /// Y plane, then U and V planes of `ceil(width / 2) x height` samples.
+    Yuv422p,
+    /// Y, U and V planes of `width x height` samples.
+    Yuv444p,
+}
+
+impl PixelFormat {
+    fn to_raw(self) -> i32 {
+        let raw = match self {
+            PixelFormat::Rgb24 => BY2_PIXEL_RGB24,
+            PixelFormat::Rgba => BY2_PIXEL_RGBA,
+            PixelFormat::Yuv420p => BY2_PIXEL_YUV420P,
+            PixelFormat::Gray8 => BY2_PIXEL_GRAY8,
+            PixelFormat::Bgr24 => BY2_PIXEL_BGR24,
+            PixelFormat::Bgra => BY2_PIXEL_BGRA,
+            PixelFormat::Nv12 => BY2_PIXEL_NV12,
+            PixelFormat::Yuv422p => BY2_PIXEL_YUV422P,
+            PixelFormat::Yuv444p => BY2_PIXEL_YUV444P,
+        };
+        raw as i32
+    }
+
+    /// Byte lengths of the planes of a `width` x `height` frame.
+    pub fn plane_sizes(self, width: u32, height: u32) -> Vec<usize> {
+        let (w, h) = (width as usize, height as usize);
+        match self {
+            PixelFormat::Gray8 => vec![w * h],
+            PixelFormat::Rgb24 | PixelFormat::Bgr24 => vec![w * h * 3],
+            PixelFormat::Rgba | PixelFormat::Bgra => vec![w * h * 4],
+            PixelFormat::Yuv420p => {
+                let chroma = w.div_ceil(2) * h.div_ceil(2);
+                vec![w * h, chroma, chroma]
+            }
+            PixelFormat::Nv12 => vec![w * h, 2 * w.div_ceil(2) * h.div_ceil(2)],
+            PixelFormat::Yuv422p => {
+                let chroma = w.div_ceil(2) * h;
+                vec![w * h, chroma, chroma]
+            }
+            PixelFormat::Yuv444p => vec![w * h; 3],
+        }
+    }
+
+    /// Bytes of a `width` x `height` frame.
+    pub fn frame_size(self, width: u32, height: u32) -> usize {
+        self.plane_sizes(width, height).iter().sum()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MediaType {
+    Video,
+    Audio,
+    Subtitle,
+    Data,
+    Unknown,
+}
+
+impl MediaType {
+    fn from_raw(raw: i32) -> Self {
+        match raw {
+            r if r == BY2_MEDIA_VIDEO as i32 => MediaType::Video,
+            r if r == BY2_MEDIA_AUDIO as i32 => MediaType::Audio,
+            r if r == BY2_MEDIA_SUBTITLE as i32 => MediaType::Subtitle,
+            r if r == BY2_MEDIA_DATA as i32 => MediaType::Data,
+            _ => MediaType::Unknown,
+        }
+    }
+}
+
+/// A stream of a media file, as its container describes it.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StreamInfo {
+    /// Position among the file's streams.
+    pub index: usize,
+    pub media_type: MediaType,
+    /// FFmpeg's name for the codec, e.g. `"h264"`.
+    pub codec: String,
+    /// Video only, 0 otherwise.
+    pub width: u32,
+    pub height: u32,
+    /// Average frames per second.
+    pub frame_rate: Option<f64>,
+    /// FFmpeg's name for the layout of decoded frames, e.g. `"yuv420p"`.
+    /// Video only.
+    pub pixel_format: Option<String>,
+    /// Audio only, 0 otherwise.
+    pub sample_rate: u32,
+    pub channels: u32,
+    /// FFmpeg's description of the channels, e.g. `"stereo"` or `"5.1"`.
+    /// Audio only.
+    pub channel_layout: Option<String>,
+    /// Bits per second.
+    pub bit_rate: Option<u64>,
+    /// Unit of the stream's timestamps in seconds, as (numerator,
+    /// denominator).
+    pub time_base: (i32, i32),
+    /// In seconds.
+    pub duration: Option<f64>,
+    /// As stated by the container.
+    pub frame_count: Option<u64>,
+}
+
+impl StreamInfo {
+    fn from_raw(raw: &by2_stream_info) -> Result<Self, By2Error> {
+        Ok(Self {
+            index: raw.index,
+            media_type: MediaType::from_raw(raw.type_),
+            codec: name(&raw.codec)?,
+            width: raw.width.max(0) as u32,
+            height: raw.height.max(0) as u32,
+            frame_rate: (raw.frame_rate > 0.0).then_some(raw.frame_rate),
+            pixel_format: Some(name(&raw.pixel_format)?).filter(|n| !n.is_empty()),
+            sample_rate: raw.sample_rate.max(0) as u32,
+            channels: raw.channels.max(0) as u32,
+            channel_layout: Some(name(&raw.channel_layout)?).filter(|n| !n.is_empty()),
+            bit_rate: (raw.bit_rate > 0).then_some(raw.bit_rate as u64),
+            time_base: (raw.time_base.num, raw.time_base.den),
+            duration: (!raw.duration.is_nan()).then_some(raw.duration),
+            frame_count: (raw.frame_count > 0).then_some(raw.frame_count as u64),
+        })
+    }
+}
+
+/// A NUL-terminated name from a fixed-size field of a native struct. The
+/// native side always terminates them; a field that isn't is an internal
+/// error rather than a read past its end.
+fn name(chars: &[c_char]) -> Result<String, By2Error> {
+    // `c_char` is `i8` or `u8`, either way the size and alignment of `u8`.
+    let bytes = unsafe { std::slice::from_raw_parts(chars.as_ptr().cast::<u8>(), chars.len()) };
+    CStr::from_bytes_until_nul(bytes)
+        .map(|name| name.to_string_lossy().into_owned())
+        .map_err(|_| By2Error::Internal("native name is not NUL-terminated".into()))
+}
+
+/// `path` as the UTF-8, NUL-terminated string the native side takes.
+fn c_path(path: &Path) -> Result<CString, By2Error> {
+    let utf8 = path.to_str().ok_or_else(|| {
+        By2Error::InvalidArgument(format!("{} is not valid UTF-8", path.display()))
+    })?;
+    CString::new(utf8)
+        .map_err(|_| By2Error::InvalidArgument(format!("{utf8:?} contains a NUL byte")))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    /// The Y plane of frame `i` of [`write_y4m`] clips: a gradient that
+    /// moves with the frame.
+    pub(super) fn luma(i: usize, width: u32, height: u32) -> Vec<u8> {
+        let mut plane = Vec::new();
+        for y in 0..height as usize {
+            for x in 0..width as usize {
+                plane.push((16 + x * 3 + y * 5 + i * 7) as u8);
+            }
+        }
+        plane
+    }
+
+    /// Writes a 25 fps YUV4MPEG2 clip, which FFmpeg reads without any
+    /// codec: frame `i` has the Y plane [`luma`] and grey chroma.
+    pub(super) fn write_y4m(path: &Path, width: u32, height: u32, frames: usize) {
+        write_y4m_frames(
+            path,
+            width,
+            height,
+            (0..frames).map(|i| luma(i, width, height)),
+        );
+    }
+
+    /// Writes a 25 fps YUV4MPEG2 clip of the given Y planes and grey chroma.
+    pub(super) fn write_y4m_frames(
+        path: &Path,
+        width: u32,
+        height: u32,
+        planes: impl IntoIterator<Item = Vec<u8>>,
+    ) {
+        let chroma = vec![128; PixelFormat::Yuv420p.plane_sizes(width, height)[1]];
+        let mut bytes =
+            format!("YUV4MPEG2 W{width} H{height} F25:1 Ip A1:1 C420jpeg\n").into_bytes();
+        for plane in planes {
+            bytes.extend_from_slice(b"FRAME\n");
+            bytes.extend(plane);
+            bytes.extend(&chroma);
+            bytes.extend(&chroma);
+        }
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    /// Writes a 16-bit PCM WAV file of interleaved `samples`.
+    pub(super) fn write_wav(path: &Path, sample_rate: u32, channels: u16, samples: &[i16]) {
+        let data = (samples.len() * 2) as u32;
+        let mut bytes = Vec::new();
+        bytes.extend(b"RIFF");
+        bytes.extend((36 + data).to_le_bytes());
+        bytes.extend(b"WAVEfmt ");
+        bytes.extend(16u32.to_le_bytes());
+        bytes.extend(1u16.to_le_bytes());
+        bytes.extend(channels.to_le_bytes());
+        bytes.extend(sample_rate.to_le_bytes());
+        bytes.extend((sample_rate * u32::from(channels) * 2).to_le_bytes());
+        bytes.extend((channels * 2).to_le_bytes());
+        bytes.extend(16u16.to_le_bytes());
+        bytes.extend(b"data");
+        bytes.extend(data.to_le_bytes());
+        for sample in samples {
+            bytes.extend(sample.to_le_bytes());
+        }
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn packed_frame_sizes() {
+        assert_eq!(PixelFormat::Rgb24.frame_size(5, 3), 45);
+        assert_eq!(PixelFormat::Rgba.frame_size(5, 3), 60);
+        assert_eq!(PixelFormat::Yuv420p.plane_sizes(5, 3), [15, 6, 6]);
+        assert_eq!(PixelFormat::Yuv420p.frame_size(6, 4), 36);
+        assert_eq!(PixelFormat::Rgb24.frame_size(0, 7), 0);
+        assert_eq!(PixelFormat::Gray8.plane_sizes(5, 3), [15]);
+        assert_eq!(PixelFormat::Bgra.frame_size(5, 3), 60);
+        assert_eq!(PixelFormat::Nv12.plane_sizes(5, 3), [15, 12]);
+        assert_eq!(PixelFormat::Yuv422p.plane_sizes(5, 3), [15, 9, 9]);
+        assert_eq!(PixelFormat::Yuv444p.frame_size(5, 3), 45);
+    }
+
+    #[test]
+    fn paths_must_be_plain_utf8() {
+        assert_eq!(
+            c_path(Path::new("clip.mkv")).unwrap().as_bytes(),
+            b"clip.mkv"
+        );
+        assert!(matches!(
+            c_path(Path::new("a\0b")),
+            Err(By2Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn names_stop_at_the_first_nul() {
+        let field = [b'h' as c_char, b'2' as c_char, 0, b'x' as c_char];
+        assert_eq!(name(&field).unwrap(), "h2");
+        assert!(matches!(
+            name(&[b'h' as c_char; 4]),
+            Err(By2Error::Internal(_))
+        ));
+    }
+}
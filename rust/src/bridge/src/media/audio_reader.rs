@@ -0,0 +1,484 @@
+use std::iter::FusedIterator;
+use std::path::Path;
+use std::ptr::{self, NonNull};
+
+use crate::bindings::{
+    BY2_SAMPLE_F32, BY2_SAMPLE_F32_PLANAR, by2_audio_chunk, by2_audio_format, by2_audio_reader,
+    by2_audio_reader_audio_stream, by2_audio_reader_default_options, by2_audio_reader_destroy,
+    by2_audio_reader_format, by2_audio_reader_next, by2_audio_reader_open,
+    by2_audio_reader_options, by2_audio_reader_stream, by2_audio_reader_stream_count,
+};
+use crate::error::{By2Error, check_status};
+
+use super::video_reader::empty_stream_info;
+use super::{StreamInfo, c_path, name};
+
+/// Layouts of decoded audio. Samples are `f32` in `[-1, 1]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SampleFormat {
+    /// The channels of each sample follow each other.
+    F32,
+    /// One plane per channel, each holding all of the chunk's samples.
+    F32Planar,
+}
+
+impl SampleFormat {
+    fn to_raw(self) -> i32 {
+        let raw = match self {
+            SampleFormat::F32 => BY2_SAMPLE_F32,
+            SampleFormat::F32Planar => BY2_SAMPLE_F32_PLANAR,
+        };
+        raw as i32
+    }
+
+    fn from_raw(raw: i32) -> Result<Self, By2Error> {
+        Ok(match raw {
+            r if r == BY2_SAMPLE_F32 as i32 => SampleFormat::F32,
+            r if r == BY2_SAMPLE_F32_PLANAR as i32 => SampleFormat::F32Planar,
+            r => return Err(By2Error::Internal(format!("unknown sample format {r}"))),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioReaderOptions {
+    /// Samples per second per channel; `None` keeps the stream's rate.
+    pub sample_rate: Option<u32>,
+    /// FFmpeg's name for the channel layout, e.g. `"mono"`, `"stereo"` or
+    /// `"5.1"`; `None` keeps the stream's.
+    pub channel_layout: Option<String>,
+    pub sample_format: SampleFormat,
+}
+
+impl Default for AudioReaderOptions {
+    fn default() -> Self {
+        let mut raw = empty_options();
+        unsafe { by2_audio_reader_default_options(&mut raw) };
+        Self {
+            sample_rate: (raw.sample_rate > 0).then_some(raw.sample_rate as u32),
+            channel_layout: Some(
+                name(&raw.channel_layout).expect("the default layout is NUL-terminated"),
+            )
+            .filter(|n| !n.is_empty()),
+            sample_format: SampleFormat::from_raw(raw.sample_format)
+                .expect("by2_audio_reader_default_options returns a known sample format"),
+        }
+    }
+}
+
+impl AudioReaderOptions {
+    fn to_raw(&self) -> Result<by2_audio_reader_options, By2Error> {
+        let mut raw = empty_options();
+        raw.sample_rate = match self.sample_rate {
+            Some(rate) => i32::try_from(rate)
+                .ok()
+                .filter(|&r| r > 0)
+                .ok_or_else(|| By2Error::InvalidArgument(format!("sample rate {rate}")))?,
+            None => 0,
+        };
+        let layout = self.channel_layout.as_deref().unwrap_or_default();
+        // Leaves room for the NUL.
+        if layout.len() >= raw.channel_layout.len() || layout.contains('\0') {
+            return Err(By2Error::InvalidArgument(format!(
+                "channel layout {layout:?}"
+            )));
+        }
+        for (to, from) in raw.channel_layout.iter_mut().zip(layout.bytes()) {
+            *to = from as _;
+        }
+        raw.sample_format = self.sample_format.to_raw();
+        Ok(raw)
+    }
+}
+
+fn empty_options() -> by2_audio_reader_options {
+    by2_audio_reader_options {
+        sample_rate: 0,
+        channel_layout: [0; 64],
+        sample_format: 0,
+    }
+}
+
+/// Resampled audio decoded from about one frame of the stream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioChunk {
+    data: Vec<f32>,
+    channels: u32,
+    sample_rate: u32,
+    format: SampleFormat,
+    position: u64,
+    time: f64,
+}
+
+impl AudioChunk {
+    /// `channels() * samples()` values in the chunk's format.
+    pub fn data(&self) -> &[f32] {
+        &self.data
+    }
+
+    pub fn into_data(self) -> Vec<f32> {
+        self.data
+    }
+
+    /// The planes of the chunk: one per channel for
+    /// [`SampleFormat::F32Planar`], a single interleaved one otherwise.
+    pub fn planes(&self) -> Vec<&[f32]> {
+        match self.format {
+            SampleFormat::F32 => vec![self.data.as_slice()],
+            SampleFormat::F32Planar => self.data.chunks(self.samples().max(1)).collect(),
+        }
+    }
+
+    /// Samples per channel.
+    pub fn samples(&self) -> usize {
+        self.data.len() / self.channels as usize
+    }
+
+    pub fn channels(&self) -> u32 {
+        self.channels
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn format(&self) -> SampleFormat {
+        self.format
+    }
+
+    /// Samples per channel before this chunk, counted from the first decoded
+    /// one.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Time of the first sample in seconds: the stream's first timestamp
+    /// plus `position() / sample_rate()`.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// In seconds.
+    pub fn duration(&self) -> f64 {
+        self.samples() as f64 / f64::from(self.sample_rate)
+    }
+}
+
+/// Decodes the best audio stream of a file and resamples it with
+/// libswresample to the rate, channel layout and [`SampleFormat`] asked for.
+///
+/// Iterating yields chunks of about one decoded frame each until the end of
+/// the stream or the first error, with timestamps that keep counting across
+/// them.
+#[derive(Debug)]
+pub struct AudioReader {
+    raw: NonNull<by2_audio_reader>,
+    streams: Vec<StreamInfo>,
+    audio_stream: usize,
+    sample_rate: u32,
+    channels: u32,
+    channel_layout: String,
+    format: SampleFormat,
+    done: bool,
+}
+
+// The decoder and resampler state is owned by the handle and only touched
+// through `&mut self`; nothing in it is tied to the thread that opened it.
+unsafe impl Send for AudioReader {}
+
+impl AudioReader {
+    /// Opens `path` and its best audio stream.
+    ///
+    /// Fails with [`By2Error::InvalidArgument`] for bad options, e.g. a
+    /// channel layout FFmpeg doesn't know, and with [`By2Error::Io`] if the
+    /// file can't be opened or has no audio stream FFmpeg can decode.
+    pub fn open(path: impl AsRef<Path>, options: &AudioReaderOptions) -> Result<Self, By2Error> {
+        let path = c_path(path.as_ref())?;
+        let options_raw = options.to_raw()?;
+        let mut raw = ptr::null_mut();
+        check_status(unsafe { by2_audio_reader_open(path.as_ptr(), &options_raw, &mut raw) })?;
+        let raw = NonNull::new(raw)
+            .ok_or_else(|| By2Error::Internal("by2_audio_reader_open returned null".into()))?;
+        // Owned from here on, so an early return closes the file.
+        let mut reader = Self {
+            raw,
+            streams: Vec::new(),
+            audio_stream: 0,
+            sample_rate: 0,
+            channels: 0,
+            channel_layout: String::new(),
+            format: options.sample_format,
+            done: false,
+        };
+
+        let count = unsafe { by2_audio_reader_stream_count(raw.as_ptr()) };
+        for index in 0..count {
+            let mut info = empty_stream_info();
+            check_status(unsafe { by2_audio_reader_stream(raw.as_ptr(), index, &mut info) })?;
+            reader.streams.push(StreamInfo::from_raw(&info)?);
+        }
+        reader.audio_stream = unsafe { by2_audio_reader_audio_stream(raw.as_ptr()) };
+
+        let mut format = by2_audio_format {
+            sample_rate: 0,
+            channels: 0,
+            channel_layout: [0; 64],
+            sample_format: 0,
+        };
+        check_status(unsafe { by2_audio_reader_format(raw.as_ptr(), &mut format) })?;
+        reader.sample_rate = format.sample_rate.max(0) as u32;
+        reader.channels = format.channels.max(0) as u32;
+        reader.channel_layout = name(&format.channel_layout)?;
+        Ok(reader)
+    }
+
+    /// All streams of the file, audio or not.
+    pub fn streams(&self) -> &[StreamInfo] {
+        &self.streams
+    }
+
+    /// The stream being decoded.
+    pub fn audio_stream(&self) -> &StreamInfo {
+        &self.streams[self.audio_stream]
+    }
+
+    /// Samples per second per channel of the chunks.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn channels(&self) -> u32 {
+        self.channels
+    }
+
+    /// FFmpeg's description of the channels of the chunks, e.g. `"stereo"`.
+    pub fn channel_layout(&self) -> &str {
+        &self.channel_layout
+    }
+
+    pub fn format(&self) -> SampleFormat {
+        self.format
+    }
+
+    /// Decodes and resamples the next chunk, or returns `None` once the
+    /// stream and the resampler are drained.
+    pub fn read_chunk(&mut self) -> Result<Option<AudioChunk>, By2Error> {
+        let mut chunk = by2_audio_chunk {
+            data: ptr::null(),
+            samples: 0,
+            position: 0,
+            time: 0.0,
+        };
+        let mut decoded = 0;
+        check_status(unsafe {
+            by2_audio_reader_next(self.raw.as_ptr(), &mut chunk, &mut decoded)
+        })?;
+        if decoded == 0 {
+            return Ok(None);
+        }
+        let len = chunk.samples.max(0) as usize * self.channels as usize;
+        // Owned by the reader until the next call; copied right away.
+        let data = if len == 0 {
+            Vec::new()
+        } else {
+            unsafe { std::slice::from_raw_parts(chunk.data, len) }.to_vec()
+        };
+        Ok(Some(AudioChunk {
+            data,
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            format: self.format,
+            position: chunk.position.max(0) as u64,
+            time: chunk.time,
+        }))
+    }
+}
+
+impl Iterator for AudioReader {
+    type Item = Result<AudioChunk, By2Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let chunk = self.read_chunk().transpose();
+        self.done = !matches!(chunk, Some(Ok(_)));
+        chunk
+    }
+}
+
+impl FusedIterator for AudioReader {}
+
+impl Drop for AudioReader {
+    fn drop(&mut self) {
+        unsafe { by2_audio_reader_destroy(self.raw.as_ptr()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::TAU;
+
+    use super::*;
+    use crate::media::MediaType;
+    use crate::media::tests::{write_wav, write_y4m};
+    use crate::testing::TempDir;
+
+    /// `seconds` of a `frequency` Hz sine at half scale, on every channel.
+    fn sine(frequency: f64, sample_rate: u32, channels: u16, seconds: f64) -> Vec<i16> {
+        let count = (seconds * f64::from(sample_rate)).round() as usize;
+        (0..count)
+            .flat_map(|i| {
+                let t = i as f64 / f64::from(sample_rate);
+                let value = (16384.0 * (TAU * frequency * t).sin()).round() as i16;
+                std::iter::repeat_n(value, channels.into())
+            })
+            .collect()
+    }
+
+    /// Frequency of a sine from its upward zero crossings.
+    fn frequency(samples: &[f32], sample_rate: u32) -> f64 {
+        let crossings: Vec<usize> = samples
+            .windows(2)
+            .enumerate()
+            .filter(|(_, w)| w[0] < 0.0 && w[1] >= 0.0)
+            .map(|(i, _)| i)
+            .collect();
+        let periods = (crossings.len() - 1) as f64;
+        let span = (crossings[crossings.len() - 1] - crossings[0]) as f64;
+        periods * f64::from(sample_rate) / span
+    }
+
+    /// Every chunk of `reader`, checking that they follow each other.
+    fn read_all(reader: &mut AudioReader) -> Vec<AudioChunk> {
+        let chunks: Vec<AudioChunk> = reader.map(Result::unwrap).collect();
+        let mut position = 0;
+        for chunk in &chunks {
+            assert_eq!(chunk.position(), position);
+            let expected = position as f64 / f64::from(chunk.sample_rate());
+            assert!((chunk.time() - expected).abs() < 1e-9, "{}", chunk.time());
+            position += chunk.samples() as u64;
+        }
+        chunks
+    }
+
+    #[test]
+    fn decodes_a_sine_at_its_own_rate() {
+        let dir = TempDir::new("audio-sine");
+        let path = dir.join("sine.wav");
+        write_wav(&path, 8000, 1, &sine(440.0, 8000, 1, 1.0));
+
+        let mut reader = AudioReader::open(&path, &AudioReaderOptions::default()).unwrap();
+        assert_eq!(reader.audio_stream().media_type, MediaType::Audio);
+        assert_eq!((reader.sample_rate(), reader.channels()), (8000, 1));
+        assert_eq!(reader.channel_layout(), "mono");
+        assert_eq!(reader.format(), SampleFormat::F32);
+
+        let chunks = read_all(&mut reader);
+        assert!(chunks.len() > 1);
+        let samples: Vec<f32> = chunks
+            .iter()
+            .flat_map(|c| c.data().iter().copied())
+            .collect();
+        let duration: f64 = chunks.iter().map(AudioChunk::duration).sum();
+        assert!((duration - 1.0).abs() < 1e-3, "{duration}");
+        assert!((frequency(&samples, 8000) - 440.0).abs() < 1.0);
+        let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!((peak - 0.5).abs() < 0.01, "{peak}");
+        // Drained for good.
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn resamples_to_the_requested_rate_and_layout() {
+        let dir = TempDir::new("audio-resample");
+        let path = dir.join("sine.wav");
+        write_wav(&path, 8000, 1, &sine(440.0, 8000, 1, 1.0));
+
+        let options = AudioReaderOptions {
+            sample_rate: Some(22050),
+            channel_layout: Some("stereo".into()),
+            sample_format: SampleFormat::F32Planar,
+        };
+        let mut reader = AudioReader::open(&path, &options).unwrap();
+        assert_eq!((reader.sample_rate(), reader.channels()), (22050, 2));
+        assert_eq!(reader.channel_layout(), "stereo");
+        // The file's stream is described as it is.
+        assert_eq!(reader.audio_stream().sample_rate, 8000);
+
+        let chunks = read_all(&mut reader);
+        let mut left = Vec::new();
+        for chunk in &chunks {
+            let planes = chunk.planes();
+            assert_eq!(planes.len(), 2);
+            assert_eq!(planes[0], planes[1]);
+            left.extend_from_slice(planes[0]);
+        }
+        let duration = left.len() as f64 / 22050.0;
+        assert!((duration - 1.0).abs() < 2e-3, "{duration}");
+        assert!((frequency(&left, 22050) - 440.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn downmixes_interleaved_channels() {
+        let dir = TempDir::new("audio-downmix");
+        let path = dir.join("stereo.wav");
+        write_wav(&path, 16000, 2, &sine(1000.0, 16000, 2, 0.5));
+
+        let stereo = AudioReader::open(&path, &AudioReaderOptions::default()).unwrap();
+        assert_eq!(stereo.channels(), 2);
+        let interleaved: Vec<f32> = stereo.flat_map(|c| c.unwrap().into_data()).collect();
+        assert_eq!(interleaved.len(), 16000);
+        assert!(interleaved.chunks(2).all(|p| p[0] == p[1]));
+
+        let options = AudioReaderOptions {
+            channel_layout: Some("mono".into()),
+            ..Default::default()
+        };
+        let mono: Vec<f32> = AudioReader::open(&path, &options)
+            .unwrap()
+            .flat_map(|c| c.unwrap().into_data())
+            .collect();
+        assert_eq!(mono.len(), 8000);
+        assert!((frequency(&mono, 16000) - 1000.0).abs() < 2.0);
+    }
+
+    #[test]
+    fn bad_options_and_files_are_rejected() {
+        let dir = TempDir::new("audio-errors");
+        let wav = dir.join("tone.wav");
+        write_wav(&wav, 8000, 1, &sine(440.0, 8000, 1, 0.1));
+        let y4m = dir.join("clip.y4m");
+        write_y4m(&y4m, 4, 2, 3);
+
+        for options in [
+            AudioReaderOptions {
+                sample_rate: Some(0),
+                ..Default::default()
+            },
+            AudioReaderOptions {
+                sample_rate: Some(u32::MAX),
+                ..Default::default()
+            },
+            AudioReaderOptions {
+                channel_layout: Some("no such layout".into()),
+                ..Default::default()
+            },
+            AudioReaderOptions {
+                channel_layout: Some("x".repeat(64)),
+                ..Default::default()
+            },
+        ] {
+            let result = AudioReader::open(&wav, &options);
+            assert!(
+                matches!(result, Err(By2Error::InvalidArgument(_))),
+                "{options:?}: {result:?}"
+            );
+        }
+        // No audio in it, or no file at all.
+        for path in [y4m, dir.join("missing.wav")] {
+            let result = AudioReader::open(&path, &AudioReaderOptions::default());
+            assert!(matches!(result, Err(By2Error::Io(_))), "{result:?}");
+        }
+    }
+}
@@ -0,0 +1,609 @@
+use std::ffi::{CStr, CString};
+use std::path::{Path, PathBuf};
+use std::ptr::{self, NonNull};
+
+use crate::bindings::{
+    BY2_IMAGE_JPEG, BY2_IMAGE_PNG, BY2_SELECT_EVERY_NTH, BY2_SELECT_INTERVAL,
+    BY2_SELECT_SCENE_CHANGE, by2_encode_image, by2_extract_default_options, by2_extract_frames,
+    by2_extract_options, by2_extracted_frame, by2_frame_set, by2_frame_set_count,
+    by2_frame_set_destroy, by2_frame_set_frame, by2_frame_set_size, by2_video_frame_info,
+};
+use crate::error::{By2Error, check_status};
+
+use super::{Frame, PixelFormat, c_path};
+
+/// Which frames [`extract_frames`] picks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameSelection {
+    /// The first frame at or after every multiple of this many seconds,
+    /// counted from the first frame. Frames without a timestamp are never
+    /// picked.
+    Interval(f64),
+    /// Frames 0, n, 2n, ...
+    EveryNth(u64),
+    /// The first frame, then every frame whose scene score reaches this
+    /// threshold in `(0, 1]`; 0.3 catches most cuts.
+    SceneChange(f64),
+}
+
+impl FrameSelection {
+    fn from_raw(raw: &by2_extract_options) -> Result<Self, By2Error> {
+        Ok(match raw.selection {
+            r if r == BY2_SELECT_INTERVAL as i32 => FrameSelection::Interval(raw.interval),
+            r if r == BY2_SELECT_EVERY_NTH as i32 => {
+                FrameSelection::EveryNth(raw.step.max(0) as u64)
+            }
+            r if r == BY2_SELECT_SCENE_CHANGE as i32 => {
+                FrameSelection::SceneChange(raw.scene_threshold)
+            }
+            r => return Err(By2Error::Internal(format!("unknown frame selection {r}"))),
+        })
+    }
+}
+
+/// [`Default`] gives the native default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageFormat {
+    /// Lossless.
+    Png,
+    Jpeg,
+}
+
+impl ImageFormat {
+    fn to_raw(self) -> i32 {
+        let raw = match self {
+            ImageFormat::Png => BY2_IMAGE_PNG,
+            ImageFormat::Jpeg => BY2_IMAGE_JPEG,
+        };
+        raw as i32
+    }
+
+    fn from_raw(raw: i32) -> Result<Self, By2Error> {
+        Ok(match raw {
+            r if r == BY2_IMAGE_PNG as i32 => ImageFormat::Png,
+            r if r == BY2_IMAGE_JPEG as i32 => ImageFormat::Jpeg,
+            r => return Err(By2Error::Internal(format!("unknown image format {r}"))),
+        })
+    }
+
+    /// What extracted images of this format end in, without the dot.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+        }
+    }
+}
+
+impl Default for ImageFormat {
+    fn default() -> Self {
+        ImageFormat::from_raw(ExtractOptions::raw_defaults().image_format)
+            .expect("by2_extract_default_options returns a known image format")
+    }
+}
+
+/// Where and how [`extract_frames`] writes images.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageOutput {
+    /// Created if needed. Frame `i` goes to `frame_<i>.png` or `.jpg` in
+    /// it, `i` padded to 8 digits.
+    pub directory: PathBuf,
+    pub format: ImageFormat,
+    /// JPEG quality from 1 to 100; PNG ignores it.
+    pub quality: u8,
+}
+
+impl ImageOutput {
+    /// Writes `format` images into `directory` at the default quality.
+    pub fn new(directory: impl Into<PathBuf>, format: ImageFormat) -> Self {
+        Self {
+            directory: directory.into(),
+            format,
+            quality: ExtractOptions::raw_defaults().quality as u8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractOptions {
+    pub selection: FrameSelection,
+    /// Size of the extracted frames. `None` for both keeps the video's,
+    /// `None` for one of them keeps its aspect ratio.
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Parts of the file decoded in parallel, each by its own reader, at
+    /// most 1024. `None` picks one per hardware thread, but none shorter
+    /// than 10 s. Either way there are no more than hardware threads or
+    /// frames to pick.
+    pub segments: Option<u32>,
+    /// Where to write the frames as images; `None` keeps them in memory.
+    pub output: Option<ImageOutput>,
+}
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        let raw = Self::raw_defaults();
+        Self {
+            selection: FrameSelection::from_raw(&raw)
+                .expect("by2_extract_default_options returns a known selection"),
+            width: None,
+            height: None,
+            segments: None,
+            output: None,
+        }
+    }
+}
+
+impl ExtractOptions {
+    fn raw_defaults() -> by2_extract_options {
+        let mut raw = by2_extract_options {
+            selection: 0,
+            interval: 0.0,
+            step: 0,
+            scene_threshold: 0.0,
+            width: 0,
+            height: 0,
+            segments: 0,
+            directory: ptr::null(),
+            image_format: 0,
+            quality: 0,
+        };
+        unsafe { by2_extract_default_options(&mut raw) };
+        raw
+    }
+
+    /// The C options, and the directory string they point into.
+    fn to_raw(&self) -> Result<(by2_extract_options, Option<CString>), By2Error> {
+        let mut raw = Self::raw_defaults();
+        match self.selection {
+            FrameSelection::Interval(seconds) => {
+                raw.selection = BY2_SELECT_INTERVAL as i32;
+                raw.interval = seconds;
+            }
+            FrameSelection::EveryNth(step) => {
+                raw.selection = BY2_SELECT_EVERY_NTH as i32;
+                raw.step = i64::try_from(step)
+                    .map_err(|_| By2Error::InvalidArgument(format!("step {step}")))?;
+            }
+            FrameSelection::SceneChange(threshold) => {
+                raw.selection = BY2_SELECT_SCENE_CHANGE as i32;
+                raw.scene_threshold = threshold;
+            }
+        }
+        let side = |value: Option<u32>| {
+            i32::try_from(value.unwrap_or(0))
+                .map_err(|_| By2Error::InvalidArgument(format!("frame size {value:?}")))
+        };
+        raw.width = side(self.width)?;
+        raw.height = side(self.height)?;
+        raw.segments = match self.segments {
+            Some(0) => {
+                return Err(By2Error::InvalidArgument(
+                    "at least one segment is needed".into(),
+                ));
+            }
+            Some(n) => {
+                i32::try_from(n).map_err(|_| By2Error::InvalidArgument(format!("{n} segments")))?
+            }
+            None => 0,
+        };
+        let directory = match &self.output {
+            Some(output) => {
+                let directory = c_path(&output.directory)?;
+                raw.directory = directory.as_ptr();
+                raw.image_format = output.format.to_raw();
+                raw.quality = output.quality.into();
+                Some(directory)
+            }
+            None => None,
+        };
+        Ok((raw, directory))
+    }
+}
+
+/// Where an extracted frame ended up.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExtractedImage {
+    /// An [`PixelFormat::Rgb24`] frame of the requested size.
+    Memory(Frame),
+    File(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractedFrame {
+    /// Position of the frame in the stream, from 0.
+    pub index: u64,
+    /// In seconds.
+    pub time: Option<f64>,
+    /// Mean absolute difference in `[0, 1]` between the luma of this frame
+    /// and the one before it. Only for [`FrameSelection::SceneChange`], and
+    /// not for the first frame.
+    pub scene_score: Option<f64>,
+    pub image: ExtractedImage,
+}
+
+/// Frees the set when the frames have been copied out, or on an error.
+struct FrameSet(NonNull<by2_frame_set>);
+
+impl Drop for FrameSet {
+    fn drop(&mut self) {
+        unsafe { by2_frame_set_destroy(self.0.as_ptr()) }
+    }
+}
+
+/// Decodes the best video stream of `path` and picks frames as `options`
+/// say, in stream order.
+///
+/// Long files are split into segments that are decoded in parallel. Each
+/// starts decoding a little before its time range and
+/// [`FrameSelection::EveryNth`] builds an index first, so the frames picked
+/// are the ones a single pass would pick.
+///
+/// Fails with [`By2Error::InvalidArgument`] for bad options and with
+/// [`By2Error::Io`] if the file can't be opened or decoded or an image can't
+/// be written.
+pub fn extract_frames(
+    path: impl AsRef<Path>,
+    options: &ExtractOptions,
+) -> Result<Vec<ExtractedFrame>, By2Error> {
+    let path = c_path(path.as_ref())?;
+    // `_directory` backs `raw.directory` until the call returns.
+    let (raw, _directory) = options.to_raw()?;
+    let mut set = ptr::null_mut();
+    check_status(unsafe { by2_extract_frames(path.as_ptr(), &raw, &mut set) })?;
+    let set = FrameSet(
+        NonNull::new(set)
+            .ok_or_else(|| By2Error::Internal("by2_extract_frames returned null".into()))?,
+    );
+
+    let (mut width, mut height) = (0, 0);
+    check_status(unsafe { by2_frame_set_size(set.0.as_ptr(), &mut width, &mut height) })?;
+    let count = unsafe { by2_frame_set_count(set.0.as_ptr()) };
+    let mut frames = Vec::with_capacity(count);
+    for index in 0..count {
+        let mut frame = by2_extracted_frame {
+            index: 0,
+            pts: 0,
+            time: 0.0,
+            key_frame: 0,
+            scene_score: 0.0,
+            data: ptr::null(),
+            size: 0,
+            path: ptr::null(),
+        };
+        check_status(unsafe { by2_frame_set_frame(set.0.as_ptr(), index, &mut frame) })?;
+        // Both are owned by the set, which outlives this loop.
+        let image = if frame.path.is_null() {
+            let data = if frame.size == 0 {
+                Vec::new()
+            } else {
+                unsafe { std::slice::from_raw_parts(frame.data, frame.size) }.to_vec()
+            };
+            let info = by2_video_frame_info {
+                pts: frame.pts,
+                time: frame.time,
+                index: frame.index,
+                key_frame: frame.key_frame,
+            };
+            ExtractedImage::Memory(Frame::from_info(
+                data,
+                width as u32,
+                height as u32,
+                PixelFormat::Rgb24,
+                &info,
+            ))
+        } else {
+            let path = unsafe { CStr::from_ptr(frame.path) };
+            ExtractedImage::File(PathBuf::from(path.to_string_lossy().into_owned()))
+        };
+        frames.push(ExtractedFrame {
+            index: frame.index as u64,
+            time: (!frame.time.is_nan()).then_some(frame.time),
+            scene_score: (!frame.scene_score.is_nan()).then_some(frame.scene_score),
+            image,
+        });
+    }
+    Ok(frames)
+}
+
+/// Encodes `frame` as a PNG or JPEG image with FFmpeg's image encoders.
+/// `quality` goes from 1 to 100 and only matters for JPEG.
+///
+/// Fails with [`By2Error::InvalidArgument`] for a quality outside 1 to 100
+/// and with [`By2Error::Io`] if encoding fails.
+pub fn encode_image(frame: &Frame, format: ImageFormat, quality: u8) -> Result<Vec<u8>, By2Error> {
+    let data = frame.data();
+    let width = i32::try_from(frame.width())
+        .map_err(|_| By2Error::InvalidArgument(format!("frame width {}", frame.width())))?;
+    let height = i32::try_from(frame.height())
+        .map_err(|_| By2Error::InvalidArgument(format!("frame height {}", frame.height())))?;
+    // Enough for almost any image, so it is encoded only once.
+    let mut bytes = vec![0; data.len() + data.len() / 2 + 4096];
+    loop {
+        let mut size = 0;
+        check_status(unsafe {
+            by2_encode_image(
+                data.as_ptr(),
+                data.len(),
+                width,
+                height,
+                frame.format().to_raw(),
+                format.to_raw(),
+                quality.into(),
+                bytes.as_mut_ptr(),
+                bytes.len(),
+                &mut size,
+            )
+        })?;
+        if size <= bytes.len() {
+            bytes.truncate(size);
+            return Ok(bytes);
+        }
+        bytes.resize(size, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::VideoReader;
+    use crate::media::tests::{write_y4m, write_y4m_frames};
+    use crate::testing::TempDir;
+
+    fn indices(frames: &[ExtractedFrame]) -> Vec<u64> {
+        frames.iter().map(|f| f.index).collect()
+    }
+
+    fn memory(frame: &ExtractedFrame) -> &Frame {
+        match &frame.image {
+            ExtractedImage::Memory(image) => image,
+            ExtractedImage::File(path) => panic!("written to {}", path.display()),
+        }
+    }
+
+    /// A 25 fps clip of three flat scenes of 20 frames, with a little
+    /// texture that moves from frame to frame.
+    fn write_scenes(path: &Path, width: u32, height: u32) {
+        let planes = (0..60).map(|i| {
+            let base = [40, 200, 110][i / 20];
+            let mut plane = Vec::new();
+            for y in 0..height as usize {
+                for x in 0..width as usize {
+                    plane.push((base + (x + y + i) % 6) as u8);
+                }
+            }
+            plane
+        });
+        write_y4m_frames(path, width, height, planes);
+    }
+
+    #[test]
+    fn defaults_are_the_native_ones() {
+        let options = ExtractOptions::default();
+        assert_eq!(options.selection, FrameSelection::Interval(1.0));
+        assert_eq!(ImageFormat::default(), ImageFormat::Png);
+        let output = ImageOutput::new("frames", ImageFormat::default());
+        assert_eq!(output.quality, 90);
+    }
+
+    #[test]
+    fn picks_frames_at_intervals() {
+        let dir = TempDir::new("extract-interval");
+        let path = dir.join("clip.y4m");
+        write_y4m(&path, 8, 6, 50);
+
+        let options = ExtractOptions {
+            selection: FrameSelection::Interval(0.4),
+            ..Default::default()
+        };
+        let frames = extract_frames(&path, &options).unwrap();
+        assert_eq!(indices(&frames), [0, 10, 20, 30, 40]);
+        let decoded: Vec<Frame> = VideoReader::open(&path, PixelFormat::Rgb24)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        for frame in &frames {
+            let expected = frame.index as f64 * 0.04;
+            assert!((frame.time.unwrap() - expected).abs() < 1e-9);
+            assert_eq!(frame.scene_score, None);
+            let image = memory(frame);
+            assert_eq!((image.width(), image.height()), (8, 6));
+            assert_eq!(image.format(), PixelFormat::Rgb24);
+            assert_eq!(image.index(), frame.index);
+            assert_eq!(image.data(), decoded[frame.index as usize].data());
+        }
+
+        let options = ExtractOptions {
+            selection: FrameSelection::EveryNth(12),
+            ..Default::default()
+        };
+        let frames = extract_frames(&path, &options).unwrap();
+        assert_eq!(indices(&frames), [0, 12, 24, 36, 48]);
+    }
+
+    #[test]
+    fn finds_scene_changes() {
+        let dir = TempDir::new("extract-scenes");
+        let path = dir.join("scenes.y4m");
+        write_scenes(&path, 16, 8);
+
+        let options = ExtractOptions {
+            selection: FrameSelection::SceneChange(0.3),
+            ..Default::default()
+        };
+        let frames = extract_frames(&path, &options).unwrap();
+        assert_eq!(indices(&frames), [0, 20, 40]);
+        assert_eq!(frames[0].scene_score, None);
+        for frame in &frames[1..] {
+            let score = frame.scene_score.unwrap();
+            assert!((0.3..=1.0).contains(&score), "{score}");
+        }
+    }
+
+    #[test]
+    fn segments_pick_what_a_single_pass_picks() {
+        let dir = TempDir::new("extract-segments");
+        // 12 s, long enough for segments of a few seconds.
+        let clip = dir.join("clip.y4m");
+        write_y4m(&clip, 4, 2, 300);
+        let scenes = dir.join("scenes.y4m");
+        write_scenes(&scenes, 16, 8);
+
+        for (path, selection) in [
+            (&clip, FrameSelection::Interval(0.5)),
+            (&clip, FrameSelection::Interval(1.3)),
+            (&clip, FrameSelection::EveryNth(7)),
+            (&scenes, FrameSelection::SceneChange(0.3)),
+        ] {
+            let pass = |segments| {
+                let options = ExtractOptions {
+                    selection,
+                    segments: Some(segments),
+                    ..Default::default()
+                };
+                extract_frames(path, &options).unwrap()
+            };
+            let single = pass(1);
+            assert!(single.len() > 2, "{selection:?}");
+            for segments in [3, 4] {
+                assert_eq!(pass(segments), single, "{selection:?}, {segments} segments");
+            }
+        }
+    }
+
+    #[test]
+    fn resizes_keeping_the_aspect_ratio() {
+        let dir = TempDir::new("extract-size");
+        let path = dir.join("clip.y4m");
+        write_y4m(&path, 16, 12, 3);
+
+        for (width, height, expected) in [
+            (Some(8), None, (8, 6)),
+            (None, Some(3), (4, 3)),
+            (Some(5), Some(7), (5, 7)),
+        ] {
+            let options = ExtractOptions {
+                selection: FrameSelection::EveryNth(1),
+                width,
+                height,
+                ..Default::default()
+            };
+            let frames = extract_frames(&path, &options).unwrap();
+            assert_eq!(frames.len(), 3);
+            for frame in &frames {
+                let image = memory(frame);
+                assert_eq!((image.width(), image.height()), expected);
+                assert_eq!(
+                    image.data().len(),
+                    PixelFormat::Rgb24.frame_size(expected.0, expected.1)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn writes_png_and_jpeg_images() {
+        let dir = TempDir::new("extract-images");
+        let path = dir.join("clip.y4m");
+        write_y4m(&path, 8, 6, 25);
+
+        for (format, magic) in [
+            (ImageFormat::Png, &b"\x89PNG\r\n\x1a\n"[..]),
+            (ImageFormat::Jpeg, &b"\xff\xd8"[..]),
+        ] {
+            let out = dir.join(format.extension());
+            let options = ExtractOptions {
+                selection: FrameSelection::EveryNth(10),
+                output: Some(ImageOutput::new(&out, format)),
+                ..Default::default()
+            };
+            let frames = extract_frames(&path, &options).unwrap();
+            assert_eq!(indices(&frames), [0, 10, 20]);
+            for frame in &frames {
+                let name = format!("frame_{:08}.{}", frame.index, format.extension());
+                assert_eq!(frame.image, ExtractedImage::File(out.join(name)));
+                let ExtractedImage::File(file) = &frame.image else {
+                    unreachable!()
+                };
+                assert!(std::fs::read(file).unwrap().starts_with(magic));
+            }
+
+            let decoded = VideoReader::open(&path, PixelFormat::Yuv420p)
+                .unwrap()
+                .next()
+                .unwrap()
+                .unwrap();
+            let image = encode_image(&decoded, format, 75).unwrap();
+            assert!(image.starts_with(magic));
+        }
+    }
+
+    #[test]
+    fn bad_options_are_rejected() {
+        let dir = TempDir::new("extract-errors");
+        let path = dir.join("clip.y4m");
+        write_y4m(&path, 4, 2, 3);
+
+        let output = |quality| ImageOutput {
+            quality,
+            ..ImageOutput::new(dir.join("out"), ImageFormat::Jpeg)
+        };
+        for options in [
+            ExtractOptions {
+                selection: FrameSelection::Interval(0.0),
+                ..Default::default()
+            },
+            ExtractOptions {
+                selection: FrameSelection::Interval(f64::NAN),
+                ..Default::default()
+            },
+            ExtractOptions {
+                selection: FrameSelection::EveryNth(0),
+                ..Default::default()
+            },
+            ExtractOptions {
+                selection: FrameSelection::SceneChange(0.0),
+                ..Default::default()
+            },
+            ExtractOptions {
+                selection: FrameSelection::SceneChange(1.5),
+                ..Default::default()
+            },
+            ExtractOptions {
+                segments: Some(0),
+                ..Default::default()
+            },
+            ExtractOptions {
+                segments: Some(1025),
+                ..Default::default()
+            },
+            ExtractOptions {
+                output: Some(output(0)),
+                ..Default::default()
+            },
+            ExtractOptions {
+                output: Some(output(101)),
+                ..Default::default()
+            },
+        ] {
+            let result = extract_frames(&path, &options);
+            assert!(
+                matches!(result, Err(By2Error::InvalidArgument(_))),
+                "{options:?}: {result:?}"
+            );
+        }
+        let result = extract_frames(dir.join("missing.y4m"), &ExtractOptions::default());
+        assert!(matches!(result, Err(By2Error::Io(_))), "{result:?}");
+
+        let frame = VideoReader::open(&path, PixelFormat::Rgb24)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let result = encode_image(&frame, ImageFormat::Jpeg, 0);
+        assert!(
+            matches!(result, Err(By2Error::InvalidArgument(_))),
+            "{result:?}"
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use std::ffi::CStr;
+use std::path::Path;
+use std::ptr::{self, NonNull};
+
+use crate::bindings::{
+    by2_media_format, by2_media_info, by2_media_info_destroy, by2_media_info_format,
+    by2_media_info_stream, by2_media_info_stream_count, by2_media_info_tag,
+    by2_media_info_tag_count, by2_media_probe,
+};
+use crate::error::{By2Error, check_status};
+
+use super::video_reader::empty_stream_info;
+use super::{MediaType, StreamInfo, c_path, name};
+
+/// A metadata entry of a file or stream, e.g. `title` or `language`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Tag {
+    pub key: String,
+    pub value: String,
+}
+
+/// A stream of a probed file with its metadata.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MediaStream {
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub info: StreamInfo,
+    /// In the order the file lists them.
+    pub tags: Vec<Tag>,
+}
+
+/// What a media file holds, as its container describes it.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MediaInfo {
+    /// FFmpeg's short name for the container, e.g. `"matroska,webm"`.
+    pub format: String,
+    /// A human readable name, e.g. `"Matroska / WebM"`.
+    pub format_long_name: String,
+    /// In seconds.
+    pub duration: Option<f64>,
+    /// Bits per second of the whole file.
+    pub bit_rate: Option<u64>,
+    /// Metadata of the file, in the order it lists them.
+    pub tags: Vec<Tag>,
+    pub streams: Vec<MediaStream>,
+}
+
+/// Frees the native description when probing is done or fails half-way.
+struct Handle(NonNull<by2_media_info>);
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        unsafe { by2_media_info_destroy(self.0.as_ptr()) }
+    }
+}
+
+impl MediaInfo {
+    /// Opens `path` and describes its container and streams, reading no
+    /// more of it than FFmpeg needs to tell what it holds.
+    ///
+    /// Fails with [`By2Error::Io`] if the file can't be opened or isn't a
+    /// format FFmpeg knows.
+    pub fn probe(path: impl AsRef<Path>) -> Result<Self, By2Error> {
+        let path = c_path(path.as_ref())?;
+        let mut raw = ptr::null_mut();
+        check_status(unsafe { by2_media_probe(path.as_ptr(), &mut raw) })?;
+        let handle = Handle(
+            NonNull::new(raw)
+                .ok_or_else(|| By2Error::Internal("by2_media_probe returned null".into()))?,
+        );
+        let info = handle.0.as_ptr();
+
+        let mut format = by2_media_format {
+            name: [0; 64],
+            long_name: [0; 128],
+            duration: 0.0,
+            bit_rate: 0,
+        };
+        check_status(unsafe { by2_media_info_format(info, &mut format) })?;
+
+        let count = unsafe { by2_media_info_stream_count(info) };
+        let mut streams = Vec::with_capacity(count);
+        for index in 0..count {
+            let mut stream = empty_stream_info();
+            check_status(unsafe { by2_media_info_stream(info, index, &mut stream) })?;
+            streams.push(MediaStream {
+                info: StreamInfo::from_raw(&stream)?,
+                tags: tags(info, index as i64)?,
+            });
+        }
+
+        Ok(Self {
+            format: name(&format.name)?,
+            format_long_name: name(&format.long_name)?,
+            duration: (!format.duration.is_nan()).then_some(format.duration),
+            bit_rate: (format.bit_rate > 0).then_some(format.bit_rate as u64),
+            tags: tags(info, -1)?,
+            streams,
+        })
+    }
+
+    /// The first stream of `media_type`, if any.
+    pub fn first_stream(&self, media_type: MediaType) -> Option<&MediaStream> {
+        self.streams
+            .iter()
+            .find(|s| s.info.media_type == media_type)
+    }
+}
+
+/// Tags of stream `stream`, or of the file for -1.
+fn tags(info: *const by2_media_info, stream: i64) -> Result<Vec<Tag>, By2Error> {
+    let count = unsafe { by2_media_info_tag_count(info, stream) };
+    (0..count)
+        .map(|index| {
+            let (mut key, mut value) = (ptr::null(), ptr::null());
+            check_status(unsafe { by2_media_info_tag(info, stream, index, &mut key, &mut value) })?;
+            // Both live as long as `info`; copied right away.
+            let text = |p| unsafe { CStr::from_ptr(p) }.to_string_lossy().into_owned();
+            Ok(Tag {
+                key: text(key),
+                value: text(value),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::tests::{write_wav, write_y4m};
+    use crate::media::{PixelFormat, VideoCodec, VideoWriter, VideoWriterOptions};
+    use crate::testing::TempDir;
+
+    #[test]
+    fn describes_video_files() {
+        let dir = TempDir::new("probe-video");
+        let path = dir.join("clip.mkv");
+        let options = VideoWriterOptions {
+            codec: VideoCodec::Ffv1,
+            ..Default::default()
+        };
+        let mut writer = VideoWriter::create(&path, 6, 4, PixelFormat::Yuv420p, &options).unwrap();
+        for _ in 0..10 {
+            writer.write(&[128; 36]).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let info = MediaInfo::probe(&path).unwrap();
+        assert!(info.format.contains("matroska"), "{}", info.format);
+        assert!(!info.format_long_name.is_empty());
+        assert!((info.duration.unwrap() - 0.4).abs() < 0.05, "{info:?}");
+        assert_eq!(info.streams.len(), 1);
+        let video = &info.first_stream(MediaType::Video).unwrap().info;
+        assert_eq!(video.codec, "ffv1");
+        assert_eq!((video.width, video.height), (6, 4));
+        assert_eq!(video.pixel_format.as_deref(), Some("yuv420p"));
+        assert!((video.frame_rate.unwrap() - 25.0).abs() < 1e-6);
+        assert_eq!(video.channel_layout, None);
+        assert!(info.first_stream(MediaType::Audio).is_none());
+
+        let y4m = dir.join("clip.y4m");
+        write_y4m(&y4m, 4, 2, 3);
+        let info = MediaInfo::probe(&y4m).unwrap();
+        assert_eq!(info.format, "yuv4mpegpipe");
+        assert_eq!(info.streams[0].info.codec, "rawvideo");
+    }
+
+    #[test]
+    fn describes_audio_files() {
+        let dir = TempDir::new("probe-audio");
+        let path = dir.join("tone.wav");
+        write_wav(&path, 8000, 2, &vec![0; 8000 * 2]);
+
+        let info = MediaInfo::probe(&path).unwrap();
+        assert_eq!(info.format, "wav");
+        assert!((info.duration.unwrap() - 1.0).abs() < 1e-3, "{info:?}");
+        let audio = &info.first_stream(MediaType::Audio).unwrap().info;
+        assert_eq!(audio.codec, "pcm_s16le");
+        assert_eq!((audio.sample_rate, audio.channels), (8000, 2));
+        assert!(audio.channel_layout.is_some());
+        assert_eq!(audio.bit_rate, Some(8000 * 2 * 16));
+        assert_eq!(audio.pixel_format, None);
+    }
+
+    #[test]
+    fn reads_file_and_stream_tags() {
+        let dir = TempDir::new("probe-tags");
+        let path = dir.join("tags.ffmeta");
+        // FFmpeg's own metadata format: file tags, then a stream's.
+        std::fs::write(
+            &path,
+            ";FFMETADATA1\ntitle=Probe test\nartist=by2\n[STREAM]\nlanguage=eng\n",
+        )
+        .unwrap();
+
+        let info = MediaInfo::probe(&path).unwrap();
+        assert_eq!(info.format, "ffmetadata");
+        let tag = |key: &str, value: &str| Tag {
+            key: key.into(),
+            value: value.into(),
+        };
+        assert_eq!(
+            info.tags,
+            [tag("title", "Probe test"), tag("artist", "by2")]
+        );
+        assert_eq!(info.streams.len(), 1);
+        assert_eq!(info.streams[0].info.media_type, MediaType::Data);
+        assert_eq!(info.streams[0].tags, [tag("language", "eng")]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes_to_json() {
+        let info = MediaInfo {
+            format: "wav".into(),
+            format_long_name: "WAV / WAVE (Waveform Audio)".into(),
+            duration: Some(1.5),
+            bit_rate: None,
+            tags: vec![Tag {
+                key: "title".into(),
+                value: "Tone".into(),
+            }],
+            streams: Vec::new(),
+        };
+        let json = serde_json::to_value(&info).unwrap();
+        assert_eq!(json["format"], "wav");
+        assert_eq!(json["duration"], 1.5);
+        assert!(json["bit_rate"].is_null());
+        assert_eq!(json["tags"][0]["value"], "Tone");
+        let back: MediaInfo = serde_json::from_value(json).unwrap();
+        assert_eq!(back, info);
+    }
+
+    #[test]
+    fn unreadable_files_are_io_errors() {
+        let dir = TempDir::new("probe-errors");
+        let empty = dir.join("empty.mkv");
+        std::fs::write(&empty, b"").unwrap();
+
+        for path in [dir.join("missing.mkv"), empty] {
+            let result = MediaInfo::probe(&path);
+            assert!(matches!(result, Err(By2Error::Io(_))), "{result:?}");
+        }
+    }
+}
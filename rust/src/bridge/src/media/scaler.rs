@@ -0,0 +1,370 @@
+use std::ptr::{self, NonNull};
+
+use crate::bindings::{
+    BY2_INTERPOLATION_AREA, BY2_INTERPOLATION_BICUBIC, BY2_INTERPOLATION_BILINEAR, by2_scaler,
+    by2_scaler_create, by2_scaler_destroy, by2_scaler_scale,
+};
+use crate::error::{By2Error, check_status};
+
+use super::{Frame, PixelFormat};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    #[default]
+    Bilinear,
+    /// Sharper than bilinear, a little slower.
+    Bicubic,
+    /// Averages the input pixels each output pixel covers; best for
+    /// shrinking.
+    Area,
+}
+
+impl Interpolation {
+    fn to_raw(self) -> i32 {
+        let raw = match self {
+            Interpolation::Bilinear => BY2_INTERPOLATION_BILINEAR,
+            Interpolation::Bicubic => BY2_INTERPOLATION_BICUBIC,
+            Interpolation::Area => BY2_INTERPOLATION_AREA,
+        };
+        raw as i32
+    }
+}
+
+/// Size and format of the frames on one side of a [`Scaler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FrameLayout {
+    pub width: u32,
+    pub height: u32,
+    pub format: PixelFormat,
+}
+
+impl FrameLayout {
+    pub fn new(width: u32, height: u32, format: PixelFormat) -> Self {
+        Self {
+            width,
+            height,
+            format,
+        }
+    }
+
+    /// Bytes of one frame.
+    pub fn frame_size(&self) -> usize {
+        self.format.frame_size(self.width, self.height)
+    }
+}
+
+/// Resizes frames and converts them between pixel formats with swscale.
+///
+/// Setting up a conversion is much more expensive than running it, so make
+/// one scaler per pair of layouts and reuse it for every frame. A scaler
+/// that only changes the format between RGB layouts copies pixels exactly.
+#[derive(Debug)]
+pub struct Scaler {
+    raw: NonNull<by2_scaler>,
+    source: FrameLayout,
+    destination: FrameLayout,
+}
+
+// The swscale context is owned by the handle and only touched through
+// `&mut self`; nothing in it is tied to the thread that created it.
+unsafe impl Send for Scaler {}
+
+impl Scaler {
+    /// Converts `source` frames into `destination` frames.
+    ///
+    /// Fails with [`By2Error::InvalidArgument`] for an empty frame size or a
+    /// conversion swscale doesn't support.
+    pub fn new(
+        source: FrameLayout,
+        destination: FrameLayout,
+        interpolation: Interpolation,
+    ) -> Result<Self, By2Error> {
+        let size = |layout: FrameLayout, n: u32| {
+            i32::try_from(n).map_err(|_| {
+                By2Error::InvalidArgument(format!("frame size {}x{}", layout.width, layout.height))
+            })
+        };
+        let mut raw = ptr::null_mut();
+        check_status(unsafe {
+            by2_scaler_create(
+                size(source, source.width)?,
+                size(source, source.height)?,
+                source.format.to_raw(),
+                size(destination, destination.width)?,
+                size(destination, destination.height)?,
+                destination.format.to_raw(),
+                interpolation.to_raw(),
+                &mut raw,
+            )
+        })?;
+        let raw = NonNull::new(raw)
+            .ok_or_else(|| By2Error::Internal("by2_scaler_create returned null".into()))?;
+        Ok(Self {
+            raw,
+            source,
+            destination,
+        })
+    }
+
+    pub fn source(&self) -> FrameLayout {
+        self.source
+    }
+
+    pub fn destination(&self) -> FrameLayout {
+        self.destination
+    }
+
+    /// Converts the tightly packed frame `source` into `destination`.
+    ///
+    /// Fails with [`By2Error::InvalidArgument`] unless the buffers are the
+    /// [`FrameLayout::frame_size`] of their layouts.
+    pub fn scale(&mut self, source: &[u8], destination: &mut [u8]) -> Result<(), By2Error> {
+        check_status(unsafe {
+            by2_scaler_scale(
+                self.raw.as_ptr(),
+                source.as_ptr(),
+                source.len(),
+                destination.as_mut_ptr(),
+                destination.len(),
+            )
+        })
+    }
+
+    /// Converts a decoded frame, which must have the source layout, into a
+    /// new frame with the same timestamps.
+    pub fn scale_frame(&mut self, frame: &Frame) -> Result<Frame, By2Error> {
+        let layout = FrameLayout::new(frame.width(), frame.height(), frame.format());
+        if layout != self.source {
+            return Err(By2Error::InvalidArgument(format!(
+                "a {}x{} {:?} frame for a {}x{} {:?} scaler",
+                layout.width,
+                layout.height,
+                layout.format,
+                self.source.width,
+                self.source.height,
+                self.source.format
+            )));
+        }
+        let to = self.destination;
+        let mut data = vec![0; to.frame_size()];
+        self.scale(frame.data(), &mut data)?;
+        Ok(frame.with_data(data, to.width, to.height, to.format))
+    }
+}
+
+impl Drop for Scaler {
+    fn drop(&mut self) {
+        unsafe { by2_scaler_destroy(self.raw.as_ptr()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::VideoReader;
+    use crate::media::tests::write_y4m;
+    use crate::testing::TempDir;
+
+    const ALL: [PixelFormat; 9] = [
+        PixelFormat::Rgb24,
+        PixelFormat::Rgba,
+        PixelFormat::Yuv420p,
+        PixelFormat::Gray8,
+        PixelFormat::Bgr24,
+        PixelFormat::Bgra,
+        PixelFormat::Nv12,
+        PixelFormat::Yuv422p,
+        PixelFormat::Yuv444p,
+    ];
+
+    fn convert(
+        data: &[u8],
+        from: FrameLayout,
+        to: FrameLayout,
+        interpolation: Interpolation,
+    ) -> Vec<u8> {
+        let mut scaler = Scaler::new(from, to, interpolation).unwrap();
+        let mut out = vec![0; to.frame_size()];
+        scaler.scale(data, &mut out).unwrap();
+        out
+    }
+
+    /// An RGB pattern with every channel varying.
+    fn pattern(width: u32, height: u32) -> Vec<u8> {
+        (0..width * height)
+            .flat_map(|i| {
+                let (x, y) = (i % width, i / width);
+                [
+                    (x * 13 + y) as u8,
+                    (y * 17 + x * 3) as u8,
+                    (x * y * 5) as u8,
+                ]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rgb_layouts_convert_exactly() {
+        let rgb = pattern(7, 5);
+        let layout = |format| FrameLayout::new(7, 5, format);
+
+        let bgr = convert(
+            &rgb,
+            layout(PixelFormat::Rgb24),
+            layout(PixelFormat::Bgr24),
+            Interpolation::Bilinear,
+        );
+        let swapped: Vec<u8> = rgb.chunks(3).flat_map(|p| [p[2], p[1], p[0]]).collect();
+        assert_eq!(bgr, swapped);
+
+        let rgba = convert(
+            &rgb,
+            layout(PixelFormat::Rgb24),
+            layout(PixelFormat::Rgba),
+            Interpolation::Bicubic,
+        );
+        let opaque: Vec<u8> = rgb
+            .chunks(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255])
+            .collect();
+        assert_eq!(rgba, opaque);
+
+        let back = convert(
+            &rgba,
+            layout(PixelFormat::Rgba),
+            layout(PixelFormat::Rgb24),
+            Interpolation::Area,
+        );
+        assert_eq!(back, rgb);
+    }
+
+    #[test]
+    fn solid_colours_survive_every_conversion() {
+        let from = FrameLayout::new(16, 12, PixelFormat::Rgb24);
+        let grey: Vec<u8> = vec![100; from.frame_size()];
+        for format in ALL {
+            for interpolation in [
+                Interpolation::Bilinear,
+                Interpolation::Bicubic,
+                Interpolation::Area,
+            ] {
+                let to = FrameLayout::new(10, 6, format);
+                let there = convert(&grey, from, to, interpolation);
+                let back = convert(&there, to, from, interpolation);
+                assert!(
+                    back.iter().all(|&c| c.abs_diff(100) <= 3),
+                    "{format:?} {interpolation:?}: {back:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn area_downscaling_averages() {
+        // Alternating black and white columns shrink to mid grey.
+        let from = FrameLayout::new(32, 8, PixelFormat::Gray8);
+        let stripes: Vec<u8> = (0..32 * 8)
+            .map(|i| if i % 2 == 0 { 0 } else { 255 })
+            .collect();
+        let to = FrameLayout::new(8, 2, PixelFormat::Gray8);
+        let small = convert(&stripes, from, to, Interpolation::Area);
+        assert!(small.iter().all(|&c| c.abs_diff(128) <= 8), "{small:?}");
+
+        // A horizontal ramp stays ordered when upscaled.
+        let ramp: Vec<u8> = (0..8 * 2).map(|i| (i % 8 * 32) as u8).collect();
+        let wide = convert(&ramp, to, from, Interpolation::Bilinear);
+        for row in wide.chunks(32) {
+            assert!(row.windows(2).all(|w| w[0] <= w[1]), "{row:?}");
+            assert!(row[0] < 32 && row[31] > 192, "{row:?}");
+        }
+    }
+
+    #[test]
+    fn yuv_round_trips_are_close() {
+        let rgb = pattern(8, 6);
+        let from = FrameLayout::new(8, 6, PixelFormat::Rgb24);
+        // Without chroma subsampling, only rounding is lost.
+        let to = FrameLayout::new(8, 6, PixelFormat::Yuv444p);
+        let yuv = convert(&rgb, from, to, Interpolation::Bilinear);
+        let back = convert(&yuv, to, from, Interpolation::Bilinear);
+        let error = rgb.iter().zip(&back).map(|(a, b)| a.abs_diff(*b)).max();
+        assert!(error.unwrap() <= 4, "{back:?}");
+
+        let gray = convert(
+            &[255, 255, 255, 0, 0, 0],
+            FrameLayout::new(2, 1, PixelFormat::Rgb24),
+            FrameLayout::new(2, 1, PixelFormat::Gray8),
+            Interpolation::Bilinear,
+        );
+        assert!(gray[0] >= 250 && gray[1] <= 5, "{gray:?}");
+    }
+
+    #[test]
+    fn scales_decoded_frames() {
+        let dir = TempDir::new("scaler");
+        let path = dir.join("clip.y4m");
+        write_y4m(&path, 8, 6, 3);
+
+        let mut reader = VideoReader::open(&path, PixelFormat::Yuv420p).unwrap();
+        let mut scaler = Scaler::new(
+            FrameLayout::new(8, 6, PixelFormat::Yuv420p),
+            FrameLayout::new(4, 3, PixelFormat::Rgb24),
+            Interpolation::Area,
+        )
+        .unwrap();
+        let frame = reader.nth(1).unwrap().unwrap();
+        let small = scaler.scale_frame(&frame).unwrap();
+        assert_eq!((small.width(), small.height()), (4, 3));
+        assert_eq!(small.format(), PixelFormat::Rgb24);
+        assert_eq!(small.data().len(), 36);
+        assert_eq!(
+            (small.index(), small.pts(), small.key_frame()),
+            (frame.index(), frame.pts(), frame.key_frame())
+        );
+
+        // Reused for another frame.
+        let next = reader.next().unwrap().unwrap();
+        assert_eq!(scaler.scale_frame(&next).unwrap().index(), 2);
+
+        let mut wrong = Scaler::new(
+            FrameLayout::new(4, 3, PixelFormat::Yuv420p),
+            FrameLayout::new(4, 3, PixelFormat::Rgb24),
+            Interpolation::Bilinear,
+        )
+        .unwrap();
+        assert!(matches!(
+            wrong.scale_frame(&frame),
+            Err(By2Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn bad_sizes_are_rejected() {
+        let from = FrameLayout::new(4, 4, PixelFormat::Rgb24);
+        let to = FrameLayout::new(2, 2, PixelFormat::Yuv420p);
+        for empty in [
+            FrameLayout::new(0, 4, PixelFormat::Rgb24),
+            FrameLayout::new(4, u32::MAX, PixelFormat::Rgb24),
+        ] {
+            let result = Scaler::new(empty, to, Interpolation::Bilinear);
+            assert!(
+                matches!(result, Err(By2Error::InvalidArgument(_))),
+                "{result:?}"
+            );
+        }
+
+        let mut scaler = Scaler::new(from, to, Interpolation::Bilinear).unwrap();
+        assert_eq!(scaler.source(), from);
+        assert_eq!(scaler.destination().frame_size(), 6);
+        let mut out = [0; 6];
+        assert!(matches!(
+            scaler.scale(&[0; 47], &mut out),
+            Err(By2Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            scaler.scale(&[0; 48], &mut out[..5]),
+            Err(By2Error::InvalidArgument(_))
+        ));
+        scaler.scale(&[0; 48], &mut out).unwrap();
+    }
+}
@@ -0,0 +1,231 @@
+use std::fmt::Write as _;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use crate::error::By2Error;
+
+use super::StreamInfo;
+
+/// First line of saved indexes, with the format version.
+const HEADER: &str = "by2 video index 2";
+
+/// A keyframe of a video stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Keyframe {
+    /// Position in the stream, from 0.
+    pub index: u64,
+    /// Presentation timestamp in the stream's time base.
+    pub pts: i64,
+}
+
+/// Where the keyframes of a video stream are, so seeks land on the right
+/// frame whatever the frame rate does. See [`VideoReader::build_index`].
+///
+/// Saved as text: a header line, `source <size> <modified> <time base>
+/// <duration>` describing the file it was built from (left out if that is
+/// unknown), `frames <count>`, then one `<index> <pts>` line per keyframe.
+///
+/// [`VideoReader::build_index`]: super::VideoReader::build_index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VideoIndex {
+    pub(super) keyframes: Vec<Keyframe>,
+    pub(super) frame_count: u64,
+    pub(super) source: Option<Source>,
+}
+
+/// What an index was built from, to tell when a saved one no longer
+/// matches its file.
+#[derive(Debug, Clone)]
+pub(super) struct Source {
+    /// File size in bytes.
+    pub(super) size: u64,
+    /// Modification time in nanoseconds since the Unix epoch.
+    pub(super) modified: u128,
+    pub(super) time_base: (i32, i32),
+    /// Duration of the stream in seconds.
+    pub(super) duration: Option<f64>,
+}
+
+impl Source {
+    /// The file at `path` as it is now, with its video stream `stream`, or
+    /// `None` if the file system can't say when it was modified.
+    pub(super) fn new(path: &Path, stream: &StreamInfo) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            size: metadata.len(),
+            modified: modified.as_nanos(),
+            time_base: stream.time_base,
+            duration: stream.duration,
+        })
+    }
+
+    fn to_text(&self) -> String {
+        let (num, den) = self.time_base;
+        let duration = self.duration.map_or("-".into(), |d| d.to_string());
+        format!("{} {} {num}/{den} {duration}", self.size, self.modified)
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        let mut fields = text.split(' ');
+        let size = fields.next()?.parse().ok()?;
+        let modified = fields.next()?.parse().ok()?;
+        let (num, den) = fields.next()?.split_once('/')?;
+        let duration = match fields.next()? {
+            "-" => None,
+            d => Some(d.parse().ok()?),
+        };
+        if fields.next().is_some() {
+            return None;
+        }
+        Some(Self {
+            size,
+            modified,
+            time_base: (num.parse().ok()?, den.parse().ok()?),
+            duration,
+        })
+    }
+}
+
+// Durations compare by their bits, so a saved one matches only itself.
+impl PartialEq for Source {
+    fn eq(&self, other: &Self) -> bool {
+        self.size == other.size
+            && self.modified == other.modified
+            && self.time_base == other.time_base
+            && self.duration.map(f64::to_bits) == other.duration.map(f64::to_bits)
+    }
+}
+
+impl Eq for Source {}
+
+impl VideoIndex {
+    /// By increasing index and pts; the first one is frame 0.
+    pub fn keyframes(&self) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{HEADER}\n");
+        if let Some(source) = &self.source {
+            writeln!(text, "source {}", source.to_text()).unwrap();
+        }
+        writeln!(text, "frames {}", self.frame_count).unwrap();
+        for k in &self.keyframes {
+            writeln!(text, "{} {}", k.index, k.pts).unwrap();
+        }
+        text
+    }
+
+    /// Parses [`VideoIndex::to_text`] output. Fails with [`By2Error::Io`]
+    /// for anything else; whether the keyframes make sense is checked when
+    /// the index is handed to a reader.
+    pub fn from_text(text: &str) -> Result<Self, By2Error> {
+        let damaged = |what: &str| By2Error::Io(format!("video index: {what}"));
+        let mut lines = text.lines().peekable();
+        if lines.next() != Some(HEADER) {
+            return Err(damaged("unknown header"));
+        }
+        let source = match lines.next_if(|l| l.starts_with("source ")) {
+            Some(line) => Some(
+                line.strip_prefix("source ")
+                    .and_then(Source::from_text)
+                    .ok_or_else(|| damaged(line))?,
+            ),
+            None => None,
+        };
+        let frame_count = lines
+            .next()
+            .and_then(|l| l.strip_prefix("frames "))
+            .and_then(|n| n.parse().ok())
+            .ok_or_else(|| damaged("no frame count"))?;
+        let keyframes = lines
+            .map(|line| {
+                let (index, pts) = line.split_once(' ').ok_or_else(|| damaged(line))?;
+                Ok(Keyframe {
+                    index: index.parse().map_err(|_| damaged(line))?,
+                    pts: pts.parse().map_err(|_| damaged(line))?,
+                })
+            })
+            .collect::<Result<_, By2Error>>()?;
+        Ok(Self {
+            keyframes,
+            frame_count,
+            source,
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), By2Error> {
+        let path = path.as_ref();
+        std::fs::write(path, self.to_text())
+            .map_err(|e| By2Error::Io(format!("{}: {e}", path.display())))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, By2Error> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| By2Error::Io(format!("{}: {e}", path.display())))?;
+        Self::from_text(&text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_round_trip() {
+        let mut index = VideoIndex {
+            keyframes: vec![
+                Keyframe { index: 0, pts: -2 },
+                Keyframe { index: 12, pts: 10 },
+            ],
+            frame_count: 20,
+            source: None,
+        };
+        let text = index.to_text();
+        assert_eq!(text, "by2 video index 2\nframes 20\n0 -2\n12 10\n");
+        assert_eq!(VideoIndex::from_text(&text).unwrap(), index);
+
+        for (duration, field) in [(Some(0.8), "0.8"), (None, "-")] {
+            index.source = Some(Source {
+                size: 1234,
+                modified: 1_700_000_000_123_456_789,
+                time_base: (1, 25),
+                duration,
+            });
+            let text = index.to_text();
+            assert_eq!(
+                text,
+                format!(
+                    "by2 video index 2\nsource 1234 1700000000123456789 1/25 {field}\n\
+                     frames 20\n0 -2\n12 10\n"
+                )
+            );
+            assert_eq!(VideoIndex::from_text(&text).unwrap(), index);
+        }
+    }
+
+    #[test]
+    fn damaged_text_is_an_io_error() {
+        for text in [
+            "",
+            "by2 video index 1\nframes 1\n0 0\n",
+            "by2 video index 2\n0 0\n",
+            "by2 video index 2\nframes 1\n0\n",
+            "by2 video index 2\nframes 1\n-1 0\n",
+            "by2 video index 2\nsource 1 2 1/25\nframes 1\n0 0\n",
+            "by2 video index 2\nsource 1 2 25 1\nframes 1\n0 0\n",
+            "by2 video index 2\nsource 1 2 1/25 1 3\nframes 1\n0 0\n",
+        ] {
+            assert!(
+                matches!(VideoIndex::from_text(text), Err(By2Error::Io(_))),
+                "{text:?}"
+            );
+        }
+    }
+}
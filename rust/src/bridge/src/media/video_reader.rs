@@ -0,0 +1,664 @@
+use std::iter::FusedIterator;
+use std::path::Path;
+use std::ptr::{self, NonNull};
+
+use crate::bindings::{
+    by2_rational, by2_stream_info, by2_video_frame_info, by2_video_keyframe, by2_video_reader,
+    by2_video_reader_build_index, by2_video_reader_destroy, by2_video_reader_frame_size,
+    by2_video_reader_index, by2_video_reader_next, by2_video_reader_open,
+    by2_video_reader_seek_frame, by2_video_reader_seek_time, by2_video_reader_set_index,
+    by2_video_reader_stream, by2_video_reader_stream_count, by2_video_reader_video_stream,
+};
+use crate::error::{By2Error, check_status};
+
+use super::video_index::Source;
+use super::{Keyframe, PixelFormat, StreamInfo, VideoIndex, c_path};
+
+/// A decoded video frame, tightly packed in its [`PixelFormat`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+    format: PixelFormat,
+    pts: Option<i64>,
+    time: Option<f64>,
+    index: u64,
+    key_frame: bool,
+}
+
+impl Frame {
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn into_data(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// The planes of the frame in the order [`PixelFormat::plane_sizes`]
+    /// lists them.
+    pub fn planes(&self) -> Vec<&[u8]> {
+        let mut rest = self.data.as_slice();
+        let mut planes = Vec::new();
+        for size in self.format.plane_sizes(self.width, self.height) {
+            let (plane, tail) = rest.split_at(size);
+            planes.push(plane);
+            rest = tail;
+        }
+        planes
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    /// Presentation timestamp in units of the stream's time base, if the
+    /// file has one for this frame.
+    pub fn pts(&self) -> Option<i64> {
+        self.pts
+    }
+
+    /// Presentation time in seconds.
+    pub fn time(&self) -> Option<f64> {
+        self.time
+    }
+
+    /// Position of the frame in the stream, from 0.
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    pub fn key_frame(&self) -> bool {
+        self.key_frame
+    }
+
+    /// A frame of `data` as by2 describes it.
+    pub(super) fn from_info(
+        data: Vec<u8>,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        info: &by2_video_frame_info,
+    ) -> Self {
+        Self {
+            data,
+            width,
+            height,
+            format,
+            pts: (info.pts != i64::MIN).then_some(info.pts),
+            time: (!info.time.is_nan()).then_some(info.time),
+            index: info.index as u64,
+            key_frame: info.key_frame != 0,
+        }
+    }
+
+    /// This frame with its pixels replaced by `data`, e.g. after scaling.
+    pub(super) fn with_data(
+        &self,
+        data: Vec<u8>,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+    ) -> Self {
+        Self {
+            data,
+            width,
+            height,
+            format,
+            ..*self
+        }
+    }
+}
+
+/// Decodes the best video stream of a file, frame by frame in presentation
+/// order.
+///
+/// Iterating yields the frames until the end of the stream or the first
+/// error, after which it stops until the next seek.
+///
+/// Seeks go back to the keyframe before the target and decode forward to
+/// it, so the next frame is exactly the one asked for. Without an index,
+/// frame numbers after a seek come from the timestamps and the average frame
+/// rate, which is exact for constant frame rate video; with one (see
+/// [`VideoReader::build_index`]) they are counted from the keyframes.
+#[derive(Debug)]
+pub struct VideoReader {
+    raw: NonNull<by2_video_reader>,
+    streams: Vec<StreamInfo>,
+    video_stream: usize,
+    format: PixelFormat,
+    frame_size: usize,
+    /// The file as it was opened, for telling saved indexes of it apart.
+    source: Option<Source>,
+    done: bool,
+}
+
+// The decoder state is owned by the handle and only touched through
+// `&mut self`; nothing in it is tied to the thread that opened it.
+unsafe impl Send for VideoReader {}
+
+impl VideoReader {
+    /// Opens `path` and its best video stream, converting frames to
+    /// `format`.
+    ///
+    /// Fails with [`By2Error::Io`] if the file can't be opened or has no
+    /// video stream FFmpeg can decode.
+    pub fn open(path: impl AsRef<Path>, format: PixelFormat) -> Result<Self, By2Error> {
+        let path = path.as_ref();
+        let c_path = c_path(path)?;
+        let mut raw = ptr::null_mut();
+        check_status(unsafe { by2_video_reader_open(c_path.as_ptr(), format.to_raw(), &mut raw) })?;
+        let raw = NonNull::new(raw)
+            .ok_or_else(|| By2Error::Internal("by2_video_reader_open returned null".into()))?;
+        // Owned from here on, so an early return closes the file.
+        let mut reader = Self {
+            raw,
+            streams: Vec::new(),
+            video_stream: 0,
+            format,
+            frame_size: 0,
+            source: None,
+            done: false,
+        };
+
+        let count = unsafe { by2_video_reader_stream_count(raw.as_ptr()) };
+        for index in 0..count {
+            let mut info = empty_stream_info();
+            check_status(unsafe { by2_video_reader_stream(raw.as_ptr(), index, &mut info) })?;
+            reader.streams.push(StreamInfo::from_raw(&info)?);
+        }
+        reader.video_stream = unsafe { by2_video_reader_video_stream(raw.as_ptr()) };
+        reader.frame_size = unsafe { by2_video_reader_frame_size(raw.as_ptr()) };
+        reader.source = Source::new(path, reader.video_stream());
+        Ok(reader)
+    }
+
+    /// All streams of the file, video or not.
+    pub fn streams(&self) -> &[StreamInfo] {
+        &self.streams
+    }
+
+    /// The stream being decoded.
+    pub fn video_stream(&self) -> &StreamInfo {
+        &self.streams[self.video_stream]
+    }
+
+    pub fn width(&self) -> u32 {
+        self.video_stream().width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.video_stream().height
+    }
+
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    /// Bytes of each frame.
+    pub fn frame_size(&self) -> usize {
+        self.frame_size
+    }
+
+    /// Decodes the next frame, or returns `None` at the end of the stream.
+    pub fn read_frame(&mut self) -> Result<Option<Frame>, By2Error> {
+        let mut data = vec![0; self.frame_size];
+        let mut info = by2_video_frame_info {
+            pts: 0,
+            time: 0.0,
+            index: 0,
+            key_frame: 0,
+        };
+        let mut decoded = 0;
+        // `data` holds exactly one frame.
+        check_status(unsafe {
+            by2_video_reader_next(
+                self.raw.as_ptr(),
+                data.as_mut_ptr(),
+                data.len(),
+                &mut info,
+                &mut decoded,
+            )
+        })?;
+        if decoded == 0 {
+            return Ok(None);
+        }
+        Ok(Some(Frame::from_info(
+            data,
+            self.width(),
+            self.height(),
+            self.format,
+            &info,
+        )))
+    }
+
+    /// Makes the next frame the first one at or after `seconds`, or none
+    /// past the end.
+    pub fn seek_to_time(&mut self, seconds: f64) -> Result<(), By2Error> {
+        check_status(unsafe { by2_video_reader_seek_time(self.raw.as_ptr(), seconds) })?;
+        self.done = false;
+        Ok(())
+    }
+
+    /// Makes the next frame frame `index`, or none past the end.
+    pub fn seek_to_frame(&mut self, index: u64) -> Result<(), By2Error> {
+        let index = i64::try_from(index)
+            .map_err(|_| By2Error::InvalidArgument(format!("frame {index} is out of range")))?;
+        check_status(unsafe { by2_video_reader_seek_frame(self.raw.as_ptr(), index) })?;
+        self.done = false;
+        Ok(())
+    }
+
+    /// Frame `index`, or `None` past the end. Reading continues from the
+    /// frame after it.
+    pub fn frame_at(&mut self, index: u64) -> Result<Option<Frame>, By2Error> {
+        self.seek_to_frame(index)?;
+        self.read_frame()
+    }
+
+    /// Reads the packets of the video stream, without decoding them, to
+    /// find its keyframes, uses the result for later seeks and rewinds to
+    /// the first frame. Streams without a frame rate get an index on their
+    /// first seek.
+    pub fn build_index(&mut self) -> Result<VideoIndex, By2Error> {
+        check_status(unsafe { by2_video_reader_build_index(self.raw.as_ptr()) })?;
+        self.done = false;
+        self.index()?
+            .ok_or_else(|| By2Error::Internal("by2_video_reader_build_index left no index".into()))
+    }
+
+    /// The index in use, if any.
+    pub fn index(&self) -> Result<Option<VideoIndex>, By2Error> {
+        let mut keyframes = Vec::new();
+        loop {
+            let mut count = 0;
+            let mut frame_count = 0;
+            // `keyframes` has room for its length; nothing is written unless
+            // every keyframe fits.
+            check_status(unsafe {
+                by2_video_reader_index(
+                    self.raw.as_ptr(),
+                    keyframes.as_mut_ptr(),
+                    keyframes.len(),
+                    &mut count,
+                    &mut frame_count,
+                )
+            })?;
+            if frame_count < 0 {
+                return Ok(None);
+            }
+            if count <= keyframes.len() {
+                keyframes.truncate(count);
+                return Ok(Some(VideoIndex {
+                    keyframes: keyframes
+                        .iter()
+                        .map(|k| Keyframe {
+                            index: k.index as u64,
+                            pts: k.pts,
+                        })
+                        .collect(),
+                    frame_count: frame_count as u64,
+                    source: self.source.clone(),
+                }));
+            }
+            keyframes.resize(count, by2_video_keyframe { index: 0, pts: 0 });
+        }
+    }
+
+    /// Uses an index built earlier for the same file, e.g. loaded with
+    /// [`VideoIndex::load`]. Fails with [`By2Error::InvalidArgument`] if
+    /// the keyframes don't increase, don't start at frame 0 or lie past the
+    /// frame count.
+    pub fn set_index(&mut self, index: &VideoIndex) -> Result<(), By2Error> {
+        let keyframes: Vec<by2_video_keyframe> = index
+            .keyframes
+            .iter()
+            .map(|k| by2_video_keyframe {
+                index: i64::try_from(k.index).unwrap_or(i64::MAX),
+                pts: k.pts,
+            })
+            .collect();
+        let frame_count = i64::try_from(index.frame_count).unwrap_or(i64::MAX);
+        check_status(unsafe {
+            by2_video_reader_set_index(
+                self.raw.as_ptr(),
+                keyframes.as_ptr(),
+                keyframes.len(),
+                frame_count,
+            )
+        })
+    }
+
+    /// Uses the index saved at `cache` if it was built from this file as it
+    /// is now, with the same size, modification time, time base and
+    /// duration, and fits the stream. Otherwise builds one and saves it
+    /// there.
+    pub fn load_or_build_index(&mut self, cache: impl AsRef<Path>) -> Result<VideoIndex, By2Error> {
+        let cache = cache.as_ref();
+        if let Ok(index) = VideoIndex::load(cache)
+            && index.source.is_some()
+            && index.source == self.source
+            && self.set_index(&index).is_ok()
+        {
+            return Ok(index);
+        }
+        let index = self.build_index()?;
+        index.save(cache)?;
+        Ok(index)
+    }
+}
+
+impl Iterator for VideoReader {
+    type Item = Result<Frame, By2Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let frame = self.read_frame().transpose();
+        self.done = !matches!(frame, Some(Ok(_)));
+        frame
+    }
+}
+
+impl FusedIterator for VideoReader {}
+
+impl Drop for VideoReader {
+    fn drop(&mut self) {
+        unsafe { by2_video_reader_destroy(self.raw.as_ptr()) }
+    }
+}
+
+pub(super) fn empty_stream_info() -> by2_stream_info {
+    by2_stream_info {
+        index: 0,
+        type_: 0,
+        codec: [0; 32],
+        width: 0,
+        height: 0,
+        frame_rate: 0.0,
+        pixel_format: [0; 32],
+        sample_rate: 0,
+        channels: 0,
+        channel_layout: [0; 64],
+        bit_rate: 0,
+        time_base: by2_rational { num: 0, den: 1 },
+        duration: 0.0,
+        frame_count: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::MediaType;
+    use crate::media::tests::{luma, write_y4m};
+    use crate::testing::TempDir;
+
+    #[test]
+    fn describes_the_streams() {
+        let dir = TempDir::new("video-streams");
+        let path = dir.join("clip.y4m");
+        write_y4m(&path, 6, 4, 3);
+
+        let reader = VideoReader::open(&path, PixelFormat::Rgb24).unwrap();
+        assert_eq!(reader.streams().len(), 1);
+        let stream = reader.video_stream();
+        assert_eq!(stream.index, 0);
+        assert_eq!(stream.media_type, MediaType::Video);
+        assert_eq!(stream.codec, "rawvideo");
+        assert_eq!((stream.width, stream.height), (6, 4));
+        assert_eq!(stream.frame_rate, Some(25.0));
+        assert_eq!(stream.time_base, (1, 25));
+        assert_eq!((reader.width(), reader.height()), (6, 4));
+        assert_eq!(reader.frame_size(), 6 * 4 * 3);
+
+        drop(reader);
+    }
+
+    #[test]
+    fn yuv_frames_round_trip() {
+        let dir = TempDir::new("video-yuv");
+        let path = dir.join("clip.y4m");
+        write_y4m(&path, 5, 3, 4);
+
+        let reader = VideoReader::open(&path, PixelFormat::Yuv420p).unwrap();
+        let frames: Vec<Frame> = reader.map(Result::unwrap).collect();
+        assert_eq!(frames.len(), 4);
+        for (i, frame) in frames.iter().enumerate() {
+            assert_eq!(frame.index(), i as u64);
+            assert_eq!(frame.pts(), Some(i as i64));
+            assert!((frame.time().unwrap() - i as f64 / 25.0).abs() < 1e-12);
+            assert!(frame.key_frame());
+            let planes = frame.planes();
+            assert_eq!(planes[0], luma(i, 5, 3));
+            assert!(planes[1].iter().chain(planes[2]).all(|&c| c == 128));
+        }
+    }
+
+    #[test]
+    fn rgb_frames_are_grey() {
+        let dir = TempDir::new("video-rgb");
+        let path = dir.join("clip.y4m");
+        write_y4m(&path, 8, 2, 1);
+
+        for (format, channels) in [(PixelFormat::Rgb24, 3), (PixelFormat::Rgba, 4)] {
+            let mut reader = VideoReader::open(&path, format).unwrap();
+            let frame = reader.read_frame().unwrap().unwrap();
+            assert_eq!(frame.data().len(), 8 * 2 * channels);
+            let pixels: Vec<&[u8]> = frame.data().chunks(channels).collect();
+            for pixel in &pixels {
+                assert!(pixel[0] == pixel[1] && pixel[1] == pixel[2], "{pixel:?}");
+                if channels == 4 {
+                    assert_eq!(pixel[3], 255);
+                }
+            }
+            // Luma grows along each row, and so does the grey level.
+            for row in pixels.chunks(8) {
+                assert!(row.windows(2).all(|w| w[0][0] < w[1][0]), "{row:?}");
+            }
+            assert!(reader.read_frame().unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn unreadable_files_are_io_errors() {
+        let dir = TempDir::new("video-errors");
+        let empty = dir.join("empty.mkv");
+        std::fs::write(&empty, b"").unwrap();
+
+        for path in [dir.join("missing.mkv"), empty] {
+            let result = VideoReader::open(&path, PixelFormat::Rgb24);
+            assert!(matches!(result, Err(By2Error::Io(_))), "{result:?}");
+        }
+        assert!(matches!(
+            VideoReader::open("clip\0.mkv", PixelFormat::Rgb24),
+            Err(By2Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn seeks_land_on_the_exact_frame() {
+        let dir = TempDir::new("video-seek");
+        let path = dir.join("clip.y4m");
+        write_y4m(&path, 4, 2, 10);
+
+        let mut reader = VideoReader::open(&path, PixelFormat::Yuv420p).unwrap();
+        let luma_of = |frame: &Frame| frame.planes()[0].to_vec();
+        for i in [7, 2, 9, 0, 5] {
+            let frame = reader.frame_at(i).unwrap().unwrap();
+            assert_eq!(frame.index(), i);
+            assert_eq!(frame.pts(), Some(i as i64));
+            assert_eq!(luma_of(&frame), luma(i as usize, 4, 2));
+        }
+        // Reading goes on after the frame sought to.
+        assert_eq!(reader.read_frame().unwrap().unwrap().index(), 6);
+
+        // 0.2 s is frame 5 exactly; 0.21 s falls between 5 and 6.
+        for (seconds, expected) in [(0.2, 5), (0.21, 6), (0.0, 0), (-1.0, 0)] {
+            reader.seek_to_time(seconds).unwrap();
+            let frame = reader.read_frame().unwrap().unwrap();
+            assert_eq!(frame.index(), expected, "{seconds}");
+            assert_eq!(luma_of(&frame), luma(expected as usize, 4, 2));
+        }
+
+        assert!(reader.frame_at(10).unwrap().is_none());
+        reader.seek_to_time(5.0).unwrap();
+        assert!(reader.read_frame().unwrap().is_none());
+        assert!(matches!(
+            reader.seek_to_time(f64::NAN),
+            Err(By2Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            reader.seek_to_frame(u64::MAX),
+            Err(By2Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn seeking_resumes_iteration() {
+        let dir = TempDir::new("video-resume");
+        let path = dir.join("clip.y4m");
+        write_y4m(&path, 2, 2, 4);
+
+        let mut reader = VideoReader::open(&path, PixelFormat::Rgb24).unwrap();
+        assert_eq!(reader.by_ref().count(), 4);
+        reader.seek_to_frame(1).unwrap();
+        let indices: Vec<u64> = reader.map(|f| f.unwrap().index()).collect();
+        assert_eq!(indices, [1, 2, 3]);
+    }
+
+    #[test]
+    fn index_finds_every_keyframe_and_is_cached() {
+        let dir = TempDir::new("video-index");
+        let path = dir.join("clip.y4m");
+        let cache = dir.join("clip.index");
+        write_y4m(&path, 4, 2, 6);
+
+        let mut reader = VideoReader::open(&path, PixelFormat::Yuv420p).unwrap();
+        assert_eq!(reader.index().unwrap(), None);
+        reader.read_frame().unwrap();
+        let index = reader.build_index().unwrap();
+        assert_eq!(index.frame_count(), 6);
+        // Raw video is all keyframes.
+        let expected: Vec<Keyframe> = (0..6)
+            .map(|i| Keyframe {
+                index: i,
+                pts: i as i64,
+            })
+            .collect();
+        assert_eq!(index.keyframes(), expected);
+        assert_eq!(reader.index().unwrap(), Some(index.clone()));
+        // Rewound to the start.
+        assert_eq!(reader.read_frame().unwrap().unwrap().index(), 0);
+
+        let mut cached = VideoReader::open(&path, PixelFormat::Yuv420p).unwrap();
+        assert_eq!(cached.load_or_build_index(&cache).unwrap(), index);
+        assert_eq!(VideoIndex::load(&cache).unwrap(), index);
+        let mut again = VideoReader::open(&path, PixelFormat::Yuv420p).unwrap();
+        assert_eq!(again.load_or_build_index(&cache).unwrap(), index);
+        for i in [4, 1, 5] {
+            let frame = again.frame_at(i).unwrap().unwrap();
+            assert_eq!(frame.index(), i);
+            assert_eq!(frame.planes()[0], luma(i as usize, 4, 2));
+        }
+        assert!(again.frame_at(6).unwrap().is_none());
+    }
+
+    #[test]
+    fn bad_indexes_are_rejected() {
+        let dir = TempDir::new("video-bad-index");
+        let path = dir.join("clip.y4m");
+        write_y4m(&path, 2, 2, 3);
+
+        let mut reader = VideoReader::open(&path, PixelFormat::Rgb24).unwrap();
+        let key = |index, pts| Keyframe { index, pts };
+        for (keyframes, frame_count) in [
+            (vec![key(1, 1)], 3),
+            (vec![key(0, 0), key(2, 2), key(1, 1)], 3),
+            (vec![key(0, 5), key(1, 4)], 3),
+            (vec![key(0, 0), key(3, 3)], 3),
+            (vec![], 3),
+        ] {
+            let index = VideoIndex {
+                keyframes,
+                frame_count,
+                source: None,
+            };
+            let result = reader.set_index(&index);
+            assert!(
+                matches!(result, Err(By2Error::InvalidArgument(_))),
+                "{index:?}"
+            );
+        }
+        assert_eq!(reader.index().unwrap(), None);
+
+        // A damaged or stale cache is rebuilt.
+        let cache = dir.join("clip.index");
+        std::fs::write(&cache, "by2 video index 2\nframes 3\n1 1\n").unwrap();
+        assert_eq!(reader.load_or_build_index(&cache).unwrap().frame_count(), 3);
+        assert_eq!(VideoIndex::load(&cache).unwrap().keyframes().len(), 3);
+    }
+
+    #[test]
+    fn indexes_of_changed_files_are_rebuilt() {
+        let dir = TempDir::new("video-stale-index");
+        let path = dir.join("clip.y4m");
+        let cache = dir.join("clip.index");
+        write_y4m(&path, 2, 2, 4);
+        let mut reader = VideoReader::open(&path, PixelFormat::Rgb24).unwrap();
+        assert_eq!(reader.load_or_build_index(&cache).unwrap().frame_count(), 4);
+
+        // A longer file in its place: the old keyframes would still fit.
+        write_y4m(&path, 2, 2, 6);
+        let mut reader = VideoReader::open(&path, PixelFormat::Rgb24).unwrap();
+        let index = reader.load_or_build_index(&cache).unwrap();
+        assert_eq!(index.frame_count(), 6);
+        assert_eq!(VideoIndex::load(&cache).unwrap(), index);
+
+        // Same size and content, touched since.
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        let modified = file.metadata().unwrap().modified().unwrap();
+        file.set_modified(modified - std::time::Duration::from_secs(60))
+            .unwrap();
+        drop(file);
+        let mut reader = VideoReader::open(&path, PixelFormat::Rgb24).unwrap();
+        let touched = reader.load_or_build_index(&cache).unwrap();
+        assert_eq!(touched.keyframes(), index.keyframes());
+        assert_ne!(touched, index);
+        assert_eq!(VideoIndex::load(&cache).unwrap(), touched);
+
+        // A saved index that doesn't say what it was built from.
+        let unsourced = touched
+            .to_text()
+            .lines()
+            .filter(|l| !l.starts_with("source "))
+            .collect::<Vec<_>>()
+            .join("\n");
+        std::fs::write(&cache, unsourced).unwrap();
+        let mut reader = VideoReader::open(&path, PixelFormat::Rgb24).unwrap();
+        assert_eq!(reader.load_or_build_index(&cache).unwrap(), touched);
+        assert_eq!(VideoIndex::load(&cache).unwrap(), touched);
+    }
+
+    #[test]
+    fn iteration_stops_at_the_end() {
+        let dir = TempDir::new("video-fused");
+        let path = dir.join("clip.y4m");
+        write_y4m(&path, 2, 2, 2);
+
+        let mut reader = VideoReader::open(&path, PixelFormat::Rgb24).unwrap();
+        assert_eq!(reader.by_ref().count(), 2);
+        assert!(reader.next().is_none());
+        assert!(reader.next().is_none());
+    }
+}
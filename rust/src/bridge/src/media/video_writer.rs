@@ -0,0 +1,444 @@
+use std::path::Path;
+use std::ptr::{self, NonNull};
+
+use crate::bindings::{
+    BY2_VIDEO_CODEC_FFV1, BY2_VIDEO_CODEC_MPEG4, by2_rational, by2_video_writer,
+    by2_video_writer_create, by2_video_writer_default_options, by2_video_writer_destroy,
+    by2_video_writer_finish, by2_video_writer_frame_count, by2_video_writer_options,
+    by2_video_writer_write,
+};
+use crate::error::{By2Error, check_status};
+
+use super::{Frame, PixelFormat, c_path};
+
+/// Encoders [`VideoWriter`] can use. Both are part of FFmpeg itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VideoCodec {
+    /// MPEG-4 part 2: lossy, plays almost everywhere.
+    Mpeg4,
+    /// FFV1: lossless, keeps RGB frames as RGB. Not in MP4.
+    Ffv1,
+}
+
+impl VideoCodec {
+    fn to_raw(self) -> i32 {
+        let raw = match self {
+            VideoCodec::Mpeg4 => BY2_VIDEO_CODEC_MPEG4,
+            VideoCodec::Ffv1 => BY2_VIDEO_CODEC_FFV1,
+        };
+        raw as i32
+    }
+
+    fn from_raw(raw: i32) -> Result<Self, By2Error> {
+        Ok(match raw {
+            r if r == BY2_VIDEO_CODEC_MPEG4 as i32 => VideoCodec::Mpeg4,
+            r if r == BY2_VIDEO_CODEC_FFV1 as i32 => VideoCodec::Ffv1,
+            r => return Err(By2Error::Internal(format!("unknown video codec {r}"))),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoWriterOptions {
+    pub codec: VideoCodec,
+    /// Frames per second, as (numerator, denominator).
+    pub frame_rate: (i32, i32),
+    /// Bits per second, or 0 for the encoder's default. Lossless codecs
+    /// ignore it.
+    pub bit_rate: u64,
+    /// Frames from one keyframe to the next; 0 makes every frame a keyframe.
+    pub gop_size: u32,
+}
+
+impl VideoWriterOptions {
+    fn to_raw(self) -> Result<by2_video_writer_options, By2Error> {
+        let too_big = |what: &str| By2Error::InvalidArgument(format!("{what} is too big"));
+        Ok(by2_video_writer_options {
+            codec: self.codec.to_raw(),
+            frame_rate: by2_rational {
+                num: self.frame_rate.0,
+                den: self.frame_rate.1,
+            },
+            bit_rate: self.bit_rate.try_into().map_err(|_| too_big("bit rate"))?,
+            gop_size: self.gop_size.try_into().map_err(|_| too_big("GOP size"))?,
+        })
+    }
+}
+
+impl Default for VideoWriterOptions {
+    fn default() -> Self {
+        let mut raw = by2_video_writer_options {
+            codec: 0,
+            frame_rate: by2_rational { num: 0, den: 1 },
+            bit_rate: 0,
+            gop_size: 0,
+        };
+        unsafe { by2_video_writer_default_options(&mut raw) };
+        Self {
+            codec: VideoCodec::from_raw(raw.codec)
+                .expect("by2_video_writer_default_options returns a known codec"),
+            frame_rate: (raw.frame_rate.num, raw.frame_rate.den),
+            bit_rate: raw.bit_rate.max(0) as u64,
+            gop_size: raw.gop_size.max(0) as u32,
+        }
+    }
+}
+
+/// Encodes frames and muxes them into a video file, whose container FFmpeg
+/// picks by extension: `.mp4`, `.mkv` or `.avi`, among others. Frame `i` is
+/// shown at `i / frame_rate` seconds.
+///
+/// [`VideoWriter::finish`] completes the file and reports what went wrong
+/// doing so; dropping the writer without it completes the file too, but
+/// ignores errors.
+#[derive(Debug)]
+pub struct VideoWriter {
+    raw: NonNull<by2_video_writer>,
+    width: u32,
+    height: u32,
+    format: PixelFormat,
+}
+
+// The encoder and muxer state is owned by the handle and only touched
+// through `&mut self`; nothing in it is tied to the thread that created it.
+unsafe impl Send for VideoWriter {}
+
+impl VideoWriter {
+    /// Creates `path` for `width` x `height` frames given in `format`.
+    ///
+    /// Fails with [`By2Error::InvalidArgument`] for bad options, an
+    /// extension FFmpeg doesn't know or a container that can't hold the
+    /// codec, and with [`By2Error::Io`] if the file can't be created.
+    pub fn create(
+        path: impl AsRef<Path>,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        options: &VideoWriterOptions,
+    ) -> Result<Self, By2Error> {
+        let path = c_path(path.as_ref())?;
+        let options = options.to_raw()?;
+        let size = |n: u32| {
+            i32::try_from(n)
+                .map_err(|_| By2Error::InvalidArgument(format!("frame size {width}x{height}")))
+        };
+        let mut raw = ptr::null_mut();
+        check_status(unsafe {
+            by2_video_writer_create(
+                path.as_ptr(),
+                size(width)?,
+                size(height)?,
+                format.to_raw(),
+                &options,
+                &mut raw,
+            )
+        })?;
+        let raw = NonNull::new(raw)
+            .ok_or_else(|| By2Error::Internal("by2_video_writer_create returned null".into()))?;
+        Ok(Self {
+            raw,
+            width,
+            height,
+            format,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    /// Bytes of one input frame.
+    pub fn frame_size(&self) -> usize {
+        self.format.frame_size(self.width, self.height)
+    }
+
+    /// Frames written so far.
+    pub fn frame_count(&self) -> u64 {
+        unsafe { by2_video_writer_frame_count(self.raw.as_ptr()) }.max(0) as u64
+    }
+
+    /// Encodes the next frame, tightly packed in the writer's format.
+    ///
+    /// Fails with [`By2Error::InvalidArgument`] if `data` isn't
+    /// [`VideoWriter::frame_size`] bytes, and with [`By2Error::Io`] if
+    /// encoding or writing fails.
+    pub fn write(&mut self, data: &[u8]) -> Result<(), By2Error> {
+        check_status(unsafe {
+            by2_video_writer_write(self.raw.as_ptr(), data.as_ptr(), data.len())
+        })
+    }
+
+    /// Encodes a decoded frame, which must have the writer's size and
+    /// format.
+    pub fn write_frame(&mut self, frame: &Frame) -> Result<(), By2Error> {
+        if (frame.width(), frame.height(), frame.format()) != (self.width, self.height, self.format)
+        {
+            return Err(By2Error::InvalidArgument(format!(
+                "a {}x{} {:?} frame for a {}x{} {:?} writer",
+                frame.width(),
+                frame.height(),
+                frame.format(),
+                self.width,
+                self.height,
+                self.format
+            )));
+        }
+        self.write(frame.data())
+    }
+
+    /// Encodes the frames the encoder still holds and completes the file.
+    pub fn finish(self) -> Result<(), By2Error> {
+        check_status(unsafe { by2_video_writer_finish(self.raw.as_ptr()) })
+    }
+}
+
+impl Drop for VideoWriter {
+    fn drop(&mut self) {
+        unsafe { by2_video_writer_destroy(self.raw.as_ptr()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::VideoReader;
+    use crate::media::tests::luma;
+    use crate::testing::TempDir;
+
+    /// Frame `i` of the RGB clips: every channel a different gradient.
+    fn rgb(i: usize, width: u32, height: u32) -> Vec<u8> {
+        let mut frame = Vec::new();
+        for y in 0..height as usize {
+            for x in 0..width as usize {
+                frame.extend([
+                    (x * 9 + i) as u8,
+                    (y * 11 + i * 3) as u8,
+                    (x * y + i * 5) as u8,
+                ]);
+            }
+        }
+        frame
+    }
+
+    /// Frame `i` of the YUV clips: [`luma`] and grey chroma.
+    fn yuv(i: usize, width: u32, height: u32) -> Vec<u8> {
+        let mut frame = luma(i, width, height);
+        frame.resize(PixelFormat::Yuv420p.frame_size(width, height), 128);
+        frame
+    }
+
+    #[test]
+    fn ffv1_round_trips_rgb_losslessly() {
+        let dir = TempDir::new("writer-ffv1");
+        let path = dir.join("clip.mkv");
+
+        let options = VideoWriterOptions {
+            codec: VideoCodec::Ffv1,
+            frame_rate: (30, 1),
+            ..Default::default()
+        };
+        let mut writer = VideoWriter::create(&path, 10, 6, PixelFormat::Rgb24, &options).unwrap();
+        assert_eq!(writer.frame_size(), 10 * 6 * 3);
+        for i in 0..4 {
+            writer.write(&rgb(i, 10, 6)).unwrap();
+        }
+        assert_eq!(writer.frame_count(), 4);
+        writer.finish().unwrap();
+
+        let reader = VideoReader::open(&path, PixelFormat::Rgb24).unwrap();
+        assert_eq!(reader.video_stream().codec, "ffv1");
+        assert!((reader.video_stream().frame_rate.unwrap() - 30.0).abs() < 1e-6);
+        let frames: Vec<Frame> = reader.map(Result::unwrap).collect();
+        assert_eq!(frames.len(), 4);
+        for (i, frame) in frames.iter().enumerate() {
+            assert_eq!(frame.data(), rgb(i, 10, 6));
+            assert!((frame.time().unwrap() - i as f64 / 30.0).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn mpeg4_keyframes_follow_the_gop() {
+        let dir = TempDir::new("writer-mpeg4");
+
+        let options = VideoWriterOptions {
+            gop_size: 5,
+            bit_rate: 400_000,
+            ..Default::default()
+        };
+        assert_eq!(options.codec, VideoCodec::Mpeg4);
+        for name in ["clip.mp4", "clip.avi"] {
+            let path = dir.join(name);
+            let mut writer =
+                VideoWriter::create(&path, 16, 16, PixelFormat::Yuv420p, &options).unwrap();
+            for i in 0..12 {
+                writer.write(&yuv(i, 16, 16)).unwrap();
+            }
+            writer.finish().unwrap();
+
+            let mut reader = VideoReader::open(&path, PixelFormat::Yuv420p).unwrap();
+            assert_eq!(reader.video_stream().codec, "mpeg4");
+            let index = reader.build_index().unwrap();
+            assert_eq!(index.frame_count(), 12, "{name}");
+            let keyframes: Vec<u64> = index.keyframes().iter().map(|k| k.index).collect();
+            for i in [0, 5, 10] {
+                assert!(keyframes.contains(&i), "{name}: {keyframes:?}");
+            }
+
+            // Lossy, but close.
+            for (i, frame) in reader.map(Result::unwrap).enumerate() {
+                let expected = luma(i, 16, 16);
+                let error: u32 = frame.planes()[0]
+                    .iter()
+                    .zip(&expected)
+                    .map(|(&a, &b)| a.abs_diff(b) as u32)
+                    .sum();
+                assert!(error < 4 * 256, "{name}: frame {i} is off by {error}");
+            }
+        }
+    }
+
+    #[test]
+    fn written_clips_seek_to_the_exact_frame() {
+        let dir = TempDir::new("writer-seek");
+        let path = dir.join("clip.mkv");
+
+        let options = VideoWriterOptions {
+            codec: VideoCodec::Ffv1,
+            gop_size: 4,
+            ..Default::default()
+        };
+        let mut writer = VideoWriter::create(&path, 6, 4, PixelFormat::Yuv420p, &options).unwrap();
+        for i in 0..10 {
+            writer.write(&yuv(i, 6, 4)).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let mut reader = VideoReader::open(&path, PixelFormat::Yuv420p).unwrap();
+        reader.build_index().unwrap();
+        for i in [6, 1, 9, 4] {
+            let frame = reader.frame_at(i).unwrap().unwrap();
+            assert_eq!(frame.index(), i);
+            assert_eq!(frame.data(), yuv(i as usize, 6, 4));
+        }
+    }
+
+    #[test]
+    fn frames_are_copied_from_a_reader() {
+        let dir = TempDir::new("writer-copy");
+        let source = dir.join("source.mkv");
+        let copy = dir.join("copy.mkv");
+
+        let options = VideoWriterOptions {
+            codec: VideoCodec::Ffv1,
+            ..Default::default()
+        };
+        let mut writer = VideoWriter::create(&source, 4, 4, PixelFormat::Rgba, &options).unwrap();
+        for i in 0..3 {
+            let mut frame = Vec::new();
+            for pixel in rgb(i, 4, 4).chunks(3) {
+                frame.extend(pixel);
+                frame.push(200);
+            }
+            writer.write(&frame).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let mut writer = VideoWriter::create(&copy, 4, 4, PixelFormat::Rgba, &options).unwrap();
+        for frame in VideoReader::open(&source, PixelFormat::Rgba).unwrap() {
+            writer.write_frame(&frame.unwrap()).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let a = VideoReader::open(&source, PixelFormat::Rgba).unwrap();
+        let b = VideoReader::open(&copy, PixelFormat::Rgba).unwrap();
+        let a: Vec<Vec<u8>> = a.map(|f| f.unwrap().into_data()).collect();
+        let b: Vec<Vec<u8>> = b.map(|f| f.unwrap().into_data()).collect();
+        assert_eq!(a.len(), 3);
+        assert_eq!(a, b);
+        assert!(a[0].chunks(4).all(|pixel| pixel[3] == 200));
+
+        let mut other = VideoWriter::create(&copy, 4, 4, PixelFormat::Rgb24, &options).unwrap();
+        let frame = VideoReader::open(&source, PixelFormat::Rgba)
+            .unwrap()
+            .read_frame()
+            .unwrap()
+            .unwrap();
+        assert!(matches!(
+            other.write_frame(&frame),
+            Err(By2Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn dropping_finishes_the_file() {
+        let dir = TempDir::new("writer-drop");
+        let path = dir.join("clip.avi");
+
+        let mut writer =
+            VideoWriter::create(&path, 8, 8, PixelFormat::Yuv420p, &Default::default()).unwrap();
+        for i in 0..3 {
+            writer.write(&yuv(i, 8, 8)).unwrap();
+        }
+        drop(writer);
+
+        let reader = VideoReader::open(&path, PixelFormat::Yuv420p).unwrap();
+        assert_eq!(reader.count(), 3);
+    }
+
+    #[test]
+    fn bad_writers_are_rejected() {
+        let dir = TempDir::new("writer-errors");
+        let ffv1 = VideoWriterOptions {
+            codec: VideoCodec::Ffv1,
+            ..Default::default()
+        };
+        let create = |name: &str, width, options: &VideoWriterOptions| {
+            VideoWriter::create(dir.join(name), width, 4, PixelFormat::Rgb24, options)
+        };
+
+        for result in [
+            create("clip.unknown", 4, &Default::default()),
+            create("clip.mp4", 4, &ffv1),
+            create("clip.mkv", 0, &Default::default()),
+            create(
+                "clip.mkv",
+                4,
+                &VideoWriterOptions {
+                    frame_rate: (0, 1),
+                    ..Default::default()
+                },
+            ),
+            create(
+                "clip.mkv",
+                4,
+                &VideoWriterOptions {
+                    bit_rate: u64::MAX,
+                    ..Default::default()
+                },
+            ),
+        ] {
+            assert!(
+                matches!(result, Err(By2Error::InvalidArgument(_))),
+                "{result:?}"
+            );
+        }
+        let result = create("missing/clip.mkv", 4, &Default::default());
+        assert!(matches!(result, Err(By2Error::Io(_))), "{result:?}");
+
+        let mut writer = create("clip.mkv", 4, &Default::default()).unwrap();
+        assert!(matches!(
+            writer.write(&[0; 10]),
+            Err(By2Error::InvalidArgument(_))
+        ));
+        assert_eq!(writer.frame_count(), 0);
+        writer.finish().unwrap();
+    }
+}
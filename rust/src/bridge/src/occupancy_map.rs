@@ -0,0 +1,873 @@
+//! Probabilistic 3D occupancy mapping on an octomap octree.
+//!
+//! [`OccupancyMap`] owns a `by2_octree` handle. Every voxel starts unknown;
+//! inserting a scan makes the voxels along each sensor ray more likely free
+//! and the voxel at its end point more likely occupied. Needs the `mapping`
+//! feature, which builds the C++ side with octomap.
+//!
+//! Planning queries walk the map without changing it: [`OccupancyMap::cast_ray`]
+//! and [`OccupancyMap::line_of_sight`] follow a ray voxel by voxel,
+//! [`OccupancyMap::nearest_occupied`] finds the closest obstacle, and
+//! [`OccupancyMap::leaves`] iterates over the leaves in a box, fetching them
+//! in batches into a fixed buffer.
+//!
+//! Maps are saved in octomap's `.bt` and `.ot` formats, to files or to bytes.
+//! Loading checks the data before octomap parses it, so corrupt or truncated
+//! input is an error rather than a crash.
+//!
+//! The occupied voxels also export as point clouds or triangle meshes in PLY
+//! or OBJ, for viewers that don't read octomap files; see
+//! [`OccupancyMap::export`].
+
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::ptr::{self, NonNull};
+
+use crate::bindings::{
+    BY2_OCTREE_BINARY, BY2_OCTREE_FULL, BY2_OCTREE_LEAVES_FREE, BY2_OCTREE_LEAVES_KNOWN,
+    BY2_OCTREE_LEAVES_OCCUPIED, BY2_OCTREE_RAY_CLEAR, BY2_OCTREE_RAY_OCCUPIED,
+    BY2_OCTREE_RAY_UNKNOWN, by2_octree, by2_octree_cast_ray, by2_octree_create,
+    by2_octree_deserialize, by2_octree_destroy, by2_octree_insert_point_cloud, by2_octree_leaf,
+    by2_octree_leaf_count, by2_octree_leaves, by2_octree_leaves_create, by2_octree_leaves_destroy,
+    by2_octree_leaves_next, by2_octree_line_of_sight, by2_octree_nearest_occupied,
+    by2_octree_occupancy, by2_octree_ray_hit, by2_octree_resolution, by2_octree_serialize,
+    by2_vec3,
+};
+use crate::error::{By2Error, check_status};
+use crate::point_cloud::PointCloudView;
+
+mod export;
+mod marching_cubes;
+
+pub use export::{Coloring, ExportFormat, ExportGeometry, ExportOptions, MeshStyle};
+
+/// Serialized map formats, as octomap writes them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OctreeFormat {
+    /// `.bt`: compact, keeps only whether each known voxel is free or
+    /// occupied. Loading it back sets voxels to the clamping bounds of the
+    /// sensor model (about 0.12 and 0.97).
+    Binary,
+    /// `.ot`: every voxel with its occupancy probability.
+    Full,
+}
+
+impl OctreeFormat {
+    fn to_raw(self) -> i32 {
+        let raw = match self {
+            OctreeFormat::Binary => BY2_OCTREE_BINARY,
+            OctreeFormat::Full => BY2_OCTREE_FULL,
+        };
+        raw as i32
+    }
+}
+
+/// Where [`OccupancyMap::cast_ray`] stopped.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RayHit {
+    /// At the occupied voxel with this center.
+    Occupied([f64; 3]),
+    /// At the unknown voxel with this center, unless unknown voxels are
+    /// ignored.
+    Unknown([f64; 3]),
+    /// Past `max_range` or the edge of the mappable volume, having crossed
+    /// only free (or ignored unknown) voxels.
+    Clear,
+}
+
+impl RayHit {
+    fn from_raw(raw: &by2_octree_ray_hit) -> Result<Self, By2Error> {
+        let voxel = [raw.voxel.x, raw.voxel.y, raw.voxel.z];
+        Ok(match raw.result {
+            r if r == BY2_OCTREE_RAY_OCCUPIED as i32 => RayHit::Occupied(voxel),
+            r if r == BY2_OCTREE_RAY_UNKNOWN as i32 => RayHit::Unknown(voxel),
+            r if r == BY2_OCTREE_RAY_CLEAR as i32 => RayHit::Clear,
+            r => return Err(By2Error::Internal(format!("unknown ray result {r}"))),
+        })
+    }
+}
+
+/// Which leaves [`OccupancyMap::leaves`] yields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeafFilter {
+    Occupied,
+    Free,
+    /// Occupied and free.
+    Known,
+}
+
+impl LeafFilter {
+    fn to_raw(self) -> i32 {
+        let raw = match self {
+            LeafFilter::Occupied => BY2_OCTREE_LEAVES_OCCUPIED,
+            LeafFilter::Free => BY2_OCTREE_LEAVES_FREE,
+            LeafFilter::Known => BY2_OCTREE_LEAVES_KNOWN,
+        };
+        raw as i32
+    }
+}
+
+/// A leaf of the map: a cube of edge length `size` (the resolution times a
+/// power of two) around `center`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OctreeLeaf {
+    pub center: [f64; 3],
+    pub size: f64,
+    pub probability: f64,
+}
+
+impl OctreeLeaf {
+    fn from_raw(raw: &by2_octree_leaf) -> Self {
+        Self {
+            center: [raw.center.x, raw.center.y, raw.center.z],
+            size: raw.size,
+            probability: raw.probability,
+        }
+    }
+}
+
+/// Voxel grid of occupancy probabilities. Coordinates are limited to
+/// [-32768, 32768) voxels along each axis.
+#[derive(Debug)]
+pub struct OccupancyMap {
+    raw: NonNull<by2_octree>,
+}
+
+// Queries only read the octree and are documented as safe to run
+// concurrently; insertion needs `&mut self`.
+unsafe impl Send for OccupancyMap {}
+unsafe impl Sync for OccupancyMap {}
+
+impl OccupancyMap {
+    /// An empty map with voxels of edge length `resolution`: positive, and at
+    /// most `f64::MAX / 65536` so the mappable volume stays finite.
+    pub fn new(resolution: f64) -> Result<Self, By2Error> {
+        let mut raw = ptr::null_mut();
+        check_status(unsafe { by2_octree_create(resolution, &mut raw) })?;
+        let raw = NonNull::new(raw)
+            .ok_or_else(|| By2Error::Internal("by2_octree_create returned null".into()))?;
+        Ok(Self { raw })
+    }
+
+    pub fn resolution(&self) -> f64 {
+        unsafe { by2_octree_resolution(self.raw.as_ptr()) }
+    }
+
+    /// Number of octree leaves. Children that all agree are merged into one
+    /// leaf, so this is not the number of known voxels.
+    pub fn leaf_count(&self) -> usize {
+        unsafe { by2_octree_leaf_count(self.raw.as_ptr()) }
+    }
+
+    /// Casts a ray from `origin` to every finite point of `cloud`. Rays
+    /// longer than `max_range` (positive, may be infinite) are cut at that
+    /// length and their end points are not marked occupied. The map is left
+    /// unchanged if `origin` or a (cut) end point is outside the mappable
+    /// volume.
+    pub fn insert_point_cloud(
+        &mut self,
+        cloud: &PointCloudView<'_>,
+        origin: [f64; 3],
+        max_range: f64,
+    ) -> Result<(), By2Error> {
+        check_status(unsafe {
+            by2_octree_insert_point_cloud(
+                self.raw.as_ptr(),
+                cloud.as_raw(),
+                &vec3(origin),
+                max_range,
+            )
+        })
+    }
+
+    /// Occupancy probability of the voxel containing `point`, or `None`
+    /// while it is unknown.
+    pub fn occupancy(&self, point: [f64; 3]) -> Result<Option<f64>, By2Error> {
+        let mut probability = 0.0;
+        let mut known = 0;
+        check_status(unsafe {
+            by2_octree_occupancy(
+                self.raw.as_ptr(),
+                &vec3(point),
+                &mut probability,
+                &mut known,
+            )
+        })?;
+        Ok((known != 0).then_some(probability))
+    }
+
+    /// Follows the ray from `origin` in `direction` up to `max_range`
+    /// (positive, may be infinite) voxel by voxel, starting with the voxel
+    /// holding `origin`, and stops at the first occupied voxel, or at the
+    /// first unknown one unless `ignore_unknown`.
+    pub fn cast_ray(
+        &self,
+        origin: [f64; 3],
+        direction: [f64; 3],
+        max_range: f64,
+        ignore_unknown: bool,
+    ) -> Result<RayHit, By2Error> {
+        let mut hit = by2_octree_ray_hit {
+            result: 0,
+            voxel: vec3([0.0; 3]),
+        };
+        check_status(unsafe {
+            by2_octree_cast_ray(
+                self.raw.as_ptr(),
+                &vec3(origin),
+                &vec3(direction),
+                max_range,
+                i32::from(ignore_unknown),
+                &mut hit,
+            )
+        })?;
+        RayHit::from_raw(&hit)
+    }
+
+    /// Whether every voxel the segment from `from` to `to` passes through,
+    /// the voxels holding its end points included, is free (or unknown, if
+    /// `ignore_unknown`).
+    pub fn line_of_sight(
+        &self,
+        from: [f64; 3],
+        to: [f64; 3],
+        ignore_unknown: bool,
+    ) -> Result<bool, By2Error> {
+        let mut visible = 0;
+        check_status(unsafe {
+            by2_octree_line_of_sight(
+                self.raw.as_ptr(),
+                &vec3(from),
+                &vec3(to),
+                i32::from(ignore_unknown),
+                &mut visible,
+            )
+        })?;
+        Ok(visible != 0)
+    }
+
+    /// The occupied leaf closest to `point` within `max_distance`
+    /// (non-negative, may be infinite), with its distance to `point`
+    /// measured to the leaf's cube (0 inside it).
+    pub fn nearest_occupied(
+        &self,
+        point: [f64; 3],
+        max_distance: f64,
+    ) -> Result<Option<(OctreeLeaf, f64)>, By2Error> {
+        let mut leaf = EMPTY_LEAF;
+        let mut distance = 0.0;
+        let mut found = 0;
+        check_status(unsafe {
+            by2_octree_nearest_occupied(
+                self.raw.as_ptr(),
+                &vec3(point),
+                max_distance,
+                &mut leaf,
+                &mut distance,
+                &mut found,
+            )
+        })?;
+        Ok((found != 0).then(|| (OctreeLeaf::from_raw(&leaf), distance)))
+    }
+
+    /// The leaves matching `filter` that intersect the box [`min`, `max`],
+    /// in octree order. Bounds may be infinite; the box is clipped to the
+    /// mappable volume. Fetching a batch of leaves can fail, hence the
+    /// `Result` items.
+    pub fn leaves(
+        &self,
+        min: [f64; 3],
+        max: [f64; 3],
+        filter: LeafFilter,
+    ) -> Result<Leaves<'_>, By2Error> {
+        let mut raw = ptr::null_mut();
+        check_status(unsafe {
+            by2_octree_leaves_create(
+                self.raw.as_ptr(),
+                &vec3(min),
+                &vec3(max),
+                filter.to_raw(),
+                &mut raw,
+            )
+        })?;
+        let raw = NonNull::new(raw)
+            .ok_or_else(|| By2Error::Internal("by2_octree_leaves_create returned null".into()))?;
+        Ok(Leaves {
+            raw,
+            batch: vec![EMPTY_LEAF; LEAF_BATCH].into_boxed_slice(),
+            len: 0,
+            next: 0,
+            exhausted: false,
+            _map: PhantomData,
+        })
+    }
+
+    /// The map as a file in `format` would hold it.
+    pub fn to_bytes(&self, format: OctreeFormat) -> Result<Vec<u8>, By2Error> {
+        let mut size = 0;
+        check_status(unsafe {
+            by2_octree_serialize(
+                self.raw.as_ptr(),
+                format.to_raw(),
+                ptr::null_mut(),
+                0,
+                &mut size,
+            )
+        })?;
+        let mut bytes = vec![0; size];
+        // `bytes` has room for the `size` bytes the first call reported.
+        check_status(unsafe {
+            by2_octree_serialize(
+                self.raw.as_ptr(),
+                format.to_raw(),
+                bytes.as_mut_ptr(),
+                bytes.len(),
+                &mut size,
+            )
+        })?;
+        bytes.truncate(size);
+        Ok(bytes)
+    }
+
+    /// Reads a map written in `format` by [`to_bytes`](Self::to_bytes) or by
+    /// octomap. Data in the other format, truncated or otherwise malformed is
+    /// an [`InvalidArgument`](By2Error::InvalidArgument) error.
+    pub fn from_bytes(bytes: &[u8], format: OctreeFormat) -> Result<Self, By2Error> {
+        let mut raw = ptr::null_mut();
+        check_status(unsafe {
+            by2_octree_deserialize(bytes.as_ptr(), bytes.len(), format.to_raw(), &mut raw)
+        })?;
+        let raw = NonNull::new(raw)
+            .ok_or_else(|| By2Error::Internal("by2_octree_deserialize returned null".into()))?;
+        Ok(Self { raw })
+    }
+
+    /// Writes a `.bt` file. See [`OctreeFormat::Binary`] for what it keeps.
+    pub fn save_binary(&self, path: impl AsRef<Path>) -> Result<(), By2Error> {
+        write(path.as_ref(), &self.to_bytes(OctreeFormat::Binary)?)
+    }
+
+    /// Reads a `.bt` file.
+    pub fn load_binary(path: impl AsRef<Path>) -> Result<Self, By2Error> {
+        Self::from_bytes(&read(path.as_ref())?, OctreeFormat::Binary)
+    }
+
+    /// Writes a `.ot` file, which keeps every occupancy probability.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), By2Error> {
+        write(path.as_ref(), &self.to_bytes(OctreeFormat::Full)?)
+    }
+
+    /// Reads a `.ot` file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, By2Error> {
+        Self::from_bytes(&read(path.as_ref())?, OctreeFormat::Full)
+    }
+}
+
+impl Drop for OccupancyMap {
+    fn drop(&mut self) {
+        unsafe { by2_octree_destroy(self.raw.as_ptr()) }
+    }
+}
+
+/// Leaves fetched per call into the C++ cursor.
+const LEAF_BATCH: usize = 256;
+
+const EMPTY_LEAF: by2_octree_leaf = by2_octree_leaf {
+    center: by2_vec3 {
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    },
+    size: 0.0,
+    probability: 0.0,
+};
+
+/// Iterator over the leaves in a box, from [`OccupancyMap::leaves`]. The
+/// map stays borrowed, and so unchanged, while it lives.
+///
+/// If the native cursor fails, the iterator yields that error once and then
+/// ends, so a truncated traversal can't pass for a complete one.
+#[derive(Debug)]
+pub struct Leaves<'a> {
+    raw: NonNull<by2_octree_leaves>,
+    batch: Box<[by2_octree_leaf]>,
+    len: usize,
+    next: usize,
+    exhausted: bool,
+    _map: PhantomData<&'a OccupancyMap>,
+}
+
+// The cursor only reads the map, which is Sync, and is itself used through
+// `&mut self`.
+unsafe impl Send for Leaves<'_> {}
+
+impl Iterator for Leaves<'_> {
+    type Item = Result<OctreeLeaf, By2Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next == self.len {
+            if self.exhausted {
+                return None;
+            }
+            let mut count = 0;
+            // `batch` has room for its length.
+            let status = check_status(unsafe {
+                by2_octree_leaves_next(
+                    self.raw.as_ptr(),
+                    self.batch.as_mut_ptr(),
+                    self.batch.len(),
+                    &mut count,
+                )
+            });
+            self.next = 0;
+            if let Err(e) = status {
+                self.len = 0;
+                self.exhausted = true;
+                return Some(Err(e));
+            }
+            self.len = count.min(self.batch.len());
+            self.exhausted = self.len < self.batch.len();
+        }
+        let leaf = self.batch[..self.len].get(self.next)?;
+        self.next += 1;
+        Some(Ok(OctreeLeaf::from_raw(leaf)))
+    }
+}
+
+impl FusedIterator for Leaves<'_> {}
+
+impl Drop for Leaves<'_> {
+    fn drop(&mut self) {
+        unsafe { by2_octree_leaves_destroy(self.raw.as_ptr()) }
+    }
+}
+
+fn vec3([x, y, z]: [f64; 3]) -> by2_vec3 {
+    by2_vec3 { x, y, z }
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, By2Error> {
+    std::fs::read(path).map_err(|e| By2Error::Io(format!("{}: {e}", path.display())))
+}
+
+fn write(path: &Path, bytes: &[u8]) -> Result<(), By2Error> {
+    std::fs::write(path, bytes).map_err(|e| By2Error::Io(format!("{}: {e}", path.display())))
+}
+
+#[cfg(test)]
+mod tests {
+    use rcore::point_cloud::PointCloud;
+
+    use super::*;
+    use crate::testing::TempDir;
+
+    /// A 1 x 1 wall of points at x = 2.05, centred on the x axis, plus a NaN
+    /// point.
+    fn wall() -> PointCloud {
+        let mut points = Vec::new();
+        for i in -5..5 {
+            for j in -5..5 {
+                points.push([2.05, i as f32 * 0.1 + 0.05, j as f32 * 0.1 + 0.05]);
+            }
+        }
+        points.push([f32::NAN, 0.0, 0.0]);
+        PointCloud::from_points(&points)
+    }
+
+    pub(super) fn scanned(max_range: f64) -> OccupancyMap {
+        let mut map = OccupancyMap::new(0.1).unwrap();
+        map.insert_point_cloud(&PointCloudView::from(&wall()), [0.05; 3], max_range)
+            .unwrap();
+        map
+    }
+
+    #[test]
+    fn new_map_is_unknown_everywhere() {
+        let map = OccupancyMap::new(0.25).unwrap();
+        assert_eq!(map.resolution(), 0.25);
+        assert_eq!(map.leaf_count(), 0);
+        assert_eq!(map.occupancy([1.0, -2.0, 3.0]).unwrap(), None);
+    }
+
+    #[test]
+    fn scan_marks_free_space_and_end_points() {
+        let map = scanned(f64::INFINITY);
+        assert!(map.leaf_count() > 0);
+        assert!(map.occupancy([2.05, 0.05, 0.05]).unwrap().unwrap() > 0.5);
+        assert!(map.occupancy([1.05, 0.05, 0.05]).unwrap().unwrap() < 0.5);
+        // Behind the wall and beside the rays.
+        assert_eq!(map.occupancy([3.05, 0.05, 0.05]).unwrap(), None);
+        assert_eq!(map.occupancy([1.05, 3.05, 0.05]).unwrap(), None);
+    }
+
+    #[test]
+    fn max_range_cuts_rays() {
+        let map = scanned(1.0);
+        assert!(map.occupancy([0.55, 0.05, 0.05]).unwrap().unwrap() < 0.5);
+        assert_eq!(map.occupancy([2.05, 0.05, 0.05]).unwrap(), None);
+    }
+
+    #[test]
+    fn repeated_scans_raise_confidence() {
+        let cloud = wall();
+        let mut map = OccupancyMap::new(0.1).unwrap();
+        let mut previous = 0.5;
+        for _ in 0..3 {
+            map.insert_point_cloud(&PointCloudView::from(&cloud), [0.05; 3], f64::INFINITY)
+                .unwrap();
+            let p = map.occupancy([2.05, 0.05, 0.05]).unwrap().unwrap();
+            assert!(p > previous);
+            previous = p;
+        }
+    }
+
+    #[test]
+    fn bad_inputs_are_rejected_without_changing_the_map() {
+        let cloud = wall();
+        let view = PointCloudView::from(&cloud);
+        // Maps about 1.6 units around the origin, less than the wall needs.
+        let mut map = OccupancyMap::new(0.00005).unwrap();
+        for (origin, max_range) in [
+            ([f64::NAN, 0.0, 0.0], f64::INFINITY),
+            ([0.0; 3], 0.0),
+            ([0.0; 3], f64::NAN),
+            ([10.0, 0.0, 0.0], f64::INFINITY),
+            ([0.0; 3], f64::INFINITY),
+        ] {
+            assert!(matches!(
+                map.insert_point_cloud(&view, origin, max_range),
+                Err(By2Error::InvalidArgument(_))
+            ));
+            assert_eq!(map.leaf_count(), 0);
+        }
+        assert!(matches!(
+            map.occupancy([0.0, f64::INFINITY, 0.0]),
+            Err(By2Error::InvalidArgument(_))
+        ));
+        assert_eq!(map.occupancy([10.0, 0.0, 0.0]).unwrap(), None);
+        // Cut rays stay inside.
+        map.insert_point_cloud(&view, [0.0; 3], 1.0).unwrap();
+        assert!(map.leaf_count() > 0);
+        assert!(matches!(
+            OccupancyMap::new(0.0),
+            Err(By2Error::InvalidArgument(_))
+        ));
+        for resolution in [f64::INFINITY, f64::MAX / 1000.0] {
+            assert!(matches!(
+                OccupancyMap::new(resolution),
+                Err(By2Error::InvalidArgument(_))
+            ));
+        }
+    }
+
+    fn assert_near(a: [f64; 3], b: [f64; 3]) {
+        assert!(
+            a.iter().zip(&b).all(|(x, y)| (x - y).abs() < 1e-9),
+            "{a:?} != {b:?}"
+        );
+    }
+
+    #[test]
+    fn rays_stop_at_the_wall_or_unknown_space() {
+        let map = scanned(f64::INFINITY);
+        let origin = [0.05; 3];
+        let RayHit::Occupied(voxel) = map
+            .cast_ray(origin, [1.0, 0.0, 0.0], f64::INFINITY, false)
+            .unwrap()
+        else {
+            panic!("missed the wall");
+        };
+        assert_near(voxel, [2.05, 0.05, 0.05]);
+        // Along the scanned ray to (2.05, 0.35, 0.05), scaled up. Its voxel
+        // boundary crossings never coincide.
+        let RayHit::Occupied(voxel) = map
+            .cast_ray(origin, [2e300, 3e299, 0.0], f64::INFINITY, false)
+            .unwrap()
+        else {
+            panic!("missed the wall");
+        };
+        assert_near(voxel, [2.05, 0.35, 0.05]);
+        assert_eq!(
+            map.cast_ray(origin, [1.0, 0.0, 0.0], 1.0, false).unwrap(),
+            RayHit::Clear
+        );
+        // Nothing was scanned behind the origin.
+        let RayHit::Unknown(voxel) = map.cast_ray(origin, [-1.0, 0.0, 0.0], 1.0, false).unwrap()
+        else {
+            panic!("expected unknown space");
+        };
+        assert_near(voxel, [-0.05, 0.05, 0.05]);
+        assert_eq!(
+            map.cast_ray(origin, [-1.0, 0.0, 0.0], 1.0, true).unwrap(),
+            RayHit::Clear
+        );
+        // Ignoring unknown voxels, a ray that misses everything runs to the
+        // edge of the volume.
+        assert_eq!(
+            map.cast_ray(origin, [-1.0, 0.0, 0.0], f64::INFINITY, true)
+                .unwrap(),
+            RayHit::Clear
+        );
+        // The origin voxel counts.
+        assert!(matches!(
+            map.cast_ray([2.05, 0.05, 0.05], [-1.0, 0.0, 0.0], 1.0, false),
+            Ok(RayHit::Occupied(_))
+        ));
+        for (origin, direction, max_range) in [
+            ([0.05; 3], [0.0; 3], 1.0),
+            ([0.05; 3], [f64::NAN, 1.0, 0.0], 1.0),
+            ([0.05; 3], [1.0, 0.0, 0.0], 0.0),
+            ([0.05; 3], [1.0, 0.0, 0.0], f64::NAN),
+            ([1e6, 0.0, 0.0], [1.0, 0.0, 0.0], 1.0),
+        ] {
+            assert!(matches!(
+                map.cast_ray(origin, direction, max_range, false),
+                Err(By2Error::InvalidArgument(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn line_of_sight_needs_free_voxels() {
+        let map = scanned(f64::INFINITY);
+        let origin = [0.05; 3];
+        // Partway along the scanned ray to (2.05, 0.35, 0.05).
+        assert!(
+            map.line_of_sight(origin, [1.55, 0.275, 0.05], false)
+                .unwrap()
+        );
+        assert!(map.line_of_sight(origin, origin, false).unwrap());
+        // Through the wall, and onto it.
+        assert!(
+            !map.line_of_sight(origin, [2.55, 0.05, 0.05], false)
+                .unwrap()
+        );
+        assert!(
+            !map.line_of_sight(origin, [2.05, 0.05, 0.05], false)
+                .unwrap()
+        );
+        // Beside the scanned rays.
+        assert!(
+            !map.line_of_sight(origin, [0.05, 1.55, 0.05], false)
+                .unwrap()
+        );
+        assert!(map.line_of_sight(origin, [0.05, 1.55, 0.05], true).unwrap());
+        assert!(matches!(
+            map.line_of_sight(origin, [0.05, 1e6, 0.05], true),
+            Err(By2Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn nearest_occupied_finds_the_wall() {
+        let map = scanned(f64::INFINITY);
+        let (leaf, distance) = map
+            .nearest_occupied([1.05, 0.05, 0.05], f64::INFINITY)
+            .unwrap()
+            .unwrap();
+        assert_near(leaf.center, [2.05, 0.05, 0.05]);
+        assert!((leaf.size - 0.1).abs() < 1e-12);
+        assert!(leaf.probability > 0.5);
+        assert!((distance - 0.95).abs() < 1e-9, "{distance}");
+        // Past the corner of the wall.
+        let (leaf, distance) = map
+            .nearest_occupied([1.0, 1.5, 0.05], 2.0)
+            .unwrap()
+            .unwrap();
+        assert_near(leaf.center, [2.05, 0.45, 0.05]);
+        assert!((distance - 1.0f64.hypot(1.0)).abs() < 1e-9, "{distance}");
+        assert_eq!(
+            map.nearest_occupied([2.07, 0.05, 0.05], 0.0)
+                .unwrap()
+                .unwrap()
+                .1,
+            0.0
+        );
+        assert_eq!(map.nearest_occupied([1.05, 0.05, 0.05], 0.9).unwrap(), None);
+        assert_eq!(
+            OccupancyMap::new(0.1)
+                .unwrap()
+                .nearest_occupied([0.0; 3], f64::INFINITY)
+                .unwrap(),
+            None
+        );
+        for (point, max_distance) in [
+            ([f64::NAN, 0.0, 0.0], 1.0),
+            ([0.0; 3], -1.0),
+            ([0.0; 3], f64::NAN),
+        ] {
+            assert!(matches!(
+                map.nearest_occupied(point, max_distance),
+                Err(By2Error::InvalidArgument(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn leaves_cover_the_known_voxels_in_a_box() {
+        let map = scanned(f64::INFINITY);
+        let everywhere = ([f64::NEG_INFINITY; 3], [f64::INFINITY; 3]);
+        let leaves = |filter| map.leaves(everywhere.0, everywhere.1, filter).unwrap();
+        // Spans several batches.
+        assert!(map.leaf_count() > LEAF_BATCH);
+        assert_eq!(leaves(LeafFilter::Known).count(), map.leaf_count());
+        let occupied: Vec<_> = leaves(LeafFilter::Occupied)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(occupied.len(), 100);
+        for leaf in &occupied {
+            assert!((leaf.center[0] - 2.05).abs() < 1e-9);
+            assert_eq!(map.occupancy(leaf.center).unwrap(), Some(leaf.probability));
+        }
+        let mut free = 0;
+        for leaf in leaves(LeafFilter::Free) {
+            let leaf = leaf.unwrap();
+            assert!(leaf.probability < 0.5);
+            assert_eq!(map.occupancy(leaf.center).unwrap(), Some(leaf.probability));
+            free += 1;
+        }
+        assert_eq!(occupied.len() + free, map.leaf_count());
+
+        // Three voxels of the wall along y, one along z.
+        let part = map
+            .leaves([0.0; 3], [3.0, 0.25, 0.05], LeafFilter::Occupied)
+            .unwrap();
+        assert_eq!(part.count(), 3);
+        let mut outside = map.leaves([1e9; 3], [2e9; 3], LeafFilter::Known).unwrap();
+        assert_eq!(outside.next(), None);
+        assert_eq!(outside.next(), None);
+        for (min, max) in [
+            ([1.0, 0.0, 0.0], [0.0; 3]),
+            ([f64::NAN, 0.0, 0.0], [1.0; 3]),
+        ] {
+            assert!(matches!(
+                map.leaves(min, max, LeafFilter::Known),
+                Err(By2Error::InvalidArgument(_))
+            ));
+        }
+    }
+
+    /// Voxel centres around the wall, the rays in front of it and the
+    /// unknown space behind it.
+    fn probes() -> Vec<[f64; 3]> {
+        let mut probes = Vec::new();
+        for i in 0..30 {
+            for j in -8..8 {
+                probes.push([i as f64 * 0.1 + 0.05, j as f64 * 0.1 + 0.05, 0.05]);
+            }
+        }
+        probes
+    }
+
+    #[test]
+    fn full_format_keeps_probabilities() {
+        let map = scanned(f64::INFINITY);
+        let bytes = map.to_bytes(OctreeFormat::Full).unwrap();
+        assert!(bytes.starts_with(b"# Octomap OcTree file"));
+        let loaded = OccupancyMap::from_bytes(&bytes, OctreeFormat::Full).unwrap();
+        assert_eq!(loaded.resolution(), map.resolution());
+        assert_eq!(loaded.leaf_count(), map.leaf_count());
+        for p in probes() {
+            assert_eq!(loaded.occupancy(p).unwrap(), map.occupancy(p).unwrap());
+        }
+    }
+
+    #[test]
+    fn binary_format_keeps_free_and_occupied() {
+        let map = scanned(f64::INFINITY);
+        let bytes = map.to_bytes(OctreeFormat::Binary).unwrap();
+        assert!(bytes.starts_with(b"# Octomap OcTree binary file"));
+        let loaded = OccupancyMap::from_bytes(&bytes, OctreeFormat::Binary).unwrap();
+        for p in probes() {
+            let occupied = |m: &OccupancyMap| m.occupancy(p).unwrap().map(|o| o > 0.5);
+            assert_eq!(occupied(&loaded), occupied(&map), "{p:?}");
+        }
+    }
+
+    #[test]
+    fn empty_maps_round_trip() {
+        // More digits than a stream prints by default.
+        let map = OccupancyMap::new(0.123456789).unwrap();
+        for format in [OctreeFormat::Binary, OctreeFormat::Full] {
+            let loaded = OccupancyMap::from_bytes(&map.to_bytes(format).unwrap(), format).unwrap();
+            assert_eq!(loaded.resolution(), 0.123456789);
+            assert_eq!(loaded.leaf_count(), 0);
+        }
+    }
+
+    #[test]
+    fn save_and_load_files() {
+        let dir = TempDir::new("occupancy-map");
+        let map = scanned(f64::INFINITY);
+        let probe = [2.05, 0.05, 0.05];
+
+        map.save_binary(dir.join("map.bt")).unwrap();
+        let loaded = OccupancyMap::load_binary(dir.join("map.bt")).unwrap();
+        assert!(loaded.occupancy(probe).unwrap().unwrap() > 0.5);
+        map.save(dir.join("map.ot")).unwrap();
+        let loaded = OccupancyMap::load(dir.join("map.ot")).unwrap();
+        assert_eq!(
+            loaded.occupancy(probe).unwrap(),
+            map.occupancy(probe).unwrap()
+        );
+
+        assert!(matches!(
+            OccupancyMap::load(dir.join("map.bt")),
+            Err(By2Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            OccupancyMap::load(dir.join("missing.ot")),
+            Err(By2Error::Io(_))
+        ));
+    }
+
+    #[test]
+    fn corrupt_data_is_rejected() {
+        let map = scanned(f64::INFINITY);
+        for format in [OctreeFormat::Binary, OctreeFormat::Full] {
+            let bytes = map.to_bytes(format).unwrap();
+            let rejects = |bytes: &[u8]| {
+                matches!(
+                    OccupancyMap::from_bytes(bytes, format),
+                    Err(By2Error::InvalidArgument(_))
+                )
+            };
+            for len in 0..bytes.len() {
+                assert!(rejects(&bytes[..len]), "{format:?} truncated to {len}");
+            }
+
+            let text = String::from_utf8_lossy(&bytes);
+            let data = text.find("data\n").unwrap() + 5;
+            let with_nodes = |header: &str, nodes: &[u8]| [header.as_bytes(), nodes].concat();
+            // Every node claims children, deeper than the tree allows.
+            let nested: &[u8] = match format {
+                OctreeFormat::Binary => &[0xff, 0xff],
+                OctreeFormat::Full => &[0, 0, 0, 0, 1],
+            };
+            assert!(rejects(&with_nodes(&text[..data], &nested.repeat(100))));
+            // The header's node count is off by one.
+            let size = text.lines().find(|l| l.starts_with("size ")).unwrap();
+            let count: usize = size[5..].parse().unwrap();
+            let header = text[..data].replacen(size, &format!("size {}", count + 1), 1);
+            assert!(rejects(&with_nodes(&header, &bytes[data..])));
+            assert!(!rejects(&with_nodes(&text[..data], &bytes[data..])));
+        }
+        assert!(rejects_header(
+            b"# Octomap OcTree binary file\nid ColorOcTree\nsize 0\nres 0.1\ndata\n"
+        ));
+        assert!(rejects_header(
+            b"# Octomap OcTree binary file\nid OcTree\nsize 0\nres -1\ndata\n"
+        ));
+        assert!(rejects_header(
+            b"# Octomap OcTree binary file\nid OcTree\nres 0.1\ndata\n"
+        ));
+    }
+
+    fn rejects_header(bytes: &[u8]) -> bool {
+        matches!(
+            OccupancyMap::from_bytes(bytes, OctreeFormat::Binary),
+            Err(By2Error::InvalidArgument(_))
+        )
+    }
+}
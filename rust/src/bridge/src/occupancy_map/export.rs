@@ -0,0 +1,638 @@
+//! Occupied voxels as point clouds and triangle meshes, in PLY or OBJ.
+//!
+//! Points are the centres of the occupied leaves. Meshes are built on the
+//! finest voxels, so coarse leaves are split up first: either as the
+//! exposed faces of each voxel cube, or as a marching cubes surface through
+//! the voxel centres, which is smoother but shrinks thin walls.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
+
+use rcore::mesh::{self, TriangleMesh};
+use rcore::point_cloud::{PointCloud, ply};
+
+use super::marching_cubes::{self, EDGES, corner};
+use super::{LeafFilter, OccupancyMap, write};
+use crate::error::By2Error;
+
+/// Meshes are refused beyond this many finest voxels.
+const MAX_MESH_VOXELS: u64 = 1 << 24;
+
+/// Per-vertex colours of an export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coloring {
+    None,
+    /// From blue at the lowest to red at the highest `z`.
+    Height,
+    /// From blue at probability 0.5 to red at 1.
+    Probability,
+}
+
+/// How [`OccupancyMap::occupied_mesh`] turns voxels into triangles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeshStyle {
+    /// The faces of each voxel cube that don't touch another occupied
+    /// voxel, as independent quads: every face has its own four vertices,
+    /// coloured after its voxel, so the faces enclose the voxels without
+    /// forming a watertight or manifold mesh.
+    Cubes,
+    /// A surface through the midpoints between occupied and free voxel
+    /// centres.
+    MarchingCubes,
+}
+
+/// What [`OccupancyMap::export`] writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportGeometry {
+    Points,
+    Mesh(MeshStyle),
+}
+
+/// File formats of [`OccupancyMap::export`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Binary little endian. Points carry their probability as `intensity`.
+    Ply,
+    /// Text. Points are `v` lines without faces.
+    Obj,
+}
+
+impl ExportFormat {
+    fn from_path(path: &Path) -> Result<Self, By2Error> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("ply") => Ok(ExportFormat::Ply),
+            Some("obj") => Ok(ExportFormat::Obj),
+            _ => Err(By2Error::InvalidArgument(format!(
+                "{} is neither a .ply nor an .obj file",
+                path.display()
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExportOptions {
+    pub geometry: ExportGeometry,
+    pub coloring: Coloring,
+}
+
+/// Vertices in map coordinates, each with the probability of the voxel it
+/// belongs to.
+#[derive(Debug, Default)]
+struct Surface {
+    vertices: Vec<[f64; 3]>,
+    probabilities: Vec<f64>,
+    triangles: Vec<[u32; 3]>,
+}
+
+impl Surface {
+    fn push_vertex(&mut self, position: [f64; 3], probability: f64) -> u32 {
+        self.vertices.push(position);
+        self.probabilities.push(probability);
+        (self.vertices.len() - 1) as u32
+    }
+}
+
+impl OccupancyMap {
+    /// One point per occupied leaf, at its centre, with its occupancy
+    /// probability as intensity.
+    pub fn occupied_points(&self, coloring: Coloring) -> Result<PointCloud, By2Error> {
+        let (centers, probabilities): (Vec<_>, Vec<_>) = self
+            .occupied_leaves()?
+            .map(|leaf| leaf.map(|leaf| (leaf.center, leaf.probability)))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .unzip();
+        let mut cloud = PointCloud::from_points(
+            &centers
+                .iter()
+                .map(|c| c.map(|v| v as f32))
+                .collect::<Vec<_>>(),
+        );
+        cloud
+            .set_intensity(probabilities.iter().map(|&p| p as f32).collect())
+            .expect("one probability per point");
+        if let Some(colors) = colors(coloring, &centers, &probabilities) {
+            let column = |c: usize| colors.iter().map(|rgb| rgb[c]).collect();
+            cloud
+                .set_colors(column(0), column(1), column(2))
+                .expect("one colour per point");
+        }
+        Ok(cloud)
+    }
+
+    /// Triangles around the occupied voxels, facing outwards. Only
+    /// [`MeshStyle::MarchingCubes`] shares vertices between neighbouring
+    /// triangles; see [`MeshStyle::Cubes`] for how the cube faces are laid
+    /// out. Fails with [`By2Error::InvalidArgument`] when the occupied leaves
+    /// hold more than 2^24 voxels of the map's resolution.
+    pub fn occupied_mesh(
+        &self,
+        style: MeshStyle,
+        coloring: Coloring,
+    ) -> Result<TriangleMesh, By2Error> {
+        let voxels = self.occupied_voxels()?;
+        let surface = match style {
+            MeshStyle::Cubes => cubes(&voxels, self.resolution()),
+            MeshStyle::MarchingCubes => marching_cubes(&voxels, self.resolution()),
+        };
+        let mut mesh = TriangleMesh::from_parts(
+            surface
+                .vertices
+                .iter()
+                .map(|v| v.map(|c| c as f32))
+                .collect(),
+            surface.triangles,
+        )
+        .expect("triangles refer to pushed vertices");
+        if let Some(colors) = colors(coloring, &surface.vertices, &surface.probabilities) {
+            mesh.set_colors(colors).expect("one colour per vertex");
+        }
+        Ok(mesh)
+    }
+
+    /// The occupied voxels as a file in `format` would hold them.
+    pub fn export(
+        &self,
+        options: &ExportOptions,
+        format: ExportFormat,
+    ) -> Result<Vec<u8>, By2Error> {
+        let mut bytes = Vec::new();
+        let result = match (options.geometry, format) {
+            (ExportGeometry::Points, ExportFormat::Ply) => {
+                let cloud = self.occupied_points(options.coloring)?;
+                ply::write(&cloud, &mut bytes, ply::Encoding::BinaryLittleEndian)
+                    .map_err(|e| e.to_string())
+            }
+            (ExportGeometry::Points, ExportFormat::Obj) => {
+                let cloud = self.occupied_points(options.coloring)?;
+                let mut points = TriangleMesh::from_parts(cloud.points().collect(), Vec::new())
+                    .expect("no triangles");
+                if let Some([r, g, b]) = cloud.colors() {
+                    points
+                        .set_colors((0..cloud.len()).map(|i| [r[i], g[i], b[i]]).collect())
+                        .expect("one colour per point");
+                }
+                mesh::obj::write(&points, &mut bytes).map_err(|e| e.to_string())
+            }
+            (ExportGeometry::Mesh(style), format) => {
+                let mesh = self.occupied_mesh(style, options.coloring)?;
+                match format {
+                    ExportFormat::Ply => {
+                        mesh::ply::write(&mesh, &mut bytes, mesh::ply::Encoding::BinaryLittleEndian)
+                    }
+                    ExportFormat::Obj => mesh::obj::write(&mesh, &mut bytes),
+                }
+                .map_err(|e| e.to_string())
+            }
+        };
+        result.map_err(By2Error::Io)?;
+        Ok(bytes)
+    }
+
+    /// Writes [`OccupancyMap::export`] to a `.ply` or `.obj` file, picked by
+    /// extension.
+    pub fn export_to_file(
+        &self,
+        path: impl AsRef<Path>,
+        options: &ExportOptions,
+    ) -> Result<(), By2Error> {
+        let path = path.as_ref();
+        let format = ExportFormat::from_path(path)?;
+        write(path, &self.export(options, format)?)
+    }
+
+    fn occupied_leaves(&self) -> Result<super::Leaves<'_>, By2Error> {
+        self.leaves(
+            [f64::NEG_INFINITY; 3],
+            [f64::INFINITY; 3],
+            LeafFilter::Occupied,
+        )
+    }
+
+    /// The occupied leaves split into voxels of the map's resolution, keyed
+    /// by their integer grid position.
+    fn occupied_voxels(&self) -> Result<BTreeMap<[i64; 3], f64>, By2Error> {
+        let resolution = self.resolution();
+        let mut leaves = Vec::new();
+        let mut count = 0u64;
+        for leaf in self.occupied_leaves()? {
+            let leaf = leaf?;
+            let side = (leaf.size / resolution).round() as u64;
+            count = count.saturating_add(side.saturating_pow(3));
+            if count > MAX_MESH_VOXELS {
+                return Err(By2Error::InvalidArgument(format!(
+                    "more than {MAX_MESH_VOXELS} occupied voxels to mesh"
+                )));
+            }
+            leaves.push((leaf, side as i64));
+        }
+
+        let mut voxels = BTreeMap::new();
+        for (leaf, side) in leaves {
+            let low = leaf
+                .center
+                .map(|c| ((c - leaf.size / 2.0) / resolution).round() as i64);
+            for i in 0..side {
+                for j in 0..side {
+                    for k in 0..side {
+                        voxels.insert([low[0] + i, low[1] + j, low[2] + k], leaf.probability);
+                    }
+                }
+            }
+        }
+        Ok(voxels)
+    }
+}
+
+fn offset(voxel: [i64; 3], by: [i64; 3]) -> [i64; 3] {
+    [voxel[0] + by[0], voxel[1] + by[1], voxel[2] + by[2]]
+}
+
+/// One quad per voxel face that no other occupied voxel covers.
+fn cubes(voxels: &BTreeMap<[i64; 3], f64>, resolution: f64) -> Surface {
+    let mut surface = Surface::default();
+    for (&voxel, &probability) in voxels {
+        for (f, ring) in marching_cubes::faces().enumerate() {
+            let mut step = [0; 3];
+            step[f / 2] = if f % 2 == 0 { -1 } else { 1 };
+            if voxels.contains_key(&offset(voxel, step)) {
+                continue;
+            }
+            let quad = ring.map(|c| {
+                let position = offset(voxel, corner(c)).map(|v| v as f64 * resolution);
+                surface.push_vertex(position, probability)
+            });
+            surface.triangles.push([quad[0], quad[1], quad[2]]);
+            surface.triangles.push([quad[0], quad[2], quad[3]]);
+        }
+    }
+    surface
+}
+
+/// Marching cubes over the lattice of voxel centres, occupied ones inside.
+/// Vertices sit halfway between an occupied and a free centre and take the
+/// occupied voxel's probability.
+fn marching_cubes(voxels: &BTreeMap<[i64; 3], f64>, resolution: f64) -> Surface {
+    let cells: BTreeSet<[i64; 3]> = voxels
+        .keys()
+        .flat_map(|&voxel| (0..8).map(move |c| offset(voxel, corner(c).map(|v| -v))))
+        .collect();
+
+    let mut surface = Surface::default();
+    // Welds the vertices of neighbouring cells: (lower voxel, axis) of the
+    // lattice edge each one sits on.
+    let mut welded: HashMap<([i64; 3], usize), u32> = HashMap::new();
+    for cell in cells {
+        let case = (0..8)
+            .filter(|&c| voxels.contains_key(&offset(cell, corner(c))))
+            .fold(0u8, |case, c| case | 1 << c);
+        for triangle in marching_cubes::triangles(case) {
+            let triangle = triangle.map(|e| {
+                let (c, axis) = EDGES[e];
+                let low = offset(cell, corner(c));
+                *welded.entry((low, axis)).or_insert_with(|| {
+                    let mut high = low;
+                    high[axis] += 1;
+                    let probability = voxels.get(&low).or(voxels.get(&high));
+                    let mut position = low.map(|v| (v as f64 + 0.5) * resolution);
+                    position[axis] += 0.5 * resolution;
+                    surface.push_vertex(position, *probability.expect("edge crosses the surface"))
+                })
+            });
+            surface.triangles.push(triangle);
+        }
+    }
+    surface
+}
+
+fn colors(
+    coloring: Coloring,
+    positions: &[[f64; 3]],
+    probabilities: &[f64],
+) -> Option<Vec<[u8; 3]>> {
+    match coloring {
+        Coloring::None => None,
+        Coloring::Height => {
+            let (low, high) = positions
+                .iter()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), p| {
+                    (low.min(p[2]), high.max(p[2]))
+                });
+            let range = high - low;
+            Some(
+                positions
+                    .iter()
+                    .map(|p| {
+                        ramp(if range > 0.0 {
+                            (p[2] - low) / range
+                        } else {
+                            0.0
+                        })
+                    })
+                    .collect(),
+            )
+        }
+        Coloring::Probability => Some(
+            probabilities
+                .iter()
+                .map(|p| ramp((p - 0.5) / 0.5))
+                .collect(),
+        ),
+    }
+}
+
+/// Blue, cyan, green, yellow, red for `t` from 0 to 1.
+fn ramp(t: f64) -> [u8; 3] {
+    const STOPS: [[f64; 3]; 5] = [
+        [0.0, 0.0, 255.0],
+        [0.0, 255.0, 255.0],
+        [0.0, 255.0, 0.0],
+        [255.0, 255.0, 0.0],
+        [255.0, 0.0, 0.0],
+    ];
+    let x = t.clamp(0.0, 1.0) * 4.0;
+    let i = (x as usize).min(3);
+    let f = x - i as f64;
+    [0, 1, 2].map(|c| (STOPS[i][c] + f * (STOPS[i + 1][c] - STOPS[i][c])).round() as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rcore::point_cloud::PointCloud;
+
+    use super::super::tests::scanned;
+    use super::*;
+    use crate::testing::TempDir;
+
+    fn voxels(positions: &[[i64; 3]]) -> BTreeMap<[i64; 3], f64> {
+        positions.iter().map(|&p| (p, 0.9)).collect()
+    }
+
+    fn mesh(surface: Surface) -> TriangleMesh {
+        TriangleMesh::from_parts(
+            surface
+                .vertices
+                .iter()
+                .map(|v| v.map(|c| c as f32))
+                .collect(),
+            surface.triangles,
+        )
+        .unwrap()
+    }
+
+    /// Every edge between two vertex positions is used as often in one
+    /// direction as in the other, so the surface is closed and consistently
+    /// oriented (cube meshes don't share vertices between faces).
+    fn assert_closed(mesh: &TriangleMesh) {
+        let key = |i: u32| mesh.vertices()[i as usize].map(f32::to_bits);
+        let mut edges: HashMap<_, i32> = HashMap::new();
+        for &[a, b, c] in mesh.triangles() {
+            for (from, to) in [(a, b), (b, c), (c, a)] {
+                *edges.entry((key(from), key(to))).or_default() += 1;
+                *edges.entry((key(to), key(from))).or_default() -= 1;
+            }
+        }
+        assert!(edges.values().all(|&n| n == 0), "the surface is open");
+        let used: HashSet<u32> = mesh.triangles().iter().flatten().copied().collect();
+        assert_eq!(used.len(), mesh.vertices().len());
+    }
+
+    #[test]
+    fn a_single_voxel() {
+        let voxel = voxels(&[[3, -2, 7]]);
+        let cube = mesh(cubes(&voxel, 0.5));
+        assert_closed(&cube);
+        assert_eq!(cube.triangles().len(), 12);
+        assert!((cube.signed_volume() - 0.125).abs() < 1e-9);
+
+        // An octahedron around the voxel centre.
+        let octahedron = mesh(marching_cubes(&voxel, 0.5));
+        assert_closed(&octahedron);
+        assert_eq!(octahedron.vertices().len(), 6);
+        assert_eq!(octahedron.triangles().len(), 8);
+        assert!((octahedron.signed_volume() - 0.125 / 6.0).abs() < 1e-9);
+        let mut center = [0.0; 3];
+        for v in octahedron.vertices() {
+            for axis in 0..3 {
+                center[axis] += f64::from(v[axis]) / 6.0;
+            }
+        }
+        for (c, expected) in center.into_iter().zip([1.75, -0.75, 3.75]) {
+            assert!((c - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn every_cell_configuration_is_closed() {
+        for case in 0..=255u8 {
+            let positions: Vec<_> = (0..8).filter(|&c| case >> c & 1 == 1).map(corner).collect();
+            let voxels = voxels(&positions);
+            for (style, surface) in [
+                ("cubes", cubes(&voxels, 1.0)),
+                ("marching cubes", marching_cubes(&voxels, 1.0)),
+            ] {
+                let mesh = mesh(surface);
+                assert_closed(&mesh);
+                let volume = mesh.signed_volume();
+                assert!(
+                    (volume > 0.0) == (case != 0),
+                    "{style} of {case:#010b}: volume {volume}"
+                );
+                if style == "cubes" {
+                    assert!((volume - positions.len() as f64).abs() < 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn a_solid_block_meshes_as_its_outside() {
+        let mut positions = Vec::new();
+        for i in 0..3 {
+            for j in 0..3 {
+                for k in 0..3 {
+                    positions.push([i, j, k]);
+                }
+            }
+        }
+        let block = voxels(&positions);
+        let cube = mesh(cubes(&block, 1.0));
+        assert_closed(&cube);
+        assert_eq!(cube.triangles().len(), 6 * 9 * 2);
+        assert!((cube.signed_volume() - 27.0).abs() < 1e-9);
+        let smooth = mesh(marching_cubes(&block, 1.0));
+        assert_closed(&smooth);
+        // The 3 x 3 x 3 box through the outer voxel centres, widened by
+        // half a voxel on each face and bevelled at its edges and corners.
+        let volume = smooth.signed_volume();
+        assert!(8.0 < volume && volume < 27.0, "{volume}");
+    }
+
+    #[test]
+    fn points_are_the_occupied_leaves() {
+        let map = scanned(10.0);
+        let cloud = map.occupied_points(Coloring::Probability).unwrap();
+        assert_eq!(cloud.len(), 100);
+        for (i, point) in cloud.points().enumerate() {
+            assert!((point[0] - 2.05).abs() < 1e-6);
+            let probability = cloud.intensity().unwrap()[i];
+            assert!(probability > 0.5);
+            let expected = map.occupancy(point.map(f64::from)).unwrap().unwrap();
+            assert!((f64::from(probability) - expected).abs() < 1e-6);
+        }
+        let [r, _, b] = cloud.colors().unwrap();
+        assert!(r.iter().zip(b).all(|(r, b)| r + b > 0));
+
+        let plain = map.occupied_points(Coloring::None).unwrap();
+        assert!(plain.colors().is_none());
+        assert!(
+            OccupancyMap::new(0.1)
+                .unwrap()
+                .occupied_points(Coloring::Height)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn meshes_wrap_the_wall() {
+        let map = scanned(10.0);
+        let cube = map.occupied_mesh(MeshStyle::Cubes, Coloring::None).unwrap();
+        assert_closed(&cube);
+        assert!((cube.signed_volume() - 100.0 * 0.001).abs() < 1e-6);
+        assert!(cube.colors().is_none());
+
+        let smooth = map
+            .occupied_mesh(MeshStyle::MarchingCubes, Coloring::Height)
+            .unwrap();
+        assert_closed(&smooth);
+        assert!(smooth.signed_volume() > 0.0);
+        let colors = smooth.colors().unwrap();
+        let (low, high) =
+            smooth
+                .vertices()
+                .iter()
+                .zip(colors)
+                .fold((None, None), |(low, high), (v, &c)| {
+                    let pick =
+                        |best: Option<(f32, [u8; 3])>, better: fn(f32, f32) -> bool| match best {
+                            Some((z, _)) if !better(v[2], z) => best,
+                            _ => Some((v[2], c)),
+                        };
+                    (pick(low, |a, b| a < b), pick(high, |a, b| a > b))
+                });
+        assert_eq!(low.unwrap().1, [0, 0, 255]);
+        assert_eq!(high.unwrap().1, [255, 0, 0]);
+    }
+
+    #[test]
+    fn exports_parse_back() {
+        let map = scanned(10.0);
+        let points = ExportOptions {
+            geometry: ExportGeometry::Points,
+            coloring: Coloring::Height,
+        };
+        let bytes = map.export(&points, ExportFormat::Ply).unwrap();
+        let cloud = ply::read(bytes.as_slice()).unwrap();
+        assert_eq!(cloud, map.occupied_points(Coloring::Height).unwrap());
+        let text = String::from_utf8(map.export(&points, ExportFormat::Obj).unwrap()).unwrap();
+        assert_eq!(text.lines().filter(|l| l.starts_with("v ")).count(), 100);
+        assert!(!text.contains("\nf "));
+
+        let cubes = ExportOptions {
+            geometry: ExportGeometry::Mesh(MeshStyle::Cubes),
+            coloring: Coloring::Probability,
+        };
+        let mesh = map
+            .occupied_mesh(MeshStyle::Cubes, Coloring::Probability)
+            .unwrap();
+        let bytes = map.export(&cubes, ExportFormat::Ply).unwrap();
+        let vertices: PointCloud = ply::read(bytes.as_slice()).unwrap();
+        assert_eq!(vertices.len(), mesh.vertices().len());
+        let text = String::from_utf8(map.export(&cubes, ExportFormat::Obj).unwrap()).unwrap();
+        assert_eq!(
+            text.lines().filter(|l| l.starts_with("f ")).count(),
+            mesh.triangles().len()
+        );
+    }
+
+    #[test]
+    fn export_files_by_extension() {
+        let map = scanned(10.0);
+        let options = ExportOptions {
+            geometry: ExportGeometry::Mesh(MeshStyle::MarchingCubes),
+            coloring: Coloring::None,
+        };
+        let dir = TempDir::new("map-export");
+        for (name, format) in [
+            ("map.ply", ExportFormat::Ply),
+            ("map.OBJ", ExportFormat::Obj),
+        ] {
+            let path = dir.join(name);
+            map.export_to_file(&path, &options).unwrap();
+            assert_eq!(
+                std::fs::read(&path).unwrap(),
+                map.export(&options, format).unwrap()
+            );
+        }
+        assert!(matches!(
+            map.export_to_file(dir.join("map.stl"), &options),
+            Err(By2Error::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            map.export_to_file(dir.join("missing").join("map.ply"), &options),
+            Err(By2Error::Io(_))
+        ));
+    }
+
+    #[test]
+    fn coarse_leaves_are_split_into_voxels() {
+        // Eight occupied siblings prune into one leaf of twice the size.
+        let mut map = OccupancyMap::new(0.5).unwrap();
+        let mut points = Vec::new();
+        for i in 0..2 {
+            for j in 0..2 {
+                for k in 0..2 {
+                    points.push([
+                        i as f32 * 0.5 + 0.25,
+                        j as f32 * 0.5 + 0.25,
+                        k as f32 * 0.5 + 0.25,
+                    ]);
+                }
+            }
+        }
+        let cloud = PointCloud::from_points(&points);
+        for _ in 0..20 {
+            map.insert_point_cloud(
+                &crate::PointCloudView::from(&cloud),
+                [5.25; 3],
+                f64::INFINITY,
+            )
+            .unwrap();
+        }
+        let points = map.occupied_points(Coloring::None).unwrap();
+        let mesh = map.occupied_mesh(MeshStyle::Cubes, Coloring::None).unwrap();
+        assert_closed(&mesh);
+        assert!((mesh.signed_volume() - 1.0).abs() < 1e-9);
+        assert_eq!(points.len(), 1);
+        assert_eq!(points.point(0), Some([0.5; 3]));
+        assert_eq!(mesh.triangles().len(), 6 * 4 * 2);
+    }
+
+    #[test]
+    fn ramp_runs_blue_to_red() {
+        assert_eq!(ramp(-1.0), [0, 0, 255]);
+        assert_eq!(ramp(0.25), [0, 255, 255]);
+        assert_eq!(ramp(0.5), [0, 255, 0]);
+        assert_eq!(ramp(0.75), [255, 255, 0]);
+        assert_eq!(ramp(1.0), [255, 0, 0]);
+        assert_eq!(ramp(2.0), [255, 0, 0]);
+    }
+}
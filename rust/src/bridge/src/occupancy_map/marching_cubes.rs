@@ -0,0 +1,144 @@
+//! The marching cubes case table.
+//!
+//! Each of the 256 ways to mark the corners of a cube inside or outside
+//! gets its triangles derived from the cube's faces instead of a hand-typed
+//! table: every face crossed by the surface holds segments from the edge
+//! where its boundary leaves the inside corners to the edge where it
+//! entered them, which keeps the inside corners of an ambiguous face
+//! apart. The segments chain into loops around the cube, and each loop is
+//! fanned into triangles. Neighbouring cubes see a shared face the same
+//! way, so the surface over a grid of cubes is closed.
+
+use std::sync::OnceLock;
+
+/// Corner `i` of a cube sits at offset `(i & 1, i >> 1 & 1, i >> 2 & 1)`.
+pub(super) fn corner(i: usize) -> [i64; 3] {
+    [i & 1, i >> 1 & 1, i >> 2 & 1].map(|c| c as i64)
+}
+
+/// Edge `e` runs from corner `EDGES[e].0` one step along axis `EDGES[e].1`.
+pub(super) const EDGES: [(usize, usize); 12] = [
+    (0, 0),
+    (2, 0),
+    (4, 0),
+    (6, 0),
+    (0, 1),
+    (1, 1),
+    (4, 1),
+    (5, 1),
+    (0, 2),
+    (1, 2),
+    (2, 2),
+    (3, 2),
+];
+
+/// Triangles, as edge triples, for the cube whose inside corners are the
+/// set bits of `case`. They face away from the inside corners.
+pub(super) fn triangles(case: u8) -> &'static [[usize; 3]] {
+    static TABLE: OnceLock<Vec<Vec<[usize; 3]>>> = OnceLock::new();
+    &TABLE.get_or_init(|| (0..=255).map(triangulate).collect())[case as usize]
+}
+
+fn edge_between(a: usize, b: usize) -> usize {
+    let axis = (a ^ b).trailing_zeros() as usize;
+    EDGES
+        .iter()
+        .position(|&edge| edge == (a.min(b), axis))
+        .expect("corners share an edge")
+}
+
+/// The six faces, corners listed counter-clockwise as seen from outside.
+/// Face `f` lies across axis `f / 2`, on its low side for even `f`.
+pub(super) fn faces() -> impl Iterator<Item = [usize; 4]> {
+    (0..3).flat_map(|axis| {
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+        [0, 1].map(|side| {
+            let at = |du: usize, dv: usize| side << axis | du << u | dv << v;
+            // Counter-clockwise around +axis; the low face looks along -axis.
+            let ring = [at(0, 0), at(1, 0), at(1, 1), at(0, 1)];
+            if side == 1 {
+                ring
+            } else {
+                [ring[0], ring[3], ring[2], ring[1]]
+            }
+        })
+    })
+}
+
+fn triangulate(case: u8) -> Vec<[usize; 3]> {
+    let inside = |c: usize| case >> c & 1 == 1;
+    // next[e]: where the contour goes from the crossing on edge e.
+    let mut next = [None; 12];
+    for face in faces() {
+        let side = |i: usize| (face[i % 4], face[(i + 1) % 4]);
+        let enters = |(a, b): (usize, usize)| !inside(a) && inside(b);
+        for i in 0..4 {
+            let (a, b) = side(i);
+            if !inside(a) || inside(b) {
+                continue;
+            }
+            // Leaving the inside corners; back to where this run of them
+            // was entered.
+            let mut j = i + 3;
+            while !enters(side(j)) {
+                j += 3;
+            }
+            let (c, d) = side(j);
+            next[edge_between(a, b)] = Some(edge_between(c, d));
+        }
+    }
+
+    let mut triangles = Vec::new();
+    let mut seen = [false; 12];
+    for start in 0..12 {
+        if seen[start] || next[start].is_none() {
+            continue;
+        }
+        let mut ring = Vec::new();
+        let mut e = start;
+        while !seen[e] {
+            seen[e] = true;
+            ring.push(e);
+            e = next[e].expect("every crossing starts a segment");
+        }
+        // The loops run clockwise around the inside as seen from outside
+        // of it, so the fan is reversed.
+        for i in 1..ring.len() - 1 {
+            triangles.push([ring[0], ring[i + 1], ring[i]]);
+        }
+    }
+    triangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edges_join_neighbouring_corners() {
+        for (e, &(a, axis)) in EDGES.iter().enumerate() {
+            assert_eq!(a >> axis & 1, 0);
+            assert_eq!(edge_between(a, a | 1 << axis), e);
+        }
+    }
+
+    #[test]
+    fn crossed_edges_are_the_surface_vertices() {
+        for case in 0..=255u8 {
+            let mut used: Vec<usize> = triangles(case).iter().flatten().copied().collect();
+            used.sort();
+            used.dedup();
+            let crossed: Vec<usize> = (0..12)
+                .filter(|&e| {
+                    let (a, axis) = EDGES[e];
+                    (case >> a & 1) != (case >> (a | 1 << axis) & 1)
+                })
+                .collect();
+            assert_eq!(used, crossed, "case {case:#010b}");
+        }
+        assert!(triangles(0).is_empty() && triangles(255).is_empty());
+        assert_eq!(triangles(1).len(), 1);
+        // Two inside corners across a face stay apart.
+        assert_eq!(triangles(0b1001).len(), 2);
+    }
+}
@@ -0,0 +1,357 @@
+//! Point clouds handed to the native core without copying.
+//!
+//! [`rcore::point_cloud::PointCloud`] already stores one column per attribute,
+//! which is exactly what `by2_point_cloud` describes, so a [`PointCloudView`]
+//! only borrows the columns and records their pointers.
+//!
+//! The processing kernels search neighbours with a kd-tree and match the
+//! brute-force versions on [`PointCloud`]: points with a NaN or infinite
+//! coordinate are skipped, and kernels that keep a subset of the points
+//! return their indices in ascending order, for [`PointCloud::select`].
+
+use std::marker::PhantomData;
+use std::ptr;
+
+use rcore::point_cloud::PointCloud;
+
+use crate::bindings::{
+    by2_aabb, by2_point_cloud, by2_point_cloud_bounds, by2_point_cloud_buffers,
+    by2_point_cloud_crop, by2_point_cloud_estimate_normals, by2_point_cloud_radius_inliers,
+    by2_point_cloud_statistical_inliers, by2_point_cloud_voxel_downsample, by2_vec3,
+};
+use crate::error::{By2Error, check_status};
+
+/// Axis-aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+impl Aabb {
+    fn to_raw(self) -> by2_aabb {
+        let [min, max] = [self.min, self.max].map(|[x, y, z]| by2_vec3 { x, y, z });
+        by2_aabb { min, max }
+    }
+
+    fn from_raw(raw: by2_aabb) -> Self {
+        Self {
+            min: [raw.min.x, raw.min.y, raw.min.z],
+            max: [raw.max.x, raw.max.y, raw.max.z],
+        }
+    }
+}
+
+/// A `by2_point_cloud` descriptor borrowing the columns of a [`PointCloud`].
+#[derive(Debug, Clone, Copy)]
+pub struct PointCloudView<'a> {
+    raw: by2_point_cloud,
+    _cloud: PhantomData<&'a PointCloud>,
+}
+
+impl<'a> From<&'a PointCloud> for PointCloudView<'a> {
+    fn from(cloud: &'a PointCloud) -> Self {
+        let normals = cloud.normals().map(|n| n.map(<[f32]>::as_ptr));
+        let colors = cloud.colors().map(|c| c.map(<[u8]>::as_ptr));
+        let [normal_x, normal_y, normal_z] = normals.unwrap_or([ptr::null(); 3]);
+        let [red, green, blue] = colors.unwrap_or([ptr::null(); 3]);
+        Self {
+            raw: by2_point_cloud {
+                count: cloud.len(),
+                x: cloud.x().as_ptr(),
+                y: cloud.y().as_ptr(),
+                z: cloud.z().as_ptr(),
+                normal_x,
+                normal_y,
+                normal_z,
+                red,
+                green,
+                blue,
+                intensity: cloud.intensity().map_or(ptr::null(), <[f32]>::as_ptr),
+            },
+            _cloud: PhantomData,
+        }
+    }
+}
+
+impl PointCloudView<'_> {
+    pub fn len(&self) -> usize {
+        self.raw.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.raw.count == 0
+    }
+
+    pub(crate) fn as_raw(&self) -> &by2_point_cloud {
+        &self.raw
+    }
+
+    /// Bounds computed by the native core. NaN coordinates are skipped; an
+    /// empty cloud is an error.
+    pub fn bounds(&self) -> Result<Aabb, By2Error> {
+        let mut out = Aabb {
+            min: [0.0; 3],
+            max: [0.0; 3],
+        }
+        .to_raw();
+        check_status(unsafe { by2_point_cloud_bounds(&self.raw, &mut out) })?;
+        Ok(Aabb::from_raw(out))
+    }
+
+    /// Replaces the points of each occupied voxel (the cube of side
+    /// `voxel_size` at `floor(p / voxel_size)`) by their centroid, ordered
+    /// by voxel. Normals, colours and intensity are averaged; normals are
+    /// not renormalized.
+    pub fn voxel_downsample(&self, voxel_size: f64) -> Result<PointCloud, By2Error> {
+        let n = self.len();
+        let columns = || [vec![0.0f32; n], vec![0.0f32; n], vec![0.0f32; n]];
+        let mut position = columns();
+        let mut normals = (!self.raw.normal_x.is_null()).then(columns);
+        let mut colors =
+            (!self.raw.red.is_null()).then(|| [vec![0u8; n], vec![0u8; n], vec![0u8; n]]);
+        let mut intensity = (!self.raw.intensity.is_null()).then(|| vec![0.0f32; n]);
+
+        let [normal_x, normal_y, normal_z] = match &mut normals {
+            Some([x, y, z]) => [x, y, z].map(|c| c.as_mut_ptr()),
+            None => [ptr::null_mut(); 3],
+        };
+        let [red, green, blue] = match &mut colors {
+            Some([r, g, b]) => [r, g, b].map(|c| c.as_mut_ptr()),
+            None => [ptr::null_mut(); 3],
+        };
+        let [x, y, z] = &mut position;
+        let buffers = by2_point_cloud_buffers {
+            capacity: n,
+            x: x.as_mut_ptr(),
+            y: y.as_mut_ptr(),
+            z: z.as_mut_ptr(),
+            normal_x,
+            normal_y,
+            normal_z,
+            red,
+            green,
+            blue,
+            intensity: intensity
+                .as_mut()
+                .map_or(ptr::null_mut(), |c| c.as_mut_ptr()),
+        };
+        let mut count = 0;
+        // Every buffer holds `n` entries, which is the capacity passed in.
+        check_status(unsafe {
+            by2_point_cloud_voxel_downsample(&self.raw, voxel_size, &buffers, &mut count)
+        })?;
+
+        let [x, y, z] = position.map(|c| truncated(c, count));
+        let mut cloud = PointCloud::from_xyz(x, y, z).map_err(internal)?;
+        if let Some(normals) = normals {
+            let [nx, ny, nz] = normals.map(|c| truncated(c, count));
+            cloud.set_normals(nx, ny, nz).map_err(internal)?;
+        }
+        if let Some(colors) = colors {
+            let [r, g, b] = colors.map(|c| truncated(c, count));
+            cloud.set_colors(r, g, b).map_err(internal)?;
+        }
+        if let Some(intensity) = intensity {
+            cloud
+                .set_intensity(truncated(intensity, count))
+                .map_err(internal)?;
+        }
+        Ok(cloud)
+    }
+
+    /// Statistical outlier removal: keeps the points whose mean distance to
+    /// their `k` nearest neighbours is at most `mean + std_ratio * stddev`
+    /// over the cloud. Needs more than `k` finite points.
+    pub fn statistical_inliers(&self, k: usize, std_ratio: f64) -> Result<Vec<usize>, By2Error> {
+        self.inliers(|raw, inliers, count| unsafe {
+            by2_point_cloud_statistical_inliers(raw, k, std_ratio, inliers, count)
+        })
+    }
+
+    /// Radius outlier removal: keeps the points with at least
+    /// `min_neighbors` other points within `radius`.
+    pub fn radius_inliers(
+        &self,
+        radius: f64,
+        min_neighbors: usize,
+    ) -> Result<Vec<usize>, By2Error> {
+        self.inliers(|raw, inliers, count| unsafe {
+            by2_point_cloud_radius_inliers(raw, radius, min_neighbors, inliers, count)
+        })
+    }
+
+    /// Keeps the points inside `bounds`, which are inclusive.
+    pub fn crop(&self, bounds: &Aabb) -> Result<Vec<usize>, By2Error> {
+        let bounds = bounds.to_raw();
+        self.inliers(|raw, inliers, count| unsafe {
+            by2_point_cloud_crop(raw, &bounds, inliers, count)
+        })
+    }
+
+    /// A unit normal per point as `[nx, ny, nz]` columns, fitted to its `k`
+    /// nearest neighbours (itself included) and facing `viewpoint`. Needs
+    /// `k >= 3`. Points that are not finite or have fewer than 3 finite
+    /// neighbours get NaN.
+    pub fn estimate_normals(
+        &self,
+        k: usize,
+        viewpoint: [f64; 3],
+    ) -> Result<[Vec<f32>; 3], By2Error> {
+        let n = self.len();
+        let mut normals = [vec![0.0f32; n], vec![0.0f32; n], vec![0.0f32; n]];
+        let [nx, ny, nz] = &mut normals;
+        let [x, y, z] = viewpoint;
+        // Each output column holds `n` entries.
+        check_status(unsafe {
+            by2_point_cloud_estimate_normals(
+                &self.raw,
+                k,
+                &by2_vec3 { x, y, z },
+                nx.as_mut_ptr(),
+                ny.as_mut_ptr(),
+                nz.as_mut_ptr(),
+            )
+        })?;
+        Ok(normals)
+    }
+
+    /// Runs an index-returning kernel with an output buffer of `len()`
+    /// entries, which is what the C API requires.
+    fn inliers(
+        &self,
+        kernel: impl FnOnce(&by2_point_cloud, *mut usize, &mut usize) -> i32,
+    ) -> Result<Vec<usize>, By2Error> {
+        let mut inliers = vec![0; self.len()];
+        let mut count = 0;
+        check_status(kernel(&self.raw, inliers.as_mut_ptr(), &mut count))?;
+        inliers.truncate(count);
+        Ok(inliers)
+    }
+}
+
+fn truncated<T>(mut column: Vec<T>, len: usize) -> Vec<T> {
+    column.truncate(len);
+    column
+}
+
+/// The columns of a cloud built on this side always have matching lengths.
+fn internal(err: rcore::point_cloud::PointCloudError) -> By2Error {
+    By2Error::Internal(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn view_borrows_the_columns() {
+        let mut cloud = PointCloud::from_points(&[[1.0, 2.0, 3.0], [-1.0, 0.5, 7.0]]);
+        cloud
+            .set_colors(vec![1, 2], vec![3, 4], vec![5, 6])
+            .unwrap();
+        let view = PointCloudView::from(&cloud);
+        let raw = view.raw;
+
+        assert_eq!(view.len(), 2);
+        assert_eq!(raw.x, cloud.x().as_ptr());
+        assert_eq!(raw.z, cloud.z().as_ptr());
+        assert_eq!(raw.green, cloud.colors().unwrap()[1].as_ptr());
+        assert!(raw.normal_x.is_null() && raw.intensity.is_null());
+    }
+
+    #[test]
+    fn bounds_skip_nan_and_reject_empty_clouds() {
+        let cloud =
+            PointCloud::from_points(&[[1.0, -2.0, 3.0], [f32::NAN, 5.0, -1.0], [-4.0, 0.0, 0.0]]);
+        let bounds = PointCloudView::from(&cloud).bounds().unwrap();
+        assert_eq!(bounds.min, [-4.0, -2.0, -1.0]);
+        assert_eq!(bounds.max, [1.0, 5.0, 3.0]);
+
+        let empty = PointCloud::new();
+        assert!(matches!(
+            PointCloudView::from(&empty).bounds(),
+            Err(By2Error::InvalidArgument(_))
+        ));
+    }
+
+    /// Points on a 1 m grid in the z = 0 plane plus one far-off point.
+    fn grid_with_outlier() -> PointCloud {
+        let mut points: Vec<[f32; 3]> = (0..100)
+            .map(|i| [(i % 10) as f32, (i / 10) as f32, 0.0])
+            .collect();
+        points.push([4.5, 4.5, 30.0]);
+        PointCloud::from_points(&points)
+    }
+
+    #[test]
+    fn voxel_downsample_keeps_attributes() {
+        let mut cloud = grid_with_outlier();
+        let n = cloud.len();
+        cloud
+            .set_intensity((0..n).map(|i| i as f32).collect())
+            .unwrap();
+        cloud
+            .set_colors(vec![10; n], vec![20; n], vec![30; n])
+            .unwrap();
+        let down = PointCloudView::from(&cloud).voxel_downsample(2.0).unwrap();
+
+        // 5 x 5 voxels of 4 grid points each, plus the outlier's voxel, which
+        // sorts right after voxel (2, 2, 0).
+        assert_eq!(down.len(), 26);
+        assert_eq!(down.point(0), Some([0.5, 0.5, 0.0]));
+        assert_eq!(down.point(12), Some([4.5, 4.5, 0.0]));
+        assert_eq!(down.point(13), Some([4.5, 4.5, 30.0]));
+        assert_eq!(
+            down.intensity().unwrap()[0],
+            (0.0 + 1.0 + 10.0 + 11.0) / 4.0
+        );
+        assert_eq!(down.colors().unwrap()[2], [30; 26]);
+        assert!(down.normals().is_none());
+        assert!(PointCloudView::from(&cloud).voxel_downsample(-1.0).is_err());
+    }
+
+    #[test]
+    fn outlier_filters_drop_the_far_point() {
+        let cloud = grid_with_outlier();
+        let view = PointCloudView::from(&cloud);
+        let grid: Vec<usize> = (0..100).collect();
+        assert_eq!(view.statistical_inliers(4, 1.0).unwrap(), grid);
+        assert_eq!(view.radius_inliers(1.0, 2).unwrap(), grid);
+        assert!(view.statistical_inliers(0, 1.0).is_err());
+        assert!(view.radius_inliers(f64::NAN, 1).is_err());
+    }
+
+    #[test]
+    fn crop_keeps_points_inside_the_box() {
+        let cloud = grid_with_outlier();
+        let view = PointCloudView::from(&cloud);
+        let inside = view
+            .crop(&Aabb {
+                min: [2.0, 3.0, -1.0],
+                max: [3.0, 3.5, 1.0],
+            })
+            .unwrap();
+        assert_eq!(inside, [32, 33]);
+        let inverted = Aabb {
+            min: [1.0; 3],
+            max: [0.0; 3],
+        };
+        assert!(matches!(
+            view.crop(&inverted),
+            Err(By2Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn normals_of_a_plane_point_at_the_viewpoint() {
+        let cloud = grid_with_outlier();
+        let view = PointCloudView::from(&cloud);
+        let [nx, ny, nz] = view.estimate_normals(9, [0.0, 0.0, -10.0]).unwrap();
+        for i in 0..100 {
+            assert!(nx[i].abs() < 1e-6 && ny[i].abs() < 1e-6, "{i}");
+            assert!((nz[i] + 1.0).abs() < 1e-6, "{i}");
+        }
+        assert!(view.estimate_normals(2, [0.0; 3]).is_err());
+    }
+}
@@ -0,0 +1,378 @@
+//! Point cloud registration with iterative closest point (ICP).
+//!
+//! [`icp`] refines an initial guess of the [`Pose`] that maps a source cloud
+//! onto a target cloud, pairing each source point with its nearest target
+//! point on every iteration. The result reports how well the final pose
+//! aligns the clouds and how the alignment evolved.
+
+use crate::bindings::{
+    BY2_ICP_CONVERGED, BY2_ICP_MAX_ITERATIONS, BY2_ICP_POINT_TO_PLANE, BY2_ICP_POINT_TO_POINT,
+    BY2_ICP_TOO_FEW_CORRESPONDENCES, by2_icp, by2_icp_default_options, by2_icp_iteration,
+    by2_icp_options, by2_icp_result,
+};
+use crate::error::{By2Error, check_status};
+use crate::geometry::Pose;
+use crate::point_cloud::PointCloudView;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IcpMethod {
+    /// Minimizes the distances between paired points.
+    PointToPoint,
+    /// Minimizes the distances from source points to the tangent planes of
+    /// their target points. Needs target normals.
+    PointToPlane,
+}
+
+impl IcpMethod {
+    fn to_raw(self) -> i32 {
+        let raw = match self {
+            IcpMethod::PointToPoint => BY2_ICP_POINT_TO_POINT,
+            IcpMethod::PointToPlane => BY2_ICP_POINT_TO_PLANE,
+        };
+        raw as i32
+    }
+
+    fn from_raw(raw: i32) -> Result<Self, By2Error> {
+        Ok(match raw {
+            r if r == BY2_ICP_POINT_TO_POINT as i32 => IcpMethod::PointToPoint,
+            r if r == BY2_ICP_POINT_TO_PLANE as i32 => IcpMethod::PointToPlane,
+            r => return Err(By2Error::Internal(format!("unknown icp method {r}"))),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IcpOptions {
+    pub method: IcpMethod,
+    pub max_iterations: usize,
+    /// Source points farther than this from their nearest target point have
+    /// no correspondence. Defaults to infinity, which pairs every point.
+    pub max_correspondence_distance: f64,
+    /// Converged once an iteration moves the pose by less than this and
+    /// `rotation_tolerance` (radians).
+    pub translation_tolerance: f64,
+    pub rotation_tolerance: f64,
+    /// Maps the source into the target frame.
+    pub initial_pose: Pose,
+}
+
+impl Default for IcpOptions {
+    fn default() -> Self {
+        let mut raw = by2_icp_options {
+            method: 0,
+            max_iterations: 0,
+            max_correspondence_distance: 0.0,
+            translation_tolerance: 0.0,
+            rotation_tolerance: 0.0,
+            initial_pose: Pose::IDENTITY.to_raw(),
+        };
+        unsafe { by2_icp_default_options(&mut raw) };
+        Self {
+            method: IcpMethod::from_raw(raw.method)
+                .expect("by2_icp_default_options returns a known method"),
+            max_iterations: raw.max_iterations,
+            max_correspondence_distance: raw.max_correspondence_distance,
+            translation_tolerance: raw.translation_tolerance,
+            rotation_tolerance: raw.rotation_tolerance,
+            initial_pose: Pose::from_raw(raw.initial_pose),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IcpTermination {
+    Converged,
+    MaxIterations,
+    /// Fewer correspondences than the method needs to determine a pose (3
+    /// point-to-point, 6 point-to-plane).
+    TooFewCorrespondences,
+}
+
+impl IcpTermination {
+    fn from_raw(raw: i32) -> Result<Self, By2Error> {
+        Ok(match raw {
+            r if r == BY2_ICP_CONVERGED as i32 => IcpTermination::Converged,
+            r if r == BY2_ICP_MAX_ITERATIONS as i32 => IcpTermination::MaxIterations,
+            r if r == BY2_ICP_TOO_FEW_CORRESPONDENCES as i32 => {
+                IcpTermination::TooFewCorrespondences
+            }
+            r => return Err(By2Error::Internal(format!("unknown icp termination {r}"))),
+        })
+    }
+}
+
+/// How well a pose aligns the clouds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IcpIteration {
+    /// Fraction of the finite source points with a correspondence.
+    pub fitness: f64,
+    /// Root mean square distance over the correspondences (0 without any).
+    pub rmse: f64,
+    pub correspondences: usize,
+}
+
+impl IcpIteration {
+    fn from_raw(raw: &by2_icp_iteration) -> Self {
+        Self {
+            fitness: raw.fitness,
+            rmse: raw.rmse,
+            correspondences: raw.correspondences,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IcpResult {
+    /// Maps the source into the target frame.
+    pub transform: Pose,
+    /// Alignment at `transform`.
+    pub fitness: f64,
+    pub rmse: f64,
+    pub correspondences: usize,
+    pub converged: bool,
+    pub termination: IcpTermination,
+    /// Number of pose updates.
+    pub iterations: usize,
+    /// One entry per pose update, evaluated at the pose before it. Only the
+    /// first [`MAX_ICP_HISTORY`] updates are kept.
+    pub history: Vec<IcpIteration>,
+}
+
+/// Most entries [`IcpResult::history`] holds, so that the buffer doesn't
+/// grow with [`IcpOptions::max_iterations`].
+pub const MAX_ICP_HISTORY: usize = 4096;
+
+/// Aligns `source` to `target` starting from `options.initial_pose`. Points
+/// that are not finite are ignored, and so are target points without a
+/// finite normal for [`IcpMethod::PointToPlane`]. Running out of iterations
+/// or correspondences is not an error: check [`IcpResult::converged`].
+pub fn icp(
+    source: &PointCloudView<'_>,
+    target: &PointCloudView<'_>,
+    options: &IcpOptions,
+) -> Result<IcpResult, By2Error> {
+    let raw_options = by2_icp_options {
+        method: options.method.to_raw(),
+        max_iterations: options.max_iterations,
+        max_correspondence_distance: options.max_correspondence_distance,
+        translation_tolerance: options.translation_tolerance,
+        rotation_tolerance: options.rotation_tolerance,
+        initial_pose: options.initial_pose.to_raw(),
+    };
+    let empty = by2_icp_iteration {
+        fitness: 0.0,
+        rmse: 0.0,
+        correspondences: 0,
+    };
+    let mut result = by2_icp_result {
+        transform: Pose::IDENTITY.to_raw(),
+        alignment: empty,
+        converged: 0,
+        termination: 0,
+        iterations: 0,
+    };
+    let mut history = vec![empty; options.max_iterations.min(MAX_ICP_HISTORY)];
+    check_status(unsafe {
+        by2_icp(
+            source.as_raw(),
+            target.as_raw(),
+            &raw_options,
+            &mut result,
+            history.as_mut_ptr(),
+            history.len(),
+        )
+    })?;
+    history.truncate(result.iterations);
+
+    Ok(IcpResult {
+        transform: Pose::from_raw(result.transform),
+        fitness: result.alignment.fitness,
+        rmse: result.alignment.rmse,
+        correspondences: result.alignment.correspondences,
+        converged: result.converged != 0,
+        termination: IcpTermination::from_raw(result.termination)?,
+        iterations: result.iterations,
+        history: history.iter().map(IcpIteration::from_raw).collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rcore::point_cloud::PointCloud;
+
+    use super::*;
+    use crate::geometry::{EulerAngles, Inverse, Quaternion};
+
+    /// Points on a closed, lumpy ellipsoid: no rigid motion near the
+    /// identity maps it onto itself, and it has no boundary to slide along.
+    fn surface() -> Vec<[f64; 3]> {
+        let mut points = Vec::new();
+        for i in 0..72 {
+            for j in 1..36 {
+                let azimuth = i as f64 * std::f64::consts::TAU / 72.0;
+                let polar = j as f64 * std::f64::consts::PI / 36.0;
+                let r = 1.0 + 0.15 * (3.0 * azimuth).sin() * polar.sin() + 0.1 * polar.cos();
+                points.push([
+                    r * polar.sin() * azimuth.cos(),
+                    0.6 * r * polar.sin() * azimuth.sin(),
+                    0.4 * r * polar.cos(),
+                ]);
+            }
+        }
+        points
+    }
+
+    fn cloud(points: &[[f64; 3]]) -> PointCloud {
+        let points: Vec<[f32; 3]> = points.iter().map(|p| p.map(|c| c as f32)).collect();
+        PointCloud::from_points(&points)
+    }
+
+    /// A misalignment of up to 0.12 * `scale` rad and 0.05 * `scale`.
+    fn known_pose(scale: f64) -> Pose {
+        let rotation = Quaternion::from_euler(EulerAngles {
+            roll: 0.08 * scale,
+            pitch: -0.05 * scale,
+            yaw: 0.12 * scale,
+        })
+        .unwrap();
+        Pose::new(rotation, [0.05, -0.04, 0.03].map(|t| t * scale))
+    }
+
+    fn assert_recovers(result: &IcpResult, expected: Pose) {
+        assert!(result.converged, "{result:?}");
+        assert_eq!(result.termination, IcpTermination::Converged);
+        let error = result.transform.inverse() * expected;
+        assert!(
+            error.translation.iter().all(|t| t.abs() < 1e-4),
+            "{result:?}"
+        );
+        assert!(error.rotation.to_axis_angle().angle < 1e-4, "{result:?}");
+        assert!(result.rmse < 1e-4);
+        assert_eq!(result.fitness, 1.0);
+    }
+
+    #[test]
+    fn point_to_point_recovers_a_known_transform() {
+        let points = surface();
+        let source = cloud(&points);
+        // Point-to-point locks onto the sampling once the pairs stop
+        // changing, so it needs a start closer than the point spacing.
+        let expected = known_pose(0.2);
+        let target = cloud(&expected.transform_points(&points));
+        let result = icp(
+            &PointCloudView::from(&source),
+            &PointCloudView::from(&target),
+            &IcpOptions::default(),
+        )
+        .unwrap();
+        assert_recovers(&result, expected);
+        assert_eq!(result.history.len(), result.iterations);
+        assert!(result.history[0].rmse > result.history.last().unwrap().rmse);
+    }
+
+    #[test]
+    fn iteration_limit_does_not_size_the_history() {
+        let points = surface();
+        let source = cloud(&points);
+        let target = cloud(&known_pose(0.2).transform_points(&points));
+        let result = icp(
+            &PointCloudView::from(&source),
+            &PointCloudView::from(&target),
+            &IcpOptions {
+                max_iterations: usize::MAX,
+                ..IcpOptions::default()
+            },
+        )
+        .unwrap();
+        assert_recovers(&result, known_pose(0.2));
+        assert_eq!(result.history.len(), result.iterations);
+    }
+
+    #[test]
+    fn point_to_plane_recovers_a_known_transform() {
+        let points = surface();
+        let source = cloud(&points);
+        let mut target = cloud(&known_pose(1.0).transform_points(&points));
+        let [nx, ny, nz] = PointCloudView::from(&target)
+            .estimate_normals(8, [0.0, 0.0, 10.0])
+            .unwrap();
+        target.set_normals(nx, ny, nz).unwrap();
+        let result = icp(
+            &PointCloudView::from(&source),
+            &PointCloudView::from(&target),
+            &IcpOptions {
+                method: IcpMethod::PointToPlane,
+                ..IcpOptions::default()
+            },
+        )
+        .unwrap();
+        assert_recovers(&result, known_pose(1.0));
+    }
+
+    #[test]
+    fn initial_pose_is_refined() {
+        let points = surface();
+        let source = cloud(&points);
+        let target = cloud(&known_pose(1.0).transform_points(&points));
+        let result = icp(
+            &PointCloudView::from(&source),
+            &PointCloudView::from(&target),
+            &IcpOptions {
+                initial_pose: known_pose(1.0),
+                max_correspondence_distance: 0.05,
+                ..IcpOptions::default()
+            },
+        )
+        .unwrap();
+        assert_recovers(&result, known_pose(1.0));
+        assert!(result.history.len() <= 2, "{result:?}");
+    }
+
+    #[test]
+    fn running_out_of_correspondences_is_not_an_error() {
+        let points = surface();
+        let source = cloud(&points);
+        let target =
+            cloud(&Pose::new(Quaternion::IDENTITY, [5.0, 0.0, 0.0]).transform_points(&points));
+        let result = icp(
+            &PointCloudView::from(&source),
+            &PointCloudView::from(&target),
+            &IcpOptions {
+                max_correspondence_distance: 0.5,
+                ..IcpOptions::default()
+            },
+        )
+        .unwrap();
+        assert!(!result.converged);
+        assert_eq!(result.termination, IcpTermination::TooFewCorrespondences);
+        assert!(result.history.is_empty());
+        assert_eq!(result.transform, Pose::IDENTITY);
+        assert_eq!((result.fitness, result.rmse), (0.0, 0.0));
+    }
+
+    #[test]
+    fn bad_inputs_are_rejected() {
+        let points = surface();
+        let source = cloud(&points);
+        let view = PointCloudView::from(&source);
+        let rejects = |options: IcpOptions| {
+            matches!(
+                icp(&view, &view, &options),
+                Err(By2Error::InvalidArgument(_))
+            )
+        };
+        // No target normals.
+        assert!(rejects(IcpOptions {
+            method: IcpMethod::PointToPlane,
+            ..IcpOptions::default()
+        }));
+        assert!(rejects(IcpOptions {
+            max_correspondence_distance: 0.0,
+            ..IcpOptions::default()
+        }));
+        assert!(rejects(IcpOptions {
+            rotation_tolerance: f64::NAN,
+            ..IcpOptions::default()
+        }));
+    }
+}
@@ -0,0 +1,596 @@
+//! Compressed sparse matrices (CSR/CSC) backed by Eigen's Sparse module in
+//! the native core.
+//!
+//! [`SparseMatrix`] owns its compressed arrays and keeps them in the shape
+//! the native side expects: offsets start at zero and never decrease, and
+//! the inner indices strictly increase within every row (CSR) or column
+//! (CSC). The arrays are therefore handed over without copying. Dense
+//! operands and results use [`MatrixView`] and [`Matrix`].
+
+use std::ops::AddAssign;
+
+use crate::bindings::{
+    BY2_SPARSE_CONJUGATE_GRADIENT, BY2_SPARSE_CSC, BY2_SPARSE_CSR, BY2_SPARSE_QR,
+    BY2_SPARSE_SIMPLICIAL_LDLT, by2_sparse_matrix, by2_sparse_multiply, by2_sparse_solve,
+    by2_sparse_solve_options,
+};
+use crate::error::{By2Error, check_status};
+use crate::matrix::{Layout, Matrix, MatrixView, Scalar};
+
+/// Storage order of a [`SparseMatrix`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SparseFormat {
+    /// Compressed rows: one offset per row, column indices.
+    Csr,
+    /// Compressed columns: one offset per column, row indices.
+    Csc,
+}
+
+impl SparseFormat {
+    fn to_raw(self) -> i32 {
+        let raw = match self {
+            SparseFormat::Csr => BY2_SPARSE_CSR,
+            SparseFormat::Csc => BY2_SPARSE_CSC,
+        };
+        raw as i32
+    }
+
+    /// Number of outer slices (rows for CSR, columns for CSC).
+    fn outer_len(self, rows: usize, cols: usize) -> usize {
+        match self {
+            SparseFormat::Csr => rows,
+            SparseFormat::Csc => cols,
+        }
+    }
+
+    /// `(outer, inner)` coordinates of element `(row, col)`.
+    fn outer_inner(self, row: usize, col: usize) -> (usize, usize) {
+        match self {
+            SparseFormat::Csr => (row, col),
+            SparseFormat::Csc => (col, row),
+        }
+    }
+}
+
+/// Solver used by [`SparseMatrix::solve`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SparseSolver {
+    /// Direct `LDL^T`. Symmetric matrices, positive definite or indefinite,
+    /// as long as no pivot is zero; the lower triangle is read.
+    SimplicialLdlt,
+    /// Direct QR with COLAMD ordering. Accepts `rows >= cols` and returns the
+    /// least-squares solution.
+    Qr,
+    /// Iterative. Symmetric positive definite matrices only. `None` keeps
+    /// Eigen's defaults: `2 * cols` iterations and a relative residual of
+    /// machine epsilon.
+    ConjugateGradient {
+        max_iterations: Option<usize>,
+        tolerance: Option<f64>,
+    },
+}
+
+impl SparseSolver {
+    /// [`SparseSolver::ConjugateGradient`] with Eigen's defaults.
+    pub const CONJUGATE_GRADIENT: Self = SparseSolver::ConjugateGradient {
+        max_iterations: None,
+        tolerance: None,
+    };
+
+    fn to_raw(self) -> by2_sparse_solve_options {
+        let (solver, max_iterations, tolerance) = match self {
+            SparseSolver::SimplicialLdlt => (BY2_SPARSE_SIMPLICIAL_LDLT, None, None),
+            SparseSolver::Qr => (BY2_SPARSE_QR, None, None),
+            SparseSolver::ConjugateGradient {
+                max_iterations,
+                tolerance,
+            } => (BY2_SPARSE_CONJUGATE_GRADIENT, max_iterations, tolerance),
+        };
+        by2_sparse_solve_options {
+            solver: solver as i32,
+            max_iterations: max_iterations.unwrap_or(0),
+            tolerance: tolerance.unwrap_or(0.0),
+        }
+    }
+}
+
+/// Owned sparse matrix in compressed row or column storage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseMatrix<T> {
+    format: SparseFormat,
+    rows: usize,
+    cols: usize,
+    offsets: Vec<usize>,
+    indices: Vec<usize>,
+    values: Vec<T>,
+}
+
+impl<T: Scalar> SparseMatrix<T> {
+    /// A `rows x cols` matrix without non-zeros.
+    pub fn zeros(rows: usize, cols: usize, format: SparseFormat) -> Self {
+        Self {
+            format,
+            rows,
+            cols,
+            offsets: vec![0; format.outer_len(rows, cols) + 1],
+            indices: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Takes ownership of compressed arrays.
+    ///
+    /// `offsets` has one entry per row (CSR) or column (CSC) plus one,
+    /// starting at 0 and ending at `indices.len() == values.len()`. The inner
+    /// indices of every slice must be in range and strictly increasing.
+    pub fn from_compressed(
+        format: SparseFormat,
+        rows: usize,
+        cols: usize,
+        offsets: Vec<usize>,
+        indices: Vec<usize>,
+        values: Vec<T>,
+    ) -> Result<Self, By2Error> {
+        let invalid = |message: String| Err(By2Error::InvalidArgument(message));
+        let outer_len = format.outer_len(rows, cols);
+        let inner_len = format.outer_len(cols, rows);
+
+        if offsets.len() != outer_len + 1 {
+            return invalid(format!(
+                "a {rows}x{cols} {format:?} matrix needs {} offsets, got {}",
+                outer_len + 1,
+                offsets.len()
+            ));
+        }
+        if indices.len() != values.len() {
+            return invalid(format!(
+                "{} indices but {} values",
+                indices.len(),
+                values.len()
+            ));
+        }
+        if offsets[0] != 0 || offsets[outer_len] != indices.len() {
+            return invalid(format!(
+                "offsets must run from 0 to {}, got {} to {}",
+                indices.len(),
+                offsets[0],
+                offsets[outer_len]
+            ));
+        }
+        for (k, bounds) in offsets.windows(2).enumerate() {
+            let slice = indices
+                .get(bounds[0]..bounds[1])
+                .ok_or_else(|| By2Error::InvalidArgument(format!("offset {} decreases", k + 1)))?;
+            if slice.last().is_some_and(|&i| i >= inner_len) {
+                return invalid(format!(
+                    "slice {k} has an index out of range for a {rows}x{cols} matrix"
+                ));
+            }
+            if slice.windows(2).any(|w| w[0] >= w[1]) {
+                return invalid(format!("indices of slice {k} are not strictly increasing"));
+            }
+        }
+
+        Ok(Self {
+            format,
+            rows,
+            cols,
+            offsets,
+            indices,
+            values,
+        })
+    }
+
+    /// Builds a matrix from `(row, col, value)` triplets in any order.
+    /// Duplicate coordinates are summed.
+    pub fn from_triplets(
+        rows: usize,
+        cols: usize,
+        format: SparseFormat,
+        triplets: &[(usize, usize, T)],
+    ) -> Result<Self, By2Error>
+    where
+        T: AddAssign,
+    {
+        let mut entries = Vec::with_capacity(triplets.len());
+        for &(row, col, value) in triplets {
+            if row >= rows || col >= cols {
+                return Err(By2Error::InvalidArgument(format!(
+                    "triplet ({row}, {col}) is out of range for a {rows}x{cols} matrix"
+                )));
+            }
+            let (outer, inner) = format.outer_inner(row, col);
+            entries.push((outer, inner, value));
+        }
+        entries.sort_by_key(|&(outer, inner, _)| (outer, inner));
+
+        let mut matrix = Self::zeros(rows, cols, format);
+        let mut last = None;
+        for (outer, inner, value) in entries {
+            if last == Some((outer, inner)) {
+                *matrix.values.last_mut().unwrap() += value;
+                continue;
+            }
+            last = Some((outer, inner));
+            matrix.offsets[outer + 1] += 1;
+            matrix.indices.push(inner);
+            matrix.values.push(value);
+        }
+        for k in 1..matrix.offsets.len() {
+            matrix.offsets[k] += matrix.offsets[k - 1];
+        }
+        Ok(matrix)
+    }
+
+    pub fn format(&self) -> SparseFormat {
+        self.format
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Number of stored entries (explicit zeros included).
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn offsets(&self) -> &[usize] {
+        &self.offsets
+    }
+
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    /// `(offsets, indices, values)`, as accepted by [`from_compressed`].
+    ///
+    /// [`from_compressed`]: SparseMatrix::from_compressed
+    pub fn into_compressed(self) -> (Vec<usize>, Vec<usize>, Vec<T>) {
+        (self.offsets, self.indices, self.values)
+    }
+
+    /// Element `(row, col)`: zero when not stored, `None` when out of bounds.
+    pub fn get(&self, row: usize, col: usize) -> Option<T> {
+        if row >= self.rows || col >= self.cols {
+            return None;
+        }
+        let (outer, inner) = self.format.outer_inner(row, col);
+        let start = self.offsets[outer];
+        let slice = &self.indices[start..self.offsets[outer + 1]];
+        Some(
+            slice
+                .binary_search(&inner)
+                .map_or(T::default(), |i| self.values[start + i]),
+        )
+    }
+
+    /// Stored entries as `(row, col, value)` in storage order.
+    pub fn triplets(&self) -> impl Iterator<Item = (usize, usize, T)> + '_ {
+        self.offsets
+            .windows(2)
+            .enumerate()
+            .flat_map(move |(outer, bounds)| {
+                (bounds[0]..bounds[1]).map(move |i| {
+                    let (row, col) = self.format.outer_inner(outer, self.indices[i]);
+                    (row, col, self.values[i])
+                })
+            })
+    }
+
+    /// The same matrix in `format`.
+    pub fn to_format(&self, format: SparseFormat) -> Self {
+        if format == self.format {
+            return self.clone();
+        }
+
+        // Counting sort on the inner index keeps every new slice sorted.
+        let outer_len = format.outer_len(self.rows, self.cols);
+        let mut offsets = vec![0; outer_len + 1];
+        for &inner in &self.indices {
+            offsets[inner + 1] += 1;
+        }
+        for k in 1..offsets.len() {
+            offsets[k] += offsets[k - 1];
+        }
+        let mut next = offsets.clone();
+        let mut indices = vec![0; self.nnz()];
+        let mut values = vec![T::default(); self.nnz()];
+        for (outer, bounds) in self.offsets.windows(2).enumerate() {
+            for i in bounds[0]..bounds[1] {
+                let slot = &mut next[self.indices[i]];
+                indices[*slot] = outer;
+                values[*slot] = self.values[i];
+                *slot += 1;
+            }
+        }
+
+        Self {
+            format,
+            rows: self.rows,
+            cols: self.cols,
+            offsets,
+            indices,
+            values,
+        }
+    }
+
+    /// Dense, column-major copy.
+    pub fn to_dense(&self) -> Matrix<T> {
+        let mut data = vec![T::default(); self.rows * self.cols];
+        for (row, col, value) in self.triplets() {
+            data[row + col * self.rows] = value;
+        }
+        Matrix::from_vec(self.rows, self.cols, Layout::ColMajor, data)
+            .expect("buffer has rows * cols elements")
+    }
+
+    /// `self * rhs`.
+    pub fn multiply(&self, rhs: &MatrixView<'_, T>) -> Result<Matrix<T>, By2Error> {
+        let mut out = Matrix::zeros(self.rows, rhs.cols(), Layout::ColMajor)?;
+        let status =
+            unsafe { by2_sparse_multiply(&self.as_raw(), &rhs.as_raw(), &out.as_raw_mut()) };
+        check_status(status)?;
+        Ok(out)
+    }
+
+    /// Solves `self * x = b` for `x`.
+    ///
+    /// Fails with [`By2Error::Singular`] when a direct factorization breaks
+    /// down and with [`By2Error::NotConverged`] when the conjugate gradient
+    /// runs out of iterations.
+    pub fn solve(
+        &self,
+        b: &MatrixView<'_, T>,
+        solver: SparseSolver,
+    ) -> Result<Matrix<T>, By2Error> {
+        let mut x = Matrix::zeros(self.cols, b.cols(), Layout::ColMajor)?;
+        let status = unsafe {
+            by2_sparse_solve(
+                &solver.to_raw(),
+                &self.as_raw(),
+                &b.as_raw(),
+                &x.as_raw_mut(),
+            )
+        };
+        check_status(status)?;
+        Ok(x)
+    }
+
+    fn as_raw(&self) -> by2_sparse_matrix {
+        by2_sparse_matrix {
+            format: self.format.to_raw(),
+            scalar_type: T::SCALAR_TYPE,
+            rows: self.rows,
+            cols: self.cols,
+            outer_offsets: self.offsets.as_ptr(),
+            inner_indices: self.indices.as_ptr(),
+            values: self.values.as_ptr().cast(),
+        }
+    }
+}
+
+/// Collects `(row, col, value)` entries for [`SparseMatrix::from_triplets`].
+#[derive(Debug, Clone)]
+pub struct TripletBuilder<T> {
+    rows: usize,
+    cols: usize,
+    triplets: Vec<(usize, usize, T)>,
+}
+
+impl<T: Scalar + AddAssign> TripletBuilder<T> {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Self::with_capacity(rows, cols, 0)
+    }
+
+    pub fn with_capacity(rows: usize, cols: usize, capacity: usize) -> Self {
+        Self {
+            rows,
+            cols,
+            triplets: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Adds `value` at `(row, col)`. Bounds are checked by [`build`], and
+    /// repeated coordinates add up.
+    ///
+    /// [`build`]: TripletBuilder::build
+    pub fn push(&mut self, row: usize, col: usize, value: T) -> &mut Self {
+        self.triplets.push((row, col, value));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.triplets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triplets.is_empty()
+    }
+
+    pub fn build(&self, format: SparseFormat) -> Result<SparseMatrix<T>, By2Error> {
+        SparseMatrix::from_triplets(self.rows, self.cols, format, &self.triplets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(values: &[f64]) -> Matrix<f64> {
+        Matrix::from_vec(values.len(), 1, Layout::ColMajor, values.to_vec()).unwrap()
+    }
+
+    /// The `n x n` tridiagonal `[-1, 2, -1]` matrix (1D Laplacian), which is
+    /// symmetric positive definite.
+    fn laplacian(n: usize, format: SparseFormat) -> SparseMatrix<f64> {
+        let mut builder = TripletBuilder::with_capacity(n, n, 3 * n);
+        for i in 0..n {
+            builder.push(i, i, 2.0);
+            if i + 1 < n {
+                builder.push(i, i + 1, -1.0).push(i + 1, i, -1.0);
+            }
+        }
+        builder.build(format).unwrap()
+    }
+
+    fn assert_close(actual: &Matrix<f64>, expected: &[f64]) {
+        assert_eq!(actual.as_slice().len(), expected.len());
+        for (a, e) in actual.as_slice().iter().zip(expected) {
+            assert!(
+                (a - e).abs() < 1e-9,
+                "{:?} != {expected:?}",
+                actual.as_slice()
+            );
+        }
+    }
+
+    #[test]
+    fn triplets_are_sorted_and_duplicates_summed() {
+        let triplets = [(1, 2, 3.0), (0, 1, 1.0), (1, 0, 2.0), (0, 1, 4.0)];
+        let csr = SparseMatrix::from_triplets(2, 3, SparseFormat::Csr, &triplets).unwrap();
+        assert_eq!(csr.offsets(), [0, 1, 3]);
+        assert_eq!(csr.indices(), [1, 0, 2]);
+        assert_eq!(csr.values(), [5.0, 2.0, 3.0]);
+        assert_eq!(csr.get(0, 1), Some(5.0));
+        assert_eq!(csr.get(0, 0), Some(0.0));
+        assert_eq!(csr.get(2, 0), None);
+
+        let csc = SparseMatrix::from_triplets(2, 3, SparseFormat::Csc, &triplets).unwrap();
+        assert_eq!(csc.offsets(), [0, 1, 2, 3]);
+        assert_eq!(csc.to_dense(), csr.to_dense());
+        assert_eq!(csr.to_format(SparseFormat::Csc), csc);
+        assert_eq!(csc.to_format(SparseFormat::Csr), csr);
+    }
+
+    #[test]
+    fn rejects_malformed_structure() {
+        let compressed = |offsets: Vec<usize>, indices: Vec<usize>| {
+            let values = vec![1.0f64; indices.len()];
+            SparseMatrix::from_compressed(SparseFormat::Csr, 2, 2, offsets, indices, values)
+        };
+        assert!(compressed(vec![0, 1, 2], vec![0, 1]).is_ok());
+        assert!(compressed(vec![0, 2], vec![0, 1]).is_err());
+        assert!(compressed(vec![0, 2, 1], vec![0, 1]).is_err());
+        assert!(compressed(vec![0, 2, 2], vec![1, 0]).is_err());
+        assert!(compressed(vec![0, 2, 2], vec![1, 1]).is_err());
+        assert!(compressed(vec![0, 1, 2], vec![0, 2]).is_err());
+        assert!(SparseMatrix::from_triplets(2, 2, SparseFormat::Csr, &[(2, 0, 1.0)]).is_err());
+    }
+
+    #[test]
+    fn multiply_in_both_formats() {
+        // [[1, 0, 2], [0, 3, 0]] * [[1, 2], [3, 4], [5, 6]]
+        let triplets = [(0, 0, 1.0), (0, 2, 2.0), (1, 1, 3.0)];
+        let b =
+            Matrix::from_vec(3, 2, Layout::RowMajor, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]).unwrap();
+        for format in [SparseFormat::Csr, SparseFormat::Csc] {
+            let a = SparseMatrix::from_triplets(2, 3, format, &triplets).unwrap();
+            let product = a.multiply(&b.view()).unwrap();
+            assert_close(&product, &[11.0, 9.0, 14.0, 12.0]);
+        }
+    }
+
+    #[test]
+    fn multiply_rejects_mismatched_dimensions() {
+        let a = laplacian(3, SparseFormat::Csr);
+        let err = a.multiply(&column(&[1.0, 2.0]).view()).unwrap_err();
+        assert!(matches!(err, By2Error::InvalidArgument(_)), "{err}");
+    }
+
+    #[test]
+    fn solve_with_every_solver() {
+        let expected = [1.0, -2.0, 3.0, 0.5, 4.0];
+        let x = column(&expected);
+        for format in [SparseFormat::Csr, SparseFormat::Csc] {
+            let a = laplacian(5, format);
+            let b = a.multiply(&x.view()).unwrap();
+            for solver in [
+                SparseSolver::SimplicialLdlt,
+                SparseSolver::Qr,
+                SparseSolver::CONJUGATE_GRADIENT,
+            ] {
+                let solution = a.solve(&b.view(), solver).unwrap();
+                assert_close(&solution, &expected);
+            }
+        }
+    }
+
+    #[test]
+    fn qr_solves_least_squares() {
+        // Fit y = c0 + c1 * t through (0, 1), (1, 3), (2, 5).
+        let a = SparseMatrix::from_triplets(
+            3,
+            2,
+            SparseFormat::Csc,
+            &[
+                (0, 0, 1.0),
+                (1, 0, 1.0),
+                (1, 1, 1.0),
+                (2, 0, 1.0),
+                (2, 1, 2.0),
+            ],
+        )
+        .unwrap();
+        let x = a
+            .solve(&column(&[1.0, 3.0, 5.0]).view(), SparseSolver::Qr)
+            .unwrap();
+        assert_close(&x, &[1.0, 2.0]);
+    }
+
+    #[test]
+    fn ldlt_solves_symmetric_indefinite_matrices() {
+        let a = SparseMatrix::from_triplets(
+            2,
+            2,
+            SparseFormat::Csc,
+            &[(0, 0, 1.0), (1, 0, 2.0), (0, 1, 2.0), (1, 1, 1.0)],
+        )
+        .unwrap();
+        let x = a
+            .solve(&column(&[5.0, 4.0]).view(), SparseSolver::SimplicialLdlt)
+            .unwrap();
+        assert_close(&x, &[1.0, 2.0]);
+    }
+
+    #[test]
+    fn singular_matrix_fails_to_factorize() {
+        let a = SparseMatrix::from_triplets(2, 2, SparseFormat::Csr, &[(0, 0, 1.0), (1, 1, 0.0)])
+            .unwrap();
+        let b = column(&[1.0, 1.0]);
+        for solver in [SparseSolver::SimplicialLdlt, SparseSolver::Qr] {
+            let err = a.solve(&b.view(), solver).unwrap_err();
+            assert!(matches!(err, By2Error::Singular(_)), "{err}");
+        }
+    }
+
+    #[test]
+    fn conjugate_gradient_reports_non_convergence() {
+        let a = laplacian(50, SparseFormat::Csr);
+        let b = column(&[1.0; 50]);
+        let solver = SparseSolver::ConjugateGradient {
+            max_iterations: Some(2),
+            tolerance: Some(1e-12),
+        };
+        let err = a.solve(&b.view(), solver).unwrap_err();
+        assert!(matches!(err, By2Error::NotConverged(_)), "{err}");
+    }
+
+    #[test]
+    fn single_precision_is_supported() {
+        let a =
+            SparseMatrix::from_triplets(2, 2, SparseFormat::Csc, &[(0, 0, 2.0f32), (1, 1, 4.0)])
+                .unwrap();
+        let b = Matrix::from_vec(2, 1, Layout::ColMajor, vec![1.0f32, 1.0]).unwrap();
+        let x = a.solve(&b.view(), SparseSolver::SimplicialLdlt).unwrap();
+        assert_eq!(x.as_slice(), [0.5, 0.25]);
+    }
+}
@@ -0,0 +1,555 @@
+//! Differential tests between the pure-Rust kernels in `rcore` and their
+//! C++ counterparts reached through `bridge`.
+//!
+//! Every kernel that exists on both sides gets one `#[test]` that feeds the
+//! same random inputs to both implementations and fails on the first
+//! divergence (after proptest has shrunk it to a minimal input).
+//!
+//! The two sides don't always share types or overflow semantics (`rcore::add`
+//! takes `u64` and panics on overflow, `ccore_add` takes `int32_t` where
+//! overflow is undefined), so each test generates inputs from the domain
+//! where both are defined and maps the outputs to a common type before
+//! comparing. To cover a new kernel, add a strategy for that shared domain
+//! and call [`assert_equivalent`]; where the semantics differ, pin the
+//! difference in a plain test (see [`add_overflow_differs_from_rcore`]).
+
+use std::f64::consts::PI;
+use std::fmt::Debug;
+
+use bridge::Inverse;
+use proptest::prelude::*;
+use proptest::test_runner::{Config, TestRunner};
+
+/// Runs `rust` and `native` on inputs drawn from `inputs` and panics with the
+/// shrunk counterexample if they ever return different values.
+fn assert_equivalent<S, O>(
+    kernel: &str,
+    inputs: S,
+    rust: impl Fn(&S::Value) -> O,
+    native: impl Fn(&S::Value) -> O,
+) where
+    S: Strategy,
+    S::Value: Debug,
+    O: PartialEq + Debug,
+{
+    let mut runner = TestRunner::new(Config::default());
+    let result = runner.run(&inputs, |input| {
+        let expected = rust(&input);
+        let actual = native(&input);
+        prop_assert_eq!(
+            &expected,
+            &actual,
+            "`{}` diverged for input {:?}",
+            kernel,
+            input
+        );
+        Ok(())
+    });
+
+    if let Err(e) = result {
+        panic!("rcore and the C++ core disagree on `{kernel}`: {e}");
+    }
+}
+
+/// Floating point outputs, equal when every component agrees to within a
+/// relative tolerance of 1e-9. Eigen and the plain Rust code round
+/// differently, so exact equality is too strict.
+#[derive(Debug)]
+struct Approx(Vec<f64>);
+
+impl PartialEq for Approx {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self
+                .0
+                .iter()
+                .zip(&other.0)
+                .all(|(a, b)| (a - b).abs() <= 1e-9 * a.abs().max(1.0))
+    }
+}
+
+/// Pairs of non-negative `i32`s whose sum still fits in an `i32`: the inputs
+/// on which both `rcore::add` and `ccore_add` are well defined.
+fn non_overflowing_pair() -> impl Strategy<Value = (i32, i32)> {
+    (0..=i32::MAX).prop_flat_map(|a| (Just(a), 0..=i32::MAX - a))
+}
+
+#[test]
+fn add_matches_rcore() {
+    assert_equivalent(
+        "add",
+        non_overflowing_pair(),
+        |&(a, b)| rcore::add(a as u64, b as u64) as i64,
+        |&(a, b)| bridge::by2_add_safe(a, b) as i64,
+    );
+}
+
+/// The two sides part ways on overflow, which [`non_overflowing_pair`] keeps
+/// out of the property tests: `rcore::add` is exact up to `u64::MAX` and
+/// panics past it in debug builds (wrapping in release), while `ccore_add`
+/// overflows `int32_t` right past `i32::MAX`, which is undefined (the
+/// sanitizer build checks that UBSan reports it, in the crate's
+/// `sanitizer_reports_signed_overflow`). Pin the edges so a change on either
+/// side shows up here.
+#[test]
+fn add_overflow_differs_from_rcore() {
+    assert_eq!(rcore::add(i32::MAX as u64, 0), i32::MAX as u64);
+    assert_eq!(bridge::by2_add_safe(i32::MAX, 0), i32::MAX);
+    // One past the native range, rcore is still exact.
+    assert_eq!(rcore::add(i32::MAX as u64, 1), 1 << 31);
+
+    let past_u64 = std::panic::catch_unwind(|| rcore::add(u64::MAX, 1));
+    if cfg!(debug_assertions) {
+        assert!(past_u64.is_err());
+    } else {
+        assert_eq!(past_u64.unwrap(), 0);
+    }
+}
+
+#[test]
+fn add_batch_matches_rcore() {
+    assert_equivalent(
+        "add_batch",
+        prop::collection::vec(non_overflowing_pair(), 0..256),
+        |pairs| {
+            let (a, b): (Vec<u64>, Vec<u64>) =
+                pairs.iter().map(|&(a, b)| (a as u64, b as u64)).unzip();
+            let mut out = vec![0; pairs.len()];
+            rcore::add_batch(&a, &b, &mut out);
+            out.into_iter().map(|v| v as i64).collect::<Vec<_>>()
+        },
+        |pairs| {
+            let (a, b): (Vec<i32>, Vec<i32>) = pairs.iter().copied().unzip();
+            let mut out = vec![0; pairs.len()];
+            bridge::by2_add_batch_safe(&a, &b, &mut out);
+            out.into_iter().map(|v| v as i64).collect::<Vec<_>>()
+        },
+    );
+}
+
+/// Raw `(w, x, y, z)` away from zero, so both sides accept and normalize it.
+fn quaternion() -> impl Strategy<Value = [f64; 4]> {
+    prop::array::uniform4(-1.0..1.0f64)
+        .prop_filter("near zero", |q| q.iter().map(|c| c * c).sum::<f64>() > 0.01)
+}
+
+/// Euler angles away from gimbal lock, where roll and yaw are not unique.
+fn euler() -> impl Strategy<Value = [f64; 3]> {
+    (-PI..PI, -1.5..1.5f64, -PI..PI).prop_map(|(roll, pitch, yaw)| [roll, pitch, yaw])
+}
+
+fn point() -> impl Strategy<Value = [f64; 3]> {
+    prop::array::uniform3(-100.0..100.0f64)
+}
+
+fn pose() -> impl Strategy<Value = ([f64; 4], [f64; 3])> {
+    (quaternion(), point())
+}
+
+fn rust_quaternion([w, x, y, z]: [f64; 4]) -> rcore::geometry::Quaternion {
+    rcore::geometry::Quaternion::new(w, x, y, z)
+        .normalized()
+        .unwrap()
+}
+
+fn native_quaternion([w, x, y, z]: [f64; 4]) -> bridge::Quaternion {
+    bridge::Quaternion::new(w, x, y, z).unwrap()
+}
+
+fn rust_pose((q, t): ([f64; 4], [f64; 3])) -> rcore::geometry::Pose {
+    rcore::geometry::Pose::new(rust_quaternion(q), t)
+}
+
+fn native_pose((q, t): ([f64; 4], [f64; 3])) -> bridge::Pose {
+    bridge::Pose::new(native_quaternion(q), t)
+}
+
+fn rust_components(q: rcore::geometry::Quaternion) -> Vec<f64> {
+    vec![q.w, q.x, q.y, q.z]
+}
+
+fn native_components(q: bridge::Quaternion) -> Vec<f64> {
+    vec![q.w(), q.x(), q.y(), q.z()]
+}
+
+#[test]
+fn quaternion_from_euler_matches_rcore() {
+    assert_equivalent(
+        "quat_from_euler",
+        euler(),
+        |&[roll, pitch, yaw]| {
+            let e = rcore::geometry::EulerAngles { roll, pitch, yaw };
+            Approx(rust_components(rcore::geometry::Quaternion::from_euler(e)))
+        },
+        |&[roll, pitch, yaw]| {
+            let e = bridge::EulerAngles { roll, pitch, yaw };
+            Approx(native_components(
+                bridge::Quaternion::from_euler(e).unwrap(),
+            ))
+        },
+    );
+}
+
+#[test]
+fn quaternion_to_euler_matches_rcore() {
+    assert_equivalent(
+        "quat_to_euler",
+        quaternion(),
+        |&q| {
+            let e = rust_quaternion(q).to_euler();
+            Approx(vec![e.roll, e.pitch, e.yaw])
+        },
+        |&q| {
+            let e = native_quaternion(q).to_euler();
+            Approx(vec![e.roll, e.pitch, e.yaw])
+        },
+    );
+}
+
+#[test]
+fn rotation_matrix_conversions_match_rcore() {
+    assert_equivalent(
+        "quat_to_matrix / quat_from_matrix",
+        quaternion(),
+        |&q| {
+            let m = rust_quaternion(q).to_rotation_matrix();
+            let back = rcore::geometry::Quaternion::from_rotation_matrix(&m).unwrap();
+            Approx(
+                m.iter()
+                    .flatten()
+                    .copied()
+                    .chain(rust_components(back))
+                    .collect(),
+            )
+        },
+        |&q| {
+            let m = native_quaternion(q).to_rotation_matrix();
+            let back = bridge::Quaternion::from_rotation_matrix(&m).unwrap();
+            Approx(
+                m.iter()
+                    .flatten()
+                    .copied()
+                    .chain(native_components(back))
+                    .collect(),
+            )
+        },
+    );
+}
+
+#[test]
+fn axis_angle_conversions_match_rcore() {
+    assert_equivalent(
+        "quat_to_axis_angle / quat_from_axis_angle",
+        (point(), -2.0 * PI..2.0 * PI).prop_filter("zero axis", |(axis, _)| {
+            axis.iter().map(|c| c * c).sum::<f64>() > 0.01
+        }),
+        |&(axis, angle)| {
+            let q = rcore::geometry::Quaternion::from_axis_angle(rcore::geometry::AxisAngle {
+                axis,
+                angle,
+            })
+            .unwrap();
+            let a = q.to_axis_angle();
+            Approx(
+                rust_components(q)
+                    .into_iter()
+                    .chain(a.axis)
+                    .chain([a.angle])
+                    .collect(),
+            )
+        },
+        |&(axis, angle)| {
+            let q = bridge::Quaternion::from_axis_angle(bridge::AxisAngle { axis, angle }).unwrap();
+            let a = q.to_axis_angle();
+            Approx(
+                native_components(q)
+                    .into_iter()
+                    .chain(a.axis)
+                    .chain([a.angle])
+                    .collect(),
+            )
+        },
+    );
+}
+
+#[test]
+fn quaternion_product_and_inverse_match_rcore() {
+    assert_equivalent(
+        "quat_multiply / quat_inverse",
+        (quaternion(), quaternion()),
+        |&(a, b)| {
+            let (a, b) = (rust_quaternion(a), rust_quaternion(b));
+            Approx(
+                [a * b, a.inverse()]
+                    .into_iter()
+                    .flat_map(rust_components)
+                    .collect(),
+            )
+        },
+        |&(a, b)| {
+            let (a, b) = (native_quaternion(a), native_quaternion(b));
+            Approx(
+                [a * b, a.inverse()]
+                    .into_iter()
+                    .flat_map(native_components)
+                    .collect(),
+            )
+        },
+    );
+}
+
+#[test]
+fn pose_compose_and_inverse_match_rcore() {
+    assert_equivalent(
+        "pose_compose / pose_inverse",
+        (pose(), pose()),
+        |&(a, b)| {
+            let (a, b) = (rust_pose(a), rust_pose(b));
+            Approx(
+                [a * b, a.inverse()]
+                    .into_iter()
+                    .flat_map(|p| rust_components(p.rotation).into_iter().chain(p.translation))
+                    .collect(),
+            )
+        },
+        |&(a, b)| {
+            let (a, b) = (native_pose(a), native_pose(b));
+            Approx(
+                [a * b, a.inverse()]
+                    .into_iter()
+                    .flat_map(|p| {
+                        native_components(p.rotation)
+                            .into_iter()
+                            .chain(p.translation)
+                    })
+                    .collect(),
+            )
+        },
+    );
+}
+
+#[test]
+fn pose_transform_points_matches_rcore() {
+    assert_equivalent(
+        "pose_transform_points",
+        (pose(), prop::collection::vec(point(), 0..64)),
+        |&(p, ref points)| Approx(rust_pose(p).transform_points(points).concat()),
+        |&(p, ref points)| Approx(native_pose(p).transform_points(points).concat()),
+    );
+}
+
+/// Clouds of 1..64 points where roughly one coordinate in ten is NaN.
+fn point_cloud() -> impl Strategy<Value = Vec<[f32; 3]>> {
+    let coordinate = prop_oneof![9 => -1e3f32..1e3, 1 => Just(f32::NAN)];
+    prop::collection::vec(prop::array::uniform3(coordinate), 1..64)
+}
+
+#[test]
+fn point_cloud_bounds_match_rcore() {
+    assert_equivalent(
+        "point_cloud_bounds",
+        point_cloud(),
+        |points| {
+            let (min, max) = rcore::point_cloud::PointCloud::from_points(points)
+                .bounds()
+                .unwrap();
+            [min, max]
+                .concat()
+                .into_iter()
+                .map(f64::from)
+                .collect::<Vec<_>>()
+        },
+        |points| {
+            let cloud = rcore::point_cloud::PointCloud::from_points(points);
+            let bounds = bridge::PointCloudView::from(&cloud).bounds().unwrap();
+            [bounds.min, bounds.max].concat()
+        },
+    );
+}
+
+/// Clouds of 8..64 points with a coarse coordinate grid, so voxels and
+/// radius searches see shared cells and exact ties, and ~1 in 10 NaN.
+fn clustered_cloud() -> impl Strategy<Value = Vec<[f32; 3]>> {
+    let coordinate = prop_oneof![9 => (-40..40).prop_map(|v| v as f32 * 0.25), 1 => Just(f32::NAN)];
+    prop::collection::vec(prop::array::uniform3(coordinate), 8..64)
+}
+
+fn with_intensity(points: &[[f32; 3]]) -> rcore::point_cloud::PointCloud {
+    let mut cloud = rcore::point_cloud::PointCloud::from_points(points);
+    cloud
+        .set_intensity((0..points.len()).map(|i| i as f32 * 0.5).collect())
+        .unwrap();
+    cloud
+}
+
+#[test]
+fn voxel_downsample_matches_rcore() {
+    assert_equivalent(
+        "point_cloud_voxel_downsample",
+        (clustered_cloud(), 0.1..5.0f64),
+        |(points, size)| with_intensity(points).voxel_downsample(*size).unwrap(),
+        |(points, size)| {
+            let cloud = with_intensity(points);
+            bridge::PointCloudView::from(&cloud)
+                .voxel_downsample(*size)
+                .unwrap()
+        },
+    );
+}
+
+#[test]
+fn outlier_removal_matches_rcore() {
+    assert_equivalent(
+        "point_cloud_statistical_inliers",
+        (clustered_cloud(), 1..8usize, -1.0..3.0f64),
+        |(points, k, ratio)| {
+            let cloud = rcore::point_cloud::PointCloud::from_points(points);
+            cloud.statistical_inliers(*k, *ratio).ok()
+        },
+        |(points, k, ratio)| {
+            let cloud = rcore::point_cloud::PointCloud::from_points(points);
+            bridge::PointCloudView::from(&cloud)
+                .statistical_inliers(*k, *ratio)
+                .ok()
+        },
+    );
+    assert_equivalent(
+        "point_cloud_radius_inliers",
+        (clustered_cloud(), 0.0..10.0f64, 0..6usize),
+        |(points, radius, min)| {
+            let cloud = rcore::point_cloud::PointCloud::from_points(points);
+            cloud.radius_inliers(*radius, *min).unwrap()
+        },
+        |(points, radius, min)| {
+            let cloud = rcore::point_cloud::PointCloud::from_points(points);
+            bridge::PointCloudView::from(&cloud)
+                .radius_inliers(*radius, *min)
+                .unwrap()
+        },
+    );
+}
+
+#[test]
+fn crop_matches_rcore() {
+    let bounds = prop::array::uniform3((-12.0..12.0f64, -12.0..12.0f64))
+        .prop_map(|axes| (axes.map(|(a, b)| a.min(b)), axes.map(|(a, b)| a.max(b))));
+    assert_equivalent(
+        "point_cloud_crop",
+        (clustered_cloud(), bounds),
+        |(points, (min, max))| {
+            let cloud = rcore::point_cloud::PointCloud::from_points(points);
+            cloud.crop(*min, *max).unwrap()
+        },
+        |(points, (min, max))| {
+            let cloud = rcore::point_cloud::PointCloud::from_points(points);
+            let bounds = bridge::Aabb {
+                min: *min,
+                max: *max,
+            };
+            bridge::PointCloudView::from(&cloud).crop(&bounds).unwrap()
+        },
+    );
+}
+
+/// Normals, equal when they agree to within 1e-4 per component (Eigen's
+/// solver and the Jacobi iteration in `rcore` round differently) and NaN in
+/// the same places.
+#[derive(Debug)]
+struct Normals([Vec<f32>; 3]);
+
+impl PartialEq for Normals {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.iter().zip(&other.0).all(|(a, b)| {
+            a.len() == b.len()
+                && a.iter()
+                    .zip(b)
+                    .all(|(a, b)| (a.is_nan() && b.is_nan()) || (a - b).abs() <= 1e-4)
+        })
+    }
+}
+
+/// Noisy samples of a random plane through the origin: the smallest
+/// eigenvalue of every neighbourhood is well separated from the others, so
+/// the normal is well conditioned.
+fn noisy_plane() -> impl Strategy<Value = Vec<[f32; 3]>> {
+    let sample = (-50.0..50.0f32, -50.0..50.0f32, -0.01..0.01f32);
+    (
+        -2.0..2.0f32,
+        -2.0..2.0f32,
+        prop::collection::vec(sample, 8..48),
+    )
+        .prop_map(|(a, b, samples)| {
+            samples
+                .into_iter()
+                .map(|(x, y, noise)| [x, y, a * x + b * y + noise])
+                .collect()
+        })
+}
+
+#[test]
+fn normal_estimation_matches_rcore() {
+    assert_equivalent(
+        "point_cloud_estimate_normals",
+        (noisy_plane(), 3..10usize),
+        |(points, k)| {
+            let cloud = rcore::point_cloud::PointCloud::from_points(points);
+            Normals(cloud.estimate_normals(*k, [0.0, 0.0, 1e3]).unwrap())
+        },
+        |(points, k)| {
+            let cloud = rcore::point_cloud::PointCloud::from_points(points);
+            let view = bridge::PointCloudView::from(&cloud);
+            Normals(view.estimate_normals(*k, [0.0, 0.0, 1e3]).unwrap())
+        },
+    );
+}
+
+/// Query points on the same coarse grid as [`clustered_cloud`], so they often
+/// coincide with a point or sit at exactly the same distance from several.
+fn grid_queries() -> impl Strategy<Value = Vec<[f64; 3]>> {
+    let coordinate = (-44..44).prop_map(|v| v as f64 * 0.25);
+    prop::collection::vec(prop::array::uniform3(coordinate), 1..8)
+}
+
+#[test]
+fn kdtree_knn_matches_rcore() {
+    assert_equivalent(
+        "kdtree_knn",
+        (clustered_cloud(), grid_queries(), 0..80usize),
+        |(points, queries, k)| {
+            let cloud = rcore::point_cloud::PointCloud::from_points(points);
+            let found: Vec<_> = queries.iter().map(|&q| cloud.knn(q, *k).unwrap()).collect();
+            (found.clone(), found)
+        },
+        |(points, queries, k)| {
+            let cloud = rcore::point_cloud::PointCloud::from_points(points);
+            let tree = bridge::KdTree::new(&bridge::PointCloudView::from(&cloud)).unwrap();
+            let found = queries.iter().map(|&q| tree.knn(q, *k).unwrap()).collect();
+            (found, tree.knn_batch(queries, *k).unwrap())
+        },
+    );
+}
+
+#[test]
+fn kdtree_radius_matches_rcore() {
+    assert_equivalent(
+        "kdtree_radius",
+        (clustered_cloud(), grid_queries(), 0.0..12.0f64),
+        |(points, queries, radius)| {
+            let cloud = rcore::point_cloud::PointCloud::from_points(points);
+            let found: Vec<_> = queries
+                .iter()
+                .map(|&q| cloud.radius_neighbors(q, *radius).unwrap())
+                .collect();
+            (found.clone(), found)
+        },
+        |(points, queries, radius)| {
+            let cloud = rcore::point_cloud::PointCloud::from_points(points);
+            let tree = bridge::KdTree::new(&bridge::PointCloudView::from(&cloud)).unwrap();
+            let found = queries
+                .iter()
+                .map(|&q| tree.radius(q, *radius).unwrap())
+                .collect();
+            (found, tree.radius_batch(queries, *radius).unwrap())
+        },
+    );
+}
@@ -0,0 +1,85 @@
+use bridge::{
+    Coloring, ExportFormat, ExportGeometry, ExportOptions, MeshStyle, OccupancyMap, OctreeFormat,
+};
+
+/// Octomap file formats: `.bt` (free/occupied only) or `.ot` (probabilities).
+pub enum MapFormat {
+    Binary,
+    Full,
+}
+
+/// What to export from an occupancy map.
+pub enum MapGeometry {
+    /// One point per occupied leaf.
+    Points,
+    /// The exposed faces of the occupied voxels.
+    Cubes,
+    /// A smooth surface around the occupied voxels.
+    MarchingCubes,
+}
+
+pub enum MapColoring {
+    None,
+    Height,
+    Probability,
+}
+
+pub enum ExportFileFormat {
+    Ply,
+    Obj,
+}
+
+fn options(geometry: MapGeometry, coloring: MapColoring) -> ExportOptions {
+    ExportOptions {
+        geometry: match geometry {
+            MapGeometry::Points => ExportGeometry::Points,
+            MapGeometry::Cubes => ExportGeometry::Mesh(MeshStyle::Cubes),
+            MapGeometry::MarchingCubes => ExportGeometry::Mesh(MeshStyle::MarchingCubes),
+        },
+        coloring: match coloring {
+            MapColoring::None => Coloring::None,
+            MapColoring::Height => Coloring::Height,
+            MapColoring::Probability => Coloring::Probability,
+        },
+    }
+}
+
+/// Exports the occupied voxels of a serialized map as PLY or OBJ bytes,
+/// e.g. for a 3D viewer.
+pub fn export_occupancy_map(
+    map: Vec<u8>,
+    map_format: MapFormat,
+    geometry: MapGeometry,
+    coloring: MapColoring,
+    format: ExportFileFormat,
+) -> Result<Vec<u8>, String> {
+    let map_format = match map_format {
+        MapFormat::Binary => OctreeFormat::Binary,
+        MapFormat::Full => OctreeFormat::Full,
+    };
+    let format = match format {
+        ExportFileFormat::Ply => ExportFormat::Ply,
+        ExportFileFormat::Obj => ExportFormat::Obj,
+    };
+    let map = OccupancyMap::from_bytes(&map, map_format).map_err(|e| e.to_string())?;
+    map.export(&options(geometry, coloring), format)
+        .map_err(|e| e.to_string())
+}
+
+/// Loads a `.bt` or `.ot` map and writes its occupied voxels to a `.ply` or
+/// `.obj` file.
+pub fn export_occupancy_map_file(
+    map_path: String,
+    out_path: String,
+    geometry: MapGeometry,
+    coloring: MapColoring,
+) -> Result<(), String> {
+    let map = if map_path.to_ascii_lowercase().ends_with(".bt") {
+        OccupancyMap::load_binary(&map_path)
+    } else {
+        OccupancyMap::load(&map_path)
+    }
+    .map_err(|e| e.to_string())?;
+    map.export_to_file(&out_path, &options(geometry, coloring))
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,8 @@
+use bridge::MediaInfo;
+
+/// Describes a media file's container, streams and metadata tags as JSON,
+/// the serialized form of `bridge::MediaInfo`.
+pub fn probe_media(path: String) -> Result<String, String> {
+    let info = MediaInfo::probe(&path).map_err(|e| e.to_string())?;
+    serde_json::to_string(&info).map_err(|e| e.to_string())
+}
@@ -1 +1,5 @@
+#[cfg(feature = "mapping")]
+pub mod mapping;
+#[cfg(feature = "media")]
+pub mod media;
 pub mod simple;
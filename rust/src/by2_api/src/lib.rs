@@ -1,2 +1,4 @@
 pub mod api;
+// Generated with every feature on (the default), so it needs them all.
+#[cfg(all(feature = "mapping", feature = "media"))]
 mod frb_generated;
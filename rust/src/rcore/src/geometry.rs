@@ -0,0 +1,379 @@
+//! Rigid-body geometry in plain Rust, mirroring `ccore/geometry.hpp`.
+//!
+//! Same conventions as the C++ side: Hamilton quaternions, row-major rotation
+//! matrices, euler angles applied as `Rz(yaw) * Ry(pitch) * Rx(roll)`, and
+//! poses mapping `p` to `rotation * p + translation`. Quaternions returned by
+//! this module are unit length with `w >= 0`.
+
+use std::ops::Mul;
+
+pub type Vec3 = [f64; 3];
+
+/// Row-major 3x3 matrix.
+pub type Mat3 = [[f64; 3]; 3];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EulerAngles {
+    pub roll: f64,
+    pub pitch: f64,
+    pub yaw: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisAngle {
+    pub axis: Vec3,
+    pub angle: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pose {
+    pub rotation: Quaternion,
+    pub translation: Vec3,
+}
+
+impl Quaternion {
+    pub const IDENTITY: Self = Self {
+        w: 1.0,
+        x: 0.0,
+        y: 0.0,
+        z: 0.0,
+    };
+
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Self { w, x, y, z }
+    }
+
+    pub fn norm(&self) -> f64 {
+        (self.w * self.w + self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+    }
+
+    /// Unit quaternion with `w >= 0`, or `None` for a zero or non-finite one.
+    pub fn normalized(&self) -> Option<Self> {
+        let norm = self.norm();
+        if !norm.is_finite() || norm < 1e-12 {
+            return None;
+        }
+        let sign = if self.w < 0.0 { -1.0 } else { 1.0 };
+        let s = sign / norm;
+        Some(Self::new(self.w * s, self.x * s, self.y * s, self.z * s))
+    }
+
+    /// `None` unless `m` is orthonormal with determinant +1 (tolerance 1e-6).
+    pub fn from_rotation_matrix(m: &Mat3) -> Option<Self> {
+        if !m.iter().flatten().all(|v| v.is_finite()) || !is_rotation(m, 1e-6) {
+            return None;
+        }
+
+        // Shepperd's method: branch on the largest diagonal term for stability.
+        let trace = m[0][0] + m[1][1] + m[2][2];
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Self::new(
+                0.25 * s,
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+            )
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt() * 2.0;
+            Self::new(
+                (m[2][1] - m[1][2]) / s,
+                0.25 * s,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+            )
+        } else if m[1][1] > m[2][2] {
+            let s = (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt() * 2.0;
+            Self::new(
+                (m[0][2] - m[2][0]) / s,
+                (m[0][1] + m[1][0]) / s,
+                0.25 * s,
+                (m[1][2] + m[2][1]) / s,
+            )
+        } else {
+            let s = (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt() * 2.0;
+            Self::new(
+                (m[1][0] - m[0][1]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                0.25 * s,
+            )
+        };
+        q.normalized()
+    }
+
+    pub fn to_rotation_matrix(&self) -> Mat3 {
+        let Self { w, x, y, z } = *self;
+        [
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+            ],
+        ]
+    }
+
+    pub fn from_euler(e: EulerAngles) -> Self {
+        let (sr, cr) = (e.roll / 2.0).sin_cos();
+        let (sp, cp) = (e.pitch / 2.0).sin_cos();
+        let (sy, cy) = (e.yaw / 2.0).sin_cos();
+        Self::new(
+            cr * cp * cy + sr * sp * sy,
+            sr * cp * cy - cr * sp * sy,
+            cr * sp * cy + sr * cp * sy,
+            cr * cp * sy - sr * sp * cy,
+        )
+        .canonical()
+    }
+
+    /// `pitch` is in `[-pi/2, pi/2]`. At gimbal lock `roll` is reported as 0.
+    pub fn to_euler(&self) -> EulerAngles {
+        let r = self.to_rotation_matrix();
+        let sin_pitch = (-r[2][0]).clamp(-1.0, 1.0);
+        let pitch = sin_pitch.asin();
+        if sin_pitch.abs() > 1.0 - 1e-12 {
+            return EulerAngles {
+                roll: 0.0,
+                pitch,
+                yaw: (-r[0][1]).atan2(r[1][1]),
+            };
+        }
+        EulerAngles {
+            roll: r[2][1].atan2(r[2][2]),
+            pitch,
+            yaw: r[1][0].atan2(r[0][0]),
+        }
+    }
+
+    /// `None` if the axis is zero.
+    pub fn from_axis_angle(a: AxisAngle) -> Option<Self> {
+        let norm = dot(a.axis, a.axis).sqrt();
+        if !norm.is_finite() || norm < 1e-12 {
+            return None;
+        }
+        let (s, c) = (a.angle / 2.0).sin_cos();
+        let k = s / norm;
+        Some(Self::new(c, a.axis[0] * k, a.axis[1] * k, a.axis[2] * k).canonical())
+    }
+
+    /// `angle` is in `[0, pi]`; the identity gives axis `(1, 0, 0)`.
+    pub fn to_axis_angle(&self) -> AxisAngle {
+        let q = self.canonical();
+        let n = (q.x * q.x + q.y * q.y + q.z * q.z).sqrt();
+        if n == 0.0 {
+            return AxisAngle {
+                axis: [1.0, 0.0, 0.0],
+                angle: 0.0,
+            };
+        }
+        AxisAngle {
+            axis: [q.x / n, q.y / n, q.z / n],
+            angle: 2.0 * n.atan2(q.w),
+        }
+    }
+
+    pub fn inverse(&self) -> Self {
+        Self::new(self.w, -self.x, -self.y, -self.z).canonical()
+    }
+
+    pub fn rotate(&self, v: Vec3) -> Vec3 {
+        // v' = v + w * t + q_v x t, with t = 2 * (q_v x v).
+        let q = [self.x, self.y, self.z];
+        let t = cross(q, v).map(|c| 2.0 * c);
+        let u = cross(q, t);
+        [
+            v[0] + self.w * t[0] + u[0],
+            v[1] + self.w * t[1] + u[1],
+            v[2] + self.w * t[2] + u[2],
+        ]
+    }
+
+    fn canonical(self) -> Self {
+        if self.w < 0.0 {
+            Self::new(-self.w, -self.x, -self.y, -self.z)
+        } else {
+            self
+        }
+    }
+}
+
+/// `a * b`: rotates by `b` first, then by `a`.
+impl Mul for Quaternion {
+    type Output = Quaternion;
+
+    fn mul(self, b: Quaternion) -> Quaternion {
+        let a = self;
+        Quaternion::new(
+            a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+            a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+        )
+        .canonical()
+    }
+}
+
+impl Pose {
+    pub const IDENTITY: Self = Self {
+        rotation: Quaternion::IDENTITY,
+        translation: [0.0; 3],
+    };
+
+    pub fn new(rotation: Quaternion, translation: Vec3) -> Self {
+        Self {
+            rotation,
+            translation,
+        }
+    }
+
+    pub fn inverse(&self) -> Self {
+        let rotation = self.rotation.inverse();
+        let t = rotation.rotate(self.translation);
+        Self::new(rotation, [-t[0], -t[1], -t[2]])
+    }
+
+    pub fn transform_point(&self, p: Vec3) -> Vec3 {
+        let r = self.rotation.rotate(p);
+        [
+            r[0] + self.translation[0],
+            r[1] + self.translation[1],
+            r[2] + self.translation[2],
+        ]
+    }
+
+    pub fn transform_points(&self, points: &[Vec3]) -> Vec<Vec3> {
+        points.iter().map(|&p| self.transform_point(p)).collect()
+    }
+}
+
+/// `a * b`: applies `b` first, then `a`.
+impl Mul for Pose {
+    type Output = Pose;
+
+    fn mul(self, b: Pose) -> Pose {
+        Pose::new(
+            self.rotation * b.rotation,
+            self.transform_point(b.translation),
+        )
+    }
+}
+
+fn dot(a: Vec3, b: Vec3) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn is_rotation(m: &Mat3, tolerance: f64) -> bool {
+    for i in 0..3 {
+        for j in 0..3 {
+            let expected = if i == j { 1.0 } else { 0.0 };
+            if (dot(m[i], m[j]) - expected).abs() > tolerance {
+                return false;
+            }
+        }
+    }
+    (dot(m[0], cross(m[1], m[2])) - 1.0).abs() <= tolerance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    fn assert_vec_close(a: Vec3, b: Vec3) {
+        for i in 0..3 {
+            assert!((a[i] - b[i]).abs() < 1e-12, "{a:?} != {b:?}");
+        }
+    }
+
+    #[test]
+    fn euler_round_trips() {
+        let e = EulerAngles {
+            roll: 0.3,
+            pitch: -0.7,
+            yaw: 2.5,
+        };
+        let back = Quaternion::from_euler(e).to_euler();
+        assert!((back.roll - e.roll).abs() < 1e-12);
+        assert!((back.pitch - e.pitch).abs() < 1e-12);
+        assert!((back.yaw - e.yaw).abs() < 1e-12);
+    }
+
+    #[test]
+    fn yaw_rotates_x_onto_y() {
+        let q = Quaternion::from_euler(EulerAngles {
+            roll: 0.0,
+            pitch: 0.0,
+            yaw: FRAC_PI_2,
+        });
+        assert_vec_close(q.rotate([1.0, 0.0, 0.0]), [0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn rotation_matrix_round_trips() {
+        let q = Quaternion::new(0.2, -0.5, 0.4, 0.7).normalized().unwrap();
+        let back = Quaternion::from_rotation_matrix(&q.to_rotation_matrix()).unwrap();
+        assert!((back.w - q.w).abs() < 1e-12 && (back.z - q.z).abs() < 1e-12);
+        assert!(
+            Quaternion::from_rotation_matrix(&[[2.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]])
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn axis_angle_keeps_angle_in_range() {
+        let q = Quaternion::from_axis_angle(AxisAngle {
+            axis: [0.0, 0.0, 2.0],
+            angle: 1.5 * PI,
+        })
+        .unwrap();
+        let a = q.to_axis_angle();
+        assert!((a.angle - 0.5 * PI).abs() < 1e-12);
+        assert_vec_close(a.axis, [0.0, 0.0, -1.0]);
+        assert!(
+            Quaternion::from_axis_angle(AxisAngle {
+                axis: [0.0; 3],
+                angle: 1.0
+            })
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn pose_times_inverse_is_identity() {
+        let pose = Pose::new(
+            Quaternion::new(0.9, 0.1, -0.3, 0.2).normalized().unwrap(),
+            [1.0, -2.0, 3.0],
+        );
+        let identity = pose * pose.inverse();
+        assert_vec_close(identity.translation, [0.0; 3]);
+        assert!((identity.rotation.w - 1.0).abs() < 1e-12);
+        let p = [0.5, 0.25, -4.0];
+        assert_vec_close(pose.inverse().transform_point(pose.transform_point(p)), p);
+    }
+}
@@ -1,7 +1,30 @@
+pub mod geometry;
+pub mod mesh;
+pub mod point_cloud;
+#[cfg(test)]
+mod testing;
+
 pub fn add(left: u64, right: u64) -> u64 {
     left + right
 }
 
+/// Element-wise add of two equally sized buffers into `out`.
+///
+/// # Panics
+///
+/// Panics if `a`, `b` and `out` do not all have the same length.
+pub fn add_batch(a: &[u64], b: &[u64], out: &mut [u64]) {
+    assert_eq!(a.len(), b.len(), "input buffers differ in length");
+    assert_eq!(
+        a.len(),
+        out.len(),
+        "output buffer length differs from inputs"
+    );
+    for ((o, &x), &y) in out.iter_mut().zip(a).zip(b) {
+        *o = add(x, y);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -11,4 +34,11 @@ mod tests {
         let result = add(2, 2);
         assert_eq!(result, 4);
     }
+
+    #[test]
+    fn add_batch_works() {
+        let mut out = [0; 3];
+        add_batch(&[1, 2, 3], &[10, 20, 30], &mut out);
+        assert_eq!(out, [11, 22, 33]);
+    }
 }
@@ -0,0 +1,246 @@
+//! Triangle meshes, written as PLY or OBJ.
+//!
+//! [`TriangleMesh`] holds vertex positions, triangles as vertex index
+//! triples and optional per-vertex RGB colours. A mesh without triangles
+//! is a point set, which both formats can hold too.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+pub mod obj;
+pub mod ply;
+
+/// Error building or writing a mesh.
+#[derive(Debug)]
+pub enum MeshError {
+    Io(io::Error),
+    /// E.g. an unknown file extension.
+    Unsupported(String),
+    /// A triangle refers to a vertex that doesn't exist.
+    InvalidIndex {
+        triangle: usize,
+        index: u32,
+    },
+    /// The colours don't have one entry per vertex.
+    LengthMismatch {
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for MeshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshError::Io(e) => write!(f, "i/o error: {e}"),
+            MeshError::Unsupported(message) => write!(f, "unsupported: {message}"),
+            MeshError::InvalidIndex { triangle, index } => {
+                write!(f, "triangle {triangle} refers to missing vertex {index}")
+            }
+            MeshError::LengthMismatch { expected, actual } => {
+                write!(f, "{actual} colours for {expected} vertices")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MeshError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MeshError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for MeshError {
+    fn from(e: io::Error) -> Self {
+        MeshError::Io(e)
+    }
+}
+
+/// Vertices, triangles over them and optional vertex colours. Triangles
+/// list their vertices counter-clockwise as seen from the front.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TriangleMesh {
+    vertices: Vec<[f32; 3]>,
+    triangles: Vec<[u32; 3]>,
+    colors: Option<Vec<[u8; 3]>>,
+}
+
+impl TriangleMesh {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks that every triangle refers to existing vertices.
+    pub fn from_parts(
+        vertices: Vec<[f32; 3]>,
+        triangles: Vec<[u32; 3]>,
+    ) -> Result<Self, MeshError> {
+        for (i, triangle) in triangles.iter().enumerate() {
+            if let Some(&index) = triangle.iter().find(|&&v| v as usize >= vertices.len()) {
+                return Err(MeshError::InvalidIndex { triangle: i, index });
+            }
+        }
+        Ok(Self {
+            vertices,
+            triangles,
+            colors: None,
+        })
+    }
+
+    pub fn vertices(&self) -> &[[f32; 3]] {
+        &self.vertices
+    }
+
+    pub fn triangles(&self) -> &[[u32; 3]] {
+        &self.triangles
+    }
+
+    pub fn colors(&self) -> Option<&[[u8; 3]]> {
+        self.colors.as_deref()
+    }
+
+    pub fn set_colors(&mut self, colors: Vec<[u8; 3]>) -> Result<(), MeshError> {
+        if colors.len() != self.vertices.len() {
+            return Err(MeshError::LengthMismatch {
+                expected: self.vertices.len(),
+                actual: colors.len(),
+            });
+        }
+        self.colors = Some(colors);
+        Ok(())
+    }
+
+    /// Enclosed volume, by the divergence theorem. Positive for a closed
+    /// mesh whose triangles face outwards.
+    pub fn signed_volume(&self) -> f64 {
+        let vertex = |i: u32| self.vertices[i as usize].map(f64::from);
+        self.triangles
+            .iter()
+            .map(|&[a, b, c]| {
+                let [a, b, c] = [vertex(a), vertex(b), vertex(c)];
+                let cross = [
+                    b[1] * c[2] - b[2] * c[1],
+                    b[2] * c[0] - b[0] * c[2],
+                    b[0] * c[1] - b[1] * c[0],
+                ];
+                (a[0] * cross[0] + a[1] * cross[1] + a[2] * cross[2]) / 6.0
+            })
+            .sum()
+    }
+
+    /// Writes a `.ply` (binary little endian) or `.obj` file, picked by
+    /// extension.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), MeshError> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        let ply = match extension.as_deref() {
+            Some("ply") => true,
+            Some("obj") => false,
+            _ => {
+                return Err(MeshError::Unsupported(format!(
+                    "{} is neither a .ply nor an .obj file",
+                    path.display()
+                )));
+            }
+        };
+        let mut writer = BufWriter::new(File::create(path)?);
+        if ply {
+            ply::write(self, &mut writer, ply::Encoding::BinaryLittleEndian)?;
+        } else {
+            obj::write(self, &mut writer)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    /// A unit tetrahedron with outward facing triangles and colours.
+    pub(super) fn tetrahedron() -> TriangleMesh {
+        let mut mesh = TriangleMesh::from_parts(
+            vec![
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [0.0, 0.0, 1.0],
+            ],
+            vec![[0, 2, 1], [0, 1, 3], [0, 3, 2], [1, 2, 3]],
+        )
+        .unwrap();
+        mesh.set_colors(vec![[255, 0, 0], [0, 255, 0], [0, 0, 255], [9, 9, 9]])
+            .unwrap();
+        mesh
+    }
+
+    #[test]
+    fn volume_of_a_closed_mesh() {
+        let mesh = tetrahedron();
+        assert!((mesh.signed_volume() - 1.0 / 6.0).abs() < 1e-12);
+        let flipped = TriangleMesh::from_parts(
+            mesh.vertices().to_vec(),
+            mesh.triangles()
+                .iter()
+                .map(|&[a, b, c]| [a, c, b])
+                .collect(),
+        )
+        .unwrap();
+        assert!((flipped.signed_volume() + 1.0 / 6.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn bad_parts_are_rejected() {
+        let err = TriangleMesh::from_parts(vec![[0.0; 3]; 3], vec![[0, 1, 2], [2, 3, 0]]);
+        assert!(matches!(
+            err,
+            Err(MeshError::InvalidIndex {
+                triangle: 1,
+                index: 3
+            })
+        ));
+        let mut mesh = tetrahedron();
+        assert!(matches!(
+            mesh.set_colors(vec![[0; 3]; 3]),
+            Err(MeshError::LengthMismatch {
+                expected: 4,
+                actual: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn save_picks_the_format_by_extension() {
+        let mesh = tetrahedron();
+        let dir = TempDir::new("mesh");
+        for (name, expected) in [
+            ("mesh.ply", {
+                let mut bytes = Vec::new();
+                ply::write(&mesh, &mut bytes, ply::Encoding::BinaryLittleEndian).unwrap();
+                bytes
+            }),
+            ("mesh.OBJ", {
+                let mut bytes = Vec::new();
+                obj::write(&mesh, &mut bytes).unwrap();
+                bytes
+            }),
+        ] {
+            let path = dir.join(name);
+            mesh.save(&path).unwrap();
+            assert_eq!(std::fs::read(&path).unwrap(), expected, "{name}");
+        }
+        assert!(matches!(
+            mesh.save(dir.join("mesh.stl")),
+            Err(MeshError::Unsupported(_))
+        ));
+    }
+}
@@ -0,0 +1,52 @@
+//! Wavefront OBJ meshes: `v x y z` lines, with `r g b` in [0, 1] appended
+//! when the mesh has colours (a widespread extension), then `f` lines of
+//! 1-based vertex indices.
+
+use std::io::Write;
+
+use super::{MeshError, TriangleMesh};
+
+pub fn write<W: Write>(mesh: &TriangleMesh, mut writer: W) -> Result<(), MeshError> {
+    let colors = mesh.colors();
+    for (i, [x, y, z]) in mesh.vertices().iter().enumerate() {
+        write!(writer, "v {x} {y} {z}")?;
+        if let Some(colors) = colors {
+            let [r, g, b] = colors[i].map(|c| f32::from(c) / 255.0);
+            write!(writer, " {r} {g} {b}")?;
+        }
+        writeln!(writer)?;
+    }
+    for [a, b, c] in mesh.triangles() {
+        writeln!(writer, "f {} {} {}", a + 1, b + 1, c + 1)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::tetrahedron;
+    use super::*;
+
+    fn write_string(mesh: &TriangleMesh) -> String {
+        let mut out = Vec::new();
+        write(mesh, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn writes_vertices_then_faces() {
+        assert_eq!(
+            write_string(&tetrahedron()),
+            "v 0 0 0 1 0 0\n\
+             v 1 0 0 0 1 0\n\
+             v 0 1 0 0 0 1\n\
+             v 0 0 1 0.03529412 0.03529412 0.03529412\n\
+             f 1 3 2\n\
+             f 1 2 4\n\
+             f 1 4 3\n\
+             f 2 3 4\n"
+        );
+        let points = TriangleMesh::from_parts(vec![[0.5, -1.0, 2.0]], Vec::new()).unwrap();
+        assert_eq!(write_string(&points), "v 0.5 -1 2\n");
+    }
+}
@@ -0,0 +1,158 @@
+//! Stanford PLY meshes: a `vertex` element with `x`, `y`, `z` and, when the
+//! mesh has them, `red`, `green`, `blue`, then a `face` element of vertex
+//! index lists. The point cloud reader reads the vertices back.
+
+use std::io::Write;
+
+pub use crate::point_cloud::ply::Encoding;
+
+use super::{MeshError, TriangleMesh};
+
+/// Writes `mesh` with `float` coordinates, `uchar` colours and `uint`
+/// vertex indices.
+pub fn write<W: Write>(
+    mesh: &TriangleMesh,
+    mut writer: W,
+    encoding: Encoding,
+) -> Result<(), MeshError> {
+    let format = match encoding {
+        Encoding::Ascii => "ascii",
+        Encoding::BinaryLittleEndian => "binary_little_endian",
+        Encoding::BinaryBigEndian => "binary_big_endian",
+    };
+    writeln!(writer, "ply")?;
+    writeln!(writer, "format {format} 1.0")?;
+    writeln!(writer, "element vertex {}", mesh.vertices().len())?;
+    for name in ["x", "y", "z"] {
+        writeln!(writer, "property float {name}")?;
+    }
+    let colors = mesh.colors();
+    if colors.is_some() {
+        for name in ["red", "green", "blue"] {
+            writeln!(writer, "property uchar {name}")?;
+        }
+    }
+    writeln!(writer, "element face {}", mesh.triangles().len())?;
+    writeln!(writer, "property list uchar uint vertex_indices")?;
+    writeln!(writer, "end_header")?;
+
+    let big_endian = encoding == Encoding::BinaryBigEndian;
+    let mut record = Vec::new();
+    for (i, vertex) in mesh.vertices().iter().enumerate() {
+        record.clear();
+        let color = colors.map(|c| c[i]);
+        if encoding == Encoding::Ascii {
+            write!(record, "{} {} {}", vertex[0], vertex[1], vertex[2])?;
+            if let Some([r, g, b]) = color {
+                write!(record, " {r} {g} {b}")?;
+            }
+            writeln!(record)?;
+        } else {
+            for c in vertex {
+                record.extend(if big_endian {
+                    c.to_be_bytes()
+                } else {
+                    c.to_le_bytes()
+                });
+            }
+            record.extend(color.iter().flatten());
+        }
+        writer.write_all(&record)?;
+    }
+    for triangle in mesh.triangles() {
+        record.clear();
+        if encoding == Encoding::Ascii {
+            writeln!(record, "3 {} {} {}", triangle[0], triangle[1], triangle[2])?;
+        } else {
+            record.push(3);
+            for index in triangle {
+                record.extend(if big_endian {
+                    index.to_be_bytes()
+                } else {
+                    index.to_le_bytes()
+                });
+            }
+        }
+        writer.write_all(&record)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::tetrahedron;
+    use super::*;
+    use crate::point_cloud::{PointCloud, ply};
+
+    fn write_vec(mesh: &TriangleMesh, encoding: Encoding) -> Vec<u8> {
+        let mut out = Vec::new();
+        write(mesh, &mut out, encoding).unwrap();
+        out
+    }
+
+    #[test]
+    fn writes_ascii() {
+        let text = String::from_utf8(write_vec(&tetrahedron(), Encoding::Ascii)).unwrap();
+        assert_eq!(
+            text,
+            "ply\n\
+             format ascii 1.0\n\
+             element vertex 4\n\
+             property float x\n\
+             property float y\n\
+             property float z\n\
+             property uchar red\n\
+             property uchar green\n\
+             property uchar blue\n\
+             element face 4\n\
+             property list uchar uint vertex_indices\n\
+             end_header\n\
+             0 0 0 255 0 0\n\
+             1 0 0 0 255 0\n\
+             0 1 0 0 0 255\n\
+             0 0 1 9 9 9\n\
+             3 0 2 1\n\
+             3 0 1 3\n\
+             3 0 3 2\n\
+             3 1 2 3\n"
+        );
+    }
+
+    #[test]
+    fn point_cloud_reader_reads_the_vertices() {
+        let mesh = tetrahedron();
+        let points: Vec<[f32; 3]> = mesh.vertices().to_vec();
+        let mut expected = PointCloud::from_points(&points);
+        let colors = mesh.colors().unwrap();
+        expected
+            .set_colors(
+                colors.iter().map(|c| c[0]).collect(),
+                colors.iter().map(|c| c[1]).collect(),
+                colors.iter().map(|c| c[2]).collect(),
+            )
+            .unwrap();
+        for encoding in [
+            Encoding::Ascii,
+            Encoding::BinaryLittleEndian,
+            Encoding::BinaryBigEndian,
+        ] {
+            let bytes = write_vec(&mesh, encoding);
+            assert_eq!(
+                ply::read(bytes.as_slice()).unwrap(),
+                expected,
+                "{encoding:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn binary_faces_are_index_lists() {
+        let mesh = TriangleMesh::from_parts(vec![[0.0; 3]; 3], vec![[0, 1, 2]]).unwrap();
+        let bytes = write_vec(&mesh, Encoding::BinaryBigEndian);
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(!text.contains("property uchar red"));
+        let body = &bytes[text.find("end_header\n").unwrap() + 11..];
+        assert_eq!(body.len(), 3 * 12 + 13);
+        assert_eq!(&body[36..], [3, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 2]);
+    }
+}
@@ -0,0 +1,542 @@
+//! Point clouds in structure-of-arrays layout, with PLY and PCD I/O.
+//!
+//! [`PointCloud`] stores one column per coordinate (`x`, `y`, `z`) plus
+//! optional normal, colour and intensity columns, all with one entry per
+//! point. That is the layout the native kernels read, so a cloud can be
+//! handed to them without copying.
+//!
+//! The processing kernels on [`PointCloud`] (neighbour queries, voxel
+//! downsampling, outlier removal, cropping, normal estimation) are
+//! brute-force references for the native ones.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+pub mod pcd;
+pub mod ply;
+mod processing;
+
+pub use processing::Neighbor;
+
+/// Error reading or writing a point cloud.
+#[derive(Debug)]
+pub enum PointCloudError {
+    Io(io::Error),
+    /// The file is malformed. `line` is the 1-based line of the offending
+    /// header or ASCII data line, when there is one.
+    Parse {
+        line: Option<usize>,
+        message: String,
+    },
+    /// The file is valid but uses something this implementation doesn't
+    /// support (e.g. compressed PCD data).
+    Unsupported(String),
+    /// A processing kernel got a parameter it can't work with.
+    InvalidArgument(String),
+    /// An attribute column doesn't have one entry per point.
+    LengthMismatch {
+        attribute: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for PointCloudError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PointCloudError::Io(e) => write!(f, "i/o error: {e}"),
+            PointCloudError::Parse {
+                line: Some(line),
+                message,
+            } => write!(f, "line {line}: {message}"),
+            PointCloudError::Parse {
+                line: None,
+                message,
+            } => f.write_str(message),
+            PointCloudError::Unsupported(message) => write!(f, "unsupported: {message}"),
+            PointCloudError::InvalidArgument(message) => write!(f, "invalid argument: {message}"),
+            PointCloudError::LengthMismatch {
+                attribute,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "`{attribute}` has {actual} entries for {expected} points"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PointCloudError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PointCloudError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PointCloudError {
+    fn from(e: io::Error) -> Self {
+        PointCloudError::Io(e)
+    }
+}
+
+/// Points with optional per-point normals, RGB colours and intensity.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PointCloud {
+    x: Vec<f32>,
+    y: Vec<f32>,
+    z: Vec<f32>,
+    normals: Option<[Vec<f32>; 3]>,
+    colors: Option<[Vec<u8>; 3]>,
+    intensity: Option<Vec<f32>>,
+}
+
+impl PointCloud {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes ownership of the coordinate columns, which must have the same
+    /// length.
+    pub fn from_xyz(x: Vec<f32>, y: Vec<f32>, z: Vec<f32>) -> Result<Self, PointCloudError> {
+        check_len("y", x.len(), y.len())?;
+        check_len("z", x.len(), z.len())?;
+        Ok(Self {
+            x,
+            y,
+            z,
+            ..Self::default()
+        })
+    }
+
+    pub fn from_points(points: &[[f32; 3]]) -> Self {
+        Self {
+            x: points.iter().map(|p| p[0]).collect(),
+            y: points.iter().map(|p| p[1]).collect(),
+            z: points.iter().map(|p| p[2]).collect(),
+            ..Self::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+
+    pub fn x(&self) -> &[f32] {
+        &self.x
+    }
+
+    pub fn y(&self) -> &[f32] {
+        &self.y
+    }
+
+    pub fn z(&self) -> &[f32] {
+        &self.z
+    }
+
+    /// Point `i`, or `None` when out of bounds.
+    pub fn point(&self, i: usize) -> Option<[f32; 3]> {
+        (i < self.len()).then(|| [self.x[i], self.y[i], self.z[i]])
+    }
+
+    pub fn points(&self) -> impl ExactSizeIterator<Item = [f32; 3]> + '_ {
+        (0..self.len()).map(|i| [self.x[i], self.y[i], self.z[i]])
+    }
+
+    /// `[nx, ny, nz]` columns.
+    pub fn normals(&self) -> Option<[&[f32]; 3]> {
+        self.normals
+            .as_ref()
+            .map(|[x, y, z]| [x.as_slice(), y.as_slice(), z.as_slice()])
+    }
+
+    /// `[red, green, blue]` columns.
+    pub fn colors(&self) -> Option<[&[u8]; 3]> {
+        self.colors
+            .as_ref()
+            .map(|[r, g, b]| [r.as_slice(), g.as_slice(), b.as_slice()])
+    }
+
+    pub fn intensity(&self) -> Option<&[f32]> {
+        self.intensity.as_deref()
+    }
+
+    pub fn set_normals(
+        &mut self,
+        nx: Vec<f32>,
+        ny: Vec<f32>,
+        nz: Vec<f32>,
+    ) -> Result<(), PointCloudError> {
+        check_len("normal_x", self.len(), nx.len())?;
+        check_len("normal_y", self.len(), ny.len())?;
+        check_len("normal_z", self.len(), nz.len())?;
+        self.normals = Some([nx, ny, nz]);
+        Ok(())
+    }
+
+    pub fn set_colors(
+        &mut self,
+        red: Vec<u8>,
+        green: Vec<u8>,
+        blue: Vec<u8>,
+    ) -> Result<(), PointCloudError> {
+        check_len("red", self.len(), red.len())?;
+        check_len("green", self.len(), green.len())?;
+        check_len("blue", self.len(), blue.len())?;
+        self.colors = Some([red, green, blue]);
+        Ok(())
+    }
+
+    pub fn set_intensity(&mut self, intensity: Vec<f32>) -> Result<(), PointCloudError> {
+        check_len("intensity", self.len(), intensity.len())?;
+        self.intensity = Some(intensity);
+        Ok(())
+    }
+
+    /// Axis-aligned `(min, max)` corners, `None` for an empty cloud. NaN
+    /// coordinates are ignored.
+    pub fn bounds(&self) -> Option<([f32; 3], [f32; 3])> {
+        if self.is_empty() {
+            return None;
+        }
+        let mut min = [f32::INFINITY; 3];
+        let mut max = [f32::NEG_INFINITY; 3];
+        for p in self.points() {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(p[axis]);
+                max[axis] = max[axis].max(p[axis]);
+            }
+        }
+        Some((min, max))
+    }
+
+    /// Reads a `.ply` or `.pcd` file, picked by extension.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, PointCloudError> {
+        let path = path.as_ref();
+        let format = FileFormat::from_path(path)?;
+        let reader = BufReader::new(File::open(path)?);
+        match format {
+            FileFormat::Ply => ply::read(reader),
+            FileFormat::Pcd => pcd::read(reader),
+        }
+    }
+
+    /// Writes a `.ply` (binary little endian) or `.pcd` (binary) file, picked
+    /// by extension. Use [`ply::write`] or [`pcd::write`] for ASCII.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), PointCloudError> {
+        let path = path.as_ref();
+        let format = FileFormat::from_path(path)?;
+        let mut writer = BufWriter::new(File::create(path)?);
+        match format {
+            FileFormat::Ply => ply::write(self, &mut writer, ply::Encoding::BinaryLittleEndian)?,
+            FileFormat::Pcd => pcd::write(self, &mut writer, pcd::Encoding::Binary)?,
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+fn check_len(
+    attribute: &'static str,
+    expected: usize,
+    actual: usize,
+) -> Result<(), PointCloudError> {
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(PointCloudError::LengthMismatch {
+            attribute,
+            expected,
+            actual,
+        })
+    }
+}
+
+enum FileFormat {
+    Ply,
+    Pcd,
+}
+
+impl FileFormat {
+    fn from_path(path: &Path) -> Result<Self, PointCloudError> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("ply") => Ok(FileFormat::Ply),
+            Some("pcd") => Ok(FileFormat::Pcd),
+            _ => Err(PointCloudError::Unsupported(format!(
+                "{} is neither a .ply nor a .pcd file",
+                path.display()
+            ))),
+        }
+    }
+}
+
+/// Numeric types shared by the PLY and PCD readers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl ScalarType {
+    fn size(self) -> usize {
+        match self {
+            ScalarType::I8 | ScalarType::U8 => 1,
+            ScalarType::I16 | ScalarType::U16 => 2,
+            ScalarType::I32 | ScalarType::U32 | ScalarType::F32 => 4,
+            ScalarType::F64 => 8,
+        }
+    }
+
+    fn is_float(self) -> bool {
+        matches!(self, ScalarType::F32 | ScalarType::F64)
+    }
+
+    /// Decodes `self.size()` bytes.
+    fn decode(self, bytes: &[u8], big_endian: bool) -> f64 {
+        macro_rules! decode {
+            ($t:ty) => {{
+                let bytes = bytes[..std::mem::size_of::<$t>()].try_into().unwrap();
+                let value = if big_endian {
+                    <$t>::from_be_bytes(bytes)
+                } else {
+                    <$t>::from_le_bytes(bytes)
+                };
+                f64::from(value)
+            }};
+        }
+        match self {
+            ScalarType::I8 => decode!(i8),
+            ScalarType::U8 => decode!(u8),
+            ScalarType::I16 => decode!(i16),
+            ScalarType::U16 => decode!(u16),
+            ScalarType::I32 => decode!(i32),
+            ScalarType::U32 => decode!(u32),
+            ScalarType::F32 => decode!(f32),
+            ScalarType::F64 => decode!(f64),
+        }
+    }
+
+    /// Parses an ASCII value, checking that integer types get integers.
+    fn parse(self, token: &str) -> Option<f64> {
+        if self.is_float() {
+            token.parse().ok()
+        } else {
+            token.parse::<i64>().ok().map(|v| v as f64)
+        }
+    }
+
+    /// A colour channel: integers are clamped to 0..=255, floats are
+    /// taken as 0..=1.
+    fn to_color(self, value: f64) -> u8 {
+        let value = if self.is_float() {
+            value * 255.0
+        } else {
+            value
+        };
+        value.round().clamp(0.0, 255.0) as u8
+    }
+}
+
+/// A column a PLY property or PCD field is read into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Attribute {
+    Position(usize),
+    Normal(usize),
+    Color(usize),
+    Intensity,
+}
+
+/// Columns of a cloud being read, one value per point and attribute.
+struct Columns {
+    position: [Vec<f32>; 3],
+    normal: Option<[Vec<f32>; 3]>,
+    color: Option<[Vec<u8>; 3]>,
+    intensity: Option<Vec<f32>>,
+}
+
+impl Columns {
+    /// `count` is the point count announced by the header. The reservation
+    /// is bounded so that a corrupt count can't allocate a lot up front.
+    fn new(count: usize, normals: bool, colors: bool, intensity: bool) -> Self {
+        let capacity = count.min(1 << 16);
+        let column = || Vec::with_capacity(capacity);
+        Self {
+            position: [column(), column(), column()],
+            normal: normals.then(|| [column(), column(), column()]),
+            color: colors.then(|| {
+                let column = || Vec::with_capacity(capacity);
+                [column(), column(), column()]
+            }),
+            intensity: intensity.then(column),
+        }
+    }
+
+    /// Appends `value`, read as `ty`, to the column of `attribute`.
+    fn push(&mut self, attribute: Attribute, value: f64, ty: ScalarType) {
+        match attribute {
+            Attribute::Position(axis) => self.position[axis].push(value as f32),
+            Attribute::Normal(axis) => {
+                if let Some(normal) = &mut self.normal {
+                    normal[axis].push(value as f32);
+                }
+            }
+            Attribute::Color(channel) => {
+                if let Some(color) = &mut self.color {
+                    color[channel].push(ty.to_color(value));
+                }
+            }
+            Attribute::Intensity => {
+                if let Some(intensity) = &mut self.intensity {
+                    intensity.push(value as f32);
+                }
+            }
+        }
+    }
+
+    fn push_rgb(&mut self, rgb: [u8; 3]) {
+        if let Some(color) = &mut self.color {
+            for (column, value) in color.iter_mut().zip(rgb) {
+                column.push(value);
+            }
+        }
+    }
+
+    fn into_cloud(self) -> Result<PointCloud, PointCloudError> {
+        let [x, y, z] = self.position;
+        let mut cloud = PointCloud::from_xyz(x, y, z)?;
+        if let Some([nx, ny, nz]) = self.normal {
+            cloud.set_normals(nx, ny, nz)?;
+        }
+        if let Some([r, g, b]) = self.color {
+            cloud.set_colors(r, g, b)?;
+        }
+        if let Some(intensity) = self.intensity {
+            cloud.set_intensity(intensity)?;
+        }
+        Ok(cloud)
+    }
+}
+
+fn parse_error(line: Option<usize>, message: impl Into<String>) -> PointCloudError {
+    PointCloudError::Parse {
+        line,
+        message: message.into(),
+    }
+}
+
+/// Reads one line without its terminator; `None` at end of input.
+fn read_line<R: BufRead>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+) -> Result<Option<String>, PointCloudError> {
+    buf.clear();
+    if reader.read_until(b'\n', buf)? == 0 {
+        return Ok(None);
+    }
+    let line = String::from_utf8_lossy(buf);
+    Ok(Some(line.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+/// `read_exact` that reports a truncated file as a parse error about `what`.
+fn read_exact<R: BufRead>(
+    reader: &mut R,
+    buf: &mut [u8],
+    what: impl FnOnce() -> String,
+) -> Result<(), PointCloudError> {
+    reader.read_exact(buf).map_err(|e| match e.kind() {
+        io::ErrorKind::UnexpectedEof => {
+            parse_error(None, format!("unexpected end of file in {}", what()))
+        }
+        _ => PointCloudError::Io(e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    /// A cloud with every attribute, exercising awkward float values.
+    pub(super) fn sample() -> PointCloud {
+        let mut cloud = PointCloud::from_points(&[
+            [0.0, 1.5, -2.25],
+            [1e-7, -3.4e38, 0.1],
+            [f32::MIN_POSITIVE, 42.0, 7.125],
+        ]);
+        cloud
+            .set_normals(
+                vec![0.0, 1.0, 0.6],
+                vec![0.0, 0.0, 0.8],
+                vec![1.0, 0.0, 0.0],
+            )
+            .unwrap();
+        cloud
+            .set_colors(vec![255, 0, 12], vec![0, 128, 34], vec![1, 2, 56])
+            .unwrap();
+        cloud.set_intensity(vec![0.5, 1.0, 1234.5]).unwrap();
+        cloud
+    }
+
+    #[test]
+    fn columns_must_match_the_point_count() {
+        let err = PointCloud::from_xyz(vec![0.0; 3], vec![0.0; 3], vec![0.0; 2]).unwrap_err();
+        assert!(matches!(
+            err,
+            PointCloudError::LengthMismatch {
+                attribute: "z",
+                expected: 3,
+                actual: 2
+            }
+        ));
+
+        let mut cloud = PointCloud::from_points(&[[0.0; 3]; 2]);
+        assert!(cloud.set_intensity(vec![1.0]).is_err());
+        assert!(
+            cloud
+                .set_colors(vec![0; 2], vec![0; 2], vec![0; 3])
+                .is_err()
+        );
+        assert!(cloud.intensity().is_none() && cloud.colors().is_none());
+    }
+
+    #[test]
+    fn bounds_skip_nan() {
+        let cloud =
+            PointCloud::from_points(&[[1.0, -2.0, 3.0], [f32::NAN, 5.0, -1.0], [-4.0, 0.0, 0.0]]);
+        assert_eq!(cloud.bounds(), Some(([-4.0, -2.0, -1.0], [1.0, 5.0, 3.0])));
+        assert_eq!(PointCloud::new().bounds(), None);
+    }
+
+    #[test]
+    fn save_and_load_pick_the_format_by_extension() {
+        let dir = TempDir::new("point-cloud");
+        let cloud = sample();
+        for name in ["cloud.ply", "cloud.PCD"] {
+            let path = dir.join(name);
+            cloud.save(&path).unwrap();
+            assert_eq!(PointCloud::load(&path).unwrap(), cloud);
+        }
+        assert!(matches!(
+            cloud.save(dir.join("cloud.xyz")),
+            Err(PointCloudError::Unsupported(_))
+        ));
+    }
+}
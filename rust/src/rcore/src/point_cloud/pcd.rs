@@ -0,0 +1,560 @@
+//! PCL's PCD format, versions 0.6 and 0.7 with `ascii` or `binary` data.
+//!
+//! Recognised fields are `x`, `y`, `z`, `normal_x`/`normal_y`/`normal_z`,
+//! `intensity` and a packed `rgb` or `rgba`; any other field is skipped.
+//! `binary_compressed` data is reported as unsupported.
+
+use std::io::{self, BufRead, Read, Write};
+
+use super::{
+    Attribute, Columns, PointCloud, PointCloudError, ScalarType, parse_error, read_exact, read_line,
+};
+
+/// Data encoding of a PCD file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Ascii,
+    Binary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Attribute(Attribute),
+    /// Packed `0x00RRGGBB` (or `0xAARRGGBB`) colour.
+    Rgb,
+    Skip,
+}
+
+struct Field {
+    name: String,
+    ty: ScalarType,
+    count: usize,
+    /// `ty.size() * count`, the bytes of the field in a binary point.
+    bytes: usize,
+    target: Target,
+}
+
+struct Header {
+    fields: Vec<Field>,
+    points: usize,
+    encoding: Encoding,
+    /// Number of lines up to and including `DATA`.
+    lines: usize,
+}
+
+/// Reads a PCD file.
+pub fn read<R: BufRead>(mut reader: R) -> Result<PointCloud, PointCloudError> {
+    let header = read_header(&mut reader)?;
+    let has = |target: Target| header.fields.iter().any(|f| f.target == target);
+    let mut columns = Columns::new(
+        header.points,
+        has(Target::Attribute(Attribute::Normal(0))),
+        has(Target::Rgb),
+        has(Target::Attribute(Attribute::Intensity)),
+    );
+    match header.encoding {
+        Encoding::Ascii => read_ascii(&mut reader, &header, &mut columns)?,
+        Encoding::Binary => read_binary(&mut reader, &header, &mut columns)?,
+    }
+    columns.into_cloud()
+}
+
+/// Writes `cloud` as PCD v0.7 with `F4` coordinates, normals and intensity
+/// and a `U4` packed `rgb`.
+pub fn write<W: Write>(
+    cloud: &PointCloud,
+    mut writer: W,
+    encoding: Encoding,
+) -> Result<(), PointCloudError> {
+    let mut floats = vec![cloud.x(), cloud.y(), cloud.z()];
+    let mut names = vec!["x", "y", "z"];
+    if let Some(normals) = cloud.normals() {
+        floats.extend(normals);
+        names.extend(["normal_x", "normal_y", "normal_z"]);
+    }
+    let intensity = cloud.intensity();
+    if let Some(intensity) = intensity {
+        floats.push(intensity);
+        names.push("intensity");
+    }
+    let colors = cloud.colors();
+    let mut types = vec!["F"; names.len()];
+    if colors.is_some() {
+        names.push("rgb");
+        types.push("U");
+    }
+
+    writeln!(writer, "# .PCD v0.7 - Point Cloud Data file format")?;
+    writeln!(writer, "VERSION 0.7")?;
+    writeln!(writer, "FIELDS {}", names.join(" "))?;
+    writeln!(writer, "SIZE {}", vec!["4"; names.len()].join(" "))?;
+    writeln!(writer, "TYPE {}", types.join(" "))?;
+    writeln!(writer, "COUNT {}", vec!["1"; names.len()].join(" "))?;
+    writeln!(writer, "WIDTH {}", cloud.len())?;
+    writeln!(writer, "HEIGHT 1")?;
+    writeln!(writer, "VIEWPOINT 0 0 0 1 0 0 0")?;
+    writeln!(writer, "POINTS {}", cloud.len())?;
+    writeln!(
+        writer,
+        "DATA {}",
+        match encoding {
+            Encoding::Ascii => "ascii",
+            Encoding::Binary => "binary",
+        }
+    )?;
+
+    let mut record = Vec::new();
+    for i in 0..cloud.len() {
+        record.clear();
+        let rgb = colors
+            .map(|[r, g, b]| (u32::from(r[i]) << 16) | (u32::from(g[i]) << 8) | u32::from(b[i]));
+        match encoding {
+            Encoding::Ascii => {
+                let mut line: Vec<String> =
+                    floats.iter().map(|column| column[i].to_string()).collect();
+                line.extend(rgb.map(|v| v.to_string()));
+                writeln!(record, "{}", line.join(" "))?;
+            }
+            Encoding::Binary => {
+                for column in &floats {
+                    record.extend(column[i].to_le_bytes());
+                }
+                if let Some(rgb) = rgb {
+                    record.extend(rgb.to_le_bytes());
+                }
+            }
+        }
+        writer.write_all(&record)?;
+    }
+    Ok(())
+}
+
+fn read_header<R: BufRead>(reader: &mut R) -> Result<Header, PointCloudError> {
+    let mut buf = Vec::new();
+    let mut line_no = 0;
+    let mut names: Option<Vec<String>> = None;
+    let mut sizes: Option<Vec<usize>> = None;
+    let mut types: Option<Vec<char>> = None;
+    let mut counts: Option<Vec<usize>> = None;
+    let mut width = None;
+    let mut height = None;
+    let mut points = None;
+
+    let encoding = loop {
+        let line = read_line(reader, &mut buf)?
+            .ok_or_else(|| parse_error(None, "unexpected end of file in the header"))?;
+        line_no += 1;
+        let line = line.split('#').next().unwrap_or_default();
+        let mut tokens = line.split_whitespace();
+        let Some(key) = tokens.next() else {
+            continue;
+        };
+        let values: Vec<&str> = tokens.collect();
+        let number = |token: &str| {
+            token
+                .parse::<usize>()
+                .map_err(|_| parse_error(Some(line_no), format!("invalid {key} value `{token}`")))
+        };
+        let single = || match values.as_slice() {
+            [value] => number(value),
+            _ => Err(parse_error(Some(line_no), format!("{key} takes one value"))),
+        };
+        match key.to_ascii_uppercase().as_str() {
+            "VERSION" => {
+                if !matches!(values.as_slice(), ["0.6" | ".6" | "0.7" | ".7"]) {
+                    return Err(PointCloudError::Unsupported(format!(
+                        "PCD version {}",
+                        values.join(" ")
+                    )));
+                }
+            }
+            "FIELDS" | "COLUMNS" => names = Some(values.iter().map(|v| v.to_string()).collect()),
+            "SIZE" => sizes = Some(values.iter().map(|v| number(v)).collect::<Result<_, _>>()?),
+            "TYPE" => {
+                types = Some(
+                    values
+                        .iter()
+                        .map(|v| match v.as_bytes() {
+                            [c] => Ok(char::from(*c).to_ascii_uppercase()),
+                            _ => Err(parse_error(
+                                Some(line_no),
+                                format!("invalid TYPE value `{v}`"),
+                            )),
+                        })
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            "COUNT" => counts = Some(values.iter().map(|v| number(v)).collect::<Result<_, _>>()?),
+            "WIDTH" => width = Some(single()?),
+            "HEIGHT" => height = Some(single()?),
+            "POINTS" => points = Some(single()?),
+            "VIEWPOINT" => {}
+            "DATA" => match values.as_slice() {
+                ["ascii"] => break Encoding::Ascii,
+                ["binary"] => break Encoding::Binary,
+                ["binary_compressed"] => {
+                    return Err(PointCloudError::Unsupported(
+                        "binary_compressed PCD data".into(),
+                    ));
+                }
+                _ => {
+                    return Err(parse_error(
+                        Some(line_no),
+                        format!("unknown DATA `{}`", values.join(" ")),
+                    ));
+                }
+            },
+            _ => {
+                return Err(parse_error(
+                    Some(line_no),
+                    format!("unknown header key `{key}`"),
+                ));
+            }
+        }
+    };
+
+    let names = names.ok_or_else(|| parse_error(None, "missing FIELDS"))?;
+    let sizes = sizes.ok_or_else(|| parse_error(None, "missing SIZE"))?;
+    let types = types.ok_or_else(|| parse_error(None, "missing TYPE"))?;
+    let counts = counts.unwrap_or_else(|| vec![1; names.len()]);
+    if sizes.len() != names.len() || types.len() != names.len() || counts.len() != names.len() {
+        return Err(parse_error(
+            None,
+            format!(
+                "FIELDS, SIZE, TYPE and COUNT have {}, {}, {} and {} entries",
+                names.len(),
+                sizes.len(),
+                types.len(),
+                counts.len()
+            ),
+        ));
+    }
+
+    let width = width.ok_or_else(|| parse_error(None, "missing WIDTH"))?;
+    let height = height.unwrap_or(1);
+    let organized = width
+        .checked_mul(height)
+        .ok_or_else(|| parse_error(None, "WIDTH * HEIGHT overflows"))?;
+    let points = points.unwrap_or(organized);
+    if points != organized {
+        return Err(parse_error(
+            None,
+            format!("POINTS is {points} but WIDTH * HEIGHT is {organized}"),
+        ));
+    }
+
+    let mut fields = Vec::with_capacity(names.len());
+    let mut point_size = 0usize;
+    for (((name, size), ty), count) in names.into_iter().zip(sizes).zip(types).zip(counts) {
+        let ty = match (ty, size) {
+            ('I', 1) => ScalarType::I8,
+            ('I', 2) => ScalarType::I16,
+            ('I', 4) => ScalarType::I32,
+            ('U', 1) => ScalarType::U8,
+            ('U', 2) => ScalarType::U16,
+            ('U', 4) => ScalarType::U32,
+            ('F', 4) => ScalarType::F32,
+            ('F', 8) => ScalarType::F64,
+            _ => {
+                return Err(PointCloudError::Unsupported(format!(
+                    "field `{name}` of TYPE {ty} and SIZE {size}"
+                )));
+            }
+        };
+        let target = match name.as_str() {
+            "x" => Target::Attribute(Attribute::Position(0)),
+            "y" => Target::Attribute(Attribute::Position(1)),
+            "z" => Target::Attribute(Attribute::Position(2)),
+            "normal_x" => Target::Attribute(Attribute::Normal(0)),
+            "normal_y" => Target::Attribute(Attribute::Normal(1)),
+            "normal_z" => Target::Attribute(Attribute::Normal(2)),
+            "intensity" => Target::Attribute(Attribute::Intensity),
+            "rgb" | "rgba" if ty.size() == 4 => Target::Rgb,
+            _ => Target::Skip,
+        };
+        if target != Target::Skip {
+            if count != 1 {
+                return Err(parse_error(
+                    None,
+                    format!("field `{name}` must have COUNT 1"),
+                ));
+            }
+            if fields.iter().any(|f: &Field| f.target == target) {
+                return Err(parse_error(None, format!("duplicate field `{name}`")));
+            }
+        }
+        let bytes = ty
+            .size()
+            .checked_mul(count)
+            .and_then(|bytes| {
+                point_size = point_size.checked_add(bytes)?;
+                Some(bytes)
+            })
+            .ok_or_else(|| {
+                parse_error(
+                    None,
+                    format!("COUNT {count} of `{name}` overflows the point size"),
+                )
+            })?;
+        fields.push(Field {
+            name,
+            ty,
+            count,
+            bytes,
+            target,
+        });
+    }
+
+    let has = |attribute| {
+        fields
+            .iter()
+            .any(|f| f.target == Target::Attribute(attribute))
+    };
+    if !(0..3).all(|axis| has(Attribute::Position(axis))) {
+        return Err(parse_error(None, "FIELDS needs all of x, y, z"));
+    }
+    let normals = (0..3).filter(|&axis| has(Attribute::Normal(axis))).count();
+    if normals != 0 && normals != 3 {
+        return Err(parse_error(
+            None,
+            "FIELDS needs all of normal_x, normal_y, normal_z",
+        ));
+    }
+
+    Ok(Header {
+        fields,
+        points,
+        encoding,
+        lines: line_no,
+    })
+}
+
+fn unpack_rgb(packed: u32) -> [u8; 3] {
+    [(packed >> 16) as u8, (packed >> 8) as u8, packed as u8]
+}
+
+fn read_ascii<R: BufRead>(
+    reader: &mut R,
+    header: &Header,
+    columns: &mut Columns,
+) -> Result<(), PointCloudError> {
+    let mut buf = Vec::new();
+    let mut line_no = header.lines;
+    for i in 0..header.points {
+        let line = loop {
+            let line = read_line(reader, &mut buf)?
+                .ok_or_else(|| parse_error(None, format!("unexpected end of file in point {i}")))?;
+            line_no += 1;
+            if !line.trim().is_empty() {
+                break line;
+            }
+        };
+
+        let mut tokens = line.split_whitespace();
+        for field in &header.fields {
+            for _ in 0..field.count {
+                let token = tokens.next().ok_or_else(|| {
+                    parse_error(Some(line_no), format!("missing value for `{}`", field.name))
+                })?;
+                let invalid = || {
+                    parse_error(
+                        Some(line_no),
+                        format!("invalid value `{token}` for `{}`", field.name),
+                    )
+                };
+                match field.target {
+                    Target::Attribute(attribute) => columns.push(
+                        attribute,
+                        field.ty.parse(token).ok_or_else(invalid)?,
+                        field.ty,
+                    ),
+                    // PCL writes a float `rgb` as the decimal float whose
+                    // bits are the packed colour.
+                    Target::Rgb if field.ty == ScalarType::F32 => {
+                        let value: f32 = token.parse().map_err(|_| invalid())?;
+                        columns.push_rgb(unpack_rgb(value.to_bits()));
+                    }
+                    Target::Rgb => {
+                        let value = field.ty.parse(token).ok_or_else(invalid)?;
+                        columns.push_rgb(unpack_rgb(value as i64 as u32));
+                    }
+                    Target::Skip => {}
+                }
+            }
+        }
+        if tokens.next().is_some() {
+            return Err(parse_error(
+                Some(line_no),
+                format!("too many values for point {i}"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn read_binary<R: BufRead>(
+    reader: &mut R,
+    header: &Header,
+    columns: &mut Columns,
+) -> Result<(), PointCloudError> {
+    // Fields that are kept have COUNT 1; skipped ones are streamed past
+    // rather than buffered, whatever their COUNT.
+    let mut buf = [0u8; 8];
+    for i in 0..header.points {
+        for field in &header.fields {
+            if field.target == Target::Skip {
+                let skipped = io::copy(
+                    &mut reader.by_ref().take(field.bytes as u64),
+                    &mut io::sink(),
+                )?;
+                if skipped != field.bytes as u64 {
+                    return Err(parse_error(
+                        None,
+                        format!("unexpected end of file in point {i}"),
+                    ));
+                }
+                continue;
+            }
+            let bytes = &mut buf[..field.bytes];
+            read_exact(reader, bytes, || format!("point {i}"))?;
+            match field.target {
+                Target::Attribute(attribute) => {
+                    columns.push(attribute, field.ty.decode(bytes, false), field.ty)
+                }
+                Target::Rgb => columns.push_rgb(unpack_rgb(u32::from_le_bytes(
+                    bytes[..4].try_into().unwrap(),
+                ))),
+                Target::Skip => unreachable!(),
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::sample;
+    use super::*;
+
+    fn read_str(text: &str) -> Result<PointCloud, PointCloudError> {
+        read(text.as_bytes())
+    }
+
+    fn write_vec(cloud: &PointCloud, encoding: Encoding) -> Vec<u8> {
+        let mut out = Vec::new();
+        write(cloud, &mut out, encoding).unwrap();
+        out
+    }
+
+    const HEADER: &str = "VERSION .7\nFIELDS x y z\nSIZE 4 4 4\nTYPE F F F\nWIDTH 2\nHEIGHT 1\nPOINTS 2\nDATA ascii\n";
+
+    #[test]
+    fn round_trips_both_encodings() {
+        let cloud = sample();
+        for encoding in [Encoding::Ascii, Encoding::Binary] {
+            let bytes = write_vec(&cloud, encoding);
+            assert_eq!(read(bytes.as_slice()).unwrap(), cloud, "{encoding:?}");
+        }
+
+        let bare = PointCloud::from_points(&[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+        let bytes = write_vec(&bare, Encoding::Ascii);
+        assert_eq!(read(bytes.as_slice()).unwrap(), bare);
+    }
+
+    #[test]
+    fn reads_pcl_style_files() {
+        // PCL packs rgb into the bits of a float and pads with `_` fields.
+        let rgb = f32::from_bits(0x00ff_8001);
+        let text = format!(
+            "# .PCD v0.7 - Point Cloud Data file format\n\
+             VERSION 0.7\n\
+             FIELDS x y z _ rgb curvature\n\
+             SIZE 4 4 8 1 4 4\n\
+             TYPE F F F U F F\n\
+             COUNT 1 1 1 4 1 1\n\
+             WIDTH 1\n\
+             HEIGHT 2\n\
+             VIEWPOINT 0 0 0 1 0 0 0\n\
+             POINTS 2\n\
+             DATA ascii\n\
+             1 2 3 0 0 0 0 {rgb:e} 0.5\n\
+             4 5 6 0 0 0 0 0 0.25\n"
+        );
+        let cloud = read_str(&text).unwrap();
+        assert_eq!(
+            cloud.points().collect::<Vec<_>>(),
+            [[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]
+        );
+        assert_eq!(cloud.colors().unwrap(), [&[255, 0][..], &[128, 0], &[1, 0]]);
+
+        let mut binary =
+            (text[..text.find("DATA").unwrap()].to_string() + "DATA binary\n").into_bytes();
+        for (p, packed) in [([1.0f32, 2.0], 0x0001_0203u32), ([3.0, 4.0], 0)] {
+            binary.extend(p[0].to_le_bytes());
+            binary.extend(p[1].to_le_bytes());
+            binary.extend(7.5f64.to_le_bytes());
+            binary.extend([9; 4]);
+            binary.extend(packed.to_le_bytes());
+            binary.extend(0f32.to_le_bytes());
+        }
+        let cloud = read(binary.as_slice()).unwrap();
+        assert_eq!(cloud.z(), [7.5, 7.5]);
+        assert_eq!(cloud.colors().unwrap(), [&[1, 0][..], &[2, 0], &[3, 0]]);
+    }
+
+    #[test]
+    fn reports_malformed_headers() {
+        assert!(matches!(
+            read_str(&HEADER.replace("DATA ascii", "DATA binary_compressed")),
+            Err(PointCloudError::Unsupported(_))
+        ));
+        assert!(matches!(
+            read_str(&HEADER.replace("TYPE F F F", "TYPE F F G")),
+            Err(PointCloudError::Unsupported(_))
+        ));
+        let err = read_str(&HEADER.replace("POINTS 2", "POINTS 3")).unwrap_err();
+        assert!(err.to_string().contains("WIDTH * HEIGHT"), "{err}");
+        let err = read_str(&HEADER.replace("WIDTH 2", "WIDTH two")).unwrap_err();
+        assert!(err.to_string().starts_with("line 5:"), "{err}");
+        assert!(read_str(&HEADER.replace("SIZE 4 4 4", "SIZE 4 4")).is_err());
+        assert!(read_str(&HEADER.replace("x y z", "x y w")).is_err());
+        assert!(read_str("VERSION .7\nFIELDS x y z\n").is_err());
+
+        // Skipped fields may have any COUNT, but not one that overflows the
+        // size of a point.
+        let skipped = |count: &str| {
+            HEADER
+                .replace("x y z", "x y z pad")
+                .replace("SIZE 4 4 4", "SIZE 4 4 4 4")
+                .replace("TYPE F F F", "TYPE F F F U")
+                .replace("WIDTH", &format!("COUNT 1 1 1 {count}\nWIDTH"))
+                .replace("DATA ascii", "DATA binary")
+        };
+        let err = read_str(&skipped("4611686018427387904")).unwrap_err();
+        assert!(
+            err.to_string().contains("overflows the point size"),
+            "{err}"
+        );
+        // Too large to buffer, so the file ends well before the point does.
+        let err = read_str(&skipped("1000000000000")).unwrap_err();
+        assert!(err.to_string().contains("end of file in point 0"), "{err}");
+    }
+
+    #[test]
+    fn reports_malformed_data() {
+        let err = read_str(&format!("{HEADER}1 2 3\n4 5 six\n")).unwrap_err();
+        assert!(
+            err.to_string().starts_with("line 10:") && err.to_string().contains("`six`"),
+            "{err}"
+        );
+        let err = read_str(&format!("{HEADER}1 2 3 4\n4 5 6\n")).unwrap_err();
+        assert!(err.to_string().starts_with("line 9:"), "{err}");
+        let err = read_str(&format!("{HEADER}1 2 3\n")).unwrap_err();
+        assert!(err.to_string().contains("end of file in point 1"), "{err}");
+
+        let mut bytes = write_vec(&sample(), Encoding::Binary);
+        bytes.pop();
+        let err = read(bytes.as_slice()).unwrap_err();
+        assert!(err.to_string().contains("end of file in point 2"), "{err}");
+    }
+}
@@ -0,0 +1,595 @@
+//! Stanford PLY, ASCII and binary in either byte order.
+//!
+//! Points come from the `vertex` element: `x`, `y` and `z` are required;
+//! `nx`/`ny`/`nz`, `red`/`green`/`blue` and `intensity` are read when
+//! present. Other vertex properties and the elements before `vertex` are
+//! skipped. Reading stops after the vertex element, so faces or edges that
+//! follow it are never looked at.
+
+use std::io::{BufRead, Write};
+
+use super::{
+    Attribute, Columns, PointCloud, PointCloudError, ScalarType, parse_error, read_exact, read_line,
+};
+
+/// Body encoding of a PLY file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum PropertyKind {
+    Scalar(ScalarType),
+    List { count: ScalarType, item: ScalarType },
+}
+
+#[derive(Debug)]
+struct Property {
+    name: String,
+    kind: PropertyKind,
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+struct Header {
+    encoding: Encoding,
+    elements: Vec<Element>,
+    /// Number of lines up to and including `end_header`.
+    lines: usize,
+}
+
+/// Reads a PLY file.
+pub fn read<R: BufRead>(mut reader: R) -> Result<PointCloud, PointCloudError> {
+    let header = read_header(&mut reader)?;
+    let vertex = header
+        .elements
+        .iter()
+        .position(|e| e.name == "vertex")
+        .ok_or_else(|| parse_error(None, "no `vertex` element"))?;
+    let elements = &header.elements[..=vertex];
+    let targets = vertex_targets(&elements[vertex])?;
+    let mut columns = Columns::new(
+        elements[vertex].count,
+        targets.contains(&Some(Attribute::Normal(0))),
+        targets.contains(&Some(Attribute::Color(0))),
+        targets.contains(&Some(Attribute::Intensity)),
+    );
+
+    match header.encoding {
+        Encoding::Ascii => read_ascii(&mut reader, elements, &targets, header.lines, &mut columns)?,
+        Encoding::BinaryLittleEndian => {
+            read_binary(&mut reader, elements, &targets, false, &mut columns)?
+        }
+        Encoding::BinaryBigEndian => {
+            read_binary(&mut reader, elements, &targets, true, &mut columns)?
+        }
+    }
+    columns.into_cloud()
+}
+
+/// Writes `cloud` with `float` coordinates, normals and intensity and
+/// `uchar` colours.
+pub fn write<W: Write>(
+    cloud: &PointCloud,
+    mut writer: W,
+    encoding: Encoding,
+) -> Result<(), PointCloudError> {
+    let format = match encoding {
+        Encoding::Ascii => "ascii",
+        Encoding::BinaryLittleEndian => "binary_little_endian",
+        Encoding::BinaryBigEndian => "binary_big_endian",
+    };
+    writeln!(writer, "ply")?;
+    writeln!(writer, "format {format} 1.0")?;
+    writeln!(writer, "element vertex {}", cloud.len())?;
+
+    let mut floats = vec![cloud.x(), cloud.y(), cloud.z()];
+    let mut float_names = vec!["x", "y", "z"];
+    if let Some(normals) = cloud.normals() {
+        floats.extend(normals);
+        float_names.extend(["nx", "ny", "nz"]);
+    }
+    for name in float_names {
+        writeln!(writer, "property float {name}")?;
+    }
+    let colors = cloud.colors();
+    if colors.is_some() {
+        for name in ["red", "green", "blue"] {
+            writeln!(writer, "property uchar {name}")?;
+        }
+    }
+    let intensity = cloud.intensity();
+    if intensity.is_some() {
+        writeln!(writer, "property float intensity")?;
+    }
+    writeln!(writer, "end_header")?;
+
+    let mut record = Vec::new();
+    for i in 0..cloud.len() {
+        record.clear();
+        let values = floats
+            .iter()
+            .map(|column| Value::Float(column[i]))
+            .chain(
+                colors
+                    .iter()
+                    .flatten()
+                    .map(|column| Value::Color(column[i])),
+            )
+            .chain(intensity.map(|column| Value::Float(column[i])));
+        match encoding {
+            Encoding::Ascii => {
+                let line: Vec<String> = values.map(|v| v.to_string()).collect();
+                writeln!(record, "{}", line.join(" "))?;
+            }
+            Encoding::BinaryLittleEndian => {
+                for value in values {
+                    match value {
+                        Value::Float(v) => record.extend(v.to_le_bytes()),
+                        Value::Color(v) => record.push(v),
+                    }
+                }
+            }
+            Encoding::BinaryBigEndian => {
+                for value in values {
+                    match value {
+                        Value::Float(v) => record.extend(v.to_be_bytes()),
+                        Value::Color(v) => record.push(v),
+                    }
+                }
+            }
+        }
+        writer.write_all(&record)?;
+    }
+    Ok(())
+}
+
+enum Value {
+    Float(f32),
+    Color(u8),
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Float(v) => write!(f, "{v}"),
+            Value::Color(v) => write!(f, "{v}"),
+        }
+    }
+}
+
+fn scalar_type(name: &str) -> Option<ScalarType> {
+    Some(match name {
+        "char" | "int8" => ScalarType::I8,
+        "uchar" | "uint8" => ScalarType::U8,
+        "short" | "int16" => ScalarType::I16,
+        "ushort" | "uint16" => ScalarType::U16,
+        "int" | "int32" => ScalarType::I32,
+        "uint" | "uint32" => ScalarType::U32,
+        "float" | "float32" => ScalarType::F32,
+        "double" | "float64" => ScalarType::F64,
+        _ => return None,
+    })
+}
+
+fn read_header<R: BufRead>(reader: &mut R) -> Result<Header, PointCloudError> {
+    let mut buf = Vec::new();
+    let mut line_no = 1;
+    if read_line(reader, &mut buf)?.as_deref() != Some("ply") {
+        return Err(parse_error(Some(1), "not a PLY file: missing `ply` magic"));
+    }
+
+    let mut encoding = None;
+    let mut elements: Vec<Element> = Vec::new();
+    loop {
+        let line = read_line(reader, &mut buf)?
+            .ok_or_else(|| parse_error(None, "unexpected end of file in the header"))?;
+        line_no += 1;
+        let type_of = |name: &str| {
+            scalar_type(name).ok_or_else(|| {
+                parse_error(Some(line_no), format!("unknown property type `{name}`"))
+            })
+        };
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            [] | ["comment", ..] | ["obj_info", ..] => {}
+            ["format", format, version] => {
+                if encoding.is_some() {
+                    return Err(parse_error(Some(line_no), "duplicate `format` line"));
+                }
+                if *version != "1.0" {
+                    return Err(PointCloudError::Unsupported(format!(
+                        "PLY version {version}"
+                    )));
+                }
+                encoding = Some(match *format {
+                    "ascii" => Encoding::Ascii,
+                    "binary_little_endian" => Encoding::BinaryLittleEndian,
+                    "binary_big_endian" => Encoding::BinaryBigEndian,
+                    _ => {
+                        return Err(parse_error(
+                            Some(line_no),
+                            format!("unknown format `{format}`"),
+                        ));
+                    }
+                });
+            }
+            ["element", name, count] => {
+                let count = count.parse().map_err(|_| {
+                    parse_error(Some(line_no), format!("invalid element count `{count}`"))
+                })?;
+                elements.push(Element {
+                    name: name.to_string(),
+                    count,
+                    properties: Vec::new(),
+                });
+            }
+            ["property", rest @ ..] => {
+                let (kind, name) = match rest {
+                    ["list", count, item, name] => (
+                        PropertyKind::List {
+                            count: type_of(count)?,
+                            item: type_of(item)?,
+                        },
+                        name,
+                    ),
+                    [ty, name] => (PropertyKind::Scalar(type_of(ty)?), name),
+                    _ => {
+                        return Err(parse_error(
+                            Some(line_no),
+                            format!("malformed property `{line}`"),
+                        ));
+                    }
+                };
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| parse_error(Some(line_no), "property before any element"))?;
+                element.properties.push(Property {
+                    name: name.to_string(),
+                    kind,
+                });
+            }
+            ["end_header"] => break,
+            _ => {
+                return Err(parse_error(
+                    Some(line_no),
+                    format!("unexpected header line `{line}`"),
+                ));
+            }
+        }
+    }
+
+    Ok(Header {
+        encoding: encoding.ok_or_else(|| parse_error(None, "missing `format` line"))?,
+        elements,
+        lines: line_no,
+    })
+}
+
+/// The column each vertex property is read into, `None` for skipped ones.
+fn vertex_targets(vertex: &Element) -> Result<Vec<Option<Attribute>>, PointCloudError> {
+    let targets: Vec<Option<Attribute>> = vertex
+        .properties
+        .iter()
+        .map(|p| match p.name.as_str() {
+            "x" => Some(Attribute::Position(0)),
+            "y" => Some(Attribute::Position(1)),
+            "z" => Some(Attribute::Position(2)),
+            "nx" => Some(Attribute::Normal(0)),
+            "ny" => Some(Attribute::Normal(1)),
+            "nz" => Some(Attribute::Normal(2)),
+            "red" => Some(Attribute::Color(0)),
+            "green" => Some(Attribute::Color(1)),
+            "blue" => Some(Attribute::Color(2)),
+            "intensity" => Some(Attribute::Intensity),
+            _ => None,
+        })
+        .collect();
+
+    for (property, target) in vertex.properties.iter().zip(&targets) {
+        if target.is_some() && matches!(property.kind, PropertyKind::List { .. }) {
+            return Err(parse_error(
+                None,
+                format!("vertex property `{}` is a list", property.name),
+            ));
+        }
+        if target.is_some() && targets.iter().filter(|t| *t == target).count() > 1 {
+            return Err(parse_error(
+                None,
+                format!("duplicate vertex property `{}`", property.name),
+            ));
+        }
+    }
+    let count = |attributes: &[Attribute]| {
+        attributes
+            .iter()
+            .filter(|a| targets.contains(&Some(**a)))
+            .count()
+    };
+    let groups: [(&[Attribute], &str); 3] = [
+        (
+            &[
+                Attribute::Position(0),
+                Attribute::Position(1),
+                Attribute::Position(2),
+            ],
+            "x, y, z",
+        ),
+        (
+            &[
+                Attribute::Normal(0),
+                Attribute::Normal(1),
+                Attribute::Normal(2),
+            ],
+            "nx, ny, nz",
+        ),
+        (
+            &[
+                Attribute::Color(0),
+                Attribute::Color(1),
+                Attribute::Color(2),
+            ],
+            "red, green, blue",
+        ),
+    ];
+    for (i, (attributes, names)) in groups.into_iter().enumerate() {
+        let found = count(attributes);
+        // Normals and colours are optional, but only as a complete set.
+        if found != 3 && (i == 0 || found != 0) {
+            return Err(parse_error(None, format!("vertex needs all of {names}")));
+        }
+    }
+    Ok(targets)
+}
+
+fn read_ascii<R: BufRead>(
+    reader: &mut R,
+    elements: &[Element],
+    targets: &[Option<Attribute>],
+    mut line_no: usize,
+    columns: &mut Columns,
+) -> Result<(), PointCloudError> {
+    let mut buf = Vec::new();
+    let vertex = elements.len() - 1;
+    for (e, element) in elements.iter().enumerate() {
+        for i in 0..element.count {
+            let line = loop {
+                let line = read_line(reader, &mut buf)?.ok_or_else(|| {
+                    parse_error(
+                        None,
+                        format!("unexpected end of file in {} {i}", element.name),
+                    )
+                })?;
+                line_no += 1;
+                if !line.trim().is_empty() {
+                    break line;
+                }
+            };
+
+            let mut tokens = line.split_whitespace();
+            let mut next = |ty: ScalarType, property: &str| {
+                let token = tokens.next().ok_or_else(|| {
+                    parse_error(Some(line_no), format!("missing value for `{property}`"))
+                })?;
+                ty.parse(token).ok_or_else(|| {
+                    parse_error(
+                        Some(line_no),
+                        format!("invalid value `{token}` for `{property}`"),
+                    )
+                })
+            };
+            for (p, property) in element.properties.iter().enumerate() {
+                match property.kind {
+                    PropertyKind::Scalar(ty) => {
+                        let value = next(ty, &property.name)?;
+                        if let (true, Some(target)) = (e == vertex, targets[p]) {
+                            columns.push(target, value, ty);
+                        }
+                    }
+                    PropertyKind::List { count, item } => {
+                        let len = list_len(next(count, &property.name)?, Some(line_no))?;
+                        for _ in 0..len {
+                            next(item, &property.name)?;
+                        }
+                    }
+                }
+            }
+            if tokens.next().is_some() {
+                return Err(parse_error(
+                    Some(line_no),
+                    format!("too many values for {} {i}", element.name),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_binary<R: BufRead>(
+    reader: &mut R,
+    elements: &[Element],
+    targets: &[Option<Attribute>],
+    big_endian: bool,
+    columns: &mut Columns,
+) -> Result<(), PointCloudError> {
+    let mut buf = [0u8; 8];
+    let vertex = elements.len() - 1;
+    for (e, element) in elements.iter().enumerate() {
+        for i in 0..element.count {
+            let mut next = |ty: ScalarType| {
+                let bytes = &mut buf[..ty.size()];
+                read_exact(reader, bytes, || format!("{} {i}", element.name))?;
+                Ok::<_, PointCloudError>(ty.decode(bytes, big_endian))
+            };
+            for (p, property) in element.properties.iter().enumerate() {
+                match property.kind {
+                    PropertyKind::Scalar(ty) => {
+                        let value = next(ty)?;
+                        if let (true, Some(target)) = (e == vertex, targets[p]) {
+                            columns.push(target, value, ty);
+                        }
+                    }
+                    PropertyKind::List { count, item } => {
+                        for _ in 0..list_len(next(count)?, None)? {
+                            next(item)?;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn list_len(value: f64, line: Option<usize>) -> Result<usize, PointCloudError> {
+    if value >= 0.0 && value.fract() == 0.0 {
+        Ok(value as usize)
+    } else {
+        Err(parse_error(line, format!("invalid list length {value}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::sample;
+    use super::*;
+
+    fn read_str(text: &str) -> Result<PointCloud, PointCloudError> {
+        read(text.as_bytes())
+    }
+
+    fn write_vec(cloud: &PointCloud, encoding: Encoding) -> Vec<u8> {
+        let mut out = Vec::new();
+        write(cloud, &mut out, encoding).unwrap();
+        out
+    }
+
+    fn parse_line(err: PointCloudError) -> Option<usize> {
+        match err {
+            PointCloudError::Parse { line, .. } => line,
+            other => panic!("expected a parse error, got {other}"),
+        }
+    }
+
+    #[test]
+    fn round_trips_every_encoding() {
+        let cloud = sample();
+        for encoding in [
+            Encoding::Ascii,
+            Encoding::BinaryLittleEndian,
+            Encoding::BinaryBigEndian,
+        ] {
+            let bytes = write_vec(&cloud, encoding);
+            assert_eq!(read(bytes.as_slice()).unwrap(), cloud, "{encoding:?}");
+        }
+
+        let bare = PointCloud::from_points(&[[1.0, 2.0, 3.0]]);
+        let bytes = write_vec(&bare, Encoding::BinaryLittleEndian);
+        assert_eq!(read(bytes.as_slice()).unwrap(), bare);
+    }
+
+    #[test]
+    fn reads_foreign_ascii_files() {
+        let text = "ply\r\n\
+                    format ascii 1.0\r\n\
+                    comment made by hand\r\n\
+                    element material 1\r\n\
+                    property list uchar int ids\r\n\
+                    element vertex 2\r\n\
+                    property double x\r\n\
+                    property double y\r\n\
+                    property double z\r\n\
+                    property int flags\r\n\
+                    property float red\r\n\
+                    property float green\r\n\
+                    property float blue\r\n\
+                    element face 1\r\n\
+                    property list uchar int vertex_indices\r\n\
+                    end_header\r\n\
+                    3 7 8 9\r\n\
+                    1.5 2 -3 0 1 0.5 0\r\n\
+                    \r\n\
+                    4 5 6 1 0 0 1\r\n\
+                    3 0 1 1\r\n";
+        let cloud = read_str(text).unwrap();
+        assert_eq!(cloud.x(), [1.5, 4.0]);
+        assert_eq!(cloud.z(), [-3.0, 6.0]);
+        assert_eq!(
+            cloud.colors().unwrap(),
+            [&[255, 0][..], &[128, 0], &[0, 255]]
+        );
+        assert!(cloud.normals().is_none() && cloud.intensity().is_none());
+    }
+
+    #[test]
+    fn reports_malformed_headers() {
+        assert_eq!(parse_line(read_str("PLY\n").unwrap_err()), Some(1));
+        let err =
+            read_str("ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\n").unwrap_err();
+        assert!(
+            err.to_string().contains("end of file in the header"),
+            "{err}"
+        );
+        let err =
+            read_str("ply\nformat ascii 1.0\nelement vertex 1\nproperty float128 x\nend_header\n")
+                .unwrap_err();
+        assert!(err.to_string().starts_with("line 4:"), "{err}");
+        assert!(matches!(
+            read_str("ply\nformat ascii 2.0\nend_header\n"),
+            Err(PointCloudError::Unsupported(_))
+        ));
+
+        let header = |properties: &str| {
+            format!("ply\nformat ascii 1.0\nelement vertex 0\n{properties}end_header\n")
+        };
+        for properties in [
+            "property float x\nproperty float y\n",
+            "property float x\nproperty float y\nproperty float z\nproperty float nx\n",
+            "property float x\nproperty float x\nproperty float y\nproperty float z\n",
+            "property list uchar float x\nproperty float y\nproperty float z\n",
+        ] {
+            assert!(read_str(&header(properties)).is_err(), "{properties}");
+        }
+        assert!(read_str("ply\nformat ascii 1.0\nelement face 0\nend_header\n").is_err());
+    }
+
+    #[test]
+    fn reports_malformed_ascii_data_by_line() {
+        let header = "ply\nformat ascii 1.0\nelement vertex 2\nproperty float x\nproperty float y\nproperty float z\nend_header\n";
+        let err = read_str(&format!("{header}1 2 3\n4 five 6\n")).unwrap_err();
+        assert!(err.to_string().contains("`five`"), "{err}");
+        assert_eq!(parse_line(err), Some(9));
+        assert_eq!(
+            parse_line(read_str(&format!("{header}1 2 3\n4 5\n")).unwrap_err()),
+            Some(9)
+        );
+        assert_eq!(
+            parse_line(read_str(&format!("{header}1 2 3 4\n")).unwrap_err()),
+            Some(8)
+        );
+        let err = read_str(&format!("{header}1 2 3\n")).unwrap_err();
+        assert!(err.to_string().contains("end of file in vertex 1"), "{err}");
+    }
+
+    #[test]
+    fn reports_truncated_binary_data() {
+        let mut bytes = write_vec(&sample(), Encoding::BinaryBigEndian);
+        bytes.truncate(bytes.len() - 3);
+        let err = read(bytes.as_slice()).unwrap_err();
+        assert!(err.to_string().contains("end of file in vertex 2"), "{err}");
+    }
+
+    #[test]
+    fn huge_counts_fail_without_allocating_them() {
+        let text = "ply\nformat binary_little_endian 1.0\nelement vertex 18446744073709551615\nproperty float x\nproperty float y\nproperty float z\nend_header\n";
+        assert!(read_str(text).is_err());
+    }
+}
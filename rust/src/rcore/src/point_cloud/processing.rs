@@ -0,0 +1,509 @@
+//! Point cloud filters and normal estimation.
+//!
+//! These are the straightforward versions: every neighbour search scans the
+//! whole cloud, so most kernels are quadratic in the number of points. They
+//! define the expected results of the kd-tree based kernels in the native
+//! core, which `bridge` exposes for clouds of any real size.
+//!
+//! Points with a NaN or infinite coordinate are skipped by every kernel:
+//! they are never part of an output and never count as neighbours.
+//! Distances are computed in `f64`, and neighbours are ordered by
+//! (distance, index).
+
+use std::collections::BTreeMap;
+
+use super::{PointCloud, PointCloudError};
+
+/// A point found by a neighbour query.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Neighbor {
+    /// Index of the point in the cloud.
+    pub index: usize,
+    /// Squared euclidean distance to the query.
+    pub squared_distance: f64,
+}
+
+impl PointCloud {
+    /// The `k` finite points nearest to `query`, or all of them when the
+    /// cloud has fewer.
+    pub fn knn(&self, query: [f64; 3], k: usize) -> Result<Vec<Neighbor>, PointCloudError> {
+        check_query(query)?;
+        Ok(self
+            .neighbors(query)
+            .into_iter()
+            .take(k)
+            .map(|(squared_distance, index)| Neighbor {
+                index,
+                squared_distance,
+            })
+            .collect())
+    }
+
+    /// The finite points within `radius` of `query`, boundary included.
+    pub fn radius_neighbors(
+        &self,
+        query: [f64; 3],
+        radius: f64,
+    ) -> Result<Vec<Neighbor>, PointCloudError> {
+        check_query(query)?;
+        if !radius.is_finite() || radius < 0.0 {
+            return Err(invalid(format!(
+                "radius must be finite and non-negative, got {radius}"
+            )));
+        }
+        Ok(self
+            .neighbors(query)
+            .into_iter()
+            .take_while(|&(d2, _)| d2 <= radius * radius)
+            .map(|(squared_distance, index)| Neighbor {
+                index,
+                squared_distance,
+            })
+            .collect())
+    }
+
+    /// Copies the points at `indices`, in that order, with all attributes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an index is out of bounds.
+    pub fn select(&self, indices: &[usize]) -> PointCloud {
+        let pick = |column: &[f32]| indices.iter().map(|&i| column[i]).collect::<Vec<_>>();
+        let pick_u8 = |column: &[u8]| indices.iter().map(|&i| column[i]).collect::<Vec<_>>();
+        PointCloud {
+            x: pick(&self.x),
+            y: pick(&self.y),
+            z: pick(&self.z),
+            normals: self.normals().map(|n| n.map(pick)),
+            colors: self.colors().map(|c| c.map(pick_u8)),
+            intensity: self.intensity().map(pick),
+        }
+    }
+
+    /// Replaces the points of each occupied voxel (the cube of side
+    /// `voxel_size` at `floor(p / voxel_size)`) by their centroid. Normals,
+    /// colours and intensity are averaged as well; normals are not
+    /// renormalized and colours are rounded. The result is ordered by voxel
+    /// (x index, then y, then z).
+    pub fn voxel_downsample(&self, voxel_size: f64) -> Result<PointCloud, PointCloudError> {
+        if !voxel_size.is_finite() || voxel_size <= 0.0 {
+            return Err(invalid(format!(
+                "voxel_size must be positive and finite, got {voxel_size}"
+            )));
+        }
+
+        let mut voxels: BTreeMap<[i64; 3], Vec<usize>> = BTreeMap::new();
+        for i in self.finite_indices() {
+            let p = self.position(i);
+            let mut key = [0; 3];
+            for axis in 0..3 {
+                let cell = (p[axis] / voxel_size).floor();
+                if cell.abs() >= 2f64.powi(62) {
+                    return Err(invalid(format!(
+                        "voxel_size {voxel_size} is too small for the point {p:?}"
+                    )));
+                }
+                key[axis] = cell as i64;
+            }
+            voxels.entry(key).or_default().push(i);
+        }
+
+        let mean = |column: &[f32], members: &[usize]| {
+            let sum: f64 = members.iter().map(|&i| f64::from(column[i])).sum();
+            (sum / members.len() as f64) as f32
+        };
+        let mean_u8 = |column: &[u8], members: &[usize]| {
+            let sum: f64 = members.iter().map(|&i| f64::from(column[i])).sum();
+            (sum / members.len() as f64).round() as u8
+        };
+        let members: Vec<&[usize]> = voxels.values().map(Vec::as_slice).collect();
+        let average = |column: &[f32]| members.iter().map(|m| mean(column, m)).collect::<Vec<_>>();
+        let average_u8 = |column: &[u8]| {
+            members
+                .iter()
+                .map(|m| mean_u8(column, m))
+                .collect::<Vec<_>>()
+        };
+        Ok(PointCloud {
+            x: average(&self.x),
+            y: average(&self.y),
+            z: average(&self.z),
+            normals: self.normals().map(|n| n.map(average)),
+            colors: self.colors().map(|c| c.map(average_u8)),
+            intensity: self.intensity().map(average),
+        })
+    }
+
+    /// Statistical outlier removal: the indices of the points whose mean
+    /// distance to their `k` nearest neighbours is at most
+    /// `mean + std_ratio * stddev` over the whole cloud (stddev with
+    /// `n - 1`). Needs more than `k` finite points.
+    pub fn statistical_inliers(
+        &self,
+        k: usize,
+        std_ratio: f64,
+    ) -> Result<Vec<usize>, PointCloudError> {
+        if k == 0 || !std_ratio.is_finite() {
+            return Err(invalid(format!(
+                "needs k > 0 and a finite std_ratio, got {k} and {std_ratio}"
+            )));
+        }
+        let finite: Vec<usize> = self.finite_indices().collect();
+        if finite.len() <= k {
+            return Err(invalid(format!(
+                "needs more than k = {k} finite points, got {}",
+                finite.len()
+            )));
+        }
+
+        let mean_distance: Vec<f64> = finite
+            .iter()
+            .map(|&i| {
+                let neighbors = self.neighbors(self.position(i));
+                let sum: f64 = neighbors
+                    .iter()
+                    .filter(|&&(_, j)| j != i)
+                    .take(k)
+                    .map(|(d, _)| d.sqrt())
+                    .sum();
+                sum / k as f64
+            })
+            .collect();
+        let n = finite.len() as f64;
+        let mean = mean_distance.iter().sum::<f64>() / n;
+        let deviations: f64 = mean_distance.iter().map(|d| (d - mean) * (d - mean)).sum();
+        let threshold = mean + std_ratio * (deviations / (n - 1.0)).sqrt();
+        Ok(finite
+            .into_iter()
+            .zip(mean_distance)
+            .filter(|&(_, d)| d <= threshold)
+            .map(|(i, _)| i)
+            .collect())
+    }
+
+    /// Radius outlier removal: the indices of the points with at least
+    /// `min_neighbors` other points within `radius` (inclusive).
+    pub fn radius_inliers(
+        &self,
+        radius: f64,
+        min_neighbors: usize,
+    ) -> Result<Vec<usize>, PointCloudError> {
+        if !radius.is_finite() || radius < 0.0 {
+            return Err(invalid(format!(
+                "radius must be finite and non-negative, got {radius}"
+            )));
+        }
+        let radius_sq = radius * radius;
+        Ok(self
+            .finite_indices()
+            .filter(|&i| {
+                let p = self.position(i);
+                let within = self
+                    .finite_indices()
+                    .filter(|&j| j != i && squared_distance(self.position(j), p) <= radius_sq)
+                    .count();
+                within >= min_neighbors
+            })
+            .collect())
+    }
+
+    /// The indices of the points inside the box `[min, max]`, bounds
+    /// included.
+    pub fn crop(&self, min: [f64; 3], max: [f64; 3]) -> Result<Vec<usize>, PointCloudError> {
+        if (0..3).any(|axis| min[axis].is_nan() || max[axis].is_nan() || min[axis] > max[axis]) {
+            return Err(invalid(format!("box min {min:?} is not <= max {max:?}")));
+        }
+        Ok(self
+            .finite_indices()
+            .filter(|&i| {
+                let p = self.position(i);
+                (0..3).all(|axis| min[axis] <= p[axis] && p[axis] <= max[axis])
+            })
+            .collect())
+    }
+
+    /// A unit normal per point, as `[nx, ny, nz]` columns: the eigenvector of
+    /// the smallest eigenvalue of the covariance of the point's `k` nearest
+    /// neighbours (itself included), flipped to face `viewpoint`. Needs
+    /// `k >= 3`. Points that are not finite, or have fewer than 3 finite
+    /// neighbours, get NaN.
+    pub fn estimate_normals(
+        &self,
+        k: usize,
+        viewpoint: [f64; 3],
+    ) -> Result<[Vec<f32>; 3], PointCloudError> {
+        if k < 3 {
+            return Err(invalid(format!("needs k >= 3, got {k}")));
+        }
+        let mut normals = [
+            vec![f32::NAN; self.len()],
+            vec![f32::NAN; self.len()],
+            vec![f32::NAN; self.len()],
+        ];
+        for i in self.finite_indices() {
+            let p = self.position(i);
+            let neighbors: Vec<[f64; 3]> = self
+                .neighbors(p)
+                .into_iter()
+                .take(k)
+                .map(|(_, j)| self.position(j))
+                .collect();
+            if neighbors.len() < 3 {
+                continue;
+            }
+
+            let n = neighbors.len() as f64;
+            let mut centroid = [0.0; 3];
+            for q in &neighbors {
+                for axis in 0..3 {
+                    centroid[axis] += q[axis];
+                }
+            }
+            centroid = centroid.map(|c| c / n);
+            let mut covariance = [[0.0; 3]; 3];
+            for q in &neighbors {
+                let d = [q[0] - centroid[0], q[1] - centroid[1], q[2] - centroid[2]];
+                for r in 0..3 {
+                    for c in 0..3 {
+                        covariance[r][c] += d[r] * d[c];
+                    }
+                }
+            }
+            let covariance = covariance.map(|row| row.map(|v| v / n));
+
+            let mut normal = smallest_eigenvector(covariance);
+            let to_viewpoint = [
+                viewpoint[0] - p[0],
+                viewpoint[1] - p[1],
+                viewpoint[2] - p[2],
+            ];
+            if (0..3)
+                .map(|axis| normal[axis] * to_viewpoint[axis])
+                .sum::<f64>()
+                < 0.0
+            {
+                normal = normal.map(|v| -v);
+            }
+            for axis in 0..3 {
+                normals[axis][i] = normal[axis] as f32;
+            }
+        }
+        Ok(normals)
+    }
+
+    fn finite_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len())
+            .filter(|&i| self.x[i].is_finite() && self.y[i].is_finite() && self.z[i].is_finite())
+    }
+
+    fn position(&self, i: usize) -> [f64; 3] {
+        [self.x[i], self.y[i], self.z[i]].map(f64::from)
+    }
+
+    /// Every finite point as `(squared distance, index)`, nearest first.
+    fn neighbors(&self, query: [f64; 3]) -> Vec<(f64, usize)> {
+        let mut neighbors: Vec<(f64, usize)> = self
+            .finite_indices()
+            .map(|j| (squared_distance(self.position(j), query), j))
+            .collect();
+        neighbors.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        neighbors
+    }
+}
+
+fn invalid(message: String) -> PointCloudError {
+    PointCloudError::InvalidArgument(message)
+}
+
+fn check_query(query: [f64; 3]) -> Result<(), PointCloudError> {
+    if query.iter().all(|c| c.is_finite()) {
+        Ok(())
+    } else {
+        Err(invalid(format!("query {query:?} is not finite")))
+    }
+}
+
+fn squared_distance(a: [f64; 3], b: [f64; 3]) -> f64 {
+    let [dx, dy, dz] = [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    dx * dx + dy * dy + dz * dz
+}
+
+/// Unit eigenvector of the smallest eigenvalue of a symmetric 3x3 matrix,
+/// by cyclic Jacobi rotations.
+fn smallest_eigenvector(mut a: [[f64; 3]; 3]) -> [f64; 3] {
+    let mut v = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    for _ in 0..64 {
+        let off = a[0][1].abs() + a[0][2].abs() + a[1][2].abs();
+        let diagonal = a[0][0].abs() + a[1][1].abs() + a[2][2].abs();
+        if off == 0.0 || off <= 1e-18 * diagonal {
+            break;
+        }
+        for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+            if a[p][q] == 0.0 {
+                continue;
+            }
+            let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+            let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+            let c = 1.0 / (t * t + 1.0).sqrt();
+            let s = t * c;
+            for row in &mut a {
+                let (kp, kq) = (row[p], row[q]);
+                row[p] = c * kp - s * kq;
+                row[q] = s * kp + c * kq;
+            }
+            let (row_p, row_q) = (a[p], a[q]);
+            a[p] = std::array::from_fn(|k| c * row_p[k] - s * row_q[k]);
+            a[q] = std::array::from_fn(|k| s * row_p[k] + c * row_q[k]);
+            for row in &mut v {
+                let (kp, kq) = (row[p], row[q]);
+                row[p] = c * kp - s * kq;
+                row[q] = s * kp + c * kq;
+            }
+        }
+    }
+    let smallest = (0..3).min_by(|&i, &j| a[i][i].total_cmp(&a[j][j])).unwrap();
+    [v[0][smallest], v[1][smallest], v[2][smallest]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 5 x 5 x 5 lattice with spacing 1 plus two far-away points.
+    fn lattice_with_outliers() -> PointCloud {
+        let mut points = Vec::new();
+        for i in 0..5 {
+            for j in 0..5 {
+                for k in 0..5 {
+                    points.push([i as f32, j as f32, k as f32]);
+                }
+            }
+        }
+        points.push([50.0, 50.0, 50.0]);
+        points.push([-40.0, 0.0, 0.0]);
+        PointCloud::from_points(&points)
+    }
+
+    #[test]
+    fn select_copies_every_attribute() {
+        let cloud = crate::point_cloud::tests::sample();
+        let picked = cloud.select(&[2, 0]);
+        assert_eq!(picked.point(0), cloud.point(2));
+        assert_eq!(picked.colors().unwrap()[1], [34, 0]);
+        assert_eq!(picked.intensity().unwrap(), [1234.5, 0.5]);
+    }
+
+    #[test]
+    fn knn_orders_by_distance_then_index() {
+        let cloud = PointCloud::from_points(&[
+            [1.0, 0.0, 0.0],
+            [f32::NAN, 0.0, 0.0],
+            [-1.0, 0.0, 0.0],
+            [0.5, 0.0, 0.0],
+            [0.0, 3.0, 0.0],
+        ]);
+        let found = cloud.knn([0.0; 3], 3).unwrap();
+        let indices: Vec<usize> = found.iter().map(|n| n.index).collect();
+        assert_eq!(indices, [3, 0, 2]);
+        assert_eq!(found[0].squared_distance, 0.25);
+        // Asking for more than there are returns every finite point.
+        assert_eq!(cloud.knn([0.0; 3], 10).unwrap().len(), 4);
+        assert!(cloud.knn([0.0; 3], 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn radius_neighbors_include_the_boundary() {
+        let cloud = lattice_with_outliers();
+        let found = cloud.radius_neighbors([2.0, 2.0, 2.0], 1.0).unwrap();
+        // The centre, then its six axis neighbours in index order.
+        let indices: Vec<usize> = found.iter().map(|n| n.index).collect();
+        assert_eq!(indices, [62, 37, 57, 61, 63, 67, 87]);
+        assert_eq!(found[0].squared_distance, 0.0);
+        assert!(found[1..].iter().all(|n| n.squared_distance == 1.0));
+        assert!(cloud.radius_neighbors([20.0; 3], 1.0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn neighbor_queries_reject_bad_arguments() {
+        let cloud = lattice_with_outliers();
+        assert!(matches!(
+            cloud.knn([f64::NAN, 0.0, 0.0], 1),
+            Err(PointCloudError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            cloud.radius_neighbors([0.0; 3], -1.0),
+            Err(PointCloudError::InvalidArgument(_))
+        ));
+        assert!(matches!(
+            cloud.radius_neighbors([0.0, f64::INFINITY, 0.0], 1.0),
+            Err(PointCloudError::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn voxel_downsample_averages_each_voxel() {
+        let mut cloud = PointCloud::from_points(&[
+            [0.1, 0.1, 0.1],
+            [0.3, 0.5, 0.1],
+            [1.5, 0.0, 0.0],
+            [-0.5, 0.0, 0.0],
+            [f32::NAN, 0.0, 0.0],
+        ]);
+        cloud
+            .set_colors(vec![10, 21, 0, 0, 0], vec![0; 5], vec![255; 5])
+            .unwrap();
+        let down = cloud.voxel_downsample(1.0).unwrap();
+        assert_eq!(down.x(), [-0.5, 0.2, 1.5]);
+        assert_eq!(down.y(), [0.0, 0.3, 0.0]);
+        // (10 + 21) / 2 rounds half away from zero.
+        assert_eq!(down.colors().unwrap()[0], [0, 16, 0]);
+
+        assert!(cloud.voxel_downsample(0.0).is_err());
+        assert!(cloud.voxel_downsample(1e-300).is_err());
+    }
+
+    #[test]
+    fn outlier_removal_drops_isolated_points() {
+        let cloud = lattice_with_outliers();
+        let lattice: Vec<usize> = (0..125).collect();
+        assert_eq!(cloud.statistical_inliers(6, 1.0).unwrap(), lattice);
+        assert_eq!(cloud.radius_inliers(1.0, 3).unwrap(), lattice);
+        // Corners of the lattice have exactly 3 neighbours at distance 1.
+        assert_eq!(cloud.radius_inliers(1.0, 4).unwrap().len(), 125 - 8);
+        assert!(cloud.statistical_inliers(127, 1.0).is_err());
+    }
+
+    #[test]
+    fn crop_is_inclusive() {
+        let cloud = lattice_with_outliers();
+        let inside = cloud.crop([1.0, 1.0, 1.0], [2.0, 2.0, 2.0]).unwrap();
+        assert_eq!(inside.len(), 8);
+        assert!(inside.iter().all(|&i| {
+            cloud
+                .point(i)
+                .unwrap()
+                .iter()
+                .all(|&v| (1.0..=2.0).contains(&v))
+        }));
+        assert!(cloud.crop([0.0, 0.0, f64::NAN], [1.0; 3]).is_err());
+    }
+
+    #[test]
+    fn normals_of_a_plane_face_the_viewpoint() {
+        let mut points = Vec::new();
+        for i in 0..6 {
+            for j in 0..6 {
+                // A tilted plane z = 0.5 x.
+                points.push([i as f32, j as f32, 0.5 * i as f32]);
+            }
+        }
+        let cloud = PointCloud::from_points(&points);
+        let [nx, ny, nz] = cloud.estimate_normals(8, [0.0, 0.0, 100.0]).unwrap();
+        let expected = [-0.5, 0.0, 1.0].map(|v: f64| v / 1.25f64.sqrt());
+        for i in 0..cloud.len() {
+            let n = [nx[i], ny[i], nz[i]].map(f64::from);
+            assert!((0..3).all(|a| (n[a] - expected[a]).abs() < 1e-6), "{n:?}");
+        }
+        assert!(cloud.estimate_normals(2, [0.0; 3]).is_err());
+    }
+}